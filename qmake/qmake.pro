@@ -160,21 +160,41 @@ macx-clang {
 # Core lib headers & sources
 HEADERS += \
     ../src/acfutils/acf_file.h \
+    ../src/acfutils/acf_limits.h \
+    ../src/acfutils/acfttype.h \
+    ../src/acfutils/afio.h \
     ../src/acfutils/airportdb.h \
+    ../src/acfutils/alerting.h \
+    ../src/acfutils/ambient.h \
+    ../src/acfutils/apu.h \
+    ../src/acfutils/arinc717.h \
     ../src/acfutils/assert.h \
+    ../src/acfutils/atis.h \
+    ../src/acfutils/aural.h \
     ../src/acfutils/avl.h \
     ../src/acfutils/avl_impl.h \
     ../src/acfutils/base64.h \
+    ../src/acfutils/bleed.h \
+    ../src/acfutils/brake.h \
+    ../src/acfutils/budget.h \
+    ../src/acfutils/cas.h \
     ../src/acfutils/cmd.h \
     ../src/acfutils/compress.h \
     ../src/acfutils/conf.h \
+    ../src/acfutils/conf_watch.h \
+    ../src/acfutils/coordparse.h \
     ../src/acfutils/core.h \
     ../src/acfutils/crc64.h \
+    ../src/acfutils/csvio.h \
+    ../src/acfutils/deadreck.h \
     ../src/acfutils/delay_line.h \
     ../src/acfutils/dr_cmd_reg.h \
     ../src/acfutils/dr.h \
     ../src/acfutils/dsf.h \
+    ../src/acfutils/etp.h \
+    ../src/acfutils/evbus.h \
     ../src/acfutils/except.h \
+    ../src/acfutils/fuelsys.h \
     ../src/acfutils/geom.h \
     ../src/acfutils/helpers.h \
     ../src/acfutils/lacf_getline.h \
@@ -182,66 +202,132 @@ HEADERS += \
     ../src/acfutils/hexcode.h \
     ../src/acfutils/hp_filter.h \
     ../src/acfutils/htbl.h \
+    ../src/acfutils/hud.h \
     ../src/acfutils/icao2cc.h \
     ../src/acfutils/intl.h \
+    ../src/acfutils/journal.h \
     ../src/acfutils/joystick.h \
+    ../src/acfutils/kvcache.h \
     ../src/acfutils/libconfig.h \
     ../src/acfutils/list.h \
     ../src/acfutils/list_impl.h \
     ../src/acfutils/log.h \
+    ../src/acfutils/logbook.h \
     ../src/acfutils/math_core.h \
     ../src/acfutils/math.h \
+    ../src/acfutils/metar.h \
     ../src/acfutils/mslibs.h \
+    ../src/acfutils/navdb.h \
     ../src/acfutils/osrand.h \
+    ../src/acfutils/oxygen.h \
     ../src/acfutils/perf.h \
+    ../src/acfutils/persist.h \
     ../src/acfutils/pid_ctl.h \
     ../src/acfutils/pid_ctl_parsing.h \
+    ../src/acfutils/pitotstatic.h \
+    ../src/acfutils/pnet.h \
+    ../src/acfutils/quat.h \
+    ../src/acfutils/range.h \
+    ../src/acfutils/ringbuf.h \
+    ../src/acfutils/rwysel.h \
     ../src/acfutils/safe_alloc.h \
+    ../src/acfutils/sensorpos.h \
+    ../src/acfutils/sensorsim.h \
+    ../src/acfutils/shmem.h \
     ../src/acfutils/sysmacros.h \
     ../src/acfutils/taskq.h \
+    ../src/acfutils/terrain.h \
     ../src/acfutils/thread.h \
     ../src/acfutils/time.h \
     ../src/acfutils/tls.h \
+    ../src/acfutils/traffic.h \
+    ../src/acfutils/trkexp.h \
     ../src/acfutils/tumbler.h \
     ../src/acfutils/types.h \
+    ../src/acfutils/wake.h \
     ../src/acfutils/widget.h \
+    ../src/acfutils/wind.h \
+    ../src/acfutils/windshear.h \
+    ../src/acfutils/winfilt.h \
     ../src/acfutils/wmm.h \
     ../src/acfutils/worker.h \
     ../src/acfutils/xpfail.h
 
 SOURCES += \
     ../src/acf_file.c \
+    ../src/acf_limits.c \
+    ../src/acfttype.c \
+    ../src/afio.c \
     ../src/airportdb.c \
+    ../src/ambient.c \
+    ../src/apu.c \
+    ../src/arinc717.c \
+    ../src/atis.c \
+    ../src/aural.c \
     ../src/avl.c \
     ../src/base64.c \
+    ../src/bleed.c \
+    ../src/brake.c \
+    ../src/budget.c \
+    ../src/cas.c \
     ../src/cmd.c \
     ../src/compress_7z.c \
     ../src/compress_zip.c \
     ../src/compress_zlib.c \
     ../src/conf.c \
+    ../src/conf_watch.c \
+    ../src/coordparse.c \
     ../src/core.c \
     ../src/crc64.c \
+    ../src/csvio.c \
+    ../src/deadreck.c \
     ../src/dr.c \
     ../src/dr_cmd_reg.c \
     ../src/dsf.c \
+    ../src/etp.c \
+    ../src/evbus.c \
     ../src/except.c \
+    ../src/fuelsys.c \
     ../src/GeomagnetismLibrary.c \
     ../src/geom.c \
     ../src/helpers.c \
     ../src/hexcode.c \
     ../src/htbl.c \
+    ../src/hud.c \
     ../src/icao2cc.c \
     ../src/intl.c \
+    ../src/journal.c \
+    ../src/kvcache.c \
     ../src/list.c \
     ../src/log.c \
+    ../src/logbook.c \
     ../src/math.c \
+    ../src/metar.c \
+    ../src/navdb.c \
     ../src/osrand.c \
+    ../src/oxygen.c \
     ../src/perf.c \
+    ../src/persist.c \
+    ../src/pitotstatic.c \
+    ../src/pnet.c \
+    ../src/quat.c \
+    ../src/range.c \
+    ../src/ringbuf.c \
+    ../src/rwysel.c \
+    ../src/sensorpos.c \
+    ../src/shmem.c \
     ../src/taskq.c \
+    ../src/terrain.c \
     ../src/time.c \
+    ../src/traffic.c \
+    ../src/trkexp.c \
     ../src/thread.c \
     ../src/tumbler.c \
+    ../src/wake.c \
     ../src/widget.c \
+    ../src/wind.c \
+    ../src/windshear.c \
+    ../src/winfilt.c \
     ../src/wmm.c \
     ../src/worker.c
 
@@ -272,6 +358,7 @@ contains(minimal, 1) {
 	    ../src/acfutils/apps.h \
 	    ../src/acfutils/chartdb.h \
 	    ../src/acfutils/cursor.h \
+	    ../src/acfutils/fplan.h \
 	    ../src/acfutils/glctx.h \
 	    ../src/acfutils/glew.h \
 	    ../src/acfutils/glew_os.h \
@@ -279,10 +366,13 @@ contains(minimal, 1) {
 	    ../src/acfutils/lacf_gl_pic.h \
 	    ../src/acfutils/mt_cairo_render.h \
 	    ../src/acfutils/odb.h \
+	    ../src/acfutils/online.h \
 	    ../src/acfutils/paste.h \
 	    ../src/acfutils/png.h \
+	    ../src/acfutils/preflight.h \
 	    ../src/acfutils/riff.h \
 	    ../src/acfutils/shader.h \
+	    ../src/acfutils/simbrief.h \
 	    ../src/acfutils/wav.h \
 	    ../src/acfutils/jsmn/*.h
 
@@ -293,6 +383,7 @@ contains(minimal, 1) {
 	    ../src/chart_prov_common.c \
 	    ../src/chart_prov_faa.c \
 	    ../src/chart_prov_navigraph.c \
+	    ../src/fplan.c \
 	    ../src/glctx.c \
 	    ../src/glew.c \
 	    ../src/glew_os.c \
@@ -301,10 +392,13 @@ contains(minimal, 1) {
 	    ../src/minimp3.c \
 	    ../src/mt_cairo_render.c \
 	    ../src/odb.c \
+	    ../src/online.c \
 	    ../src/paste.c \
 	    ../src/png.c \
+	    ../src/preflight.c \
 	    ../src/riff.c \
 	    ../src/shader.c \
+	    ../src/simbrief.c \
 	    ../src/wav.c
 
 	win32 {
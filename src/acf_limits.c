@@ -0,0 +1,289 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <math.h>
+#include <string.h>
+
+#include <acfutils/acf_limits.h>
+#include <acfutils/assert.h>
+#include <acfutils/helpers.h>
+
+/*
+ * Initializes a limitation monitor.
+ *
+ * @param lim The limit_t structure to initialize.
+ * @param name Optional human-readable name of the limitation (e.g. "VMO").
+ *	Pass NULL if you don't care. The name is truncated to fit into
+ *	the `name' field of the limit_t.
+ * @param type Type of limit. LIMIT_TYPE_MAX means the limit is exceeded
+ *	when the monitored value rises above `limit'. LIMIT_TYPE_MIN means
+ *	the limit is exceeded when the monitored value drops below `limit'.
+ * @param limit The limit value.
+ * @param hyst Hysteresis band. Once an exceedance has begun, the monitored
+ *	value must return to within the limit by at least `hyst' before the
+ *	exceedance is considered ended. This prevents a value hovering right
+ *	at the limit from generating a flurry of short exceedances.
+ *	Must be non-negative.
+ */
+void
+limit_init(limit_t *lim, const char *name, limit_type_t type, double limit,
+    double hyst)
+{
+	ASSERT(lim != NULL);
+	ASSERT(type == LIMIT_TYPE_MAX || type == LIMIT_TYPE_MIN);
+	ASSERT(!isnan(limit));
+	ASSERT3F(hyst, >=, 0);
+
+	memset(lim, 0, sizeof (*lim));
+	if (name != NULL)
+		lacf_strlcpy(lim->name, name, sizeof (lim->name));
+	lim->type = type;
+	lim->limit = limit;
+	lim->hyst = hyst;
+}
+
+/*
+ * Resets the exceedance history of a limit monitor. Any exceedance
+ * currently in progress is discarded WITHOUT calling the callback.
+ * The limit configuration (limit value, hysteresis, minimum duration
+ * and callback) is retained.
+ */
+void
+limit_reset(limit_t *lim)
+{
+	ASSERT(lim != NULL);
+	lim->now = 0;
+	lim->active = B_FALSE;
+	lim->confirmed = B_FALSE;
+	memset(&lim->cur, 0, sizeof (lim->cur));
+	memset(&lim->last, 0, sizeof (lim->last));
+	lim->num_exceed = 0;
+	lim->total_dur = 0;
+}
+
+/*
+ * Changes the limit value. This can be called every frame to implement
+ * limits which themselves vary (e.g. VMO as a function of altitude, or
+ * a flap placard speed depending on flap position).
+ */
+void
+limit_set_limit(limit_t *lim, double limit)
+{
+	ASSERT(lim != NULL);
+	ASSERT(!isnan(limit));
+	lim->limit = limit;
+}
+
+double
+limit_get_limit(const limit_t *lim)
+{
+	ASSERT(lim != NULL);
+	return (lim->limit);
+}
+
+void
+limit_set_hyst(limit_t *lim, double hyst)
+{
+	ASSERT(lim != NULL);
+	ASSERT3F(hyst, >=, 0);
+	lim->hyst = hyst;
+}
+
+double
+limit_get_hyst(const limit_t *lim)
+{
+	ASSERT(lim != NULL);
+	return (lim->hyst);
+}
+
+/*
+ * Sets a minimum duration (in seconds) for which the monitored value
+ * must remain beyond the limit before an exceedance is confirmed and
+ * recorded. Brief transients shorter than this are ignored. The default
+ * is 0, meaning any sample beyond the limit immediately confirms an
+ * exceedance.
+ */
+void
+limit_set_min_dur(limit_t *lim, double min_dur)
+{
+	ASSERT(lim != NULL);
+	ASSERT3F(min_dur, >=, 0);
+	lim->min_dur = min_dur;
+}
+
+double
+limit_get_min_dur(const limit_t *lim)
+{
+	ASSERT(lim != NULL);
+	return (lim->min_dur);
+}
+
+/*
+ * Installs an exceedance notification callback. This is the hook through
+ * which other subsystems (e.g. a wear/fatigue model or a flight data
+ * recorder) can be informed of exceedances as they happen. Pass NULL
+ * for `cb' to remove a previously installed callback.
+ */
+void
+limit_set_cb(limit_t *lim, limit_exceed_cb_t cb, void *userinfo)
+{
+	ASSERT(lim != NULL);
+	lim->cb = cb;
+	lim->cb_userinfo = userinfo;
+}
+
+static bool_t
+is_beyond(const limit_t *lim, double value, double limit)
+{
+	if (lim->type == LIMIT_TYPE_MAX)
+		return (value > limit);
+	else
+		return (value < limit);
+}
+
+/*
+ * Feeds a new value of the monitored parameter to the limit monitor.
+ * This should be called once per simulator frame.
+ *
+ * @param lim The limit monitor to update.
+ * @param value Current value of the monitored parameter. Passing NAN
+ *	simply advances the monitor time without changing the exceedance
+ *	state (useful when the parameter is temporarily unavailable).
+ * @param d_t Time elapsed since the last call, in seconds. Must be
+ *	non-negative. Passing a simulator-time delta (rather than wall
+ *	clock time) means exceedances don't accumulate while the sim is
+ *	paused.
+ *
+ * @return B_TRUE if a confirmed exceedance is in progress, B_FALSE if not.
+ */
+bool_t
+limit_update(limit_t *lim, double value, double d_t)
+{
+	ASSERT(lim != NULL);
+	ASSERT3F(d_t, >=, 0);
+
+	lim->now += d_t;
+	if (isnan(value))
+		return (lim->confirmed);
+
+	if (!lim->active) {
+		if (!is_beyond(lim, value, lim->limit))
+			return (B_FALSE);
+		lim->active = B_TRUE;
+		lim->confirmed = B_FALSE;
+		lim->cur.start_t = lim->now;
+		lim->cur.dur = 0;
+		lim->cur.peak = value;
+		lim->cur.limit = lim->limit;
+	} else {
+		double clear_lim = (lim->type == LIMIT_TYPE_MAX ?
+		    lim->limit - lim->hyst : lim->limit + lim->hyst);
+
+		if (!is_beyond(lim, value, clear_lim)) {
+			/* Exceedance has ended */
+			lim->active = B_FALSE;
+			if (lim->confirmed) {
+				lim->confirmed = B_FALSE;
+				lim->num_exceed++;
+				lim->total_dur += lim->cur.dur;
+				lim->last = lim->cur;
+				if (lim->cb != NULL) {
+					lim->cb(lim, &lim->last, B_TRUE,
+					    lim->cb_userinfo);
+				}
+			}
+			return (B_FALSE);
+		}
+		lim->cur.dur = lim->now - lim->cur.start_t;
+		if (is_beyond(lim, value, lim->cur.peak)) {
+			lim->cur.peak = value;
+			lim->cur.limit = lim->limit;
+		}
+	}
+	if (!lim->confirmed && lim->cur.dur >= lim->min_dur) {
+		lim->confirmed = B_TRUE;
+		if (lim->cb != NULL)
+			lim->cb(lim, &lim->cur, B_FALSE, lim->cb_userinfo);
+	}
+
+	return (lim->confirmed);
+}
+
+/*
+ * Returns B_TRUE if a confirmed exceedance is currently in progress.
+ */
+bool_t
+limit_is_exceeded(const limit_t *lim)
+{
+	ASSERT(lim != NULL);
+	return (lim->confirmed);
+}
+
+/*
+ * Retrieves information about the exceedance currently in progress.
+ * Returns B_TRUE and fills in `exc' if a confirmed exceedance is in
+ * progress, otherwise returns B_FALSE and leaves `exc' untouched.
+ */
+bool_t
+limit_get_cur_exceed(const limit_t *lim, limit_exceed_t *exc)
+{
+	ASSERT(lim != NULL);
+	ASSERT(exc != NULL);
+	if (!lim->confirmed)
+		return (B_FALSE);
+	*exc = lim->cur;
+	return (B_TRUE);
+}
+
+/*
+ * Retrieves information about the last completed exceedance. Returns
+ * B_TRUE and fills in `exc' if at least one exceedance has been completed
+ * since the monitor was initialized or reset, otherwise returns B_FALSE.
+ */
+bool_t
+limit_get_last_exceed(const limit_t *lim, limit_exceed_t *exc)
+{
+	ASSERT(lim != NULL);
+	ASSERT(exc != NULL);
+	if (lim->num_exceed == 0)
+		return (B_FALSE);
+	*exc = lim->last;
+	return (B_TRUE);
+}
+
+/*
+ * Returns the number of completed exceedances.
+ */
+unsigned
+limit_get_num_exceed(const limit_t *lim)
+{
+	ASSERT(lim != NULL);
+	return (lim->num_exceed);
+}
+
+/*
+ * Returns the total accumulated duration (in seconds) of all completed
+ * exceedances, plus the duration of the currently in-progress exceedance
+ * (if any).
+ */
+double
+limit_get_total_dur(const limit_t *lim)
+{
+	ASSERT(lim != NULL);
+	return (lim->total_dur + (lim->confirmed ? lim->cur.dur : 0));
+}
@@ -0,0 +1,493 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <stddef.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include <acfutils/acfttype.h>
+#include <acfutils/assert.h>
+#include <acfutils/avl.h>
+#include <acfutils/helpers.h>
+#include <acfutils/log.h>
+#include <acfutils/safe_alloc.h>
+
+typedef struct {
+	const char	*type;
+	const char	*desc;
+	wake_cat_t	wtc;
+	recat_t		recat;
+	char		apch_cat;
+} builtin_type_t;
+
+/*
+ * ICAO Doc 8643 type designators and descriptions of common aircraft
+ * types. The RECAT-EU categories follow from the type's MTOW and
+ * wingspan (see recat_from_mtow_span), except where RECAT-EU makes an
+ * explicit exception (e.g. the B757 is upper medium despite its 38m
+ * wingspan). Approach categories are based on the typical Vat at
+ * maximum landing weight.
+ * This table MUST be kept sorted by type designator, since we bsearch it.
+ */
+static const builtin_type_t builtin_types[] = {
+    { "A124", "L4J", WAKE_CAT_H, RECAT_B, 'D' },
+    { "A19N", "L2J", WAKE_CAT_M, RECAT_D, 'C' },
+    { "A20N", "L2J", WAKE_CAT_M, RECAT_D, 'C' },
+    { "A21N", "L2J", WAKE_CAT_M, RECAT_D, 'C' },
+    { "A225", "L6J", WAKE_CAT_J, RECAT_A, 'D' },
+    { "A306", "L2J", WAKE_CAT_H, RECAT_C, 'C' },
+    { "A30B", "L2J", WAKE_CAT_H, RECAT_C, 'C' },
+    { "A310", "L2J", WAKE_CAT_H, RECAT_C, 'C' },
+    { "A318", "L2J", WAKE_CAT_M, RECAT_D, 'C' },
+    { "A319", "L2J", WAKE_CAT_M, RECAT_D, 'C' },
+    { "A320", "L2J", WAKE_CAT_M, RECAT_D, 'C' },
+    { "A321", "L2J", WAKE_CAT_M, RECAT_D, 'C' },
+    { "A332", "L2J", WAKE_CAT_H, RECAT_B, 'C' },
+    { "A333", "L2J", WAKE_CAT_H, RECAT_B, 'C' },
+    { "A338", "L2J", WAKE_CAT_H, RECAT_B, 'C' },
+    { "A339", "L2J", WAKE_CAT_H, RECAT_B, 'C' },
+    { "A342", "L4J", WAKE_CAT_H, RECAT_B, 'C' },
+    { "A343", "L4J", WAKE_CAT_H, RECAT_B, 'C' },
+    { "A345", "L4J", WAKE_CAT_H, RECAT_B, 'D' },
+    { "A346", "L4J", WAKE_CAT_H, RECAT_B, 'D' },
+    { "A359", "L2J", WAKE_CAT_H, RECAT_B, 'C' },
+    { "A35K", "L2J", WAKE_CAT_H, RECAT_B, 'C' },
+    { "A388", "L4J", WAKE_CAT_J, RECAT_A, 'C' },
+    { "A400", "L4T", WAKE_CAT_H, RECAT_C, 'C' },
+    { "AT43", "L2T", WAKE_CAT_M, RECAT_E, 'B' },
+    { "AT45", "L2T", WAKE_CAT_M, RECAT_E, 'B' },
+    { "AT72", "L2T", WAKE_CAT_M, RECAT_E, 'B' },
+    { "AT75", "L2T", WAKE_CAT_M, RECAT_E, 'B' },
+    { "AT76", "L2T", WAKE_CAT_M, RECAT_E, 'B' },
+    { "B350", "L2T", WAKE_CAT_L, RECAT_F, 'B' },
+    { "B38M", "L2J", WAKE_CAT_M, RECAT_D, 'C' },
+    { "B39M", "L2J", WAKE_CAT_M, RECAT_D, 'C' },
+    { "B712", "L2J", WAKE_CAT_M, RECAT_E, 'C' },
+    { "B733", "L2J", WAKE_CAT_M, RECAT_E, 'C' },
+    { "B734", "L2J", WAKE_CAT_M, RECAT_E, 'C' },
+    { "B735", "L2J", WAKE_CAT_M, RECAT_E, 'C' },
+    { "B736", "L2J", WAKE_CAT_M, RECAT_D, 'C' },
+    { "B737", "L2J", WAKE_CAT_M, RECAT_D, 'C' },
+    { "B738", "L2J", WAKE_CAT_M, RECAT_D, 'C' },
+    { "B739", "L2J", WAKE_CAT_M, RECAT_D, 'C' },
+    { "B744", "L4J", WAKE_CAT_H, RECAT_B, 'D' },
+    { "B748", "L4J", WAKE_CAT_H, RECAT_B, 'D' },
+    { "B752", "L2J", WAKE_CAT_M, RECAT_D, 'C' },
+    { "B753", "L2J", WAKE_CAT_M, RECAT_D, 'C' },
+    { "B762", "L2J", WAKE_CAT_H, RECAT_C, 'C' },
+    { "B763", "L2J", WAKE_CAT_H, RECAT_C, 'C' },
+    { "B764", "L2J", WAKE_CAT_H, RECAT_C, 'C' },
+    { "B772", "L2J", WAKE_CAT_H, RECAT_B, 'D' },
+    { "B773", "L2J", WAKE_CAT_H, RECAT_B, 'D' },
+    { "B77L", "L2J", WAKE_CAT_H, RECAT_B, 'D' },
+    { "B77W", "L2J", WAKE_CAT_H, RECAT_B, 'D' },
+    { "B788", "L2J", WAKE_CAT_H, RECAT_B, 'D' },
+    { "B789", "L2J", WAKE_CAT_H, RECAT_B, 'D' },
+    { "B78X", "L2J", WAKE_CAT_H, RECAT_B, 'D' },
+    { "BCS1", "L2J", WAKE_CAT_M, RECAT_D, 'C' },
+    { "BCS3", "L2J", WAKE_CAT_M, RECAT_D, 'C' },
+    { "BE20", "L2T", WAKE_CAT_L, RECAT_F, 'B' },
+    { "BE58", "L2P", WAKE_CAT_L, RECAT_F, 'B' },
+    { "C152", "L1P", WAKE_CAT_L, RECAT_F, 'A' },
+    { "C17", "L4J", WAKE_CAT_H, RECAT_C, 'C' },
+    { "C172", "L1P", WAKE_CAT_L, RECAT_F, 'A' },
+    { "C182", "L1P", WAKE_CAT_L, RECAT_F, 'A' },
+    { "C208", "L1T", WAKE_CAT_L, RECAT_F, 'A' },
+    { "C25A", "L2J", WAKE_CAT_L, RECAT_F, 'B' },
+    { "C510", "L2J", WAKE_CAT_L, RECAT_F, 'B' },
+    { "C56X", "L2J", WAKE_CAT_M, RECAT_F, 'B' },
+    { "C680", "L2J", WAKE_CAT_M, RECAT_F, 'B' },
+    { "C700", "L2J", WAKE_CAT_M, RECAT_E, 'B' },
+    { "CL60", "L2J", WAKE_CAT_M, RECAT_E, 'C' },
+    { "CRJ2", "L2J", WAKE_CAT_M, RECAT_E, 'C' },
+    { "CRJ7", "L2J", WAKE_CAT_M, RECAT_E, 'C' },
+    { "CRJ9", "L2J", WAKE_CAT_M, RECAT_E, 'C' },
+    { "CRJX", "L2J", WAKE_CAT_M, RECAT_E, 'C' },
+    { "DA40", "L1P", WAKE_CAT_L, RECAT_F, 'A' },
+    { "DA42", "L2P", WAKE_CAT_L, RECAT_F, 'A' },
+    { "DC10", "L3J", WAKE_CAT_H, RECAT_C, 'C' },
+    { "DH8A", "L2T", WAKE_CAT_M, RECAT_E, 'B' },
+    { "DH8C", "L2T", WAKE_CAT_M, RECAT_E, 'B' },
+    { "DH8D", "L2T", WAKE_CAT_M, RECAT_E, 'B' },
+    { "DHC6", "L2T", WAKE_CAT_L, RECAT_F, 'A' },
+    { "E135", "L2J", WAKE_CAT_M, RECAT_E, 'C' },
+    { "E145", "L2J", WAKE_CAT_M, RECAT_E, 'C' },
+    { "E170", "L2J", WAKE_CAT_M, RECAT_E, 'C' },
+    { "E175", "L2J", WAKE_CAT_M, RECAT_E, 'C' },
+    { "E190", "L2J", WAKE_CAT_M, RECAT_E, 'C' },
+    { "E195", "L2J", WAKE_CAT_M, RECAT_E, 'C' },
+    { "E290", "L2J", WAKE_CAT_M, RECAT_D, 'C' },
+    { "E295", "L2J", WAKE_CAT_M, RECAT_D, 'C' },
+    { "EC35", "H2T", WAKE_CAT_L, RECAT_F, 'H' },
+    { "F100", "L2J", WAKE_CAT_M, RECAT_E, 'C' },
+    { "GLEX", "L2J", WAKE_CAT_M, RECAT_E, 'C' },
+    { "GLF4", "L2J", WAKE_CAT_M, RECAT_E, 'C' },
+    { "GLF5", "L2J", WAKE_CAT_M, RECAT_E, 'C' },
+    { "GLF6", "L2J", WAKE_CAT_M, RECAT_E, 'C' },
+    { "IL76", "L4J", WAKE_CAT_H, RECAT_C, 'C' },
+    { "IL96", "L4J", WAKE_CAT_H, RECAT_B, 'C' },
+    { "MD11", "L3J", WAKE_CAT_H, RECAT_B, 'D' },
+    { "MD82", "L2J", WAKE_CAT_M, RECAT_D, 'C' },
+    { "MD83", "L2J", WAKE_CAT_M, RECAT_D, 'C' },
+    { "MD88", "L2J", WAKE_CAT_M, RECAT_D, 'C' },
+    { "P28A", "L1P", WAKE_CAT_L, RECAT_F, 'A' },
+    { "PC12", "L1T", WAKE_CAT_L, RECAT_F, 'A' },
+    { "R44", "H1P", WAKE_CAT_L, RECAT_F, 'H' },
+    { "SR22", "L1P", WAKE_CAT_L, RECAT_F, 'A' },
+    { "SU95", "L2J", WAKE_CAT_M, RECAT_E, 'C' },
+    { "TBM9", "L1T", WAKE_CAT_L, RECAT_F, 'A' }
+};
+
+typedef struct {
+	acfttype_t	info;
+	avl_node_t	node;
+} db_type_t;
+
+struct acfttype_db_s {
+	avl_tree_t	types;
+};
+
+static int
+builtin_compar(const void *a, const void *b)
+{
+	const builtin_type_t *ta = a, *tb = b;
+	return (strcmp(ta->type, tb->type));
+}
+
+static int
+db_type_compar(const void *a, const void *b)
+{
+	const db_type_t *ta = a, *tb = b;
+	int res = strcmp(ta->info.type, tb->info.type);
+
+	if (res < 0)
+		return (-1);
+	if (res > 0)
+		return (1);
+	return (0);
+}
+
+/*
+ * Decodes an ICAO type description (e.g. "L2J") into the aircraft class,
+ * number of engines and engine type. Returns B_FALSE if the description
+ * is malformed.
+ */
+static bool_t
+parse_desc(const char *desc, acfttype_t *info)
+{
+	if (strlen(desc) != 3 || desc[1] < '1' || desc[1] > '8')
+		return (B_FALSE);
+	switch (desc[0]) {
+	case 'L':
+		info->cls = ACFT_CLASS_LANDPLANE;
+		break;
+	case 'S':
+		info->cls = ACFT_CLASS_SEAPLANE;
+		break;
+	case 'A':
+		info->cls = ACFT_CLASS_AMPHIBIAN;
+		break;
+	case 'H':
+		info->cls = ACFT_CLASS_HELICOPTER;
+		break;
+	case 'G':
+		info->cls = ACFT_CLASS_GYROCOPTER;
+		break;
+	case 'T':
+		info->cls = ACFT_CLASS_TILTROTOR;
+		break;
+	default:
+		return (B_FALSE);
+	}
+	info->num_eng = desc[1] - '0';
+	switch (desc[2]) {
+	case 'P':
+		info->eng = ACFT_ENG_PISTON;
+		break;
+	case 'T':
+		info->eng = ACFT_ENG_TURBOPROP;
+		break;
+	case 'J':
+		info->eng = ACFT_ENG_JET;
+		break;
+	case 'E':
+		info->eng = ACFT_ENG_ELECTRIC;
+		break;
+	case 'R':
+		info->eng = ACFT_ENG_ROCKET;
+		break;
+	default:
+		return (B_FALSE);
+	}
+	lacf_strlcpy(info->desc, desc, sizeof (info->desc));
+
+	return (B_TRUE);
+}
+
+/*
+ * Looks up an aircraft type in the built-in database.
+ *
+ * @param type ICAO Doc 8643 aircraft type designator (e.g. "B738").
+ * @param info Output structure which will be filled with the type's
+ *	information.
+ *
+ * @return B_TRUE if the type was found, B_FALSE otherwise (in which case
+ *	`info' is left untouched).
+ */
+bool_t
+acfttype_lookup(const char *type, acfttype_t *info)
+{
+	builtin_type_t srch = { .type = type };
+	const builtin_type_t *bt;
+
+	ASSERT(type != NULL);
+	ASSERT(info != NULL);
+
+	bt = bsearch(&srch, builtin_types, ARRAY_NUM_ELEM(builtin_types),
+	    sizeof (*builtin_types), builtin_compar);
+	if (bt == NULL)
+		return (B_FALSE);
+	memset(info, 0, sizeof (*info));
+	lacf_strlcpy(info->type, bt->type, sizeof (info->type));
+	VERIFY(parse_desc(bt->desc, info));
+	info->wtc = bt->wtc;
+	info->recat = bt->recat;
+	info->apch_cat = bt->apch_cat;
+
+	return (B_TRUE);
+}
+
+/*
+ * Creates an aircraft type database, which extends the built-in database
+ * with additional types, or overrides the information of built-in types.
+ * Use acfttype_db_load or acfttype_db_set to add types to the database
+ * and acfttype_db_lookup to look them up. The database isn't internally
+ * locked, so you shouldn't modify it while other threads might be
+ * looking up types in it.
+ */
+acfttype_db_t *
+acfttype_db_alloc(void)
+{
+	acfttype_db_t *db = safe_calloc(1, sizeof (*db));
+
+	avl_create(&db->types, db_type_compar, sizeof (db_type_t),
+	    offsetof(db_type_t, node));
+
+	return (db);
+}
+
+void
+acfttype_db_free(acfttype_db_t *db)
+{
+	void *cookie = NULL;
+	db_type_t *dt;
+
+	if (db == NULL)
+		return;
+	while ((dt = avl_destroy_nodes(&db->types, &cookie)) != NULL)
+		free(dt);
+	avl_destroy(&db->types);
+	free(db);
+}
+
+/*
+ * Adds a type to the database, or replaces the information of a type
+ * already present in the database.
+ */
+void
+acfttype_db_set(acfttype_db_t *db, const acfttype_t *info)
+{
+	db_type_t srch, *dt;
+	avl_index_t where;
+
+	ASSERT(db != NULL);
+	ASSERT(info != NULL);
+	ASSERT(info->type[0] != '\0');
+
+	lacf_strlcpy(srch.info.type, info->type, sizeof (srch.info.type));
+	dt = avl_find(&db->types, &srch, &where);
+	if (dt == NULL) {
+		dt = safe_calloc(1, sizeof (*dt));
+		avl_insert(&db->types, dt, where);
+	}
+	dt->info = *info;
+}
+
+static bool_t
+parse_wtc(const char *str, wake_cat_t *wtc)
+{
+	for (wake_cat_t cat = WAKE_CAT_L; cat < NUM_WAKE_CATS; cat++) {
+		if (strcmp(str, wake_cat2str(cat)) == 0) {
+			*wtc = cat;
+			return (B_TRUE);
+		}
+	}
+	return (B_FALSE);
+}
+
+static bool_t
+parse_recat(const char *str, recat_t *recat)
+{
+	for (recat_t cat = RECAT_A; cat < NUM_RECATS; cat++) {
+		if (strcmp(str, recat2str(cat)) == 0) {
+			*recat = cat;
+			return (B_TRUE);
+		}
+	}
+	return (B_FALSE);
+}
+
+static bool_t
+parse_key(const char *key, const char *value, acfttype_t *info)
+{
+	char buf[8];
+
+	lacf_strlcpy(buf, value, sizeof (buf));
+	strtoupper(buf);
+
+	if (strcmp(key, "desc") == 0)
+		return (parse_desc(buf, info));
+	if (strcmp(key, "wtc") == 0)
+		return (parse_wtc(buf, &info->wtc));
+	if (strcmp(key, "recat") == 0)
+		return (parse_recat(buf, &info->recat));
+	if (strcmp(key, "apch_cat") == 0) {
+		if (strlen(buf) != 1 || ((buf[0] < 'A' || buf[0] > 'E') &&
+		    buf[0] != 'H'))
+			return (B_FALSE);
+		info->apch_cat = buf[0];
+		return (B_TRUE);
+	}
+	return (B_FALSE);
+}
+
+/*
+ * Loads aircraft types from a configuration (see conf.h). Each type is
+ * described by a set of keys prefixed with the type designator:
+ *
+ *	A20N/desc = L2J		(ICAO type description)
+ *	A20N/wtc = M		(ICAO wake turbulence category, L/M/H/J)
+ *	A20N/recat = D		(RECAT-EU category, A - F)
+ *	A20N/apch_cat = C	(approach category, A - E or H)
+ *
+ * If the type is already in the database or in the built-in database,
+ * only the keys present in the configuration are overridden. Otherwise,
+ * unspecified fields are left unknown (or zero).
+ *
+ * @return B_TRUE if all keys were parsed successfully. If a key is
+ *	malformed, an error is logged, the function returns B_FALSE and
+ *	the database is left unmodified.
+ */
+bool_t
+acfttype_db_load(acfttype_db_t *db, const conf_t *conf)
+{
+	acfttype_db_t *tmp = acfttype_db_alloc();
+	const char *key, *value;
+	void *cookie = NULL;
+	db_type_t *dt;
+
+	ASSERT(db != NULL);
+	ASSERT(conf != NULL);
+
+	while (conf_walk(conf, &key, &value, &cookie)) {
+		const char *slash = strchr(key, '/');
+		acfttype_t info;
+
+		if (slash == NULL || slash == key ||
+		    slash - key >= (ptrdiff_t)sizeof (info.type)) {
+			logMsg("Error loading aircraft types: invalid key "
+			    "\"%s\"", key);
+			acfttype_db_free(tmp);
+			return (B_FALSE);
+		}
+		memset(&info, 0, sizeof (info));
+		lacf_strlcpy(info.type, key, slash - key + 1);
+		strtoupper(info.type);
+		if (!acfttype_db_lookup(tmp, info.type, &info))
+			(void) acfttype_db_lookup(db, info.type, &info);
+		if (!parse_key(slash + 1, value, &info)) {
+			logMsg("Error loading aircraft types: invalid key "
+			    "\"%s\" or value \"%s\"", key, value);
+			acfttype_db_free(tmp);
+			return (B_FALSE);
+		}
+		acfttype_db_set(tmp, &info);
+	}
+	for (dt = avl_first(&tmp->types); dt != NULL;
+	    dt = AVL_NEXT(&tmp->types, dt)) {
+		acfttype_db_set(db, &dt->info);
+	}
+	acfttype_db_free(tmp);
+
+	return (B_TRUE);
+}
+
+/*
+ * Same as acfttype_db_load, but reads the configuration from a file.
+ */
+bool_t
+acfttype_db_load_file(acfttype_db_t *db, const char *filename)
+{
+	int errline;
+	conf_t *conf;
+	bool_t res;
+
+	ASSERT(db != NULL);
+	ASSERT(filename != NULL);
+
+	conf = conf_read_file(filename, &errline);
+	if (conf == NULL) {
+		if (errline < 0) {
+			logMsg("Error loading aircraft types from %s: "
+			    "can't open file", filename);
+		} else {
+			logMsg("Error loading aircraft types from %s: "
+			    "syntax error on line %d", filename, errline);
+		}
+		return (B_FALSE);
+	}
+	res = acfttype_db_load(db, conf);
+	conf_free(conf);
+
+	return (res);
+}
+
+/*
+ * Looks up an aircraft type in the database. If the type wasn't added to
+ * the database, falls back to the built-in database (see
+ * acfttype_lookup).
+ */
+bool_t
+acfttype_db_lookup(const acfttype_db_t *db, const char *type,
+    acfttype_t *info)
+{
+	db_type_t srch;
+	const db_type_t *dt;
+
+	ASSERT(db != NULL);
+	ASSERT(type != NULL);
+	ASSERT(info != NULL);
+
+	lacf_strlcpy(srch.info.type, type, sizeof (srch.info.type));
+	dt = avl_find(&db->types, &srch, NULL);
+	if (dt != NULL) {
+		*info = dt->info;
+		return (B_TRUE);
+	}
+	return (acfttype_lookup(type, info));
+}
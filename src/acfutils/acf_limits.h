@@ -0,0 +1,107 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_ACF_LIMITS_H_
+#define	_ACF_UTILS_ACF_LIMITS_H_
+
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Generic aircraft limitation monitor. A limit_t is declared with a
+ * limit value (e.g. VMO in knots, max EGT in degrees C, max load factor
+ * in G) and is then fed the current value of the monitored parameter
+ * once per simulator frame. The monitor tracks exceedances (their
+ * duration and peak value) and notifies an optional callback when an
+ * exceedance begins and ends. The units used are entirely up to the
+ * caller, as long as the limit and the monitored value agree.
+ */
+
+typedef enum {
+	LIMIT_TYPE_MAX,		/* exceeded when value rises above limit */
+	LIMIT_TYPE_MIN		/* exceeded when value drops below limit */
+} limit_type_t;
+
+typedef struct {
+	double		start_t;	/* monitor time at start, seconds */
+	double		dur;		/* duration in seconds */
+	double		peak;		/* most extreme value reached */
+	double		limit;		/* limit in effect when peak was hit */
+} limit_exceed_t;
+
+typedef struct limit_s limit_t;
+
+/*
+ * Exceedance notification callback. Called once when an exceedance
+ * is confirmed (`ended' = B_FALSE) and once when it clears (`ended' =
+ * B_TRUE). In the latter case, `exc' contains the final duration and
+ * peak value of the exceedance.
+ */
+typedef void (*limit_exceed_cb_t)(const limit_t *lim,
+    const limit_exceed_t *exc, bool_t ended, void *userinfo);
+
+struct limit_s {
+	char			name[32];
+	limit_type_t		type;
+	double			limit;
+	double			hyst;
+	double			min_dur;
+	double			now;
+
+	bool_t			active;
+	bool_t			confirmed;
+	limit_exceed_t		cur;
+	limit_exceed_t		last;
+	unsigned		num_exceed;
+	double			total_dur;
+
+	limit_exceed_cb_t	cb;
+	void			*cb_userinfo;
+};
+
+API_EXPORT void limit_init(limit_t *lim, const char *name, limit_type_t type,
+    double limit, double hyst);
+API_EXPORT void limit_reset(limit_t *lim);
+
+API_EXPORT void limit_set_limit(limit_t *lim, double limit);
+API_EXPORT double limit_get_limit(const limit_t *lim);
+API_EXPORT void limit_set_hyst(limit_t *lim, double hyst);
+API_EXPORT double limit_get_hyst(const limit_t *lim);
+API_EXPORT void limit_set_min_dur(limit_t *lim, double min_dur);
+API_EXPORT double limit_get_min_dur(const limit_t *lim);
+API_EXPORT void limit_set_cb(limit_t *lim, limit_exceed_cb_t cb,
+    void *userinfo);
+
+API_EXPORT bool_t limit_update(limit_t *lim, double value, double d_t);
+API_EXPORT bool_t limit_is_exceeded(const limit_t *lim);
+API_EXPORT bool_t limit_get_cur_exceed(const limit_t *lim,
+    limit_exceed_t *exc);
+API_EXPORT bool_t limit_get_last_exceed(const limit_t *lim,
+    limit_exceed_t *exc);
+API_EXPORT unsigned limit_get_num_exceed(const limit_t *lim);
+API_EXPORT double limit_get_total_dur(const limit_t *lim);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_ACF_LIMITS_H_ */
@@ -0,0 +1,82 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_ACFTTYPE_H_
+#define	_ACF_UTILS_ACFTTYPE_H_
+
+#include "conf.h"
+#include "sysmacros.h"
+#include "types.h"
+#include "wake.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Aircraft type designator database. See acfttype.c for usage info.
+ */
+
+typedef enum {
+	ACFT_CLASS_UNKNOWN,
+	ACFT_CLASS_LANDPLANE,	/* "L" */
+	ACFT_CLASS_SEAPLANE,	/* "S" */
+	ACFT_CLASS_AMPHIBIAN,	/* "A" */
+	ACFT_CLASS_HELICOPTER,	/* "H" */
+	ACFT_CLASS_GYROCOPTER,	/* "G" */
+	ACFT_CLASS_TILTROTOR	/* "T" */
+} acft_class_t;
+
+typedef enum {
+	ACFT_ENG_UNKNOWN,
+	ACFT_ENG_PISTON,	/* "P" */
+	ACFT_ENG_TURBOPROP,	/* "T", includes turboshaft */
+	ACFT_ENG_JET,		/* "J" */
+	ACFT_ENG_ELECTRIC,	/* "E" */
+	ACFT_ENG_ROCKET		/* "R" */
+} acft_eng_t;
+
+typedef struct {
+	char		type[8];	/* ICAO type designator, e.g. "B738" */
+	char		desc[4];	/* ICAO type description, e.g. "L2J" */
+	acft_class_t	cls;
+	unsigned	num_eng;
+	acft_eng_t	eng;
+	wake_cat_t	wtc;
+	recat_t		recat;
+	char		apch_cat;	/* 'A' - 'E', 'H', or 0 if unknown */
+} acfttype_t;
+
+typedef struct acfttype_db_s acfttype_db_t;
+
+API_EXPORT bool_t acfttype_lookup(const char *type, acfttype_t *info);
+
+API_EXPORT acfttype_db_t *acfttype_db_alloc(void);
+API_EXPORT void acfttype_db_free(acfttype_db_t *db);
+API_EXPORT bool_t acfttype_db_load(acfttype_db_t *db, const conf_t *conf);
+API_EXPORT bool_t acfttype_db_load_file(acfttype_db_t *db,
+    const char *filename);
+API_EXPORT void acfttype_db_set(acfttype_db_t *db, const acfttype_t *info);
+API_EXPORT bool_t acfttype_db_lookup(const acfttype_db_t *db,
+    const char *type, acfttype_t *info);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_ACFTTYPE_H_ */
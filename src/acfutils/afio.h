@@ -0,0 +1,59 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_AFIO_H_
+#define	_ACF_UTILS_AFIO_H_
+
+#include <stdlib.h>
+
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Asynchronous file I/O performed on background worker threads, with
+ * completion callbacks delivered on the caller's thread. See afio.c for
+ * usage info.
+ */
+
+typedef struct afio_s afio_t;
+typedef struct afio_req_s afio_req_t;
+
+typedef void (*afio_done_cb_t)(const char *path, void *buf, size_t len,
+    int err, void *userinfo);
+
+API_EXPORT afio_t *afio_alloc(unsigned max_threads);
+API_EXPORT void afio_free(afio_t *afio);
+
+API_EXPORT afio_req_t *afio_read(afio_t *afio, const char *path,
+    afio_done_cb_t done_cb, void *userinfo);
+API_EXPORT afio_req_t *afio_write(afio_t *afio, const char *path,
+    const void *buf, size_t len, afio_done_cb_t done_cb, void *userinfo);
+API_EXPORT void afio_cancel(afio_t *afio, afio_req_t *req);
+
+API_EXPORT unsigned afio_poll(afio_t *afio);
+API_EXPORT unsigned afio_get_num_pending(afio_t *afio);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_AFIO_H_ */
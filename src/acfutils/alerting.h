@@ -0,0 +1,87 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_ALERTING_H_
+#define	_ACF_UTILS_ALERTING_H_
+
+#include "assert.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Priority arbitration between simultaneously active flight deck
+ * alerts, so that only the most important aural & visual alert is
+ * annunciated. The sources are listed in order of increasing priority,
+ * following the usual stall > windshear > TAWS > TCAS ordering.
+ */
+typedef enum {
+	ALERT_NONE,
+	ALERT_TCAS_TA,		/* TCAS traffic advisory */
+	ALERT_TCAS_RA,		/* TCAS resolution advisory */
+	ALERT_TAWS_CAUTION,	/* TAWS/GPWS caution */
+	ALERT_WS_CAUTION,	/* windshear caution */
+	ALERT_TAWS_WARNING,	/* TAWS/GPWS warning ("PULL UP") */
+	ALERT_WS_WARNING,	/* windshear warning */
+	ALERT_STALL,		/* stall warning */
+	NUM_ALERT_SRCS
+} alert_src_t;
+
+#define	ALERT_MASK(src)	(1u << (src))
+
+/*
+ * Given a bitmask of active alerts (constructed using ALERT_MASK), returns
+ * the highest priority alert which should be annunciated, or ALERT_NONE.
+ * While a windshear or TAWS warning is active, TCAS resolution advisories
+ * are inhibited, since an RA commanding a descent could conflict with the
+ * escape maneuver. The RA is then reported as a traffic advisory instead.
+ */
+static inline alert_src_t
+alert_arbitrate(unsigned active)
+{
+	ASSERT0(active & ~((1u << NUM_ALERT_SRCS) - 1));
+	if ((active & ALERT_MASK(ALERT_TCAS_RA)) &&
+	    (active & (ALERT_MASK(ALERT_TAWS_WARNING) |
+	    ALERT_MASK(ALERT_WS_WARNING)))) {
+		active &= ~ALERT_MASK(ALERT_TCAS_RA);
+		active |= ALERT_MASK(ALERT_TCAS_TA);
+	}
+	for (int src = NUM_ALERT_SRCS - 1; src > ALERT_NONE; src--) {
+		if (active & ALERT_MASK(src))
+			return ((alert_src_t)src);
+	}
+	return (ALERT_NONE);
+}
+
+/*
+ * Returns true if `src' is the alert which should be annunciated given
+ * the currently active alerts in `active'.
+ */
+static inline bool_t
+alert_is_annunciated(unsigned active, alert_src_t src)
+{
+	return (src != ALERT_NONE && alert_arbitrate(active) == src);
+}
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_ALERTING_H_ */
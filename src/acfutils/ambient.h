@@ -0,0 +1,64 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_AMBIENT_H_
+#define	_ACF_UTILS_AMBIENT_H_
+
+#include <time.h>
+
+#include "geom.h"
+#include "quat.h"
+#include "sysmacros.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Sun position and cockpit ambient light estimation for automatic
+ * display brightness control. See ambient.c for usage info.
+ */
+
+typedef struct {
+	/* Smoothed outputs. Read these, but don't modify them. */
+	double	lux;		/* cockpit ambient illuminance, lux */
+	double	glare;		/* 0 = no glare, 1 = sun straight ahead */
+
+	/* Parameters, set by ambient_init, may be modified by the caller. */
+	double	lag;		/* smoothing lag, seconds */
+	double	window_trans;	/* fraction of outside light in cockpit */
+
+	/* private */
+	double	log_lux;
+} ambient_t;
+
+API_EXPORT void ambient_sun_pos(time_t t, geo_pos2_t pos, double *elev,
+    double *azi);
+API_EXPORT double ambient_outside_lux(double sun_elev, double cloud);
+
+API_EXPORT void ambient_init(ambient_t *amb, double lag);
+API_EXPORT void ambient_update(ambient_t *amb, double sun_elev,
+    double sun_azi, euler_t att, double cloud, double d_t);
+API_EXPORT double ambient_brightness(const ambient_t *amb,
+    double min_bright, double max_bright);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_AMBIENT_H_ */
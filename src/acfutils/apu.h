@@ -0,0 +1,85 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_APU_H_
+#define	_ACF_UTILS_APU_H_
+
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Auxiliary power unit model. See apu.c for usage info.
+ */
+
+typedef enum {
+	APU_STATE_OFF,
+	APU_STATE_STARTING,
+	APU_STATE_RUNNING,
+	APU_STATE_COOLDOWN,	/* running unloaded before shutdown */
+	APU_STATE_SPOOLDOWN	/* fuel cut off, spinning down */
+} apu_state_t;
+
+typedef enum {
+	APU_FAULT_NONE,
+	APU_FAULT_NO_LIGHTOFF,	/* failed to ignite during start */
+	APU_FAULT_OVERTEMP,	/* EGT exceeded the limit */
+	APU_FAULT_FIRE,
+	APU_FAULT_FUEL		/* fuel supply lost */
+} apu_fault_t;
+
+typedef struct {
+	bool_t		master;		/* APU master switch */
+	bool_t		start;		/* start switch (momentary) */
+	bool_t		dc_power;	/* starter power available */
+	bool_t		fuel_avail;	/* fuel pressure available */
+	bool_t		fire;		/* fire detected or handle pulled */
+	double		oat;		/* degrees C */
+	double		bleed_load;	/* 0 - 1, fraction of max bleed flow */
+	double		elec_load;	/* 0 - 1, fraction of gen rating */
+
+	/* failures */
+	bool_t		fail_start;	/* ignition failure */
+	bool_t		fail_overtemp;	/* fuel control runs hot */
+	bool_t		fail_gen;	/* generator failure */
+} apu_inputs_t;
+
+typedef struct apu_s apu_t;
+
+API_EXPORT apu_t *apu_alloc(void);
+API_EXPORT void apu_free(apu_t *apu);
+API_EXPORT void apu_update(apu_t *apu, const apu_inputs_t *in, double d_t);
+
+API_EXPORT apu_state_t apu_get_state(const apu_t *apu);
+API_EXPORT apu_fault_t apu_get_fault(const apu_t *apu);
+API_EXPORT double apu_get_n(const apu_t *apu);
+API_EXPORT double apu_get_egt(const apu_t *apu);
+API_EXPORT double apu_get_ff(const apu_t *apu);
+API_EXPORT bool_t apu_is_avail(const apu_t *apu);
+API_EXPORT bool_t apu_bleed_is_avail(const apu_t *apu);
+API_EXPORT bool_t apu_gen_is_avail(const apu_t *apu);
+API_EXPORT double apu_get_gen_freq(const apu_t *apu);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_APU_H_ */
@@ -0,0 +1,96 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_ARINC717_H_
+#define	_ACF_UTILS_ARINC717_H_
+
+#include <stdint.h>
+
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * ARINC 717 flight data frame encoder. See arinc717.c for usage info.
+ */
+
+#define	ARINC717_NUM_SUBFRAMES	4
+#define	ARINC717_WORD_BITS	12
+#define	ARINC717_WORD_MASK	0xfff
+
+/* Standard sync words for subframes 1 through 4 (in octal) */
+#define	ARINC717_SYNC1		01107
+#define	ARINC717_SYNC2		02670
+#define	ARINC717_SYNC3		05107
+#define	ARINC717_SYNC4		06670
+
+/* Subframe masks for arinc717_param_add */
+#define	ARINC717_SF1		(1 << 0)
+#define	ARINC717_SF2		(1 << 1)
+#define	ARINC717_SF3		(1 << 2)
+#define	ARINC717_SF4		(1 << 3)
+#define	ARINC717_SF_ALL	\
+	(ARINC717_SF1 | ARINC717_SF2 | ARINC717_SF3 | ARINC717_SF4)
+
+typedef enum {
+	ARINC717_ENC_BNR,	/* two's complement binary */
+	ARINC717_ENC_UNSIGNED,	/* unsigned binary */
+	ARINC717_ENC_BCD,	/* binary coded decimal */
+	ARINC717_ENC_DISCRETE	/* single bit on/off */
+} arinc717_enc_t;
+
+typedef enum {
+	ARINC717_OUT_RAW16,	/* each 12-bit word in a 16-bit LE integer */
+	ARINC717_OUT_PACKED12,	/* continuous big-endian 12-bit bitstream */
+	ARINC717_OUT_CSV	/* decoded engineering values, CSV text */
+} arinc717_out_t;
+
+typedef struct arinc717_s arinc717_t;
+
+API_EXPORT arinc717_t *arinc717_alloc(unsigned wps);
+API_EXPORT void arinc717_free(arinc717_t *enc);
+
+API_EXPORT int arinc717_param_add(arinc717_t *enc, const char *name,
+    unsigned sf_mask, unsigned word, unsigned lsb_bit, unsigned num_bits,
+    arinc717_enc_t encoding, double resolution, double offset);
+API_EXPORT int arinc717_param_find(const arinc717_t *enc, const char *name);
+API_EXPORT unsigned arinc717_get_num_params(const arinc717_t *enc);
+API_EXPORT unsigned arinc717_get_wps(const arinc717_t *enc);
+
+API_EXPORT void arinc717_param_set(arinc717_t *enc, int param_idx,
+    double value);
+API_EXPORT double arinc717_param_get(const arinc717_t *enc, int param_idx);
+
+API_EXPORT void arinc717_encode_subframe(const arinc717_t *enc,
+    unsigned subframe, uint16_t *words);
+API_EXPORT double arinc717_decode(const arinc717_t *enc, int param_idx,
+    const uint16_t *words);
+
+API_EXPORT bool_t arinc717_open(arinc717_t *enc, const char *filename,
+    arinc717_out_t fmt);
+API_EXPORT void arinc717_close(arinc717_t *enc);
+API_EXPORT bool_t arinc717_write_subframe(arinc717_t *enc);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_ARINC717_H_ */
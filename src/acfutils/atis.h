@@ -0,0 +1,50 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_ATIS_H_
+#define	_ACF_UTILS_ATIS_H_
+
+#include "airportdb.h"
+#include "metar.h"
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Spoken-style ATIS text generator. See atis.c for usage info.
+ */
+
+typedef struct atis_s atis_t;
+
+API_EXPORT atis_t *atis_alloc(const char *name);
+API_EXPORT void atis_free(atis_t *atis);
+
+API_EXPORT bool_t atis_update(atis_t *atis, const metar_t *metar,
+    const airport_t *arpt);
+API_EXPORT char atis_get_letter(const atis_t *atis);
+API_EXPORT const char *atis_get_rwy(const atis_t *atis);
+API_EXPORT const char *atis_get_text(const atis_t *atis);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_ATIS_H_ */
@@ -0,0 +1,98 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_AURAL_H_
+#define	_ACF_UTILS_AURAL_H_
+
+#include "acf_limits.h"
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Common transport aircraft aural warning logic (cabin altitude horn,
+ * overspeed clacker, takeoff configuration horn and altitude alert).
+ * See aural.c for usage info.
+ */
+
+typedef enum {
+	AURAL_CABIN_ALT,	/* cabin altitude horn */
+	AURAL_OVERSPEED,	/* overspeed clacker */
+	AURAL_TO_CONFIG,	/* takeoff configuration horn */
+	AURAL_ALT_ALERT,	/* altitude alert chime */
+	NUM_AURALS
+} aural_id_t;
+
+typedef enum {
+	AURAL_PH_GROUND,
+	AURAL_PH_TAKEOFF,
+	AURAL_PH_CLIMB,
+	AURAL_PH_CRUISE,
+	AURAL_PH_DESCENT,
+	AURAL_PH_APPROACH,
+	AURAL_PH_LANDING,
+	NUM_AURAL_PHASES
+} aural_phase_t;
+
+#define	AURAL_PH_MASK(phase)	(1u << (phase))
+
+typedef enum {
+	AURAL_EVT_START,	/* continuous sound starts */
+	AURAL_EVT_STOP,		/* continuous sound stops */
+	AURAL_EVT_ONESHOT	/* play a sound once */
+} aural_evt_t;
+
+typedef struct {
+	aural_phase_t	phase;
+	double		cabin_alt;	/* feet */
+	double		ias;		/* knots */
+	double		vmo;		/* knots, current (may vary) */
+	double		mach;
+	double		mmo;
+	bool_t		to_thrust;	/* thrust levers in takeoff range */
+	bool_t		to_config_ok;	/* flaps, trim, brakes, etc. OK */
+	double		alt;		/* indicated altitude, feet */
+	double		sel_alt;	/* MCP/FCU selected altitude, feet */
+} aural_inputs_t;
+
+typedef void (*aural_cb_t)(aural_id_t id, aural_evt_t evt, void *userinfo);
+
+typedef struct aural_s aural_t;
+
+API_EXPORT aural_t *aural_alloc(aural_cb_t cb, void *userinfo);
+API_EXPORT void aural_free(aural_t *aur);
+
+API_EXPORT void aural_update(aural_t *aur, const aural_inputs_t *in,
+    double d_t);
+API_EXPORT bool_t aural_is_active(const aural_t *aur, aural_id_t id);
+API_EXPORT void aural_silence(aural_t *aur, aural_id_t id);
+
+API_EXPORT void aural_set_inhibit(aural_t *aur, aural_id_t id,
+    unsigned phase_mask);
+API_EXPORT unsigned aural_get_inhibit(const aural_t *aur, aural_id_t id);
+API_EXPORT void aural_set_cabin_alt_limit(aural_t *aur, double cabin_alt);
+API_EXPORT limit_t *aural_get_limit(aural_t *aur, aural_id_t id);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_AURAL_H_ */
@@ -0,0 +1,97 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_BLEED_H_
+#define	_ACF_UTILS_BLEED_H_
+
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Bleed air & anti-ice system model with engine performance penalties.
+ * See bleed.c for usage info.
+ */
+
+#define	BLEED_MAX_ENG	4
+
+typedef enum {
+	BLEED_SIDE_L,
+	BLEED_SIDE_R,
+	NUM_BLEED_SIDES
+} bleed_side_t;
+
+typedef enum {
+	BLEED_XBLEED_CLOSED,
+	BLEED_XBLEED_AUTO,
+	BLEED_XBLEED_OPEN
+} bleed_xbleed_t;
+
+typedef struct {
+	/* engine & ambient state */
+	double		n2[BLEED_MAX_ENG];	/* percent */
+	double		oat;			/* degrees C */
+	bool_t		apu_avail;		/* APU running & ready */
+	bool_t		gnd_cart;		/* ground cart connected */
+
+	/* cockpit switches */
+	bool_t		eng_bleed[BLEED_MAX_ENG];
+	bool_t		apu_bleed;
+	bleed_xbleed_t	xbleed;
+	bool_t		pack[NUM_BLEED_SIDES];
+	bool_t		wai;			/* wing anti-ice */
+	bool_t		eai[BLEED_MAX_ENG];	/* engine anti-ice */
+
+	/* failures */
+	bool_t		eng_bleed_fail[BLEED_MAX_ENG];
+	bool_t		leak[NUM_BLEED_SIDES];
+} bleed_inputs_t;
+
+typedef struct bleed_s bleed_t;
+
+API_EXPORT bleed_t *bleed_alloc(unsigned num_eng);
+API_EXPORT void bleed_free(bleed_t *bleed);
+API_EXPORT void bleed_update(bleed_t *bleed, const bleed_inputs_t *in,
+    double d_t);
+
+API_EXPORT double bleed_get_press(const bleed_t *bleed, bleed_side_t side);
+API_EXPORT double bleed_get_temp(const bleed_t *bleed, bleed_side_t side);
+API_EXPORT bool_t bleed_xbleed_is_open(const bleed_t *bleed);
+API_EXPORT bool_t bleed_apu_valve_is_open(const bleed_t *bleed);
+API_EXPORT bool_t bleed_eng_valve_is_open(const bleed_t *bleed,
+    unsigned eng);
+API_EXPORT bool_t bleed_hp_valve_is_open(const bleed_t *bleed, unsigned eng);
+
+API_EXPORT bool_t bleed_pack_is_supplied(const bleed_t *bleed,
+    bleed_side_t side);
+API_EXPORT bool_t bleed_wai_is_supplied(const bleed_t *bleed,
+    bleed_side_t side);
+API_EXPORT bool_t bleed_eai_is_supplied(const bleed_t *bleed, unsigned eng);
+
+API_EXPORT double bleed_get_eng_flow(const bleed_t *bleed, unsigned eng);
+API_EXPORT double bleed_get_n1_penalty(const bleed_t *bleed, unsigned eng);
+API_EXPORT double bleed_get_ff_penalty(const bleed_t *bleed, unsigned eng);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_BLEED_H_ */
@@ -0,0 +1,71 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_BRAKE_H_
+#define	_ACF_UTILS_BRAKE_H_
+
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Wheel brake thermal model with energy accounting and fuse plugs.
+ * See brake.c for usage info.
+ */
+
+typedef enum {
+	BRAKE_EVT_STOP_END,	/* a braking stop has ended */
+	BRAKE_EVT_FUSE_MELT	/* fuse plug melted, tire deflated */
+} brake_evt_t;
+
+typedef struct {
+	unsigned	num_stops;
+	double		total_energy;		/* J */
+	double		last_stop_energy;	/* J */
+	double		max_stop_energy;	/* J */
+	double		peak_temp;		/* degrees C */
+} brake_stats_t;
+
+typedef void (*brake_cb_t)(brake_evt_t evt, unsigned idx, void *userinfo);
+
+typedef struct brake_s brake_t;
+
+API_EXPORT brake_t *brake_alloc(unsigned num_brakes, double mass,
+    double spec_heat, double oat, brake_cb_t cb, void *userinfo);
+API_EXPORT void brake_free(brake_t *brk);
+API_EXPORT void brake_update(brake_t *brk, const double *force, double gs,
+    double oat, double d_t);
+
+API_EXPORT double brake_get_temp(const brake_t *brk, unsigned idx);
+API_EXPORT double brake_get_wheel_temp(const brake_t *brk, unsigned idx);
+API_EXPORT double brake_get_stop_energy(const brake_t *brk, unsigned idx);
+API_EXPORT bool_t brake_fuse_melted(const brake_t *brk, unsigned idx);
+API_EXPORT void brake_get_stats(const brake_t *brk, unsigned idx,
+    brake_stats_t *stats);
+API_EXPORT void brake_set_fuse_temp(brake_t *brk, double temp);
+API_EXPORT void brake_service(brake_t *brk, unsigned idx);
+API_EXPORT void brake_replace(brake_t *brk, unsigned idx);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_BRAKE_H_ */
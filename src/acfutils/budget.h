@@ -0,0 +1,63 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_BUDGET_H_
+#define	_ACF_UTILS_BUDGET_H_
+
+#include <stdint.h>
+
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Per-frame time budgeting of incremental tasks. See budget.c for usage
+ * info.
+ */
+
+/*
+ * Performs one small step of a task. Return B_TRUE if the task has more
+ * work to do, or B_FALSE once it is complete, which removes the task.
+ */
+typedef bool_t (*budget_step_t)(void *userinfo);
+
+typedef struct budget_s budget_t;
+typedef struct budget_task_s budget_task_t;
+
+API_EXPORT budget_t *budget_alloc(uint64_t budget_us);
+API_EXPORT void budget_free(budget_t *budget);
+API_EXPORT void budget_set_budget(budget_t *budget, uint64_t budget_us);
+API_EXPORT uint64_t budget_get_budget(const budget_t *budget);
+
+API_EXPORT budget_task_t *budget_task_add(budget_t *budget,
+    budget_step_t step, int prio, void *userinfo);
+API_EXPORT void budget_task_remove(budget_t *budget, budget_task_t *task);
+API_EXPORT size_t budget_get_num_tasks(const budget_t *budget);
+
+API_EXPORT void budget_run(budget_t *budget);
+API_EXPORT uint64_t budget_get_last_run_us(const budget_t *budget);
+API_EXPORT unsigned budget_get_last_num_steps(const budget_t *budget);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_BUDGET_H_ */
@@ -0,0 +1,90 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_CAS_H_
+#define	_ACF_UTILS_CAS_H_
+
+#include <stdint.h>
+#include <stdlib.h>
+
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Crew alerting system (EICAS/ECAM style) message model. See cas.c for
+ * usage info.
+ */
+
+typedef enum {
+	CAS_ADVISORY,
+	CAS_CAUTION,
+	CAS_WARNING
+} cas_level_t;
+
+typedef enum {
+	CAS_EVT_ACTIVATE,	/* message appeared on the display */
+	CAS_EVT_DEACTIVATE	/* message disappeared from the display */
+} cas_evt_t;
+
+/* Builds a flight phase inhibit mask from flight phase numbers 0 - 31 */
+#define	CAS_PHASE(phase)	(1u << (phase))
+
+typedef struct {
+	const char	*text;		/* message text */
+	cas_level_t	level;
+	uint32_t	inhibit;	/* mask of CAS_PHASE() values */
+	bool_t		latching;	/* stays on until cleared */
+} cas_alert_def_t;
+
+typedef struct cas_s cas_t;
+typedef struct cas_alert_s cas_alert_t;
+
+typedef void (*cas_cb_t)(cas_evt_t evt, const char *text, cas_level_t level,
+    void *userinfo);
+
+API_EXPORT cas_t *cas_alloc(cas_cb_t cb, void *userinfo);
+API_EXPORT void cas_free(cas_t *cas);
+
+API_EXPORT cas_alert_t *cas_alert_add(cas_t *cas, const cas_alert_def_t *def);
+API_EXPORT void cas_alert_remove(cas_t *cas, cas_alert_t *alert);
+API_EXPORT void cas_alert_set(cas_alert_t *alert, bool_t active);
+API_EXPORT bool_t cas_alert_is_active(const cas_alert_t *alert);
+API_EXPORT void cas_alert_clear(cas_alert_t *alert);
+API_EXPORT void cas_clear_all(cas_t *cas);
+
+API_EXPORT void cas_set_phase(cas_t *cas, unsigned phase);
+API_EXPORT unsigned cas_get_phase(const cas_t *cas);
+API_EXPORT void cas_update(cas_t *cas);
+
+API_EXPORT size_t cas_get_num_msgs(const cas_t *cas);
+API_EXPORT const char *cas_get_msg(const cas_t *cas, size_t idx,
+    cas_level_t *level);
+
+API_EXPORT bool_t cas_master_warning(const cas_t *cas);
+API_EXPORT bool_t cas_master_caution(const cas_t *cas);
+API_EXPORT void cas_master_ack(cas_t *cas);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_CAS_H_ */
@@ -134,6 +134,19 @@ API_EXPORT bool conf_get_b2_v(const conf_t *conf,
 #define	conf_set_b2_v	conf_set_b_v
 #endif	/* __STDC_VERSION__ >= 199901L */
 
+API_EXPORT size_t conf_get_str_arr(const conf_t *conf, const char *key,
+    const char **values, size_t cap);
+API_EXPORT size_t conf_get_lli_arr(const conf_t *conf, const char *key,
+    long long *values, size_t cap);
+API_EXPORT size_t conf_get_d_arr(const conf_t *conf, const char *key,
+    double *values, size_t cap);
+API_EXPORT void conf_set_str_arr(conf_t *conf, const char *key,
+    const char *const *values, size_t n);
+API_EXPORT void conf_set_lli_arr(conf_t *conf, const char *key,
+    const long long *values, size_t n);
+API_EXPORT void conf_set_d_arr(conf_t *conf, const char *key,
+    const double *values, size_t n);
+
 API_EXPORT bool_t conf_walk(const conf_t *conf, const char **key,
     const char **value, void **cookie);
 
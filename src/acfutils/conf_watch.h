@@ -0,0 +1,53 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_CONF_WATCH_H_
+#define	_ACF_UTILS_CONF_WATCH_H_
+
+#include <stdint.h>
+#include <stdlib.h>
+
+#include "conf.h"
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Configuration file change monitoring. See conf_watch.c for usage info.
+ */
+
+typedef struct conf_watch_s conf_watch_t;
+
+typedef void (*conf_watch_cb_t)(const conf_t *conf, const char *const *keys,
+    size_t num_keys, void *userinfo);
+
+API_EXPORT conf_watch_t *conf_watch_alloc(const char *path,
+    uint64_t intval_us, conf_watch_cb_t cb, void *userinfo);
+API_EXPORT void conf_watch_free(conf_watch_t *cw);
+
+API_EXPORT bool_t conf_watch_poll(conf_watch_t *cw);
+API_EXPORT const conf_t *conf_watch_get_conf(const conf_watch_t *cw);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_CONF_WATCH_H_ */
@@ -0,0 +1,58 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_COORDPARSE_H_
+#define	_ACF_UTILS_COORDPARSE_H_
+
+#include "geom.h"
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Parsing of geographic coordinates in the many formats used in user
+ * input and flight plans. See coordparse.c for usage info.
+ */
+
+typedef enum {
+	COORD_OK,
+	COORD_ERR_EMPTY,	/* input is empty */
+	COORD_ERR_SYNTAX,	/* unrecognized format */
+	COORD_ERR_HEMI,		/* missing, duplicate or misplaced N/S/E/W */
+	COORD_ERR_LAT,		/* latitude out of range */
+	COORD_ERR_LON,		/* longitude out of range */
+	COORD_ERR_MIN,		/* minutes out of range */
+	COORD_ERR_SEC		/* seconds out of range */
+} coord_err_t;
+
+API_EXPORT bool_t coord_parse(const char *str, geo_pos2_t *pos,
+    coord_err_t *err);
+API_EXPORT bool_t coord_parse_lat(const char *str, double *lat,
+    coord_err_t *err);
+API_EXPORT bool_t coord_parse_lon(const char *str, double *lon,
+    coord_err_t *err);
+API_EXPORT const char *coord_err2str(coord_err_t err);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_COORDPARSE_H_ */
@@ -51,6 +51,26 @@ API_EXPORT double crc64_rand_fract(void);
 #define	crc64_rand_normal	ACFSYM(crc64_rand_normal)
 API_EXPORT double crc64_rand_normal(double sigma);
 
+/*
+ * Independent PRNG instance. The state is a plain integer, so you can
+ * copy, save and restore it to reproduce a random sequence.
+ */
+typedef struct {
+	uint64_t	state;
+} crc64_rng_t;
+
+#define	crc64_rng_seed		ACFSYM(crc64_rng_seed)
+API_EXPORT void crc64_rng_seed(crc64_rng_t *rng, uint64_t seed);
+#define	crc64_rng_next		ACFSYM(crc64_rng_next)
+API_EXPORT uint64_t crc64_rng_next(crc64_rng_t *rng);
+#define	crc64_rng_fract		ACFSYM(crc64_rng_fract)
+API_EXPORT double crc64_rng_fract(crc64_rng_t *rng);
+#define	crc64_rng_normal	ACFSYM(crc64_rng_normal)
+API_EXPORT double crc64_rng_normal(crc64_rng_t *rng, double sigma);
+#define	crc64_rng_range		ACFSYM(crc64_rng_range)
+API_EXPORT uint64_t crc64_rng_range(crc64_rng_t *rng, uint64_t lo,
+    uint64_t hi);
+
 #ifdef	__cplusplus
 }
 #endif
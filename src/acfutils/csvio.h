@@ -0,0 +1,69 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_CSVIO_H_
+#define	_ACF_UTILS_CSVIO_H_
+
+#include <stdio.h>
+
+#include "geom.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * CSV data table reading & writing. See csvio.c for usage info.
+ */
+
+typedef struct csv_s csv_t;
+
+API_EXPORT csv_t *csv_parse(const char *buf, char sep);
+API_EXPORT csv_t *csv_read(const char *path, char sep);
+API_EXPORT void csv_free(csv_t *csv);
+
+API_EXPORT unsigned csv_get_num_cols(const csv_t *csv);
+API_EXPORT unsigned csv_get_num_rows(const csv_t *csv);
+API_EXPORT const char *csv_col_name(const csv_t *csv, unsigned col);
+API_EXPORT const char *csv_col_unit(const csv_t *csv, unsigned col);
+API_EXPORT int csv_find_col(const csv_t *csv, const char *name);
+
+API_EXPORT const char *csv_get_str(const csv_t *csv, unsigned row,
+    unsigned col);
+API_EXPORT double csv_get_d(const csv_t *csv, unsigned row, unsigned col);
+API_EXPORT double csv_get_base(const csv_t *csv, unsigned row, unsigned col);
+
+API_EXPORT bool_t csv_unit_conv(const char *unit, double *mult,
+    double *offset);
+API_EXPORT double csv_str2d(const char *str, char sep);
+
+API_EXPORT vect2_t *csv_get_curve(const csv_t *csv, unsigned xcol,
+    unsigned ycol, bool_t base_units, size_t *num_pts);
+API_EXPORT double csv_interp2(const csv_t *csv, double x, double y);
+
+API_EXPORT void csv_write_row(FILE *fp, char sep, const char *const *fields,
+    size_t n);
+API_EXPORT void csv_write_row_d(FILE *fp, char sep, const double *vals,
+    size_t n, int prec);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_CSVIO_H_ */
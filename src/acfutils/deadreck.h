@@ -0,0 +1,71 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_DEADRECK_H_
+#define	_ACF_UTILS_DEADRECK_H_
+
+#include "geom.h"
+#include "sysmacros.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Dead reckoning navigation state propagator. See deadreck.c for usage
+ * info.
+ */
+
+typedef struct {
+	/*
+	 * Current estimated state. Read these, but use the functions
+	 * below to modify them.
+	 */
+	geo_pos2_t	pos;
+	double		trk;		/* ground track, degrees true */
+	double		gs;		/* ground speed, m/s */
+	double		err;		/* position uncertainty, meters */
+	double		t_fix;		/* seconds since the last fix */
+
+	/* velocity inputs */
+	double		hdg;		/* true heading, degrees */
+	double		tas;		/* true airspeed, m/s */
+	double		wind_dir;	/* wind from, degrees true */
+	double		wind_spd;	/* m/s */
+
+	/* uncertainty growth parameters */
+	double		err_fix;	/* uncertainty at the last fix, m */
+	double		drift_rate;	/* m/s, default 1 NM/hr */
+	double		wind_err;	/* m/s, default 0 */
+} deadreck_t;
+
+API_EXPORT void deadreck_init(deadreck_t *dr, geo_pos2_t pos, double err);
+API_EXPORT void deadreck_fix(deadreck_t *dr, geo_pos2_t pos, double err);
+API_EXPORT void deadreck_set_vel(deadreck_t *dr, double hdg, double tas,
+    double wind_dir, double wind_spd);
+API_EXPORT void deadreck_set_err_params(deadreck_t *dr, double drift_rate,
+    double wind_err);
+API_EXPORT void deadreck_step(deadreck_t *dr, double d_t);
+API_EXPORT void deadreck_predict(const deadreck_t *dr, double d_t,
+    geo_pos2_t *pos, double *err);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_DEADRECK_H_ */
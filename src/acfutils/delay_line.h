@@ -26,6 +26,7 @@
 #ifndef	_ACF_UTILS_DELAY_LINE_H_
 #define	_ACF_UTILS_DELAY_LINE_H_
 
+#include <math.h>
 #include <string.h>
 #include <stdint.h>
 
@@ -101,6 +102,32 @@ delay_line_init_time_func(delay_line_t *line, uint64_t delay_us,
 	line->time_func_userinfo = time_func_userinfo;
 }
 
+/*
+ * Time source for delay lines using the system's monotonic clock. The
+ * default time source of a delay line (microclock) follows the real
+ * time clock, so when the system clock is adjusted (e.g. by NTP or the
+ * user), pending changes in the delay line can fire too early or get
+ * stuck for a long time. The monotonic clock is immune to that, but its
+ * epoch is arbitrary, so delay lines using it shouldn't be serialized
+ * and restored in a different process.
+ */
+static inline uint64_t
+delay_line_mono_time(void *userinfo)
+{
+	UNUSED(userinfo);
+	return (nanoclock() / 1000llu);
+}
+
+/*
+ * Same as delay_line_init, but makes the delay line use the monotonic
+ * clock as its time source (see delay_line_mono_time).
+ */
+static inline void
+delay_line_init_mono(delay_line_t *line, uint64_t delay_us)
+{
+	delay_line_init_time_func(line, delay_us, delay_line_mono_time, NULL);
+}
+
 static inline void
 delay_line_refresh_delay(delay_line_t *line)
 {
@@ -293,6 +320,337 @@ delay_line_get_time_since_change(const delay_line_t *line)
 	return (now - line->changed_t);
 }
 
+/*
+ * Implements a debounced variable. Unlike a delay line, where a pending
+ * change takes effect after the delay regardless of what happens to the
+ * input in the meantime, a debouncer only accepts a new value once it
+ * has been continuously pushed for the debounce delay. Any change of the
+ * input in the meantime restarts the delay. This is useful for filtering
+ * out contact bounce of switches, or short spikes of sensor signals.
+ */
+typedef struct {
+	union {
+		int64_t		i64;
+		uint64_t	u64;
+		double		f64;
+	};
+	union {
+		int64_t		i64_cand;
+		uint64_t	u64_cand;
+		double		f64_cand;
+	};
+	uint64_t		cand_t;
+	uint64_t		delay_us;
+	delay_line_time_func_t	time_func;
+	void			*time_func_userinfo;
+} debounce_t;
+
+/*
+ * Initializes a debouncer.
+ * @param db Pointer to the debouncer to initialize.
+ * @param delay_us Microsecond duration for which a new value must be
+ *	continuously pushed to the debouncer before it is accepted.
+ */
+static inline void
+debounce_init(debounce_t *db, uint64_t delay_us)
+{
+	ASSERT(db != NULL);
+	memset(db, 0, sizeof (*db));
+	db->delay_us = delay_us;
+}
+
+/*
+ * Same as debounce_init, but allows specifying a custom time source,
+ * similarly to delay_line_init_time_func.
+ */
+static inline void
+debounce_init_time_func(debounce_t *db, uint64_t delay_us,
+    delay_line_time_func_t time_func, void *time_func_userinfo)
+{
+	debounce_init(db, delay_us);
+	db->time_func = time_func;
+	db->time_func_userinfo = time_func_userinfo;
+}
+
+static inline void
+debounce_set_delay(debounce_t *db, uint64_t delay_us)
+{
+	ASSERT(db != NULL);
+	db->delay_us = delay_us;
+}
+
+static inline uint64_t
+debounce_get_delay(const debounce_t *db)
+{
+	ASSERT(db != NULL);
+	return (db->delay_us);
+}
+
+/*
+ * Functions that push a new value to a debouncer:
+ *	debounce_push_i64	- pushes an int64_t to the debouncer
+ *	debounce_push_u64	- pushes a uint64_t to the debouncer
+ *	debounce_push_f64	- pushes a double to the debouncer
+ * You should call these periodically (e.g. every frame) with the raw
+ * input value. The debounced value is returned, which only changes to
+ * the input value once the input has remained unchanged for `delay_us'
+ * microseconds.
+ */
+#define	DEF_DEBOUNCE_PUSH(typename, abbrev_type) \
+static inline typename \
+debounce_push_ ## abbrev_type(debounce_t *db, typename value) \
+{ \
+	uint64_t now; \
+	ASSERT(db != NULL); \
+	now = (db->time_func != NULL ? \
+	    db->time_func(db->time_func_userinfo) : microclock()); \
+	if (value != db->abbrev_type ## _cand) { \
+		db->abbrev_type ## _cand = value; \
+		db->cand_t = now; \
+	} \
+	if (db->abbrev_type != db->abbrev_type ## _cand && \
+	    now - db->cand_t >= db->delay_us) \
+		db->abbrev_type = db->abbrev_type ## _cand; \
+	return (db->abbrev_type); \
+}
+DEF_DEBOUNCE_PUSH(int64_t, i64)
+DEF_DEBOUNCE_PUSH(uint64_t, u64)
+DEF_DEBOUNCE_PUSH(double, f64)
+
+/*
+ * Functions to read the current debounced value without pushing a new
+ * input value:
+ *	debounce_peek_i64	- reads the debouncer as an int64_t
+ *	debounce_peek_u64	- reads the debouncer as a uint64_t
+ *	debounce_peek_f64	- reads the debouncer as a double
+ */
+#define	DEF_DEBOUNCE_PEEK(typename, abbrev_type) \
+static inline typename \
+debounce_peek_ ## abbrev_type(const debounce_t *db) \
+{ \
+	ASSERT(db != NULL); \
+	return (db->abbrev_type); \
+}
+DEF_DEBOUNCE_PEEK(int64_t, i64)
+DEF_DEBOUNCE_PEEK(uint64_t, u64)
+DEF_DEBOUNCE_PEEK(double, f64)
+
+/*
+ * Functions that set the value of a debouncer immediately, bypassing
+ * the debounce delay:
+ *	debounce_push_imm_i64	- sets the debouncer to an int64_t
+ *	debounce_push_imm_u64	- sets the debouncer to a uint64_t
+ *	debounce_push_imm_f64	- sets the debouncer to a double
+ */
+#define	DEF_DEBOUNCE_PUSH_IMM(typename, abbrev_type) \
+static inline typename \
+debounce_push_imm_ ## abbrev_type(debounce_t *db, typename value) \
+{ \
+	ASSERT(db != NULL); \
+	db->abbrev_type = value; \
+	db->abbrev_type ## _cand = value; \
+	return (db->abbrev_type); \
+}
+DEF_DEBOUNCE_PUSH_IMM(int64_t, i64)
+DEF_DEBOUNCE_PUSH_IMM(uint64_t, u64)
+DEF_DEBOUNCE_PUSH_IMM(double, f64)
+
+/*
+ * Implements a boolean state driven by a numeric input with separate
+ * on & off thresholds, to prevent the state from chattering when the
+ * input hovers around a single threshold. If `on_thresh' is greater
+ * than `off_thresh', the state turns on when the input rises to or
+ * above `on_thresh' and turns off when it drops to or below `off_thresh'
+ * (e.g. an overtemperature warning). If `on_thresh' is less than
+ * `off_thresh', the sense is reversed and the state turns on when the
+ * input drops to or below `on_thresh' (e.g. a low pressure warning).
+ * Combine with a debouncer if the state must also persist for a while
+ * before being acted upon.
+ */
+typedef struct {
+	double	on_thresh;
+	double	off_thresh;
+	bool_t	state;
+} hysteresis_t;
+
+static inline void
+hysteresis_init(hysteresis_t *hyst, double on_thresh, double off_thresh,
+    bool_t state)
+{
+	ASSERT(hyst != NULL);
+	ASSERT(!isnan(on_thresh));
+	ASSERT(!isnan(off_thresh));
+	hyst->on_thresh = on_thresh;
+	hyst->off_thresh = off_thresh;
+	hyst->state = state;
+}
+
+/*
+ * Updates the hysteresis state with a new input value and returns the
+ * new state. NAN input values leave the state unchanged.
+ */
+static inline bool_t
+hysteresis_update(hysteresis_t *hyst, double value)
+{
+	ASSERT(hyst != NULL);
+	if (hyst->on_thresh >= hyst->off_thresh) {
+		if (value >= hyst->on_thresh)
+			hyst->state = B_TRUE;
+		else if (value <= hyst->off_thresh)
+			hyst->state = B_FALSE;
+	} else {
+		if (value <= hyst->on_thresh)
+			hyst->state = B_TRUE;
+		else if (value >= hyst->off_thresh)
+			hyst->state = B_FALSE;
+	}
+	return (hyst->state);
+}
+
+static inline bool_t
+hysteresis_get(const hysteresis_t *hyst)
+{
+	ASSERT(hyst != NULL);
+	return (hyst->state);
+}
+
+static inline void
+hysteresis_set(hysteresis_t *hyst, bool_t state)
+{
+	ASSERT(hyst != NULL);
+	hyst->state = state;
+}
+
+/*
+ * Implements a rate limiter (slew rate limiter), which limits how fast a
+ * numeric value may change. The value moves towards a target value at
+ * no more than `up_rate' units per second when increasing and no more
+ * than `down_rate' units per second when decreasing. Optionally, jerk
+ * limiting can be enabled using rate_lim_set_jerk, which additionally
+ * limits how fast the rate of change itself may change, producing smooth
+ * starts & stops (e.g. for control surface or gauge needle movement).
+ *
+ * Typical usage:
+ *
+ *	rate_lim_t rl;
+ *	rate_lim_init(&rl, 0, 10, 20);
+ *	...every frame:
+ *	value = rate_lim_update(&rl, target, d_t);
+ */
+typedef struct {
+	double	value;
+	double	rate;		/* current rate of change, units/sec */
+	double	up_rate;	/* units/sec, positive */
+	double	down_rate;	/* units/sec, positive */
+	double	jerk;		/* units/sec^2, 0 for no jerk limiting */
+} rate_lim_t;
+
+static inline void
+rate_lim_init(rate_lim_t *rl, double value, double up_rate, double down_rate)
+{
+	ASSERT(rl != NULL);
+	ASSERT(!isnan(value));
+	ASSERT3F(up_rate, >, 0);
+	ASSERT3F(down_rate, >, 0);
+	memset(rl, 0, sizeof (*rl));
+	rl->value = value;
+	rl->up_rate = up_rate;
+	rl->down_rate = down_rate;
+}
+
+static inline void
+rate_lim_set_rates(rate_lim_t *rl, double up_rate, double down_rate)
+{
+	ASSERT(rl != NULL);
+	ASSERT3F(up_rate, >, 0);
+	ASSERT3F(down_rate, >, 0);
+	rl->up_rate = up_rate;
+	rl->down_rate = down_rate;
+}
+
+/*
+ * Sets the maximum rate at which the rate of change may itself change
+ * (in units/sec^2). Pass 0 to disable jerk limiting.
+ */
+static inline void
+rate_lim_set_jerk(rate_lim_t *rl, double jerk)
+{
+	ASSERT(rl != NULL);
+	ASSERT3F(jerk, >=, 0);
+	rl->jerk = jerk;
+}
+
+/*
+ * Moves the value towards `target' and returns the new value.
+ * @param target Target value. NAN leaves the value unchanged.
+ * @param d_t Time elapsed since the last update in seconds.
+ */
+static inline double
+rate_lim_update(rate_lim_t *rl, double target, double d_t)
+{
+	double err, rate;
+
+	ASSERT(rl != NULL);
+	ASSERT3F(d_t, >=, 0);
+	if (isnan(target) || d_t == 0)
+		return (rl->value);
+
+	err = target - rl->value;
+	if (err >= 0)
+		rate = MIN(err / d_t, rl->up_rate);
+	else
+		rate = MAX(err / d_t, -rl->down_rate);
+	if (rl->jerk != 0) {
+		double max_chg = rl->jerk * d_t;
+		/* the fastest rate from which we can still stop in time */
+		double stop_rate = sqrt(2 * rl->jerk * fabs(err)) + max_chg;
+
+		if (err >= 0)
+			rate = MIN(rate, stop_rate);
+		else
+			rate = MAX(rate, -stop_rate);
+		rate = MIN(MAX(rate, rl->rate - max_chg), rl->rate + max_chg);
+	}
+	rl->rate = rate;
+	rl->value += rate * d_t;
+	/* Prevent an overshoot */
+	if ((err >= 0 && rl->value >= target) ||
+	    (err < 0 && rl->value <= target)) {
+		rl->value = target;
+		rl->rate = 0;
+	}
+	return (rl->value);
+}
+
+static inline double
+rate_lim_get(const rate_lim_t *rl)
+{
+	ASSERT(rl != NULL);
+	return (rl->value);
+}
+
+/*
+ * Returns the current rate of change in units/sec.
+ */
+static inline double
+rate_lim_get_rate(const rate_lim_t *rl)
+{
+	ASSERT(rl != NULL);
+	return (rl->rate);
+}
+
+/*
+ * Sets the value immediately, bypassing the rate limits.
+ */
+static inline void
+rate_lim_set_imm(rate_lim_t *rl, double value)
+{
+	ASSERT(rl != NULL);
+	ASSERT(!isnan(value));
+	rl->value = value;
+	rl->rate = 0;
+}
+
 #ifdef	__cplusplus
 }
 #endif
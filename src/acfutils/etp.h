@@ -0,0 +1,74 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_ETP_H_
+#define	_ACF_UTILS_ETP_H_
+
+#include <stdlib.h>
+
+#include "geom.h"
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Route midpoint, equal-time-point and point-of-no-return calculators.
+ * See etp.c for usage info.
+ */
+
+typedef struct {
+	geo_pos2_t	pos;
+	/* wind on the leg from this waypoint to the next one */
+	double		wind_dir;	/* wind from, degrees true */
+	double		wind_spd;	/* m/s */
+} etp_wpt_t;
+
+typedef struct {
+	double	tas;		/* cruise true airspeed, m/s */
+	double	fuel_flow;	/* cruise fuel flow, kg/s */
+	double	div_tas;	/* diversion true airspeed, m/s, 0 = tas */
+	double	div_fuel_flow;	/* diversion fuel flow, kg/s, 0 = fuel_flow */
+	double	fuel;		/* fuel on board at the route start, kg */
+	double	reserve;	/* fuel which must remain on landing, kg */
+} etp_perf_t;
+
+typedef struct {
+	geo_pos2_t	pos;
+	double		dist;		/* along the route from its start, m */
+	double		time;		/* flight time from route start, s */
+	double		fuel;		/* fuel remaining at the point, kg */
+	double		div_time;	/* time to fly the diversion, s */
+	double		div_fuel;	/* fuel burned on the diversion, kg */
+} etp_point_t;
+
+API_EXPORT double etp_route_dist(const etp_wpt_t *wpts, size_t num_wpts);
+API_EXPORT bool_t etp_midpoint(const etp_wpt_t *wpts, size_t num_wpts,
+    const etp_perf_t *perf, etp_point_t *pt);
+API_EXPORT bool_t etp_calc(const etp_wpt_t *wpts, size_t num_wpts,
+    const etp_perf_t *perf, etp_point_t *pt);
+API_EXPORT bool_t etp_pnr_calc(const etp_wpt_t *wpts, size_t num_wpts,
+    const etp_perf_t *perf, etp_point_t *pt);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_ETP_H_ */
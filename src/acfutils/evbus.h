@@ -0,0 +1,57 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_EVBUS_H_
+#define	_ACF_UTILS_EVBUS_H_
+
+#include <stdlib.h>
+
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Publish/subscribe event bus. See evbus.c for usage info.
+ */
+
+typedef struct evbus_s evbus_t;
+typedef struct evbus_sub_s evbus_sub_t;
+
+typedef void (*evbus_cb_t)(const char *topic, const void *data, size_t len,
+    void *userinfo);
+
+API_EXPORT evbus_t *evbus_alloc(void);
+API_EXPORT void evbus_free(evbus_t *bus);
+
+API_EXPORT evbus_sub_t *evbus_subscribe(evbus_t *bus, const char *topic,
+    evbus_cb_t cb, void *userinfo, bool_t queued);
+API_EXPORT void evbus_unsubscribe(evbus_t *bus, evbus_sub_t *sub);
+
+API_EXPORT void evbus_publish(evbus_t *bus, const char *topic,
+    const void *data, size_t len);
+API_EXPORT size_t evbus_drain(evbus_t *bus, evbus_sub_t *sub);
+API_EXPORT size_t evbus_drain_all(evbus_t *bus);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_EVBUS_H_ */
@@ -32,10 +32,30 @@
 extern "C" {
 #endif
 
+typedef void (*except_hook_t)(void *userinfo);
+
+/*
+ * What to do with the process after a crash has been logged and the
+ * hooks have run. See except_set_policy.
+ */
+typedef enum {
+	EXCEPT_POLICY_CHAIN,	/* pass to the previous handler, then exit */
+	EXCEPT_POLICY_ABORT,	/* let the OS kill the process (core dump) */
+	EXCEPT_POLICY_EXIT	/* exit quietly without any other handlers */
+} except_policy_t;
+
 #define	except_init	ACFSYM(except_init)
 API_EXPORT void except_init(void);
 #define	except_fini	ACFSYM(except_fini)
 API_EXPORT void except_fini(void);
+#define	except_set_report_dir	ACFSYM(except_set_report_dir)
+API_EXPORT void except_set_report_dir(const char *dir);
+#define	except_add_hook		ACFSYM(except_add_hook)
+API_EXPORT void except_add_hook(except_hook_t hook, void *userinfo);
+#define	except_remove_hook	ACFSYM(except_remove_hook)
+API_EXPORT void except_remove_hook(except_hook_t hook, void *userinfo);
+#define	except_set_policy	ACFSYM(except_set_policy)
+API_EXPORT void except_set_policy(except_policy_t policy);
 
 #ifdef __cplusplus
 }
@@ -0,0 +1,84 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_FPLAN_H_
+#define	_ACF_UTILS_FPLAN_H_
+
+#include <stdlib.h>
+
+#include "geom.h"
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Flight plan file reader & writer for the X-Plane .fms and MSFS/FSX
+ * .pln formats. See fplan.c for usage info.
+ */
+
+typedef enum {
+	FPLAN_WPT_APT,
+	FPLAN_WPT_NDB,
+	FPLAN_WPT_VOR,
+	FPLAN_WPT_FIX,
+	FPLAN_WPT_LATLON
+} fplan_wpt_type_t;
+
+typedef struct {
+	fplan_wpt_type_t	type;
+	char			ident[16];
+	char			region[4];	/* ICAO region, may be empty */
+	char			via[16];	/* airway, empty means direct */
+	geo_pos2_t		pos;
+	int			alt;		/* feet, 0 if unspecified */
+} fplan_wpt_t;
+
+typedef struct {
+	char		dep[8];
+	char		dep_rwy[8];	/* without "RW" prefix, e.g. "27L" */
+	char		sid[16];
+	char		sid_trans[16];
+	char		arr[8];
+	char		arr_rwy[8];	/* without "RW" prefix, e.g. "27L" */
+	char		star[16];
+	char		star_trans[16];
+	char		app[16];
+	char		app_trans[16];
+	int		cruise_alt;	/* feet, 0 if unspecified */
+
+	size_t		num_wpts;
+	fplan_wpt_t	*wpts;
+} fplan_t;
+
+API_EXPORT fplan_t *fplan_alloc(void);
+API_EXPORT void fplan_free(fplan_t *fpl);
+API_EXPORT void fplan_add_wpt(fplan_t *fpl, const fplan_wpt_t *wpt);
+
+API_EXPORT fplan_t *fplan_read_fms(const char *filename);
+API_EXPORT bool_t fplan_write_fms(const fplan_t *fpl, const char *filename);
+API_EXPORT fplan_t *fplan_read_pln(const char *filename);
+API_EXPORT bool_t fplan_write_pln(const fplan_t *fpl, const char *filename);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_FPLAN_H_ */
@@ -0,0 +1,75 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_FUELSYS_H_
+#define	_ACF_UTILS_FUELSYS_H_
+
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Fuel tanks and a simple fuel flow network connecting them to engines.
+ * See fuelsys.c for usage info.
+ */
+
+typedef struct fuelsys_s fuelsys_t;
+
+API_EXPORT fuelsys_t *fuelsys_alloc(double dens_ref, double dens_coeff);
+API_EXPORT void fuelsys_free(fuelsys_t *fs);
+API_EXPORT double fuelsys_get_dens(const fuelsys_t *fs, double temp);
+
+API_EXPORT unsigned fuelsys_add_tank(fuelsys_t *fs, double capacity,
+    double unusable);
+API_EXPORT void fuelsys_tank_set_qty(fuelsys_t *fs, unsigned tank,
+    double qty);
+API_EXPORT double fuelsys_tank_get_qty(const fuelsys_t *fs, unsigned tank);
+API_EXPORT double fuelsys_tank_get_usable(const fuelsys_t *fs,
+    unsigned tank);
+API_EXPORT double fuelsys_tank_get_vol(const fuelsys_t *fs, unsigned tank);
+API_EXPORT double fuelsys_tank_get_max_qty(const fuelsys_t *fs,
+    unsigned tank);
+API_EXPORT void fuelsys_tank_set_temp(fuelsys_t *fs, unsigned tank,
+    double temp);
+API_EXPORT double fuelsys_tank_get_temp(const fuelsys_t *fs, unsigned tank);
+
+API_EXPORT unsigned fuelsys_add_engine(fuelsys_t *fs);
+API_EXPORT void fuelsys_engine_set_demand(fuelsys_t *fs, unsigned eng,
+    double rate);
+API_EXPORT double fuelsys_engine_get_supply(const fuelsys_t *fs,
+    unsigned eng);
+
+API_EXPORT unsigned fuelsys_add_feed(fuelsys_t *fs, unsigned tank,
+    unsigned eng, double max_rate);
+API_EXPORT unsigned fuelsys_add_xfer(fuelsys_t *fs, unsigned src_tank,
+    unsigned dst_tank, double rate);
+API_EXPORT void fuelsys_line_set_open(fuelsys_t *fs, unsigned line,
+    bool_t open);
+API_EXPORT bool_t fuelsys_line_is_open(const fuelsys_t *fs, unsigned line);
+API_EXPORT double fuelsys_line_get_flow(const fuelsys_t *fs, unsigned line);
+
+API_EXPORT void fuelsys_update(fuelsys_t *fs, double d_t);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_FUELSYS_H_ */
@@ -385,6 +385,35 @@ API_EXPORT vect3_t sph_xlate_vect(vect3_t pos, const sph_xlate_t *xlate)
 API_EXPORT double gc_distance(geo_pos2_t start, geo_pos2_t end);
 #define	gc_point_hdg	ACFSYM(gc_point_hdg)
 API_EXPORT double gc_point_hdg(geo_pos2_t start, geo_pos2_t end);
+#define	gc_final_hdg	ACFSYM(gc_final_hdg)
+API_EXPORT double gc_final_hdg(geo_pos2_t start, geo_pos2_t end);
+#define	gc_xtrk_dist	ACFSYM(gc_xtrk_dist)
+API_EXPORT double gc_xtrk_dist(geo_pos2_t start, geo_pos2_t end,
+    geo_pos2_t pos);
+#define	gc_atrk_dist	ACFSYM(gc_atrk_dist)
+API_EXPORT double gc_atrk_dist(geo_pos2_t start, geo_pos2_t end,
+    geo_pos2_t pos);
+
+/*
+ * Rhumb line (loxodrome) functions.
+ */
+#define	rhumb_distance	ACFSYM(rhumb_distance)
+API_EXPORT double rhumb_distance(geo_pos2_t start, geo_pos2_t end);
+#define	rhumb_hdg	ACFSYM(rhumb_hdg)
+API_EXPORT double rhumb_hdg(geo_pos2_t start, geo_pos2_t end);
+#define	rhumb_displace	ACFSYM(rhumb_displace)
+API_EXPORT geo_pos2_t rhumb_displace(geo_pos2_t pos, double truehdg,
+    double dist);
+
+/*
+ * Ellipsoidal geodesic functions.
+ */
+#define	geodesic_inverse	ACFSYM(geodesic_inverse)
+API_EXPORT double geodesic_inverse(const ellip_t *ellip, geo_pos2_t start,
+    geo_pos2_t end, double *init_hdg, double *final_hdg);
+#define	geodesic_direct	ACFSYM(geodesic_direct)
+API_EXPORT geo_pos2_t geodesic_direct(const ellip_t *ellip, geo_pos2_t pos,
+    double truehdg, double dist, double *final_hdg);
 
 /*
  * Generic spherical - to - flat-plane projections.
@@ -425,6 +454,7 @@ typedef struct {
 API_EXPORT lcc_t lcc_init(double reflat, double reflon, double stdpar1,
     double stdpar2);
 API_EXPORT vect2_t geo2lcc(geo_pos2_t pos, const lcc_t *lcc) PURE_ATTR;
+API_EXPORT geo_pos2_t lcc2geo(vect2_t pos, const lcc_t *lcc) PURE_ATTR;
 
 /*
  *  Bezier curve functions.
@@ -435,6 +465,14 @@ API_EXPORT double quad_bezier_func(double x, const bezier_t *func);
 API_EXPORT double *quad_bezier_func_inv(double y, const bezier_t *func,
     size_t *n_xs);
 
+API_EXPORT vect2_t quad_bezier_pt(const vect2_t p[3], double t) PURE_ATTR;
+API_EXPORT vect2_t cubic_bezier_pt(const vect2_t p[4], double t) PURE_ATTR;
+API_EXPORT vect2_t cubic_bezier_dir(const vect2_t p[4], double t) PURE_ATTR;
+API_EXPORT void quad2cubic_bezier(const vect2_t q[3], vect2_t c[4]);
+API_EXPORT double cubic_bezier_len(const vect2_t p[4], double t) PURE_ATTR;
+API_EXPORT double cubic_bezier_len2t(const vect2_t p[4], double len)
+    PURE_ATTR;
+
 /*
  * Matrix math.
  */
@@ -453,6 +491,15 @@ typedef struct {
 
 API_EXPORT void mat4_ident(mat4_t *mat);
 API_EXPORT void mat3_ident(mat3_t *mat);
+API_EXPORT void mat4_mul(const mat4_t *a, const mat4_t *b, mat4_t *out);
+API_EXPORT void mat3_mul(const mat3_t *a, const mat3_t *b, mat3_t *out);
+API_EXPORT void mat4_transpose(const mat4_t *a, mat4_t *out);
+API_EXPORT void mat3_transpose(const mat3_t *a, mat3_t *out);
+API_EXPORT double mat3_det(const mat3_t *a);
+API_EXPORT bool_t mat3_inv(const mat3_t *a, mat3_t *out);
+API_EXPORT void mat3_rot(mat3_t *mat, double a, unsigned axis);
+API_EXPORT vect3_t mat3_vect3_mul(const mat3_t *mat, vect3_t v);
+API_EXPORT vect3_t mat4_vect3_mul(const mat4_t *mat, vect3_t v);
 
 #ifdef	__cplusplus
 }
@@ -123,6 +123,38 @@ normalize_hdg(double hdg)
 	return (hdg);
 }
 
+/*
+ * Heading-aware version of wavg. Interpolates between two headings along
+ * the shortest angular path, so that e.g. halfway between 350 and 10
+ * degrees is 0 degrees, not 180 degrees. The headings don't need to be
+ * normalized. 'w' is the weight fraction from 0.0 = all of h1 to 1.0 =
+ * all of h2. The result is normalized to 0 - 360 degrees.
+ */
+static inline double
+wavg_hdg(double h1, double h2, double w)
+{
+	h1 = normalize_hdg(h1);
+	h2 = normalize_hdg(h2);
+	return (normalize_hdg(h1 + rel_hdg(h1, h2) * w));
+}
+
+/*
+ * Heading-aware version of FILTER_IN. Moves `old_val' towards `new_val'
+ * along the shortest angular path, correctly handling the wrap around
+ * 0/360 degrees. The result stored in `old_val' is normalized to
+ * 0 - 360 degrees.
+ */
+#define	FILTER_IN_HDG(old_val, new_val, d_t, lag) \
+	do { \
+		double o = normalize_hdg(old_val); \
+		double delta = rel_hdg(o, normalize_hdg(new_val)); \
+		double step = delta * ((d_t) / (lag)); \
+		/* Prevent an overshoot */ \
+		if (fabs(step) > fabs(delta)) \
+			step = delta; \
+		(old_val) = normalize_hdg(o + step); \
+	} while (0)
+
 static inline double
 normalize_lon(double lon)
 {
@@ -186,6 +218,21 @@ static inline bool_t is_valid_ndb_freq_hz(uint32_t freq_hz)
 }
 #define	is_valid_tacan_freq	ACFSYM(is_valid_tacan_freq)
 API_EXPORT bool_t is_valid_tacan_freq(double freq_mhz);
+
+/* COM radio channels, see helpers.c for details */
+#define	COM_CHAN_STR_LEN	8
+#define	is_valid_com_chan	ACFSYM(is_valid_com_chan)
+API_EXPORT bool_t is_valid_com_chan(unsigned chan_khz, bool_t spacing_833);
+#define	com_chan2freq	ACFSYM(com_chan2freq)
+API_EXPORT uint32_t com_chan2freq(unsigned chan_khz);
+#define	com_freq2chan	ACFSYM(com_freq2chan)
+API_EXPORT unsigned com_freq2chan(uint32_t freq_hz, bool_t spacing_833);
+#define	com_chan_from_xp	ACFSYM(com_chan_from_xp)
+API_EXPORT unsigned com_chan_from_xp(int freq_10khz);
+#define	com_chan_to_xp	ACFSYM(com_chan_to_xp)
+API_EXPORT int com_chan_to_xp(unsigned chan_khz);
+#define	com_chan2str	ACFSYM(com_chan2str)
+API_EXPORT void com_chan2str(unsigned chan_khz, char buf[COM_CHAN_STR_LEN]);
 #define	is_valid_rwy_ID	ACFSYM(is_valid_rwy_ID)
 API_EXPORT bool_t is_valid_rwy_ID(const char *rwy_ID);
 #define	copy_rwy_ID	ACFSYM(copy_rwy_ID)
@@ -442,6 +489,17 @@ API_EXPORT bool_t create_directory_recursive(const char *dirname);
 API_EXPORT bool_t remove_directory(const char *dirname);
 #define	remove_file			ACFSYM(remove_file)
 API_EXPORT bool_t remove_file(const char *filename, bool_t notfound_ok);
+#define	remove_directory_contents	ACFSYM(remove_directory_contents)
+API_EXPORT bool_t remove_directory_contents(const char *dirname);
+#define	copy_file			ACFSYM(copy_file)
+API_EXPORT bool_t copy_file(const char *src, const char *dst);
+#define	copy_directory_recursive	ACFSYM(copy_directory_recursive)
+API_EXPORT bool_t copy_directory_recursive(const char *src, const char *dst);
+#define	replace_file			ACFSYM(replace_file)
+API_EXPORT bool_t replace_file(const char *filename_new, const char *filename);
+#define	file_write_atomic		ACFSYM(file_write_atomic)
+API_EXPORT bool_t file_write_atomic(const char *filename, const void *buf,
+    size_t bufsz);
 
 API_EXPORT char *lacf_dirname(const char *filename);
 
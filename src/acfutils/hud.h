@@ -0,0 +1,69 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_HUD_H_
+#define	_ACF_UTILS_HUD_H_
+
+#include "geom.h"
+#include "quat.h"
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Head-up display conformal symbology projection. See hud.c for usage
+ * info.
+ */
+
+typedef struct {
+	vect3_t	eye;		/* design eye position, body frame, meters */
+	double	depression;	/* boresight below the body X axis, degrees */
+	double	fov_x;		/* horizontal field of view, degrees */
+	double	fov_y;		/* vertical field of view, degrees */
+	vect2_t	size;		/* display size, pixels */
+
+	/* private */
+	vect3_t	fwd;
+	vect3_t	up;
+	vect2_t	scale;
+} hud_t;
+
+API_EXPORT void hud_init(hud_t *hud, vect3_t eye, double depression,
+    double fov_x, double fov_y, vect2_t size);
+
+API_EXPORT bool_t hud_dir2scr(const hud_t *hud, vect3_t dir, vect2_t *scr);
+API_EXPORT bool_t hud_pt2scr(const hud_t *hud, vect3_t pt, vect2_t *scr);
+API_EXPORT bool_t hud_scr_in_fov(const hud_t *hud, vect2_t scr);
+
+API_EXPORT bool_t hud_fpv(const hud_t *hud, euler_t att, vect3_t vel,
+    vect2_t *scr);
+API_EXPORT bool_t hud_horizon(const hud_t *hud, euler_t att, vect2_t *pt,
+    vect2_t *dir);
+API_EXPORT bool_t hud_pitch2scr(const hud_t *hud, euler_t att, double pitch,
+    vect2_t *scr);
+API_EXPORT bool_t hud_geo2scr(const hud_t *hud, euler_t att,
+    geo_pos3_t acf_pos, geo_pos3_t pos, vect2_t *scr);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_HUD_H_ */
@@ -19,7 +19,10 @@
 #ifndef	_ICAO2CC_H_
 #define	_ICAO2CC_H_
 
+#include <stdlib.h>
+
 #include "core.h"
+#include "types.h"
 
 #ifdef	__cplusplus
 extern "C" {
@@ -30,6 +33,25 @@ API_EXPORT const char *icao2cc(const char *icao);
 #define	icao2lang	ACFSYM(icao2lang)
 API_EXPORT const char *icao2lang(const char *icao);
 
+typedef struct {
+	const char	*icao;		/* matched ICAO code prefix */
+	const char	*cc;		/* ISO 3166-1 alpha-2 country code */
+	const char	*lang;		/* principal language code, or "XX" */
+	const char	*name;		/* English country name */
+} icao2cc_info_t;
+
+#define	icao2cc_info	ACFSYM(icao2cc_info)
+API_EXPORT bool_t icao2cc_info(const char *icao, icao2cc_info_t *info);
+#define	cc2name		ACFSYM(cc2name)
+API_EXPORT const char *cc2name(const char *cc);
+#define	cc2icao_regions	ACFSYM(cc2icao_regions)
+API_EXPORT size_t cc2icao_regions(const char *cc, const char **prefixes,
+    size_t cap);
+#define	icao2cc_count	ACFSYM(icao2cc_count)
+API_EXPORT size_t icao2cc_count(void);
+#define	icao2cc_get	ACFSYM(icao2cc_get)
+API_EXPORT void icao2cc_get(size_t i, icao2cc_info_t *info);
+
 #ifdef	__cplusplus
 }
 #endif
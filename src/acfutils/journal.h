@@ -0,0 +1,59 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_JOURNAL_H_
+#define	_ACF_UTILS_JOURNAL_H_
+
+#include <stdint.h>
+#include <stdlib.h>
+
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Crash-resilient append-only journal of state changes. See journal.c
+ * for usage info.
+ */
+
+#define	JOURNAL_MAX_REC_LEN	(1 << 24)	/* 16 MiB */
+
+typedef struct journal_s journal_t;
+
+typedef void (*journal_replay_cb_t)(uint32_t type, uint64_t seq,
+    const void *data, size_t len, void *userinfo);
+
+API_EXPORT journal_t *journal_open(const char *path,
+    journal_replay_cb_t replay_cb, void *userinfo);
+API_EXPORT void journal_close(journal_t *journal);
+
+API_EXPORT bool_t journal_append(journal_t *journal, uint32_t type,
+    const void *data, size_t len);
+API_EXPORT bool_t journal_reset(journal_t *journal);
+
+API_EXPORT uint64_t journal_get_seq(journal_t *journal);
+API_EXPORT void journal_set_sync(journal_t *journal, bool_t sync);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_JOURNAL_H_ */
@@ -0,0 +1,66 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_KVCACHE_H_
+#define	_ACF_UTILS_KVCACHE_H_
+
+#include <stdint.h>
+#include <stdlib.h>
+
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Bounded, thread-safe key-value cache with time-to-live expiry. See
+ * kvcache.c for usage info.
+ */
+
+typedef struct kvcache_s kvcache_t;
+
+typedef struct {
+	uint64_t	hits;
+	uint64_t	misses;
+	uint64_t	evictions;	/* removed to make room */
+	uint64_t	expirations;	/* removed due to TTL expiry */
+	size_t		num_entries;
+	size_t		total_size;	/* bytes of cached values */
+} kvcache_stats_t;
+
+API_EXPORT kvcache_t *kvcache_alloc(size_t max_entries, size_t max_size,
+    double ttl);
+API_EXPORT void kvcache_free(kvcache_t *cache);
+
+API_EXPORT void kvcache_put(kvcache_t *cache, const char *key,
+    const void *value, size_t len);
+API_EXPORT void *kvcache_get(kvcache_t *cache, const char *key, size_t *len);
+API_EXPORT bool_t kvcache_remove(kvcache_t *cache, const char *key);
+
+API_EXPORT void kvcache_expire(kvcache_t *cache);
+API_EXPORT void kvcache_purge(kvcache_t *cache);
+
+API_EXPORT void kvcache_get_stats(kvcache_t *cache, kvcache_stats_t *stats);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_KVCACHE_H_ */
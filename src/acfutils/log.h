@@ -20,8 +20,10 @@
 #define	_ACF_UTILS_LOG_H_
 
 #include <stdarg.h>
+#include <time.h>
 
 #include "sysmacros.h"
+#include "types.h"
 
 #ifdef __cplusplus
 extern "C" {
@@ -56,6 +58,95 @@ API_EXPORT void log_impl(const char *filename, int line,
     PRINTF_FORMAT(const char *fmt), ...) PRINTF_ATTR(3);
 API_EXPORT void log_impl_v(const char *filename, int line, const char *fmt,
     va_list ap);
+
+/*
+ * Leveled logging. Messages below the configured level (see log_set_level
+ * and log_set_module_level) are discarded without being formatted.
+ * Plain logMsg is never filtered.
+ */
+typedef enum {
+	LOG_LVL_DEBUG,
+	LOG_LVL_INFO,
+	LOG_LVL_WARN,
+	LOG_LVL_ERR
+} log_lvl_t;
+
+#define	logDbg(...) \
+	log_lvl_impl(LOG_LVL_DEBUG, log_basename(__FILE__), __LINE__, \
+	    __VA_ARGS__)
+#define	logInfo(...) \
+	log_lvl_impl(LOG_LVL_INFO, log_basename(__FILE__), __LINE__, \
+	    __VA_ARGS__)
+#define	logWarn(...) \
+	log_lvl_impl(LOG_LVL_WARN, log_basename(__FILE__), __LINE__, \
+	    __VA_ARGS__)
+#define	logErr(...) \
+	log_lvl_impl(LOG_LVL_ERR, log_basename(__FILE__), __LINE__, \
+	    __VA_ARGS__)
+API_EXPORT void log_lvl_impl(log_lvl_t lvl, const char *filename, int line,
+    PRINTF_FORMAT(const char *fmt), ...) PRINTF_ATTR(4);
+API_EXPORT void log_lvl_impl_v(log_lvl_t lvl, const char *filename,
+    int line, const char *fmt, va_list ap);
+
+/*
+ * Structured logging. Each field is passed as one of the LOG_KV_* macros
+ * below, so a structured message looks like this:
+ *
+ *	logMsgKV("engine start", LOG_KV_INT("eng", 2),
+ *	    LOG_KV_DBL("n2", 23.5));
+ *
+ * At least one field must be passed (use plain logMsg otherwise). See
+ * log_set_format for how the fields are rendered.
+ */
+typedef enum {
+	LOG_KV_TYPE_END,
+	LOG_KV_TYPE_INT,
+	LOG_KV_TYPE_DBL,
+	LOG_KV_TYPE_BOOL,
+	LOG_KV_TYPE_STR
+} log_kv_type_t;
+
+#define	LOG_KV_INT(key, val)	LOG_KV_TYPE_INT, (const char *)(key), \
+	(long long)(val)
+#define	LOG_KV_DBL(key, val)	LOG_KV_TYPE_DBL, (const char *)(key), \
+	(double)(val)
+#define	LOG_KV_BOOL(key, val)	LOG_KV_TYPE_BOOL, (const char *)(key), \
+	(int)!!(val)
+#define	LOG_KV_STR(key, val)	LOG_KV_TYPE_STR, (const char *)(key), \
+	(const char *)(val)
+
+#define	logMsgKV(msg, ...) \
+	log_kv_impl(log_basename(__FILE__), __LINE__, (msg), __VA_ARGS__, \
+	    LOG_KV_TYPE_END)
+API_EXPORT void log_kv_impl(const char *filename, int line, const char *msg,
+    ...);
+API_EXPORT void log_kv_impl_v(const char *filename, int line,
+    const char *msg, va_list ap);
+
+typedef enum {
+	LOG_FMT_TEXT,
+	LOG_FMT_JSON
+} log_fmt_t;
+
+API_EXPORT void log_set_format(log_fmt_t fmt);
+API_EXPORT log_fmt_t log_get_format(void);
+
+API_EXPORT void log_set_level(log_lvl_t lvl);
+API_EXPORT log_lvl_t log_get_level(void);
+API_EXPORT void log_set_module_level(const char *module, log_lvl_t lvl);
+API_EXPORT void log_clear_module_level(const char *module);
+API_EXPORT bool_t log_lvl_enabled(log_lvl_t lvl, const char *filename);
+
+typedef struct {
+	time_t	time;
+	char	*msg;
+} log_msg_t;
+
+API_EXPORT void log_set_recent_cap(unsigned cap);
+API_EXPORT log_msg_t *log_get_recent(unsigned n, unsigned *num_msgs);
+API_EXPORT void log_free_recent(log_msg_t *msgs, unsigned num_msgs);
+API_EXPORT void log_write_recent(int fd);
+
 API_EXPORT void log_backtrace(int skip_frames);
 #if	IBM
 API_EXPORT void log_backtrace_sw64(PCONTEXT ctx);
@@ -0,0 +1,86 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_LOGBOOK_H_
+#define	_ACF_UTILS_LOGBOOK_H_
+
+#include <stdlib.h>
+
+#include "geom.h"
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Automatic pilot logbook. See logbook.c for usage info.
+ */
+
+typedef struct {
+	char		dep[8];		/* departure ICAO, may be empty */
+	char		arr[8];		/* arrival ICAO, may be empty */
+	double		block_off_t;	/* UNIX time of block off */
+	double		takeoff_t;	/* UNIX time of first takeoff */
+	double		landing_t;	/* UNIX time of last landing */
+	double		block_on_t;	/* UNIX time of block on */
+	double		block_time;	/* seconds */
+	double		air_time;	/* seconds, summed over all takeoffs */
+	unsigned	num_landings;
+	double		touchdown_vs;	/* m/s at last touchdown, descent > 0 */
+	double		dist;		/* meters flown while airborne */
+} logbook_entry_t;
+
+typedef struct {
+	geo_pos2_t	pos;
+	bool_t		on_ground;
+	bool_t		eng_running;	/* any engine running */
+	double		gs;		/* ground speed, m/s */
+	double		vs;		/* vertical speed, m/s, climb > 0 */
+} logbook_state_t;
+
+typedef struct logbook_s logbook_t;
+
+typedef void (*logbook_entry_cb_t)(const logbook_entry_t *entry,
+    void *userinfo);
+
+API_EXPORT logbook_t *logbook_open(const char *filename);
+API_EXPORT void logbook_close(logbook_t *lb);
+
+API_EXPORT void logbook_set_route(logbook_t *lb, const char *dep,
+    const char *arr);
+API_EXPORT void logbook_set_cb(logbook_t *lb, logbook_entry_cb_t cb,
+    void *userinfo);
+API_EXPORT void logbook_update(logbook_t *lb, const logbook_state_t *st,
+    double utime);
+API_EXPORT bool_t logbook_is_in_flight(const logbook_t *lb);
+API_EXPORT bool_t logbook_get_cur_entry(const logbook_t *lb,
+    logbook_entry_t *entry);
+
+API_EXPORT size_t logbook_get_num_entries(const logbook_t *lb);
+API_EXPORT bool_t logbook_get_entry(const logbook_t *lb, size_t idx,
+    logbook_entry_t *entry);
+API_EXPORT bool_t logbook_export_csv(const logbook_t *lb,
+    const char *filename);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_LOGBOOK_H_ */
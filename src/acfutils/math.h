@@ -36,6 +36,15 @@ struct vect2_s;
 
 #define	quadratic_solve		ACFSYM(quadratic_solve)
 API_EXPORT unsigned quadratic_solve(double a, double b, double c, double x[2]);
+#define	cubic_solve		ACFSYM(cubic_solve)
+API_EXPORT unsigned cubic_solve(double a, double b, double c, double d,
+    double x[3]);
+#define	poly_eval		ACFSYM(poly_eval)
+API_EXPORT double poly_eval(double x, const double *coeff, unsigned n)
+    PURE_ATTR;
+#define	poly_deriv		ACFSYM(poly_deriv)
+API_EXPORT unsigned poly_deriv(const double *coeff, unsigned n,
+    double *deriv);
 #define	fx_lin			ACFSYM(fx_lin)
 API_EXPORT double fx_lin(double x, double x1, double y1, double x2, double y2)
     PURE_ATTR;
@@ -49,6 +58,44 @@ API_EXPORT double *fx_lin_multi_inv(double y, const struct vect2_s *points,
 API_EXPORT double *fx_lin_multi_inv2(double y, const struct vect2_s *points,
     bool_t extrapolate, size_t *num_out);
 
+/*
+ * Immutable interpolation tables with binary search lookup. Once built,
+ * a table may be evaluated from multiple threads concurrently.
+ */
+typedef struct lin_tbl_s lin_tbl_t;
+typedef struct lin_tbl2_s lin_tbl2_t;
+
+#define	lin_tbl_alloc		ACFSYM(lin_tbl_alloc)
+API_EXPORT lin_tbl_t *lin_tbl_alloc(const struct vect2_s *points, size_t n);
+#define	lin_tbl_free		ACFSYM(lin_tbl_free)
+API_EXPORT void lin_tbl_free(lin_tbl_t *tbl);
+#define	lin_tbl_eval		ACFSYM(lin_tbl_eval)
+API_EXPORT double lin_tbl_eval(const lin_tbl_t *tbl, double x,
+    bool_t extrapolate);
+
+#define	lin_tbl2_alloc		ACFSYM(lin_tbl2_alloc)
+API_EXPORT lin_tbl2_t *lin_tbl2_alloc(const double *x, size_t nx,
+    const double *y, size_t ny, const double *z);
+#define	lin_tbl2_free		ACFSYM(lin_tbl2_free)
+API_EXPORT void lin_tbl2_free(lin_tbl2_t *tbl);
+#define	lin_tbl2_eval		ACFSYM(lin_tbl2_eval)
+API_EXPORT double lin_tbl2_eval(const lin_tbl2_t *tbl, double x, double y,
+    bool_t extrapolate);
+
+/*
+ * Natural cubic spline interpolation.
+ */
+typedef struct cspline_s cspline_t;
+
+#define	cspline_alloc		ACFSYM(cspline_alloc)
+API_EXPORT cspline_t *cspline_alloc(const struct vect2_s *points, size_t n);
+#define	cspline_free		ACFSYM(cspline_free)
+API_EXPORT void cspline_free(cspline_t *sp);
+#define	cspline_eval		ACFSYM(cspline_eval)
+API_EXPORT double cspline_eval(const cspline_t *sp, double x);
+#define	cspline_deriv		ACFSYM(cspline_deriv)
+API_EXPORT double cspline_deriv(const cspline_t *sp, double x);
+
 /*
  * Weighted avg, 'w' is weight fraction from 0.0 = all of x to 1.0 = all of y.
  */
@@ -0,0 +1,139 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_METAR_H_
+#define	_ACF_UTILS_METAR_H_
+
+#include <stdlib.h>
+
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * METAR weather report and TAF forecast parser. See metar.c for usage info.
+ */
+
+#define	METAR_MAX_WX		8
+#define	METAR_MAX_CLDS		6
+#define	METAR_WIND_VRB		-1	/* variable wind direction */
+#define	METAR_VIS_MAX		10000	/* meters, "9999" or "CAVOK" */
+#define	METAR_MAX_RMK		256
+#define	TAF_MAX_PERIODS		16
+
+typedef enum {
+	METAR_CLD_FEW,
+	METAR_CLD_SCT,
+	METAR_CLD_BKN,
+	METAR_CLD_OVC,
+	METAR_CLD_VV		/* vertical visibility (sky obscured) */
+} metar_cld_cover_t;
+
+typedef struct {
+	metar_cld_cover_t	cover;
+	int			base;		/* feet AGL */
+	bool_t			cb;		/* cumulonimbus */
+	bool_t			tcu;		/* towering cumulus */
+} metar_cld_t;
+
+typedef struct {
+	char		station[8];
+	int		day;		/* day of month, 0 if not reported */
+	int		hour;		/* UTC */
+	int		min;		/* UTC */
+	bool_t		is_auto;	/* automated observation */
+
+	bool_t		wind_valid;
+	int		wind_dir;	/* deg true, or METAR_WIND_VRB */
+	int		wind_spd;	/* knots */
+	int		wind_gust;	/* knots, 0 if no gusts reported */
+	int		wind_var_from;	/* degrees true, -1 if not reported */
+	int		wind_var_to;	/* degrees true, -1 if not reported */
+
+	bool_t		cavok;
+	bool_t		vis_valid;
+	double		vis;		/* meters, capped at METAR_VIS_MAX */
+	bool_t		vis_sm;		/* originally reported in statute mi */
+
+	size_t		num_wx;
+	char		wx[METAR_MAX_WX][12];	/* e.g. "-SHRA", "VCTS" */
+
+	bool_t		sky_clear;	/* SKC, CLR, NSC or NCD */
+	size_t		num_clds;
+	metar_cld_t	clds[METAR_MAX_CLDS];
+
+	bool_t		temp_valid;
+	int		temp;		/* Celsius */
+	int		dewpt;		/* Celsius */
+
+	bool_t		qnh_valid;
+	double		qnh;		/* hPa */
+	bool_t		qnh_inhg;	/* originally reported in inHg */
+
+	char		rmk[METAR_MAX_RMK];	/* raw remarks text */
+} metar_t;
+
+typedef enum {
+	TAF_CHG_BASE,		/* initial forecast conditions */
+	TAF_CHG_FM,		/* "FMddhhmm": conditions change completely */
+	TAF_CHG_BECMG,		/* conditions change gradually */
+	TAF_CHG_TEMPO,		/* temporary fluctuations */
+	TAF_CHG_PROB		/* "PROBnn" without TEMPO */
+} taf_chg_t;
+
+typedef struct {
+	taf_chg_t	type;
+	int		prob;		/* percent, 0 if not a PROB group */
+	int		from_day;
+	int		from_hour;
+	int		from_min;
+	int		to_day;		/* -1 for FM (until the next FM) */
+	int		to_hour;	/* -1 for FM (until the next FM) */
+	bool_t		nsw;		/* no significant weather */
+	metar_t		wx;		/* forecast conditions */
+} taf_period_t;
+
+typedef struct {
+	char		station[8];
+	int		day;		/* issue time, 0 if not reported */
+	int		hour;
+	int		min;
+	bool_t		amd;		/* amended forecast */
+	bool_t		cor;		/* corrected forecast */
+	bool_t		cnl;		/* cancelled forecast */
+
+	int		from_day;	/* validity */
+	int		from_hour;
+	int		to_day;
+	int		to_hour;
+
+	size_t		num_periods;
+	taf_period_t	periods[TAF_MAX_PERIODS];
+} taf_t;
+
+API_EXPORT bool_t metar_parse(const char *str, metar_t *metar);
+API_EXPORT bool_t taf_parse(const char *str, taf_t *taf);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_METAR_H_ */
@@ -0,0 +1,127 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_NAVDB_H_
+#define	_ACF_UTILS_NAVDB_H_
+
+#include <stdlib.h>
+
+#include "geom.h"
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Reader for X-Plane's earth_fix.dat, earth_nav.dat and earth_awy.dat
+ * navigation data files. See navdb.c for usage info.
+ */
+
+#define	NAVDB_IDENT_LEN		8
+#define	NAVDB_REGION_LEN	4
+#define	NAVDB_NAME_LEN		48
+
+typedef enum {
+	NAVAID_NDB,
+	NAVAID_VOR,
+	NAVAID_LOC,	/* localizer, either part of an ILS or standalone */
+	NAVAID_GS,	/* ILS glideslope */
+	NAVAID_MKR,	/* outer, middle or inner marker beacon */
+	NAVAID_DME,	/* DME, either standalone or part of a VOR/ILS */
+	NAVAID_OTHER	/* FPAP, GLS, LTP/FTP, etc. */
+} navaid_type_t;
+
+typedef enum {
+	NAVDB_PT_FIX,
+	NAVDB_PT_NDB,
+	NAVDB_PT_VOR
+} navdb_pt_type_t;
+
+typedef struct {
+	char		ident[NAVDB_IDENT_LEN];
+	char		region[NAVDB_REGION_LEN];
+	char		apt[NAVDB_IDENT_LEN];	/* terminal airport or "ENRT" */
+	geo_pos2_t	pos;
+} navdb_fix_t;
+
+typedef struct {
+	navaid_type_t	type;
+	int		xp_type;	/* raw row code in earth_nav.dat */
+	char		ident[NAVDB_IDENT_LEN];
+	char		region[NAVDB_REGION_LEN];
+	char		apt[NAVDB_IDENT_LEN];	/* terminal airport or "ENRT" */
+	char		name[NAVDB_NAME_LEN];
+	geo_pos3_t	pos;		/* elevation in feet AMSL */
+	double		freq;		/* Hz */
+	double		range;		/* NM */
+	double		param;		/* type-dependent, see navdb.c */
+} navdb_navaid_t;
+
+typedef struct {
+	char		ident[NAVDB_IDENT_LEN];
+	char		region[NAVDB_REGION_LEN];
+	navdb_pt_type_t	type;
+	geo_pos2_t	pos;		/* NULL_GEO_POS2 if not found */
+} navdb_awy_pt_t;
+
+typedef struct {
+	char		name[NAVDB_NAME_LEN];	/* e.g. "UL607" or "J1-J2" */
+	navdb_awy_pt_t	from;
+	navdb_awy_pt_t	to;
+	bool_t		fwd_ok;		/* may be flown from -> to */
+	bool_t		back_ok;	/* may be flown to -> from */
+	bool_t		high;		/* high-altitude airway */
+	int		base_fl;
+	int		top_fl;
+} navdb_awy_seg_t;
+
+typedef struct navdb_s navdb_t;
+
+API_EXPORT navdb_t *navdb_open(const char *xpdir);
+API_EXPORT navdb_t *navdb_open_files(const char *fix_dat,
+    const char *nav_dat, const char *awy_dat);
+API_EXPORT void navdb_close(navdb_t *db);
+
+API_EXPORT size_t navdb_get_num_fixes(const navdb_t *db);
+API_EXPORT size_t navdb_get_num_navaids(const navdb_t *db);
+API_EXPORT size_t navdb_get_num_awy_segs(const navdb_t *db);
+
+API_EXPORT size_t navdb_lookup_fix(const navdb_t *db, const char *ident,
+    void (*found_cb)(const navdb_fix_t *fix, void *userinfo), void *userinfo);
+API_EXPORT size_t navdb_lookup_navaid(const navdb_t *db, const char *ident,
+    void (*found_cb)(const navdb_navaid_t *navaid, void *userinfo),
+    void *userinfo);
+API_EXPORT size_t navdb_lookup_awy(const navdb_t *db, const char *name,
+    void (*found_cb)(const navdb_awy_seg_t *seg, void *userinfo),
+    void *userinfo);
+
+API_EXPORT size_t navdb_find_fixes(const navdb_t *db, geo_pos2_t pos,
+    double radius, void (*found_cb)(const navdb_fix_t *fix, void *userinfo),
+    void *userinfo);
+API_EXPORT size_t navdb_find_navaids(const navdb_t *db, geo_pos2_t pos,
+    double radius,
+    void (*found_cb)(const navdb_navaid_t *navaid, void *userinfo),
+    void *userinfo);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_NAVDB_H_ */
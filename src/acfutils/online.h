@@ -0,0 +1,86 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_ONLINE_H_
+#define	_ACF_UTILS_ONLINE_H_
+
+#include <stdint.h>
+#include <stdlib.h>
+#include <time.h>
+
+#include "geom.h"
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * VATSIM & IVAO online network status fetcher. See online.c for usage
+ * info.
+ */
+
+typedef enum {
+	ONLINE_NET_VATSIM,
+	ONLINE_NET_IVAO
+} online_net_t;
+
+typedef enum {
+	ONLINE_FAC_OBS,
+	ONLINE_FAC_FSS,
+	ONLINE_FAC_DEL,
+	ONLINE_FAC_GND,
+	ONLINE_FAC_TWR,
+	ONLINE_FAC_APP,
+	ONLINE_FAC_CTR,
+	ONLINE_FAC_ATIS
+} online_fac_t;
+
+typedef struct {
+	char		callsign[16];
+	char		name[64];	/* controller's name, may be empty */
+	online_fac_t	facility;
+	uint64_t	freq;		/* Hz */
+	geo_pos2_t	pos;
+	double		range;		/* visibility range, meters */
+	double		dist;		/* distance from query point, meters */
+	char		atis_code;	/* '\0' if unknown */
+	char		*atis_text;	/* lines separated by '\n', or NULL */
+} online_station_t;
+
+typedef struct online_s online_t;
+
+API_EXPORT online_t *online_alloc(online_net_t net, const char *cainfo);
+API_EXPORT void online_free(online_t *ol);
+
+API_EXPORT void online_set_refresh_intval(online_t *ol, unsigned secs);
+API_EXPORT void online_refresh(online_t *ol);
+API_EXPORT time_t online_get_update_time(online_t *ol);
+
+API_EXPORT size_t online_get_stations(online_t *ol, geo_pos2_t pos,
+    double radius, online_station_t **stations);
+API_EXPORT bool_t online_get_atis(online_t *ol, const char *icao,
+    online_station_t *station);
+API_EXPORT void online_free_stations(online_station_t *stations, size_t n);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_ONLINE_H_ */
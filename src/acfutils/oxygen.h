@@ -0,0 +1,82 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_OXYGEN_H_
+#define	_ACF_UTILS_OXYGEN_H_
+
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Crew & passenger oxygen system and time of useful consciousness
+ * model. See oxygen.c for usage info.
+ */
+
+#define	OXY_MAX_CREW	4
+
+typedef enum {
+	OXY_MASK_OFF,		/* mask stowed */
+	OXY_MASK_NORMAL,	/* diluter demand */
+	OXY_MASK_100,		/* 100% oxygen on demand */
+	OXY_MASK_EMER		/* 100% oxygen under positive pressure */
+} oxy_mask_t;
+
+typedef enum {
+	OXY_EVT_PAX_MASKS_DROP,		/* passenger masks deployed */
+	OXY_EVT_PAX_DEPLETED,		/* passenger generators expended */
+	OXY_EVT_CREW_LOW,		/* crew bottle below low threshold */
+	OXY_EVT_CREW_DEPLETED,		/* crew bottle empty */
+	OXY_EVT_CREW_INCAP,		/* crew member lost consciousness */
+	OXY_EVT_CREW_RECOVERED		/* crew member regained consciousness */
+} oxy_evt_t;
+
+typedef void (*oxy_cb_t)(oxy_evt_t evt, unsigned crew_idx, void *userinfo);
+
+typedef struct oxy_s oxy_t;
+
+API_EXPORT double oxy_tuc(double cabin_alt, bool_t rapid);
+
+API_EXPORT oxy_t *oxy_alloc(unsigned num_crew, double crew_cap,
+    double pax_dur, oxy_cb_t cb, void *userinfo);
+API_EXPORT void oxy_free(oxy_t *oxy);
+API_EXPORT void oxy_update(oxy_t *oxy, double cabin_alt, double d_t);
+
+API_EXPORT void oxy_set_crew_mask(oxy_t *oxy, unsigned crew_idx,
+    oxy_mask_t mask);
+API_EXPORT oxy_mask_t oxy_get_crew_mask(const oxy_t *oxy, unsigned crew_idx);
+API_EXPORT double oxy_get_crew_qty(const oxy_t *oxy);
+API_EXPORT void oxy_set_crew_qty(oxy_t *oxy, double qty);
+API_EXPORT double oxy_get_crew_flow(const oxy_t *oxy, unsigned crew_idx);
+API_EXPORT double oxy_get_crew_tuc(const oxy_t *oxy, unsigned crew_idx);
+API_EXPORT bool_t oxy_crew_is_conscious(const oxy_t *oxy, unsigned crew_idx);
+
+API_EXPORT void oxy_pax_deploy(oxy_t *oxy);
+API_EXPORT void oxy_set_pax_deploy_alt(oxy_t *oxy, double cabin_alt);
+API_EXPORT bool_t oxy_pax_masks_deployed(const oxy_t *oxy);
+API_EXPORT double oxy_get_pax_time_rem(const oxy_t *oxy);
+API_EXPORT void oxy_pax_reset(oxy_t *oxy);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_OXYGEN_H_ */
@@ -64,9 +64,19 @@ extern "C" {
 
 #define	USG2LIT(usg)	((usg) * 3.785411784)
 #define	LIT2USG(lit)	((lit) / 3.785411784)
+#define	LIT2M3(lit)	((lit) / 1000.0)
+#define	M32LIT(m3)	((m3) * 1000.0)
+#define	USG2M3(usg)	LIT2M3(USG2LIT(usg))
+#define	M32USG(m3)	LIT2USG(M32LIT(m3))
 #define	LBS2KG(lbs)	((lbs) * 0.45359237)
 #define	KG2LBS(kg)	((kg) / 0.45359237)
 
+/*
+ * Density unit conversions.
+ */
+#define	LBUSG2KGM3(d)	(LBS2KG(d) / USG2M3(1))	/* lb/USgal to kg/m^3 */
+#define	KGM32LBUSG(d)	(KG2LBS(d) * USG2M3(1))	/* kg/m^3 to lb/USgal */
+
 #define	LBF2NEWTON(lb)	(LBS2KG(lb) * EARTH_GRAVITY)
 #define	NEWTON2LBF(f)	(KG2LBS((f) / EARTH_GRAVITY))
 
@@ -114,6 +124,23 @@ extern "C" {
 #define	JETA_KG2GAL(kg)		((kg) / 3.08447722)
 #define	JETA_GAL2KG(gal)	((gal) * 3.08447722)
 
+/*
+ * Standard fuel densities at 15 degrees C in kg/m^3. JETA_DENS matches
+ * the JETA_KG2GAL/JETA_GAL2KG conversions above, AVGAS_DENS is the usual
+ * 6 lb/USgal figure for 100LL.
+ */
+#define	JETA_DENS	814.8327	/* 6.8 lb/USgal */
+#define	AVGAS_DENS	718.9586	/* 6.0 lb/USgal */
+
+/*
+ * Conversions between fluid volume (m^3) and mass (kg) at density `dens'
+ * (kg/m^3). Since density doesn't depend on time, these work equally
+ * well for converting volume flow rates (m^3/s) to mass flow rates
+ * (kg/s) and back.
+ */
+#define	VOL2MASS(vol, dens)	((vol) * (dens))
+#define	MASS2VOL(mass, dens)	((mass) / (dens))
+
 typedef struct {
 	int	spd;
 	double	Cd;
@@ -261,6 +288,14 @@ API_EXPORT double perf_get_turn_rate(double bank_ratio, double gs_kts,
 API_EXPORT double alt2press(double alt_ft, double qnh_Pa);
 #define	press2alt	ACFSYM(press2alt)
 API_EXPORT double press2alt(double press_Pa, double qnh_Pa);
+#define	qnh2qfe		ACFSYM(qnh2qfe)
+API_EXPORT double qnh2qfe(double qnh_Pa, double elev_ft);
+#define	qfe2qnh		ACFSYM(qfe2qnh)
+API_EXPORT double qfe2qnh(double qfe_Pa, double elev_ft);
+#define	altim_setting_inhg	ACFSYM(altim_setting_inhg)
+API_EXPORT double altim_setting_inhg(double press_Pa);
+#define	altim_setting_hpa	ACFSYM(altim_setting_hpa)
+API_EXPORT double altim_setting_hpa(double press_Pa);
 /*
  * Generic barometric versions - altitudes in meters!
  */
@@ -271,6 +306,36 @@ API_EXPORT double alt2press_baro(double alt_m, double p0_Pa, double T0_K,
 API_EXPORT double press2alt_baro(double p_Pa, double p0_Pa, double T0_K,
     double g_mss);
 
+/*
+ * Full ISA model, valid up to 47 km - altitudes in meters!
+ */
+typedef struct {
+	double	temp;		/* Kelvin */
+	double	press;		/* Pa */
+	double	dens;		/* kg/m^3 */
+	double	sos;		/* speed of sound, m/s */
+} isa_atmos_t;
+
+#define	isa_atmos	ACFSYM(isa_atmos)
+API_EXPORT void isa_atmos(double alt_m, double isadev, isa_atmos_t *isa);
+#define	isa_press2alt	ACFSYM(isa_press2alt)
+API_EXPORT double isa_press2alt(double press_Pa);
+#define	isa_dens_alt	ACFSYM(isa_dens_alt)
+API_EXPORT double isa_dens_alt(double press_Pa, double oat);
+
+#define	ISA_TABLE_STEP	1000	/* meters */
+typedef struct {
+	double	alt;		/* meters, geopotential */
+	double	temp;		/* Kelvin */
+	double	press;		/* Pa */
+	double	dens;		/* kg/m^3 */
+} isa_table_ent_t;
+
+#define	isa_table_get		ACFSYM(isa_table_get)
+API_EXPORT const isa_table_ent_t *isa_table_get(size_t *num);
+#define	isa_table_lookup	ACFSYM(isa_table_lookup)
+API_EXPORT void isa_table_lookup(double alt_m, isa_atmos_t *isa);
+
 #define	alt2fl		ACFSYM(alt2fl)
 API_EXPORT double alt2fl(double alt, double qnh);
 #define	fl2alt		ACFSYM(fl2alt)
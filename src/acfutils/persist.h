@@ -0,0 +1,58 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_PERSIST_H_
+#define	_ACF_UTILS_PERSIST_H_
+
+#include "conf.h"
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Versioned, integrity-checked state persistence with schema migrations.
+ * See persist.c for usage info.
+ */
+
+/*
+ * Migrates a configuration from `from_version' to `from_version + 1'.
+ * Return B_FALSE if the migration failed.
+ */
+typedef bool_t (*persist_migrate_t)(conf_t *conf, unsigned from_version,
+    void *userinfo);
+
+typedef struct persist_s persist_t;
+
+API_EXPORT persist_t *persist_alloc(const char *format, unsigned version);
+API_EXPORT void persist_free(persist_t *persist);
+API_EXPORT void persist_add_migration(persist_t *persist,
+    unsigned from_version, persist_migrate_t func, void *userinfo);
+
+API_EXPORT bool_t persist_write(const persist_t *persist, const conf_t *conf,
+    const char *filename);
+API_EXPORT conf_t *persist_read(const persist_t *persist,
+    const char *filename, unsigned *file_version);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_PERSIST_H_ */
@@ -62,6 +62,10 @@ typedef struct {
 	double	r_d;		/* derivative update rate */
 
 	bool_t	integ_clamp;
+
+	double	out_min;	/* output saturation limits */
+	double	out_max;
+	double	k_aw;		/* back-calculation anti-windup gain */
 } pid_ctl_t;
 
 static inline void pid_ctl_reset(pid_ctl_t *pid);
@@ -70,6 +74,7 @@ static inline void pid_ctl_set_k_i(pid_ctl_t *pid, double k_i);
 static inline void pid_ctl_set_lim_i(pid_ctl_t *pid, double lim_i);
 static inline void pid_ctl_set_k_d(pid_ctl_t *pid, double k_d);
 static inline void pid_ctl_set_r_d(pid_ctl_t *pid, double r_d);
+static inline double pid_ctl_get_raw(const pid_ctl_t *pid);
 
 /*
  * Initializes a PID controller.
@@ -106,6 +111,9 @@ pid_ctl_init(pid_ctl_t *pid, double k_p, double k_i, double lim_i, double k_d,
 	pid->k_d_gain = 1;
 	pid->r_d = r_d;
 	pid->integ_clamp = B_TRUE;
+	pid->out_min = -INFINITY;
+	pid->out_max = INFINITY;
+	pid->k_aw = 0;
 }
 
 static inline void
@@ -155,6 +163,22 @@ pid_ctl_update_dV(pid_ctl_t *pid, double e, double V, double d_t)
 		FILTER_IN_NAN(pid->deriv, delta_V, d_t, pid->r_d);
 	pid->e_prev = e;
 	pid->V_prev = V;
+	/*
+	 * Back-calculation anti-windup: while the output is saturated,
+	 * bleed off the integrator in proportion to how far the raw
+	 * output lies outside of the output limits.
+	 */
+	if (pid->k_aw != 0 && pid->k_i_gain * pid->k_i != 0 &&
+	    !isnan(pid->deriv)) {
+		double out = pid_ctl_get_raw(pid);
+		double out_sat = clamp(out, pid->out_min, pid->out_max);
+
+		if (out != out_sat) {
+			pid->integ = clamp(pid->integ + pid->k_aw *
+			    (out_sat - out) * d_t / (pid->k_i_gain * pid->k_i),
+			    -pid->lim_i, pid->lim_i);
+		}
+	}
 }
 
 /*
@@ -180,6 +204,16 @@ pid_ctl_update(pid_ctl_t *pid, double e, double d_t)
  */
 static inline double
 pid_ctl_get(const pid_ctl_t *pid)
+{
+	return (clamp(pid_ctl_get_raw(pid), pid->out_min, pid->out_max));
+}
+
+/*
+ * Same as pid_ctl_get, but returns the controller's output without
+ * applying the output saturation limits set by pid_ctl_set_out_lim.
+ */
+static inline double
+pid_ctl_get_raw(const pid_ctl_t *pid)
 {
 	ASSERT(pid != NULL);
 	ASSERT(!isnan(pid->e_prev));
@@ -359,6 +393,61 @@ pid_ctl_get_deriv(const pid_ctl_t *pid)
 	return (pid->deriv);
 }
 
+/*
+ * Sets the PID controller's output saturation limits. pid_ctl_get
+ * clamps its return value to (out_min,out_max) inclusive. By default,
+ * the output is unlimited (-INFINITY,+INFINITY). This should match the
+ * physical travel limits of whatever the controller is driving (e.g.
+ * a servo), so that anti-windup (see pid_ctl_set_k_aw) can work.
+ */
+static inline void
+pid_ctl_set_out_lim(pid_ctl_t *pid, double out_min, double out_max)
+{
+	ASSERT(pid != NULL);
+	ASSERT3F(out_min, <=, out_max);
+	pid->out_min = out_min;
+	pid->out_max = out_max;
+}
+
+static inline double
+pid_ctl_get_out_min(const pid_ctl_t *pid)
+{
+	ASSERT(pid != NULL);
+	return (pid->out_min);
+}
+
+static inline double
+pid_ctl_get_out_max(const pid_ctl_t *pid)
+{
+	ASSERT(pid != NULL);
+	return (pid->out_max);
+}
+
+/*
+ * Sets the PID controller's back-calculation anti-windup gain. When the
+ * output is saturated (see pid_ctl_set_out_lim), the integrator is driven
+ * back so that the raw output approaches the output limit at a rate
+ * proportional to this gain (per unit of time used in d_t). This keeps
+ * the integrator from winding up while the output is pegged against its
+ * limits, which would otherwise cause large overshoots once the error
+ * reverses. A value of 0 (the default) disables anti-windup. A good
+ * starting point is a value close to k_i/k_p.
+ */
+static inline void
+pid_ctl_set_k_aw(pid_ctl_t *pid, double k_aw)
+{
+	ASSERT(pid != NULL);
+	ASSERT3F(k_aw, >=, 0);
+	pid->k_aw = k_aw;
+}
+
+static inline double
+pid_ctl_get_k_aw(const pid_ctl_t *pid)
+{
+	ASSERT(pid != NULL);
+	return (pid->k_aw);
+}
+
 #define	PID_CTL_DEBUG(pid_ptr) \
 	do { \
 		const pid_ctl_t *pid = (pid_ptr); \
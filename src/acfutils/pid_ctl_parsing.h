@@ -28,16 +28,24 @@
 
 #include "assert.h"
 #include "conf.h"
+#include "log.h"
 #include "pid_ctl.h"
 
 #ifdef __cplusplus
 extern "C" {
 #endif
 
-static inline void
+/*
+ * Initializes a PID controller from its tuning stored in a conf_t under
+ * `prefix' (see pid_ctl_write for the format). If the output limits or
+ * the anti-windup gain are invalid, the error is logged, `pid' is left
+ * untouched and B_FALSE is returned.
+ */
+static inline bool_t
 pid_ctl_parse(pid_ctl_t *pid, const conf_t *conf, const char *prefix)
 {
 	double k_p = 0, k_i = 0, lim_i = 0, k_d = 0, r_d = 0;
+	double out_min = -INFINITY, out_max = INFINITY, k_aw = 0;
 	bool_t integ_clamp;
 
 	ASSERT(pid != NULL);
@@ -50,9 +58,54 @@ pid_ctl_parse(pid_ctl_t *pid, const conf_t *conf, const char *prefix)
 	conf_get_d_v(conf, "%s/k_d", &k_d, prefix);
 	conf_get_d_v(conf, "%s/r_d", &r_d, prefix);
 	conf_get_b_v(conf, "%s/integ_clamp", &integ_clamp, prefix);
+	/* optional, default to no output limits & no anti-windup */
+	conf_get_d_v(conf, "%s/out_min", &out_min, prefix);
+	conf_get_d_v(conf, "%s/out_max", &out_max, prefix);
+	conf_get_d_v(conf, "%s/k_aw", &k_aw, prefix);
+	if (!(out_min <= out_max)) {
+		logMsg("Invalid PID controller config %s: out_min (%g) must "
+		    "not be greater than out_max (%g)", prefix, out_min,
+		    out_max);
+		return (B_FALSE);
+	}
+	if (!(k_aw >= 0)) {
+		logMsg("Invalid PID controller config %s: k_aw (%g) must "
+		    "not be negative", prefix, k_aw);
+		return (B_FALSE);
+	}
 
 	pid_ctl_init(pid, k_p, k_i, lim_i, k_d, r_d);
 	pid_ctl_set_integ_clamp(pid, integ_clamp);
+	pid_ctl_set_out_lim(pid, out_min, out_max);
+	pid_ctl_set_k_aw(pid, k_aw);
+
+	return (B_TRUE);
+}
+
+/*
+ * Writes the tuning of a PID controller into a conf_t, in the format
+ * expected by pid_ctl_parse. Output limits and the anti-windup gain are
+ * only written if they have been set.
+ */
+static inline void
+pid_ctl_write(const pid_ctl_t *pid, conf_t *conf, const char *prefix)
+{
+	ASSERT(pid != NULL);
+	ASSERT(conf != NULL);
+	ASSERT(prefix != NULL);
+
+	conf_set_d_v(conf, "%s/k_p", pid->k_p, prefix);
+	conf_set_d_v(conf, "%s/k_i", pid->k_i, prefix);
+	conf_set_d_v(conf, "%s/lim_i", pid->lim_i, prefix);
+	conf_set_d_v(conf, "%s/k_d", pid->k_d, prefix);
+	conf_set_d_v(conf, "%s/r_d", pid->r_d, prefix);
+	conf_set_b_v(conf, "%s/integ_clamp", pid->integ_clamp, prefix);
+	if (isfinite(pid->out_min))
+		conf_set_d_v(conf, "%s/out_min", pid->out_min, prefix);
+	if (isfinite(pid->out_max))
+		conf_set_d_v(conf, "%s/out_max", pid->out_max, prefix);
+	if (pid->k_aw != 0)
+		conf_set_d_v(conf, "%s/k_aw", pid->k_aw, prefix);
 }
 
 #ifdef __cplusplus
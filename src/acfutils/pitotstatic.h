@@ -0,0 +1,72 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_PITOTSTATIC_H_
+#define	_ACF_UTILS_PITOTSTATIC_H_
+
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Pitot-static system simulation. See pitotstatic.c for usage info.
+ */
+
+typedef enum {
+	PS_PORT_OK,		/* port working normally */
+	PS_PORT_BLOCKED,	/* port blocked, pitot drain hole open */
+	PS_PORT_BLOCKED_DRAIN	/* port and pitot drain hole both blocked */
+} ps_port_fail_t;
+
+typedef struct {
+	/* Outputs. Read these, but don't modify them. */
+	double		ias;		/* indicated airspeed, knots */
+	double		mach;		/* indicated Mach number */
+	double		alt;		/* indicated altitude, feet */
+	double		vs;		/* indicated vertical speed, ft/min */
+	double		pt;		/* sensed total pressure, Pa */
+	double		ps;		/* sensed static pressure, Pa */
+
+	/* Parameters, set by pitotstatic_init, may be modified. */
+	double		pitot_lag;	/* pitot line lag, seconds */
+	double		static_lag;	/* static line lag, seconds */
+	double		vsi_lag;	/* VSI lag, seconds */
+	double		drain_lag;	/* blocked pitot drain lag, seconds */
+	double		pos_err;	/* static error, fraction of qc */
+
+	/* Failures, may be set by the caller at any time. */
+	ps_port_fail_t	pitot_fail;
+	ps_port_fail_t	static_fail;
+
+	/* private */
+	bool_t		inited;
+	double		vs_alt;
+} pitotstatic_t;
+
+API_EXPORT void pitotstatic_init(pitotstatic_t *ps);
+API_EXPORT void pitotstatic_update(pitotstatic_t *ps, double press,
+    double oat, double ktas, double baro, double d_t);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_PITOTSTATIC_H_ */
@@ -0,0 +1,66 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_PNET_H_
+#define	_ACF_UTILS_PNET_H_
+
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Quasi-static hydraulic & pneumatic pressure networks. See pnet.c for
+ * usage info.
+ */
+
+typedef struct pnet_s pnet_t;
+
+API_EXPORT pnet_t *pnet_alloc(void);
+API_EXPORT void pnet_free(pnet_t *pnet);
+
+API_EXPORT unsigned pnet_add_res(pnet_t *pnet, double press, double qty);
+API_EXPORT unsigned pnet_add_node(pnet_t *pnet, double compliance);
+API_EXPORT unsigned pnet_add_accum(pnet_t *pnet, double volume,
+    double precharge);
+API_EXPORT double pnet_node_get_press(const pnet_t *pnet, unsigned node);
+API_EXPORT void pnet_res_set_qty(pnet_t *pnet, unsigned res, double qty);
+API_EXPORT double pnet_res_get_qty(const pnet_t *pnet, unsigned res);
+API_EXPORT double pnet_accum_get_fluid(const pnet_t *pnet, unsigned accum);
+
+API_EXPORT unsigned pnet_add_pump(pnet_t *pnet, unsigned res, unsigned node,
+    double max_flow, double max_press);
+API_EXPORT void pnet_pump_set_speed(pnet_t *pnet, unsigned pump,
+    double speed);
+API_EXPORT double pnet_pump_get_flow(const pnet_t *pnet, unsigned pump);
+
+API_EXPORT unsigned pnet_add_valve(pnet_t *pnet, unsigned node_a,
+    unsigned node_b, double conductance, bool_t check);
+API_EXPORT void pnet_valve_set_open(pnet_t *pnet, unsigned valve,
+    double open);
+API_EXPORT double pnet_valve_get_flow(const pnet_t *pnet, unsigned valve);
+
+API_EXPORT void pnet_update(pnet_t *pnet, double d_t);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_PNET_H_ */
@@ -0,0 +1,67 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_PREFLIGHT_H_
+#define	_ACF_UTILS_PREFLIGHT_H_
+
+#include <stdlib.h>
+
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Startup dependency checking. See preflight.c for usage info.
+ */
+
+typedef struct {
+	/* feature requiring this, or NULL if required by the whole plugin */
+	const char	*feature;
+	const char	*desc;		/* human-readable description */
+	bool_t		passed;
+} preflight_result_t;
+
+typedef struct preflight_s preflight_t;
+
+API_EXPORT preflight_t *preflight_alloc(void);
+API_EXPORT void preflight_free(preflight_t *pf);
+
+API_EXPORT void preflight_add_file(preflight_t *pf, const char *feature,
+    const char *path, bool_t is_dir);
+API_EXPORT void preflight_add_xp_version(preflight_t *pf, const char *feature,
+    int min_xp_ver, int min_xplm_ver);
+API_EXPORT void preflight_add_gl_version(preflight_t *pf, const char *feature,
+    int major, int minor);
+API_EXPORT void preflight_add_gl_ext(preflight_t *pf, const char *feature,
+    const char *ext);
+
+API_EXPORT bool_t preflight_run(preflight_t *pf);
+API_EXPORT bool_t preflight_feature_ok(const preflight_t *pf,
+    const char *feature);
+API_EXPORT const preflight_result_t *preflight_get_results(
+    const preflight_t *pf, size_t *num);
+API_EXPORT char *preflight_get_report(const preflight_t *pf);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_PREFLIGHT_H_ */
@@ -0,0 +1,68 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_QUAT_H_
+#define	_ACF_UTILS_QUAT_H_
+
+#include "geom.h"
+#include "sysmacros.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Quaternion and Euler angle attitude representation. See quat.c for
+ * usage info.
+ */
+
+typedef struct {
+	double	w;
+	double	x;
+	double	y;
+	double	z;
+} quat_t;
+
+typedef struct {
+	double	pitch;		/* degrees, positive nose up */
+	double	roll;		/* degrees, positive right wing down */
+	double	hdg;		/* degrees true */
+} euler_t;
+
+#define	QUAT(w, x, y, z)	((quat_t){(w), (x), (y), (z)})
+#define	QUAT_IDENT		QUAT(1, 0, 0, 0)
+#define	EULER(pitch, roll, hdg)	((euler_t){(pitch), (roll), (hdg)})
+
+API_EXPORT quat_t quat_from_euler(euler_t euler) PURE_ATTR;
+API_EXPORT euler_t quat_to_euler(quat_t q) PURE_ATTR;
+API_EXPORT quat_t quat_from_axis_angle(vect3_t axis, double angle) PURE_ATTR;
+
+API_EXPORT quat_t quat_mul(quat_t a, quat_t b) PURE_ATTR;
+API_EXPORT quat_t quat_conj(quat_t q) PURE_ATTR;
+API_EXPORT double quat_abs(quat_t q) PURE_ATTR;
+API_EXPORT quat_t quat_unit(quat_t q) PURE_ATTR;
+API_EXPORT quat_t quat_slerp(quat_t a, quat_t b, double t) PURE_ATTR;
+
+API_EXPORT vect3_t quat_rot(quat_t q, vect3_t v) PURE_ATTR;
+API_EXPORT vect3_t quat_rot_inv(quat_t q, vect3_t v) PURE_ATTR;
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_QUAT_H_ */
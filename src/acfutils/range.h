@@ -0,0 +1,132 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_RANGE_H_
+#define	_ACF_UTILS_RANGE_H_
+
+#include <math.h>
+#include <stdlib.h>
+
+#include "assert.h"
+#include "conf.h"
+#include "geom.h"
+#include "math.h"
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Value ranges (min/max pairs) and 2D envelopes, such as an operating
+ * speed range or a weight-vs-CG envelope. A range is a closed interval,
+ * so both of its limits are considered to be inside of the range. A
+ * range with min > max is empty (see RANGE_EMPTY).
+ */
+
+typedef struct {
+	double	min;
+	double	max;
+} range_t;
+
+#define	RANGE(min, max)	((range_t){(min), (max)})
+#define	RANGE_EMPTY	RANGE(INFINITY, -INFINITY)
+
+static inline bool_t
+range_is_empty(range_t r)
+{
+	return (!(r.min <= r.max));
+}
+
+static inline double
+range_width(range_t r)
+{
+	return (range_is_empty(r) ? 0 : r.max - r.min);
+}
+
+static inline bool_t
+range_contains(range_t r, double x)
+{
+	return (x >= r.min && x <= r.max);
+}
+
+/*
+ * Clamps `x' to lie within `r'. The range mustn't be empty.
+ */
+static inline double
+range_clamp(range_t r, double x)
+{
+	ASSERT(!range_is_empty(r));
+	return (clamp(x, r.min, r.max));
+}
+
+/*
+ * Returns the intersection of two ranges. If the ranges don't overlap,
+ * the result is empty.
+ */
+static inline range_t
+range_isect(range_t a, range_t b)
+{
+	range_t r = RANGE(MAX(a.min, b.min), MIN(a.max, b.max));
+	return (range_is_empty(r) ? RANGE_EMPTY : r);
+}
+
+/*
+ * Returns the smallest range containing both `a' and `b'.
+ */
+static inline range_t
+range_union(range_t a, range_t b)
+{
+	if (range_is_empty(a))
+		return (b);
+	if (range_is_empty(b))
+		return (a);
+	return (RANGE(MIN(a.min, b.min), MAX(a.max, b.max)));
+}
+
+/*
+ * Returns where `x' lies within the range, 0 being at `min' and 1 at
+ * `max'. The range mustn't be empty or of zero width.
+ */
+static inline double
+range_fract(range_t r, double x, bool_t clamp_output)
+{
+	ASSERT(!range_is_empty(r));
+	return (iter_fract(x, r.min, r.max, clamp_output));
+}
+
+API_EXPORT bool_t range_conf_get(const conf_t *conf, const char *key,
+    range_t *r);
+API_EXPORT void range_conf_set(conf_t *conf, const char *key, range_t r);
+
+typedef struct envelope_s envelope_t;
+
+API_EXPORT envelope_t *envelope_alloc(const vect2_t *pts, size_t num_pts);
+API_EXPORT void envelope_free(envelope_t *env);
+API_EXPORT bool_t envelope_contains(const envelope_t *env, vect2_t pt);
+API_EXPORT vect2_t envelope_clamp(const envelope_t *env, vect2_t pt);
+API_EXPORT range_t envelope_range_x(const envelope_t *env);
+API_EXPORT range_t envelope_range_y(const envelope_t *env);
+API_EXPORT range_t envelope_range_y_at(const envelope_t *env, double x);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_RANGE_H_ */
@@ -0,0 +1,59 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_RINGBUF_H_
+#define	_ACF_UTILS_RINGBUF_H_
+
+#include <stdint.h>
+#include <stdlib.h>
+
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Fixed-size ring buffers for passing data between threads without
+ * allocation. See ringbuf.c for usage info.
+ */
+
+typedef enum {
+	RINGBUF_MULTI_PROD =	1 << 0,	/* allow multiple producer threads */
+	RINGBUF_OVERWRITE =	1 << 1	/* drop the oldest element when full */
+} ringbuf_flags_t;
+
+typedef struct ringbuf_s ringbuf_t;
+
+API_EXPORT ringbuf_t *ringbuf_alloc(size_t elem_sz, size_t cap,
+    unsigned flags);
+API_EXPORT void ringbuf_free(ringbuf_t *rb);
+
+API_EXPORT bool_t ringbuf_push(ringbuf_t *rb, const void *elem);
+API_EXPORT bool_t ringbuf_pop(ringbuf_t *rb, void *elem);
+
+API_EXPORT size_t ringbuf_get_count(const ringbuf_t *rb);
+API_EXPORT size_t ringbuf_get_cap(const ringbuf_t *rb);
+API_EXPORT uint64_t ringbuf_get_num_dropped(const ringbuf_t *rb);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_RINGBUF_H_ */
@@ -0,0 +1,79 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_RWYSEL_H_
+#define	_ACF_UTILS_RWYSEL_H_
+
+#include <stdlib.h>
+
+#include "airportdb.h"
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Runway-in-use selection based on wind and airport layout. See
+ * rwysel.c for usage info.
+ */
+
+typedef enum {
+	RWYSEL_DEP,
+	RWYSEL_ARR
+} rwysel_op_t;
+
+typedef struct {
+	char	id[4];		/* runway end ID, e.g. "27L" */
+	bool_t	dep;		/* preferential for departures */
+	bool_t	arr;		/* preferential for arrivals */
+} rwysel_pref_t;
+
+typedef struct {
+	double			calm_wind;	/* knots, default 5 */
+	double			max_tailwind;	/* knots, default 5 */
+	double			max_xwind;	/* knots, default 25 */
+	double			min_len;	/* meters, default 0 */
+	const rwysel_pref_t	*prefs;		/* preferential runways */
+	size_t			num_prefs;
+	const char		*cur_id;	/* runway end now in use */
+} rwysel_params_t;
+
+typedef struct {
+	const runway_t		*rwy;
+	const runway_end_t	*end;
+	double			headwind;	/* knots, negative = tailwind */
+	double			xwind;		/* knots, always positive */
+	bool_t			within_limits;
+	double			score;
+} rwysel_cand_t;
+
+API_EXPORT void rwysel_params_init(rwysel_params_t *params);
+API_EXPORT size_t rwysel_rank(const airport_t *arpt, double wind_dir,
+    double wind_spd, rwysel_op_t op, const rwysel_params_t *params,
+    rwysel_cand_t *cands, size_t max_cands);
+API_EXPORT bool_t rwysel_select(const airport_t *arpt, double wind_dir,
+    double wind_spd, rwysel_op_t op, const rwysel_params_t *params,
+    rwysel_cand_t *cand);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_RWYSEL_H_ */
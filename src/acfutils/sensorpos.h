@@ -0,0 +1,59 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_SENSORPOS_H_
+#define	_ACF_UTILS_SENSORPOS_H_
+
+#include "geom.h"
+#include "quat.h"
+#include "sysmacros.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Sensor and antenna lever arm corrections. See sensorpos.c for usage
+ * info.
+ */
+
+API_EXPORT vect3_t sensorpos_arm(vect3_t sensor_pos, vect3_t cg_pos)
+    PURE_ATTR;
+
+API_EXPORT vect3_t sensorpos_vel(vect3_t vel, vect3_t omega, vect3_t arm)
+    PURE_ATTR;
+API_EXPORT vect3_t sensorpos_accel(vect3_t accel, vect3_t omega,
+    vect3_t omega_dot, vect3_t arm) PURE_ATTR;
+API_EXPORT void sensorpos_airflow(vect3_t vel, vect3_t omega, vect3_t arm,
+    double *tas, double *aoa, double *aos);
+
+API_EXPORT double sensorpos_cg2ra(double hgt, euler_t att, vect3_t arm)
+    PURE_ATTR;
+API_EXPORT double sensorpos_ra2cg(double ra, euler_t att, vect3_t arm)
+    PURE_ATTR;
+
+API_EXPORT geo_pos3_t sensorpos_cg2geo(geo_pos3_t pos, euler_t att,
+    vect3_t arm) PURE_ATTR;
+API_EXPORT geo_pos3_t sensorpos_geo2cg(geo_pos3_t pos, euler_t att,
+    vect3_t arm) PURE_ATTR;
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_SENSORPOS_H_ */
@@ -0,0 +1,183 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_SENSORSIM_H_
+#define	_ACF_UTILS_SENSORSIM_H_
+
+#include <math.h>
+#include <stdint.h>
+
+#include "assert.h"
+#include "crc64.h"
+#include "sysmacros.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Simulated sensor imperfections. A sensorsim_t takes the true value of
+ * a measured quantity (e.g. a temperature or pressure) and produces what
+ * a real gauge would show. This consists of:
+ *
+ * - a first-order lag with time constant `lag' (see FILTER_IN), so the
+ *	output approaches the true value gradually,
+ * - gaussian noise with a standard deviation of `noise',
+ * - quantization to multiples of `quant', modeling the resolution of
+ *	the sensor or its A/D converter,
+ * - failure modes (see sensorsim_set_fail).
+ *
+ * Setting `lag', `noise' or `quant' to zero disables that effect. The
+ * noise is generated by a private crc64_rng_t seeded with `seed', so the
+ * output is reproducible. Since the PRNG uses crc64, crc64_init must be
+ * called before using noisy sensors.
+ *
+ * Typical usage:
+ *
+ *	sensorsim_t egt;
+ *	sensorsim_init(&egt, 1.5, 2, 1, 12345);
+ *	...
+ *	every frame:
+ *	double egt_ind = sensorsim_update(&egt, true_egt, d_t);
+ *	if (isnan(egt_ind))
+ *		... sensor is offline, show dashes ...
+ */
+typedef enum {
+	SENSORSIM_FAIL_NONE,
+	SENSORSIM_FAIL_STUCK,	/* output is frozen at its last value */
+	SENSORSIM_FAIL_DRIFT,	/* output drifts away at `drift_rate' */
+	SENSORSIM_FAIL_OFFLINE	/* output is NAN */
+} sensorsim_fail_t;
+
+typedef struct {
+	double			lag;
+	double			noise;
+	double			quant;
+	sensorsim_fail_t	fail;
+	double			drift_rate;	/* units per second */
+	double			drift;		/* accumulated drift */
+	double			filtered;	/* lagged true value */
+	double			out;
+	crc64_rng_t		rng;
+} sensorsim_t;
+
+static inline void
+sensorsim_init(sensorsim_t *sens, double lag, double noise, double quant,
+    uint64_t seed)
+{
+	ASSERT(sens != NULL);
+	ASSERT3F(lag, >=, 0);
+	ASSERT3F(noise, >=, 0);
+	ASSERT3F(quant, >=, 0);
+	sens->lag = lag;
+	sens->noise = noise;
+	sens->quant = quant;
+	sens->fail = SENSORSIM_FAIL_NONE;
+	sens->drift_rate = 0;
+	sens->drift = 0;
+	sens->filtered = NAN;
+	sens->out = NAN;
+	crc64_rng_seed(&sens->rng, seed);
+}
+
+/*
+ * Feeds the true value `value' into the sensor and returns the new
+ * sensor output. `d_t' is the time elapsed since the last update. The
+ * lag keeps tracking the true value even while the sensor is failed,
+ * so the output resumes smoothly once the failure is cleared. Passing
+ * a NAN `value' makes the output NAN too.
+ */
+static inline double
+sensorsim_update(sensorsim_t *sens, double value, double d_t)
+{
+	double out;
+
+	ASSERT(sens != NULL);
+	ASSERT3F(d_t, >, 0);
+
+	if (sens->lag > 0 && !isnan(sens->filtered) && !isnan(value))
+		FILTER_IN(sens->filtered, value, d_t, sens->lag);
+	else
+		sens->filtered = value;
+
+	switch (sens->fail) {
+	case SENSORSIM_FAIL_STUCK:
+		return (sens->out);
+	case SENSORSIM_FAIL_OFFLINE:
+		sens->out = NAN;
+		return (sens->out);
+	case SENSORSIM_FAIL_DRIFT:
+		sens->drift += sens->drift_rate * d_t;
+		break;
+	default:
+		break;
+	}
+	out = sens->filtered + sens->drift;
+	if (sens->noise > 0)
+		out += crc64_rng_normal(&sens->rng, sens->noise);
+	if (sens->quant > 0)
+		out = round(out / sens->quant) * sens->quant;
+	sens->out = out;
+
+	return (sens->out);
+}
+
+/*
+ * Returns the sensor output computed by the last call to sensorsim_update.
+ */
+static inline double
+sensorsim_get(const sensorsim_t *sens)
+{
+	ASSERT(sens != NULL);
+	return (sens->out);
+}
+
+/*
+ * Sets the failure mode of the sensor. For SENSORSIM_FAIL_DRIFT,
+ * `drift_rate' is the rate (in units per second) at which the output
+ * drifts away from the true value. It is ignored for the other failure
+ * modes. Setting SENSORSIM_FAIL_NONE clears any failure, as well as any
+ * drift accumulated so far.
+ */
+static inline void
+sensorsim_set_fail(sensorsim_t *sens, sensorsim_fail_t fail,
+    double drift_rate)
+{
+	ASSERT(sens != NULL);
+	sens->fail = fail;
+	if (fail == SENSORSIM_FAIL_DRIFT) {
+		sens->drift_rate = drift_rate;
+	} else {
+		sens->drift_rate = 0;
+		if (fail == SENSORSIM_FAIL_NONE)
+			sens->drift = 0;
+	}
+}
+
+static inline sensorsim_fail_t
+sensorsim_get_fail(const sensorsim_t *sens)
+{
+	ASSERT(sens != NULL);
+	return (sens->fail);
+}
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_SENSORSIM_H_ */
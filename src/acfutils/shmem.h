@@ -0,0 +1,52 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_SHMEM_H_
+#define	_ACF_UTILS_SHMEM_H_
+
+#include <stdint.h>
+#include <stdlib.h>
+
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Named shared memory regions for exchanging data between processes or
+ * plugins. See shmem.c for usage info.
+ */
+
+typedef struct shmem_s shmem_t;
+
+API_EXPORT shmem_t *shmem_open(const char *name, size_t size, bool_t create);
+API_EXPORT void shmem_close(shmem_t *shm);
+API_EXPORT size_t shmem_get_size(const shmem_t *shm);
+
+API_EXPORT void shmem_write(shmem_t *shm, const void *data, size_t size);
+API_EXPORT bool_t shmem_read(const shmem_t *shm, void *data, size_t size,
+    uint64_t *seq);
+API_EXPORT uint64_t shmem_get_seq(const shmem_t *shm);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_SHMEM_H_ */
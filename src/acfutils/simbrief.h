@@ -0,0 +1,88 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_SIMBRIEF_H_
+#define	_ACF_UTILS_SIMBRIEF_H_
+
+#include <stdlib.h>
+
+#include "geom.h"
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * SimBrief operational flight plan (OFP) fetcher & parser. See
+ * simbrief.c for usage info.
+ */
+
+typedef struct {
+	char		ident[16];
+	char		type[8];	/* "wpt", "vor", "ndb", "apt", etc. */
+	char		via[16];	/* airway, "DCT" or procedure name */
+	geo_pos2_t	pos;
+	int		alt;		/* planned altitude, feet */
+} simbrief_fix_t;
+
+typedef struct {
+	char		callsign[16];
+	char		acf_icao[8];
+	char		orig[8];
+	char		orig_rwy[8];
+	char		dest[8];
+	char		dest_rwy[8];
+	char		*route;		/* ATC route string */
+	int		cruise_alt;	/* initial cruise altitude, feet */
+	int		cost_index;
+
+	size_t		num_altns;
+	char		(*altns)[8];	/* ICAO codes of the alternates */
+
+	/* Weights, all in kg */
+	double		zfw;
+	double		tow;
+	double		ldw;
+	double		payload;
+
+	/* Fuel plan, all in kg */
+	double		fuel_ramp;
+	double		fuel_taxi;
+	double		fuel_trip;
+	double		fuel_contingency;
+	double		fuel_altn;
+	double		fuel_reserve;
+	double		fuel_extra;
+
+	size_t		num_fixes;
+	simbrief_fix_t	*fixes;
+} simbrief_ofp_t;
+
+API_EXPORT simbrief_ofp_t *simbrief_fetch(const char *pilot_id,
+    const char *cainfo);
+API_EXPORT simbrief_ofp_t *simbrief_parse(const void *buf, size_t len);
+API_EXPORT simbrief_ofp_t *simbrief_parse_file(const char *filename);
+API_EXPORT void simbrief_free(simbrief_ofp_t *ofp);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_SIMBRIEF_H_ */
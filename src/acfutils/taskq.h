@@ -27,18 +27,23 @@
 #define	_ACFUTILS_TASKQ_H_
 
 #include <stdbool.h>
+#include <stddef.h>
 #include <stdint.h>
 
+#include "sysmacros.h"
+
 #ifdef	__cplusplus
 extern "C" {
 #endif
 
 typedef struct taskq_s taskq_t;
+typedef struct taskq_future_s taskq_future_t;
 
 typedef void *(*taskq_init_thr_t)(void *userinfo);
 typedef void (*taskq_fini_thr_t)(void *userinfo, void *thr_info);
 typedef void (*taskq_proc_task_t)(void *userinfo, void *thr_info, void *task);
 typedef void (*taskq_discard_task_t)(void *userinfo, void *task);
+typedef void *(*taskq_func_t)(void *arg);
 
 API_EXPORT taskq_t *taskq_alloc(unsigned num_threads_min,
     unsigned num_threads_max, uint64_t thr_stop_delay_us,
@@ -48,6 +53,15 @@ API_EXPORT taskq_t *taskq_alloc(unsigned num_threads_min,
 API_EXPORT void taskq_free(taskq_t *tq);
 
 API_EXPORT void taskq_submit(taskq_t *tq, void *task);
+API_EXPORT bool taskq_try_submit(taskq_t *tq, void *task);
+API_EXPORT taskq_future_t *taskq_submit_func(taskq_t *tq, taskq_func_t func,
+    void *arg);
+API_EXPORT void taskq_wait_idle(taskq_t *tq);
+API_EXPORT size_t taskq_get_num_queued(taskq_t *tq);
+
+API_EXPORT bool taskq_future_is_done(taskq_future_t *fut);
+API_EXPORT void *taskq_future_wait(taskq_future_t *fut, bool *canceled);
+API_EXPORT void taskq_future_free(taskq_future_t *fut);
 API_EXPORT bool taskq_wants_shutdown(taskq_t *tq);
 
 API_EXPORT void taskq_set_num_threads_min(taskq_t *tq, unsigned n_threads_min);
@@ -56,6 +70,8 @@ API_EXPORT void taskq_set_num_threads_max(taskq_t *tq, unsigned n_threads_max);
 API_EXPORT unsigned taskq_get_num_threads_max(const taskq_t *tq);
 API_EXPORT void taskq_set_thr_stop_delay(taskq_t *tq, uint64_t stop_delay_us);
 API_EXPORT uint64_t taskq_get_thr_stop_delay(const taskq_t *tq);
+API_EXPORT void taskq_set_max_queued(taskq_t *tq, size_t max_queued);
+API_EXPORT size_t taskq_get_max_queued(taskq_t *tq);
 
 #ifdef	__cplusplus
 }
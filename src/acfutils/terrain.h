@@ -0,0 +1,54 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_TERRAIN_H_
+#define	_ACF_UTILS_TERRAIN_H_
+
+#include <stdlib.h>
+
+#include "geom.h"
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Terrain elevation database built from X-Plane DSF DEM rasters. See
+ * terrain.c for usage info.
+ */
+
+typedef struct terr_s terr_t;
+
+API_EXPORT terr_t *terr_alloc(void);
+API_EXPORT void terr_free(terr_t *terr);
+
+API_EXPORT bool_t terr_add_dsf(terr_t *terr, const char *filename);
+API_EXPORT bool_t terr_remove_tile(terr_t *terr, int lat, int lon);
+API_EXPORT size_t terr_get_num_tiles(terr_t *terr);
+
+API_EXPORT double terr_get_elev(terr_t *terr, geo_pos2_t pos);
+API_EXPORT double terr_get_profile(terr_t *terr, geo_pos2_t start,
+    geo_pos2_t end, size_t num_pts, double *elevs);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_TERRAIN_H_ */
@@ -26,6 +26,7 @@
 
 #if	APL || LIN
 #include <pthread.h>
+#include <sched.h>
 #include <stdint.h>
 #include <time.h>
 #else	/* !APL && !LIN */
@@ -81,6 +82,10 @@ extern "C" {
  *	mutex_enter(&my_lock);		-- grab a lock
  *	... do some critical, exclusiony-type stuff ...
  *	mutex_exit(&my_lock);		-- release a lock
+ *	if (mutex_tryenter(&my_lock)) {	-- grab a lock if it's free
+ *		... do some critical stuff ...
+ *		mutex_exit(&my_lock);
+ *	}
  *	mutex_destroy(&my_lock);	-- free a lock
  *
  * Example of how to use a condvar_t:
@@ -156,6 +161,9 @@ typedef struct {
 #define	atomic64_t		_Atomic int64_t
 #define	atomic_inc_64(x)	atomic_fetch_add((x), 1)
 #define	atomic_dec_64(x)	atomic_fetch_add((x), -1)
+#define	atomic_cas_32(x, oldval, newval) \
+	atomic_compare_exchange_strong((x), &(int32_t){ (oldval) }, (newval))
+#define	memory_barrier()	atomic_thread_fence(memory_order_seq_cst)
 #elif	IBM
 #define	atomic32_t		volatile LONG
 #define	atomic_inc_32(x)	InterlockedIncrement((x))
@@ -163,6 +171,9 @@ typedef struct {
 #define	atomic64_t		volatile LONG64
 #define	atomic_inc_64(x)	InterlockedIncrement64((x))
 #define	atomic_dec_64(x)	InterlockedDecrement64((x))
+#define	atomic_cas_32(x, oldval, newval) \
+	(InterlockedCompareExchange((x), (newval), (oldval)) == (oldval))
+#define	memory_barrier()	MemoryBarrier()
 #elif	APL
 #define	atomic32_t		volatile int32_t
 #define	atomic_inc_32(x)	OSAtomicAdd32(1, (x))
@@ -170,6 +181,9 @@ typedef struct {
 #define	atomic64_t		volatile int64_t
 #define	atomic_inc_64(x)	OSAtomicAdd64(1, (x))
 #define	atomic_dec_64(x)	OSAtomicAdd64(-1, (x))
+#define	atomic_cas_32(x, oldval, newval) \
+	OSAtomicCompareAndSwap32Barrier((oldval), (newval), (x))
+#define	memory_barrier()	__sync_synchronize()
 #else	/* LIN */
 #define	atomic32_t		volatile int32_t
 #define	atomic_inc_32(x)	__sync_add_and_fetch((x), 1)
@@ -177,6 +191,9 @@ typedef struct {
 #define	atomic64_t		volatile int64_t
 #define	atomic_inc_64(x)	__sync_add_and_fetch((x), 1)
 #define	atomic_dec_64(x)	__sync_add_and_fetch((x), -1)
+#define	atomic_cas_32(x, oldval, newval) \
+	__sync_bool_compare_and_swap((x), (oldval), (newval))
+#define	memory_barrier()	__sync_synchronize()
 #endif	/* LIN */
 
 #if	APL || LIN
@@ -196,6 +213,7 @@ typedef struct {
 	} while (0)
 #define	mutex_destroy(mtx)	pthread_mutex_destroy((mtx))
 #define	mutex_enter(mtx)	pthread_mutex_lock((mtx))
+#define	mutex_tryenter(mtx)	(pthread_mutex_trylock((mtx)) == 0)
 #define	mutex_exit(mtx)		pthread_mutex_unlock((mtx))
 
 #if	LIN
@@ -240,6 +258,12 @@ typedef struct {
 		ASSERT((x)->inited); \
 		LeaveCriticalSection(&(x)->cs); \
 	} while (0)
+static inline bool_t
+mutex_tryenter(mutex_t *x)
+{
+	ASSERT(x->inited);
+	return (TryEnterCriticalSection(&x->cs) != 0);
+}
 #define	VERIFY_MUTEX_HELD(mtx)		(void)1
 #define	VERIFY_MUTEX_NOT_HELD(mtx)	(void)1
 
@@ -561,6 +585,219 @@ rwmutex_held_write(rwmutex_t *rw)
 	return (rw->writer == curthread);
 }
 
+/*
+ * Spinlocks are a low-latency alternative to mutex_t for protecting
+ * very short critical sections (e.g. copying a small structure), where
+ * the cost of a mutex's potential system call to park & unpark the
+ * waiting thread would exceed the time spent in the critical section.
+ * Spinlocks never enter the kernel: a thread waiting for a spinlock
+ * simply busy-waits until the lock is released. Consequently, you must
+ * never hold a spinlock for a long time, block while holding it, or
+ * attempt to re-enter a spinlock you already hold (spinlocks aren't
+ * recursive, unlike mutex_t).
+ *
+ *	spinlock_t lock;
+ *	spinlock_init(&lock);
+ *	spinlock_enter(&lock);
+ *	... copy some data ...
+ *	spinlock_exit(&lock);
+ *
+ * Spinlocks need no destruction.
+ */
+typedef struct {
+	atomic32_t	locked;
+} spinlock_t;
+
+/*
+ * Number of times a spinning waiter backs off (doubling the number of
+ * CPU pauses each time) before it starts yielding its CPU to other
+ * threads instead, in case the lock holder has been preempted.
+ */
+#define	SPINLOCK_MAX_BACKOFF	6
+
+/*
+ * Tells the CPU that we're busy-waiting. This saves power, lets the
+ * other hardware thread on the same core run and avoids a memory order
+ * mis-speculation penalty when the lock is finally released.
+ */
+static inline void
+cpu_pause(void)
+{
+#if	IBM
+	YieldProcessor();
+#elif	defined(__x86_64__) || defined(__i386__)
+	__builtin_ia32_pause();
+#elif	defined(__aarch64__) || defined(__arm__)
+	__asm__ __volatile__("yield");
+#endif
+}
+
+/*
+ * Waits a little before a busy-waiting thread re-checks a contended lock.
+ * `backoff' must start out at 0 and is updated on every call.
+ */
+static inline void
+spin_backoff(unsigned *backoff)
+{
+	if (*backoff < SPINLOCK_MAX_BACKOFF) {
+		for (unsigned i = 0; i < (1u << *backoff); i++)
+			cpu_pause();
+		(*backoff)++;
+	} else {
+#if	IBM
+		SwitchToThread();
+#else
+		sched_yield();
+#endif
+	}
+}
+
+static inline void
+spinlock_init(spinlock_t *lock)
+{
+	ASSERT(lock != NULL);
+	lock->locked = 0;
+	memory_barrier();
+}
+
+/*
+ * Attempts to grab a spinlock without waiting. Returns B_TRUE if the lock
+ * was acquired, or B_FALSE if it is held by someone else.
+ */
+static inline bool_t
+spinlock_tryenter(spinlock_t *lock)
+{
+	ASSERT(lock != NULL);
+	return (atomic_cas_32(&lock->locked, 0, 1));
+}
+
+static inline void
+spinlock_enter(spinlock_t *lock)
+{
+	unsigned backoff = 0;
+
+	ASSERT(lock != NULL);
+	while (!atomic_cas_32(&lock->locked, 0, 1)) {
+		/* spin on a plain read to avoid hammering the cache line */
+		while (lock->locked != 0)
+			spin_backoff(&backoff);
+	}
+}
+
+static inline void
+spinlock_exit(spinlock_t *lock)
+{
+	ASSERT(lock != NULL);
+	ASSERT(lock->locked != 0);
+	memory_barrier();
+	lock->locked = 0;
+}
+
+/*
+ * A seqlock lets a writer publish data to readers, with readers never
+ * blocking the writer and never entering the kernel. This is ideal for
+ * handing off per-frame data between a worker thread and the simulator's
+ * flight loop: the reader makes a copy of the data and then checks if
+ * the writer modified it in the meantime, in which case the copy is
+ * simply retried.
+ *
+ *	seqlock_t seq;		-- protects `shared_data'
+ *	seqlock_init(&seq);
+ *
+ *	-- writer:
+ *		seqlock_write_begin(&seq);
+ *		shared_data = new_data;
+ *		seqlock_write_end(&seq);
+ *
+ *	-- reader:
+ *		uint32_t gen;
+ *		do {
+ *			gen = seqlock_read_begin(&seq);
+ *			my_copy = shared_data;
+ *		} while (seqlock_read_retry(&seq, gen));
+ *
+ * The SEQLOCK_READ and SEQLOCK_WRITE macros wrap the above patterns for
+ * simple structure copies. Only one writer may be in a write section at
+ * a time. If you have multiple writers, serialize them using a spinlock
+ * or mutex. Readers must only copy data out in the read section, since
+ * the data can change under them at any time, and act on it only once
+ * seqlock_read_retry has returned B_FALSE.
+ */
+typedef struct {
+	atomic32_t	seq;
+} seqlock_t;
+
+static inline void
+seqlock_init(seqlock_t *lock)
+{
+	ASSERT(lock != NULL);
+	lock->seq = 0;
+	memory_barrier();
+}
+
+static inline void
+seqlock_write_begin(seqlock_t *lock)
+{
+	ASSERT(lock != NULL);
+	/* odd sequence number: write in progress */
+	atomic_inc_32(&lock->seq);
+	memory_barrier();
+}
+
+static inline void
+seqlock_write_end(seqlock_t *lock)
+{
+	ASSERT(lock != NULL);
+	memory_barrier();
+	atomic_inc_32(&lock->seq);
+}
+
+/*
+ * Starts a read section. Returns a sequence number which must be passed
+ * to seqlock_read_retry at the end of the read section. If a write is in
+ * progress, this waits for it to complete.
+ */
+static inline uint32_t
+seqlock_read_begin(const seqlock_t *lock)
+{
+	uint32_t seq;
+	unsigned backoff = 0;
+
+	ASSERT(lock != NULL);
+	while ((seq = lock->seq) & 1)
+		spin_backoff(&backoff);
+	memory_barrier();
+	return (seq);
+}
+
+/*
+ * Ends a read section. Returns B_TRUE if the data was modified during
+ * the read section, in which case the read must be retried.
+ */
+static inline bool_t
+seqlock_read_retry(const seqlock_t *lock, uint32_t seq)
+{
+	ASSERT(lock != NULL);
+	memory_barrier();
+	return (lock->seq != (int32_t)seq);
+}
+
+#define	SEQLOCK_READ(lock, dst, src) \
+	do { \
+		uint32_t __seq; \
+		do { \
+			__seq = seqlock_read_begin((lock)); \
+			memcpy((dst), (src), sizeof (*(dst))); \
+		} while (seqlock_read_retry((lock), __seq)); \
+	} while (0)
+
+#define	SEQLOCK_WRITE(lock, dst, src) \
+	do { \
+		seqlock_write_begin((lock)); \
+		memcpy((dst), (src), sizeof (*(dst))); \
+		seqlock_write_end((lock)); \
+	} while (0)
+
 #ifdef	DEBUG
 #define	ASSERT_MUTEX_HELD(mtx)		VERIFY_MUTEX_HELD(mtx)
 #define	ASSERT_MUTEX_NOT_HELD(mtx)	VERIFY_MUTEX_NOT_HELD(mtx)
@@ -69,6 +69,15 @@ nanoclock(void)
  */
 time_t lacf_timegm(const struct tm *tm);
 
+/*
+ * Formats a unixtime (with fractional seconds) as an ISO 8601 UTC date
+ * and time string with millisecond precision, e.g.
+ * "2024-03-01T12:34:56.789Z". Unlike gmtime & strftime, this is
+ * thread-safe. `buf' must be at least LACF_ISO8601_BUFSZ bytes long.
+ */
+#define	LACF_ISO8601_BUFSZ	32
+void lacf_fmt_iso8601(double utime, char buf[LACF_ISO8601_BUFSZ]);
+
 /*
  * Returns time in the system's real time clock as the number of microseconds
  * since UTC 1970-01-01 (unixtime). In essence, this is a microsecond-
@@ -0,0 +1,82 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_TRAFFIC_H_
+#define	_ACF_UTILS_TRAFFIC_H_
+
+#include <stdlib.h>
+
+#include "geom.h"
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Traffic target table with smoothing and extrapolation of sparse
+ * position reports. See traffic.c for usage info.
+ */
+
+#define	TRAFFIC_ID_LEN	16
+
+typedef struct {
+	char		id[TRAFFIC_ID_LEN];	/* unique, e.g. callsign */
+	geo_pos3_t	pos;			/* elevation in meters */
+	double		trk;			/* degrees true */
+	double		gs;			/* m/s */
+	double		vs;			/* m/s */
+	double		time;			/* seconds, see traffic.c */
+} traffic_report_t;
+
+typedef struct {
+	char		id[TRAFFIC_ID_LEN];
+	geo_pos3_t	pos;			/* elevation in meters */
+	double		trk;			/* degrees true */
+	double		gs;			/* m/s */
+	double		vs;			/* m/s */
+	double		age;			/* secs since last report */
+	bool_t		frozen;			/* extrapolation limit hit */
+} traffic_tgt_t;
+
+typedef struct traffic_s traffic_t;
+
+API_EXPORT traffic_t *traffic_alloc(void);
+API_EXPORT void traffic_free(traffic_t *traffic);
+
+API_EXPORT void traffic_set_smooth_time(traffic_t *traffic, double secs);
+API_EXPORT void traffic_set_max_extrap(traffic_t *traffic, double secs);
+API_EXPORT void traffic_set_max_age(traffic_t *traffic, double secs);
+
+API_EXPORT void traffic_report(traffic_t *traffic,
+    const traffic_report_t *report);
+API_EXPORT void traffic_remove(traffic_t *traffic, const char *id);
+API_EXPORT void traffic_update(traffic_t *traffic, double now);
+
+API_EXPORT size_t traffic_get_num_tgts(traffic_t *traffic);
+API_EXPORT size_t traffic_get_tgts(traffic_t *traffic, traffic_tgt_t *tgts,
+    size_t cap);
+API_EXPORT bool_t traffic_get_tgt(traffic_t *traffic, const char *id,
+    traffic_tgt_t *tgt);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_TRAFFIC_H_ */
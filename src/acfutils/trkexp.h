@@ -0,0 +1,59 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_TRKEXP_H_
+#define	_ACF_UTILS_TRKEXP_H_
+
+#include <stdlib.h>
+
+#include "geom.h"
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Flight path exporter. Records a position history and writes it out
+ * as GPX tracks and/or KML files. See trkexp.c for usage info.
+ */
+
+/* Output format masks for trkexp_alloc */
+#define	TRKEXP_FMT_GPX	(1 << 0)
+#define	TRKEXP_FMT_KML	(1 << 1)
+
+typedef struct trkexp_s trkexp_t;
+
+API_EXPORT trkexp_t *trkexp_alloc(const char *outdir, unsigned fmt_mask);
+API_EXPORT void trkexp_free(trkexp_t *trk);
+
+API_EXPORT void trkexp_add_pt(trkexp_t *trk, geo_pos3_t pos, double utime);
+API_EXPORT size_t trkexp_get_num_pts(const trkexp_t *trk);
+API_EXPORT void trkexp_end_flight(trkexp_t *trk, const char *name);
+
+API_EXPORT bool_t trkexp_write_gpx(const trkexp_t *trk, const char *filename,
+    const char *name);
+API_EXPORT bool_t trkexp_write_kml(const trkexp_t *trk, const char *filename,
+    const char *name);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_TRKEXP_H_ */
@@ -0,0 +1,78 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_WAKE_H_
+#define	_ACF_UTILS_WAKE_H_
+
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Wake turbulence categories and separation minima. See wake.c for
+ * usage info.
+ */
+
+/*
+ * ICAO Doc 8643 / Doc 4444 wake turbulence categories.
+ */
+typedef enum {
+	WAKE_CAT_UNKNOWN,
+	WAKE_CAT_L,		/* light, MTOW <= 7000 kg */
+	WAKE_CAT_M,		/* medium */
+	WAKE_CAT_H,		/* heavy, MTOW >= 136000 kg */
+	WAKE_CAT_J,		/* super (A380-800, An-225) */
+	NUM_WAKE_CATS
+} wake_cat_t;
+
+/*
+ * RECAT-EU wake turbulence categories.
+ */
+typedef enum {
+	RECAT_UNKNOWN,
+	RECAT_A,		/* super heavy */
+	RECAT_B,		/* upper heavy */
+	RECAT_C,		/* lower heavy */
+	RECAT_D,		/* upper medium */
+	RECAT_E,		/* lower medium */
+	RECAT_F,		/* light */
+	NUM_RECATS
+} recat_t;
+
+API_EXPORT wake_cat_t wake_cat_from_type(const char *icao_type);
+API_EXPORT wake_cat_t wake_cat_from_mtow(double mtow);
+API_EXPORT const char *wake_cat2str(wake_cat_t cat);
+
+API_EXPORT recat_t recat_from_type(const char *icao_type);
+API_EXPORT recat_t recat_from_mtow_span(double mtow, double span);
+API_EXPORT const char *recat2str(recat_t cat);
+
+API_EXPORT double wake_sep_dist(wake_cat_t lead, wake_cat_t follow);
+API_EXPORT double wake_dep_sep_time(wake_cat_t lead, wake_cat_t follow,
+    bool_t intersection);
+API_EXPORT double recat_sep_dist(recat_t lead, recat_t follow);
+API_EXPORT double recat_dep_sep_time(recat_t lead, recat_t follow);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_WAKE_H_ */
@@ -0,0 +1,58 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_WIND_H_
+#define	_ACF_UTILS_WIND_H_
+
+#include "geom.h"
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Layered wind model with gusts and turbulence. See wind.c for usage
+ * info.
+ */
+
+typedef struct {
+	double	alt;	/* layer altitude, meters AMSL */
+	double	dir;	/* direction the wind is blowing from, degrees true */
+	double	spd;	/* mean wind speed, m/s */
+	double	gust;	/* peak gust speed, m/s (0 or <= spd for no gusts) */
+	double	turb;	/* RMS turbulence intensity, m/s */
+} wind_layer_t;
+
+typedef struct wind_s wind_t;
+
+API_EXPORT wind_t *wind_alloc(uint64_t seed);
+API_EXPORT void wind_free(wind_t *wind);
+
+API_EXPORT void wind_set_layers(wind_t *wind, const wind_layer_t *layers,
+    size_t num_layers);
+API_EXPORT vect3_t wind_get_mean(const wind_t *wind, double alt);
+API_EXPORT vect3_t wind_sample(wind_t *wind, double alt, double agl,
+    double tas, double d_t);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_WIND_H_ */
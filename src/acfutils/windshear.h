@@ -0,0 +1,82 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_WINDSHEAR_H_
+#define	_ACF_UTILS_WINDSHEAR_H_
+
+#include "alerting.h"
+#include "geom.h"
+#include "sysmacros.h"
+#include "types.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Reactive & predictive windshear detection and escape guidance. See
+ * windshear.c for usage info.
+ */
+
+typedef enum {
+	WS_ALERT_NONE,
+	WS_ALERT_CAUTION,	/* increasing performance / shear ahead */
+	WS_ALERT_WARNING	/* decreasing performance / shear close ahead */
+} ws_alert_t;
+
+/*
+ * Predictive windshear gust field query. Given a distance ahead of the
+ * aircraft along its track in meters, the callback must fill in the
+ * wind at that point: `wind->x' is the headwind component (m/s, positive
+ * on the nose) and `wind->y' is the vertical wind (m/s, positive up).
+ * Return B_FALSE if no data is available for that point (e.g. out of
+ * radar range or no moisture to return echoes).
+ */
+typedef bool_t (*ws_gust_field_cb_t)(double dist, vect2_t *wind,
+    void *userinfo);
+
+typedef struct {
+	double		tas;		/* m/s */
+	double		headwind;	/* m/s, positive on the nose */
+	double		vert_wind;	/* m/s, positive up */
+	double		ra;		/* radio altitude, feet */
+	double		pitch;		/* degrees */
+	double		aoa;		/* degrees */
+	double		aoa_limit;	/* stick shaker AoA, degrees */
+	bool_t		on_ground;
+	bool_t		pws_on;		/* predictive radar scan enabled */
+} ws_inputs_t;
+
+typedef struct ws_s ws_t;
+
+API_EXPORT ws_t *ws_alloc(ws_gust_field_cb_t gust_cb, void *userinfo);
+API_EXPORT void ws_free(ws_t *ws);
+API_EXPORT void ws_update(ws_t *ws, const ws_inputs_t *in, double d_t);
+
+API_EXPORT double ws_get_ffactor(const ws_t *ws);
+API_EXPORT double ws_get_pred_ffactor(const ws_t *ws);
+API_EXPORT ws_alert_t ws_get_alert(const ws_t *ws, bool_t *predictive);
+API_EXPORT unsigned ws_get_alert_mask(const ws_t *ws);
+API_EXPORT bool_t ws_escape_is_active(const ws_t *ws);
+API_EXPORT double ws_get_escape_pitch(const ws_t *ws);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_WINDSHEAR_H_ */
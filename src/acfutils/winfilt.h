@@ -0,0 +1,55 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#ifndef	_ACF_UTILS_WINFILT_H_
+#define	_ACF_UTILS_WINFILT_H_
+
+#include <stdlib.h>
+
+#include "sysmacros.h"
+
+#ifdef	__cplusplus
+extern "C" {
+#endif
+
+/*
+ * Windowed moving average, RMS and median filters. See winfilt.c for
+ * usage info.
+ */
+
+typedef struct winfilt_s winfilt_t;
+
+API_EXPORT winfilt_t *winfilt_alloc(size_t cap, double max_age);
+API_EXPORT void winfilt_free(winfilt_t *filt);
+API_EXPORT void winfilt_reset(winfilt_t *filt);
+
+API_EXPORT void winfilt_push(winfilt_t *filt, double value, double now);
+API_EXPORT void winfilt_expire(winfilt_t *filt, double now);
+
+API_EXPORT size_t winfilt_get_num(const winfilt_t *filt);
+API_EXPORT double winfilt_get_avg(const winfilt_t *filt);
+API_EXPORT double winfilt_get_rms(const winfilt_t *filt);
+API_EXPORT double winfilt_get_median(const winfilt_t *filt);
+API_EXPORT double winfilt_get_min(const winfilt_t *filt);
+API_EXPORT double winfilt_get_max(const winfilt_t *filt);
+
+#ifdef	__cplusplus
+}
+#endif
+
+#endif	/* _ACF_UTILS_WINFILT_H_ */
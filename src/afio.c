@@ -0,0 +1,405 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <errno.h>
+#include <stddef.h>
+#include <stdio.h>
+#include <string.h>
+
+#include <acfutils/afio.h>
+#include <acfutils/assert.h>
+#include <acfutils/list.h>
+#include <acfutils/safe_alloc.h>
+#include <acfutils/taskq.h>
+#include <acfutils/thread.h>
+#include <acfutils/time.h>
+
+/* How long idle worker threads linger before exiting. */
+#define	THR_STOP_DELAY	SEC2USEC(5)
+
+typedef enum {
+	REQ_PENDING,
+	REQ_RUNNING,
+	REQ_DONE
+} req_state_t;
+
+struct afio_req_s {
+	afio_t		*afio;
+	char		*path;
+	bool_t		is_write;
+	afio_done_cb_t	done_cb;
+	void		*userinfo;
+
+	/* protected by afio->lock */
+	req_state_t	state;
+	bool_t		cancelled;
+	void		*buf;		/* read result or data to write */
+	size_t		len;
+	int		err;
+
+	list_node_t	node;
+};
+
+struct afio_s {
+	taskq_t		*tq;
+	mutex_t		lock;
+	list_t		reqs;		/* protected by lock */
+};
+
+static void *
+read_file(const char *path, size_t *len, int *err)
+{
+	FILE *fp = fopen(path, "rb");
+	long sz;
+	void *buf = NULL;
+
+	*len = 0;
+	if (fp == NULL) {
+		*err = errno;
+		return (NULL);
+	}
+	if (fseek(fp, 0, SEEK_END) != 0 || (sz = ftell(fp)) < 0 ||
+	    fseek(fp, 0, SEEK_SET) != 0) {
+		*err = errno;
+		goto out;
+	}
+	if (sz != 0) {
+		buf = safe_malloc(sz);
+		if (fread(buf, 1, sz, fp) != (size_t)sz) {
+			*err = (ferror(fp) ? errno : EIO);
+			free(buf);
+			buf = NULL;
+			goto out;
+		}
+	}
+	*len = sz;
+	*err = 0;
+out:
+	fclose(fp);
+	return (buf);
+}
+
+static int
+write_file(const char *path, const void *buf, size_t len)
+{
+	FILE *fp = fopen(path, "wb");
+	int err = 0;
+
+	if (fp == NULL)
+		return (errno);
+	if (len != 0 && fwrite(buf, 1, len, fp) != len)
+		err = errno;
+	if (fclose(fp) != 0 && err == 0)
+		err = errno;
+
+	return (err);
+}
+
+static void
+req_free(afio_req_t *req)
+{
+	free(req->path);
+	free(req->buf);
+	free(req);
+}
+
+static void
+proc_task(void *userinfo, void *thr_info, void *task)
+{
+	afio_t *afio = userinfo;
+	afio_req_t *req = task;
+	void *buf = NULL;
+	size_t len = 0;
+	int err;
+
+	UNUSED(thr_info);
+
+	mutex_enter(&afio->lock);
+	if (req->cancelled) {
+		req->state = REQ_DONE;
+		mutex_exit(&afio->lock);
+		return;
+	}
+	req->state = REQ_RUNNING;
+	mutex_exit(&afio->lock);
+	/*
+	 * The request's path and write buffer aren't modified by anybody
+	 * until the request is done, so we can access them unlocked.
+	 */
+	if (req->is_write)
+		err = write_file(req->path, req->buf, req->len);
+	else
+		buf = read_file(req->path, &len, &err);
+
+	mutex_enter(&afio->lock);
+	if (!req->is_write) {
+		req->buf = buf;
+		req->len = len;
+	}
+	req->err = err;
+	req->state = REQ_DONE;
+	mutex_exit(&afio->lock);
+}
+
+static void
+discard_task(void *userinfo, void *task)
+{
+	/* Requests are owned by the afio_t and freed in afio_free. */
+	UNUSED(userinfo);
+	UNUSED(task);
+}
+
+/*
+ * Creates an asynchronous file I/O engine. Reading or writing large files
+ * can easily take tens of milliseconds, which results in a visible stutter
+ * if done on the simulator's main thread. This engine instead performs
+ * the I/O on background worker threads and hands the results back to
+ * you on your own thread, when you call afio_poll.
+ *
+ * Typical usage:
+ *
+ *	afio_t *afio = afio_alloc(2);
+ *	...
+ *	req = afio_read(afio, path, read_done, my_state);
+ *	...in your flight loop callback:
+ *	afio_poll(afio);
+ *	...
+ *	static void
+ *	read_done(const char *path, void *buf, size_t len, int err,
+ *	    void *userinfo)
+ *	{
+ *		if (err != 0) {
+ *			logMsg("Error reading %s: %s", path, strerror(err));
+ *			return;
+ *		}
+ *		...use buf, then free it...
+ *		free(buf);
+ *	}
+ *
+ * All functions in this module, except afio_get_num_pending, must be
+ * called from the same thread (normally the simulator's main thread).
+ * The completion callbacks are also invoked on that thread, from inside
+ * of afio_poll.
+ *
+ * @param max_threads Maximum number of worker threads performing I/O
+ *	in parallel. Worker threads are only started when there is work
+ *	to do and exit after a few seconds of inactivity.
+ *
+ * @return The new I/O engine. Use afio_free to free it.
+ */
+afio_t *
+afio_alloc(unsigned max_threads)
+{
+	afio_t *afio = safe_calloc(1, sizeof (*afio));
+
+	ASSERT(max_threads != 0);
+	mutex_init(&afio->lock);
+	list_create(&afio->reqs, sizeof (afio_req_t),
+	    offsetof(afio_req_t, node));
+	afio->tq = taskq_alloc(0, max_threads, THR_STOP_DELAY, NULL, NULL,
+	    proc_task, discard_task, afio);
+
+	return (afio);
+}
+
+/*
+ * Frees an I/O engine. Requests which haven't started yet are cancelled,
+ * while requests which are in progress are allowed to finish. None of
+ * the completion callbacks of undelivered requests are called.
+ */
+void
+afio_free(afio_t *afio)
+{
+	afio_req_t *req;
+
+	if (afio == NULL)
+		return;
+	mutex_enter(&afio->lock);
+	for (req = list_head(&afio->reqs); req != NULL;
+	    req = list_next(&afio->reqs, req))
+		req->cancelled = B_TRUE;
+	mutex_exit(&afio->lock);
+	/* waits for any in-progress I/O to finish */
+	taskq_free(afio->tq);
+
+	while ((req = list_remove_head(&afio->reqs)) != NULL)
+		req_free(req);
+	list_destroy(&afio->reqs);
+	mutex_destroy(&afio->lock);
+	free(afio);
+}
+
+static afio_req_t *
+req_submit(afio_t *afio, const char *path, bool_t is_write, void *buf,
+    size_t len, afio_done_cb_t done_cb, void *userinfo)
+{
+	afio_req_t *req = safe_calloc(1, sizeof (*req));
+
+	ASSERT(afio != NULL);
+	ASSERT(path != NULL);
+	ASSERT(done_cb != NULL);
+
+	req->afio = afio;
+	req->path = safe_strdup(path);
+	req->is_write = is_write;
+	req->buf = buf;
+	req->len = len;
+	req->done_cb = done_cb;
+	req->userinfo = userinfo;
+	req->state = REQ_PENDING;
+
+	mutex_enter(&afio->lock);
+	list_insert_tail(&afio->reqs, req);
+	mutex_exit(&afio->lock);
+	taskq_submit(afio->tq, req);
+
+	return (req);
+}
+
+/*
+ * Starts reading a whole file in the background. Once the read is done,
+ * the next call to afio_poll invokes `done_cb' with:
+ *
+ *	- `path': the path of the file.
+ *	- `buf' and `len': the file's contents. The buffer becomes owned by
+ *	  the callback, which must free it using free() (or lacf_free if
+ *	  you are using libacfutils as a DLL). For an empty file, `buf' is
+ *	  NULL and `len' is 0.
+ *	- `err': 0 on success, or an errno value describing the failure,
+ *	  in which case `buf' is NULL.
+ *	- `userinfo': the `userinfo' passed here.
+ *
+ * @return A request handle, which you can pass to afio_cancel, until the
+ *	completion callback is invoked.
+ */
+afio_req_t *
+afio_read(afio_t *afio, const char *path, afio_done_cb_t done_cb,
+    void *userinfo)
+{
+	return (req_submit(afio, path, B_FALSE, NULL, 0, done_cb, userinfo));
+}
+
+/*
+ * Starts writing a whole file in the background, replacing its previous
+ * contents. The data in `buf' is copied, so you can reuse the buffer as
+ * soon as this function returns. Once the write is done, the next call
+ * to afio_poll invokes `done_cb' with `buf' set to NULL, `len' set to 0
+ * and `err' set to 0 on success, or an errno value on failure.
+ *
+ * @return A request handle, which you can pass to afio_cancel, until the
+ *	completion callback is invoked.
+ */
+afio_req_t *
+afio_write(afio_t *afio, const char *path, const void *buf, size_t len,
+    afio_done_cb_t done_cb, void *userinfo)
+{
+	void *copy = NULL;
+
+	ASSERT(buf != NULL || len == 0);
+	if (len != 0) {
+		copy = safe_malloc(len);
+		memcpy(copy, buf, len);
+	}
+	return (req_submit(afio, path, B_TRUE, copy, len, done_cb, userinfo));
+}
+
+/*
+ * Cancels a request. Once this function returns, the request's completion
+ * callback is guaranteed never to be called and the request handle is no
+ * longer valid. If the I/O hasn't started yet, it never will. A read
+ * which is already in progress runs to completion, but its result is
+ * discarded. A write which is already in progress also runs to
+ * completion, so you can't rely on cancellation to keep a file intact.
+ *
+ * You mustn't cancel a request whose completion callback has already
+ * been invoked.
+ */
+void
+afio_cancel(afio_t *afio, afio_req_t *req)
+{
+	ASSERT(afio != NULL);
+	ASSERT(req != NULL);
+	ASSERT3P(req->afio, ==, afio);
+
+	mutex_enter(&afio->lock);
+	ASSERT(!req->cancelled);
+	req->cancelled = B_TRUE;
+	mutex_exit(&afio->lock);
+}
+
+/*
+ * Delivers the results of finished requests by invoking their completion
+ * callbacks and disposes of finished cancelled requests. Call this
+ * periodically, e.g. from a flight loop callback. The callbacks may
+ * submit new requests.
+ *
+ * @return The number of completion callbacks invoked.
+ */
+unsigned
+afio_poll(afio_t *afio)
+{
+	list_t done;
+	afio_req_t *req, *next_req;
+	unsigned n = 0;
+
+	ASSERT(afio != NULL);
+
+	list_create(&done, sizeof (afio_req_t), offsetof(afio_req_t, node));
+	mutex_enter(&afio->lock);
+	for (req = list_head(&afio->reqs); req != NULL; req = next_req) {
+		next_req = list_next(&afio->reqs, req);
+		if (req->state == REQ_DONE) {
+			list_remove(&afio->reqs, req);
+			list_insert_tail(&done, req);
+		}
+	}
+	mutex_exit(&afio->lock);
+	/* Callbacks are invoked unlocked, so they can submit new requests */
+	while ((req = list_remove_head(&done)) != NULL) {
+		if (!req->cancelled) {
+			void *buf = (req->is_write ? NULL : req->buf);
+
+			if (!req->is_write)
+				req->buf = NULL;
+			req->done_cb(req->path, buf, (req->is_write ? 0 :
+			    req->len), req->err, req->userinfo);
+			n++;
+		}
+		req_free(req);
+	}
+	list_destroy(&done);
+
+	return (n);
+}
+
+/*
+ * Returns the number of requests which haven't been disposed of by
+ * afio_poll yet. This is safe to call from any thread.
+ */
+unsigned
+afio_get_num_pending(afio_t *afio)
+{
+	unsigned n;
+
+	ASSERT(afio != NULL);
+	mutex_enter(&afio->lock);
+	n = list_count(&afio->reqs);
+	mutex_exit(&afio->lock);
+
+	return (n);
+}
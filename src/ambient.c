@@ -0,0 +1,225 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <math.h>
+#include <string.h>
+
+#include <acfutils/ambient.h>
+#include <acfutils/assert.h>
+#include <acfutils/helpers.h>
+#include <acfutils/math.h>
+
+#define	SOLAR_LUX	128000.0	/* extraterrestrial solar illuminance */
+#define	EXTINCTION	0.21		/* clear sky extinction coefficient */
+#define	DIFFUSE_LUX	15000.0		/* clear sky diffuse, sun overhead */
+#define	TWILIGHT_LUX	400.0		/* diffuse sky light at sunset */
+#define	NIGHT_LUX	0.01		/* moonless night sky */
+#define	DFL_WINDOW_TRANS	0.3
+#define	LOG_LUX_DIM	0.0		/* log10 lux for min brightness */
+#define	LOG_LUX_BRIGHT	4.5		/* log10 lux for max brightness */
+
+/*
+ * Computes the sun's position in the sky using the low precision
+ * algorithm from the Astronomical Almanac. This is accurate to about
+ * 0.01 degrees between 1950 and 2050, which is far more than is needed
+ * for lighting purposes.
+ *
+ * @param t Time as a UNIX timestamp (UTC).
+ * @param pos Observer position.
+ * @param elev Filled with the sun's elevation above the horizon in
+ *	degrees (negative when the sun is below the horizon).
+ * @param azi Filled with the sun's true azimuth in degrees.
+ */
+void
+ambient_sun_pos(time_t t, geo_pos2_t pos, double *elev, double *azi)
+{
+	/* days since J2000.0 (2000-01-01 12:00 UTC) */
+	double d = t / 86400.0 - 10957.5;
+	double g = DEG2RAD(357.529 + 0.98560028 * d);
+	double q = 280.459 + 0.98564736 * d;
+	double L = DEG2RAD(q + 1.915 * sin(g) + 0.020 * sin(2 * g));
+	double e = DEG2RAD(23.439 - 0.00000036 * d);
+	double ra = atan2(cos(e) * sin(L), cos(L));
+	double dec = asin(sin(e) * sin(L));
+	double gmst = 280.46061837 + 360.98564736629 * d;
+	double H = DEG2RAD(gmst + pos.lon) - ra;
+	double lat = DEG2RAD(pos.lat);
+
+	ASSERT(elev != NULL);
+	ASSERT(azi != NULL);
+
+	*elev = RAD2DEG(asin(clamp(sin(lat) * sin(dec) +
+	    cos(lat) * cos(dec) * cos(H), -1, 1)));
+	*azi = normalize_hdg(RAD2DEG(atan2(-sin(H),
+	    tan(dec) * cos(lat) - sin(lat) * cos(H))));
+}
+
+/*
+ * Splits daylight into the direct sunlight illuminance (on a surface
+ * facing the sun) and diffuse sky light illuminance.
+ */
+static void
+light_comps(double sun_elev, double cloud, double *direct, double *diffuse)
+{
+	double s = sin(DEG2RAD(sun_elev));
+
+	cloud = clamp(cloud, 0, 1);
+	if (sun_elev > 0) {
+		/* Air mass grows as the sun sinks, attenuating the beam */
+		*direct = SOLAR_LUX * exp(-EXTINCTION / MAX(s, 0.02));
+		*diffuse = MAX(DIFFUSE_LUX * sqrt(s), TWILIGHT_LUX);
+	} else {
+		/*
+		 * During twilight, sky light falls off roughly one order of
+		 * magnitude for every 2.5 degrees of sun depression.
+		 */
+		*direct = 0;
+		*diffuse = MAX(TWILIGHT_LUX * pow(10, sun_elev / 2.5),
+		    NIGHT_LUX);
+	}
+	/* Clouds block the sun's disc, but scatter some of it back in */
+	*direct *= 1 - cloud;
+	*diffuse *= 1 - 0.5 * cloud;
+}
+
+/*
+ * Estimates the outside illuminance on a horizontal surface.
+ *
+ * @param sun_elev Sun elevation in degrees (see ambient_sun_pos).
+ * @param cloud Cloud cover fraction, from 0 (clear) to 1 (overcast).
+ *
+ * @return The illuminance in lux. This ranges from about 100000 lux in
+ *	clear skies with the sun high up, through a few hundred lux at
+ *	sunset, down to 0.01 lux at night.
+ */
+double
+ambient_outside_lux(double sun_elev, double cloud)
+{
+	double direct, diffuse;
+
+	light_comps(sun_elev, cloud, &direct, &diffuse);
+	return (direct * MAX(sin(DEG2RAD(sun_elev)), 0) + diffuse);
+}
+
+/*
+ * Initializes a cockpit ambient light estimator. The estimator combines
+ * the sun's position, the aircraft's attitude and the cloud cover into
+ * an estimate of the light level in the cockpit, together with how much
+ * the sun is shining into the pilots' eyes. Both outputs are smoothed,
+ * so that the display brightness derived from them doesn't jump around
+ * as the aircraft maneuvers.
+ *
+ * Typical usage:
+ *
+ *	ambient_t amb;
+ *	ambient_init(&amb, 2);
+ *	...on every frame:
+ *	ambient_sun_pos(time(NULL), acf_pos, &sun_elev, &sun_azi);
+ *	ambient_update(&amb, sun_elev, sun_azi,
+ *	    EULER(pitch, roll, hdg), cloud_cover, d_t);
+ *	...set your display brightness to:
+ *	ambient_brightness(&amb, 0.05, 1.0);
+ *
+ * The fraction of the outside light which reaches the cockpit is given
+ * by the `window_trans' field and defaults to 0.3. You can change it
+ * after calling ambient_init to suit your cockpit.
+ *
+ * @param lag How long (in seconds) the smoothed outputs take to follow a
+ *	change in lighting conditions.
+ */
+void
+ambient_init(ambient_t *amb, double lag)
+{
+	ASSERT(amb != NULL);
+	ASSERT3F(lag, >, 0);
+	memset(amb, 0, sizeof (*amb));
+	amb->lag = lag;
+	amb->window_trans = DFL_WINDOW_TRANS;
+	amb->lux = NAN;
+	amb->glare = NAN;
+	amb->log_lux = NAN;
+}
+
+/*
+ * Updates the ambient light estimate. The first update sets the outputs
+ * directly, subsequent updates smooth them.
+ *
+ * @param sun_elev Sun elevation in degrees (see ambient_sun_pos).
+ * @param sun_azi Sun true azimuth in degrees.
+ * @param att Aircraft attitude.
+ * @param cloud Cloud cover fraction, from 0 (clear) to 1 (overcast).
+ * @param d_t Time elapsed since the previous update in seconds.
+ */
+void
+ambient_update(ambient_t *amb, double sun_elev, double sun_azi, euler_t att,
+    double cloud, double d_t)
+{
+	double direct, diffuse, el = DEG2RAD(sun_elev), az = DEG2RAD(sun_azi);
+	double exposure, glare, lux;
+	vect3_t sun;
+
+	ASSERT(amb != NULL);
+	ASSERT3F(d_t, >=, 0);
+
+	light_comps(sun_elev, cloud, &direct, &diffuse);
+	/* sun direction in the NED frame, transformed into the body frame */
+	sun = quat_rot_inv(quat_from_euler(att),
+	    VECT3(cos(el) * cos(az), cos(el) * sin(az), -sin(el)));
+	/*
+	 * Cockpit windows mostly face forward and to the sides, while the
+	 * roof and floor block the sun. This is a rough heuristic, but it
+	 * captures the sun flooding the cockpit as we turn towards it.
+	 */
+	exposure = MAX(0.7 * sun.x + 0.4 * fabs(sun.y), 0) *
+	    clamp(1 - sun.z / 0.25, 0, 1);
+	/* glare grows sharply as the sun approaches the nose */
+	glare = pow(MAX(sun.x, 0), 8) * clamp(1 - sun.z / 0.25, 0, 1) *
+	    MIN(direct / (SOLAR_LUX * exp(-EXTINCTION)), 1);
+	lux = amb->window_trans * (diffuse + direct * exposure);
+
+	if (d_t > 0) {
+		FILTER_IN_NAN(amb->log_lux, log10(lux), d_t, amb->lag);
+		FILTER_IN_NAN(amb->glare, glare, d_t, amb->lag);
+	} else if (isnan(amb->log_lux)) {
+		amb->log_lux = log10(lux);
+		amb->glare = glare;
+	}
+	amb->lux = pow(10, amb->log_lux);
+}
+
+/*
+ * Maps the current ambient light estimate to a display brightness. The
+ * mapping is logarithmic (the way our eyes perceive light), reaching
+ * `min_bright' in a cockpit darker than 1 lux and `max_bright' in about
+ * 30000 lux. Sun glare pushes the brightness up, so that the displays
+ * remain legible with the sun in the pilots' eyes. Before the first
+ * ambient_update, this returns `max_bright'.
+ */
+double
+ambient_brightness(const ambient_t *amb, double min_bright, double max_bright)
+{
+	double fract;
+
+	ASSERT(amb != NULL);
+	if (isnan(amb->log_lux))
+		return (max_bright);
+	fract = iter_fract(amb->log_lux, LOG_LUX_DIM, LOG_LUX_BRIGHT, B_TRUE);
+	fract = MAX(fract, amb->glare);
+
+	return (wavg(min_bright, max_bright, fract));
+}
@@ -0,0 +1,383 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <math.h>
+
+#include <acfutils/apu.h>
+#include <acfutils/assert.h>
+#include <acfutils/math.h>
+#include <acfutils/safe_alloc.h>
+
+#define	LIGHTOFF_N		7	/* percent */
+#define	LIGHTOFF_TIMEOUT	20	/* seconds */
+#define	STARTER_RATE		3	/* percent/sec */
+#define	STARTER_MAX_N		25	/* max speed on the starter alone */
+#define	STARTER_CUTOUT_N	50	/* percent */
+#define	START_ACCEL		2.5	/* percent/sec */
+#define	AVAIL_N			95	/* percent */
+#define	AVAIL_DELAY		2	/* seconds */
+#define	RUN_N_LAG		1	/* seconds */
+#define	SPOOL_LAG		12	/* seconds */
+#define	COOLDOWN_TIME		60	/* seconds */
+#define	IDLE_EGT		405	/* degrees C above OAT */
+#define	BLEED_EGT		150	/* extra EGT at full bleed load */
+#define	ELEC_EGT		60	/* extra EGT at full electrical load */
+#define	OVERTEMP_FAIL_EGT	400	/* extra EGT with fail_overtemp */
+#define	START_EGT_LIM		980	/* degrees C */
+#define	RUN_EGT_LIM		720	/* degrees C */
+#define	EGT_LAG			1.5	/* seconds */
+#define	EGT_COOL_LAG		30	/* seconds */
+#define	IDLE_FF			0.02	/* kg/s */
+#define	BLEED_FF		0.025	/* kg/s at full bleed load */
+#define	ELEC_FF			0.01	/* kg/s at full electrical load */
+#define	GEN_FREQ		400	/* Hz */
+
+/* EGT above OAT vs N during a start, producing the start EGT spike */
+static const vect2_t start_egt_curve[] = {
+    { 0, 0 },
+    { LIGHTOFF_N, 100 },
+    { 15, 450 },
+    { 30, 720 },
+    { 45, 680 },
+    { 70, 520 },
+    { AVAIL_N, IDLE_EGT },
+    { 100, IDLE_EGT },
+    NULL_VECT2
+};
+
+struct apu_s {
+	apu_state_t	state;
+	apu_fault_t	fault;
+	bool_t		lit;
+	double		n;
+	double		egt;
+	double		ff;
+	double		start_time;
+	double		avail_time;
+	double		cooldown_rem;
+	bool_t		gen_fail;
+};
+
+/*
+ * Creates a new APU model. The APU starts out shut down and cold.
+ *
+ * Typical usage:
+ *
+ *	apu_t *apu = apu_alloc();
+ *	apu_inputs_t in = {};
+ *	...on every frame, fill in `in' and then:
+ *	apu_update(apu, &in, d_t);
+ *	bleed_in.apu_avail = apu_bleed_is_avail(apu);
+ *	...connect the APU generator to your electrical system when
+ *	apu_gen_is_avail returns B_TRUE and burn apu_get_ff(apu) * d_t
+ *	kilograms of fuel.
+ *
+ * The start sequence begins when the `start' input is set while the
+ * master switch is on, DC power is available and no fault is present.
+ * The starter cranks the APU up to light-off at 7% N, after which EGT
+ * spikes to around 700 degrees C above OAT and then settles as the
+ * APU accelerates. The starter cuts out at 50% N. The APU becomes
+ * available 2 seconds after reaching 95% N. A start taking longer than
+ * 20 seconds to light off is aborted with APU_FAULT_NO_LIGHTOFF.
+ *
+ * Switching off the master switch after bleed air has been used in the
+ * last 60 seconds first runs the APU unloaded for a cooldown period
+ * before shutting it down. Switching the master back on during the
+ * cooldown cancels the shutdown.
+ *
+ * The APU shuts down automatically, without cooldown, on fire, fuel
+ * supply loss or EGT overtemperature. The fault is latched until the
+ * master switch is turned off.
+ */
+apu_t *
+apu_alloc(void)
+{
+	apu_t *apu = safe_calloc(1, sizeof (*apu));
+
+	apu->state = APU_STATE_OFF;
+	apu->egt = NAN;
+
+	return (apu);
+}
+
+void
+apu_free(apu_t *apu)
+{
+	free(apu);
+}
+
+static void
+apu_shutdown(apu_t *apu, apu_fault_t fault)
+{
+	if (fault != APU_FAULT_NONE)
+		apu->fault = fault;
+	apu->state = APU_STATE_SPOOLDOWN;
+	apu->lit = B_FALSE;
+	apu->cooldown_rem = 0;
+}
+
+static void
+update_state(apu_t *apu, const apu_inputs_t *in, double d_t)
+{
+	if (!in->master)
+		apu->fault = APU_FAULT_NONE;
+	if (apu->lit) {
+		double lim = (apu->state == APU_STATE_STARTING ?
+		    START_EGT_LIM : RUN_EGT_LIM);
+
+		if (in->fire) {
+			apu_shutdown(apu, APU_FAULT_FIRE);
+			return;
+		}
+		if (!in->fuel_avail) {
+			apu_shutdown(apu, APU_FAULT_FUEL);
+			return;
+		}
+		if (apu->egt > lim) {
+			apu_shutdown(apu, APU_FAULT_OVERTEMP);
+			return;
+		}
+	}
+
+	switch (apu->state) {
+	case APU_STATE_OFF:
+	case APU_STATE_SPOOLDOWN:
+		if (in->master && in->start && in->dc_power && !in->fire &&
+		    apu->fault == APU_FAULT_NONE && apu->n < LIGHTOFF_N) {
+			apu->state = APU_STATE_STARTING;
+			apu->start_time = 0;
+		}
+		break;
+	case APU_STATE_STARTING:
+		if (!in->master) {
+			apu_shutdown(apu, APU_FAULT_NONE);
+			break;
+		}
+		apu->start_time += d_t;
+		if (!apu->lit && apu->n >= LIGHTOFF_N && in->fuel_avail &&
+		    !in->fail_start)
+			apu->lit = B_TRUE;
+		if (!apu->lit && apu->start_time > LIGHTOFF_TIMEOUT) {
+			apu_shutdown(apu, APU_FAULT_NO_LIGHTOFF);
+			break;
+		}
+		if (apu->n >= AVAIL_N) {
+			apu->state = APU_STATE_RUNNING;
+			apu->avail_time = 0;
+		}
+		break;
+	case APU_STATE_RUNNING:
+		apu->avail_time += d_t;
+		if (in->bleed_load > 0 && apu_bleed_is_avail(apu))
+			apu->cooldown_rem = COOLDOWN_TIME;
+		else
+			apu->cooldown_rem = MAX(apu->cooldown_rem - d_t, 0);
+		if (!in->master) {
+			if (apu->cooldown_rem > 0)
+				apu->state = APU_STATE_COOLDOWN;
+			else
+				apu_shutdown(apu, APU_FAULT_NONE);
+		}
+		break;
+	case APU_STATE_COOLDOWN:
+		apu->avail_time += d_t;
+		apu->cooldown_rem -= d_t;
+		if (in->master)
+			apu->state = APU_STATE_RUNNING;
+		else if (apu->cooldown_rem <= 0)
+			apu_shutdown(apu, APU_FAULT_NONE);
+		break;
+	}
+}
+
+static void
+update_n(apu_t *apu, const apu_inputs_t *in, double d_t)
+{
+	switch (apu->state) {
+	case APU_STATE_STARTING:
+		if (apu->lit) {
+			apu->n += START_ACCEL * clamp((100 - apu->n) / 40,
+			    0.3, 1) * d_t;
+		} else if (in->dc_power && apu->n < STARTER_MAX_N) {
+			apu->n = MIN(apu->n + STARTER_RATE * d_t,
+			    STARTER_MAX_N);
+		}
+		apu->n = MIN(apu->n, 100);
+		break;
+	case APU_STATE_RUNNING:
+	case APU_STATE_COOLDOWN:
+		FILTER_IN(apu->n, 100.0, d_t, RUN_N_LAG);
+		break;
+	case APU_STATE_OFF:
+	case APU_STATE_SPOOLDOWN:
+		apu->n = MAX(apu->n - apu->n * d_t / SPOOL_LAG, 0);
+		if (apu->state == APU_STATE_SPOOLDOWN && apu->n < 1) {
+			apu->n = 0;
+			apu->state = APU_STATE_OFF;
+		}
+		break;
+	}
+}
+
+static void
+update_egt_ff(apu_t *apu, const apu_inputs_t *in, double d_t)
+{
+	double bleed_load = clamp(in->bleed_load, 0, 1);
+	double elec_load = clamp(in->elec_load, 0, 1);
+
+	if (isnan(apu->egt))
+		apu->egt = in->oat;
+	if (!apu->lit) {
+		FILTER_IN(apu->egt, in->oat, d_t, EGT_COOL_LAG);
+		apu->ff = 0;
+		return;
+	}
+	if (apu->state == APU_STATE_STARTING) {
+		double tgt = in->oat + fx_lin_multi(apu->n, start_egt_curve,
+		    B_FALSE);
+
+		if (in->fail_overtemp)
+			tgt += OVERTEMP_FAIL_EGT;
+		FILTER_IN(apu->egt, tgt, d_t, EGT_LAG);
+		apu->ff = IDLE_FF * apu->n / 100;
+	} else {
+		double tgt = in->oat + IDLE_EGT;
+
+		/* during cooldown, the APU runs unloaded */
+		if (apu->state == APU_STATE_RUNNING) {
+			tgt += BLEED_EGT * bleed_load + ELEC_EGT * elec_load;
+			apu->ff = IDLE_FF + BLEED_FF * bleed_load +
+			    ELEC_FF * elec_load;
+		} else {
+			apu->ff = IDLE_FF;
+		}
+		if (in->fail_overtemp)
+			tgt += OVERTEMP_FAIL_EGT;
+		FILTER_IN(apu->egt, tgt, d_t, EGT_LAG);
+	}
+}
+
+/*
+ * Updates the APU state.
+ *
+ * @param in Current APU inputs (switches, environment and failures).
+ * @param d_t Time elapsed since the previous update in seconds.
+ */
+void
+apu_update(apu_t *apu, const apu_inputs_t *in, double d_t)
+{
+	ASSERT(apu != NULL);
+	ASSERT(in != NULL);
+	ASSERT(!isnan(in->oat));
+	ASSERT3F(d_t, >=, 0);
+
+	update_state(apu, in, d_t);
+	update_n(apu, in, d_t);
+	update_egt_ff(apu, in, d_t);
+	apu->gen_fail = in->fail_gen;
+}
+
+apu_state_t
+apu_get_state(const apu_t *apu)
+{
+	ASSERT(apu != NULL);
+	return (apu->state);
+}
+
+/*
+ * Returns the latched fault which caused the last automatic shutdown,
+ * or APU_FAULT_NONE.
+ */
+apu_fault_t
+apu_get_fault(const apu_t *apu)
+{
+	ASSERT(apu != NULL);
+	return (apu->fault);
+}
+
+/*
+ * Returns the APU rotational speed in percent.
+ */
+double
+apu_get_n(const apu_t *apu)
+{
+	ASSERT(apu != NULL);
+	return (apu->n);
+}
+
+/*
+ * Returns the APU exhaust gas temperature in degrees C, or NAN before
+ * the first call to apu_update.
+ */
+double
+apu_get_egt(const apu_t *apu)
+{
+	ASSERT(apu != NULL);
+	return (apu->egt);
+}
+
+/*
+ * Returns the APU fuel flow in kg/s.
+ */
+double
+apu_get_ff(const apu_t *apu)
+{
+	ASSERT(apu != NULL);
+	return (apu->ff);
+}
+
+/*
+ * Returns B_TRUE if the APU is running at speed and can supply power.
+ */
+bool_t
+apu_is_avail(const apu_t *apu)
+{
+	ASSERT(apu != NULL);
+	return ((apu->state == APU_STATE_RUNNING ||
+	    apu->state == APU_STATE_COOLDOWN) &&
+	    apu->avail_time >= AVAIL_DELAY);
+}
+
+/*
+ * Returns B_TRUE if the APU can supply bleed air. Bleed air isn't
+ * available during the cooldown before a shutdown.
+ */
+bool_t
+apu_bleed_is_avail(const apu_t *apu)
+{
+	return (apu_is_avail(apu) && apu->state == APU_STATE_RUNNING);
+}
+
+bool_t
+apu_gen_is_avail(const apu_t *apu)
+{
+	return (apu_is_avail(apu) && !apu->gen_fail);
+}
+
+/*
+ * Returns the APU generator output frequency in Hz, or 0 if the
+ * generator has failed.
+ */
+double
+apu_get_gen_freq(const apu_t *apu)
+{
+	ASSERT(apu != NULL);
+	if (apu->gen_fail)
+		return (0);
+	return (GEN_FREQ * apu->n / 100);
+}
@@ -0,0 +1,592 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <errno.h>
+#include <math.h>
+#include <stdio.h>
+#include <string.h>
+
+#include <acfutils/arinc717.h>
+#include <acfutils/assert.h>
+#include <acfutils/helpers.h>
+#include <acfutils/log.h>
+#include <acfutils/math_core.h>
+#include <acfutils/safe_alloc.h>
+
+/*
+ * ARINC 717 is the standard data frame format used by digital flight data
+ * acquisition units to feed flight data recorders. A frame consists of
+ * 4 subframes, each 1 second long. Each subframe consists of a fixed
+ * number of 12-bit words (the "words per second", or WPS, typically 64,
+ * 128, 256, 512 or 1024). The first word of each subframe is a sync word,
+ * which identifies the subframe number. The remaining words carry the
+ * recorded parameters, according to a data frame layout which assigns
+ * each parameter to a particular word (or a range of bits within a word)
+ * in one or more subframes.
+ *
+ * This encoder lets you declare such a layout, set the current values of
+ * the parameters and emit subframes in a format which off-the-shelf
+ * flight data analysis software can ingest:
+ *
+ * 1) Allocate the encoder using arinc717_alloc, passing it the WPS rate.
+ * 2) Register your parameters using arinc717_param_add. This lets you
+ *	define the parameter's word slot, bit field and encoding.
+ * 3) Open an output file using arinc717_open.
+ * 4) Each simulator frame, update parameter values using
+ *	arinc717_param_set.
+ * 5) Once per second, call arinc717_write_subframe. This samples the
+ *	current parameter values, encodes them into the next subframe in
+ *	sequence and writes it out.
+ * 6) When done, call arinc717_close and arinc717_free.
+ *
+ * If you would rather handle output yourself, you can call
+ * arinc717_encode_subframe to just encode the data into a buffer of
+ * words and use arinc717_decode to reverse the encoding.
+ *
+ * The encoder performs no locking. If you update parameter values from
+ * a different thread than the one writing subframes, you must provide
+ * your own serialization.
+ */
+
+typedef struct {
+	char		name[32];
+	unsigned	sf_mask;
+	unsigned	word;		/* 1-based, word 1 is sync */
+	unsigned	lsb_bit;	/* 1-based, bit 1 is the word LSB */
+	unsigned	num_bits;
+	arinc717_enc_t	encoding;
+	double		resolution;
+	double		offset;
+	double		value;
+} param_t;
+
+struct arinc717_s {
+	unsigned	wps;
+	/* bitmask of bits already used, per subframe & word */
+	uint16_t	*used[ARINC717_NUM_SUBFRAMES];
+
+	param_t		*params;
+	unsigned	num_params;
+
+	FILE		*fp;
+	arinc717_out_t	fmt;
+	unsigned	cur_sf;
+	uint64_t	frame_nr;
+	/* leftover half-byte for ARINC717_OUT_PACKED12 */
+	bool_t		have_nibble;
+	uint8_t		nibble;
+};
+
+static const uint16_t sync_words[ARINC717_NUM_SUBFRAMES] = {
+    ARINC717_SYNC1, ARINC717_SYNC2, ARINC717_SYNC3, ARINC717_SYNC4
+};
+
+/*
+ * Allocates a new ARINC 717 encoder.
+ *
+ * @param wps Words-per-second rate of the data frame. Must be a power of
+ *	two between 32 and 8192 inclusive.
+ *
+ * @return The new encoder. Use arinc717_free to release it.
+ */
+arinc717_t *
+arinc717_alloc(unsigned wps)
+{
+	arinc717_t *enc = safe_calloc(1, sizeof (*enc));
+
+	ASSERT3U(wps, >=, 32);
+	ASSERT3U(wps, <=, 8192);
+	ASSERT0(wps & (wps - 1));
+
+	enc->wps = wps;
+	for (int i = 0; i < ARINC717_NUM_SUBFRAMES; i++)
+		enc->used[i] = safe_calloc(wps, sizeof (*enc->used[i]));
+
+	return (enc);
+}
+
+void
+arinc717_free(arinc717_t *enc)
+{
+	if (enc == NULL)
+		return;
+	arinc717_close(enc);
+	for (int i = 0; i < ARINC717_NUM_SUBFRAMES; i++)
+		free(enc->used[i]);
+	free(enc->params);
+	free(enc);
+}
+
+/*
+ * Registers a new parameter in the data frame layout.
+ *
+ * @param enc The encoder to add the parameter to.
+ * @param name Name of the parameter (used as the column header in CSV
+ *	output and for lookups using arinc717_param_find).
+ * @param sf_mask Bitmask of subframes in which the parameter is recorded
+ *	(ARINC717_SF1 through ARINC717_SF4, or ARINC717_SF_ALL). Parameters
+ *	recorded in all subframes are sampled once per second, parameters
+ *	recorded in only one subframe are sampled once every 4 seconds.
+ * @param word Word slot within the subframe (1-based). Word 1 is always
+ *	occupied by the sync word, so this must be between 2 and the WPS
+ *	rate of the encoder. To sample a parameter more often than once a
+ *	second, register it multiple times in different word slots.
+ * @param lsb_bit Position of the least significant bit of the parameter
+ *	in the word, using ARINC numbering (1 = LSB, 12 = MSB).
+ * @param num_bits Number of bits occupied by the parameter. The field
+ *	must fit within the 12 bits of the word.
+ * @param encoding How the value is encoded:
+ *	- ARINC717_ENC_BNR: two's complement binary. The most significant
+ *	  bit of the field is the sign bit.
+ *	- ARINC717_ENC_UNSIGNED: unsigned binary.
+ *	- ARINC717_ENC_BCD: binary coded decimal, 4 bits per decimal digit.
+ *	  The most significant digit may be shorter than 4 bits.
+ *	- ARINC717_ENC_DISCRETE: a single bit, set if the value is non-zero.
+ *	  `num_bits' must be 1.
+ * @param resolution Engineering value of one count of the least
+ *	significant bit (e.g. 0.25 for a parameter with 0.25 kt resolution).
+ *	Ignored for discretes.
+ * @param offset Engineering value corresponding to a raw value of 0.
+ *	Ignored for discretes.
+ *
+ * @return The index of the new parameter (for use in arinc717_param_set),
+ *	or -1 if the parameter's bit field overlaps another parameter or
+ *	otherwise doesn't fit into the frame layout. An error message is
+ *	logged in that case.
+ */
+int
+arinc717_param_add(arinc717_t *enc, const char *name, unsigned sf_mask,
+    unsigned word, unsigned lsb_bit, unsigned num_bits,
+    arinc717_enc_t encoding, double resolution, double offset)
+{
+	param_t *param;
+	uint16_t mask;
+
+	ASSERT(enc != NULL);
+	ASSERT(name != NULL);
+	ASSERT(encoding != ARINC717_ENC_DISCRETE || num_bits == 1);
+	ASSERT(encoding == ARINC717_ENC_DISCRETE || resolution != 0);
+
+	if (sf_mask == 0 || (sf_mask & ~ARINC717_SF_ALL) != 0) {
+		logMsg("Cannot add ARINC 717 parameter %s: invalid subframe "
+		    "mask %x", name, sf_mask);
+		return (-1);
+	}
+	if (word < 2 || word > enc->wps) {
+		logMsg("Cannot add ARINC 717 parameter %s: word %d out of "
+		    "range (2..%d)", name, word, enc->wps);
+		return (-1);
+	}
+	if (lsb_bit < 1 || num_bits < 1 ||
+	    lsb_bit + num_bits - 1 > ARINC717_WORD_BITS) {
+		logMsg("Cannot add ARINC 717 parameter %s: bit field "
+		    "%d..%d doesn't fit into a 12-bit word", name, lsb_bit,
+		    lsb_bit + num_bits - 1);
+		return (-1);
+	}
+	mask = ((1 << num_bits) - 1) << (lsb_bit - 1);
+	for (int sf = 0; sf < ARINC717_NUM_SUBFRAMES; sf++) {
+		if ((sf_mask & (1 << sf)) != 0 &&
+		    (enc->used[sf][word - 1] & mask) != 0) {
+			logMsg("Cannot add ARINC 717 parameter %s: subframe "
+			    "%d word %d bits %d..%d overlap another parameter",
+			    name, sf + 1, word, lsb_bit,
+			    lsb_bit + num_bits - 1);
+			return (-1);
+		}
+	}
+	for (int sf = 0; sf < ARINC717_NUM_SUBFRAMES; sf++) {
+		if ((sf_mask & (1 << sf)) != 0)
+			enc->used[sf][word - 1] |= mask;
+	}
+
+	enc->params = safe_realloc(enc->params,
+	    (enc->num_params + 1) * sizeof (*enc->params));
+	param = &enc->params[enc->num_params];
+	memset(param, 0, sizeof (*param));
+	lacf_strlcpy(param->name, name, sizeof (param->name));
+	param->sf_mask = sf_mask;
+	param->word = word;
+	param->lsb_bit = lsb_bit;
+	param->num_bits = num_bits;
+	param->encoding = encoding;
+	param->resolution = resolution;
+	param->offset = offset;
+	param->value = NAN;
+
+	return (enc->num_params++);
+}
+
+/*
+ * Looks up a parameter by name. Returns the index of the first
+ * parameter registered under `name', or -1 if not found.
+ */
+int
+arinc717_param_find(const arinc717_t *enc, const char *name)
+{
+	ASSERT(enc != NULL);
+	ASSERT(name != NULL);
+	for (unsigned i = 0; i < enc->num_params; i++) {
+		if (strcmp(enc->params[i].name, name) == 0)
+			return (i);
+	}
+	return (-1);
+}
+
+unsigned
+arinc717_get_num_params(const arinc717_t *enc)
+{
+	ASSERT(enc != NULL);
+	return (enc->num_params);
+}
+
+unsigned
+arinc717_get_wps(const arinc717_t *enc)
+{
+	ASSERT(enc != NULL);
+	return (enc->wps);
+}
+
+/*
+ * Sets the current engineering value of a parameter. The value will be
+ * picked up the next time the parameter's subframe is encoded. A NAN
+ * value encodes as all-zero bits.
+ */
+void
+arinc717_param_set(arinc717_t *enc, int param_idx, double value)
+{
+	ASSERT(enc != NULL);
+	ASSERT3S(param_idx, >=, 0);
+	ASSERT3S(param_idx, <, enc->num_params);
+	enc->params[param_idx].value = value;
+}
+
+double
+arinc717_param_get(const arinc717_t *enc, int param_idx)
+{
+	ASSERT(enc != NULL);
+	ASSERT3S(param_idx, >=, 0);
+	ASSERT3S(param_idx, <, enc->num_params);
+	return (enc->params[param_idx].value);
+}
+
+static uint16_t
+param_encode(const param_t *param)
+{
+	uint16_t field_mask = (1 << param->num_bits) - 1;
+	double x;
+	long raw;
+
+	if (isnan(param->value))
+		return (0);
+
+	switch (param->encoding) {
+	case ARINC717_ENC_DISCRETE:
+		return (param->value != 0);
+	case ARINC717_ENC_BNR: {
+		long lim = 1l << (param->num_bits - 1);
+		x = round((param->value - param->offset) / param->resolution);
+		if (isnan(x))
+			return (0);
+		/* clamp before converting, out-of-range conversion is UB */
+		raw = clamp(x, -lim, lim - 1);
+		return (raw & field_mask);
+	}
+	case ARINC717_ENC_UNSIGNED:
+		x = round((param->value - param->offset) / param->resolution);
+		if (isnan(x))
+			return (0);
+		return (clamp(x, 0, field_mask));
+	case ARINC717_ENC_BCD: {
+		uint16_t bcd = 0;
+		long max_val = 0, mult = 1;
+
+		/* The most significant digit might be less than 4 bits */
+		for (unsigned shift = 0; shift < param->num_bits; shift += 4) {
+			unsigned bits = MIN(param->num_bits - shift, 4);
+			max_val += MIN((1 << bits) - 1, 9) * mult;
+			mult *= 10;
+		}
+		x = round((param->value - param->offset) / param->resolution);
+		if (isnan(x))
+			return (0);
+		raw = clamp(x, 0, max_val);
+		for (unsigned shift = 0; shift < param->num_bits; shift += 4) {
+			bcd |= (raw % 10) << shift;
+			raw /= 10;
+		}
+		return (bcd & field_mask);
+	}
+	default:
+		VERIFY_FAIL();
+	}
+}
+
+static double
+param_decode(const param_t *param, uint16_t word)
+{
+	uint16_t field_mask = (1 << param->num_bits) - 1;
+	uint16_t raw = (word >> (param->lsb_bit - 1)) & field_mask;
+
+	switch (param->encoding) {
+	case ARINC717_ENC_DISCRETE:
+		return (raw);
+	case ARINC717_ENC_BNR: {
+		long val = raw;
+		if (raw & (1 << (param->num_bits - 1)))
+			val -= (1l << param->num_bits);
+		return (val * param->resolution + param->offset);
+	}
+	case ARINC717_ENC_UNSIGNED:
+		return (raw * param->resolution + param->offset);
+	case ARINC717_ENC_BCD: {
+		long val = 0, mult = 1;
+		for (unsigned shift = 0; shift < param->num_bits; shift += 4) {
+			val += ((raw >> shift) & 0xf) * mult;
+			mult *= 10;
+		}
+		return (val * param->resolution + param->offset);
+	}
+	default:
+		VERIFY_FAIL();
+	}
+}
+
+/*
+ * Encodes the current parameter values into a subframe.
+ *
+ * @param enc The encoder.
+ * @param subframe Subframe number to encode (1 through 4).
+ * @param words Output buffer. Must have space for at least WPS words.
+ *	Each word is stored in the low 12 bits of a uint16_t.
+ */
+void
+arinc717_encode_subframe(const arinc717_t *enc, unsigned subframe,
+    uint16_t *words)
+{
+	ASSERT(enc != NULL);
+	ASSERT3U(subframe, >=, 1);
+	ASSERT3U(subframe, <=, ARINC717_NUM_SUBFRAMES);
+	ASSERT(words != NULL);
+
+	memset(words, 0, enc->wps * sizeof (*words));
+	words[0] = sync_words[subframe - 1];
+	for (unsigned i = 0; i < enc->num_params; i++) {
+		const param_t *param = &enc->params[i];
+
+		if ((param->sf_mask & (1 << (subframe - 1))) == 0)
+			continue;
+		words[param->word - 1] |=
+		    param_encode(param) << (param->lsb_bit - 1);
+	}
+}
+
+/*
+ * Decodes a parameter from an encoded subframe. The caller is responsible
+ * for passing a subframe in which the parameter is actually recorded.
+ *
+ * @return The engineering value of the parameter as recovered from the
+ *	encoded data (i.e. after quantization to the parameter resolution).
+ */
+double
+arinc717_decode(const arinc717_t *enc, int param_idx, const uint16_t *words)
+{
+	const param_t *param;
+
+	ASSERT(enc != NULL);
+	ASSERT3S(param_idx, >=, 0);
+	ASSERT3S(param_idx, <, enc->num_params);
+	ASSERT(words != NULL);
+	param = &enc->params[param_idx];
+
+	return (param_decode(param, words[param->word - 1]));
+}
+
+/*
+ * Opens an output file for the encoder. If the encoder already had an
+ * output file open, it is closed first. Output always starts at
+ * subframe 1 of a new frame.
+ *
+ * @param filename Output file path. An existing file is overwritten.
+ * @param fmt Output format:
+ *	- ARINC717_OUT_RAW16: raw words, each word stored in the low 12 bits
+ *	  of a little-endian 16-bit integer. This is the "16-bit aligned"
+ *	  raw format most commonly produced by FDR download tools.
+ *	- ARINC717_OUT_PACKED12: raw words as a continuous big-endian
+ *	  bitstream with no padding (2 words per 3 bytes), exactly as
+ *	  they'd appear on the data bus.
+ *	- ARINC717_OUT_CSV: decoded engineering values of all parameters.
+ *	  One row per subframe. Parameters not recorded in a particular
+ *	  subframe are left empty in that row.
+ *
+ * @return B_TRUE on success, B_FALSE if the file couldn't be opened
+ *	(an error message is logged in that case).
+ */
+bool_t
+arinc717_open(arinc717_t *enc, const char *filename, arinc717_out_t fmt)
+{
+	ASSERT(enc != NULL);
+	ASSERT(filename != NULL);
+
+	arinc717_close(enc);
+
+	enc->fp = fopen(filename, fmt == ARINC717_OUT_CSV ? "w" : "wb");
+	if (enc->fp == NULL) {
+		logMsg("Error opening ARINC 717 output file %s: %s",
+		    filename, strerror(errno));
+		return (B_FALSE);
+	}
+	enc->fmt = fmt;
+	enc->cur_sf = 1;
+	enc->frame_nr = 0;
+	enc->have_nibble = B_FALSE;
+
+	if (fmt == ARINC717_OUT_CSV) {
+		fprintf(enc->fp, "frame,subframe");
+		for (unsigned i = 0; i < enc->num_params; i++)
+			fprintf(enc->fp, ",%s", enc->params[i].name);
+		fprintf(enc->fp, "\n");
+	}
+
+	return (B_TRUE);
+}
+
+/*
+ * Closes the output file of the encoder (if one is open). Any pending
+ * partial byte in ARINC717_OUT_PACKED12 output is flushed out padded
+ * with zero bits.
+ */
+void
+arinc717_close(arinc717_t *enc)
+{
+	ASSERT(enc != NULL);
+
+	if (enc->fp == NULL)
+		return;
+	if (enc->fmt == ARINC717_OUT_PACKED12 && enc->have_nibble) {
+		uint8_t b = enc->nibble << 4;
+		(void) fwrite(&b, 1, 1, enc->fp);
+	}
+	fclose(enc->fp);
+	enc->fp = NULL;
+}
+
+static bool_t
+write_raw16(arinc717_t *enc, const uint16_t *words)
+{
+	for (unsigned i = 0; i < enc->wps; i++) {
+		uint8_t b[2] = { words[i] & 0xff, (words[i] >> 8) & 0xff };
+		if (fwrite(b, 1, sizeof (b), enc->fp) != sizeof (b))
+			return (B_FALSE);
+	}
+	return (B_TRUE);
+}
+
+static bool_t
+write_packed12(arinc717_t *enc, const uint16_t *words)
+{
+	for (unsigned i = 0; i < enc->wps; i++) {
+		uint16_t w = words[i] & ARINC717_WORD_MASK;
+		uint8_t b[2];
+		size_t n;
+
+		if (enc->have_nibble) {
+			b[0] = (enc->nibble << 4) | (w >> 8);
+			b[1] = w & 0xff;
+			n = 2;
+			enc->have_nibble = B_FALSE;
+		} else {
+			b[0] = w >> 4;
+			enc->nibble = w & 0xf;
+			enc->have_nibble = B_TRUE;
+			n = 1;
+		}
+		if (fwrite(b, 1, n, enc->fp) != n)
+			return (B_FALSE);
+	}
+	return (B_TRUE);
+}
+
+static bool_t
+write_csv(arinc717_t *enc, const uint16_t *words)
+{
+	fprintf(enc->fp, "%llu,%d", (unsigned long long)enc->frame_nr,
+	    enc->cur_sf);
+	for (unsigned i = 0; i < enc->num_params; i++) {
+		const param_t *param = &enc->params[i];
+
+		if ((param->sf_mask & (1 << (enc->cur_sf - 1))) != 0) {
+			fprintf(enc->fp, ",%g", param_decode(param,
+			    words[param->word - 1]));
+		} else {
+			fprintf(enc->fp, ",");
+		}
+	}
+	return (fprintf(enc->fp, "\n") > 0);
+}
+
+/*
+ * Encodes the next subframe in sequence from the current parameter values
+ * and writes it to the output file opened with arinc717_open. Call this
+ * once per second of recording. The subframe counter automatically wraps
+ * around from subframe 4 to subframe 1 of the next frame.
+ *
+ * @return B_TRUE on success, B_FALSE on write error (an error message is
+ *	logged and the output file is closed in that case).
+ */
+bool_t
+arinc717_write_subframe(arinc717_t *enc)
+{
+	uint16_t *words;
+	bool_t result = B_FALSE;
+
+	ASSERT(enc != NULL);
+	ASSERT(enc->fp != NULL);
+
+	words = safe_malloc(enc->wps * sizeof (*words));
+	arinc717_encode_subframe(enc, enc->cur_sf, words);
+
+	switch (enc->fmt) {
+	case ARINC717_OUT_RAW16:
+		result = write_raw16(enc, words);
+		break;
+	case ARINC717_OUT_PACKED12:
+		result = write_packed12(enc, words);
+		break;
+	case ARINC717_OUT_CSV:
+		result = write_csv(enc, words);
+		break;
+	default:
+		VERIFY_FAIL();
+	}
+	free(words);
+
+	if (!result) {
+		logMsg("Error writing ARINC 717 output: %s", strerror(errno));
+		arinc717_close(enc);
+		return (B_FALSE);
+	}
+	if (enc->cur_sf == ARINC717_NUM_SUBFRAMES) {
+		enc->cur_sf = 1;
+		enc->frame_nr++;
+	} else {
+		enc->cur_sf++;
+	}
+
+	return (B_TRUE);
+}
@@ -0,0 +1,457 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <ctype.h>
+#include <math.h>
+#include <string.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/atis.h>
+#include <acfutils/helpers.h>
+#include <acfutils/rwysel.h>
+#include <acfutils/safe_alloc.h>
+
+struct atis_s {
+	char	name[32];
+	char	letter;		/* '\0' until the first update */
+	char	rwy[4];
+	char	*text;
+	/* identification of the last METAR, to detect new reports */
+	char	station[8];
+	int	day, hour, min;
+};
+
+static const char *digit_words[] = {
+	"zero", "one", "two", "three", "four",
+	"five", "six", "seven", "eight", "niner"
+};
+
+static const char *phonetic[] = {
+	"Alpha", "Bravo", "Charlie", "Delta", "Echo", "Foxtrot", "Golf",
+	"Hotel", "India", "Juliett", "Kilo", "Lima", "Mike", "November",
+	"Oscar", "Papa", "Quebec", "Romeo", "Sierra", "Tango", "Uniform",
+	"Victor", "Whiskey", "X-ray", "Yankee", "Zulu"
+};
+
+/*
+ * Appends the digits of `str' one by one as spoken words. Non-digit
+ * characters are skipped.
+ */
+static void
+say_digits(char **text, size_t *sz, const char *str)
+{
+	bool_t first = B_TRUE;
+
+	for (; *str != '\0'; str++) {
+		if (*str < '0' || *str > '9')
+			continue;
+		append_format(text, sz, "%s%s", first ? "" : " ",
+		    digit_words[*str - '0']);
+		first = B_FALSE;
+	}
+}
+
+static void
+say_num(char **text, size_t *sz, int num, int min_digits)
+{
+	char buf[16];
+
+	if (num < 0) {
+		append_format(text, sz, "minus ");
+		num = -num;
+	}
+	snprintf(buf, sizeof (buf), "%0*d", min_digits, num);
+	say_digits(text, sz, buf);
+}
+
+/*
+ * Says a height the way ATC does, e.g. "two thousand five hundred".
+ */
+static void
+say_height(char **text, size_t *sz, int ft)
+{
+	int thousands = ft / 1000, hundreds = (ft % 1000) / 100;
+
+	if (thousands != 0) {
+		say_num(text, sz, thousands, 1);
+		append_format(text, sz, " thousand");
+	}
+	if (hundreds != 0 || thousands == 0) {
+		append_format(text, sz, "%s%s hundred",
+		    thousands != 0 ? " " : "", digit_words[hundreds]);
+	}
+	append_format(text, sz, " feet");
+}
+
+static void
+say_rwy(char **text, size_t *sz, const char *rwy)
+{
+	say_digits(text, sz, rwy);
+	switch (rwy[strlen(rwy) - 1]) {
+	case 'L':
+		append_format(text, sz, " left");
+		break;
+	case 'R':
+		append_format(text, sz, " right");
+		break;
+	case 'C':
+		append_format(text, sz, " center");
+		break;
+	}
+}
+
+static void
+say_wx(char **text, size_t *sz, const char *wx)
+{
+	static const struct {
+		const char	*code;
+		const char	*word;
+	} words[] = {
+		{"MI", "shallow"}, {"BC", "patches of"}, {"PR", "partial"},
+		{"DR", "low drifting"}, {"BL", "blowing"},
+		{"TS", "thunderstorm with"}, {"FZ", "freezing"},
+		{"DZ", "drizzle"}, {"RA", "rain"}, {"SN", "snow"},
+		{"SG", "snow grains"}, {"IC", "ice crystals"},
+		{"PL", "ice pellets"}, {"GR", "hail"}, {"GS", "small hail"},
+		{"UP", "unknown precipitation"}, {"BR", "mist"},
+		{"FG", "fog"}, {"FU", "smoke"}, {"VA", "volcanic ash"},
+		{"DU", "dust"}, {"SA", "sand"}, {"HZ", "haze"},
+		{"PY", "spray"}, {"PO", "dust whirls"}, {"SQ", "squalls"},
+		{"FC", "funnel cloud"}, {"SS", "sandstorm"},
+		{"DS", "duststorm"}
+	};
+	bool_t vicinity = B_FALSE, showers = B_FALSE, first = B_TRUE;
+	size_t len;
+
+	if (wx[0] == '-') {
+		append_format(text, sz, "light ");
+		wx++;
+	} else if (wx[0] == '+') {
+		append_format(text, sz, "heavy ");
+		wx++;
+	} else if (strncmp(wx, "VC", 2) == 0) {
+		vicinity = B_TRUE;
+		wx += 2;
+	}
+	len = strlen(wx);
+	/* a bare "TS" is just a thunderstorm */
+	if (strcmp(wx, "TS") == 0) {
+		append_format(text, sz, "thunderstorm");
+		first = B_FALSE;
+		len = 0;
+	}
+	for (size_t i = 0; i + 1 < len; i += 2) {
+		if (strncmp(&wx[i], "SH", 2) == 0) {
+			showers = B_TRUE;
+			continue;
+		}
+		for (size_t j = 0; j < ARRAY_NUM_ELEM(words); j++) {
+			if (strncmp(&wx[i], words[j].code, 2) == 0) {
+				append_format(text, sz, "%s%s",
+				    first ? "" : " ", words[j].word);
+				first = B_FALSE;
+				break;
+			}
+		}
+	}
+	if (showers)
+		append_format(text, sz, first ? "showers" : " showers");
+	if (vicinity)
+		append_format(text, sz, " in the vicinity");
+}
+
+static void
+say_clds(char **text, size_t *sz, const metar_t *metar)
+{
+	static const char *covers[] = {
+		"few", "scattered", "broken", "overcast",
+		"vertical visibility"
+	};
+
+	if (metar->num_clds == 0) {
+		if (metar->sky_clear)
+			append_format(text, sz, "Sky clear. ");
+		return;
+	}
+	append_format(text, sz, "Clouds ");
+	for (size_t i = 0; i < metar->num_clds; i++) {
+		const metar_cld_t *cld = &metar->clds[i];
+
+		append_format(text, sz, "%s%s ", i != 0 ? ", " : "",
+		    covers[cld->cover]);
+		say_height(text, sz, cld->base);
+		if (cld->cb)
+			append_format(text, sz, " cumulonimbus");
+		else if (cld->tcu)
+			append_format(text, sz, " towering cumulus");
+	}
+	append_format(text, sz, ". ");
+}
+
+static void
+say_vis(char **text, size_t *sz, const metar_t *metar)
+{
+	append_format(text, sz, "Visibility ");
+	if (metar->vis_sm) {
+		double sm = metar->vis / 1609.344;
+
+		if (sm >= 1) {
+			say_num(text, sz, round(sm), 1);
+			append_format(text, sz, round(sm) == 1 ? " mile" :
+			    " miles");
+		} else {
+			append_format(text, sz, "less than one mile");
+		}
+	} else if (metar->vis >= METAR_VIS_MAX) {
+		append_format(text, sz, "one zero kilometers or more");
+	} else if (metar->vis >= 5000) {
+		say_num(text, sz, metar->vis / 1000, 1);
+		append_format(text, sz, " kilometers");
+	} else {
+		say_num(text, sz, metar->vis, 1);
+		append_format(text, sz, " meters");
+	}
+	append_format(text, sz, ". ");
+}
+
+static void
+say_wind(char **text, size_t *sz, const metar_t *metar)
+{
+	if (metar->wind_spd == 0) {
+		append_format(text, sz, "Wind calm. ");
+		return;
+	}
+	append_format(text, sz, "Wind ");
+	if (metar->wind_dir == METAR_WIND_VRB) {
+		append_format(text, sz, "variable");
+	} else {
+		say_num(text, sz, metar->wind_dir, 3);
+		append_format(text, sz, " degrees");
+	}
+	append_format(text, sz, ", ");
+	say_num(text, sz, metar->wind_spd, 1);
+	append_format(text, sz, " knots");
+	if (metar->wind_gust != 0) {
+		append_format(text, sz, ", gusting ");
+		say_num(text, sz, metar->wind_gust, 1);
+		append_format(text, sz, " knots");
+	}
+	if (metar->wind_var_from >= 0 && metar->wind_var_to >= 0) {
+		append_format(text, sz, ", variable between ");
+		say_num(text, sz, metar->wind_var_from, 3);
+		append_format(text, sz, " and ");
+		say_num(text, sz, metar->wind_var_to, 3);
+		append_format(text, sz, " degrees");
+	}
+	append_format(text, sz, ". ");
+}
+
+/*
+ * Selects the arrival runway from the reported wind. Gusts are taken
+ * into account conservatively. The current runway gets a bonus in the
+ * selection, so it stays in use in calm or marginal conditions.
+ */
+static void
+select_rwy(atis_t *atis, const metar_t *metar, const airport_t *arpt)
+{
+	rwysel_params_t params;
+	rwysel_cand_t cand;
+	double wind_dir = NAN, wind_spd = 0;
+
+	if (metar->wind_valid) {
+		if (metar->wind_dir != METAR_WIND_VRB)
+			wind_dir = metar->wind_dir;
+		wind_spd = MAX(metar->wind_spd, metar->wind_gust);
+	}
+	rwysel_params_init(&params);
+	params.cur_id = atis->rwy;
+	if (rwysel_select(arpt, wind_dir, wind_spd, RWYSEL_ARR, &params,
+	    &cand))
+		lacf_strlcpy(atis->rwy, cand.end->id, sizeof (atis->rwy));
+	else
+		atis->rwy[0] = '\0';
+}
+
+static void
+gen_text(atis_t *atis, const metar_t *metar, const airport_t *arpt)
+{
+	char *text = NULL;
+	size_t sz = 0;
+	const char *letter = phonetic[atis->letter - 'A'];
+
+	append_format(&text, &sz, "%s information %s, time ",
+	    atis->name[0] != '\0' ? atis->name : arpt->name, letter);
+	say_num(&text, &sz, metar->hour * 100 + metar->min, 4);
+	append_format(&text, &sz, " zulu. ");
+	if (atis->rwy[0] != '\0') {
+		append_format(&text, &sz, "Runway in use ");
+		say_rwy(&text, &sz, atis->rwy);
+		append_format(&text, &sz, ". ");
+	}
+	if (arpt->TL > 0) {
+		append_format(&text, &sz, "Transition level ");
+		say_num(&text, &sz, round(arpt->TL / 100), 1);
+		append_format(&text, &sz, ". ");
+	}
+	if (metar->wind_valid)
+		say_wind(&text, &sz, metar);
+	if (metar->cavok) {
+		append_format(&text, &sz, "CAVOK. ");
+	} else {
+		if (metar->vis_valid)
+			say_vis(&text, &sz, metar);
+		for (size_t i = 0; i < metar->num_wx; i++) {
+			size_t start = sz;
+
+			say_wx(&text, &sz, metar->wx[i]);
+			text[start] = toupper(text[start]);
+			append_format(&text, &sz, ". ");
+		}
+		say_clds(&text, &sz, metar);
+	}
+	if (metar->temp_valid) {
+		append_format(&text, &sz, "Temperature ");
+		say_num(&text, &sz, metar->temp, 1);
+		append_format(&text, &sz, ", dew point ");
+		say_num(&text, &sz, metar->dewpt, 1);
+		append_format(&text, &sz, ". ");
+	}
+	if (metar->qnh_valid) {
+		if (metar->qnh_inhg) {
+			append_format(&text, &sz, "Altimeter ");
+			say_num(&text, &sz, round(metar->qnh / 0.338639), 4);
+		} else {
+			append_format(&text, &sz, "QNH ");
+			say_num(&text, &sz, round(metar->qnh), 4);
+		}
+		append_format(&text, &sz, ". ");
+	}
+	append_format(&text, &sz, "Advise on initial contact you have "
+	    "information %s.", letter);
+
+	free(atis->text);
+	atis->text = text;
+}
+
+/*
+ * Creates a new ATIS generator for a single airport. The generator keeps
+ * track of the current information letter and runway in use between
+ * METAR updates.
+ *
+ * @param name Spoken name of the airport used in the ATIS text (e.g.
+ *	"Frankfurt"). Pass NULL to use the airport name from airportdb.
+ *
+ * @return The generator. Use atis_free to dispose of it.
+ */
+atis_t *
+atis_alloc(const char *name)
+{
+	atis_t *atis = safe_calloc(1, sizeof (*atis));
+
+	if (name != NULL)
+		lacf_strlcpy(atis->name, name, sizeof (atis->name));
+
+	return (atis);
+}
+
+void
+atis_free(atis_t *atis)
+{
+	if (atis == NULL)
+		return;
+	free(atis->text);
+	free(atis);
+}
+
+/*
+ * Updates the ATIS with a new METAR. If the METAR is a new report (i.e.
+ * its observation time differs from the last one), the information
+ * letter advances to the next one (wrapping around from Z to A), the
+ * runway in use is re-selected based on the reported wind and the ATIS
+ * text is regenerated. Re-submitting the same report does nothing.
+ *
+ * @param metar The parsed METAR of the airport.
+ * @param arpt The airport. Must have been loaded by airportdb, so that
+ *	its runway information is available.
+ *
+ * @return B_TRUE if a new ATIS information was issued, B_FALSE if the
+ *	METAR wasn't new.
+ */
+bool_t
+atis_update(atis_t *atis, const metar_t *metar, const airport_t *arpt)
+{
+	ASSERT(atis != NULL);
+	ASSERT(metar != NULL);
+	ASSERT(arpt != NULL);
+
+	if (atis->letter != '\0' &&
+	    strcmp(atis->station, metar->station) == 0 &&
+	    atis->day == metar->day && atis->hour == metar->hour &&
+	    atis->min == metar->min)
+		return (B_FALSE);
+
+	lacf_strlcpy(atis->station, metar->station, sizeof (atis->station));
+	atis->day = metar->day;
+	atis->hour = metar->hour;
+	atis->min = metar->min;
+	if (atis->letter == '\0' || atis->letter == 'Z')
+		atis->letter = 'A';
+	else
+		atis->letter++;
+
+	select_rwy(atis, metar, arpt);
+	gen_text(atis, metar, arpt);
+
+	return (B_TRUE);
+}
+
+/*
+ * Returns the current information letter ('A' through 'Z'), or '\0' if
+ * atis_update hasn't been called yet.
+ */
+char
+atis_get_letter(const atis_t *atis)
+{
+	ASSERT(atis != NULL);
+	return (atis->letter);
+}
+
+/*
+ * Returns the ID of the runway in use (e.g. "27L"). The returned string
+ * is empty if no runway has been selected yet.
+ */
+const char *
+atis_get_rwy(const atis_t *atis)
+{
+	ASSERT(atis != NULL);
+	return (atis->rwy);
+}
+
+/*
+ * Returns the full ATIS text, formatted for text-to-speech playback
+ * (numbers are spelled out digit by digit and letters use the ICAO
+ * phonetic alphabet). Returns NULL if atis_update hasn't been called
+ * yet. The returned string remains valid until the next atis_update
+ * or atis_free call.
+ */
+const char *
+atis_get_text(const atis_t *atis)
+{
+	ASSERT(atis != NULL);
+	return (atis->text);
+}
@@ -0,0 +1,337 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <math.h>
+#include <string.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/aural.h>
+#include <acfutils/safe_alloc.h>
+
+#define	DFL_CABIN_ALT_LIM	10000	/* feet */
+#define	CABIN_ALT_HYST		500	/* feet */
+#define	OVERSPEED_HYST		0.005	/* fraction of VMO/MMO */
+#define	ALT_ALERT_OUTER		900	/* feet */
+#define	ALT_ALERT_INNER		300	/* feet */
+
+typedef enum {
+	ALT_ALERT_FAR,		/* more than ALT_ALERT_OUTER away */
+	ALT_ALERT_NEAR,		/* approaching, chime has sounded */
+	ALT_ALERT_CAPTURED,	/* within ALT_ALERT_INNER */
+	ALT_ALERT_DEVIATED	/* departed from the selected altitude */
+} alt_alert_state_t;
+
+struct aural_s {
+	aural_cb_t		cb;
+	void			*userinfo;
+
+	limit_t			cabin_alt;
+	limit_t			overspeed;
+	unsigned		inhibit[NUM_AURALS];
+	bool_t			active[NUM_AURALS];
+	bool_t			silenced[NUM_AURALS];
+
+	alt_alert_state_t	alt_state;
+	double			sel_alt;
+};
+
+static void
+aural_set_active(aural_t *aur, aural_id_t id, bool_t active)
+{
+	if (aur->active[id] == active)
+		return;
+	aur->active[id] = active;
+	if (!active) {
+		/* silencing only lasts until the condition clears */
+		if (aur->silenced[id]) {
+			aur->silenced[id] = B_FALSE;
+			return;
+		}
+	} else if (aur->silenced[id]) {
+		return;
+	}
+	aur->cb(id, active ? AURAL_EVT_START : AURAL_EVT_STOP, aur->userinfo);
+}
+
+/*
+ * Creates a set of aural warnings. This implements the logic behind the
+ * aural warnings found on nearly every transport category aircraft:
+ *
+ *	- AURAL_CABIN_ALT: an intermittent horn which sounds when the cabin
+ *	  altitude exceeds 10000 ft. It can be silenced by the crew (using
+ *	  aural_silence), until the cabin altitude drops again.
+ *	- AURAL_OVERSPEED: a clacker which sounds whenever the airspeed
+ *	  exceeds VMO or the Mach number exceeds MMO. It can't be silenced.
+ *	- AURAL_TO_CONFIG: a horn which sounds when takeoff thrust is set
+ *	  while the aircraft isn't configured for takeoff. It can't be
+ *	  silenced.
+ *	- AURAL_ALT_ALERT: a chime which sounds once when the aircraft
+ *	  comes within 900 ft of the selected altitude, and once again
+ *	  when it departs from the selected altitude by more than 300 ft
+ *	  after having captured it.
+ *
+ * Each warning is inhibited in some flight phases by default: the cabin
+ * altitude horn on the ground (so it doesn't sound at high altitude
+ * airports), the takeoff configuration horn everywhere except on the
+ * ground and during takeoff, and the altitude alert on the ground,
+ * during takeoff, approach and landing. Use aural_set_inhibit to
+ * change these.
+ *
+ * The module doesn't play any sounds itself. Instead, it invokes `cb'
+ * with AURAL_EVT_START and AURAL_EVT_STOP for continuous warnings (horns
+ * and the clacker) and with AURAL_EVT_ONESHOT for the chime. Hook these
+ * up to your sound playback (e.g. using wav.h).
+ *
+ * Typical usage:
+ *
+ *	aural_t *aur = aural_alloc(play_sound, NULL);
+ *	...on every frame:
+ *	aural_inputs_t in = {
+ *	    .phase = cur_phase, .cabin_alt = cab_alt, .ias = ias,
+ *	    .vmo = vmo, .mach = mach, .mmo = mmo, ...
+ *	};
+ *	aural_update(aur, &in, d_t);
+ *
+ * The cabin altitude and overspeed monitors are limit_t monitors (see
+ * acf_limits.h), so you can adjust their configuration or read their
+ * exceedance history using aural_get_limit.
+ */
+aural_t *
+aural_alloc(aural_cb_t cb, void *userinfo)
+{
+	aural_t *aur = safe_calloc(1, sizeof (*aur));
+
+	ASSERT(cb != NULL);
+	aur->cb = cb;
+	aur->userinfo = userinfo;
+
+	limit_init(&aur->cabin_alt, "cabin_alt", LIMIT_TYPE_MAX,
+	    DFL_CABIN_ALT_LIM, CABIN_ALT_HYST);
+	limit_init(&aur->overspeed, "overspeed", LIMIT_TYPE_MAX, 1,
+	    OVERSPEED_HYST);
+
+	aur->inhibit[AURAL_CABIN_ALT] = AURAL_PH_MASK(AURAL_PH_GROUND);
+	aur->inhibit[AURAL_OVERSPEED] = 0;
+	aur->inhibit[AURAL_TO_CONFIG] = ~(AURAL_PH_MASK(AURAL_PH_GROUND) |
+	    AURAL_PH_MASK(AURAL_PH_TAKEOFF)) &
+	    (AURAL_PH_MASK(NUM_AURAL_PHASES) - 1);
+	aur->inhibit[AURAL_ALT_ALERT] = AURAL_PH_MASK(AURAL_PH_GROUND) |
+	    AURAL_PH_MASK(AURAL_PH_TAKEOFF) |
+	    AURAL_PH_MASK(AURAL_PH_APPROACH) | AURAL_PH_MASK(AURAL_PH_LANDING);
+	aur->alt_state = ALT_ALERT_FAR;
+	aur->sel_alt = NAN;
+
+	return (aur);
+}
+
+/*
+ * Frees a set of aural warnings. Callbacks aren't invoked, so make sure
+ * to stop any sounds which might still be playing.
+ */
+void
+aural_free(aural_t *aur)
+{
+	free(aur);
+}
+
+static bool_t
+is_inhibited(const aural_t *aur, aural_id_t id, aural_phase_t phase)
+{
+	return ((aur->inhibit[id] & AURAL_PH_MASK(phase)) != 0);
+}
+
+static void
+alt_alert_update(aural_t *aur, const aural_inputs_t *in)
+{
+	double dev;
+
+	if (isnan(in->alt) || isnan(in->sel_alt)) {
+		aur->alt_state = ALT_ALERT_FAR;
+		return;
+	}
+	dev = fabs(in->alt - in->sel_alt);
+	if (in->sel_alt != aur->sel_alt) {
+		/* a new selection re-arms the alert */
+		aur->sel_alt = in->sel_alt;
+		aur->alt_state = (dev <= ALT_ALERT_INNER ?
+		    ALT_ALERT_CAPTURED : ALT_ALERT_FAR);
+		if (dev > ALT_ALERT_INNER && dev <= ALT_ALERT_OUTER)
+			aur->alt_state = ALT_ALERT_NEAR;
+		return;
+	}
+	switch (aur->alt_state) {
+	case ALT_ALERT_FAR:
+		if (dev <= ALT_ALERT_OUTER) {
+			aur->alt_state = (dev <= ALT_ALERT_INNER ?
+			    ALT_ALERT_CAPTURED : ALT_ALERT_NEAR);
+			if (!is_inhibited(aur, AURAL_ALT_ALERT, in->phase)) {
+				aur->cb(AURAL_ALT_ALERT, AURAL_EVT_ONESHOT,
+				    aur->userinfo);
+			}
+		}
+		break;
+	case ALT_ALERT_NEAR:
+		if (dev <= ALT_ALERT_INNER)
+			aur->alt_state = ALT_ALERT_CAPTURED;
+		else if (dev > ALT_ALERT_OUTER)
+			aur->alt_state = ALT_ALERT_FAR;
+		break;
+	case ALT_ALERT_CAPTURED:
+		if (dev > ALT_ALERT_INNER) {
+			aur->alt_state = ALT_ALERT_DEVIATED;
+			if (!is_inhibited(aur, AURAL_ALT_ALERT, in->phase)) {
+				aur->cb(AURAL_ALT_ALERT, AURAL_EVT_ONESHOT,
+				    aur->userinfo);
+			}
+		}
+		break;
+	case ALT_ALERT_DEVIATED:
+		if (dev <= ALT_ALERT_INNER)
+			aur->alt_state = ALT_ALERT_CAPTURED;
+		else if (dev > ALT_ALERT_OUTER)
+			aur->alt_state = ALT_ALERT_FAR;
+		break;
+	}
+}
+
+/*
+ * Updates the aural warning logic. Call this once per simulator frame.
+ * The START/STOP/ONESHOT callbacks are invoked from within this function.
+ *
+ * @param in Current aircraft state. Any of the floating point inputs
+ *	can be NAN if unavailable, in which case the associated warning
+ *	retains its previous state (or, for the altitude alert, is
+ *	re-armed).
+ * @param d_t Time elapsed since the last call, in seconds.
+ */
+void
+aural_update(aural_t *aur, const aural_inputs_t *in, double d_t)
+{
+	double spd_ratio = NAN;
+
+	ASSERT(aur != NULL);
+	ASSERT(in != NULL);
+	ASSERT3U(in->phase, <, NUM_AURAL_PHASES);
+
+	limit_update(&aur->cabin_alt, in->cabin_alt, d_t);
+	aural_set_active(aur, AURAL_CABIN_ALT,
+	    limit_is_exceeded(&aur->cabin_alt) &&
+	    !is_inhibited(aur, AURAL_CABIN_ALT, in->phase));
+
+	if (in->vmo > 0)
+		spd_ratio = in->ias / in->vmo;
+	if (in->mmo > 0 && !isnan(in->mach) &&
+	    (isnan(spd_ratio) || in->mach / in->mmo > spd_ratio))
+		spd_ratio = in->mach / in->mmo;
+	limit_update(&aur->overspeed, spd_ratio, d_t);
+	aural_set_active(aur, AURAL_OVERSPEED,
+	    limit_is_exceeded(&aur->overspeed) &&
+	    !is_inhibited(aur, AURAL_OVERSPEED, in->phase));
+
+	aural_set_active(aur, AURAL_TO_CONFIG, in->to_thrust &&
+	    !in->to_config_ok &&
+	    !is_inhibited(aur, AURAL_TO_CONFIG, in->phase));
+
+	alt_alert_update(aur, in);
+}
+
+/*
+ * Returns B_TRUE if the condition of a continuous warning is currently
+ * present and not inhibited, even if the warning has been silenced. The
+ * altitude alert chime is never considered active.
+ */
+bool_t
+aural_is_active(const aural_t *aur, aural_id_t id)
+{
+	ASSERT(aur != NULL);
+	ASSERT3U(id, <, NUM_AURALS);
+	return (aur->active[id]);
+}
+
+/*
+ * Silences an active warning (e.g. in response to the cabin altitude horn
+ * cutout button). Only the cabin altitude horn can be silenced, calls
+ * for other warnings are ignored. The silencing lasts until the
+ * warning's condition clears, so a new exceedance sounds the horn again.
+ */
+void
+aural_silence(aural_t *aur, aural_id_t id)
+{
+	ASSERT(aur != NULL);
+	ASSERT3U(id, <, NUM_AURALS);
+
+	if (id != AURAL_CABIN_ALT || !aur->active[id] || aur->silenced[id])
+		return;
+	aur->silenced[id] = B_TRUE;
+	aur->cb(id, AURAL_EVT_STOP, aur->userinfo);
+}
+
+/*
+ * Sets the flight phases in which a warning is inhibited.
+ *
+ * @param phase_mask Bitwise OR of AURAL_PH_MASK() of the phases to
+ *	inhibit the warning in, or 0 to never inhibit it.
+ */
+void
+aural_set_inhibit(aural_t *aur, aural_id_t id, unsigned phase_mask)
+{
+	ASSERT(aur != NULL);
+	ASSERT3U(id, <, NUM_AURALS);
+	aur->inhibit[id] = phase_mask;
+}
+
+unsigned
+aural_get_inhibit(const aural_t *aur, aural_id_t id)
+{
+	ASSERT(aur != NULL);
+	ASSERT3U(id, <, NUM_AURALS);
+	return (aur->inhibit[id]);
+}
+
+/*
+ * Sets the cabin altitude (in feet) above which the cabin altitude horn
+ * sounds. The default is 10000 ft.
+ */
+void
+aural_set_cabin_alt_limit(aural_t *aur, double cabin_alt)
+{
+	ASSERT(aur != NULL);
+	limit_set_limit(&aur->cabin_alt, cabin_alt);
+}
+
+/*
+ * Returns the limit monitor behind a warning, or NULL if the warning
+ * isn't based on one. The cabin altitude monitor works in feet, while
+ * the overspeed monitor works with the ratio of the airspeed to VMO (or
+ * the Mach number to MMO, whichever is greater), so its limit is 1.
+ */
+limit_t *
+aural_get_limit(aural_t *aur, aural_id_t id)
+{
+	ASSERT(aur != NULL);
+	ASSERT3U(id, <, NUM_AURALS);
+
+	switch (id) {
+	case AURAL_CABIN_ALT:
+		return (&aur->cabin_alt);
+	case AURAL_OVERSPEED:
+		return (&aur->overspeed);
+	default:
+		return (NULL);
+	}
+}
@@ -0,0 +1,404 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <math.h>
+#include <string.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/bleed.h>
+#include <acfutils/math.h>
+#include <acfutils/safe_alloc.h>
+
+/*
+ * Typical transport category values. Pressures are in PSI gauge, flows
+ * in kg/s and temperatures in degrees C.
+ */
+#define	MIN_N2		50	/* percent N2 below which there's no bleed */
+#define	LP_MAX_PRESS	60	/* LP stage pressure at 100% N2 */
+#define	HP_RATIO	2.5	/* HP stage pressure vs LP stage pressure */
+#define	HP_SW_PRESS	30	/* LP pressure below which the HP valve opens */
+#define	HP_SW_HYST	3
+#define	PRV_PRESS	45	/* pressure regulating valve setting */
+#define	ENG_CAP		1.5	/* engine bleed flow capacity at 100% N2 */
+#define	APU_PRESS	40
+#define	APU_CAP		1.2
+#define	CART_PRESS	35
+#define	CART_CAP	1.0
+#define	ENG_TEMP	200	/* precooler outlet temperature */
+#define	APU_TEMP	180
+#define	CART_TEMP	120
+#define	PACK_FLOW	0.5
+#define	WAI_FLOW	0.4	/* per wing */
+#define	EAI_FLOW	0.15	/* per engine */
+#define	LEAK_FLOW	0.8
+#define	PACK_MIN_PRESS	15
+#define	WAI_MIN_PRESS	18
+#define	N1_PENALTY	2.0	/* percent N1 per kg/s of bleed extraction */
+#define	FF_PENALTY	0.04	/* fuel flow fraction per kg/s of extraction */
+#define	PRESS_LAG	1	/* seconds */
+#define	TEMP_LAG	10	/* seconds */
+
+typedef struct {
+	bool_t		running;
+	bool_t		valve;
+	bool_t		hp_valve;
+	double		press;		/* stage pressure */
+	double		cap;
+	double		flow;
+	bool_t		eai;
+} eng_t;
+
+typedef struct {
+	double		press;
+	double		temp;
+	bool_t		pack;
+	bool_t		wai;
+} side_t;
+
+/* A set of sources and consumers connected together via the ducting */
+typedef struct {
+	double		press;		/* highest source pressure */
+	double		cap;
+	double		demand;
+	double		temp_sum;	/* capacity-weighted temperature sum */
+} group_t;
+
+struct bleed_s {
+	unsigned	num_eng;
+	eng_t		eng[BLEED_MAX_ENG];
+	side_t		side[NUM_BLEED_SIDES];
+	bool_t		apu_valve;
+	bool_t		xbleed;
+	bool_t		inited;
+};
+
+/*
+ * Creates a bleed air system model. The system consists of two bleed
+ * ducts (left and right), joined by a crossbleed valve. The engines are
+ * split between the ducts, with the left half of the engines feeding
+ * the left duct and the right half feeding the right duct (on aircraft
+ * with an odd number of engines, the middle engine feeds the left duct).
+ * The APU and ground cart connect to the left duct.
+ *
+ * Each duct feeds an air conditioning pack and one wing's anti-ice.
+ * Engine (nacelle) anti-ice draws air directly from its engine,
+ * independently of the engine bleed valve, so it's available whenever
+ * the engine is running.
+ *
+ * Typical usage:
+ *
+ *	bleed_t *bleed = bleed_alloc(2);
+ *	bleed_inputs_t in = {};
+ *	...on every frame, fill in `in' and then:
+ *	bleed_update(bleed, &in, d_t);
+ *	for (unsigned i = 0; i < 2; i++) {
+ *		engine_set_n1_limit(i, n1_limit -
+ *		    bleed_get_n1_penalty(bleed, i));
+ *		engine_set_ff_mult(i, 1 + bleed_get_ff_penalty(bleed, i));
+ *	}
+ *
+ * The following automatic logic is simulated:
+ *
+ *	- each engine bleed valve is pneumatically operated and closes
+ *	  when its engine isn't running. It also closes when the APU bleed
+ *	  valve is open, so the APU takes over bleed supply.
+ *	- each engine switches from its LP to its HP compressor stage when
+ *	  the LP stage pressure is insufficient (typically at low thrust).
+ *	- in AUTO, the crossbleed valve opens when the APU or ground cart
+ *	  is supplying air, or when one duct has no source, so that both
+ *	  ducts remain pressurized.
+ *	- when demand exceeds what the sources can supply, the duct
+ *	  pressure drops and consumers may lose their supply.
+ *
+ * @param num_eng Number of engines, up to BLEED_MAX_ENG.
+ */
+bleed_t *
+bleed_alloc(unsigned num_eng)
+{
+	bleed_t *bleed = safe_calloc(1, sizeof (*bleed));
+
+	ASSERT3U(num_eng, >, 0);
+	ASSERT3U(num_eng, <=, BLEED_MAX_ENG);
+	bleed->num_eng = num_eng;
+
+	return (bleed);
+}
+
+void
+bleed_free(bleed_t *bleed)
+{
+	free(bleed);
+}
+
+static bleed_side_t
+eng_side(const bleed_t *bleed, unsigned eng)
+{
+	return (eng * 2 < bleed->num_eng ? BLEED_SIDE_L : BLEED_SIDE_R);
+}
+
+static void
+add_source(group_t *grp, double press, double cap, double temp)
+{
+	grp->press = MAX(grp->press, press);
+	grp->cap += cap;
+	grp->temp_sum += cap * temp;
+}
+
+static void
+update_engines(bleed_t *bleed, const bleed_inputs_t *in)
+{
+	for (unsigned i = 0; i < bleed->num_eng; i++) {
+		eng_t *eng = &bleed->eng[i];
+		double lp;
+
+		ASSERT(!isnan(in->n2[i]));
+		eng->running = (in->n2[i] >= MIN_N2);
+		lp = LP_MAX_PRESS * POW2(MAX(in->n2[i], 0) / 100);
+		if (!eng->running)
+			eng->hp_valve = B_FALSE;
+		else if (eng->hp_valve && lp > HP_SW_PRESS + HP_SW_HYST)
+			eng->hp_valve = B_FALSE;
+		else if (!eng->hp_valve && lp < HP_SW_PRESS)
+			eng->hp_valve = B_TRUE;
+		eng->press = (eng->hp_valve ? lp * HP_RATIO : lp);
+		eng->cap = ENG_CAP * POW2(MAX(in->n2[i], 0) / 100);
+		eng->valve = (in->eng_bleed[i] && !in->eng_bleed_fail[i] &&
+		    eng->running && !bleed->apu_valve);
+		eng->eai = (in->eai[i] && eng->running);
+		eng->flow = (eng->eai ? EAI_FLOW : 0);
+	}
+}
+
+static void
+build_groups(bleed_t *bleed, const bleed_inputs_t *in,
+    group_t grp[NUM_BLEED_SIDES])
+{
+	memset(grp, 0, NUM_BLEED_SIDES * sizeof (*grp));
+	for (unsigned i = 0; i < bleed->num_eng; i++) {
+		const eng_t *eng = &bleed->eng[i];
+
+		if (eng->valve) {
+			add_source(&grp[eng_side(bleed, i)],
+			    MIN(eng->press, PRV_PRESS), eng->cap, ENG_TEMP);
+		}
+	}
+	if (bleed->apu_valve)
+		add_source(&grp[BLEED_SIDE_L], APU_PRESS, APU_CAP, APU_TEMP);
+	if (in->gnd_cart)
+		add_source(&grp[BLEED_SIDE_L], CART_PRESS, CART_CAP, CART_TEMP);
+
+	switch (in->xbleed) {
+	case BLEED_XBLEED_CLOSED:
+		bleed->xbleed = B_FALSE;
+		break;
+	case BLEED_XBLEED_AUTO:
+		bleed->xbleed = (bleed->apu_valve || in->gnd_cart ||
+		    (grp[BLEED_SIDE_L].cap == 0) !=
+		    (grp[BLEED_SIDE_R].cap == 0));
+		break;
+	case BLEED_XBLEED_OPEN:
+		bleed->xbleed = B_TRUE;
+		break;
+	}
+
+	for (int s = 0; s < NUM_BLEED_SIDES; s++) {
+		if (in->pack[s])
+			grp[s].demand += PACK_FLOW;
+		if (in->wai)
+			grp[s].demand += WAI_FLOW;
+		if (in->leak[s])
+			grp[s].demand += LEAK_FLOW;
+	}
+	if (bleed->xbleed) {
+		/* merge the right duct into the left */
+		grp[BLEED_SIDE_L].press = MAX(grp[BLEED_SIDE_L].press,
+		    grp[BLEED_SIDE_R].press);
+		grp[BLEED_SIDE_L].cap += grp[BLEED_SIDE_R].cap;
+		grp[BLEED_SIDE_L].demand += grp[BLEED_SIDE_R].demand;
+		grp[BLEED_SIDE_L].temp_sum += grp[BLEED_SIDE_R].temp_sum;
+		grp[BLEED_SIDE_R] = grp[BLEED_SIDE_L];
+	}
+}
+
+/*
+ * Updates the bleed system state.
+ *
+ * @param in Current engine state and cockpit switch positions.
+ * @param d_t Time elapsed since the previous update in seconds.
+ */
+void
+bleed_update(bleed_t *bleed, const bleed_inputs_t *in, double d_t)
+{
+	group_t grp[NUM_BLEED_SIDES];
+
+	ASSERT(bleed != NULL);
+	ASSERT(in != NULL);
+	ASSERT(!isnan(in->oat));
+	ASSERT3F(d_t, >=, 0);
+
+	bleed->apu_valve = (in->apu_bleed && in->apu_avail);
+	update_engines(bleed, in);
+	build_groups(bleed, in, grp);
+
+	for (int s = 0; s < NUM_BLEED_SIDES; s++) {
+		side_t *side = &bleed->side[s];
+		const group_t *g = &grp[s];
+		double ratio = 0, press, temp;
+
+		if (g->cap > 0)
+			ratio = (g->demand > g->cap ? g->cap / g->demand : 1);
+		press = g->press * ratio;
+		temp = (g->cap > 0 ? g->temp_sum / g->cap : in->oat);
+		if (!bleed->inited || d_t == 0) {
+			side->press = press;
+			side->temp = temp;
+		} else {
+			FILTER_IN(side->press, press, d_t, PRESS_LAG);
+			/* without flow, ducts cool down towards OAT */
+			FILTER_IN(side->temp, temp, d_t, TEMP_LAG);
+		}
+		side->pack = (in->pack[s] && side->press >= PACK_MIN_PRESS);
+		side->wai = (in->wai && side->press >= WAI_MIN_PRESS);
+	}
+	/* distribute the supplied flow among the engines by capacity */
+	for (unsigned i = 0; i < bleed->num_eng; i++) {
+		eng_t *eng = &bleed->eng[i];
+		const group_t *g = &grp[eng_side(bleed, i)];
+
+		if (eng->valve && g->cap > 0)
+			eng->flow += MIN(g->demand, g->cap) * eng->cap / g->cap;
+	}
+	bleed->inited = B_TRUE;
+}
+
+/*
+ * Returns the pressure in a bleed duct in PSI gauge.
+ */
+double
+bleed_get_press(const bleed_t *bleed, bleed_side_t side)
+{
+	ASSERT(bleed != NULL);
+	ASSERT3U(side, <, NUM_BLEED_SIDES);
+	return (bleed->side[side].press);
+}
+
+/*
+ * Returns the temperature in a bleed duct in degrees C.
+ */
+double
+bleed_get_temp(const bleed_t *bleed, bleed_side_t side)
+{
+	ASSERT(bleed != NULL);
+	ASSERT3U(side, <, NUM_BLEED_SIDES);
+	return (bleed->side[side].temp);
+}
+
+bool_t
+bleed_xbleed_is_open(const bleed_t *bleed)
+{
+	ASSERT(bleed != NULL);
+	return (bleed->xbleed);
+}
+
+bool_t
+bleed_apu_valve_is_open(const bleed_t *bleed)
+{
+	ASSERT(bleed != NULL);
+	return (bleed->apu_valve);
+}
+
+bool_t
+bleed_eng_valve_is_open(const bleed_t *bleed, unsigned eng)
+{
+	ASSERT(bleed != NULL);
+	ASSERT3U(eng, <, bleed->num_eng);
+	return (bleed->eng[eng].valve);
+}
+
+bool_t
+bleed_hp_valve_is_open(const bleed_t *bleed, unsigned eng)
+{
+	ASSERT(bleed != NULL);
+	ASSERT3U(eng, <, bleed->num_eng);
+	return (bleed->eng[eng].hp_valve);
+}
+
+/*
+ * Returns B_TRUE if a pack is switched on and receives enough bleed
+ * pressure to operate.
+ */
+bool_t
+bleed_pack_is_supplied(const bleed_t *bleed, bleed_side_t side)
+{
+	ASSERT(bleed != NULL);
+	ASSERT3U(side, <, NUM_BLEED_SIDES);
+	return (bleed->side[side].pack);
+}
+
+/*
+ * Returns B_TRUE if a wing's anti-ice is switched on and receives enough
+ * bleed pressure to be effective.
+ */
+bool_t
+bleed_wai_is_supplied(const bleed_t *bleed, bleed_side_t side)
+{
+	ASSERT(bleed != NULL);
+	ASSERT3U(side, <, NUM_BLEED_SIDES);
+	return (bleed->side[side].wai);
+}
+
+bool_t
+bleed_eai_is_supplied(const bleed_t *bleed, unsigned eng)
+{
+	ASSERT(bleed != NULL);
+	ASSERT3U(eng, <, bleed->num_eng);
+	return (bleed->eng[eng].eai);
+}
+
+/*
+ * Returns the total bleed air flow extracted from an engine in kg/s,
+ * including its engine anti-ice.
+ */
+double
+bleed_get_eng_flow(const bleed_t *bleed, unsigned eng)
+{
+	ASSERT(bleed != NULL);
+	ASSERT3U(eng, <, bleed->num_eng);
+	return (bleed->eng[eng].flow);
+}
+
+/*
+ * Returns by how many percent the engine's maximum N1 should be reduced
+ * due to bleed air extraction.
+ */
+double
+bleed_get_n1_penalty(const bleed_t *bleed, unsigned eng)
+{
+	return (bleed_get_eng_flow(bleed, eng) * N1_PENALTY);
+}
+
+/*
+ * Returns the fractional increase of the engine's fuel flow due to bleed
+ * air extraction (e.g. 0.02 means the fuel flow should be increased by
+ * 2%).
+ */
+double
+bleed_get_ff_penalty(const bleed_t *bleed, unsigned eng)
+{
+	return (bleed_get_eng_flow(bleed, eng) * FF_PENALTY);
+}
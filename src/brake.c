@@ -0,0 +1,290 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <math.h>
+#include <string.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/brake.h>
+#include <acfutils/math.h>
+#include <acfutils/safe_alloc.h>
+
+#define	COOL_RATE	(1.0 / 1200)	/* 1/s, stationary heat sink cooling */
+#define	COOL_REF_SPD	30		/* m/s, speed doubling the cooling */
+#define	WHEEL_FRAC	0.5		/* wheel temp rise vs heat sink rise */
+#define	WHEEL_LAG	600		/* seconds */
+#define	DFL_FUSE_TEMP	177		/* degrees C */
+#define	STOP_MIN_POWER	10000		/* W, per brake */
+#define	STOP_MIN_GS	2.5		/* m/s */
+#define	STOP_END_GS	0.5		/* m/s */
+#define	STOP_GAP	10		/* seconds */
+
+typedef struct {
+	double		temp;
+	double		wheel_temp;
+	double		stop_energy;
+	bool_t		melted;
+	brake_stats_t	stats;
+} brk_t;
+
+struct brake_s {
+	unsigned	num_brakes;
+	brk_t		*brakes;
+	double		heat_cap;	/* J/K */
+	double		fuse_temp;
+	bool_t		in_stop;
+	double		stop_idle;	/* time without braking in the stop */
+	brake_cb_t	cb;
+	void		*userinfo;
+};
+
+/*
+ * Creates a brake thermal model for a set of wheel brakes. Each brake's
+ * heat sink absorbs the work done by the brake and cools down towards
+ * the outside air temperature, faster when the aircraft is moving. The
+ * wheel rim slowly soaks up heat from the heat sink. When the wheel
+ * temperature exceeds the fuse plug melting point (177 degrees C by
+ * default, see brake_set_fuse_temp), the fuse plug melts and deflates
+ * the tire, which is reported using BRAKE_EVT_FUSE_MELT.
+ *
+ * Braking is split into stops. A stop starts when any brake absorbs
+ * more than 10 kW above 2.5 m/s ground speed and ends when the aircraft
+ * comes to a halt, or no braking has occurred for 10 seconds. At the end
+ * of a stop, each brake's stop energy is added to its usage statistics
+ * (e.g. for estimating heat sink wear) and BRAKE_EVT_STOP_END is reported
+ * for each brake. The energy of the stop in progress can be read using
+ * brake_get_stop_energy, e.g. to compare against the maximum brake
+ * energy limits for a rejected takeoff.
+ *
+ * Typical usage:
+ *
+ *	brk = brake_alloc(4, 60, 1420, oat, brake_event, NULL);
+ *	...on every frame, with brake_force[i] the retarding force of
+ *	each brake in Newtons:
+ *	brake_update(brk, brake_force, gs, oat, d_t);
+ *
+ * @param num_brakes Number of brakes.
+ * @param mass Mass of each brake's heat sink in kg.
+ * @param spec_heat Specific heat of the heat sink material in J/kg/K
+ *	(approximately 1420 for carbon and 500 for steel brakes).
+ * @param oat Outside air temperature in degrees C. The brakes start out
+ *	at this temperature.
+ * @param cb Event callback, called from inside of brake_update.
+ */
+brake_t *
+brake_alloc(unsigned num_brakes, double mass, double spec_heat, double oat,
+    brake_cb_t cb, void *userinfo)
+{
+	brake_t *brk = safe_calloc(1, sizeof (*brk));
+
+	ASSERT(num_brakes != 0);
+	ASSERT3F(mass, >, 0);
+	ASSERT3F(spec_heat, >, 0);
+	ASSERT(!isnan(oat));
+	ASSERT(cb != NULL);
+
+	brk->num_brakes = num_brakes;
+	brk->brakes = safe_calloc(num_brakes, sizeof (*brk->brakes));
+	for (unsigned i = 0; i < num_brakes; i++) {
+		brk->brakes[i].temp = oat;
+		brk->brakes[i].wheel_temp = oat;
+		brk->brakes[i].stats.peak_temp = oat;
+	}
+	brk->heat_cap = mass * spec_heat;
+	brk->fuse_temp = DFL_FUSE_TEMP;
+	brk->cb = cb;
+	brk->userinfo = userinfo;
+
+	return (brk);
+}
+
+void
+brake_free(brake_t *brk)
+{
+	if (brk == NULL)
+		return;
+	free(brk->brakes);
+	free(brk);
+}
+
+static void
+end_stop(brake_t *brk)
+{
+	brk->in_stop = B_FALSE;
+	for (unsigned i = 0; i < brk->num_brakes; i++) {
+		brk_t *b = &brk->brakes[i];
+
+		b->stats.num_stops++;
+		b->stats.last_stop_energy = b->stop_energy;
+		b->stats.max_stop_energy = MAX(b->stats.max_stop_energy,
+		    b->stop_energy);
+		brk->cb(BRAKE_EVT_STOP_END, i, brk->userinfo);
+		b->stop_energy = 0;
+	}
+}
+
+/*
+ * Updates the brake temperatures.
+ *
+ * @param force Array of `num_brakes' retarding forces in Newtons, one
+ *	for each brake, as applied at the tire contact patch.
+ * @param gs Ground speed in m/s.
+ * @param oat Outside air temperature in degrees C.
+ * @param d_t Time elapsed since the previous update in seconds.
+ */
+void
+brake_update(brake_t *brk, const double *force, double gs, double oat,
+    double d_t)
+{
+	bool_t braking = B_FALSE;
+	double cool;
+
+	ASSERT(brk != NULL);
+	ASSERT(force != NULL);
+	ASSERT(!isnan(gs));
+	ASSERT(!isnan(oat));
+	ASSERT3F(d_t, >=, 0);
+
+	gs = fabs(gs);
+	cool = COOL_RATE * (1 + gs / COOL_REF_SPD);
+	for (unsigned i = 0; i < brk->num_brakes; i++) {
+		brk_t *b = &brk->brakes[i];
+		double power = fabs(force[i]) * gs;
+		double energy = power * d_t;
+
+		b->temp += energy / brk->heat_cap;
+		b->temp += (oat - b->temp) * MIN(cool * d_t, 1);
+		FILTER_IN(b->wheel_temp, oat + WHEEL_FRAC * (b->temp - oat),
+		    d_t, MAX(WHEEL_LAG, d_t));
+		b->stats.total_energy += energy;
+		b->stats.peak_temp = MAX(b->stats.peak_temp, b->temp);
+		if (power > STOP_MIN_POWER && gs > STOP_MIN_GS)
+			braking = B_TRUE;
+		if (brk->in_stop || braking)
+			b->stop_energy += energy;
+		if (!b->melted && b->wheel_temp > brk->fuse_temp) {
+			b->melted = B_TRUE;
+			brk->cb(BRAKE_EVT_FUSE_MELT, i, brk->userinfo);
+		}
+	}
+
+	if (braking) {
+		brk->in_stop = B_TRUE;
+		brk->stop_idle = 0;
+	} else if (brk->in_stop) {
+		brk->stop_idle += d_t;
+		if (gs < STOP_END_GS || brk->stop_idle > STOP_GAP)
+			end_stop(brk);
+	}
+}
+
+/*
+ * Returns a brake's heat sink temperature in degrees C.
+ */
+double
+brake_get_temp(const brake_t *brk, unsigned idx)
+{
+	ASSERT(brk != NULL);
+	ASSERT3U(idx, <, brk->num_brakes);
+	return (brk->brakes[idx].temp);
+}
+
+/*
+ * Returns a brake's wheel rim (fuse plug) temperature in degrees C.
+ */
+double
+brake_get_wheel_temp(const brake_t *brk, unsigned idx)
+{
+	ASSERT(brk != NULL);
+	ASSERT3U(idx, <, brk->num_brakes);
+	return (brk->brakes[idx].wheel_temp);
+}
+
+/*
+ * Returns the energy in Joules absorbed by a brake during the stop in
+ * progress, or 0 if no stop is in progress.
+ */
+double
+brake_get_stop_energy(const brake_t *brk, unsigned idx)
+{
+	ASSERT(brk != NULL);
+	ASSERT3U(idx, <, brk->num_brakes);
+	return (brk->brakes[idx].stop_energy);
+}
+
+bool_t
+brake_fuse_melted(const brake_t *brk, unsigned idx)
+{
+	ASSERT(brk != NULL);
+	ASSERT3U(idx, <, brk->num_brakes);
+	return (brk->brakes[idx].melted);
+}
+
+/*
+ * Returns a brake's cumulative usage statistics since it was last
+ * replaced (see brake_replace).
+ */
+void
+brake_get_stats(const brake_t *brk, unsigned idx, brake_stats_t *stats)
+{
+	ASSERT(brk != NULL);
+	ASSERT3U(idx, <, brk->num_brakes);
+	ASSERT(stats != NULL);
+	*stats = brk->brakes[idx].stats;
+}
+
+/*
+ * Sets the wheel temperature in degrees C above which the fuse plugs
+ * melt.
+ */
+void
+brake_set_fuse_temp(brake_t *brk, double temp)
+{
+	ASSERT(brk != NULL);
+	ASSERT(!isnan(temp));
+	brk->fuse_temp = temp;
+}
+
+/*
+ * Replaces a deflated tire and its fuse plugs. The replacement will
+ * melt again if the wheel is still hot.
+ */
+void
+brake_service(brake_t *brk, unsigned idx)
+{
+	ASSERT(brk != NULL);
+	ASSERT3U(idx, <, brk->num_brakes);
+	brk->brakes[idx].melted = B_FALSE;
+}
+
+/*
+ * Replaces a brake heat sink, resetting its usage statistics. This also
+ * services the wheel (see brake_service).
+ */
+void
+brake_replace(brake_t *brk, unsigned idx)
+{
+	brk_t *b;
+
+	ASSERT(brk != NULL);
+	ASSERT3U(idx, <, brk->num_brakes);
+	b = &brk->brakes[idx];
+	memset(&b->stats, 0, sizeof (b->stats));
+	b->stats.peak_temp = b->temp;
+	b->melted = B_FALSE;
+}
@@ -0,0 +1,277 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <stddef.h>
+#include <stdlib.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/budget.h>
+#include <acfutils/list.h>
+#include <acfutils/safe_alloc.h>
+#include <acfutils/time.h>
+
+struct budget_task_s {
+	budget_step_t	step;
+	void		*userinfo;
+	int		prio;
+	uint64_t	last_run;	/* sequence number of the last step */
+	uint64_t	step_us;	/* duration of the last step */
+	bool_t		done;
+	list_node_t	node;
+};
+
+struct budget_s {
+	uint64_t	budget_us;
+	uint64_t	seq;
+	list_t		tasks;
+	bool_t		running;
+	uint64_t	last_run_us;
+	unsigned	last_num_steps;
+};
+
+/*
+ * Allocates a per-frame task budgeter. This lets you spread heavy work
+ * (terrain prefetching, chart rasterization, file checks, etc.) across
+ * multiple frames on the simulator's main thread, without causing frame
+ * rate stutters. The work needs to be broken up into small incremental
+ * steps, which are performed by calling the task's step callback. On
+ * every frame, budget_run then keeps invoking task steps until the time
+ * budget for that frame is used up. Tasks resume on the next frame where
+ * they left off.
+ *
+ * Tasks with a higher priority are always stepped first. Tasks of equal
+ * priority are stepped round-robin, so that they all make progress.
+ * A step isn't started if, based on the duration of the task's previous
+ * step, it would overrun the budget. However, at least one step is
+ * performed on every call to budget_run, so a slow step can't stall all
+ * work indefinitely. Consequently, each step should take only a small
+ * fraction of the time budget, otherwise the budget will be overrun.
+ *
+ * Typical usage:
+ *
+ *	budget_t *budget = budget_alloc(500);	// 0.5 ms per frame
+ *	budget_task_add(budget, prefetch_step, 1, prefetch_state);
+ *	budget_task_add(budget, raster_step, 0, raster_state);
+ *	...from a flight loop callback, once per frame:
+ *	budget_run(budget);
+ *	...on exit:
+ *	budget_free(budget);
+ *
+ * Tasks are timed using the system's monotonic clock. The budgeter isn't
+ * thread-safe, so all calls must come from the same thread.
+ *
+ * @param budget_us Time budget per call to budget_run in microseconds.
+ *
+ * @return The new budgeter. Use budget_free to free it.
+ */
+budget_t *
+budget_alloc(uint64_t budget_us)
+{
+	budget_t *budget = safe_calloc(1, sizeof (*budget));
+
+	budget->budget_us = budget_us;
+	list_create(&budget->tasks, sizeof (budget_task_t),
+	    offsetof(budget_task_t, node));
+
+	return (budget);
+}
+
+/*
+ * Frees the budgeter along with any unfinished tasks. The tasks' step
+ * callbacks aren't called anymore, so if they hold any resources, you
+ * must release those yourself.
+ */
+void
+budget_free(budget_t *budget)
+{
+	budget_task_t *task;
+
+	if (budget == NULL)
+		return;
+	ASSERT(!budget->running);
+	while ((task = list_remove_head(&budget->tasks)) != NULL)
+		free(task);
+	list_destroy(&budget->tasks);
+	free(budget);
+}
+
+void
+budget_set_budget(budget_t *budget, uint64_t budget_us)
+{
+	ASSERT(budget != NULL);
+	budget->budget_us = budget_us;
+}
+
+uint64_t
+budget_get_budget(const budget_t *budget)
+{
+	ASSERT(budget != NULL);
+	return (budget->budget_us);
+}
+
+/*
+ * Adds a new task to the budgeter. The task's first step will be run
+ * on the next call to budget_run. This may be called from within a
+ * task's step callback.
+ *
+ * @param step Callback which performs one step of the task.
+ * @param prio Task priority. Tasks with higher numbers are run first.
+ * @param userinfo Optional userinfo argument to pass to `step'.
+ *
+ * @return A handle to the task, which remains valid until the task
+ *	completes or is removed using budget_task_remove.
+ */
+budget_task_t *
+budget_task_add(budget_t *budget, budget_step_t step, int prio,
+    void *userinfo)
+{
+	budget_task_t *task = safe_calloc(1, sizeof (*task));
+
+	ASSERT(budget != NULL);
+	ASSERT(step != NULL);
+	task->step = step;
+	task->prio = prio;
+	task->userinfo = userinfo;
+	list_insert_tail(&budget->tasks, task);
+
+	return (task);
+}
+
+/*
+ * Removes an unfinished task. This may be called from within a step
+ * callback (including for the task itself).
+ */
+void
+budget_task_remove(budget_t *budget, budget_task_t *task)
+{
+	ASSERT(budget != NULL);
+	ASSERT(task != NULL);
+	if (budget->running) {
+		/* budget_run is iterating, it will free the task */
+		task->done = B_TRUE;
+		return;
+	}
+	list_remove(&budget->tasks, task);
+	free(task);
+}
+
+/*
+ * Returns the number of unfinished tasks.
+ */
+size_t
+budget_get_num_tasks(const budget_t *budget)
+{
+	size_t n = 0;
+
+	ASSERT(budget != NULL);
+	for (const budget_task_t *task = list_head(&budget->tasks);
+	    task != NULL; task = list_next(&budget->tasks, task)) {
+		if (!task->done)
+			n++;
+	}
+	return (n);
+}
+
+static budget_task_t *
+pick_task(budget_t *budget)
+{
+	budget_task_t *best = NULL;
+
+	for (budget_task_t *task = list_head(&budget->tasks); task != NULL;
+	    task = list_next(&budget->tasks, task)) {
+		if (task->done)
+			continue;
+		if (best == NULL || task->prio > best->prio ||
+		    (task->prio == best->prio &&
+		    task->last_run < best->last_run))
+			best = task;
+	}
+	return (best);
+}
+
+static void
+reap_tasks(budget_t *budget)
+{
+	budget_task_t *next;
+
+	for (budget_task_t *task = list_head(&budget->tasks); task != NULL;
+	    task = next) {
+		next = list_next(&budget->tasks, task);
+		if (task->done) {
+			list_remove(&budget->tasks, task);
+			free(task);
+		}
+	}
+}
+
+/*
+ * Runs task steps until the time budget is used up, or until no tasks
+ * remain. Call this once per frame.
+ */
+void
+budget_run(budget_t *budget)
+{
+	uint64_t start, now;
+	budget_task_t *task;
+
+	ASSERT(budget != NULL);
+	ASSERT(!budget->running);
+
+	budget->running = B_TRUE;
+	budget->last_num_steps = 0;
+	start = nanoclock() / 1000;
+	now = start;
+	while ((task = pick_task(budget)) != NULL) {
+		uint64_t step_start = now;
+
+		/* don't start a step which we expect to overrun the budget */
+		if (budget->last_num_steps != 0 &&
+		    now - start + task->step_us > budget->budget_us)
+			break;
+		task->last_run = ++budget->seq;
+		if (!task->step(task->userinfo))
+			task->done = B_TRUE;
+		budget->last_num_steps++;
+		now = nanoclock() / 1000;
+		task->step_us = now - step_start;
+	}
+	budget->running = B_FALSE;
+	budget->last_run_us = now - start;
+	reap_tasks(budget);
+}
+
+/*
+ * Returns the time spent in the last call to budget_run in microseconds.
+ * If this regularly exceeds the budget, your task steps are too long.
+ */
+uint64_t
+budget_get_last_run_us(const budget_t *budget)
+{
+	ASSERT(budget != NULL);
+	return (budget->last_run_us);
+}
+
+/*
+ * Returns the number of task steps performed in the last budget_run.
+ */
+unsigned
+budget_get_last_num_steps(const budget_t *budget)
+{
+	ASSERT(budget != NULL);
+	return (budget->last_num_steps);
+}
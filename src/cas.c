@@ -0,0 +1,439 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <stddef.h>
+#include <string.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/cas.h>
+#include <acfutils/list.h>
+#include <acfutils/safe_alloc.h>
+
+struct cas_alert_s {
+	char		*text;
+	cas_level_t	level;
+	uint32_t	inhibit;
+	bool_t		latching;
+
+	bool_t		cond;		/* condition as set by the caller */
+	bool_t		latched;
+	uint64_t	seq;		/* activation order */
+	list_node_t	node;
+};
+
+typedef struct {
+	const char	*text;
+	cas_level_t	level;
+	uint64_t	seq;
+} cas_msg_t;
+
+struct cas_s {
+	cas_cb_t	cb;
+	void		*userinfo;
+	unsigned	phase;
+	uint64_t	seq;
+	list_t		alerts;
+
+	cas_msg_t	*msgs;		/* currently displayed messages */
+	size_t		num_msgs;
+	cas_msg_t	*prev_msgs;	/* as of the previous update */
+	size_t		num_prev_msgs;
+
+	bool_t		master_warn;
+	bool_t		master_caut;
+};
+
+/*
+ * Allocates a crew alerting system (CAS) message model, as used to drive
+ * EICAS or ECAM style message displays in airliners.
+ *
+ * You declare alerts up front using cas_alert_add, each with a message
+ * text, an alert level (warning, caution or advisory), a set of flight
+ * phases during which the alert is inhibited, and whether the alert is
+ * latching. Your systems code then simply sets each alert's condition
+ * using cas_alert_set and calls cas_update once per frame. The CAS takes
+ * care of:
+ *
+ * - Inhibits: alerts whose condition is true during an inhibited flight
+ *	phase are not displayed until the flight phase changes (e.g. most
+ *	cautions are inhibited during the takeoff roll). Flight phases are
+ *	arbitrary numbers 0 - 31, set using cas_set_phase.
+ * - Latching: a latching alert remains displayed once its condition has
+ *	been true, until the crew clears it using cas_alert_clear or
+ *	cas_clear_all, even if the condition goes away.
+ * - Deduplication: multiple alerts can share the same message text (e.g.
+ *	when several sensors detect the same fault). The message is only
+ *	displayed once, at the highest level of all the active alerts.
+ * - Ordering: messages are sorted by level (warnings first), and within
+ *	the same level, the most recently activated messages come first.
+ * - Master warning & caution: these are set whenever a new warning or
+ *	caution message appears, until acknowledged by cas_master_ack.
+ * - Events: the callback passed to cas_alloc is invoked from cas_update
+ *	whenever a message appears (or is raised to a higher level) or
+ *	disappears, so you can trigger the appropriate aural alerts. Use
+ *	alerting.h to arbitrate these against other aural alert sources.
+ *
+ * Typical usage:
+ *
+ *	cas_t *cas = cas_alloc(my_aural_cb, NULL);
+ *	cas_alert_t *eng_fire = cas_alert_add(cas, &(cas_alert_def_t){
+ *	    .text = "ENG 1 FIRE", .level = CAS_WARNING
+ *	});
+ *	cas_alert_t *hyd_press = cas_alert_add(cas, &(cas_alert_def_t){
+ *	    .text = "HYD 1 PRESS", .level = CAS_CAUTION,
+ *	    .inhibit = CAS_PHASE(PHASE_TAKEOFF) | CAS_PHASE(PHASE_LANDING)
+ *	});
+ *	...every frame:
+ *	cas_set_phase(cas, cur_phase);
+ *	cas_alert_set(eng_fire, eng1_fire_det);
+ *	cas_alert_set(hyd_press, hyd1_press < 1500);
+ *	cas_update(cas);
+ *	for (size_t i = 0; i < cas_get_num_msgs(cas); i++) {
+ *	    cas_level_t level;
+ *	    const char *text = cas_get_msg(cas, i, &level);
+ *	    ...draw the message in the color for `level'...
+ *	}
+ *	...on exit:
+ *	cas_free(cas);
+ *
+ * The CAS isn't thread-safe, so all calls must come from the same thread.
+ *
+ * @param cb Optional callback to invoke when messages appear or disappear.
+ * @param userinfo Optional userinfo argument to pass to `cb'.
+ *
+ * @return The new CAS. Use cas_free to free it.
+ */
+cas_t *
+cas_alloc(cas_cb_t cb, void *userinfo)
+{
+	cas_t *cas = safe_calloc(1, sizeof (*cas));
+
+	cas->cb = cb;
+	cas->userinfo = userinfo;
+	list_create(&cas->alerts, sizeof (cas_alert_t),
+	    offsetof(cas_alert_t, node));
+
+	return (cas);
+}
+
+void
+cas_free(cas_t *cas)
+{
+	cas_alert_t *alert;
+
+	if (cas == NULL)
+		return;
+	while ((alert = list_remove_head(&cas->alerts)) != NULL) {
+		free(alert->text);
+		free(alert);
+	}
+	list_destroy(&cas->alerts);
+	free(cas->msgs);
+	free(cas->prev_msgs);
+	free(cas);
+}
+
+/*
+ * Declares a new alert. The alert definition is copied, so it can be a
+ * temporary object.
+ *
+ * @return A handle to the alert, which remains valid until the alert is
+ *	removed using cas_alert_remove or the CAS is freed.
+ */
+cas_alert_t *
+cas_alert_add(cas_t *cas, const cas_alert_def_t *def)
+{
+	cas_alert_t *alert = safe_calloc(1, sizeof (*alert));
+
+	ASSERT(cas != NULL);
+	ASSERT(def != NULL);
+	ASSERT(def->text != NULL);
+	ASSERT3U(def->level, <=, CAS_WARNING);
+
+	alert->text = safe_strdup(def->text);
+	alert->level = def->level;
+	alert->inhibit = def->inhibit;
+	alert->latching = def->latching;
+	list_insert_tail(&cas->alerts, alert);
+
+	return (alert);
+}
+
+/*
+ * Removes an alert from the CAS. If the alert was being displayed, its
+ * message disappears immediately, without a CAS_EVT_DEACTIVATE event.
+ */
+void
+cas_alert_remove(cas_t *cas, cas_alert_t *alert)
+{
+	size_t n = 0;
+
+	ASSERT(cas != NULL);
+	ASSERT(alert != NULL);
+	list_remove(&cas->alerts, alert);
+	/* the message list points to the alert's text */
+	for (size_t i = 0; i < cas->num_msgs; i++) {
+		if (cas->msgs[i].text != alert->text)
+			cas->msgs[n++] = cas->msgs[i];
+	}
+	cas->num_msgs = n;
+	free(alert->text);
+	free(alert);
+}
+
+/*
+ * Sets the condition of an alert. The display is only updated at the
+ * next call to cas_update.
+ */
+void
+cas_alert_set(cas_alert_t *alert, bool_t active)
+{
+	ASSERT(alert != NULL);
+	alert->cond = active;
+}
+
+static bool_t
+alert_is_on(const cas_alert_t *alert)
+{
+	return (alert->cond || alert->latched);
+}
+
+/*
+ * Returns true if the alert's condition is true, or if the alert is
+ * latched. This is independent of flight phase inhibits.
+ */
+bool_t
+cas_alert_is_active(const cas_alert_t *alert)
+{
+	ASSERT(alert != NULL);
+	return (alert_is_on(alert));
+}
+
+/*
+ * Clears a latched alert. If the alert's condition is still true, the
+ * alert remains active.
+ */
+void
+cas_alert_clear(cas_alert_t *alert)
+{
+	ASSERT(alert != NULL);
+	alert->latched = B_FALSE;
+}
+
+/*
+ * Clears all latched alerts.
+ */
+void
+cas_clear_all(cas_t *cas)
+{
+	ASSERT(cas != NULL);
+	for (cas_alert_t *alert = list_head(&cas->alerts); alert != NULL;
+	    alert = list_next(&cas->alerts, alert))
+		alert->latched = B_FALSE;
+}
+
+/*
+ * Sets the current flight phase (0 - 31), which is used to evaluate the
+ * alert inhibits.
+ */
+void
+cas_set_phase(cas_t *cas, unsigned phase)
+{
+	ASSERT(cas != NULL);
+	ASSERT3U(phase, <, 32);
+	cas->phase = phase;
+}
+
+unsigned
+cas_get_phase(const cas_t *cas)
+{
+	ASSERT(cas != NULL);
+	return (cas->phase);
+}
+
+static int
+msg_text_compar(const void *a, const void *b)
+{
+	const cas_msg_t *ma = a, *mb = b;
+	int res = strcmp(ma->text, mb->text);
+
+	if (res != 0)
+		return (res);
+	/* put the most important instance of the message first */
+	if (ma->level != mb->level)
+		return (ma->level > mb->level ? -1 : 1);
+	if (ma->seq != mb->seq)
+		return (ma->seq > mb->seq ? -1 : 1);
+	return (0);
+}
+
+static int
+msg_disp_compar(const void *a, const void *b)
+{
+	const cas_msg_t *ma = a, *mb = b;
+
+	if (ma->level != mb->level)
+		return (ma->level > mb->level ? -1 : 1);
+	if (ma->seq != mb->seq)
+		return (ma->seq > mb->seq ? -1 : 1);
+	return (strcmp(ma->text, mb->text));
+}
+
+static const cas_msg_t *
+find_msg(const cas_msg_t *msgs, size_t num_msgs, const char *text)
+{
+	for (size_t i = 0; i < num_msgs; i++) {
+		if (strcmp(msgs[i].text, text) == 0)
+			return (&msgs[i]);
+	}
+	return (NULL);
+}
+
+/*
+ * Recomputes the list of displayed messages from the alert conditions
+ * and fires the message appearance & disappearance events. Call this
+ * once per frame after setting all the alert conditions.
+ */
+void
+cas_update(cas_t *cas)
+{
+	size_t n = 0, num_alerts;
+
+	ASSERT(cas != NULL);
+
+	num_alerts = list_count(&cas->alerts);
+	free(cas->prev_msgs);
+	cas->prev_msgs = cas->msgs;
+	cas->num_prev_msgs = cas->num_msgs;
+	cas->msgs = safe_calloc(MAX(num_alerts, 1), sizeof (*cas->msgs));
+
+	for (cas_alert_t *alert = list_head(&cas->alerts); alert != NULL;
+	    alert = list_next(&cas->alerts, alert)) {
+		if (alert->cond && alert->latching)
+			alert->latched = B_TRUE;
+		if (!alert_is_on(alert)) {
+			alert->seq = 0;
+			continue;
+		}
+		if (alert->inhibit & CAS_PHASE(cas->phase))
+			continue;
+		if (alert->seq == 0)
+			alert->seq = ++cas->seq;
+		cas->msgs[n++] = (cas_msg_t){
+		    .text = alert->text, .level = alert->level,
+		    .seq = alert->seq
+		};
+	}
+	/* deduplicate, keeping the most important instance of each text */
+	qsort(cas->msgs, n, sizeof (*cas->msgs), msg_text_compar);
+	cas->num_msgs = 0;
+	for (size_t i = 0; i < n; i++) {
+		if (cas->num_msgs != 0 && strcmp(cas->msgs[i].text,
+		    cas->msgs[cas->num_msgs - 1].text) == 0)
+			continue;
+		cas->msgs[cas->num_msgs++] = cas->msgs[i];
+	}
+	qsort(cas->msgs, cas->num_msgs, sizeof (*cas->msgs), msg_disp_compar);
+
+	for (size_t i = 0; i < cas->num_msgs; i++) {
+		const cas_msg_t *msg = &cas->msgs[i];
+		const cas_msg_t *prev = find_msg(cas->prev_msgs,
+		    cas->num_prev_msgs, msg->text);
+
+		if (prev != NULL && prev->level >= msg->level)
+			continue;
+		if (msg->level == CAS_WARNING)
+			cas->master_warn = B_TRUE;
+		else if (msg->level == CAS_CAUTION)
+			cas->master_caut = B_TRUE;
+		if (cas->cb != NULL)
+			cas->cb(CAS_EVT_ACTIVATE, msg->text, msg->level,
+			    cas->userinfo);
+	}
+	for (size_t i = 0; i < cas->num_prev_msgs; i++) {
+		const cas_msg_t *msg = &cas->prev_msgs[i];
+
+		if (cas->cb != NULL &&
+		    find_msg(cas->msgs, cas->num_msgs, msg->text) == NULL)
+			cas->cb(CAS_EVT_DEACTIVATE, msg->text, msg->level,
+			    cas->userinfo);
+	}
+}
+
+/*
+ * Returns the number of messages currently displayed.
+ */
+size_t
+cas_get_num_msgs(const cas_t *cas)
+{
+	ASSERT(cas != NULL);
+	return (cas->num_msgs);
+}
+
+/*
+ * Returns the text of a displayed message. The returned string remains
+ * valid until the next call to cas_update.
+ *
+ * @param idx Message index in display order, from 0 (top of the display)
+ *	to cas_get_num_msgs() - 1.
+ * @param level Optional return argument, which will be filled with the
+ *	level of the message.
+ */
+const char *
+cas_get_msg(const cas_t *cas, size_t idx, cas_level_t *level)
+{
+	ASSERT(cas != NULL);
+	ASSERT3U(idx, <, cas->num_msgs);
+	if (level != NULL)
+		*level = cas->msgs[idx].level;
+	return (cas->msgs[idx].text);
+}
+
+/*
+ * Returns true if a new warning message has appeared since the last
+ * call to cas_master_ack.
+ */
+bool_t
+cas_master_warning(const cas_t *cas)
+{
+	ASSERT(cas != NULL);
+	return (cas->master_warn);
+}
+
+/*
+ * Returns true if a new caution message has appeared since the last
+ * call to cas_master_ack.
+ */
+bool_t
+cas_master_caution(const cas_t *cas)
+{
+	ASSERT(cas != NULL);
+	return (cas->master_caut);
+}
+
+/*
+ * Acknowledges the master warning & caution, extinguishing them until
+ * a new warning or caution message appears.
+ */
+void
+cas_master_ack(cas_t *cas)
+{
+	ASSERT(cas != NULL);
+	cas->master_warn = B_FALSE;
+	cas->master_caut = B_FALSE;
+}
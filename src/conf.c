@@ -18,6 +18,7 @@
 
 #include <ctype.h>
 #include <errno.h>
+#include <math.h>
 #include <stddef.h>
 #include <stdio.h>
 #include <stdlib.h>
@@ -1122,6 +1123,169 @@ void conf_set_data_v(conf_t *conf, const char *fmt, const void *buf,
 	VARIABLE_SET(conf_set_data, sz, buf, sz);
 }
 
+/*
+ * Array accessors. Arrays are stored as a sequence of keys with the
+ * element index appended in square brackets, so e.g. an array "tank_cap"
+ * with 3 elements is stored as:
+ *
+ *	tank_cap[0] = 1200
+ *	tank_cap[1] = 3400
+ *	tank_cap[2] = 1200
+ *
+ * The length of an array is given by the number of consecutive elements
+ * present, starting at index 0.
+ */
+
+/*
+ * Retrieves the string elements of an array.
+ *
+ * @param values Optional array to be filled with up to `cap' elements.
+ *	The returned strings point into the configuration and remain valid
+ *	until the respective keys are modified or the configuration is
+ *	freed.
+ * @param cap Capacity of `values'.
+ *
+ * @return The length of the array, which may be greater than `cap'. Call
+ *	with `values' set to NULL to find out how large an array you need.
+ *	Returns 0 if the array doesn't exist.
+ */
+size_t
+conf_get_str_arr(const conf_t *conf, const char *key, const char **values,
+    size_t cap)
+{
+	size_t n;
+
+	ASSERT(conf != NULL);
+	ASSERT(key != NULL);
+	ASSERT(values != NULL || cap == 0);
+
+	for (n = 0;; n++) {
+		const char *value;
+
+		if (!conf_get_str_v(conf, "%s[%u]", &value, key, (unsigned)n))
+			break;
+		if (n < cap)
+			values[n] = value;
+	}
+	return (n);
+}
+
+/*
+ * Same as conf_get_str_arr, but parses the elements as 64-bit integers.
+ */
+size_t
+conf_get_lli_arr(const conf_t *conf, const char *key, long long *values,
+    size_t cap)
+{
+	size_t n;
+
+	ASSERT(conf != NULL);
+	ASSERT(key != NULL);
+	ASSERT(values != NULL || cap == 0);
+
+	for (n = 0;; n++) {
+		long long value;
+
+		if (!conf_get_lli_v(conf, "%s[%u]", &value, key, (unsigned)n))
+			break;
+		if (n < cap)
+			values[n] = value;
+	}
+	return (n);
+}
+
+/*
+ * Same as conf_get_str_arr, but parses the elements as doubles. Elements
+ * which aren't valid numbers are returned as NAN.
+ */
+size_t
+conf_get_d_arr(const conf_t *conf, const char *key, double *values,
+    size_t cap)
+{
+	size_t n;
+
+	ASSERT(conf != NULL);
+	ASSERT(key != NULL);
+	ASSERT(values != NULL || cap == 0);
+
+	for (n = 0;; n++) {
+		const char *str;
+		double value;
+
+		if (!conf_get_str_v(conf, "%s[%u]", &str, key, (unsigned)n))
+			break;
+		if (n < cap) {
+			if (!conf_get_d_v(conf, "%s[%u]", &value, key,
+			    (unsigned)n))
+				value = NAN;
+			values[n] = value;
+		}
+	}
+	return (n);
+}
+
+/*
+ * Removes array elements from index `n' onward, so that a shorter array
+ * replaces a longer one cleanly.
+ */
+static void
+conf_trunc_arr(conf_t *conf, const char *key, size_t n)
+{
+	for (const char *value; conf_get_str_v(conf, "%s[%u]", &value, key,
+	    (unsigned)n); n++)
+		conf_set_str_v(conf, "%s[%u]", NULL, key, (unsigned)n);
+}
+
+/*
+ * Stores an array of strings, replacing any previous array of the same
+ * name. Passing n = 0 removes the array.
+ */
+void
+conf_set_str_arr(conf_t *conf, const char *key, const char *const *values,
+    size_t n)
+{
+	ASSERT(conf != NULL);
+	ASSERT(key != NULL);
+	ASSERT(values != NULL || n == 0);
+
+	for (size_t i = 0; i < n; i++) {
+		ASSERT(values[i] != NULL);
+		conf_set_str_v(conf, "%s[%u]", values[i], key, (unsigned)i);
+	}
+	conf_trunc_arr(conf, key, n);
+}
+
+/*
+ * Same as conf_set_str_arr, but for 64-bit integers.
+ */
+void
+conf_set_lli_arr(conf_t *conf, const char *key, const long long *values,
+    size_t n)
+{
+	ASSERT(conf != NULL);
+	ASSERT(key != NULL);
+	ASSERT(values != NULL || n == 0);
+
+	for (size_t i = 0; i < n; i++)
+		conf_set_lli_v(conf, "%s[%u]", values[i], key, (unsigned)i);
+	conf_trunc_arr(conf, key, n);
+}
+
+/*
+ * Same as conf_set_str_arr, but for doubles.
+ */
+void
+conf_set_d_arr(conf_t *conf, const char *key, const double *values, size_t n)
+{
+	ASSERT(conf != NULL);
+	ASSERT(key != NULL);
+	ASSERT(values != NULL || n == 0);
+
+	for (size_t i = 0; i < n; i++)
+		conf_set_d_v(conf, "%s[%u]", values[i], key, (unsigned)i);
+	conf_trunc_arr(conf, key, n);
+}
+
 /*
  * Walks all configuration key-value pairs. You must set *cookie to NULL
  * on the first call. The function uses it to know how far it has progressed
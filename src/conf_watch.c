@@ -0,0 +1,270 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <string.h>
+#include <sys/stat.h>
+#include <sys/types.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/conf_watch.h>
+#include <acfutils/helpers.h>
+#include <acfutils/log.h>
+#include <acfutils/safe_alloc.h>
+#include <acfutils/thread.h>
+#include <acfutils/worker.h>
+
+struct conf_watch_s {
+	char		*path;
+	conf_watch_cb_t	cb;
+	void		*userinfo;
+	worker_t	wk;
+
+	/* only accessed from the worker */
+	time_t		mtime;
+	off_t		size;
+
+	/* protected by lock */
+	mutex_t		lock;
+	conf_t		*pending;
+
+	/* only accessed from the polling thread */
+	conf_t		*conf;
+};
+
+static bool_t
+file_changed(conf_watch_t *cw)
+{
+	struct stat st;
+
+	/* a missing file is not a change, we keep the last contents */
+	if (stat(cw->path, &st) != 0)
+		return (B_FALSE);
+	if (st.st_mtime == cw->mtime && st.st_size == cw->size)
+		return (B_FALSE);
+	cw->mtime = st.st_mtime;
+	cw->size = st.st_size;
+
+	return (B_TRUE);
+}
+
+static conf_t *
+read_conf(const char *path)
+{
+	int errline;
+	conf_t *conf = conf_read_file(path, &errline);
+
+	if (conf == NULL) {
+		if (errline < 0) {
+			logMsg("Error reading %s", path);
+		} else {
+			logMsg("Error parsing %s: syntax error on line %d",
+			    path, errline);
+		}
+	}
+	return (conf);
+}
+
+static bool_t
+watch_worker(void *userinfo)
+{
+	conf_watch_t *cw = userinfo;
+	conf_t *conf;
+
+	if (!file_changed(cw))
+		return (B_TRUE);
+	/*
+	 * If the file is malformed (e.g. because we caught it halfway
+	 * through being written), we keep the previous contents. Once the
+	 * writer is done, its mtime or size will change again.
+	 */
+	if ((conf = read_conf(cw->path)) == NULL)
+		return (B_TRUE);
+
+	mutex_enter(&cw->lock);
+	if (cw->pending != NULL)
+		conf_free(cw->pending);
+	cw->pending = conf;
+	mutex_exit(&cw->lock);
+
+	return (B_TRUE);
+}
+
+/*
+ * Starts monitoring a configuration file for changes. The file is read
+ * immediately to establish its initial contents, which are available
+ * via conf_watch_get_conf. A background worker then checks the file's
+ * modification time and size every `intval_us' microseconds and
+ * re-reads it whenever either changes.
+ *
+ * Changes are delivered to `cb' from inside of conf_watch_poll, which
+ * you should call periodically (e.g. from a flight loop callback). The
+ * callback receives the new configuration and the sorted list of keys
+ * which were added, removed or whose values have changed. If the file
+ * was rewritten, but its contents haven't changed, the callback isn't
+ * called. Both the configuration and the key list are only valid for
+ * the duration of the callback. Use conf_create_copy if you need to
+ * retain the configuration.
+ *
+ * Typical usage:
+ *
+ *	cw = conf_watch_alloc(path, SEC2USEC(1), settings_changed, NULL);
+ *	...in your flight loop callback:
+ *	conf_watch_poll(cw);
+ *	...
+ *	static void
+ *	settings_changed(const conf_t *conf, const char *const *keys,
+ *	    size_t num_keys, void *userinfo)
+ *	{
+ *		for (size_t i = 0; i < num_keys; i++) {
+ *			if (strcmp(keys[i], "livery.logo") == 0)
+ *				...reload the logo...
+ *		}
+ *	}
+ *
+ * Only string-valued keys (i.e. everything read from a file) are
+ * compared. If the file doesn't exist or can't be parsed, the last
+ * successfully read contents are retained and the error is logged.
+ *
+ * All functions in this module must be called from the same thread.
+ *
+ * @return The new watcher. Use conf_watch_free to free it.
+ */
+conf_watch_t *
+conf_watch_alloc(const char *path, uint64_t intval_us, conf_watch_cb_t cb,
+    void *userinfo)
+{
+	conf_watch_t *cw = safe_calloc(1, sizeof (*cw));
+
+	ASSERT(path != NULL);
+	ASSERT(intval_us != 0);
+	ASSERT(cb != NULL);
+
+	cw->path = safe_strdup(path);
+	cw->cb = cb;
+	cw->userinfo = userinfo;
+	mutex_init(&cw->lock);
+	if (file_changed(cw))
+		cw->conf = read_conf(path);
+	worker_init(&cw->wk, watch_worker, intval_us, cw, "conf_watch");
+
+	return (cw);
+}
+
+/*
+ * Stops monitoring and frees the watcher. Undelivered changes are
+ * discarded.
+ */
+void
+conf_watch_free(conf_watch_t *cw)
+{
+	if (cw == NULL)
+		return;
+	worker_fini(&cw->wk);
+	if (cw->pending != NULL)
+		conf_free(cw->pending);
+	if (cw->conf != NULL)
+		conf_free(cw->conf);
+	mutex_destroy(&cw->lock);
+	free(cw->path);
+	free(cw);
+}
+
+static void
+add_key(char ***keys, size_t *num_keys, const char *key)
+{
+	*keys = safe_realloc(*keys, (*num_keys + 1) * sizeof (**keys));
+	(*keys)[(*num_keys)++] = safe_strdup(key);
+}
+
+static int
+key_compar(const void *a, const void *b)
+{
+	return (strcmp(*(char *const *)a, *(char *const *)b));
+}
+
+static char **
+conf_diff(const conf_t *old, const conf_t *new, size_t *num_keys)
+{
+	const char *key, *value, *old_value;
+	void *cookie;
+	char **keys = NULL;
+
+	*num_keys = 0;
+	cookie = NULL;
+	while (conf_walk(new, &key, &value, &cookie)) {
+		if (old == NULL || !conf_get_str(old, key, &old_value) ||
+		    strcmp(value, old_value) != 0)
+			add_key(&keys, num_keys, key);
+	}
+	cookie = NULL;
+	while (old != NULL && conf_walk(old, &key, &value, &cookie)) {
+		if (!conf_get_str(new, key, &value))
+			add_key(&keys, num_keys, key);
+	}
+	if (*num_keys != 0)
+		qsort(keys, *num_keys, sizeof (*keys), key_compar);
+
+	return (keys);
+}
+
+/*
+ * Delivers changes detected since the last call to the callback passed
+ * to conf_watch_alloc.
+ *
+ * @return B_TRUE if the callback was called, B_FALSE otherwise.
+ */
+bool_t
+conf_watch_poll(conf_watch_t *cw)
+{
+	conf_t *conf;
+	char **keys;
+	size_t num_keys;
+
+	ASSERT(cw != NULL);
+
+	mutex_enter(&cw->lock);
+	conf = cw->pending;
+	cw->pending = NULL;
+	mutex_exit(&cw->lock);
+
+	if (conf == NULL)
+		return (B_FALSE);
+	keys = conf_diff(cw->conf, conf, &num_keys);
+	if (cw->conf != NULL)
+		conf_free(cw->conf);
+	cw->conf = conf;
+	if (num_keys == 0)
+		return (B_FALSE);
+	cw->cb(conf, (const char *const *)keys, num_keys, cw->userinfo);
+	free_strlist(keys, num_keys);
+
+	return (B_TRUE);
+}
+
+/*
+ * Returns the most recently delivered contents of the watched file, or
+ * NULL if the file has never been successfully read. The returned
+ * configuration is replaced by the next call to conf_watch_poll which
+ * delivers a change.
+ */
+const conf_t *
+conf_watch_get_conf(const conf_watch_t *cw)
+{
+	ASSERT(cw != NULL);
+	return (cw->conf);
+}
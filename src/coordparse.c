@@ -0,0 +1,458 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <ctype.h>
+#include <math.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/coordparse.h>
+#include <acfutils/helpers.h>
+#include <acfutils/parser_funcs.h>
+
+#define	MAX_COORD_LEN	128
+#define	MAX_COMPS	3	/* degrees, minutes, seconds */
+
+/*
+ * We can't use isdigit, since the input can contain UTF-8 symbols, which
+ * would be passed to it as negative values.
+ */
+static inline bool_t
+is_digit(char c)
+{
+	return (c >= '0' && c <= '9');
+}
+
+static inline bool_t
+is_comp_start(const char *str, const char *s)
+{
+	return ((is_digit(*s) || *s == '.') &&
+	    (s == str || !(is_digit(s[-1]) || s[-1] == '.')));
+}
+
+static bool_t
+set_err(coord_err_t *err, coord_err_t value)
+{
+	if (err != NULL)
+		*err = value;
+	return (value == COORD_OK);
+}
+
+/*
+ * Parses a single angle (latitude or longitude) without its hemisphere
+ * letter from str[0..len). The angle can be given as separate degree,
+ * minute and second components (separated by spaces, degree/minute/second
+ * symbols or colons), with decimals allowed on the last component. If
+ * the angle is a single number, the number of integer digits determines
+ * its format: up to 3 digits are degrees, 4 (5 for longitude) digits are
+ * DDMM, 6 (7 for longitude) digits are DDMMSS.
+ */
+static bool_t
+parse_angle(const char *str, size_t len, bool_t is_lon, bool_t sign_ok,
+    double *angle, coord_err_t *err)
+{
+	char comps[MAX_COMPS][32];
+	unsigned n_comps = 0;
+	double val[MAX_COMPS] = { 0 };
+	double deg, min = 0, sec = 0;
+	bool_t neg = B_FALSE, seen_sign = B_FALSE;
+	size_t i = 0;
+
+	while (i < len) {
+		char c = str[i];
+
+		if (is_digit(c) || c == '.') {
+			size_t start = i;
+
+			if (n_comps == MAX_COMPS)
+				return (set_err(err, COORD_ERR_SYNTAX));
+			while (i < len && (is_digit(str[i]) || str[i] == '.'))
+				i++;
+			if (i - start >= sizeof (comps[0]))
+				return (set_err(err, COORD_ERR_SYNTAX));
+			lacf_strlcpy(comps[n_comps], &str[start],
+			    i - start + 1);
+			n_comps++;
+			continue;
+		}
+		if (c == '+' || c == '-') {
+			if (!sign_ok || seen_sign || n_comps != 0)
+				return (set_err(err, COORD_ERR_SYNTAX));
+			seen_sign = B_TRUE;
+			neg = (c == '-');
+		} else if (isalpha((unsigned char)c)) {
+			return (set_err(err, COORD_ERR_SYNTAX));
+		}
+		/*
+		 * Everything else (whitespace, commas, colons, degree/minute/
+		 * second symbols, including their UTF-8 encodings) is simply
+		 * a separator between components.
+		 */
+		i++;
+	}
+	if (n_comps == 0)
+		return (set_err(err, COORD_ERR_SYNTAX));
+	for (unsigned j = 0; j < n_comps; j++) {
+		const char *dot = strchr(comps[j], '.');
+		char *end;
+
+		/* only the last component may have decimals */
+		if (dot != NULL && (j + 1 < n_comps ||
+		    strchr(dot + 1, '.') != NULL))
+			return (set_err(err, COORD_ERR_SYNTAX));
+		if (strcmp(comps[j], ".") == 0)
+			return (set_err(err, COORD_ERR_SYNTAX));
+		val[j] = strtod(comps[j], &end);
+		if (*end != '\0')
+			return (set_err(err, COORD_ERR_SYNTAX));
+	}
+	if (n_comps == 1) {
+		const char *dot = strchr(comps[0], '.');
+		size_t digits = (dot != NULL ? (size_t)(dot - comps[0]) :
+		    strlen(comps[0]));
+
+		if (digits <= 3) {
+			deg = val[0];
+		} else if (digits == (is_lon ? 5u : 4u)) {
+			deg = floor(val[0] / 100);
+			min = val[0] - deg * 100;
+		} else if (digits == (is_lon ? 7u : 6u)) {
+			deg = floor(val[0] / 10000);
+			min = floor((val[0] - deg * 10000) / 100);
+			sec = val[0] - deg * 10000 - min * 100;
+		} else {
+			return (set_err(err, COORD_ERR_SYNTAX));
+		}
+	} else {
+		if (val[0] != floor(val[0]))
+			return (set_err(err, COORD_ERR_SYNTAX));
+		deg = val[0];
+		min = val[1];
+		sec = val[2];
+	}
+	if (min >= 60)
+		return (set_err(err, COORD_ERR_MIN));
+	if (sec >= 60)
+		return (set_err(err, COORD_ERR_SEC));
+	deg += min / 60 + sec / 3600;
+	if (deg > (is_lon ? 180 : 90))
+		return (set_err(err, is_lon ? COORD_ERR_LON : COORD_ERR_LAT));
+	*angle = (neg ? -deg : deg);
+
+	return (set_err(err, COORD_OK));
+}
+
+static bool_t
+is_blank(const char *str, size_t len)
+{
+	for (size_t i = 0; i < len; i++) {
+		if (!isspace((unsigned char)str[i]) && str[i] != ',')
+			return (B_FALSE);
+	}
+	return (B_TRUE);
+}
+
+/*
+ * Parses ARINC 424 oceanic position shorthand (5 characters):
+ *
+ * - "5020N": 50N 020W, the letter at the end is the quadrant
+ *	(N = north/west, E = north/east, S = south/east, W = south/west)
+ * - "50N20": 50N 120W, the letter in the middle marks a longitude of
+ *	100 degrees or more
+ * - "N5020": 50°30'N 020W, the letter at the start marks a half-degree
+ *	latitude
+ */
+static bool_t
+parse_arinc(const char *str, geo_pos2_t *pos)
+{
+	static const struct {
+		char	quad;
+		int	lat_sign;
+		int	lon_sign;
+	} quads[] = {
+	    { 'N', 1, -1 }, { 'E', 1, 1 }, { 'S', -1, 1 }, { 'W', -1, -1 }
+	};
+	char digits[5];
+	char quad;
+	double lat, lon;
+	unsigned n = 0;
+
+	if (strlen(str) != 5)
+		return (B_FALSE);
+	for (int i = 0; i < 5; i++) {
+		if (is_digit(str[i]))
+			digits[n++] = str[i];
+	}
+	if (n != 4)
+		return (B_FALSE);
+	if (!is_digit(str[4])) {
+		quad = str[4];
+		lat = (digits[0] - '0') * 10 + (digits[1] - '0');
+		lon = (digits[2] - '0') * 10 + (digits[3] - '0');
+	} else if (!is_digit(str[2])) {
+		quad = str[2];
+		lat = (digits[0] - '0') * 10 + (digits[1] - '0');
+		lon = 100 + (digits[2] - '0') * 10 + (digits[3] - '0');
+	} else if (!is_digit(str[0])) {
+		quad = str[0];
+		lat = (digits[0] - '0') * 10 + (digits[1] - '0') + 0.5;
+		lon = (digits[2] - '0') * 10 + (digits[3] - '0');
+	} else {
+		return (B_FALSE);
+	}
+	for (size_t i = 0; i < ARRAY_NUM_ELEM(quads); i++) {
+		if (quads[i].quad == quad) {
+			if (lat > 90 || lon > 180)
+				return (B_FALSE);
+			pos->lat = lat * quads[i].lat_sign;
+			pos->lon = lon * quads[i].lon_sign;
+			return (B_TRUE);
+		}
+	}
+	return (B_FALSE);
+}
+
+/*
+ * Splits a coordinate pair without hemisphere letters into latitude and
+ * longitude. If the input contains a comma, it is split at the comma.
+ * Otherwise, it must consist of an even number of numeric components,
+ * the first half of which form the latitude.
+ */
+static bool_t
+split_unlettered(const char *str, size_t *lat_len, const char **lon)
+{
+	const char *comma = strchr(str, ',');
+	unsigned n_comps = 0, half;
+
+	if (comma != NULL) {
+		if (strchr(comma + 1, ',') != NULL)
+			return (B_FALSE);
+		*lat_len = comma - str;
+		*lon = comma + 1;
+		return (B_TRUE);
+	}
+	for (const char *s = str; *s != '\0'; s++) {
+		if (is_comp_start(str, s))
+			n_comps++;
+	}
+	if (n_comps < 2 || n_comps > 2 * MAX_COMPS || n_comps % 2 != 0)
+		return (B_FALSE);
+	half = n_comps / 2;
+	n_comps = 0;
+	for (const char *s = str; *s != '\0'; s++) {
+		if (is_comp_start(str, s)) {
+			if (n_comps == half) {
+				/* a sign belongs to the longitude */
+				while (s > str && (s[-1] == '-' ||
+				    s[-1] == '+'))
+					s--;
+				*lat_len = s - str;
+				*lon = s;
+				return (B_TRUE);
+			}
+			n_comps++;
+		}
+	}
+	VERIFY_FAIL();
+}
+
+/*
+ * Parses a geographic coordinate pair typed in by a user or read from a
+ * flight plan. The parser is case-insensitive and accepts:
+ *
+ * - decimal degrees: "37.6183 -122.375", "37.6183, -122.375",
+ *	"N37.6183 W122.375", "37.6183N 122.375W"
+ * - degrees and decimal minutes: "N37°37.1' W122°22.5'", "N37 37.1
+ *	W122 22.5", "N3737.1W12222.5"
+ * - degrees, minutes and seconds: "37°37'06\"N 122°22'30\"W",
+ *	"373706N1222230W"
+ * - compact whole minutes: "3737N12222W"
+ * - ARINC 424 oceanic shorthand: "5020N", "50N20", "N5020" (see
+ *	parse_arinc above for details)
+ *
+ * Hemisphere letters may precede or follow the numbers, but must be used
+ * consistently for both latitude and longitude. Negative numbers may only
+ * be used without hemisphere letters.
+ *
+ * @param str The string to parse.
+ * @param pos Output argument which will be filled with the parsed
+ *	position on success.
+ * @param err Optional output argument which will be filled with the
+ *	reason for a parse failure. Use coord_err2str to convert the error
+ *	into a message suitable for display to the user.
+ *
+ * @return B_TRUE if the string was parsed successfully, B_FALSE otherwise.
+ */
+bool_t
+coord_parse(const char *str, geo_pos2_t *pos, coord_err_t *err)
+{
+	char buf[MAX_COORD_LEN];
+	const char *ns = NULL, *ew = NULL;
+	const char *lat_str, *lon_str;
+	size_t lat_len, lon_len;
+	double lat, lon;
+
+	ASSERT(str != NULL);
+	ASSERT(pos != NULL);
+
+	lacf_strlcpy(buf, str, sizeof (buf));
+	strip_space(buf);
+	strtoupper(buf);
+	if (*buf == '\0')
+		return (set_err(err, COORD_ERR_EMPTY));
+
+	if (parse_arinc(buf, pos))
+		return (set_err(err, COORD_OK));
+
+	for (const char *s = buf; *s != '\0'; s++) {
+		if (*s == 'N' || *s == 'S') {
+			if (ns != NULL)
+				return (set_err(err, COORD_ERR_HEMI));
+			ns = s;
+		} else if (*s == 'E' || *s == 'W') {
+			if (ew != NULL)
+				return (set_err(err, COORD_ERR_HEMI));
+			ew = s;
+		}
+	}
+	if (ns == NULL && ew == NULL) {
+		if (!split_unlettered(buf, &lat_len, &lon_str))
+			return (set_err(err, COORD_ERR_SYNTAX));
+		if (!parse_angle(buf, lat_len, B_FALSE, B_TRUE, &lat, err) ||
+		    !parse_angle(lon_str, strlen(lon_str), B_TRUE, B_TRUE,
+		    &lon, err))
+			return (B_FALSE);
+		*pos = GEO_POS2(lat, lon);
+		return (set_err(err, COORD_OK));
+	}
+	if (ns == NULL || ew == NULL || ew < ns)
+		return (set_err(err, COORD_ERR_HEMI));
+	if (ns == buf) {
+		/* prefix form: "N37 37.1 W122 22.5" */
+		lat_str = ns + 1;
+		lat_len = ew - lat_str;
+		lon_str = ew + 1;
+		lon_len = strlen(lon_str);
+	} else {
+		/* suffix form: "3737N 12222W" */
+		lat_str = buf;
+		lat_len = ns - buf;
+		lon_str = ns + 1;
+		lon_len = ew - lon_str;
+		if (!is_blank(ew + 1, strlen(ew + 1)))
+			return (set_err(err, COORD_ERR_HEMI));
+	}
+	if (!parse_angle(lat_str, lat_len, B_FALSE, B_FALSE, &lat, err) ||
+	    !parse_angle(lon_str, lon_len, B_TRUE, B_FALSE, &lon, err))
+		return (B_FALSE);
+	*pos = GEO_POS2(*ns == 'S' ? -lat : lat, *ew == 'W' ? -lon : lon);
+
+	return (set_err(err, COORD_OK));
+}
+
+static bool_t
+coord_parse_comp(const char *str, bool_t is_lon, double *angle,
+    coord_err_t *err)
+{
+	char buf[MAX_COORD_LEN];
+	const char *hemi = (is_lon ? "EW" : "NS");
+	const char *letter = NULL;
+	size_t len;
+
+	ASSERT(str != NULL);
+	ASSERT(angle != NULL);
+
+	lacf_strlcpy(buf, str, sizeof (buf));
+	strip_space(buf);
+	strtoupper(buf);
+	len = strlen(buf);
+	if (len == 0)
+		return (set_err(err, COORD_ERR_EMPTY));
+
+	if (strchr(hemi, buf[0]) != NULL)
+		letter = &buf[0];
+	else if (strchr(hemi, buf[len - 1]) != NULL)
+		letter = &buf[len - 1];
+	if (letter == NULL) {
+		/* e.g. a longitude hemisphere letter on a latitude */
+		if (strpbrk(buf, "NSEW") != NULL)
+			return (set_err(err, COORD_ERR_HEMI));
+		return (parse_angle(buf, len, is_lon, B_TRUE, angle, err));
+	}
+	if (!parse_angle(letter == buf ? buf + 1 : buf, len - 1, is_lon,
+	    B_FALSE, angle, err)) {
+		/* a stray hemisphere letter is more helpful to report */
+		if (err != NULL && *err == COORD_ERR_SYNTAX &&
+		    strpbrk(letter == buf ? buf + 1 : buf, "NSEW") != NULL)
+			*err = COORD_ERR_HEMI;
+		return (B_FALSE);
+	}
+	if (*letter == 'S' || *letter == 'W')
+		*angle = -*angle;
+
+	return (set_err(err, COORD_OK));
+}
+
+/*
+ * Same as coord_parse, but parses a latitude on its own, e.g. from a
+ * separate input field. Accepts all the single-coordinate formats of
+ * coord_parse, such as "N37 37.1", "373706N" or "-37.6183".
+ */
+bool_t
+coord_parse_lat(const char *str, double *lat, coord_err_t *err)
+{
+	return (coord_parse_comp(str, B_FALSE, lat, err));
+}
+
+/*
+ * Same as coord_parse_lat, but parses a longitude.
+ */
+bool_t
+coord_parse_lon(const char *str, double *lon, coord_err_t *err)
+{
+	return (coord_parse_comp(str, B_TRUE, lon, err));
+}
+
+/*
+ * Returns a human-readable description of a coordinate parse error.
+ */
+const char *
+coord_err2str(coord_err_t err)
+{
+	switch (err) {
+	case COORD_OK:
+		return ("OK");
+	case COORD_ERR_EMPTY:
+		return ("No coordinates entered");
+	case COORD_ERR_SYNTAX:
+		return ("Unrecognized coordinate format");
+	case COORD_ERR_HEMI:
+		return ("Missing or misplaced hemisphere (N/S/E/W)");
+	case COORD_ERR_LAT:
+		return ("Latitude out of range");
+	case COORD_ERR_LON:
+		return ("Longitude out of range");
+	case COORD_ERR_MIN:
+		return ("Minutes out of range");
+	case COORD_ERR_SEC:
+		return ("Seconds out of range");
+	default:
+		return ("Unknown error");
+	}
+}
@@ -33,7 +33,7 @@
 #define	CRC64_POLY	0xC96C5795D7870F42ULL	/* ECMA-182, reflected form */
 
 static uint64_t crc64_table[256];
-static uint64_t rand_seed = 0;
+static crc64_rng_t global_rng = { .state = 0 };
 
 void
 crc64_init(void)
@@ -74,11 +74,17 @@ crc64_append(uint64_t crc, const void *input, size_t sz)
  * Initializes the CRC64-based pseudo random number generator. Pass in some
  * random seed (e.g. current microclock() usually does nicely). Obviously
  * you only want to call this once in your app.
+ *
+ * The crc64_srand/crc64_rand family of functions operates on a single
+ * global generator state. If you need several independent, reproducible
+ * random sequences (e.g. one per simulated object, or one per thread),
+ * use a crc64_rng_t instance with the crc64_rng_* functions instead. For
+ * the same seed, both produce identical sequences.
  */
 void
 crc64_srand(uint64_t seed)
 {
-	rand_seed = seed;
+	crc64_rng_seed(&global_rng, seed);
 }
 
 /*
@@ -95,8 +101,7 @@ crc64_srand(uint64_t seed)
 uint64_t
 crc64_rand(void)
 {
-	rand_seed = crc64(&rand_seed, sizeof (rand_seed));
-	return (rand_seed);
+	return (crc64_rng_next(&global_rng));
 }
 
 /*
@@ -107,7 +112,7 @@ crc64_rand(void)
 double
 crc64_rand_fract(void)
 {
-	return (crc64_rand() / (double)UINT64_MAX);
+	return (crc64_rng_fract(&global_rng));
 }
 
 /*
@@ -117,8 +122,63 @@ crc64_rand_fract(void)
 double
 crc64_rand_normal(double sigma)
 {
-	double x = crc64_rand_fract();
-	double y = crc64_rand_fract();
+	return (crc64_rng_normal(&global_rng, sigma));
+}
+
+/*
+ * Seeds an independent PRNG instance. Each instance only touches its own
+ * state, so different threads can safely use different instances without
+ * locking, and each instance yields the same sequence for the same seed.
+ * The same caveats regarding randomness quality as for crc64_rand apply.
+ */
+void
+crc64_rng_seed(crc64_rng_t *rng, uint64_t seed)
+{
+	ASSERT(rng != NULL);
+	rng->state = seed;
+}
+
+/*
+ * Same as crc64_rand, but operates on a PRNG instance.
+ */
+uint64_t
+crc64_rng_next(crc64_rng_t *rng)
+{
+	ASSERT(rng != NULL);
+	rng->state = crc64(&rng->state, sizeof (rng->state));
+	return (rng->state);
+}
+
+/*
+ * Same as crc64_rand_fract, but operates on a PRNG instance.
+ */
+double
+crc64_rng_fract(crc64_rng_t *rng)
+{
+	return (crc64_rng_next(rng) / (double)UINT64_MAX);
+}
+
+/*
+ * Same as crc64_rand_normal, but operates on a PRNG instance.
+ */
+double
+crc64_rng_normal(crc64_rng_t *rng, double sigma)
+{
+	double x = crc64_rng_fract(rng);
+	double y = crc64_rng_fract(rng);
 	double z = sqrt(-2 * log(x)) * cos(2 * M_PI * y);
 	return (sigma * z);
 }
+
+/*
+ * Returns a random integer from the interval [lo, hi). The result has a
+ * slight bias towards lower values when (hi - lo) isn't a power of two,
+ * which is negligible unless the interval spans a large fraction of the
+ * 64-bit range.
+ */
+uint64_t
+crc64_rng_range(crc64_rng_t *rng, uint64_t lo, uint64_t hi)
+{
+	ASSERT3U(lo, <, hi);
+	return (lo + crc64_rng_next(rng) % (hi - lo));
+}
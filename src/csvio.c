@@ -0,0 +1,754 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <ctype.h>
+#include <math.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/csvio.h>
+#include <acfutils/helpers.h>
+#include <acfutils/math.h>
+#include <acfutils/log.h>
+#include <acfutils/safe_alloc.h>
+
+/*
+ * This module implements a forgiving CSV reader, intended primarily for
+ * performance data tables, as well as a simple writer for exporting
+ * recorded data (flight data recorders, logbooks and the like).
+ *
+ * The reader accepts:
+ *
+ *	- comma, semicolon or tab separated fields. Pass a `sep' of 0 to
+ *	  csv_parse and csv_read to auto-detect the separator from the
+ *	  first (header) line.
+ *	- RFC 4180-style quoted fields, including embedded separators,
+ *	  doubled quotes ("") and line breaks.
+ *	- comment lines starting with '#' and empty lines, both of which
+ *	  are skipped.
+ *	- LF and CRLF line endings. Whitespace around unquoted fields is
+ *	  trimmed.
+ *	- decimal commas (as produced by spreadsheets in many locales) in
+ *	  numeric fields, provided the separator isn't a comma.
+ *	- rows with fewer fields than the header. Missing fields read as
+ *	  empty strings and NAN. Excess fields are ignored.
+ *
+ * The first non-comment line is the header, which names the columns.
+ * Column names can carry a unit, written as "alt_ft", "alt (ft)" or
+ * "alt [ft]". The bracketed forms accept any unit text, whereas the
+ * underscore form is only recognized for units known to csv_unit_conv
+ * (so that a column named "fuel_flow" isn't mistaken for a "flow" unit).
+ * The unit is stripped from the name and returned by csv_col_unit.
+ * csv_get_base then reads a field converted to the base units listed at
+ * csv_unit_conv.
+ *
+ * 1D tables (e.g. "alt_ft,temp_c") map directly onto fx_lin_multi curves
+ * via csv_get_curve. 2D tables are laid out with the first column holding
+ * the row breakpoints and the remaining column names holding the column
+ * breakpoints, e.g.:
+ *
+ *	weight_kg, 0, 10000, 20000, 30000
+ *	40000, 2.1, 2.3, 2.6, 3.0
+ *	50000, 2.4, 2.6, 3.0, 3.5
+ *
+ * and are bilinearly interpolated using csv_interp2.
+ */
+
+struct csv_s {
+	char		sep;
+	unsigned	num_cols;
+	unsigned	num_rows;
+	char		**names;
+	char		**units;
+	char		***rows;
+	/* 2D table layout, see csv_interp2 */
+	unsigned	num_grid;
+	double		*grid_y;
+	vect2_t		**grid_curves;
+};
+
+typedef struct {
+	const char	*name;
+	double		mult;
+	double		offset;
+} unit_conv_t;
+
+/*
+ * Base units: meters, m/s, kg, kg/s, seconds, Kelvin, Pascal, liters
+ * and degrees.
+ */
+static const unit_conv_t units[] = {
+    { "m", 1, 0 },
+    { "ft", 0.3048, 0 },
+    { "km", 1000, 0 },
+    { "nm", 1852, 0 },
+    { "sm", 1609.344, 0 },
+    { "mps", 1, 0 },
+    { "m/s", 1, 0 },
+    { "kt", 1852.0 / 3600, 0 },
+    { "kts", 1852.0 / 3600, 0 },
+    { "kph", 1 / 3.6, 0 },
+    { "kmh", 1 / 3.6, 0 },
+    { "km/h", 1 / 3.6, 0 },
+    { "fpm", 0.3048 / 60, 0 },
+    { "ft/min", 0.3048 / 60, 0 },
+    { "kg", 1, 0 },
+    { "lb", 0.45359237, 0 },
+    { "lbs", 0.45359237, 0 },
+    { "t", 1000, 0 },
+    { "kgh", 1 / 3600.0, 0 },
+    { "kg/h", 1 / 3600.0, 0 },
+    { "pph", 0.45359237 / 3600, 0 },
+    { "lb/h", 0.45359237 / 3600, 0 },
+    { "s", 1, 0 },
+    { "sec", 1, 0 },
+    { "min", 60, 0 },
+    { "h", 3600, 0 },
+    { "hr", 3600, 0 },
+    { "k", 1, 0 },
+    { "c", 1, 273.15 },
+    { "degc", 1, 273.15 },
+    { "f", 5.0 / 9, 459.67 * 5.0 / 9 },
+    { "degf", 5.0 / 9, 459.67 * 5.0 / 9 },
+    { "pa", 1, 0 },
+    { "hpa", 100, 0 },
+    { "mb", 100, 0 },
+    { "inhg", 3386.389, 0 },
+    { "psi", 6894.757, 0 },
+    { "l", 1, 0 },
+    { "gal", 3.785411784, 0 },
+    { "deg", 1, 0 },
+    { "rad", 180 / M_PI, 0 }
+};
+
+/*
+ * Looks up the conversion of a unit to its base unit. Lookup is case
+ * insensitive. A value `x' in `unit' converts to the base unit as
+ * `x * mult + offset'. Either of `mult' and `offset' may be NULL.
+ *
+ * @return B_TRUE if the unit is known, B_FALSE otherwise.
+ */
+bool_t
+csv_unit_conv(const char *unit, double *mult, double *offset)
+{
+	ASSERT(unit != NULL);
+	for (size_t i = 0; i < ARRAY_NUM_ELEM(units); i++) {
+		if (strcasecmp(unit, units[i].name) == 0) {
+			if (mult != NULL)
+				*mult = units[i].mult;
+			if (offset != NULL)
+				*offset = units[i].offset;
+			return (B_TRUE);
+		}
+	}
+	return (B_FALSE);
+}
+
+/*
+ * Converts a numeric field to a double. If `sep' isn't a comma, a comma
+ * in the number is accepted as a decimal mark.
+ *
+ * @return The parsed number, or NAN if the field is empty or isn't a
+ *	valid number.
+ */
+double
+csv_str2d(const char *str, char sep)
+{
+	char buf[64];
+	char *end;
+	double val;
+
+	ASSERT(str != NULL);
+	while (isspace(*str))
+		str++;
+	if (*str == 0)
+		return (NAN);
+	lacf_strlcpy(buf, str, sizeof (buf));
+	if (sep != ',') {
+		for (char *p = buf; *p != 0; p++) {
+			if (*p == ',')
+				*p = '.';
+		}
+	}
+	val = strtod(buf, &end);
+	while (isspace(*end))
+		end++;
+	if (end == buf || *end != 0)
+		return (NAN);
+
+	return (val);
+}
+
+static char
+detect_sep(const char *buf)
+{
+	unsigned n_comma = 0, n_semi = 0, n_tab = 0;
+	bool_t quoted = B_FALSE;
+
+	/* skip leading comment and empty lines */
+	for (;;) {
+		while (isspace(*buf))
+			buf++;
+		if (*buf != '#')
+			break;
+		buf += strcspn(buf, "\n");
+	}
+	for (; *buf != 0 && (quoted || (*buf != '\n' && *buf != '\r'));
+	    buf++) {
+		if (*buf == '"')
+			quoted = !quoted;
+		else if (!quoted && *buf == ',')
+			n_comma++;
+		else if (!quoted && *buf == ';')
+			n_semi++;
+		else if (!quoted && *buf == '\t')
+			n_tab++;
+	}
+	if (n_tab > n_comma && n_tab > n_semi)
+		return ('\t');
+	if (n_semi > n_comma)
+		return (';');
+	return (',');
+}
+
+static bool_t
+is_blank(char c, char sep)
+{
+	return ((c == ' ' || c == '\t') && c != sep);
+}
+
+/*
+ * Parses a single field starting at `*pp' and advances `*pp' to the
+ * separator or line break which terminates it.
+ */
+static char *
+parse_field(const char **pp, char sep)
+{
+	const char *p = *pp;
+	char *field = safe_calloc(strlen(p) + 1, 1);
+	size_t len = 0;
+
+	while (is_blank(*p, sep))
+		p++;
+	if (*p == '"') {
+		for (p++; *p != 0; p++) {
+			if (*p == '"') {
+				if (p[1] != '"') {
+					p++;
+					break;
+				}
+				p++;
+			}
+			field[len++] = *p;
+		}
+		/* ignore any junk between the closing quote and separator */
+		while (*p != 0 && *p != sep && *p != '\n' && *p != '\r')
+			p++;
+	} else {
+		while (*p != 0 && *p != sep && *p != '\n' && *p != '\r')
+			field[len++] = *p++;
+		while (len > 0 && is_blank(field[len - 1], sep))
+			len--;
+	}
+	field[len] = 0;
+	*pp = p;
+
+	return (field);
+}
+
+/*
+ * Parses a single record, skipping comment and blank lines.
+ *
+ * @return The record's fields, or NULL at the end of the input.
+ */
+static char **
+parse_record(const char **pp, char sep, unsigned *num_fields)
+{
+	const char *p = *pp;
+	char **fields = NULL;
+	unsigned n = 0;
+
+	for (;;) {
+		const char *q = p;
+
+		while (is_blank(*q, sep))
+			q++;
+		if (*q == '#') {
+			p = q + strcspn(q, "\n");
+		} else if (*q == '\r' || *q == '\n') {
+			p = q;
+		} else {
+			break;
+		}
+		while (*p == '\r' || *p == '\n')
+			p++;
+	}
+	if (*p == 0) {
+		*pp = p;
+		return (NULL);
+	}
+	for (;;) {
+		fields = safe_realloc(fields, (n + 1) * sizeof (*fields));
+		fields[n++] = parse_field(&p, sep);
+		if (*p != sep)
+			break;
+		p++;
+	}
+	if (*p == '\r')
+		p++;
+	if (*p == '\n')
+		p++;
+	*pp = p;
+	*num_fields = n;
+
+	return (fields);
+}
+
+static void
+free_fields(char **fields, unsigned n)
+{
+	for (unsigned i = 0; i < n; i++)
+		free(fields[i]);
+	free(fields);
+}
+
+/*
+ * Splits a header field into a column name and unit.
+ */
+static void
+parse_header(const char *field, char **name, char **unit)
+{
+	const char *br = strpbrk(field, "([");
+
+	if (br != NULL && br != field) {
+		char close = (*br == '(' ? ')' : ']');
+		const char *end = strchr(br + 1, close);
+		size_t len = br - field;
+
+		if (end != NULL) {
+			while (len > 0 && isspace(field[len - 1]))
+				len--;
+			*name = safe_calloc(len + 1, 1);
+			memcpy(*name, field, len);
+			*unit = safe_calloc(end - br, 1);
+			memcpy(*unit, br + 1, end - br - 1);
+			return;
+		}
+	}
+	br = strrchr(field, '_');
+	if (br != NULL && br != field && csv_unit_conv(br + 1, NULL, NULL)) {
+		*name = safe_calloc(br - field + 1, 1);
+		memcpy(*name, field, br - field);
+		*unit = safe_strdup(br + 1);
+		return;
+	}
+	*name = safe_strdup(field);
+	*unit = safe_strdup("");
+}
+
+static int
+vect2_x_compar(const void *a, const void *b)
+{
+	const vect2_t *va = a, *vb = b;
+
+	if (va->x < vb->x)
+		return (-1);
+	if (va->x > vb->x)
+		return (1);
+	return (0);
+}
+
+/*
+ * If the header names following the first column are all numeric, the
+ * table is a 2D table and we precompute its column curves.
+ */
+static void
+setup_grid(csv_t *csv)
+{
+	if (csv->num_cols < 3)
+		return;
+	for (unsigned c = 1; c < csv->num_cols; c++) {
+		if (isnan(csv_str2d(csv->names[c], csv->sep)))
+			return;
+	}
+	csv->num_grid = csv->num_cols - 1;
+	csv->grid_y = safe_calloc(csv->num_grid, sizeof (*csv->grid_y));
+	csv->grid_curves = safe_calloc(csv->num_grid,
+	    sizeof (*csv->grid_curves));
+	for (unsigned i = 0; i < csv->num_grid; i++) {
+		csv->grid_y[i] = csv_str2d(csv->names[i + 1], csv->sep);
+		csv->grid_curves[i] = csv_get_curve(csv, 0, i + 1, B_FALSE,
+		    NULL);
+		if (i > 0 && csv->grid_y[i] <= csv->grid_y[i - 1]) {
+			logMsg("CSV 2D table column breakpoints must be "
+			    "strictly increasing, disabling csv_interp2");
+			csv->num_grid = 0;
+			return;
+		}
+	}
+}
+
+/*
+ * Parses a CSV table from a NUL-terminated string. See the description
+ * at the top of this file for the accepted format.
+ *
+ * @param sep Field separator, or 0 to auto-detect it.
+ *
+ * @return The parsed table, which must be freed using csv_free, or NULL
+ *	if the input contains no header line.
+ */
+csv_t *
+csv_parse(const char *buf, char sep)
+{
+	csv_t *csv;
+	char **fields;
+	unsigned n;
+
+	ASSERT(buf != NULL);
+	if (sep == 0)
+		sep = detect_sep(buf);
+	fields = parse_record(&buf, sep, &n);
+	if (fields == NULL)
+		return (NULL);
+
+	csv = safe_calloc(1, sizeof (*csv));
+	csv->sep = sep;
+	csv->num_cols = n;
+	csv->names = safe_calloc(n, sizeof (*csv->names));
+	csv->units = safe_calloc(n, sizeof (*csv->units));
+	for (unsigned i = 0; i < n; i++)
+		parse_header(fields[i], &csv->names[i], &csv->units[i]);
+	free_fields(fields, n);
+
+	while ((fields = parse_record(&buf, sep, &n)) != NULL) {
+		char **row = safe_calloc(csv->num_cols, sizeof (*row));
+
+		for (unsigned i = 0; i < csv->num_cols; i++) {
+			if (i < n) {
+				row[i] = fields[i];
+				fields[i] = NULL;
+			} else {
+				row[i] = safe_strdup("");
+			}
+		}
+		free_fields(fields, n);
+		csv->rows = safe_realloc(csv->rows,
+		    (csv->num_rows + 1) * sizeof (*csv->rows));
+		csv->rows[csv->num_rows++] = row;
+	}
+	setup_grid(csv);
+
+	return (csv);
+}
+
+/*
+ * Same as csv_parse, but reads the table from a file.
+ *
+ * @return The parsed table, or NULL if the file couldn't be read or
+ *	contains no header line.
+ */
+csv_t *
+csv_read(const char *path, char sep)
+{
+	char *buf = file2str_name(NULL, path);
+	csv_t *csv;
+
+	if (buf == NULL)
+		return (NULL);
+	csv = csv_parse(buf, sep);
+	if (csv == NULL)
+		logMsg("Error reading %s: file contains no CSV header", path);
+	free(buf);
+
+	return (csv);
+}
+
+void
+csv_free(csv_t *csv)
+{
+	if (csv == NULL)
+		return;
+	for (unsigned i = 0; i < csv->num_rows; i++)
+		free_fields(csv->rows[i], csv->num_cols);
+	free(csv->rows);
+	free_fields(csv->names, csv->num_cols);
+	free_fields(csv->units, csv->num_cols);
+	for (unsigned i = 0; csv->grid_curves != NULL &&
+	    i < csv->num_cols - 1; i++)
+		free(csv->grid_curves[i]);
+	free(csv->grid_curves);
+	free(csv->grid_y);
+	free(csv);
+}
+
+unsigned
+csv_get_num_cols(const csv_t *csv)
+{
+	ASSERT(csv != NULL);
+	return (csv->num_cols);
+}
+
+unsigned
+csv_get_num_rows(const csv_t *csv)
+{
+	ASSERT(csv != NULL);
+	return (csv->num_rows);
+}
+
+/*
+ * Returns a column's name, with any unit stripped.
+ */
+const char *
+csv_col_name(const csv_t *csv, unsigned col)
+{
+	ASSERT(csv != NULL);
+	ASSERT3U(col, <, csv->num_cols);
+	return (csv->names[col]);
+}
+
+/*
+ * Returns a column's unit as written in the header, or an empty string
+ * if the column has no unit.
+ */
+const char *
+csv_col_unit(const csv_t *csv, unsigned col)
+{
+	ASSERT(csv != NULL);
+	ASSERT3U(col, <, csv->num_cols);
+	return (csv->units[col]);
+}
+
+/*
+ * Looks up a column by its name (without the unit). Lookup is case
+ * insensitive.
+ *
+ * @return The column index, or -1 if no such column exists.
+ */
+int
+csv_find_col(const csv_t *csv, const char *name)
+{
+	ASSERT(csv != NULL);
+	ASSERT(name != NULL);
+	for (unsigned i = 0; i < csv->num_cols; i++) {
+		if (strcasecmp(csv->names[i], name) == 0)
+			return (i);
+	}
+	return (-1);
+}
+
+const char *
+csv_get_str(const csv_t *csv, unsigned row, unsigned col)
+{
+	ASSERT(csv != NULL);
+	ASSERT3U(row, <, csv->num_rows);
+	ASSERT3U(col, <, csv->num_cols);
+	return (csv->rows[row][col]);
+}
+
+/*
+ * Returns a field's numeric value as written, or NAN if the field is
+ * empty or not a number.
+ */
+double
+csv_get_d(const csv_t *csv, unsigned row, unsigned col)
+{
+	return (csv_str2d(csv_get_str(csv, row, col), csv->sep));
+}
+
+/*
+ * Same as csv_get_d, but converts the value from the column's unit to
+ * its base unit (see csv_unit_conv). Values in columns without a known
+ * unit are returned unconverted.
+ */
+double
+csv_get_base(const csv_t *csv, unsigned row, unsigned col)
+{
+	double val = csv_get_d(csv, row, col);
+	double mult, offset;
+
+	if (csv_unit_conv(csv->units[col], &mult, &offset))
+		return (val * mult + offset);
+	return (val);
+}
+
+/*
+ * Extracts two columns as a curve suitable for use with fx_lin_multi.
+ * Rows in which either value is missing are skipped and the points are
+ * sorted by increasing X. Of rows with duplicate X values, only the
+ * first is used.
+ *
+ * @param base_units If B_TRUE, values are converted to base units
+ *	(see csv_get_base), otherwise they're returned as written.
+ * @param num_pts Optional return of the number of points in the curve.
+ *
+ * @return A NULL_VECT2-terminated array of points, which must be freed
+ *	by the caller using lacf_free, or NULL if the columns contain
+ *	fewer than 2 usable rows.
+ */
+vect2_t *
+csv_get_curve(const csv_t *csv, unsigned xcol, unsigned ycol,
+    bool_t base_units, size_t *num_pts)
+{
+	vect2_t *pts;
+	size_t n = 0;
+
+	ASSERT(csv != NULL);
+	ASSERT3U(xcol, <, csv->num_cols);
+	ASSERT3U(ycol, <, csv->num_cols);
+
+	pts = safe_calloc(csv->num_rows + 1, sizeof (*pts));
+	for (unsigned i = 0; i < csv->num_rows; i++) {
+		double x = (base_units ? csv_get_base(csv, i, xcol) :
+		    csv_get_d(csv, i, xcol));
+		double y = (base_units ? csv_get_base(csv, i, ycol) :
+		    csv_get_d(csv, i, ycol));
+
+		if (!isnan(x) && !isnan(y))
+			pts[n++] = VECT2(x, y);
+	}
+	if (n < 2) {
+		free(pts);
+		return (NULL);
+	}
+	qsort(pts, n, sizeof (*pts), vect2_x_compar);
+	/* fx_lin_multi requires strictly increasing X, drop duplicates */
+	for (size_t i = 1; i < n;) {
+		if (pts[i].x == pts[i - 1].x) {
+			memmove(&pts[i], &pts[i + 1],
+			    (n - i - 1) * sizeof (*pts));
+			n--;
+		} else {
+			i++;
+		}
+	}
+	if (n < 2) {
+		free(pts);
+		return (NULL);
+	}
+	pts[n] = NULL_VECT2;
+	if (num_pts != NULL)
+		*num_pts = n;
+
+	return (pts);
+}
+
+/*
+ * Same as fx_lin_multi, but clamps `x' to the curve's range.
+ */
+static double
+curve_lookup(double x, const vect2_t *pts)
+{
+	double val = fx_lin_multi(x, pts, B_FALSE);
+
+	if (!isnan(val))
+		return (val);
+	if (x < pts[0].x)
+		return (pts[0].y);
+	while (!IS_NULL_VECT(pts[1]))
+		pts++;
+	return (pts[0].y);
+}
+
+/*
+ * Bilinearly interpolates a 2D table (see the top of this file for the
+ * layout). `x' is looked up in the first column and `y' in the column
+ * breakpoints. Values outside of the table are clamped to its edges.
+ * Values are interpolated as written, without unit conversion.
+ *
+ * @return The interpolated value, or NAN if the table isn't a 2D table.
+ */
+double
+csv_interp2(const csv_t *csv, double x, double y)
+{
+	unsigned i;
+	double v1, v2;
+
+	ASSERT(csv != NULL);
+	if (csv->num_grid == 0)
+		return (NAN);
+	for (i = 0; i < csv->num_grid; i++) {
+		if (csv->grid_curves[i] == NULL)
+			return (NAN);
+	}
+	y = clamp(y, csv->grid_y[0], csv->grid_y[csv->num_grid - 1]);
+	for (i = 0; i + 2 < csv->num_grid && y > csv->grid_y[i + 1]; i++)
+		;
+	v1 = curve_lookup(x, csv->grid_curves[i]);
+	v2 = curve_lookup(x, csv->grid_curves[i + 1]);
+
+	return (fx_lin(y, csv->grid_y[i], v1, csv->grid_y[i + 1], v2));
+}
+
+static void
+write_field(FILE *fp, char sep, const char *field)
+{
+	size_t len = strlen(field);
+
+	if (strchr(field, sep) != NULL || strpbrk(field, "\"\r\n") != NULL ||
+	    (len > 0 && (isspace(field[0]) || isspace(field[len - 1])))) {
+		fputc('"', fp);
+		for (const char *p = field; *p != 0; p++) {
+			if (*p == '"')
+				fputc('"', fp);
+			fputc(*p, fp);
+		}
+		fputc('"', fp);
+	} else {
+		fputs(field, fp);
+	}
+}
+
+/*
+ * Writes a single CSV record, quoting fields as necessary so that they
+ * read back identically using csv_parse. NULL fields are written as
+ * empty fields.
+ */
+void
+csv_write_row(FILE *fp, char sep, const char *const *fields, size_t n)
+{
+	ASSERT(fp != NULL);
+	ASSERT(fields != NULL || n == 0);
+	for (size_t i = 0; i < n; i++) {
+		if (i > 0)
+			fputc(sep, fp);
+		if (fields[i] != NULL)
+			write_field(fp, sep, fields[i]);
+	}
+	fputc('\n', fp);
+}
+
+/*
+ * Writes a single CSV record of numbers, using `prec' decimal places.
+ * Numbers are always written with a decimal point. NAN values are
+ * written as empty fields.
+ */
+void
+csv_write_row_d(FILE *fp, char sep, const double *vals, size_t n, int prec)
+{
+	ASSERT(fp != NULL);
+	ASSERT(vals != NULL || n == 0);
+	ASSERT3S(prec, >=, 0);
+	for (size_t i = 0; i < n; i++) {
+		if (i > 0)
+			fputc(sep, fp);
+		if (!isnan(vals[i]))
+			fprintf(fp, "%.*f", prec, vals[i]);
+	}
+	fputc('\n', fp);
+}
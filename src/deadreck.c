@@ -0,0 +1,178 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <math.h>
+#include <string.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/deadreck.h>
+#include <acfutils/helpers.h>
+#include <acfutils/math.h>
+#include <acfutils/perf.h>
+
+#define	DFL_DRIFT_RATE	(NM2MET(1) / 3600.0)	/* 1 NM/hr */
+
+static double
+err_at(const deadreck_t *dr, double t_fix)
+{
+	/*
+	 * Sensor drift and wind estimation errors are independent, and
+	 * both make the position error grow linearly with time, so we
+	 * combine them (and the initial uncertainty) as a root sum square.
+	 */
+	return (sqrt(POW2(dr->err_fix) + (POW2(dr->drift_rate) +
+	    POW2(dr->wind_err)) * POW2(t_fix)));
+}
+
+/*
+ * Initializes a dead reckoning propagator. The propagator estimates the
+ * position of a vehicle from its last known position, its heading and
+ * true airspeed and the wind, together with an estimate of how uncertain
+ * that position has become. This is how an IRS-only navigation system
+ * operates, and it can also be used to extrapolate the position of
+ * traffic between position reports.
+ *
+ * Typical usage:
+ *
+ *	deadreck_t dr;
+ *	deadreck_init(&dr, last_known_pos, 0);
+ *	...on every frame:
+ *	deadreck_set_vel(&dr, hdg, tas, wind_dir, wind_spd);
+ *	deadreck_step(&dr, d_t);
+ *	...use dr.pos and dr.err...
+ *	...when an external position fix becomes available:
+ *	deadreck_fix(&dr, fix_pos, fix_err);
+ *
+ * The velocity inputs are initially all zero, so the vehicle doesn't move
+ * until you call deadreck_set_vel. The position uncertainty grows from
+ * `err' with a drift rate of 1 NM per hour, which is typical of an IRS.
+ * Use deadreck_set_err_params to change it.
+ *
+ * @param pos Initial position.
+ * @param err Uncertainty of the initial position in meters.
+ */
+void
+deadreck_init(deadreck_t *dr, geo_pos2_t pos, double err)
+{
+	ASSERT(dr != NULL);
+	memset(dr, 0, sizeof (*dr));
+	dr->drift_rate = DFL_DRIFT_RATE;
+	deadreck_fix(dr, pos, err);
+}
+
+/*
+ * Updates the propagator with an external position fix (e.g. from GPS or
+ * radio navigation). This resets the uncertainty to that of the fix.
+ */
+void
+deadreck_fix(deadreck_t *dr, geo_pos2_t pos, double err)
+{
+	ASSERT(dr != NULL);
+	ASSERT(!IS_NULL_GEO_POS2(pos));
+	ASSERT3F(err, >=, 0);
+
+	dr->pos = pos;
+	dr->err_fix = err;
+	dr->err = err;
+	dr->t_fix = 0;
+}
+
+/*
+ * Sets the velocity inputs of the propagator. These are used for all
+ * subsequent steps until changed.
+ *
+ * @param hdg True heading in degrees.
+ * @param tas True airspeed in m/s.
+ * @param wind_dir True direction in degrees the wind is blowing from.
+ * @param wind_spd Wind speed in m/s.
+ */
+void
+deadreck_set_vel(deadreck_t *dr, double hdg, double tas, double wind_dir,
+    double wind_spd)
+{
+	vect2_t air, wind, gnd;
+
+	ASSERT(dr != NULL);
+	ASSERT3F(tas, >=, 0);
+	ASSERT3F(wind_spd, >=, 0);
+
+	dr->hdg = normalize_hdg(hdg);
+	dr->tas = tas;
+	dr->wind_dir = normalize_hdg(wind_dir);
+	dr->wind_spd = wind_spd;
+
+	air = vect2_scmul(hdg2dir(dr->hdg), tas);
+	wind = vect2_scmul(hdg2dir(dr->wind_dir), -wind_spd);
+	gnd = vect2_add(air, wind);
+	dr->gs = vect2_abs(gnd);
+	/* with no ground speed, the track is undefined, so keep heading */
+	dr->trk = (dr->gs > 0 ? dir2hdg(gnd) : dr->hdg);
+}
+
+/*
+ * Sets the rates at which the position uncertainty grows over time.
+ *
+ * @param drift_rate Drift rate of the heading and airspeed sensors
+ *	(e.g. of an IRS) in m/s.
+ * @param wind_err Uncertainty of the wind input in m/s.
+ */
+void
+deadreck_set_err_params(deadreck_t *dr, double drift_rate, double wind_err)
+{
+	ASSERT(dr != NULL);
+	ASSERT3F(drift_rate, >=, 0);
+	ASSERT3F(wind_err, >=, 0);
+	dr->drift_rate = drift_rate;
+	dr->wind_err = wind_err;
+	dr->err = err_at(dr, dr->t_fix);
+}
+
+/*
+ * Advances the propagator by `d_t' seconds along the current ground
+ * track and speed.
+ */
+void
+deadreck_step(deadreck_t *dr, double d_t)
+{
+	ASSERT(dr != NULL);
+	ASSERT3F(d_t, >=, 0);
+	deadreck_predict(dr, d_t, &dr->pos, &dr->err);
+	dr->t_fix += d_t;
+}
+
+/*
+ * Same as deadreck_step, but doesn't modify the propagator. Returns the
+ * position and uncertainty the propagator would have after `d_t' seconds
+ * in `pos' and `err'. Either may be NULL if you don't need it.
+ */
+void
+deadreck_predict(const deadreck_t *dr, double d_t, geo_pos2_t *pos,
+    double *err)
+{
+	ASSERT(dr != NULL);
+	ASSERT3F(d_t, >=, 0);
+
+	if (pos != NULL) {
+		if (dr->gs * d_t > 0)
+			*pos = rhumb_displace(dr->pos, dr->trk, dr->gs * d_t);
+		else
+			*pos = dr->pos;
+	}
+	if (err != NULL)
+		*err = err_at(dr, dr->t_fix + d_t);
+}
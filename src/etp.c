@@ -0,0 +1,383 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <math.h>
+#include <string.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/etp.h>
+#include <acfutils/helpers.h>
+#include <acfutils/math.h>
+#include <acfutils/safe_alloc.h>
+
+/* Solutions are refined by bisection down to this distance. */
+#define	BISECT_PREC	1.0	/* meters */
+
+typedef struct {
+	const etp_wpt_t	*wpts;
+	size_t		num_wpts;
+	double		*dist;		/* cumulative, num_wpts entries */
+	double		*trk;		/* mean leg track, num_wpts - 1 */
+	double		tas;
+	double		fuel_flow;
+	double		div_tas;
+	double		div_fuel_flow;
+} route_t;
+
+static geo_pos2_t
+gc_interp(geo_pos2_t a, geo_pos2_t b, double frac)
+{
+	double lat1 = DEG2RAD(a.lat), lon1 = DEG2RAD(a.lon);
+	double lat2 = DEG2RAD(b.lat), lon2 = DEG2RAD(b.lon);
+	double d = acos(clamp(sin(lat1) * sin(lat2) +
+	    cos(lat1) * cos(lat2) * cos(lon2 - lon1), -1, 1));
+	double A, B, x, y, z;
+
+	if (d < 1e-12)
+		return (a);
+	A = sin((1 - frac) * d) / sin(d);
+	B = sin(frac * d) / sin(d);
+	x = A * cos(lat1) * cos(lon1) + B * cos(lat2) * cos(lon2);
+	y = A * cos(lat1) * sin(lon1) + B * cos(lat2) * sin(lon2);
+	z = A * sin(lat1) + B * sin(lat2);
+
+	return (GEO_POS2(RAD2DEG(atan2(z, sqrt(POW2(x) + POW2(y)))),
+	    RAD2DEG(atan2(y, x))));
+}
+
+static bool_t
+route_init(route_t *rt, const etp_wpt_t *wpts, size_t num_wpts,
+    const etp_perf_t *perf)
+{
+	ASSERT(wpts != NULL);
+	ASSERT(perf != NULL);
+
+	memset(rt, 0, sizeof (*rt));
+	if (num_wpts < 2 || perf->tas <= 0)
+		return (B_FALSE);
+	rt->wpts = wpts;
+	rt->num_wpts = num_wpts;
+	rt->dist = safe_calloc(num_wpts, sizeof (*rt->dist));
+	rt->trk = safe_calloc(num_wpts - 1, sizeof (*rt->trk));
+	rt->tas = perf->tas;
+	rt->fuel_flow = perf->fuel_flow;
+	rt->div_tas = (perf->div_tas > 0 ? perf->div_tas : perf->tas);
+	rt->div_fuel_flow = (perf->div_fuel_flow > 0 ?
+	    perf->div_fuel_flow : perf->fuel_flow);
+
+	for (size_t i = 0; i + 1 < num_wpts; i++) {
+		geo_pos2_t p1 = wpts[i].pos, p2 = wpts[i + 1].pos;
+		/*
+		 * The track along a great circle changes as we fly it, so
+		 * for the purposes of wind correction, we use the track at
+		 * the leg's midpoint.
+		 */
+		rt->dist[i + 1] = rt->dist[i] + gc_distance(p1, p2);
+		rt->trk[i] = gc_point_hdg(gc_interp(p1, p2, 0.5), p2);
+	}
+	if (rt->dist[num_wpts - 1] <= 0) {
+		free(rt->dist);
+		free(rt->trk);
+		return (B_FALSE);
+	}
+
+	return (B_TRUE);
+}
+
+static void
+route_fini(route_t *rt)
+{
+	free(rt->dist);
+	free(rt->trk);
+}
+
+static inline double
+route_len(const route_t *rt)
+{
+	return (rt->dist[rt->num_wpts - 1]);
+}
+
+/*
+ * Returns the ground speed achieved on track `trk' at `tas' in the wind
+ * of leg `leg', or 0 if the wind is too strong to make progress.
+ */
+static double
+leg_gs(const route_t *rt, size_t leg, double trk, double tas)
+{
+	const etp_wpt_t *wpt = &rt->wpts[leg];
+	double angle = DEG2RAD(wpt->wind_dir - trk);
+	double hwind = wpt->wind_spd * cos(angle);
+	double xwind = wpt->wind_spd * sin(angle);
+
+	if (fabs(xwind) >= tas)
+		return (0);
+	return (MAX(sqrt(POW2(tas) - POW2(xwind)) - hwind, 0));
+}
+
+/*
+ * Returns the time needed to fly along the route from distance `from' to
+ * distance `to'. If `to' is less than `from', the route is flown in
+ * reverse. Returns INFINITY if the wind on any of the legs prevents us
+ * from making progress.
+ */
+static double
+route_time(const route_t *rt, double from, double to, double tas)
+{
+	bool_t reverse = (to < from);
+	double lo = MIN(from, to), hi = MAX(from, to);
+	double t = 0;
+
+	for (size_t i = 0; i + 1 < rt->num_wpts; i++) {
+		double len = MIN(hi, rt->dist[i + 1]) - MAX(lo, rt->dist[i]);
+		double trk, gs;
+
+		if (len <= 0)
+			continue;
+		trk = (reverse ? normalize_hdg(rt->trk[i] + 180) : rt->trk[i]);
+		gs = leg_gs(rt, i, trk, tas);
+		if (gs <= 0)
+			return (INFINITY);
+		t += len / gs;
+	}
+
+	return (t);
+}
+
+static geo_pos2_t
+route_pos(const route_t *rt, double d)
+{
+	for (size_t i = 0; i + 1 < rt->num_wpts; i++) {
+		double len = rt->dist[i + 1] - rt->dist[i];
+
+		if (d > rt->dist[i + 1] && i + 2 < rt->num_wpts)
+			continue;
+		if (len <= 0)
+			return (rt->wpts[i].pos);
+		return (gc_interp(rt->wpts[i].pos, rt->wpts[i + 1].pos,
+		    clamp((d - rt->dist[i]) / len, 0, 1)));
+	}
+	VERIFY_FAIL();
+}
+
+/*
+ * Fills in the position, time and fuel of a point at distance `d' along
+ * the route. Returns B_FALSE if the point can't be reached due to wind.
+ */
+static bool_t
+route_point(const route_t *rt, const etp_perf_t *perf, double d,
+    etp_point_t *pt)
+{
+	memset(pt, 0, sizeof (*pt));
+	pt->pos = route_pos(rt, d);
+	pt->dist = d;
+	pt->time = route_time(rt, 0, d, rt->tas);
+	if (isinf(pt->time))
+		return (B_FALSE);
+	pt->fuel = perf->fuel - pt->time * rt->fuel_flow;
+
+	return (B_TRUE);
+}
+
+/*
+ * Fills in the diversion from a point to whichever end of the route takes
+ * longer to reach, at the diversion airspeed.
+ */
+static bool_t
+route_div(const route_t *rt, etp_point_t *pt)
+{
+	double t_back = route_time(rt, pt->dist, 0, rt->div_tas);
+	double t_fwd = route_time(rt, pt->dist, route_len(rt), rt->div_tas);
+
+	pt->div_time = MAX(t_back, t_fwd);
+	if (isinf(pt->div_time))
+		return (B_FALSE);
+	pt->div_fuel = pt->div_time * rt->div_fuel_flow;
+
+	return (B_TRUE);
+}
+
+/*
+ * Returns the total great circle length of a route in meters.
+ *
+ * @param wpts Route waypoints. Only their positions are used.
+ * @param num_wpts Number of elements in `wpts'.
+ */
+double
+etp_route_dist(const etp_wpt_t *wpts, size_t num_wpts)
+{
+	double d = 0;
+
+	ASSERT(wpts != NULL || num_wpts == 0);
+	for (size_t i = 0; i + 1 < num_wpts; i++)
+		d += gc_distance(wpts[i].pos, wpts[i + 1].pos);
+
+	return (d);
+}
+
+/*
+ * Calculates the midpoint of a route, i.e. the point halfway along the
+ * route's great circle legs (not the midpoint between its end points).
+ * The returned point's time and fuel are for flying to it at the cruise
+ * airspeed. Its diversion time and fuel are for flying from it to the
+ * farther (in time) of the route's two end points at the diversion
+ * airspeed.
+ *
+ * @param wpts The route, at least two waypoints. The wind on each leg
+ *	is given by the leg's starting waypoint (the wind in the last
+ *	waypoint is ignored).
+ * @param num_wpts Number of elements in `wpts'.
+ * @param perf Aircraft performance and fuel state at the route start.
+ * @param pt Filled in with the computed point.
+ *
+ * @return B_TRUE on success, B_FALSE if the route is degenerate (fewer
+ *	than two waypoints, zero length or zero airspeed), or if the wind
+ *	is too strong to complete the route or the diversion.
+ */
+bool_t
+etp_midpoint(const etp_wpt_t *wpts, size_t num_wpts, const etp_perf_t *perf,
+    etp_point_t *pt)
+{
+	route_t rt;
+	bool_t res;
+
+	ASSERT(pt != NULL);
+	if (!route_init(&rt, wpts, num_wpts, perf))
+		return (B_FALSE);
+	res = route_point(&rt, perf, route_len(&rt) / 2, pt) &&
+	    route_div(&rt, pt);
+	route_fini(&rt);
+
+	return (res);
+}
+
+/*
+ * Calculates the equal time point (ETP, also called the critical point)
+ * between the start and end of a route. From the ETP, it takes the same
+ * amount of time to fly back to the route start as to continue on to the
+ * route end, flying along the route at the diversion airspeed and taking
+ * into account the winds on each leg. For oceanic operations, the route
+ * is normally the oceanic portion of the flight plan between the two
+ * en-route alternates, and the diversion airspeed and fuel flow are those
+ * of an engine-out or depressurized descent.
+ *
+ * With no wind, the ETP coincides with the route midpoint. A headwind on
+ * the way out moves the ETP toward the route end, since returning is
+ * quicker.
+ *
+ * The arguments and return value are the same as in etp_midpoint. The
+ * returned point's diversion time and fuel are for flying to either
+ * end of the route.
+ */
+bool_t
+etp_calc(const etp_wpt_t *wpts, size_t num_wpts, const etp_perf_t *perf,
+    etp_point_t *pt)
+{
+	route_t rt;
+	double lo, hi;
+	bool_t res;
+
+	ASSERT(pt != NULL);
+	if (!route_init(&rt, wpts, num_wpts, perf))
+		return (B_FALSE);
+	/*
+	 * Time back to the start minus time to the end grows monotonically
+	 * as we progress along the route, so we simply bisect for its zero
+	 * crossing.
+	 */
+	lo = 0;
+	hi = route_len(&rt);
+	while (hi - lo > BISECT_PREC) {
+		double d = (lo + hi) / 2;
+		double t_back = route_time(&rt, d, 0, rt.div_tas);
+		double t_fwd = route_time(&rt, d, route_len(&rt), rt.div_tas);
+
+		if (isinf(t_back) && isinf(t_fwd)) {
+			route_fini(&rt);
+			return (B_FALSE);
+		}
+		if (t_back < t_fwd)
+			lo = d;
+		else
+			hi = d;
+	}
+	res = route_point(&rt, perf, (lo + hi) / 2, pt) && route_div(&rt, pt);
+	route_fini(&rt);
+
+	return (res);
+}
+
+/*
+ * Calculates the point of no return (PNR) of a route. This is the farthest
+ * point along the route from which the aircraft can still fly back to the
+ * route start and land there with the reserve fuel remaining, with both
+ * the outbound and return legs flown at the cruise airspeed and fuel
+ * flow. The returned point's diversion time and fuel are for the return
+ * to the route start.
+ *
+ * The arguments are the same as in etp_midpoint.
+ *
+ * @return B_TRUE if the PNR lies on the route. B_FALSE if the route is
+ *	degenerate, if the fuel on board doesn't cover the reserve, or if
+ *	the PNR lies beyond the end of the route (i.e. the aircraft can fly
+ *	the entire route and return). In the last case, `pt' is still
+ *	filled in with the route end point.
+ */
+bool_t
+etp_pnr_calc(const etp_wpt_t *wpts, size_t num_wpts, const etp_perf_t *perf,
+    etp_point_t *pt)
+{
+	route_t rt;
+	double usable, lo, hi;
+	bool_t res;
+
+	ASSERT(pt != NULL);
+	if (!route_init(&rt, wpts, num_wpts, perf))
+		return (B_FALSE);
+	usable = perf->fuel - perf->reserve;
+	if (usable < 0) {
+		route_fini(&rt);
+		return (B_FALSE);
+	}
+#define	RT_FUEL(d) \
+	((route_time(&rt, 0, (d), rt.tas) + \
+	    route_time(&rt, (d), 0, rt.tas)) * rt.fuel_flow)
+	lo = 0;
+	hi = route_len(&rt);
+	if (RT_FUEL(hi) <= usable) {
+		route_point(&rt, perf, hi, pt);
+		pt->div_time = route_time(&rt, hi, 0, rt.tas);
+		pt->div_fuel = pt->div_time * rt.fuel_flow;
+		route_fini(&rt);
+		return (B_FALSE);
+	}
+	while (hi - lo > BISECT_PREC) {
+		double d = (lo + hi) / 2;
+
+		if (RT_FUEL(d) <= usable)
+			lo = d;
+		else
+			hi = d;
+	}
+#undef	RT_FUEL
+	res = route_point(&rt, perf, lo, pt);
+	pt->div_time = route_time(&rt, lo, 0, rt.tas);
+	pt->div_fuel = pt->div_time * rt.fuel_flow;
+	route_fini(&rt);
+
+	return (res && !isinf(pt->div_time));
+}
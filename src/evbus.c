@@ -0,0 +1,366 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <stddef.h>
+#include <string.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/evbus.h>
+#include <acfutils/list.h>
+#include <acfutils/safe_alloc.h>
+#include <acfutils/thread.h>
+
+typedef struct {
+	char		*topic;
+	void		*data;
+	size_t		len;
+	list_node_t	node;
+} evbus_msg_t;
+
+struct evbus_sub_s {
+	char		*topic;
+	size_t		prefix_len;	/* for wildcard topics, else 0 */
+	evbus_cb_t	cb;
+	void		*userinfo;
+	bool_t		queued;
+	bool_t		dead;		/* unsubscribed during delivery */
+	list_t		msgs;		/* pending messages, under lock */
+	list_node_t	node;
+};
+
+struct evbus_s {
+	mutex_t		lock;
+	list_t		subs;
+	unsigned	delivering;	/* nesting depth of deliveries */
+};
+
+/*
+ * Allocates a publish/subscribe event bus. Event buses let separate
+ * subsystems of a plugin communicate without knowing about each other,
+ * similarly to how separate plugins communicate using datarefs &
+ * commands. Publishers post messages under a topic, which is an
+ * arbitrary string (e.g. "elec.bus1.fail"). Subscribers register a
+ * callback for a topic, or a group of topics if the subscription topic
+ * ends in "*" (e.g. "elec.*" matches all topics starting with "elec.").
+ *
+ * Message payloads are opaque data blocks which the bus copies when
+ * queueing, so they must be plain data structures. Payloads of the same
+ * topic should always have the same format. It's best to declare a
+ * structure for each topic in a header shared by publishers and
+ * subscribers.
+ *
+ * Subscriptions come in two kinds:
+ * - synchronous subscriptions are delivered immediately from within
+ *	evbus_publish, on the publishing thread.
+ * - queued subscriptions receive a copy of each message in a private
+ *	queue. The messages are delivered when the subscriber calls
+ *	evbus_drain or evbus_drain_all, e.g. from its flight loop callback.
+ *	This lets worker threads publish messages which are then processed
+ *	safely on the simulator's main thread.
+ *
+ * Typical usage:
+ *
+ *	evbus_t *bus = evbus_alloc();
+ *	...subscriber:
+ *	evbus_sub_t *sub = evbus_subscribe(bus, "elec.*", elec_evt_cb,
+ *	    my_data, B_TRUE);
+ *	...publisher:
+ *	bus_fail_t fail = { .bus_nr = 1 };
+ *	evbus_publish(bus, "elec.bus.fail", &fail, sizeof (fail));
+ *	...subscriber's flight loop:
+ *	evbus_drain(bus, sub);		-- calls elec_evt_cb
+ *	...cleanup:
+ *	evbus_unsubscribe(bus, sub);
+ *	evbus_free(bus);
+ *
+ * The bus is thread-safe and doesn't hold its internal lock while
+ * invoking callbacks. Subscription callbacks may themselves publish
+ * messages, as well as subscribe and unsubscribe (including removing
+ * their own subscription). Subscriptions are matched by a linear scan,
+ * so the bus is meant for tens of subscriptions, not thousands.
+ *
+ * @return The new event bus. Use evbus_free to free it.
+ */
+evbus_t *
+evbus_alloc(void)
+{
+	evbus_t *bus = safe_calloc(1, sizeof (*bus));
+
+	mutex_init(&bus->lock);
+	list_create(&bus->subs, sizeof (evbus_sub_t),
+	    offsetof(evbus_sub_t, node));
+
+	return (bus);
+}
+
+static void
+msg_free(evbus_msg_t *msg)
+{
+	free(msg->topic);
+	free(msg->data);
+	free(msg);
+}
+
+static void
+sub_free(evbus_sub_t *sub)
+{
+	evbus_msg_t *msg;
+
+	while ((msg = list_remove_head(&sub->msgs)) != NULL)
+		msg_free(msg);
+	list_destroy(&sub->msgs);
+	free(sub->topic);
+	free(sub);
+}
+
+/*
+ * Frees an event bus, including any remaining subscriptions and their
+ * undelivered messages.
+ */
+void
+evbus_free(evbus_t *bus)
+{
+	evbus_sub_t *sub;
+
+	if (bus == NULL)
+		return;
+	ASSERT0(bus->delivering);
+	while ((sub = list_remove_head(&bus->subs)) != NULL)
+		sub_free(sub);
+	list_destroy(&bus->subs);
+	mutex_destroy(&bus->lock);
+	free(bus);
+}
+
+/*
+ * Subscribes to a topic.
+ *
+ * @param topic Topic to subscribe to. If this ends in "*", all topics
+ *	starting with the preceding string are subscribed to ("*" alone
+ *	subscribes to all topics).
+ * @param cb Callback to invoke for each message.
+ * @param userinfo Optional userinfo argument to pass to `cb'.
+ * @param queued If B_FALSE, messages are delivered synchronously from
+ *	evbus_publish. If B_TRUE, messages are queued until drained using
+ *	evbus_drain or evbus_drain_all.
+ *
+ * @return A subscription handle, which remains valid until passed to
+ *	evbus_unsubscribe or the bus is freed.
+ */
+evbus_sub_t *
+evbus_subscribe(evbus_t *bus, const char *topic, evbus_cb_t cb,
+    void *userinfo, bool_t queued)
+{
+	evbus_sub_t *sub = safe_calloc(1, sizeof (*sub));
+	size_t len;
+
+	ASSERT(bus != NULL);
+	ASSERT(topic != NULL);
+	ASSERT(cb != NULL);
+
+	sub->topic = safe_strdup(topic);
+	len = strlen(topic);
+	if (len != 0 && topic[len - 1] == '*') {
+		sub->topic[len - 1] = '\0';
+		/* non-zero even for a bare "*" so we know it's a wildcard */
+		sub->prefix_len = len;
+	}
+	sub->cb = cb;
+	sub->userinfo = userinfo;
+	sub->queued = queued;
+	list_create(&sub->msgs, sizeof (evbus_msg_t),
+	    offsetof(evbus_msg_t, node));
+
+	mutex_enter(&bus->lock);
+	list_insert_tail(&bus->subs, sub);
+	mutex_exit(&bus->lock);
+
+	return (sub);
+}
+
+/*
+ * Removes a subscription. Any undelivered queued messages are discarded.
+ * No new deliveries to the subscription are started after this returns,
+ * but if another thread is concurrently publishing to or draining the
+ * subscription, a callback invocation which has already started may
+ * still be running.
+ */
+void
+evbus_unsubscribe(evbus_t *bus, evbus_sub_t *sub)
+{
+	ASSERT(bus != NULL);
+	ASSERT(sub != NULL);
+
+	mutex_enter(&bus->lock);
+	if (bus->delivering != 0) {
+		/* we're iterating the list, defer the removal */
+		sub->dead = B_TRUE;
+	} else {
+		list_remove(&bus->subs, sub);
+		sub_free(sub);
+	}
+	mutex_exit(&bus->lock);
+}
+
+static bool_t
+topic_matches(const evbus_sub_t *sub, const char *topic)
+{
+	if (sub->prefix_len != 0) {
+		return (strncmp(sub->topic, topic,
+		    sub->prefix_len - 1) == 0);
+	}
+	return (strcmp(sub->topic, topic) == 0);
+}
+
+/*
+ * Removes subscriptions which were unsubscribed during a delivery.
+ * Caller must hold bus->lock.
+ */
+static void
+reap_subs(evbus_t *bus)
+{
+	evbus_sub_t *next;
+
+	if (bus->delivering != 0)
+		return;
+	for (evbus_sub_t *sub = list_head(&bus->subs); sub != NULL;
+	    sub = next) {
+		next = list_next(&bus->subs, sub);
+		if (sub->dead) {
+			list_remove(&bus->subs, sub);
+			sub_free(sub);
+		}
+	}
+}
+
+/*
+ * Publishes a message. Synchronous subscribers of the topic are invoked
+ * immediately, queued subscribers receive a copy of the message.
+ *
+ * @param topic Topic of the message. Must not contain "*".
+ * @param data Message payload. May be NULL if `len' is 0.
+ * @param len Length of the payload in bytes.
+ */
+void
+evbus_publish(evbus_t *bus, const char *topic, const void *data, size_t len)
+{
+	ASSERT(bus != NULL);
+	ASSERT(topic != NULL);
+	ASSERT(strchr(topic, '*') == NULL);
+	ASSERT(data != NULL || len == 0);
+
+	mutex_enter(&bus->lock);
+	bus->delivering++;
+	for (evbus_sub_t *sub = list_head(&bus->subs); sub != NULL;
+	    sub = list_next(&bus->subs, sub)) {
+		if (sub->dead || !topic_matches(sub, topic))
+			continue;
+		if (sub->queued) {
+			evbus_msg_t *msg = safe_calloc(1, sizeof (*msg));
+
+			msg->topic = safe_strdup(topic);
+			if (len != 0) {
+				msg->data = safe_malloc(len);
+				memcpy(msg->data, data, len);
+			}
+			msg->len = len;
+			list_insert_tail(&sub->msgs, msg);
+		} else {
+			mutex_exit(&bus->lock);
+			sub->cb(topic, data, len, sub->userinfo);
+			mutex_enter(&bus->lock);
+		}
+	}
+	bus->delivering--;
+	reap_subs(bus);
+	mutex_exit(&bus->lock);
+}
+
+/*
+ * Caller must hold bus->lock and have bumped bus->delivering, which
+ * keeps `sub' from being freed while we drop the lock.
+ */
+static size_t
+drain_impl(evbus_t *bus, evbus_sub_t *sub)
+{
+	size_t n = 0;
+	evbus_msg_t *msg;
+
+	ASSERT(bus->delivering != 0);
+	while (!sub->dead && (msg = list_remove_head(&sub->msgs)) != NULL) {
+		mutex_exit(&bus->lock);
+		sub->cb(msg->topic, msg->data, msg->len, sub->userinfo);
+		msg_free(msg);
+		mutex_enter(&bus->lock);
+		n++;
+	}
+	return (n);
+}
+
+/*
+ * Delivers all queued messages of a subscription by calling its
+ * callback. This should be called from the thread on which the
+ * subscriber wants to process messages.
+ *
+ * @return The number of messages delivered.
+ */
+size_t
+evbus_drain(evbus_t *bus, evbus_sub_t *sub)
+{
+	size_t n;
+
+	ASSERT(bus != NULL);
+	ASSERT(sub != NULL);
+	ASSERT(sub->queued);
+
+	mutex_enter(&bus->lock);
+	bus->delivering++;
+	n = drain_impl(bus, sub);
+	bus->delivering--;
+	reap_subs(bus);
+	mutex_exit(&bus->lock);
+
+	return (n);
+}
+
+/*
+ * Delivers the queued messages of all queued subscriptions. Use this
+ * if all subscribers process their messages on the same thread.
+ *
+ * @return The total number of messages delivered.
+ */
+size_t
+evbus_drain_all(evbus_t *bus)
+{
+	size_t n = 0;
+
+	ASSERT(bus != NULL);
+
+	mutex_enter(&bus->lock);
+	bus->delivering++;
+	for (evbus_sub_t *sub = list_head(&bus->subs); sub != NULL;
+	    sub = list_next(&bus->subs, sub)) {
+		if (sub->queued)
+			n += drain_impl(bus, sub);
+	}
+	bus->delivering--;
+	reap_subs(bus);
+	mutex_exit(&bus->lock);
+
+	return (n);
+}
@@ -25,20 +25,148 @@
 
 #include <stdio.h>
 #include <stdlib.h>
+#include <string.h>
+#include <time.h>
 
 #if	APL || LIN
 #include <signal.h>
 #include <err.h>
+#include <fcntl.h>
+#include <unistd.h>
+#include <sys/utsname.h>
 #else	/* !APL && !LIN */
 #include <windows.h>
+#include <fcntl.h>
+#include <io.h>
+#include <sys/stat.h>
 #endif	/* !APL && !LIN */
 
 #include "acfutils/assert.h"
+#include "acfutils/core.h"
 #include "acfutils/except.h"
 #include "acfutils/helpers.h"
 #include "acfutils/log.h"
+#include "acfutils/safe_alloc.h"
+#include "acfutils/thread.h"
+
+#define	MAX_HOOKS	16
+
+typedef struct {
+	except_hook_t	hook;
+	void		*userinfo;
+} hook_t;
 
 static bool_t inited = B_FALSE;
+/*
+ * The crash report's path and its header (everything up to the recent
+ * log messages) are prepared in except_set_report_dir, so that writing
+ * the report from the crash handler doesn't need to allocate memory or
+ * call into non-reentrant library functions.
+ */
+static char *report_path = NULL;
+static char *report_hdr = NULL;
+/*
+ * The hooks are statically allocated and the crash handler walks them
+ * without taking `hooks_lock', so that running them can't deadlock if
+ * the crashed thread was holding the lock.
+ */
+static mutex_t hooks_lock;
+static hook_t hooks[MAX_HOOKS];
+static unsigned num_hooks = 0;
+static volatile except_policy_t policy = EXCEPT_POLICY_CHAIN;
+
+static void
+append_sys_info(char **buf, size_t *len)
+{
+#if	APL || LIN
+	struct utsname un;
+
+	if (uname(&un) == 0) {
+		append_format(buf, len, "OS: %s %s %s %s\n", un.sysname,
+		    un.release, un.version, un.machine);
+	}
+	append_format(buf, len, "CPUs: %ld\n",
+	    (long)sysconf(_SC_NPROCESSORS_ONLN));
+#else	/* !APL && !LIN */
+	SYSTEM_INFO si;
+
+	GetNativeSystemInfo(&si);
+	append_format(buf, len, "OS: Windows\n");
+	append_format(buf, len, "CPUs: %u\n",
+	    (unsigned)si.dwNumberOfProcessors);
+#endif	/* !APL && !LIN */
+}
+
+static void
+write_str(int fd, const char *str)
+{
+	size_t len = strlen(str);
+
+	while (len > 0) {
+#if	APL || LIN
+		ssize_t n = write(fd, str, len);
+#else	/* !APL && !LIN */
+		int n = _write(fd, str, len);
+#endif	/* !APL && !LIN */
+		if (n <= 0)
+			return;
+		str += n;
+		len -= n;
+	}
+}
+
+/*
+ * Writes a crash report file to the path prepared in
+ * except_set_report_dir. Must be called after the crash reason and
+ * backtrace have been logged, so that they are part of the recent log
+ * messages included in the report. This runs in the crash handler, so
+ * it only uses open/write/close on static data.
+ */
+static void
+write_crash_report(void)
+{
+	char timebuf[32], *p;
+	unsigned long long t;
+	int fd;
+
+	if (report_path == NULL)
+		return;
+#if	APL || LIN
+	fd = open(report_path, O_WRONLY | O_CREAT | O_TRUNC, 0644);
+#else	/* !APL && !LIN */
+	fd = _open(report_path, _O_WRONLY | _O_CREAT | _O_TRUNC | _O_BINARY,
+	    _S_IREAD | _S_IWRITE);
+#endif	/* !APL && !LIN */
+	if (fd < 0)
+		return;
+
+	write_str(fd, report_hdr);
+	/* format the crash time by hand, strftime isn't reentrant */
+	t = (unsigned long long)time(NULL);
+	p = &timebuf[sizeof (timebuf) - 1];
+	*p = '\0';
+	do {
+		*(--p) = '0' + (t % 10);
+		t /= 10;
+	} while (t != 0);
+	write_str(fd, "Crash time (UNIX time): ");
+	write_str(fd, p);
+	write_str(fd, "\n\nRecent log messages:\n");
+	log_write_recent(fd);
+
+#if	APL || LIN
+	close(fd);
+#else	/* !APL && !LIN */
+	_close(fd);
+#endif	/* !APL && !LIN */
+}
+
+static void
+run_hooks(void)
+{
+	for (unsigned i = 0; i < num_hooks; i++)
+		hooks[i].hook(hooks[i].userinfo);
+}
 
 #if	APL || LIN
 
@@ -136,6 +264,28 @@ handle_posix_sig(int sig, siginfo_t *siginfo, void *context)
 	}
 
 	log_backtrace(1);
+	/* termination requests aren't crashes */
+	if (sig != SIGTERM && sig != SIGINT)
+		write_crash_report();
+	run_hooks();
+
+	if (sig != SIGTERM && sig != SIGINT) {
+		switch (policy) {
+		case EXCEPT_POLICY_ABORT:
+			/*
+			 * The signal is blocked while we're in the handler,
+			 * so it gets delivered with the default action as
+			 * soon as we return.
+			 */
+			signal(sig, SIG_DFL);
+			raise(sig);
+			return;
+		case EXCEPT_POLICY_EXIT:
+			_exit(EXIT_FAILURE);
+		default:
+			break;
+		}
+	}
 
 	switch (sig) {
 	case SIGSEGV:
@@ -272,6 +422,18 @@ handle_windows_exception(EXCEPTION_POINTERS *ei)
 		break;
 	}
 	log_backtrace_sw64(ei->ContextRecord);
+	write_crash_report();
+	run_hooks();
+
+	switch (policy) {
+	case EXCEPT_POLICY_ABORT:
+		return (EXCEPTION_CONTINUE_SEARCH);
+	case EXCEPT_POLICY_EXIT:
+		TerminateProcess(GetCurrentProcess(), EXIT_FAILURE);
+		return (EXCEPTION_EXECUTE_HANDLER);
+	default:
+		break;
+	}
 
 	if (prev_windows_except_handler != NULL)
 		return (prev_windows_except_handler(ei));
@@ -286,6 +448,8 @@ except_init(void)
 {
 	ASSERT(!inited);
 	inited = B_TRUE;
+	mutex_init(&hooks_lock);
+	num_hooks = 0;
 
 #if	LIN || APL
 	signal_handler_init();
@@ -307,4 +471,128 @@ except_fini(void)
 #else	/* !LIN && !APL */
 	SetUnhandledExceptionFilter(prev_windows_except_handler);
 #endif	/* !LIN && !APL */
+	free(report_path);
+	report_path = NULL;
+	free(report_hdr);
+	report_hdr = NULL;
+	mutex_destroy(&hooks_lock);
+}
+
+/*
+ * Enables writing of crash reports. When a crash is caught, a standalone
+ * report file named "crash_<date>_<time>.txt" is written into `dir',
+ * where the date and time are those of the call to this function (i.e.
+ * usually of when the plugin was started). It contains the library
+ * version, basic OS and CPU information, and the most recent log
+ * messages, including the crash reason and backtrace. This gives users a
+ * single file to attach to their bug reports. SIGINT and SIGTERM don't
+ * produce crash reports. Pass NULL to disable crash reports again (the
+ * default). The directory must already exist. Call this after
+ * except_init.
+ */
+void
+except_set_report_dir(const char *dir)
+{
+	char timedate[32];
+	char *path = NULL, *hdr = NULL;
+	char *old_path = report_path, *old_hdr = report_hdr;
+	size_t hdr_len = 0;
+	time_t t;
+
+	ASSERT(inited);
+
+	if (dir != NULL) {
+		t = time(NULL);
+		strftime(timedate, sizeof (timedate), "%Y%m%d_%H%M%S",
+		    localtime(&t));
+		path = sprintf_alloc("%s%ccrash_%s.txt", dir, DIRSEP,
+		    timedate);
+		strftime(timedate, sizeof (timedate), "%Y-%m-%d %H:%M:%S",
+		    localtime(&t));
+		append_format(&hdr, &hdr_len, "Crash report\n"
+		    "Session started: %s\n"
+		    "libacfutils version: %s\n", timedate,
+		    libacfutils_version);
+		append_sys_info(&hdr, &hdr_len);
+	}
+	/*
+	 * Swap the new values in before freeing the old ones, so a crash
+	 * handler running concurrently never sees freed memory or a path
+	 * without a header.
+	 */
+	report_path = NULL;
+	memory_barrier();
+	report_hdr = hdr;
+	memory_barrier();
+	report_path = path;
+	free(old_path);
+	free(old_hdr);
+}
+
+/*
+ * Registers a hook to be called when a crash is caught (or on macOS and
+ * Linux, a SIGINT or SIGTERM), after the crash has been logged and the
+ * crash report (if enabled) has been written.
+ * Use this to e.g. flush important state to disk before the process goes
+ * down. Hooks are called in the order in which they were added. Keep in
+ * mind that the process is in an undefined state when the hook runs (and
+ * on Linux, it runs on a small alternate signal stack), so hooks should
+ * do as little as possible. Up to 16 hooks can be added.
+ */
+void
+except_add_hook(except_hook_t hook, void *userinfo)
+{
+	ASSERT(inited);
+	ASSERT(hook != NULL);
+
+	mutex_enter(&hooks_lock);
+	VERIFY3U(num_hooks, <, MAX_HOOKS);
+	hooks[num_hooks] = (hook_t){ .hook = hook, .userinfo = userinfo };
+	memory_barrier();
+	num_hooks++;
+	mutex_exit(&hooks_lock);
+}
+
+/*
+ * Removes a hook previously added using except_add_hook. Both `hook' and
+ * `userinfo' must match the values passed to except_add_hook.
+ */
+void
+except_remove_hook(except_hook_t hook, void *userinfo)
+{
+	ASSERT(inited);
+
+	mutex_enter(&hooks_lock);
+	for (unsigned i = 0; i < num_hooks; i++) {
+		if (hooks[i].hook == hook && hooks[i].userinfo == userinfo) {
+			memmove(&hooks[i], &hooks[i + 1],
+			    (num_hooks - i - 1) * sizeof (*hooks));
+			num_hooks--;
+			break;
+		}
+	}
+	mutex_exit(&hooks_lock);
+}
+
+/*
+ * Sets what happens to the process after a crash has been logged, the
+ * crash report written and the hooks have run:
+ * - EXCEPT_POLICY_CHAIN (the default): the crash is passed on to the
+ *	handler that was installed before except_init (e.g. the simulator's
+ *	own crash reporter) and the process then exits.
+ * - EXCEPT_POLICY_ABORT: the crash is handed straight to the OS, without
+ *	calling any previous handler. On macOS and Linux this produces a
+ *	core dump, on Windows it invokes Windows Error Reporting.
+ * - EXCEPT_POLICY_EXIT: the process exits immediately with a failure
+ *	status, without calling any other handlers.
+ * A crash in C can't be unwound, so none of the policies allows the
+ * process to keep running. SIGINT and SIGTERM always use
+ * EXCEPT_POLICY_CHAIN. This can be called at any time, including
+ * before except_init.
+ */
+void
+except_set_policy(except_policy_t new_policy)
+{
+	ASSERT3U(new_policy, <=, EXCEPT_POLICY_EXIT);
+	policy = new_policy;
 }
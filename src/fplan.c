@@ -0,0 +1,809 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <ctype.h>
+#include <errno.h>
+#include <math.h>
+#include <stdio.h>
+#include <string.h>
+#include <time.h>
+
+#include <libxml/parser.h>
+#include <libxml/xpath.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/fplan.h>
+#include <acfutils/helpers.h>
+#include <acfutils/log.h>
+#include <acfutils/safe_alloc.h>
+
+/*
+ * Creates a new empty flight plan. Use fplan_free to dispose of it.
+ */
+fplan_t *
+fplan_alloc(void)
+{
+	return (safe_calloc(1, sizeof (fplan_t)));
+}
+
+void
+fplan_free(fplan_t *fpl)
+{
+	if (fpl == NULL)
+		return;
+	free(fpl->wpts);
+	free(fpl);
+}
+
+/*
+ * Appends a copy of `wpt' to the end of the flight plan's waypoint list.
+ */
+void
+fplan_add_wpt(fplan_t *fpl, const fplan_wpt_t *wpt)
+{
+	ASSERT(fpl != NULL);
+	ASSERT(wpt != NULL);
+	fpl->wpts = safe_realloc(fpl->wpts,
+	    (fpl->num_wpts + 1) * sizeof (*fpl->wpts));
+	fpl->wpts[fpl->num_wpts] = *wpt;
+	fpl->num_wpts++;
+}
+
+static bool_t
+fms_type_parse(int code, fplan_wpt_type_t *type)
+{
+	switch (code) {
+	case 1:
+		*type = FPLAN_WPT_APT;
+		return (B_TRUE);
+	case 2:
+		*type = FPLAN_WPT_NDB;
+		return (B_TRUE);
+	case 3:
+		*type = FPLAN_WPT_VOR;
+		return (B_TRUE);
+	case 11:
+		*type = FPLAN_WPT_FIX;
+		return (B_TRUE);
+	case 28:
+		*type = FPLAN_WPT_LATLON;
+		return (B_TRUE);
+	default:
+		return (B_FALSE);
+	}
+}
+
+static int
+fms_type_code(fplan_wpt_type_t type)
+{
+	switch (type) {
+	case FPLAN_WPT_APT:
+		return (1);
+	case FPLAN_WPT_NDB:
+		return (2);
+	case FPLAN_WPT_VOR:
+		return (3);
+	case FPLAN_WPT_FIX:
+		return (11);
+	default:
+		return (28);
+	}
+}
+
+static void
+copy_rwy(char rwy[8], const char *str)
+{
+	/* .fms files prefix runway IDs with "RW" */
+	if (strncmp(str, "RW", 2) == 0)
+		str += 2;
+	lacf_strlcpy(rwy, str, 8);
+}
+
+/*
+ * Parses a waypoint line from an .fms file. Both the version 1100 format
+ * ("type ident via alt lat lon") and the legacy version 3 format ("type
+ * ident alt lat lon") are accepted.
+ */
+static bool_t
+fms_parse_wpt(char **comps, size_t n, fplan_wpt_t *wpt)
+{
+	int code;
+	size_t alt_i;
+
+	if (n < 5)
+		return (B_FALSE);
+	memset(wpt, 0, sizeof (*wpt));
+	code = atoi(comps[0]);
+	if (!fms_type_parse(code, &wpt->type))
+		return (B_FALSE);
+	lacf_strlcpy(wpt->ident, comps[1], sizeof (wpt->ident));
+	/*
+	 * A non-numeric third field means this is the 1100 format with a
+	 * "via" field present.
+	 */
+	if (n >= 6 && !isdigit((unsigned char)comps[2][0]) &&
+	    comps[2][0] != '-' && comps[2][0] != '+' && comps[2][0] != '.') {
+		if (strcmp(comps[2], "ADEP") != 0 &&
+		    strcmp(comps[2], "ADES") != 0 &&
+		    strcmp(comps[2], "DRCT") != 0 &&
+		    strcmp(comps[2], "DCT") != 0)
+			lacf_strlcpy(wpt->via, comps[2], sizeof (wpt->via));
+		alt_i = 3;
+	} else {
+		alt_i = 2;
+	}
+	wpt->alt = atof(comps[alt_i]);
+	wpt->pos = GEO_POS2(atof(comps[alt_i + 1]), atof(comps[alt_i + 2]));
+
+	return (is_valid_lat(wpt->pos.lat) && is_valid_lon(wpt->pos.lon));
+}
+
+/*
+ * Reads an X-Plane .fms flight plan file. Both the X-Plane 11/12 format
+ * (version 1100) and the legacy X-Plane 9/10 format (version 3) are
+ * supported. The parser is deliberately lenient, since many files found
+ * in the wild are slightly malformed: the header lines are optional,
+ * keywords are case-insensitive, the NUMENR count is ignored (all valid
+ * waypoint lines are read) and waypoint lines which can't be parsed are
+ * skipped with a warning in the log, rather than failing the whole file.
+ *
+ * @return The flight plan, or NULL if the file couldn't be read or
+ *	contained no valid waypoints (the error is logged).
+ */
+fplan_t *
+fplan_read_fms(const char *filename)
+{
+	FILE *fp;
+	fplan_t *fpl;
+	char *line = NULL;
+	size_t linecap = 0;
+	unsigned linenum = 0;
+
+	ASSERT(filename != NULL);
+
+	fp = fopen(filename, "r");
+	if (fp == NULL) {
+		logMsg("Error reading flight plan %s: %s", filename,
+		    strerror(errno));
+		return (NULL);
+	}
+	fpl = fplan_alloc();
+	while (parser_get_next_line(fp, &line, &linecap, &linenum) > 0) {
+		char **comps;
+		size_t n;
+		fplan_wpt_t wpt;
+
+		strtoupper(line);
+		comps = strsplit(line, " ", B_TRUE, &n);
+		if (n == 0) {
+			free_strlist(comps, n);
+			continue;
+		}
+		if (n == 1 || strcmp(comps[1], "VERSION") == 0) {
+			/* "I"/"A" header, version line, or bare counts */
+		} else if (strcmp(comps[0], "ADEP") == 0) {
+			lacf_strlcpy(fpl->dep, comps[1], sizeof (fpl->dep));
+		} else if (strcmp(comps[0], "DEPRWY") == 0) {
+			copy_rwy(fpl->dep_rwy, comps[1]);
+		} else if (strcmp(comps[0], "SID") == 0) {
+			lacf_strlcpy(fpl->sid, comps[1], sizeof (fpl->sid));
+		} else if (strcmp(comps[0], "SIDTRANS") == 0) {
+			lacf_strlcpy(fpl->sid_trans, comps[1],
+			    sizeof (fpl->sid_trans));
+		} else if (strcmp(comps[0], "ADES") == 0) {
+			lacf_strlcpy(fpl->arr, comps[1], sizeof (fpl->arr));
+		} else if (strcmp(comps[0], "DESRWY") == 0) {
+			copy_rwy(fpl->arr_rwy, comps[1]);
+		} else if (strcmp(comps[0], "STAR") == 0) {
+			lacf_strlcpy(fpl->star, comps[1], sizeof (fpl->star));
+		} else if (strcmp(comps[0], "STARTRANS") == 0) {
+			lacf_strlcpy(fpl->star_trans, comps[1],
+			    sizeof (fpl->star_trans));
+		} else if (strcmp(comps[0], "APP") == 0) {
+			lacf_strlcpy(fpl->app, comps[1], sizeof (fpl->app));
+		} else if (strcmp(comps[0], "APPTRANS") == 0) {
+			lacf_strlcpy(fpl->app_trans, comps[1],
+			    sizeof (fpl->app_trans));
+		} else if (isdigit((unsigned char)comps[0][0])) {
+			/* type 0 is the end-of-list marker in legacy files */
+			if (atoi(comps[0]) != 0) {
+				if (fms_parse_wpt(comps, n, &wpt)) {
+					fplan_add_wpt(fpl, &wpt);
+					fpl->cruise_alt = MAX(fpl->cruise_alt,
+					    wpt.alt);
+				} else {
+					logMsg("%s:%d: invalid waypoint line, "
+					    "skipping", filename, linenum);
+				}
+			}
+		}
+		/* Anything else (CYCLE, DEP, DES, NUMENR, etc.) is ignored */
+		free_strlist(comps, n);
+	}
+	free(line);
+	fclose(fp);
+
+	if (fpl->num_wpts == 0) {
+		logMsg("Error reading flight plan %s: no valid waypoints "
+		    "found", filename);
+		fplan_free(fpl);
+		return (NULL);
+	}
+	/* Legacy files don't contain ADEP/ADES, so use the airports */
+	if (fpl->dep[0] == '\0' && fpl->wpts[0].type == FPLAN_WPT_APT) {
+		lacf_strlcpy(fpl->dep, fpl->wpts[0].ident, sizeof (fpl->dep));
+	}
+	if (fpl->arr[0] == '\0' && fpl->num_wpts > 1 &&
+	    fpl->wpts[fpl->num_wpts - 1].type == FPLAN_WPT_APT) {
+		lacf_strlcpy(fpl->arr, fpl->wpts[fpl->num_wpts - 1].ident,
+		    sizeof (fpl->arr));
+	}
+
+	return (fpl);
+}
+
+static bool_t
+close_file(FILE *fp, const char *filename)
+{
+	bool_t ok = !ferror(fp);
+
+	if (fclose(fp) != 0)
+		ok = B_FALSE;
+	if (!ok) {
+		logMsg("Error writing flight plan %s: %s", filename,
+		    strerror(errno));
+	}
+	return (ok);
+}
+
+/*
+ * Writes a flight plan into an X-Plane 11/12 .fms file (version 1100).
+ * The AIRAC cycle in the file is set to the cycle current at the time
+ * of writing.
+ *
+ * @return B_TRUE on success, B_FALSE on failure (error is logged).
+ */
+bool_t
+fplan_write_fms(const fplan_t *fpl, const char *filename)
+{
+	FILE *fp;
+
+	ASSERT(fpl != NULL);
+	ASSERT(filename != NULL);
+
+	fp = fopen(filename, "wb");
+	if (fp == NULL) {
+		logMsg("Error writing flight plan %s: %s", filename,
+		    strerror(errno));
+		return (B_FALSE);
+	}
+	fprintf(fp, "I\n1100 Version\nCYCLE %d\n",
+	    airac_time2cycle(time(NULL)));
+	if (fpl->dep[0] != '\0')
+		fprintf(fp, "ADEP %s\n", fpl->dep);
+	else if (fpl->num_wpts != 0)
+		fprintf(fp, "DEP %s\n", fpl->wpts[0].ident);
+	if (fpl->dep_rwy[0] != '\0')
+		fprintf(fp, "DEPRWY RW%s\n", fpl->dep_rwy);
+	if (fpl->sid[0] != '\0')
+		fprintf(fp, "SID %s\n", fpl->sid);
+	if (fpl->sid_trans[0] != '\0')
+		fprintf(fp, "SIDTRANS %s\n", fpl->sid_trans);
+	if (fpl->arr[0] != '\0')
+		fprintf(fp, "ADES %s\n", fpl->arr);
+	else if (fpl->num_wpts != 0)
+		fprintf(fp, "DES %s\n", fpl->wpts[fpl->num_wpts - 1].ident);
+	if (fpl->arr_rwy[0] != '\0')
+		fprintf(fp, "DESRWY RW%s\n", fpl->arr_rwy);
+	if (fpl->star[0] != '\0')
+		fprintf(fp, "STAR %s\n", fpl->star);
+	if (fpl->star_trans[0] != '\0')
+		fprintf(fp, "STARTRANS %s\n", fpl->star_trans);
+	if (fpl->app[0] != '\0')
+		fprintf(fp, "APP %s\n", fpl->app);
+	if (fpl->app_trans[0] != '\0')
+		fprintf(fp, "APPTRANS %s\n", fpl->app_trans);
+	fprintf(fp, "NUMENR %d\n", (int)fpl->num_wpts);
+	for (size_t i = 0; i < fpl->num_wpts; i++) {
+		const fplan_wpt_t *wpt = &fpl->wpts[i];
+		const char *via;
+
+		if (i == 0 && wpt->type == FPLAN_WPT_APT &&
+		    strcmp(wpt->ident, fpl->dep) == 0)
+			via = "ADEP";
+		else if (i + 1 == fpl->num_wpts &&
+		    wpt->type == FPLAN_WPT_APT &&
+		    strcmp(wpt->ident, fpl->arr) == 0)
+			via = "ADES";
+		else if (wpt->via[0] != '\0')
+			via = wpt->via;
+		else
+			via = "DRCT";
+		fprintf(fp, "%d %s %s %f %f %f\n", fms_type_code(wpt->type),
+		    wpt->ident, via, (double)wpt->alt, wpt->pos.lat,
+		    wpt->pos.lon);
+	}
+
+	return (close_file(fp, filename));
+}
+
+/*
+ * Parses a single latitude or longitude component of a .pln position
+ * string, e.g. `N50° 6' 2.00"'. A plain decimal number is accepted too.
+ */
+static bool_t
+pln_parse_coord(const char *str, bool_t is_lat, double *out)
+{
+	double parts[3] = { 0, 0, 0 };
+	double sign = 1;
+	int n = 0, hemi;
+
+	while (isspace((unsigned char)*str))
+		str++;
+	hemi = toupper((unsigned char)*str);
+	switch (hemi) {
+	case 'S':
+	case 'W':
+		sign = -1;
+		/*FALLTHROUGH*/
+	case 'N':
+	case 'E':
+		if ((hemi == 'N' || hemi == 'S') != is_lat)
+			return (B_FALSE);
+		str++;
+		break;
+	}
+	while (*str != '\0' && n < 3) {
+		char *end;
+
+		if (!isdigit((unsigned char)*str) && *str != '.' &&
+		    *str != '-') {
+			/* skip degree/minute/second signs & spaces */
+			str++;
+			continue;
+		}
+		parts[n++] = strtod(str, &end);
+		if (end == str)
+			break;
+		str = end;
+	}
+	if (n == 0)
+		return (B_FALSE);
+	*out = sign * (parts[0] + parts[1] / 60 + parts[2] / 3600);
+
+	return (is_lat ? is_valid_lat(*out) : is_valid_lon(*out));
+}
+
+/*
+ * Parses a .pln position string, e.g.
+ * `N50° 6' 2.00",E14° 15' 36.00",+001247.00'.
+ */
+static bool_t
+pln_parse_pos(const char *str, geo_pos2_t *pos, int *alt)
+{
+	char **comps;
+	size_t n;
+	bool_t ok;
+
+	comps = strsplit(str, ",", B_FALSE, &n);
+	ok = (n >= 2 && pln_parse_coord(comps[0], B_TRUE, &pos->lat) &&
+	    pln_parse_coord(comps[1], B_FALSE, &pos->lon));
+	if (ok && alt != NULL && n >= 3)
+		*alt = atof(comps[2]);
+	free_strlist(comps, n);
+
+	return (ok);
+}
+
+static void
+pln_fmt_coord(FILE *fp, double x, char pos_hemi, char neg_hemi)
+{
+	/* work in hundredths of a second to avoid rounding artifacts */
+	long long hsec = llround(fabs(x) * 360000);
+
+	fprintf(fp, "%c%lld\xc2\xb0 %lld' %lld.%02lld\"",
+	    x >= 0 ? pos_hemi : neg_hemi, hsec / 360000,
+	    (hsec / 6000) % 60, (hsec / 100) % 60, hsec % 100);
+}
+
+static void
+pln_write_pos(FILE *fp, geo_pos2_t pos, int alt)
+{
+	pln_fmt_coord(fp, pos.lat, 'N', 'S');
+	fputc(',', fp);
+	pln_fmt_coord(fp, pos.lon, 'E', 'W');
+	fprintf(fp, ",%+010.2f", (double)alt);
+}
+
+static void
+pln_write_str(FILE *fp, const char *str)
+{
+	for (; *str != '\0'; str++) {
+		switch (*str) {
+		case '&':
+			fputs("&amp;", fp);
+			break;
+		case '<':
+			fputs("&lt;", fp);
+			break;
+		case '>':
+			fputs("&gt;", fp);
+			break;
+		case '"':
+			fputs("&quot;", fp);
+			break;
+		default:
+			fputc(*str, fp);
+			break;
+		}
+	}
+}
+
+static char *
+pln_get_child(const xmlNode *parent, const char *name)
+{
+	for (const xmlNode *node = parent->children; node != NULL;
+	    node = node->next) {
+		if (node->type == XML_ELEMENT_NODE &&
+		    strcmp((char *)node->name, name) == 0) {
+			xmlChar *content = xmlNodeGetContent(node);
+			char *str = safe_strdup((char *)content);
+
+			xmlFree(content);
+			return (str);
+		}
+	}
+	return (NULL);
+}
+
+static fplan_wpt_type_t
+pln_type_parse(const char *str)
+{
+	if (str == NULL)
+		return (FPLAN_WPT_FIX);
+	if (strcmp(str, "Airport") == 0)
+		return (FPLAN_WPT_APT);
+	if (strcmp(str, "VOR") == 0)
+		return (FPLAN_WPT_VOR);
+	if (strcmp(str, "NDB") == 0)
+		return (FPLAN_WPT_NDB);
+	if (strcmp(str, "User") == 0)
+		return (FPLAN_WPT_LATLON);
+	return (FPLAN_WPT_FIX);
+}
+
+static const char *
+pln_type_name(fplan_wpt_type_t type)
+{
+	switch (type) {
+	case FPLAN_WPT_APT:
+		return ("Airport");
+	case FPLAN_WPT_VOR:
+		return ("VOR");
+	case FPLAN_WPT_NDB:
+		return ("NDB");
+	case FPLAN_WPT_FIX:
+		return ("Intersection");
+	default:
+		return ("User");
+	}
+}
+
+/*
+ * Converts an MSFS runway number & designator pair (e.g. "27" + "LEFT")
+ * into a runway ID (e.g. "27L").
+ */
+static void
+pln_rwy_parse(const char *num, const char *desig, char rwy[8])
+{
+	char suffix[2] = { '\0', '\0' };
+
+	if (num == NULL)
+		return;
+	if (desig != NULL && strcmp(desig, "NONE") != 0 && desig[0] != '\0')
+		suffix[0] = desig[0];
+	snprintf(rwy, 8, "%02d%s", atoi(num), suffix);
+}
+
+static void
+pln_parse_wpt(fplan_t *fpl, const xmlNode *wpt_node, const char *filename)
+{
+	fplan_wpt_t wpt;
+	char *str;
+	const xmlNode *icao = NULL;
+
+	memset(&wpt, 0, sizeof (wpt));
+
+	str = pln_get_child(wpt_node, "ATCWaypointType");
+	wpt.type = pln_type_parse(str);
+	free(str);
+
+	str = pln_get_child(wpt_node, "WorldPosition");
+	if (str == NULL || !pln_parse_pos(str, &wpt.pos, &wpt.alt)) {
+		logMsg("%s: invalid waypoint position, skipping", filename);
+		free(str);
+		return;
+	}
+	free(str);
+
+	for (const xmlNode *node = wpt_node->children; node != NULL;
+	    node = node->next) {
+		if (node->type == XML_ELEMENT_NODE &&
+		    strcmp((char *)node->name, "ICAO") == 0)
+			icao = node;
+	}
+	if (icao != NULL) {
+		if ((str = pln_get_child(icao, "ICAOIdent")) != NULL) {
+			lacf_strlcpy(wpt.ident, str, sizeof (wpt.ident));
+			free(str);
+		}
+		if ((str = pln_get_child(icao, "ICAORegion")) != NULL) {
+			lacf_strlcpy(wpt.region, str, sizeof (wpt.region));
+			free(str);
+		}
+	}
+	if (wpt.ident[0] == '\0') {
+		xmlChar *id = xmlGetProp(wpt_node, (xmlChar *)"id");
+
+		if (id != NULL) {
+			lacf_strlcpy(wpt.ident, (char *)id, sizeof (wpt.ident));
+			xmlFree(id);
+		}
+	}
+	if ((str = pln_get_child(wpt_node, "ATCAirway")) != NULL) {
+		lacf_strlcpy(wpt.via, str, sizeof (wpt.via));
+		free(str);
+	}
+	/* MSFS-specific procedure info */
+	if (fpl->sid[0] == '\0' &&
+	    (str = pln_get_child(wpt_node, "DepartureFP")) != NULL) {
+		lacf_strlcpy(fpl->sid, str, sizeof (fpl->sid));
+		free(str);
+	}
+	if (fpl->star[0] == '\0' &&
+	    (str = pln_get_child(wpt_node, "ArrivalFP")) != NULL) {
+		lacf_strlcpy(fpl->star, str, sizeof (fpl->star));
+		free(str);
+	}
+	if (wpt.type == FPLAN_WPT_APT) {
+		char *num = pln_get_child(wpt_node, "RunwayNumberFP");
+		char *desig = pln_get_child(wpt_node, "RunwayDesignatorFP");
+
+		if (fpl->num_wpts == 0)
+			pln_rwy_parse(num, desig, fpl->dep_rwy);
+		else
+			pln_rwy_parse(num, desig, fpl->arr_rwy);
+		free(num);
+		free(desig);
+	}
+	if (wpt.ident[0] == '\0') {
+		logMsg("%s: waypoint without identifier, skipping", filename);
+		return;
+	}
+	fplan_add_wpt(fpl, &wpt);
+}
+
+/*
+ * Reads an MSFS/FSX .pln flight plan file. Only the route information
+ * is read (departure, destination, cruise altitude and waypoints, plus
+ * SID/STAR names and runways if the file was produced by MSFS).
+ *
+ * @return The flight plan, or NULL if the file couldn't be parsed or
+ *	contained no valid waypoints (the error is logged).
+ */
+fplan_t *
+fplan_read_pln(const char *filename)
+{
+	xmlDoc *doc;
+	xmlXPathContext *ctx = NULL;
+	xmlXPathObject *obj = NULL;
+	fplan_t *fpl = NULL;
+	const xmlNode *fp_node;
+	char *str;
+
+	ASSERT(filename != NULL);
+
+	doc = xmlReadFile(filename, NULL,
+	    XML_PARSE_NOERROR | XML_PARSE_NOWARNING | XML_PARSE_NONET);
+	if (doc == NULL) {
+		logMsg("Error reading flight plan %s: XML parsing error",
+		    filename);
+		return (NULL);
+	}
+	ctx = xmlXPathNewContext(doc);
+	if (ctx == NULL) {
+		logMsg("Error creating XPath context for document %s.",
+		    filename);
+		goto errout;
+	}
+	obj = xmlXPathEvalExpression((xmlChar *)
+	    "/SimBase.Document/FlightPlan.FlightPlan", ctx);
+	if (obj == NULL || obj->nodesetval == NULL ||
+	    obj->nodesetval->nodeNr == 0) {
+		logMsg("Error reading flight plan %s: missing "
+		    "FlightPlan.FlightPlan element", filename);
+		goto errout;
+	}
+	fp_node = obj->nodesetval->nodeTab[0];
+
+	fpl = fplan_alloc();
+	if ((str = pln_get_child(fp_node, "DepartureID")) != NULL) {
+		lacf_strlcpy(fpl->dep, str, sizeof (fpl->dep));
+		free(str);
+	}
+	if ((str = pln_get_child(fp_node, "DestinationID")) != NULL) {
+		lacf_strlcpy(fpl->arr, str, sizeof (fpl->arr));
+		free(str);
+	}
+	if ((str = pln_get_child(fp_node, "CruisingAlt")) != NULL) {
+		fpl->cruise_alt = atof(str);
+		free(str);
+	}
+	for (const xmlNode *node = fp_node->children; node != NULL;
+	    node = node->next) {
+		if (node->type == XML_ELEMENT_NODE &&
+		    strcmp((char *)node->name, "ATCWaypoint") == 0)
+			pln_parse_wpt(fpl, node, filename);
+	}
+	if (fpl->num_wpts == 0) {
+		logMsg("Error reading flight plan %s: no valid waypoints "
+		    "found", filename);
+		fplan_free(fpl);
+		fpl = NULL;
+	}
+errout:
+	if (obj != NULL)
+		xmlXPathFreeObject(obj);
+	if (ctx != NULL)
+		xmlXPathFreeContext(ctx);
+	xmlFreeDoc(doc);
+
+	return (fpl);
+}
+
+static void
+pln_write_rwy(FILE *fp, const char *rwy)
+{
+	const char *desig;
+
+	if (rwy[0] == '\0')
+		return;
+	fprintf(fp, "            <RunwayNumberFP>%d</RunwayNumberFP>\n",
+	    atoi(rwy));
+	switch (rwy[strlen(rwy) - 1]) {
+	case 'L':
+		desig = "LEFT";
+		break;
+	case 'R':
+		desig = "RIGHT";
+		break;
+	case 'C':
+		desig = "CENTER";
+		break;
+	default:
+		return;
+	}
+	fprintf(fp, "            <RunwayDesignatorFP>%s"
+	    "</RunwayDesignatorFP>\n", desig);
+}
+
+/*
+ * Writes a flight plan into an MSFS/FSX .pln file. SID & STAR names and
+ * the departure & arrival runways are written using the MSFS-specific
+ * elements, which FSX simply ignores. Approach information is not
+ * written, since the .pln format has no way to express it.
+ *
+ * @return B_TRUE on success, B_FALSE on failure (error is logged).
+ */
+bool_t
+fplan_write_pln(const fplan_t *fpl, const char *filename)
+{
+	FILE *fp;
+	const fplan_wpt_t *first, *last;
+
+	ASSERT(fpl != NULL);
+	ASSERT(filename != NULL);
+
+	if (fpl->num_wpts == 0) {
+		logMsg("Error writing flight plan %s: flight plan contains "
+		    "no waypoints", filename);
+		return (B_FALSE);
+	}
+	first = &fpl->wpts[0];
+	last = &fpl->wpts[fpl->num_wpts - 1];
+
+	fp = fopen(filename, "wb");
+	if (fp == NULL) {
+		logMsg("Error writing flight plan %s: %s", filename,
+		    strerror(errno));
+		return (B_FALSE);
+	}
+	fprintf(fp, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"
+	    "<SimBase.Document Type=\"AceXML\" version=\"1,0\">\n"
+	    "    <Descr>AceXML Document</Descr>\n"
+	    "    <FlightPlan.FlightPlan>\n"
+	    "        <Title>");
+	pln_write_str(fp, fpl->dep[0] != '\0' ? fpl->dep : first->ident);
+	fprintf(fp, " to ");
+	pln_write_str(fp, fpl->arr[0] != '\0' ? fpl->arr : last->ident);
+	fprintf(fp, "</Title>\n"
+	    "        <FPType>IFR</FPType>\n"
+	    "        <RouteType>%s</RouteType>\n"
+	    "        <CruisingAlt>%d</CruisingAlt>\n",
+	    fpl->cruise_alt >= 18000 ? "HighAlt" : "LowAlt", fpl->cruise_alt);
+	fprintf(fp, "        <DepartureID>");
+	pln_write_str(fp, fpl->dep[0] != '\0' ? fpl->dep : first->ident);
+	fprintf(fp, "</DepartureID>\n        <DepartureLLA>");
+	pln_write_pos(fp, first->pos, first->alt);
+	fprintf(fp, "</DepartureLLA>\n        <DestinationID>");
+	pln_write_str(fp, fpl->arr[0] != '\0' ? fpl->arr : last->ident);
+	fprintf(fp, "</DestinationID>\n        <DestinationLLA>");
+	pln_write_pos(fp, last->pos, last->alt);
+	fprintf(fp, "</DestinationLLA>\n");
+
+	for (size_t i = 0; i < fpl->num_wpts; i++) {
+		const fplan_wpt_t *wpt = &fpl->wpts[i];
+
+		fprintf(fp, "        <ATCWaypoint id=\"");
+		pln_write_str(fp, wpt->ident);
+		fprintf(fp, "\">\n"
+		    "            <ATCWaypointType>%s</ATCWaypointType>\n"
+		    "            <WorldPosition>",
+		    pln_type_name(wpt->type));
+		pln_write_pos(fp, wpt->pos, wpt->alt);
+		fprintf(fp, "</WorldPosition>\n");
+		if (wpt->via[0] != '\0') {
+			fprintf(fp, "            <ATCAirway>");
+			pln_write_str(fp, wpt->via);
+			fprintf(fp, "</ATCAirway>\n");
+		}
+		if (i == 0 && wpt->type == FPLAN_WPT_APT) {
+			pln_write_rwy(fp, fpl->dep_rwy);
+		} else if (i + 1 == fpl->num_wpts &&
+		    wpt->type == FPLAN_WPT_APT) {
+			pln_write_rwy(fp, fpl->arr_rwy);
+		}
+		if (i == 1 && fpl->sid[0] != '\0') {
+			fprintf(fp, "            <DepartureFP>");
+			pln_write_str(fp, fpl->sid);
+			fprintf(fp, "</DepartureFP>\n");
+		}
+		if (i + 2 == fpl->num_wpts && fpl->star[0] != '\0') {
+			fprintf(fp, "            <ArrivalFP>");
+			pln_write_str(fp, fpl->star);
+			fprintf(fp, "</ArrivalFP>\n");
+		}
+		if (wpt->type != FPLAN_WPT_LATLON) {
+			fprintf(fp, "            <ICAO>\n");
+			if (wpt->region[0] != '\0') {
+				fprintf(fp, "                <ICAORegion>");
+				pln_write_str(fp, wpt->region);
+				fprintf(fp, "</ICAORegion>\n");
+			}
+			fprintf(fp, "                <ICAOIdent>");
+			pln_write_str(fp, wpt->ident);
+			fprintf(fp, "</ICAOIdent>\n"
+			    "            </ICAO>\n");
+		}
+		fprintf(fp, "        </ATCWaypoint>\n");
+	}
+	fprintf(fp, "    </FlightPlan.FlightPlan>\n"
+	    "</SimBase.Document>\n");
+
+	return (close_file(fp, filename));
+}
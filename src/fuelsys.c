@@ -0,0 +1,457 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <math.h>
+#include <stdlib.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/fuelsys.h>
+#include <acfutils/safe_alloc.h>
+
+#define	DENS_REF_TEMP	15	/* degrees C */
+
+typedef struct {
+	double		capacity;	/* m^3 */
+	double		unusable;	/* kg */
+	double		qty;		/* kg */
+	double		temp;		/* degrees C */
+} tank_t;
+
+typedef struct {
+	double		demand;		/* kg/s */
+	double		supply;		/* kg/s */
+} engine_t;
+
+typedef enum {
+	LINE_FEED,
+	LINE_XFER
+} line_type_t;
+
+typedef struct {
+	line_type_t	type;
+	unsigned	src;		/* source tank */
+	unsigned	dst;		/* destination tank or engine */
+	double		rate;		/* max (feed) or nominal (xfer), kg/s */
+	bool_t		open;
+	double		flow;		/* kg/s during the last update */
+} line_t;
+
+struct fuelsys_s {
+	double		dens_ref;
+	double		dens_coeff;
+
+	tank_t		*tanks;
+	unsigned	num_tanks;
+	engine_t	*engines;
+	unsigned	num_engines;
+	line_t		*lines;
+	unsigned	num_lines;
+};
+
+/*
+ * Allocates an empty fuel system. The fuel system consists of tanks,
+ * engines and lines connecting them:
+ *
+ * - a tank has a fixed volumetric capacity. Its contents are tracked by
+ *	mass, so the maximum mass of fuel a tank can hold depends on the
+ *	fuel's density, which in turn depends on the fuel temperature.
+ * - an engine demands fuel at a certain mass flow rate (e.g. from the
+ *	engine model's fuel flow).
+ * - a feed line supplies an engine from a tank, up to a maximum flow rate
+ *	(modeling the boost pump or feed valve). When an engine is fed by
+ *	multiple open feed lines, the demand is split evenly among them.
+ * - a transfer line moves fuel between two tanks at a fixed rate (e.g.
+ *	a transfer pump), until the source runs out of usable fuel or the
+ *	destination is full.
+ *
+ * Lines start out open. Close them using fuelsys_line_set_open to model
+ * pumps turned off or valves closed.
+ *
+ * The fuel density is `dens_ref' (in kg/m^3) at 15 degrees C, changing by
+ * `dens_coeff' (in kg/m^3 per degree C, normally negative) with
+ * temperature. Units used throughout: mass in kg, mass flow in kg/s,
+ * volume in m^3, temperature in degrees C.
+ *
+ * Typical usage:
+ *
+ *	fuelsys_t *fs = fuelsys_alloc(804, -0.72);
+ *	unsigned ctr = fuelsys_add_tank(fs, 13.0, 20);
+ *	unsigned wing_l = fuelsys_add_tank(fs, 4.5, 10);
+ *	unsigned eng1 = fuelsys_add_engine(fs);
+ *	unsigned fwd_pump = fuelsys_add_feed(fs, wing_l, eng1, 2.5);
+ *	unsigned ctr_xfer = fuelsys_add_xfer(fs, ctr, wing_l, 0.8);
+ *	fuelsys_tank_set_qty(fs, ctr, 5000);
+ *	...
+ *	every frame:
+ *	fuelsys_line_set_open(fs, fwd_pump, fwd_pump_sw_on);
+ *	fuelsys_engine_set_demand(fs, eng1, eng1_ff);
+ *	fuelsys_update(fs, d_t);
+ *	if (fuelsys_engine_get_supply(fs, eng1) < eng1_ff)
+ *		... engine is fuel-starved ...
+ *	...
+ *	fuelsys_free(fs);
+ */
+fuelsys_t *
+fuelsys_alloc(double dens_ref, double dens_coeff)
+{
+	fuelsys_t *fs = safe_calloc(1, sizeof (*fs));
+
+	ASSERT3F(dens_ref, >, 0);
+	fs->dens_ref = dens_ref;
+	fs->dens_coeff = dens_coeff;
+
+	return (fs);
+}
+
+void
+fuelsys_free(fuelsys_t *fs)
+{
+	if (fs == NULL)
+		return;
+	free(fs->tanks);
+	free(fs->engines);
+	free(fs->lines);
+	free(fs);
+}
+
+/*
+ * Returns the fuel density (in kg/m^3) at temperature `temp'.
+ */
+double
+fuelsys_get_dens(const fuelsys_t *fs, double temp)
+{
+	ASSERT(fs != NULL);
+	return (MAX(fs->dens_ref + fs->dens_coeff * (temp - DENS_REF_TEMP),
+	    1e-3));
+}
+
+/*
+ * Adds a tank with a volumetric capacity of `capacity' (in m^3). The
+ * last `unusable' kg of fuel in the tank can't be pumped out. The tank
+ * starts out empty at 15 degrees C. Returns the tank's number.
+ */
+unsigned
+fuelsys_add_tank(fuelsys_t *fs, double capacity, double unusable)
+{
+	tank_t *tank;
+
+	ASSERT(fs != NULL);
+	ASSERT3F(capacity, >, 0);
+	ASSERT3F(unusable, >=, 0);
+
+	fs->tanks = safe_realloc(fs->tanks, (fs->num_tanks + 1) *
+	    sizeof (*fs->tanks));
+	tank = &fs->tanks[fs->num_tanks];
+	tank->capacity = capacity;
+	tank->unusable = unusable;
+	tank->qty = 0;
+	tank->temp = DENS_REF_TEMP;
+
+	return (fs->num_tanks++);
+}
+
+static tank_t *
+get_tank(const fuelsys_t *fs, unsigned tank)
+{
+	ASSERT(fs != NULL);
+	ASSERT3U(tank, <, fs->num_tanks);
+	return (&fs->tanks[tank]);
+}
+
+static double
+tank_max_qty(const fuelsys_t *fs, const tank_t *tank)
+{
+	return (tank->capacity * fuelsys_get_dens(fs, tank->temp));
+}
+
+static double
+tank_usable(const tank_t *tank)
+{
+	return (MAX(tank->qty - tank->unusable, 0));
+}
+
+/*
+ * Sets the mass of fuel in a tank, clamped to what the tank can hold.
+ */
+void
+fuelsys_tank_set_qty(fuelsys_t *fs, unsigned tank_nr, double qty)
+{
+	tank_t *tank = get_tank(fs, tank_nr);
+
+	ASSERT3F(qty, >=, 0);
+	tank->qty = MIN(qty, tank_max_qty(fs, tank));
+}
+
+/*
+ * Returns the total mass of fuel in a tank, including unusable fuel.
+ */
+double
+fuelsys_tank_get_qty(const fuelsys_t *fs, unsigned tank)
+{
+	return (get_tank(fs, tank)->qty);
+}
+
+/*
+ * Returns the mass of fuel in a tank which can still be pumped out.
+ */
+double
+fuelsys_tank_get_usable(const fuelsys_t *fs, unsigned tank)
+{
+	return (tank_usable(get_tank(fs, tank)));
+}
+
+/*
+ * Returns the volume (in m^3) occupied by the fuel in a tank.
+ */
+double
+fuelsys_tank_get_vol(const fuelsys_t *fs, unsigned tank_nr)
+{
+	const tank_t *tank = get_tank(fs, tank_nr);
+	return (tank->qty / fuelsys_get_dens(fs, tank->temp));
+}
+
+/*
+ * Returns the maximum mass of fuel the tank can hold at its current
+ * fuel temperature.
+ */
+double
+fuelsys_tank_get_max_qty(const fuelsys_t *fs, unsigned tank)
+{
+	return (tank_max_qty(fs, get_tank(fs, tank)));
+}
+
+/*
+ * Sets the temperature of the fuel in a tank. Heating up a full tank
+ * can make the fuel's volume exceed the tank's capacity. The excess
+ * isn't vented, but no more fuel can be added to the tank until it
+ * fits again.
+ */
+void
+fuelsys_tank_set_temp(fuelsys_t *fs, unsigned tank, double temp)
+{
+	get_tank(fs, tank)->temp = temp;
+}
+
+double
+fuelsys_tank_get_temp(const fuelsys_t *fs, unsigned tank)
+{
+	return (get_tank(fs, tank)->temp);
+}
+
+/*
+ * Adds an engine to the fuel system. The engine starts out with zero
+ * fuel demand. Returns the engine's number.
+ */
+unsigned
+fuelsys_add_engine(fuelsys_t *fs)
+{
+	ASSERT(fs != NULL);
+	fs->engines = safe_realloc(fs->engines, (fs->num_engines + 1) *
+	    sizeof (*fs->engines));
+	fs->engines[fs->num_engines] = (engine_t){ .demand = 0 };
+	return (fs->num_engines++);
+}
+
+/*
+ * Sets the fuel mass flow rate (in kg/s) the engine demands.
+ */
+void
+fuelsys_engine_set_demand(fuelsys_t *fs, unsigned eng, double rate)
+{
+	ASSERT(fs != NULL);
+	ASSERT3U(eng, <, fs->num_engines);
+	ASSERT3F(rate, >=, 0);
+	fs->engines[eng].demand = rate;
+}
+
+/*
+ * Returns the fuel mass flow rate (in kg/s) actually supplied to the
+ * engine during the last call to fuelsys_update. If this is less than
+ * the demand, the engine is being starved of fuel.
+ */
+double
+fuelsys_engine_get_supply(const fuelsys_t *fs, unsigned eng)
+{
+	ASSERT(fs != NULL);
+	ASSERT3U(eng, <, fs->num_engines);
+	return (fs->engines[eng].supply);
+}
+
+static unsigned
+add_line(fuelsys_t *fs, line_type_t type, unsigned src, unsigned dst,
+    double rate)
+{
+	ASSERT3F(rate, >, 0);
+	fs->lines = safe_realloc(fs->lines, (fs->num_lines + 1) *
+	    sizeof (*fs->lines));
+	fs->lines[fs->num_lines] = (line_t){
+	    .type = type, .src = src, .dst = dst, .rate = rate, .open = B_TRUE
+	};
+	return (fs->num_lines++);
+}
+
+/*
+ * Adds a feed line supplying engine `eng' from tank `tank' at up to
+ * `max_rate' kg/s. Returns the line's number.
+ */
+unsigned
+fuelsys_add_feed(fuelsys_t *fs, unsigned tank, unsigned eng,
+    double max_rate)
+{
+	ASSERT(fs != NULL);
+	ASSERT3U(tank, <, fs->num_tanks);
+	ASSERT3U(eng, <, fs->num_engines);
+	return (add_line(fs, LINE_FEED, tank, eng, max_rate));
+}
+
+/*
+ * Adds a transfer line moving fuel from `src_tank' to `dst_tank' at
+ * `rate' kg/s. Returns the line's number.
+ */
+unsigned
+fuelsys_add_xfer(fuelsys_t *fs, unsigned src_tank, unsigned dst_tank,
+    double rate)
+{
+	ASSERT(fs != NULL);
+	ASSERT3U(src_tank, <, fs->num_tanks);
+	ASSERT3U(dst_tank, <, fs->num_tanks);
+	ASSERT3U(src_tank, !=, dst_tank);
+	return (add_line(fs, LINE_XFER, src_tank, dst_tank, rate));
+}
+
+void
+fuelsys_line_set_open(fuelsys_t *fs, unsigned line, bool_t open)
+{
+	ASSERT(fs != NULL);
+	ASSERT3U(line, <, fs->num_lines);
+	fs->lines[line].open = open;
+}
+
+bool_t
+fuelsys_line_is_open(const fuelsys_t *fs, unsigned line)
+{
+	ASSERT(fs != NULL);
+	ASSERT3U(line, <, fs->num_lines);
+	return (fs->lines[line].open);
+}
+
+/*
+ * Returns the actual fuel mass flow rate (in kg/s) through a line during
+ * the last call to fuelsys_update.
+ */
+double
+fuelsys_line_get_flow(const fuelsys_t *fs, unsigned line)
+{
+	ASSERT(fs != NULL);
+	ASSERT3U(line, <, fs->num_lines);
+	return (fs->lines[line].flow);
+}
+
+static void
+update_xfers(fuelsys_t *fs, double d_t)
+{
+	for (unsigned i = 0; i < fs->num_lines; i++) {
+		line_t *line = &fs->lines[i];
+		tank_t *src, *dst;
+		double amount;
+
+		if (line->type != LINE_XFER || !line->open)
+			continue;
+		src = &fs->tanks[line->src];
+		dst = &fs->tanks[line->dst];
+		amount = MIN(line->rate * d_t, tank_usable(src));
+		amount = MIN(amount, MAX(tank_max_qty(fs, dst) - dst->qty, 0));
+		src->qty -= amount;
+		dst->qty += amount;
+		line->flow = amount / d_t;
+	}
+}
+
+/*
+ * Draws the engine's demand from all its open feed lines which still
+ * have fuel available, splitting it evenly among them. Lines which hit
+ * their flow limit or run their tank dry drop out, with the remainder
+ * of the demand redistributed among the other lines.
+ */
+static void
+update_engine(fuelsys_t *fs, unsigned eng_nr, double d_t)
+{
+	engine_t *eng = &fs->engines[eng_nr];
+	double remaining = eng->demand * d_t;
+
+	/*
+	 * Each round, at least one line drops out, so this is bounded by
+	 * the number of lines. The explicit bound protects against rounding
+	 * errors leaving lines marginally below their limits.
+	 */
+	for (unsigned round = 0; round <= fs->num_lines; round++) {
+		unsigned num_active = 0;
+		double share;
+		bool_t exhausted = B_FALSE;
+
+		for (unsigned i = 0; i < fs->num_lines; i++) {
+			const line_t *line = &fs->lines[i];
+
+			if (line->type == LINE_FEED && line->dst == eng_nr &&
+			    line->open && line->flow < line->rate &&
+			    tank_usable(&fs->tanks[line->src]) > 0)
+				num_active++;
+		}
+		if (num_active == 0 || remaining <= 0)
+			break;
+		share = remaining / num_active;
+		for (unsigned i = 0; i < fs->num_lines; i++) {
+			line_t *line = &fs->lines[i];
+			tank_t *tank = &fs->tanks[line->src];
+			double amount;
+
+			if (line->type != LINE_FEED || line->dst != eng_nr ||
+			    !line->open || line->flow >= line->rate ||
+			    tank_usable(tank) <= 0)
+				continue;
+			amount = MIN(share, (line->rate - line->flow) * d_t);
+			amount = MIN(amount, tank_usable(tank));
+			if (amount < share)
+				exhausted = B_TRUE;
+			tank->qty -= amount;
+			line->flow += amount / d_t;
+			remaining -= amount;
+		}
+		/* Everybody took their full share, so we're done. */
+		if (!exhausted)
+			break;
+	}
+	eng->supply = eng->demand - MAX(remaining, 0) / d_t;
+}
+
+/*
+ * Runs the fuel system for `d_t' seconds. Transfers are performed first,
+ * then the engines draw their fuel from their feed lines.
+ */
+void
+fuelsys_update(fuelsys_t *fs, double d_t)
+{
+	ASSERT(fs != NULL);
+	ASSERT3F(d_t, >, 0);
+
+	for (unsigned i = 0; i < fs->num_lines; i++)
+		fs->lines[i].flow = 0;
+	update_xfers(fs, d_t);
+	for (unsigned i = 0; i < fs->num_engines; i++)
+		update_engine(fs, i, d_t);
+}
@@ -1503,6 +1503,300 @@ gc_point_hdg(geo_pos2_t start, geo_pos2_t end)
 	return (dir2hdg(geo2fpp(end, &fpp)));
 }
 
+/*
+ * Returns the final true heading (in degrees) on arrival at `end' when
+ * following a great circle from `start' to `end'. Contrast with
+ * gc_point_hdg, which returns the initial heading at `start'.
+ */
+double
+gc_final_hdg(geo_pos2_t start, geo_pos2_t end)
+{
+	return (normalize_hdg(gc_point_hdg(end, start) + 180));
+}
+
+/*
+ * Returns the cross-track distance (in meters) of point `pos' from the
+ * great circle course running from `start' to `end'. The returned value
+ * is positive if `pos' lies to the right of the course and negative if
+ * it lies to the left. The course is treated as a full great circle,
+ * i.e. it is not limited to the segment between `start' and `end'.
+ */
+double
+gc_xtrk_dist(geo_pos2_t start, geo_pos2_t end, geo_pos2_t pos)
+{
+	double d13 = gc_distance(start, pos) / EARTH_MSL;
+	double crs12 = DEG2RAD(gc_point_hdg(start, end));
+	double crs13 = DEG2RAD(gc_point_hdg(start, pos));
+
+	return (asin(sin(d13) * sin(crs13 - crs12)) * EARTH_MSL);
+}
+
+/*
+ * Returns the along-track distance (in meters) from `start' to the point
+ * on the great circle course from `start' to `end' closest to `pos'.
+ * The returned value is negative if that closest point lies behind
+ * `start' (i.e. on the opposite side from `end').
+ */
+double
+gc_atrk_dist(geo_pos2_t start, geo_pos2_t end, geo_pos2_t pos)
+{
+	double d13 = gc_distance(start, pos) / EARTH_MSL;
+	double crs12 = DEG2RAD(gc_point_hdg(start, end));
+	double crs13 = DEG2RAD(gc_point_hdg(start, pos));
+	double dxt = asin(sin(d13) * sin(crs13 - crs12));
+	double cos_dxt = cos(dxt);
+	double datk;
+
+	if (cos_dxt == 0)
+		return (0);
+	datk = acos(clamp(cos(d13) / cos_dxt, -1, 1));
+	if (cos(crs13 - crs12) < 0)
+		datk = -datk;
+
+	return (datk * EARTH_MSL);
+}
+
+/*
+ * Computes the "stretched" latitude difference used in rhumb line
+ * (loxodrome) calculations, i.e. the difference in Mercator projection
+ * ordinates of the two latitudes (in radians).
+ */
+static double
+rhumb_dpsi(double lat1_r, double lat2_r)
+{
+	return (log(tan(M_PI / 4 + lat2_r / 2) / tan(M_PI / 4 + lat1_r / 2)));
+}
+
+/*
+ * Returns the ratio of latitude difference to stretched latitude
+ * difference, properly handling E-W courses (where dpsi == 0).
+ */
+static double
+rhumb_q(double lat1_r, double dlat_r, double dpsi)
+{
+	if (fabs(dpsi) > 1e-12)
+		return (dlat_r / dpsi);
+	return (cos(lat1_r));
+}
+
+/*
+ * Returns the distance (in meters) between `start' and `end' along a
+ * rhumb line (loxodrome), i.e. a line of constant true heading.
+ */
+double
+rhumb_distance(geo_pos2_t start, geo_pos2_t end)
+{
+	double lat1 = DEG2RAD(start.lat), lat2 = DEG2RAD(end.lat);
+	double dlat = lat2 - lat1;
+	double dlon = DEG2RAD(normalize_lon(end.lon - start.lon));
+	double q = rhumb_q(lat1, dlat, rhumb_dpsi(lat1, lat2));
+
+	return (sqrt(POW2(dlat) + POW2(q * dlon)) * EARTH_MSL);
+}
+
+/*
+ * Returns the constant true heading (in degrees) of the rhumb line
+ * (loxodrome) from `start' to `end'.
+ */
+double
+rhumb_hdg(geo_pos2_t start, geo_pos2_t end)
+{
+	double lat1 = DEG2RAD(start.lat), lat2 = DEG2RAD(end.lat);
+	double dlon = DEG2RAD(normalize_lon(end.lon - start.lon));
+
+	return (normalize_hdg(RAD2DEG(atan2(dlon, rhumb_dpsi(lat1, lat2)))));
+}
+
+/*
+ * Displaces `pos' along a rhumb line (loxodrome) with a constant true
+ * heading `truehdg' (in degrees) by `dist' meters. If the rhumb line
+ * would cross over a pole, the resulting latitude is reflected back
+ * to the other side of the pole.
+ */
+geo_pos2_t
+rhumb_displace(geo_pos2_t pos, double truehdg, double dist)
+{
+	double lat1 = DEG2RAD(pos.lat);
+	double hdg = DEG2RAD(truehdg);
+	double delta = dist / EARTH_MSL;
+	double dlat = delta * cos(hdg);
+	double lat2 = lat1 + dlat;
+	double q, dlon;
+
+	/*
+	 * q must come from the unreflected latitude change. Past a pole
+	 * the stretched latitude is undefined, so we clamp it at the pole.
+	 */
+	q = rhumb_q(lat1, dlat, rhumb_dpsi(lat1,
+	    clamp(lat2, -M_PI / 2, M_PI / 2)));
+	dlon = delta * sin(hdg) / q;
+	if (fabs(lat2) > M_PI / 2)
+		lat2 = (lat2 > 0 ? M_PI - lat2 : -M_PI - lat2);
+
+	return (GEO_POS2(RAD2DEG(lat2), normalize_lon(pos.lon +
+	    RAD2DEG(dlon))));
+}
+
+#define	VINCENTY_MAX_ITER	200
+#define	VINCENTY_EPSILON	1e-12
+
+/*
+ * Solves the inverse geodesic problem on ellipsoid `ellip' using
+ * Vincenty's iterative formulae, i.e. computes the shortest distance
+ * (in meters) along the surface of the ellipsoid between `start' and
+ * `end'. If `init_hdg' and/or `final_hdg' are non-NULL, they are filled
+ * with the initial true heading at `start' and final true heading at
+ * `end' (in degrees). If `start' and `end' are coincident, both headings
+ * are set to zero. For nearly antipodal points, where Vincenty's method
+ * can fail to converge, the best approximation after a fixed number of
+ * iterations is returned.
+ */
+double
+geodesic_inverse(const ellip_t *ellip, geo_pos2_t start, geo_pos2_t end,
+    double *init_hdg, double *final_hdg)
+{
+	double a, b, f, L, U1, U2, sinU1, cosU1, sinU2, cosU2;
+	double lambda, lambda_prev;
+	double sin_sigma = 0, cos_sigma = 0, sigma = 0;
+	double cos2_alpha = 0, cos_2sigma_m = 0;
+	double sin_lambda = 0, cos_lambda = 0;
+	double u2, A, B, d_sigma;
+
+	ASSERT(ellip != NULL);
+
+	a = ellip->a;
+	b = ellip->b;
+	f = ellip->f;
+	L = DEG2RAD(normalize_lon(end.lon - start.lon));
+	U1 = atan((1 - f) * tan(DEG2RAD(start.lat)));
+	U2 = atan((1 - f) * tan(DEG2RAD(end.lat)));
+	sinU1 = sin(U1);
+	cosU1 = cos(U1);
+	sinU2 = sin(U2);
+	cosU2 = cos(U2);
+	lambda = L;
+
+	for (int i = 0; i < VINCENTY_MAX_ITER; i++) {
+		double sin_alpha, C;
+
+		sin_lambda = sin(lambda);
+		cos_lambda = cos(lambda);
+		sin_sigma = sqrt(POW2(cosU2 * sin_lambda) +
+		    POW2(cosU1 * sinU2 - sinU1 * cosU2 * cos_lambda));
+		if (sin_sigma == 0) {
+			/* coincident points */
+			if (init_hdg != NULL)
+				*init_hdg = 0;
+			if (final_hdg != NULL)
+				*final_hdg = 0;
+			return (0);
+		}
+		cos_sigma = sinU1 * sinU2 + cosU1 * cosU2 * cos_lambda;
+		sigma = atan2(sin_sigma, cos_sigma);
+		sin_alpha = cosU1 * cosU2 * sin_lambda / sin_sigma;
+		cos2_alpha = 1 - POW2(sin_alpha);
+		/* equatorial line has cos2_alpha == 0 */
+		cos_2sigma_m = (cos2_alpha != 0 ?
+		    cos_sigma - 2 * sinU1 * sinU2 / cos2_alpha : 0);
+		C = f / 16 * cos2_alpha * (4 + f * (4 - 3 * cos2_alpha));
+		lambda_prev = lambda;
+		lambda = L + (1 - C) * f * sin_alpha * (sigma + C * sin_sigma *
+		    (cos_2sigma_m + C * cos_sigma *
+		    (-1 + 2 * POW2(cos_2sigma_m))));
+		if (fabs(lambda - lambda_prev) < VINCENTY_EPSILON)
+			break;
+	}
+
+	u2 = cos2_alpha * (POW2(a) - POW2(b)) / POW2(b);
+	A = 1 + u2 / 16384 * (4096 + u2 * (-768 + u2 * (320 - 175 * u2)));
+	B = u2 / 1024 * (256 + u2 * (-128 + u2 * (74 - 47 * u2)));
+	d_sigma = B * sin_sigma * (cos_2sigma_m + B / 4 *
+	    (cos_sigma * (-1 + 2 * POW2(cos_2sigma_m)) - B / 6 *
+	    cos_2sigma_m * (-3 + 4 * POW2(sin_sigma)) *
+	    (-3 + 4 * POW2(cos_2sigma_m))));
+
+	if (init_hdg != NULL) {
+		*init_hdg = normalize_hdg(RAD2DEG(atan2(cosU2 * sin_lambda,
+		    cosU1 * sinU2 - sinU1 * cosU2 * cos_lambda)));
+	}
+	if (final_hdg != NULL) {
+		*final_hdg = normalize_hdg(RAD2DEG(atan2(cosU1 * sin_lambda,
+		    -sinU1 * cosU2 + cosU1 * sinU2 * cos_lambda)));
+	}
+
+	return (b * A * (sigma - d_sigma));
+}
+
+/*
+ * Solves the direct geodesic problem on ellipsoid `ellip' using
+ * Vincenty's iterative formulae, i.e. displaces `pos' along a geodesic
+ * with an initial true heading of `truehdg' (in degrees) by `dist'
+ * meters. If `final_hdg' is non-NULL, it is filled with the true heading
+ * of the geodesic at the resulting point (in degrees).
+ */
+geo_pos2_t
+geodesic_direct(const ellip_t *ellip, geo_pos2_t pos, double truehdg,
+    double dist, double *final_hdg)
+{
+	double a, b, f, alpha1, sin_alpha1, cos_alpha1;
+	double tanU1, cosU1, sinU1, sigma1, sin_alpha, cos2_alpha;
+	double u2, A, B, sigma, sigma_prev;
+	double sin_sigma = 0, cos_sigma = 0, cos_2sigma_m = 0;
+	double tmp, lat2, lambda, C, L;
+
+	ASSERT(ellip != NULL);
+
+	a = ellip->a;
+	b = ellip->b;
+	f = ellip->f;
+	alpha1 = DEG2RAD(truehdg);
+	sin_alpha1 = sin(alpha1);
+	cos_alpha1 = cos(alpha1);
+	tanU1 = (1 - f) * tan(DEG2RAD(pos.lat));
+	cosU1 = 1 / sqrt(1 + POW2(tanU1));
+	sinU1 = tanU1 * cosU1;
+	sigma1 = atan2(tanU1, cos_alpha1);
+	sin_alpha = cosU1 * sin_alpha1;
+	cos2_alpha = 1 - POW2(sin_alpha);
+	u2 = cos2_alpha * (POW2(a) - POW2(b)) / POW2(b);
+	A = 1 + u2 / 16384 * (4096 + u2 * (-768 + u2 * (320 - 175 * u2)));
+	B = u2 / 1024 * (256 + u2 * (-128 + u2 * (74 - 47 * u2)));
+	sigma = dist / (b * A);
+
+	for (int i = 0; i < VINCENTY_MAX_ITER; i++) {
+		double d_sigma;
+
+		cos_2sigma_m = cos(2 * sigma1 + sigma);
+		sin_sigma = sin(sigma);
+		cos_sigma = cos(sigma);
+		d_sigma = B * sin_sigma * (cos_2sigma_m + B / 4 *
+		    (cos_sigma * (-1 + 2 * POW2(cos_2sigma_m)) - B / 6 *
+		    cos_2sigma_m * (-3 + 4 * POW2(sin_sigma)) *
+		    (-3 + 4 * POW2(cos_2sigma_m))));
+		sigma_prev = sigma;
+		sigma = dist / (b * A) + d_sigma;
+		if (fabs(sigma - sigma_prev) < VINCENTY_EPSILON)
+			break;
+	}
+	sin_sigma = sin(sigma);
+	cos_sigma = cos(sigma);
+	cos_2sigma_m = cos(2 * sigma1 + sigma);
+
+	tmp = sinU1 * sin_sigma - cosU1 * cos_sigma * cos_alpha1;
+	lat2 = atan2(sinU1 * cos_sigma + cosU1 * sin_sigma * cos_alpha1,
+	    (1 - f) * sqrt(POW2(sin_alpha) + POW2(tmp)));
+	lambda = atan2(sin_sigma * sin_alpha1, cosU1 * cos_sigma -
+	    sinU1 * sin_sigma * cos_alpha1);
+	C = f / 16 * cos2_alpha * (4 + f * (4 - 3 * cos2_alpha));
+	L = lambda - (1 - C) * f * sin_alpha * (sigma + C * sin_sigma *
+	    (cos_2sigma_m + C * cos_sigma * (-1 + 2 * POW2(cos_2sigma_m))));
+
+	if (final_hdg != NULL)
+		*final_hdg = normalize_hdg(RAD2DEG(atan2(sin_alpha, -tmp)));
+
+	return (GEO_POS2(RAD2DEG(lat2), normalize_lon(pos.lon + RAD2DEG(L))));
+}
+
 /*
  * Prepares a set of projection parameters for projections from a fixed
  * origin along the projection axis onto a flat projection plane. The
@@ -1719,7 +2013,7 @@ fpp_get_scale(const fpp_t *fpp)
  * @param stdpar1 First standard parallel in degrees.
  * @param stdpar2 Second standard parallel in degrees.
  *
- * @return The set of lcc parameters to pass to geo2lcc.
+ * @return The set of lcc parameters to pass to geo2lcc and lcc2geo.
  */
 lcc_t
 lcc_init(double reflat, double reflon, double stdpar1, double stdpar2)
@@ -1737,7 +2031,7 @@ lcc_init(double reflat, double reflon, double stdpar1, double stdpar2)
 	else
 		lcc.n = log(cos(phi1) * sec(phi2)) / log(tan(M_PI / 4.0 +
 		    phi2 / 2.0) * cot(M_PI / 4.0 + phi1 / 2.0));
-	lcc.F = (cos(phi1) * pow(tan(M_PI / 4.0 * phi1 / 2.0), lcc.n)) /
+	lcc.F = (cos(phi1) * pow(tan(M_PI / 4.0 + phi1 / 2.0), lcc.n)) /
 	    lcc.n;
 	lcc.rho0 = lcc.F * pow(cot(M_PI / 4.0 + phi0 / 2.0), lcc.n);
 
@@ -1745,23 +2039,53 @@ lcc_init(double reflat, double reflon, double stdpar1, double stdpar2)
 }
 
 /*
- * Projects a point at `pos' using the projection `lcc'.
+ * Projects a point at `pos' using the projection `lcc'. The result is
+ * in units of the radius of the Earth, with the origin at the reference
+ * point and the Y axis pointing to the north along the reference
+ * longitude.
  */
 vect2_t
 geo2lcc(geo_pos2_t pos, const lcc_t *lcc)
 {
 	vect2_t		result;
-	double		rho;
+	double		rho, theta;
 	double		lat = DEG2RAD(pos.lat);
 	double		lon = DEG2RAD(pos.lon);
 
 	rho = lcc->F * pow(cot(M_PI / 4 + lat / 2), lcc->n);
-	result.x = rho * sin(lon - lcc->reflon);
-	result.y = lcc->rho0 - rho * cos(lcc->n * (lat - lcc->reflat));
+	theta = lcc->n * (lon - lcc->reflon);
+	result.x = rho * sin(theta);
+	result.y = lcc->rho0 - rho * cos(theta);
 
 	return (result);
 }
 
+/*
+ * Inverse of geo2lcc. Converts a projected point back to geographic
+ * coordinates.
+ */
+geo_pos2_t
+lcc2geo(vect2_t pos, const lcc_t *lcc)
+{
+	double	rho, theta, lat, lon;
+	double	dy = lcc->rho0 - pos.y;
+
+	if (lcc->n >= 0) {
+		rho = sqrt(POW2(pos.x) + POW2(dy));
+		theta = atan2(pos.x, dy);
+	} else {
+		rho = -sqrt(POW2(pos.x) + POW2(dy));
+		theta = atan2(-pos.x, -dy);
+	}
+	if (rho == 0)
+		lat = (lcc->n >= 0 ? M_PI / 2 : -M_PI / 2);
+	else
+		lat = 2 * atan(pow(lcc->F / rho, 1 / lcc->n)) - M_PI / 2;
+	lon = theta / lcc->n + lcc->reflon;
+
+	return (GEO_POS2(RAD2DEG(lat), normalize_lon(RAD2DEG(lon))));
+}
+
 /*
  * Allocates a new generic Bezier curve structure with n_pts points.
  */
@@ -1952,6 +2276,132 @@ quad_bezier_func_inv(double y, const bezier_t *func, size_t *n_xs)
 	return (xs);
 }
 
+/*
+ * Evaluates a parametric quadratic Bezier curve defined by the control
+ * points p[0] - p[2] at parameter `t' (0 - 1).
+ */
+vect2_t
+quad_bezier_pt(const vect2_t p[3], double t)
+{
+	double u = 1 - t;
+
+	return (VECT2(POW2(u) * p[0].x + 2 * u * t * p[1].x + POW2(t) * p[2].x,
+	    POW2(u) * p[0].y + 2 * u * t * p[1].y + POW2(t) * p[2].y));
+}
+
+/*
+ * Evaluates a parametric cubic Bezier curve defined by the control
+ * points p[0] - p[3] at parameter `t' (0 - 1):
+ *
+ * B(t) = (1-t)^3.P0 + 3(1-t)^2.t.P1 + 3(1-t).t^2.P2 + t^3.P3
+ */
+vect2_t
+cubic_bezier_pt(const vect2_t p[4], double t)
+{
+	double u = 1 - t;
+	double b0 = POW3(u), b1 = 3 * POW2(u) * t, b2 = 3 * u * POW2(t);
+	double b3 = POW3(t);
+
+	return (VECT2(b0 * p[0].x + b1 * p[1].x + b2 * p[2].x + b3 * p[3].x,
+	    b0 * p[0].y + b1 * p[1].y + b2 * p[2].y + b3 * p[3].y));
+}
+
+/*
+ * Returns the derivative (tangent vector) of a cubic Bezier curve with
+ * respect to `t'.
+ */
+vect2_t
+cubic_bezier_dir(const vect2_t p[4], double t)
+{
+	double u = 1 - t;
+	double b0 = 3 * POW2(u), b1 = 6 * u * t, b2 = 3 * POW2(t);
+
+	return (VECT2(b0 * (p[1].x - p[0].x) + b1 * (p[2].x - p[1].x) +
+	    b2 * (p[3].x - p[2].x), b0 * (p[1].y - p[0].y) +
+	    b1 * (p[2].y - p[1].y) + b2 * (p[3].y - p[2].y)));
+}
+
+/*
+ * Converts a quadratic Bezier curve into an identical cubic Bezier
+ * curve (degree elevation), so it can be used with the cubic functions.
+ */
+void
+quad2cubic_bezier(const vect2_t q[3], vect2_t c[4])
+{
+	c[0] = q[0];
+	c[1] = vect2_add(q[0], vect2_scmul(vect2_sub(q[1], q[0]), 2.0 / 3));
+	c[2] = vect2_add(q[2], vect2_scmul(vect2_sub(q[1], q[2]), 2.0 / 3));
+	c[3] = q[2];
+}
+
+/*
+ * Calculates the arc length of a cubic Bezier curve from its start to
+ * parameter `t' (0 - 1). The integral is evaluated numerically using
+ * Gauss-Legendre quadrature, which is accurate to well below 1e-6 of
+ * the curve's length for all reasonably shaped curves.
+ */
+double
+cubic_bezier_len(const vect2_t p[4], double t)
+{
+	static const double gl_x[] = {
+	    0, -0.5384693101056831, 0.5384693101056831,
+	    -0.9061798459386640, 0.9061798459386640
+	};
+	static const double gl_w[] = {
+	    0.5688888888888889, 0.4786286704993665, 0.4786286704993665,
+	    0.2369268850561891, 0.2369268850561891
+	};
+	enum { NUM_SEGS = 16 };
+	double len = 0, seg = t / NUM_SEGS;
+
+	ASSERT3F(t, >=, 0);
+	ASSERT3F(t, <=, 1);
+	for (int i = 0; i < NUM_SEGS; i++) {
+		double mid = (i + 0.5) * seg;
+
+		for (size_t j = 0; j < ARRAY_NUM_ELEM(gl_x); j++) {
+			len += gl_w[j] * vect2_abs(cubic_bezier_dir(p,
+			    mid + gl_x[j] * seg / 2)) * seg / 2;
+		}
+	}
+	return (len);
+}
+
+/*
+ * Inverse of cubic_bezier_len: finds the parameter `t' at which the
+ * curve's arc length from its start equals `len'. This allows moving
+ * along a curve at constant speed (arc-length parameterization). `len'
+ * is clamped to the curve's total length.
+ */
+double
+cubic_bezier_len2t(const vect2_t p[4], double len)
+{
+	double total = cubic_bezier_len(p, 1);
+	double lo = 0, hi = 1, t;
+
+	if (len <= 0 || total <= 0)
+		return (0);
+	if (len >= total)
+		return (1);
+	t = len / total;
+	/* Newton's method, falling back to bisection if it misbehaves */
+	for (int i = 0; i < 32; i++) {
+		double err = cubic_bezier_len(p, t) - len;
+		double spd = vect2_abs(cubic_bezier_dir(p, t));
+
+		if (fabs(err) < 1e-9 * total)
+			break;
+		if (err > 0)
+			hi = t;
+		else
+			lo = t;
+		t = (spd > 0 ? t - err / spd : (lo + hi) / 2);
+		if (t <= lo || t >= hi)
+			t = (lo + hi) / 2;
+	}
+	return (t);
+}
+
 API_EXPORT void
 mat4_ident(mat4_t *mat)
 {
@@ -1970,3 +2420,206 @@ mat3_ident(mat3_t *mat)
 	MAT3(mat, 1, 1) = 1;
 	MAT3(mat, 2, 2) = 1;
 }
+
+/*
+ * Multiplies matrices `a' and `b' and places the result in `out'
+ * (out = a * b). `out' may point to the same matrix as `a' or `b'.
+ */
+API_EXPORT void
+mat4_mul(const mat4_t *a, const mat4_t *b, mat4_t *out)
+{
+	mat4_t res;
+
+	ASSERT(a != NULL);
+	ASSERT(b != NULL);
+	ASSERT(out != NULL);
+
+	for (int col = 0; col < 4; col++) {
+		for (int row = 0; row < 4; row++) {
+			MAT4(&res, col, row) = 0;
+			for (int i = 0; i < 4; i++) {
+				MAT4(&res, col, row) += MAT4(a, i, row) *
+				    MAT4(b, col, i);
+			}
+		}
+	}
+	*out = res;
+}
+
+/*
+ * Same as mat4_mul, but for 3x3 matrices.
+ */
+API_EXPORT void
+mat3_mul(const mat3_t *a, const mat3_t *b, mat3_t *out)
+{
+	mat3_t res;
+
+	ASSERT(a != NULL);
+	ASSERT(b != NULL);
+	ASSERT(out != NULL);
+
+	for (int col = 0; col < 3; col++) {
+		for (int row = 0; row < 3; row++) {
+			MAT3(&res, col, row) = 0;
+			for (int i = 0; i < 3; i++) {
+				MAT3(&res, col, row) += MAT3(a, i, row) *
+				    MAT3(b, col, i);
+			}
+		}
+	}
+	*out = res;
+}
+
+/*
+ * Transposes matrix `a' and places the result in `out'. `out' may point
+ * to the same matrix as `a'.
+ */
+API_EXPORT void
+mat4_transpose(const mat4_t *a, mat4_t *out)
+{
+	mat4_t res;
+
+	ASSERT(a != NULL);
+	ASSERT(out != NULL);
+	for (int col = 0; col < 4; col++) {
+		for (int row = 0; row < 4; row++)
+			MAT4(&res, col, row) = MAT4(a, row, col);
+	}
+	*out = res;
+}
+
+/*
+ * Same as mat4_transpose, but for 3x3 matrices.
+ */
+API_EXPORT void
+mat3_transpose(const mat3_t *a, mat3_t *out)
+{
+	mat3_t res;
+
+	ASSERT(a != NULL);
+	ASSERT(out != NULL);
+	for (int col = 0; col < 3; col++) {
+		for (int row = 0; row < 3; row++)
+			MAT3(&res, col, row) = MAT3(a, row, col);
+	}
+	*out = res;
+}
+
+/*
+ * Returns the determinant of a 3x3 matrix.
+ */
+API_EXPORT double
+mat3_det(const mat3_t *a)
+{
+	ASSERT(a != NULL);
+	return (MAT3(a, 0, 0) * (MAT3(a, 1, 1) * MAT3(a, 2, 2) -
+	    MAT3(a, 2, 1) * MAT3(a, 1, 2)) -
+	    MAT3(a, 1, 0) * (MAT3(a, 0, 1) * MAT3(a, 2, 2) -
+	    MAT3(a, 2, 1) * MAT3(a, 0, 2)) +
+	    MAT3(a, 2, 0) * (MAT3(a, 0, 1) * MAT3(a, 1, 2) -
+	    MAT3(a, 1, 1) * MAT3(a, 0, 2)));
+}
+
+/*
+ * Inverts 3x3 matrix `a' and places the result in `out'. `out' may point
+ * to the same matrix as `a'.
+ *
+ * @return B_TRUE if the inversion succeeded, or B_FALSE if the matrix
+ *	is singular (in which case `out' is left untouched).
+ */
+API_EXPORT bool_t
+mat3_inv(const mat3_t *a, mat3_t *out)
+{
+	mat3_t res;
+	double det;
+
+	ASSERT(a != NULL);
+	ASSERT(out != NULL);
+
+	det = mat3_det(a);
+	if (det == 0 || !isfinite(det))
+		return (B_FALSE);
+	for (int col = 0; col < 3; col++) {
+		for (int row = 0; row < 3; row++) {
+			/*
+			 * Inverse = adjugate / det. The adjugate is the
+			 * transpose of the cofactor matrix, hence the
+			 * swapped row & column indices on the input.
+			 */
+			int c1 = (row + 1) % 3, c2 = (row + 2) % 3;
+			int r1 = (col + 1) % 3, r2 = (col + 2) % 3;
+
+			MAT3(&res, col, row) = (MAT3(a, c1, r1) *
+			    MAT3(a, c2, r2) - MAT3(a, c2, r1) *
+			    MAT3(a, c1, r2)) / det;
+		}
+	}
+	*out = res;
+
+	return (B_TRUE);
+}
+
+/*
+ * Constructs a rotation matrix which rotates vectors by `a' degrees
+ * around a given axis. The rotation direction and the `axis' argument
+ * follow the same conventions as vect3_rot, so that
+ * mat3_vect3_mul(&mat, v) == vect3_rot(v, a, axis).
+ */
+API_EXPORT void
+mat3_rot(mat3_t *mat, double a, unsigned axis)
+{
+	ASSERT(mat != NULL);
+	ASSERT3U(axis, <=, 2);
+	for (int col = 0; col < 3; col++) {
+		vect3_t v = vect3_rot(VECT3(col == 0, col == 1, col == 2),
+		    a, axis);
+
+		MAT3(mat, col, 0) = v.x;
+		MAT3(mat, col, 1) = v.y;
+		MAT3(mat, col, 2) = v.z;
+	}
+}
+
+/*
+ * Multiplies vector `v' by matrix `mat' (i.e. returns mat * v).
+ */
+API_EXPORT vect3_t
+mat3_vect3_mul(const mat3_t *mat, vect3_t v)
+{
+	ASSERT(mat != NULL);
+	return (VECT3(
+	    MAT3(mat, 0, 0) * v.x + MAT3(mat, 1, 0) * v.y +
+	    MAT3(mat, 2, 0) * v.z,
+	    MAT3(mat, 0, 1) * v.x + MAT3(mat, 1, 1) * v.y +
+	    MAT3(mat, 2, 1) * v.z,
+	    MAT3(mat, 0, 2) * v.x + MAT3(mat, 1, 2) * v.y +
+	    MAT3(mat, 2, 2) * v.z));
+}
+
+/*
+ * Transforms point `v' by matrix `mat'. The point is treated as a
+ * homogeneous coordinate with w = 1 and the result is divided by the
+ * resulting w component (if it is non-zero), so this works for both
+ * affine and projective transforms.
+ */
+API_EXPORT vect3_t
+mat4_vect3_mul(const mat4_t *mat, vect3_t v)
+{
+	vect3_t res;
+	double w;
+
+	ASSERT(mat != NULL);
+	res = VECT3(
+	    MAT4(mat, 0, 0) * v.x + MAT4(mat, 1, 0) * v.y +
+	    MAT4(mat, 2, 0) * v.z + MAT4(mat, 3, 0),
+	    MAT4(mat, 0, 1) * v.x + MAT4(mat, 1, 1) * v.y +
+	    MAT4(mat, 2, 1) * v.z + MAT4(mat, 3, 1),
+	    MAT4(mat, 0, 2) * v.x + MAT4(mat, 1, 2) * v.y +
+	    MAT4(mat, 2, 2) * v.z + MAT4(mat, 3, 2));
+	w = MAT4(mat, 0, 3) * v.x + MAT4(mat, 1, 3) * v.y +
+	    MAT4(mat, 2, 3) * v.z + MAT4(mat, 3, 3);
+	if (w != 0 && w != 1)
+		res = vect3_scmul(res, 1 / w);
+
+	return (res);
+}
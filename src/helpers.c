@@ -35,11 +35,13 @@
 #if	IBM
 #include <windows.h>
 #include <strsafe.h>
+#include <io.h>
 #else	/* !IBM */
 #include <sys/types.h>
 #include <sys/time.h>
 #include <sys/stat.h>
 #include <dirent.h>
+#include <fcntl.h>
 #include <unistd.h>
 #endif	/* !IBM */
 
@@ -273,6 +275,119 @@ is_valid_ndb_freq(double freq_khz)
 	return (freq_hz >= 177000 && freq_hz <= 1750000);
 }
 
+/*
+ * COM radio channel helpers. COM channels are identified by their
+ * "channel name" in kHz, i.e. the number displayed on the radio (118305
+ * for "118.305"). With 25 kHz spacing, the channel name is simply the
+ * frequency. With 8.33 kHz spacing, each 25 kHz block is split into three
+ * channels and the names no longer match the frequencies exactly:
+ *
+ *	name	frequency		spacing
+ *	118.000	118.000000 MHz		25 kHz
+ *	118.005	118.000000 MHz		8.33 kHz
+ *	118.010	118.008333 MHz		8.33 kHz
+ *	118.015	118.016667 MHz		8.33 kHz
+ *	118.025	118.025000 MHz		25 kHz
+ *	118.030	118.025000 MHz		8.33 kHz
+ *	...
+ *
+ * Names ending in .x20, .x45, .x70 and .x95 are never valid.
+ */
+#define	COM_BAND_MIN	118000	/* kHz */
+#define	COM_BAND_MAX	136990	/* kHz */
+
+/*
+ * Checks if `chan_khz' is a valid COM channel name. If `spacing_833' is
+ * B_FALSE, only 25 kHz channels are accepted, otherwise both 25 kHz and
+ * 8.33 kHz channel names are valid.
+ */
+bool_t
+is_valid_com_chan(unsigned chan_khz, bool_t spacing_833)
+{
+	unsigned rem = chan_khz % 25;
+
+	if (chan_khz < COM_BAND_MIN || chan_khz > COM_BAND_MAX)
+		return (B_FALSE);
+	if (!spacing_833)
+		return (rem == 0);
+	return (rem == 0 || rem == 5 || rem == 10 || rem == 15);
+}
+
+/*
+ * Translates a COM channel name into the actual frequency in Hz.
+ */
+uint32_t
+com_chan2freq(unsigned chan_khz)
+{
+	unsigned block = chan_khz - chan_khz % 25;
+
+	switch (chan_khz % 25) {
+	case 10:
+		return (block * 1000 + 8333);
+	case 15:
+		return (block * 1000 + 16667);
+	default:
+		return (block * 1000);
+	}
+}
+
+/*
+ * Rounds an arbitrary frequency in Hz to the nearest COM channel and
+ * returns the channel name. If `spacing_833' is B_TRUE, the result is
+ * always an 8.33 kHz channel name (so 118.000 MHz rounds to "118.005").
+ * The frequency isn't clamped to the COM band, use is_valid_com_chan
+ * to check the result.
+ */
+unsigned
+com_freq2chan(uint32_t freq_hz, bool_t spacing_833)
+{
+	static const unsigned names_833[3] = { 5, 10, 15 };
+	unsigned n;
+
+	if (!spacing_833)
+		return (((freq_hz + 12500) / 25000) * 25);
+	/* number of 8.33 kHz steps, rounded to nearest */
+	n = ((uint64_t)freq_hz * 3 + 12500) / 25000;
+	return ((n / 3) * 25 + names_833[n % 3]);
+}
+
+/*
+ * Converts the value of X-Plane's 10 kHz-unit COM frequency datarefs
+ * (e.g. sim/cockpit2/radios/actuators/com1_frequency_hz) into a 25 kHz
+ * channel name. X-Plane truncates the last digit there, so 118.325 is
+ * stored as 11832 and gets restored here. The "_833" variants of these
+ * datarefs already contain channel names in kHz and need no conversion.
+ */
+unsigned
+com_chan_from_xp(int freq_10khz)
+{
+	unsigned chan_khz = MAX(freq_10khz, 0) * 10;
+
+	if (chan_khz % 25 != 0)
+		chan_khz += 5;
+	return (chan_khz);
+}
+
+/*
+ * Converts a COM channel name into X-Plane's 10 kHz-unit dataref
+ * encoding. 8.33 kHz channels lose their last digit.
+ */
+int
+com_chan_to_xp(unsigned chan_khz)
+{
+	return (chan_khz / 10);
+}
+
+/*
+ * Formats a COM channel name for display, e.g. "118.305".
+ */
+void
+com_chan2str(unsigned chan_khz, char buf[COM_CHAN_STR_LEN])
+{
+	snprintf(buf, COM_CHAN_STR_LEN, "%03u.%03u", (chan_khz / 1000) % 1000,
+	    chan_khz % 1000);
+}
+
 /*
  * Checks if a string is a valid ICAO airport code. ICAO airport codes always:
  * 1) are 4 characters long
@@ -974,6 +1089,45 @@ win_perror(DWORD err, const char *fmt, ...)
 	LocalFree(win_msg);
 }
 
+/*
+ * Converts a UTF-8 path into the wide-character form expected by the
+ * Win32 API. The path is made absolute and given the "\\?\" prefix (or
+ * "\\?\UNC\" for network paths), which lifts the MAX_PATH limit on
+ * the path's length. The caller must free the returned string.
+ */
+static WCHAR *
+win_long_path(const char *path)
+{
+	int len = MultiByteToWideChar(CP_UTF8, 0, path, -1, NULL, 0);
+	WCHAR *pathW = safe_calloc(len + 1, sizeof (*pathW));
+	WCHAR *fullW, *longW;
+	DWORD full_len;
+
+	MultiByteToWideChar(CP_UTF8, 0, path, -1, pathW, len + 1);
+	if (wcsncmp(pathW, L"\\\\?\\", 4) == 0 ||
+	    wcsncmp(pathW, L"\\\\.\\", 4) == 0)
+		return (pathW);
+	full_len = GetFullPathNameW(pathW, 0, NULL, NULL);
+	if (full_len == 0) {
+		/* leave it to the caller's API call to report the error */
+		return (pathW);
+	}
+	fullW = safe_calloc(full_len, sizeof (*fullW));
+	GetFullPathNameW(pathW, full_len, fullW, NULL);
+	free(pathW);
+
+	longW = safe_calloc(full_len + 8, sizeof (*longW));
+	if (wcsncmp(fullW, L"\\\\", 2) == 0) {
+		StringCchPrintfW(longW, full_len + 8, L"\\\\?\\UNC\\%s",
+		    &fullW[2]);
+	} else {
+		StringCchPrintfW(longW, full_len + 8, L"\\\\?\\%s", fullW);
+	}
+	free(fullW);
+
+	return (longW);
+}
+
 #endif	/* IBM */
 
 /*
@@ -984,12 +1138,10 @@ bool_t
 file_exists(const char *filename, bool_t *isdir)
 {
 #if	IBM
-	int len = strlen(filename);
-	TCHAR filenameT[len + 1];
-	DWORD attr;
+	WCHAR *filenameW = win_long_path(filename);
+	DWORD attr = GetFileAttributesW(filenameW);
 
-	MultiByteToWideChar(CP_UTF8, 0, filename, -1, filenameT, len + 1);
-	attr = GetFileAttributes(filenameT);
+	free(filenameW);
 	if (isdir != NULL)
 		*isdir = !!(attr & FILE_ATTRIBUTE_DIRECTORY);
 	if (attr == INVALID_FILE_ATTRIBUTES) {
@@ -1023,15 +1175,15 @@ create_directory(const char *dirname)
 	ASSERT(dirname != NULL);
 #if	IBM
 	DWORD err;
-	int len = strlen(dirname);
-	WCHAR dirnameW[len + 1];
+	WCHAR *dirnameW = win_long_path(dirname);
 
-	MultiByteToWideChar(CP_UTF8, 0, dirname, -1, dirnameW, len + 1);
-	if (!CreateDirectory(dirnameW, NULL) &&
+	if (!CreateDirectoryW(dirnameW, NULL) &&
 	    (err = GetLastError()) != ERROR_ALREADY_EXISTS) {
 		win_perror(err, "Error creating directory %s", dirname);
+		free(dirnameW);
 		return (B_FALSE);
 	}
+	free(dirnameW);
 #else	/* !IBM */
 	if (mkdir(dirname, 0777) != 0 && errno != EEXIST) {
 		logMsg("Error creating directory %s: %s", dirname,
@@ -1054,8 +1206,10 @@ create_directory_recursive(const char *dirname)
 	for (const char *start = dirname, *end = strchr(&dirname[1], DIRSEP);
 	    end != NULL; start = end, end = strchr(&start[1], DIRSEP)) {
 		strncat(partname, start, end - start);
-		if (!create_directory(partname))
+		if (!create_directory(partname)) {
+			free(partname);
 			return (B_FALSE);
+		}
 	}
 
 	free(partname);
@@ -1063,71 +1217,6 @@ create_directory_recursive(const char *dirname)
 	return (create_directory(dirname));
 }
 
-#if	IBM
-
-static bool_t
-win_rmdir(const LPTSTR dirnameT)
-{
-	WIN32_FIND_DATA find_data;
-	HANDLE h_find;
-	int dirname_len = wcslen(dirnameT);
-	TCHAR srchnameT[dirname_len + 4];
-
-	StringCchPrintf(srchnameT, dirname_len + 4, TEXT("%s\\*"), dirnameT);
-	h_find = FindFirstFile(srchnameT, &find_data);
-	if (h_find == INVALID_HANDLE_VALUE) {
-		int err = GetLastError();
-		char dirname[MAX_PATH];
-		WideCharToMultiByte(CP_UTF8, 0, dirnameT, -1, dirname,
-		    sizeof (dirname), NULL, NULL);
-		win_perror(err, "Error listing directory %s", dirname);
-		return (B_FALSE);
-	}
-	do {
-		TCHAR filepathT[MAX_PATH];
-		DWORD attrs;
-
-		if (wcscmp(find_data.cFileName, TEXT(".")) == 0 ||
-		    wcscmp(find_data.cFileName, TEXT("..")) == 0)
-			continue;
-
-		StringCchPrintf(filepathT, MAX_PATH, TEXT("%s\\%s"), dirnameT,
-		    find_data.cFileName);
-		attrs = GetFileAttributes(filepathT);
-
-		if (attrs & FILE_ATTRIBUTE_DIRECTORY) {
-			if (!win_rmdir(filepathT))
-				goto errout;
-		} else {
-			if (!DeleteFile(filepathT)) {
-				char filepath[MAX_PATH];
-				WideCharToMultiByte(CP_UTF8, 0, filepathT, -1,
-				    filepath, sizeof (filepath), NULL, NULL);
-				win_perror(GetLastError(), "Error removing "
-				    "file %s", filepath);
-				goto errout;
-			}
-		}
-	} while (FindNextFile(h_find, &find_data));
-	FindClose(h_find);
-
-	if (!RemoveDirectory(dirnameT)) {
-		char dirname[MAX_PATH];
-		WideCharToMultiByte(CP_UTF8, 0, dirnameT, -1,
-		    dirname, sizeof (dirname), NULL, NULL);
-		win_perror(GetLastError(), "Error removing directory %s",
-		    dirname);
-		return (B_FALSE);
-	}
-
-	return (B_TRUE);
-errout:
-	FindClose(h_find);
-	return (B_FALSE);
-}
-
-#endif	/* IBM */
-
 /*
  * Recursive directory removal, including all its contents.
  */
@@ -1135,11 +1224,19 @@ bool_t
 remove_directory(const char *dirname)
 {
 #if	IBM
-	TCHAR dirnameT[strlen(dirname) + 1];
+	WCHAR *dirnameW;
 
-	MultiByteToWideChar(CP_UTF8, 0, dirname, -1, dirnameT,
-	    sizeof (dirnameT));
-	return (win_rmdir(dirnameT));
+	if (!remove_directory_contents(dirname))
+		return (B_FALSE);
+	dirnameW = win_long_path(dirname);
+	if (!RemoveDirectoryW(dirnameW)) {
+		win_perror(GetLastError(), "Error removing directory %s",
+		    dirname);
+		free(dirnameW);
+		return (B_FALSE);
+	}
+	free(dirnameW);
+	return (B_TRUE);
 #else	/* !IBM */
 	DIR *dp;
 	struct dirent *de;
@@ -1199,16 +1296,16 @@ bool_t
 remove_file(const char *filename, bool_t notfound_ok)
 {
 #if	IBM
-	TCHAR filenameT[strlen(filename) + 1];
+	WCHAR *filenameW = win_long_path(filename);
 	DWORD error;
 
-	MultiByteToWideChar(CP_UTF8, 0, filename, -1, filenameT,
-	    sizeof (filenameT));
-	if (!DeleteFile(filenameT) && ((error = GetLastError()) !=
+	if (!DeleteFileW(filenameW) && ((error = GetLastError()) !=
 	    ERROR_FILE_NOT_FOUND || !notfound_ok)) {
 		win_perror(error, "Cannot remove file %s", filename);
+		free(filenameW);
 		return (B_FALSE);
 	}
+	free(filenameW);
 	return (B_TRUE);
 #else	/* !IBM */
 	if (unlink(filename) < 0 && (errno != ENOENT || !notfound_ok)) {
@@ -1219,6 +1316,317 @@ remove_file(const char *filename, bool_t notfound_ok)
 #endif	/* !IBM */
 }
 
+/*
+ * Sets `isdir' to indicate whether `path' is a directory. Returns B_FALSE
+ * if `path' couldn't be examined. On POSIX, symlinks aren't followed, so
+ * a symlink to a directory is treated as a plain file.
+ */
+static bool_t
+is_directory(const char *path, bool_t *isdir)
+{
+#if	IBM
+	return (file_exists(path, isdir));
+#else	/* !IBM */
+	struct stat st;
+
+	if (lstat(path, &st) < 0) {
+		logMsg("Cannot stat %s: %s", path, strerror(errno));
+		return (B_FALSE);
+	}
+	*isdir = S_ISDIR(st.st_mode);
+	return (B_TRUE);
+#endif	/* !IBM */
+}
+
+/*
+ * Removes all files and subdirectories inside of `dirname', but leaves
+ * the (now empty) directory itself in place.
+ */
+bool_t
+remove_directory_contents(const char *dirname)
+{
+	DIR *dp;
+	struct dirent *de;
+	bool_t res = B_TRUE;
+
+	ASSERT(dirname != NULL);
+
+	if ((dp = opendir(dirname)) == NULL) {
+		logMsg("Error removing contents of %s: cannot open directory",
+		    dirname);
+		return (B_FALSE);
+	}
+	while (res && (de = readdir(dp)) != NULL) {
+		char *path;
+		bool_t isdir;
+
+		if (strcmp(de->d_name, ".") == 0 ||
+		    strcmp(de->d_name, "..") == 0)
+			continue;
+		path = mkpathname(dirname, de->d_name, NULL);
+		res = is_directory(path, &isdir);
+		if (res) {
+			res = (isdir ? remove_directory(path) :
+			    remove_file(path, B_FALSE));
+		}
+		free(path);
+	}
+	closedir(dp);
+
+	return (res);
+}
+
+/*
+ * Same as fopen, but on Windows `filename' is treated as UTF-8, the same
+ * as in the rest of our file handling functions, instead of being passed
+ * through the ANSI codepage.
+ */
+static FILE *
+fopen_utf8(const char *filename, const char *mode)
+{
+#if	IBM
+	WCHAR *filenameW = win_long_path(filename);
+	WCHAR modeW[strlen(mode) + 1];
+	FILE *fp;
+
+	MultiByteToWideChar(CP_UTF8, 0, mode, -1, modeW, strlen(mode) + 1);
+	fp = _wfopen(filenameW, modeW);
+	free(filenameW);
+
+	return (fp);
+#else	/* !IBM */
+	return (fopen(filename, mode));
+#endif	/* !IBM */
+}
+
+/*
+ * Copies the file `src' to `dst', overwriting `dst' if it exists.
+ */
+bool_t
+copy_file(const char *src, const char *dst)
+{
+	FILE *src_fp, *dst_fp;
+	uint8_t buf[65536];
+	size_t n;
+	bool_t res = B_TRUE;
+
+	ASSERT(src != NULL);
+	ASSERT(dst != NULL);
+
+	if ((src_fp = fopen_utf8(src, "rb")) == NULL) {
+		logMsg("Error copying %s: %s", src, strerror(errno));
+		return (B_FALSE);
+	}
+	if ((dst_fp = fopen_utf8(dst, "wb")) == NULL) {
+		logMsg("Error copying %s to %s: %s", src, dst,
+		    strerror(errno));
+		fclose(src_fp);
+		return (B_FALSE);
+	}
+	while ((n = fread(buf, 1, sizeof (buf), src_fp)) != 0) {
+		if (fwrite(buf, 1, n, dst_fp) != n) {
+			res = B_FALSE;
+			break;
+		}
+	}
+	if (ferror(src_fp))
+		res = B_FALSE;
+	if (fclose(dst_fp) != 0)
+		res = B_FALSE;
+	fclose(src_fp);
+	if (!res)
+		logMsg("Error copying %s to %s: %s", src, dst, strerror(errno));
+
+	return (res);
+}
+
+/*
+ * Recursively copies the contents of directory `src' into `dst'. `dst'
+ * and any missing intermediate directories leading up to it are created
+ * as necessary. Existing files in `dst' are overwritten.
+ */
+bool_t
+copy_directory_recursive(const char *src, const char *dst)
+{
+	DIR *dp;
+	struct dirent *de;
+	bool_t res;
+
+	ASSERT(src != NULL);
+	ASSERT(dst != NULL);
+
+	if (!create_directory_recursive(dst))
+		return (B_FALSE);
+	if ((dp = opendir(src)) == NULL) {
+		logMsg("Error copying %s: cannot open directory", src);
+		return (B_FALSE);
+	}
+	res = B_TRUE;
+	while (res && (de = readdir(dp)) != NULL) {
+		char *src_path, *dst_path;
+		bool_t isdir;
+
+		if (strcmp(de->d_name, ".") == 0 ||
+		    strcmp(de->d_name, "..") == 0)
+			continue;
+		src_path = mkpathname(src, de->d_name, NULL);
+		dst_path = mkpathname(dst, de->d_name, NULL);
+		res = is_directory(src_path, &isdir);
+		if (res) {
+			res = (isdir ? copy_directory_recursive(src_path,
+			    dst_path) : copy_file(src_path, dst_path));
+		}
+		free(src_path);
+		free(dst_path);
+	}
+	closedir(dp);
+
+	return (res);
+}
+
+#if	!IBM
+/*
+ * Flushes the directory entries of the directory containing `filename'
+ * to stable storage, so that a preceding rename of `filename' survives
+ * a crash of the machine.
+ */
+static bool_t
+sync_parent_dir(const char *filename)
+{
+	char *dirname = lacf_dirname(filename);
+	const char *path;
+	int fd;
+	bool_t res = B_TRUE;
+
+	if (*dirname != '\0')
+		path = dirname;
+	else if (filename[0] == '/')
+		path = "/";
+	else
+		path = ".";
+	fd = open(path, O_RDONLY);
+	/* Some filesystems don't support fsync on directories at all */
+	if (fd < 0 || (fsync(fd) != 0 && errno != EINVAL)) {
+		logMsg("Error writing %s: cannot sync directory %s: %s",
+		    filename, path, strerror(errno));
+		res = B_FALSE;
+	}
+	if (fd >= 0)
+		close(fd);
+	free(dirname);
+
+	return (res);
+}
+#endif	/* !IBM */
+
+/*
+ * Atomically replaces `filename' with `filename_new', i.e. `filename'
+ * either keeps its old contents, or gets the new contents in full.
+ * On POSIX, the containing directory is synced after the rename, so
+ * the replacement is durable once this function returns. On Windows,
+ * durability of the replacement is up to the filesystem.
+ */
+bool_t
+replace_file(const char *filename_new, const char *filename)
+{
+#if	IBM
+	WCHAR *filename_newW, *filenameW;
+	bool_t res = B_TRUE;
+#endif	/* IBM */
+
+	ASSERT(filename_new != NULL);
+	ASSERT(filename != NULL);
+#if	IBM
+	filename_newW = win_long_path(filename_new);
+	filenameW = win_long_path(filename);
+	/*
+	 * Windows needs special handling, because it doesn't let us
+	 * use rename for the replace operation.
+	 */
+	if (file_exists(filename, NULL)) {
+		if (!ReplaceFileW(filenameW, filename_newW, NULL,
+		    REPLACEFILE_IGNORE_MERGE_ERRORS |
+		    REPLACEFILE_IGNORE_ACL_ERRORS, NULL, NULL)) {
+			win_perror(GetLastError(), "Error writing %s: "
+			    "ReplaceFile failed", filename);
+			res = B_FALSE;
+		}
+	} else if (!MoveFileExW(filename_newW, filenameW,
+	    MOVEFILE_REPLACE_EXISTING | MOVEFILE_WRITE_THROUGH)) {
+		win_perror(GetLastError(), "Error writing %s: "
+		    "MoveFileEx failed", filename);
+		res = B_FALSE;
+	}
+	free(filename_newW);
+	free(filenameW);
+	return (res);
+#else	/* !IBM */
+	if (rename(filename_new, filename) != 0) {
+		logMsg("Error writing %s: atomic rename failed: %s",
+		    filename, strerror(errno));
+		return (B_FALSE);
+	}
+	return (sync_parent_dir(filename));
+#endif	/* !IBM */
+}
+
+/*
+ * Flushes everything written to `fp' to stable storage.
+ */
+static bool_t
+sync_file(FILE *fp)
+{
+	if (fflush(fp) != 0)
+		return (B_FALSE);
+#if	IBM
+	return (_commit(_fileno(fp)) == 0);
+#else	/* !IBM */
+	return (fsync(fileno(fp)) == 0);
+#endif	/* !IBM */
+}
+
+/*
+ * Crash-safely writes `bufsz' bytes from `buf' to `filename'. The data
+ * is first written to a temporary file next to `filename' and flushed
+ * to stable storage, before atomically replacing `filename' with it.
+ * If the process or the machine crash midway through, `filename' is
+ * left either with its old contents, or with the new contents in full,
+ * but never with a partially written mix of the two.
+ */
+bool_t
+file_write_atomic(const char *filename, const void *buf, size_t bufsz)
+{
+	char *filename_tmp;
+	FILE *fp;
+	bool_t res = B_FALSE;
+
+	ASSERT(filename != NULL);
+	ASSERT(buf != NULL || bufsz == 0);
+
+	filename_tmp = sprintf_alloc("%s.tmp", filename);
+	fp = fopen_utf8(filename_tmp, "wb");
+	if (fp == NULL) {
+		logMsg("Error writing %s: %s", filename_tmp, strerror(errno));
+		goto out;
+	}
+	if (fwrite(buf, 1, bufsz, fp) != bufsz || !sync_file(fp)) {
+		logMsg("Error writing %s: %s", filename_tmp, strerror(errno));
+		fclose(fp);
+		goto out;
+	}
+	if (fclose(fp) != 0) {
+		logMsg("Error writing %s: %s", filename_tmp, strerror(errno));
+		goto out;
+	}
+	res = replace_file(filename_tmp, filename);
+out:
+	if (!res)
+		(void) remove_file(filename_tmp, B_TRUE);
+	free(filename_tmp);
+
+	return (res);
+}
+
 API_EXPORT char *
 lacf_dirname(const char *filename)
 {
@@ -1246,16 +1654,21 @@ DIR *
 opendir(const char *path)
 {
 	DIR	*dirp = safe_calloc(1, sizeof (*dirp));
-	TCHAR	pathT[strlen(path) + 3];	/* For '\*' at the end */
+	WCHAR	*pathW = win_long_path(path);
+	size_t	srch_len = wcslen(pathW) + 3;	/* For '\*' at the end */
+	WCHAR	*srchW = safe_calloc(srch_len, sizeof (*srchW));
 
-	MultiByteToWideChar(CP_UTF8, 0, path, -1, pathT, sizeof (pathT));
-	StringCchCat(pathT, sizeof (pathT), TEXT("\\*"));
-	dirp->handle = FindFirstFile(pathT, &dirp->find_data);
+	StringCchPrintfW(srchW, srch_len, L"%s\\*", pathW);
+	dirp->handle = FindFirstFile(srchW, &dirp->find_data);
 	if (dirp->handle == INVALID_HANDLE_VALUE) {
 		win_perror(GetLastError(), "Cannot open directory %s", path);
+		free(pathW);
+		free(srchW);
 		free(dirp);
 		return (NULL);
 	}
+	free(pathW);
+	free(srchW);
 	dirp->first = B_TRUE;
 	return (dirp);
 }
@@ -1287,7 +1700,7 @@ stat(const char *pathname, struct stat *buf)
 {
 	FILETIME	wtime, atime;
 	HANDLE		fh;
-	TCHAR		pathnameT[strlen(pathname) + 1];
+	WCHAR		*pathnameW;
 	LARGE_INTEGER	sz;
 	ULARGE_INTEGER	ftime;
 	bool_t		isdir;
@@ -1298,17 +1711,18 @@ stat(const char *pathname, struct stat *buf)
 		return (-1);
 	}
 
-	MultiByteToWideChar(CP_UTF8, 0, pathname, -1, pathnameT,
-	    sizeof (pathnameT));
-	fh = CreateFile(pathnameT, (!isdir ? FILE_READ_ATTRIBUTES : 0) |
+	pathnameW = win_long_path(pathname);
+	fh = CreateFileW(pathnameW, (!isdir ? FILE_READ_ATTRIBUTES : 0) |
 	    GENERIC_READ, FILE_SHARE_READ, NULL, OPEN_EXISTING, 0, NULL);
 	if (fh == INVALID_HANDLE_VALUE ||
 	    !GetFileTime(fh, NULL, &atime, &wtime) ||
 	    (!isdir && !GetFileSizeEx(fh, &sz))) {
 		win_perror(GetLastError(), "Cannot stat %s", pathname);
+		free(pathnameW);
 		errno = EACCES;
 		return (-1);
 	}
+	free(pathnameW);
 	if (isdir)
 		buf->st_size = 0;
 	else
@@ -0,0 +1,270 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <math.h>
+#include <string.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/hud.h>
+#include <acfutils/math.h>
+
+/* Minimum ground speed for a meaningful flight path vector, m/s. */
+#define	MIN_FPV_SPD	1.0
+/* Heading offset of the second point used to construct the horizon. */
+#define	HORIZON_SAMPLE_ANGLE	1.0	/* degrees */
+
+/*
+ * Initializes the geometry of a head-up display. A HUD draws symbols on
+ * a transparent combiner, such that to the pilot's eye they appear to
+ * overlay the outside world (they are "conformal"). This module converts
+ * world-referenced quantities into HUD screen coordinates.
+ *
+ * Coordinate conventions:
+ *
+ *	- The aircraft body frame has its X axis pointing forward, Y out
+ *	  along the right wing and Z down (same as in quat.h). Positions
+ *	  in the body frame are relative to the aircraft's reference point
+ *	  (whatever position you pass to hud_geo2scr as `acf_pos').
+ *	- Velocities are in the local North-East-Down (NED) frame.
+ *	- Screen coordinates are in pixels, with the origin at the center
+ *	  of the display, X increasing to the right and Y increasing up.
+ *	  The display center lies on the HUD's boresight.
+ *
+ * The projection is gnomonic (rectilinear), which maps straight lines
+ * in the world to straight lines on the display, so e.g. runway edges
+ * can be drawn as simple lines between their projected corners.
+ *
+ * Typical usage:
+ *
+ *	hud_t hud;
+ *	hud_init(&hud, VECT3(12.5, -0.5, -1.2), 0, 30, 24,
+ *	    VECT2(1024, 768));
+ *	...on every frame:
+ *	euler_t att = EULER(pitch, roll, true_hdg);
+ *	vect2_t fpv;
+ *	if (hud_fpv(&hud, att, vel_ned, &fpv) && hud_scr_in_fov(&hud, fpv))
+ *		draw_fpv(fpv);
+ *
+ * @param eye The pilot's design eye position in the body frame. This only
+ *	matters for objects close to the aircraft, such as runway corners
+ *	during the landing flare.
+ * @param depression Angle (in degrees) by which the HUD's boresight is
+ *	depressed below the body X axis. Most HUDs are aligned with the
+ *	body axis, so this is usually zero.
+ * @param fov_x Total horizontal field of view of the display in degrees.
+ * @param fov_y Total vertical field of view of the display in degrees.
+ * @param size Size of the display in pixels.
+ */
+void
+hud_init(hud_t *hud, vect3_t eye, double depression, double fov_x,
+    double fov_y, vect2_t size)
+{
+	double d = DEG2RAD(depression);
+
+	ASSERT(hud != NULL);
+	ASSERT3F(fov_x, >, 0);
+	ASSERT3F(fov_x, <, 180);
+	ASSERT3F(fov_y, >, 0);
+	ASSERT3F(fov_y, <, 180);
+	ASSERT3F(size.x, >, 0);
+	ASSERT3F(size.y, >, 0);
+
+	memset(hud, 0, sizeof (*hud));
+	hud->eye = eye;
+	hud->depression = depression;
+	hud->fov_x = fov_x;
+	hud->fov_y = fov_y;
+	hud->size = size;
+
+	hud->fwd = VECT3(cos(d), 0, sin(d));
+	hud->up = VECT3(sin(d), 0, -cos(d));
+	hud->scale = VECT2(size.x / 2 / tan(DEG2RAD(fov_x / 2)),
+	    size.y / 2 / tan(DEG2RAD(fov_y / 2)));
+}
+
+/*
+ * Projects a direction in the body frame (i.e. an object at infinity)
+ * onto the display. The direction needn't be a unit vector.
+ *
+ * @return B_TRUE if the direction lies in front of the pilot and `scr'
+ *	was filled in. The position may still lie outside of the display
+ *	(use hud_scr_in_fov to check). B_FALSE if the direction points
+ *	90 degrees or more away from the boresight.
+ */
+bool_t
+hud_dir2scr(const hud_t *hud, vect3_t dir, vect2_t *scr)
+{
+	double f;
+
+	ASSERT(hud != NULL);
+	ASSERT(scr != NULL);
+
+	f = vect3_dotprod(dir, hud->fwd);
+	if (f <= 0)
+		return (B_FALSE);
+	*scr = VECT2(dir.y / f * hud->scale.x,
+	    vect3_dotprod(dir, hud->up) / f * hud->scale.y);
+
+	return (B_TRUE);
+}
+
+/*
+ * Projects a point in the body frame onto the display, as seen from the
+ * design eye position. Returns the same as hud_dir2scr.
+ */
+bool_t
+hud_pt2scr(const hud_t *hud, vect3_t pt, vect2_t *scr)
+{
+	ASSERT(hud != NULL);
+	return (hud_dir2scr(hud, vect3_sub(pt, hud->eye), scr));
+}
+
+/*
+ * Returns B_TRUE if a screen position lies within the display area.
+ */
+bool_t
+hud_scr_in_fov(const hud_t *hud, vect2_t scr)
+{
+	ASSERT(hud != NULL);
+	return (fabs(scr.x) <= hud->size.x / 2 &&
+	    fabs(scr.y) <= hud->size.y / 2);
+}
+
+/*
+ * Computes the screen position of the flight path vector (FPV), which
+ * shows where the aircraft is actually going.
+ *
+ * @param att Aircraft attitude.
+ * @param vel Aircraft velocity in the NED frame in m/s (e.g. ground
+ *	speed north, ground speed east and vertical speed with the sign
+ *	flipped).
+ * @param scr Filled with the FPV's screen position.
+ *
+ * @return B_TRUE on success, B_FALSE if the aircraft is moving too
+ *	slowly for the FPV to be meaningful, or if the FPV lies outside of
+ *	the pilot's forward hemisphere.
+ */
+bool_t
+hud_fpv(const hud_t *hud, euler_t att, vect3_t vel, vect2_t *scr)
+{
+	if (vect3_abs(vel) < MIN_FPV_SPD)
+		return (B_FALSE);
+	return (hud_dir2scr(hud, quat_rot_inv(quat_from_euler(att), vel),
+	    scr));
+}
+
+/*
+ * Projects the direction `pitch' degrees above the horizon at true
+ * heading `hdg'.
+ */
+static bool_t
+sky_dir2scr(const hud_t *hud, euler_t att, double pitch, double hdg,
+    vect2_t *scr)
+{
+	double p = DEG2RAD(pitch), h = DEG2RAD(hdg);
+	vect3_t dir = VECT3(cos(p) * cos(h), cos(p) * sin(h), -sin(p));
+
+	return (hud_dir2scr(hud, quat_rot_inv(quat_from_euler(att), dir),
+	    scr));
+}
+
+/*
+ * Computes the conformal horizon line. The horizon is returned as a point
+ * and a direction, since its end points usually lie well outside of the
+ * display. Clip the line to the display area before drawing it.
+ *
+ * @param att Aircraft attitude.
+ * @param pt Filled with the screen position of the horizon straight
+ *	ahead along the aircraft's heading.
+ * @param dir Filled with a unit vector along the horizon line, pointing
+ *	towards increasing headings (i.e. to the right in upright flight).
+ *
+ * @return B_TRUE on success, B_FALSE if the aircraft is pitched so
+ *	steeply that the horizon ahead isn't in front of the pilot.
+ */
+bool_t
+hud_horizon(const hud_t *hud, euler_t att, vect2_t *pt, vect2_t *dir)
+{
+	vect2_t pt2;
+
+	ASSERT(pt != NULL);
+	ASSERT(dir != NULL);
+
+	if (!hud_pitch2scr(hud, att, 0, pt))
+		return (B_FALSE);
+	/*
+	 * Projections of all level directions lie on the horizon line, so
+	 * a direction slightly to the right of our heading gives us a
+	 * second point on it.
+	 */
+	if (!sky_dir2scr(hud, att, 0, att.hdg + HORIZON_SAMPLE_ANGLE, &pt2))
+		return (B_FALSE);
+	*dir = vect2_unit(vect2_sub(pt2, *pt), NULL);
+
+	return (B_TRUE);
+}
+
+/*
+ * Computes the screen position of a pitch ladder line's center, i.e. of
+ * the direction `pitch' degrees above the horizon along the aircraft's
+ * heading. Returns the same as hud_dir2scr.
+ */
+bool_t
+hud_pitch2scr(const hud_t *hud, euler_t att, double pitch, vect2_t *scr)
+{
+	return (sky_dir2scr(hud, att, pitch, att.hdg, scr));
+}
+
+/*
+ * Converts a difference of ECEF coordinates into the local NED frame at
+ * `ref'.
+ */
+static vect3_t
+ecef2ned(vect3_t d, geo_pos3_t ref)
+{
+	double slat = sin(DEG2RAD(ref.lat)), clat = cos(DEG2RAD(ref.lat));
+	double slon = sin(DEG2RAD(ref.lon)), clon = cos(DEG2RAD(ref.lon));
+
+	return (VECT3(-slat * clon * d.x - slat * slon * d.y + clat * d.z,
+	    -slon * d.x + clon * d.y,
+	    -clat * clon * d.x - clat * slon * d.y - slat * d.z));
+}
+
+/*
+ * Projects a geographic position (such as a runway threshold corner)
+ * onto the display.
+ *
+ * @param att Aircraft attitude.
+ * @param acf_pos Position of the aircraft's reference point. The
+ *	elevation is in meters above the ellipsoid.
+ * @param pos Position to project. The elevation is in meters above the
+ *	ellipsoid.
+ * @param scr Filled with the screen position.
+ *
+ * @return Same as hud_dir2scr.
+ */
+bool_t
+hud_geo2scr(const hud_t *hud, euler_t att, geo_pos3_t acf_pos,
+    geo_pos3_t pos, vect2_t *scr)
+{
+	vect3_t d = vect3_sub(geo2ecef_mtr(pos, &wgs84),
+	    geo2ecef_mtr(acf_pos, &wgs84));
+
+	return (hud_pt2scr(hud, quat_rot_inv(quat_from_euler(att),
+	    ecef2ned(d, acf_pos)), scr));
+}
@@ -20,6 +20,7 @@
 #include <stdio.h>
 #include <string.h>
 
+#include <acfutils/assert.h>
 #include <acfutils/icao2cc.h>
 
 typedef struct {
@@ -421,12 +422,259 @@ static icao2cc_t icao2cc_table[] = {
     { .icao = NULL, .cc = NULL, .lang = NULL }	/* Last entry */
 };
 
+typedef struct {
+	const char *cc;
+	const char *name;
+} cc2name_t;
+
 /*
- * Converts an ICAO code to a country code. This performs a simple prefix
- * match using the icao2cc_table.
+ * Country names, sorted by country code for bsearch.
  */
-const char *
-icao2cc(const char *icao)
+static const cc2name_t cc2name_table[] = {
+    { .cc = "AE", .name = "United Arab Emirates" },
+    { .cc = "AF", .name = "Afghanistan" },
+    { .cc = "AG", .name = "Antigua and Barbuda" },
+    { .cc = "AI", .name = "Anguilla" },
+    { .cc = "AL", .name = "Albania" },
+    { .cc = "AM", .name = "Armenia" },
+    { .cc = "AO", .name = "Angola" },
+    { .cc = "AR", .name = "Argentina" },
+    { .cc = "AS", .name = "American Samoa" },
+    { .cc = "AT", .name = "Austria" },
+    { .cc = "AU", .name = "Australia" },
+    { .cc = "AW", .name = "Aruba" },
+    { .cc = "AZ", .name = "Azerbaijan" },
+    { .cc = "BA", .name = "Bosnia and Herzegovina" },
+    { .cc = "BB", .name = "Barbados" },
+    { .cc = "BD", .name = "Bangladesh" },
+    { .cc = "BE", .name = "Belgium" },
+    { .cc = "BF", .name = "Burkina Faso" },
+    { .cc = "BG", .name = "Bulgaria" },
+    { .cc = "BH", .name = "Bahrain" },
+    { .cc = "BI", .name = "Burundi" },
+    { .cc = "BJ", .name = "Benin" },
+    { .cc = "BL", .name = "Saint Barthélemy" },
+    { .cc = "BM", .name = "Bermuda" },
+    { .cc = "BN", .name = "Brunei" },
+    { .cc = "BO", .name = "Bolivia" },
+    { .cc = "BQ", .name = "Caribbean Netherlands" },
+    { .cc = "BR", .name = "Brazil" },
+    { .cc = "BS", .name = "Bahamas" },
+    { .cc = "BT", .name = "Bhutan" },
+    { .cc = "BW", .name = "Botswana" },
+    { .cc = "BY", .name = "Belarus" },
+    { .cc = "BZ", .name = "Belize" },
+    { .cc = "CA", .name = "Canada" },
+    { .cc = "CD", .name = "Democratic Republic of the Congo" },
+    { .cc = "CF", .name = "Central African Republic" },
+    { .cc = "CG", .name = "Republic of the Congo" },
+    { .cc = "CH", .name = "Switzerland" },
+    { .cc = "CI", .name = "Ivory Coast" },
+    { .cc = "CK", .name = "Cook Islands" },
+    { .cc = "CL", .name = "Chile" },
+    { .cc = "CM", .name = "Cameroon" },
+    { .cc = "CN", .name = "China" },
+    { .cc = "CO", .name = "Colombia" },
+    { .cc = "CR", .name = "Costa Rica" },
+    { .cc = "CU", .name = "Cuba" },
+    { .cc = "CV", .name = "Cape Verde" },
+    { .cc = "CW", .name = "Curaçao" },
+    { .cc = "CX", .name = "Christmas Island" },
+    { .cc = "CY", .name = "Cyprus" },
+    { .cc = "CZ", .name = "Czech Republic" },
+    { .cc = "DE", .name = "Germany" },
+    { .cc = "DJ", .name = "Djibouti" },
+    { .cc = "DK", .name = "Denmark" },
+    { .cc = "DM", .name = "Dominica" },
+    { .cc = "DO", .name = "Dominican Republic" },
+    { .cc = "DZ", .name = "Algeria" },
+    { .cc = "EC", .name = "Ecuador" },
+    { .cc = "EE", .name = "Estonia" },
+    { .cc = "EG", .name = "Egypt" },
+    { .cc = "EH", .name = "Western Sahara" },
+    { .cc = "ER", .name = "Eritrea" },
+    { .cc = "ES", .name = "Spain" },
+    { .cc = "ET", .name = "Ethiopia" },
+    { .cc = "FI", .name = "Finland" },
+    { .cc = "FJ", .name = "Fiji" },
+    { .cc = "FK", .name = "Falkland Islands" },
+    { .cc = "FM", .name = "Federated States of Micronesia" },
+    { .cc = "FR", .name = "France" },
+    { .cc = "GA", .name = "Gabon" },
+    { .cc = "GB", .name = "United Kingdom" },
+    { .cc = "GD", .name = "Grenada" },
+    { .cc = "GE", .name = "Georgia" },
+    { .cc = "GF", .name = "French Guiana" },
+    { .cc = "GH", .name = "Ghana" },
+    { .cc = "GI", .name = "Gibraltar" },
+    { .cc = "GL", .name = "Greenland" },
+    { .cc = "GM", .name = "Gambia" },
+    { .cc = "GN", .name = "Guinea" },
+    { .cc = "GP", .name = "Guadeloupe" },
+    { .cc = "GQ", .name = "Equatorial Guinea" },
+    { .cc = "GR", .name = "Greece" },
+    { .cc = "GS", .name = "South Georgia and the South Sandwich Islands" },
+    { .cc = "GT", .name = "Guatemala" },
+    { .cc = "GU", .name = "Guam" },
+    { .cc = "GW", .name = "Guinea-Bissau" },
+    { .cc = "GY", .name = "Guyana" },
+    { .cc = "HK", .name = "Hong Kong" },
+    { .cc = "HN", .name = "Honduras" },
+    { .cc = "HR", .name = "Croatia" },
+    { .cc = "HT", .name = "Haiti" },
+    { .cc = "HU", .name = "Hungary" },
+    { .cc = "ID", .name = "Indonesia" },
+    { .cc = "IE", .name = "Ireland" },
+    { .cc = "IL", .name = "Israel" },
+    { .cc = "IN", .name = "India" },
+    { .cc = "IO", .name = "British Indian Ocean Territory" },
+    { .cc = "IQ", .name = "Iraq" },
+    { .cc = "IR", .name = "Iran" },
+    { .cc = "IS", .name = "Iceland" },
+    { .cc = "IT", .name = "Italy" },
+    { .cc = "JM", .name = "Jamaica" },
+    { .cc = "JO", .name = "Jordan" },
+    { .cc = "JP", .name = "Japan" },
+    { .cc = "KE", .name = "Kenya" },
+    { .cc = "KG", .name = "Kyrgyzstan" },
+    { .cc = "KH", .name = "Cambodia" },
+    { .cc = "KI", .name = "Kiribati" },
+    { .cc = "KM", .name = "Comoros" },
+    { .cc = "KN", .name = "Saint Kitts and Nevis" },
+    { .cc = "KP", .name = "North Korea" },
+    { .cc = "KR", .name = "South Korea" },
+    { .cc = "KW", .name = "Kuwait" },
+    { .cc = "KY", .name = "Cayman Islands" },
+    { .cc = "KZ", .name = "Kazakhstan" },
+    { .cc = "LA", .name = "Laos" },
+    { .cc = "LB", .name = "Lebanon" },
+    { .cc = "LC", .name = "Saint Lucia" },
+    { .cc = "LK", .name = "Sri Lanka" },
+    { .cc = "LR", .name = "Liberia" },
+    { .cc = "LS", .name = "Lesotho" },
+    { .cc = "LT", .name = "Lithuania" },
+    { .cc = "LU", .name = "Luxembourg" },
+    { .cc = "LV", .name = "Latvia" },
+    { .cc = "LY", .name = "Libya" },
+    { .cc = "MA", .name = "Morocco" },
+    { .cc = "MC", .name = "Monaco" },
+    { .cc = "MD", .name = "Moldova" },
+    { .cc = "ME", .name = "Montenegro" },
+    { .cc = "MF", .name = "Saint Martin" },
+    { .cc = "MG", .name = "Madagascar" },
+    { .cc = "MH", .name = "Marshall Islands" },
+    { .cc = "MK", .name = "Macedonia" },
+    { .cc = "ML", .name = "Mali" },
+    { .cc = "MM", .name = "Myanmar" },
+    { .cc = "MN", .name = "Mongolia" },
+    { .cc = "MO", .name = "Macau" },
+    { .cc = "MP", .name = "Northern Mariana Islands" },
+    { .cc = "MQ", .name = "Martinique" },
+    { .cc = "MR", .name = "Mauritania" },
+    { .cc = "MS", .name = "Montserrat" },
+    { .cc = "MT", .name = "Malta" },
+    { .cc = "MU", .name = "Mauritius" },
+    { .cc = "MV", .name = "Maldives" },
+    { .cc = "MW", .name = "Malawi" },
+    { .cc = "MX", .name = "Mexico" },
+    { .cc = "MY", .name = "Malaysia" },
+    { .cc = "MZ", .name = "Mozambique" },
+    { .cc = "NA", .name = "Namibia" },
+    { .cc = "NC", .name = "New Caledonia" },
+    { .cc = "NE", .name = "Niger" },
+    { .cc = "NG", .name = "Nigeria" },
+    { .cc = "NI", .name = "Nicaragua" },
+    { .cc = "NL", .name = "Netherlands" },
+    { .cc = "NO", .name = "Norway" },
+    { .cc = "NP", .name = "Nepal" },
+    { .cc = "NR", .name = "Nauru" },
+    { .cc = "NU", .name = "Niue" },
+    { .cc = "NZ", .name = "New Zealand" },
+    { .cc = "OM", .name = "Oman" },
+    { .cc = "PA", .name = "Panama" },
+    { .cc = "PE", .name = "Peru" },
+    { .cc = "PF", .name = "French Polynesia" },
+    { .cc = "PG", .name = "Papua New Guinea" },
+    { .cc = "PH", .name = "Philippines" },
+    { .cc = "PK", .name = "Pakistan" },
+    { .cc = "PL", .name = "Poland" },
+    { .cc = "PM", .name = "Saint Pierre and Miquelon" },
+    { .cc = "PR", .name = "Puerto Rico" },
+    { .cc = "PS", .name = "Palestine" },
+    { .cc = "PT", .name = "Portugal" },
+    { .cc = "PW", .name = "Palau" },
+    { .cc = "PY", .name = "Paraguay" },
+    { .cc = "QA", .name = "Qatar" },
+    { .cc = "RE", .name = "Réunion" },
+    { .cc = "RO", .name = "Romania" },
+    { .cc = "RS", .name = "Serbia" },
+    { .cc = "RU", .name = "Russia" },
+    { .cc = "RW", .name = "Rwanda" },
+    { .cc = "SA", .name = "Saudi Arabia" },
+    { .cc = "SB", .name = "Solomon Islands" },
+    { .cc = "SC", .name = "Seychelles" },
+    { .cc = "SD", .name = "Sudan" },
+    { .cc = "SE", .name = "Sweden" },
+    { .cc = "SG", .name = "Singapore" },
+    { .cc = "SH", .name = "Saint Helena, Ascension and Tristan da Cunha" },
+    { .cc = "SI", .name = "Slovenia" },
+    { .cc = "SK", .name = "Slovakia" },
+    { .cc = "SL", .name = "Sierra Leone" },
+    { .cc = "SN", .name = "Senegal" },
+    { .cc = "SO", .name = "Somalia" },
+    { .cc = "SR", .name = "Suriname" },
+    { .cc = "SS", .name = "South Sudan" },
+    { .cc = "ST", .name = "São Tomé and Príncipe" },
+    { .cc = "SV", .name = "El Salvador" },
+    { .cc = "SX", .name = "Sint Maarten" },
+    { .cc = "SY", .name = "Syria" },
+    { .cc = "SZ", .name = "Swaziland" },
+    { .cc = "TC", .name = "Turks and Caicos Islands" },
+    { .cc = "TD", .name = "Chad" },
+    { .cc = "TG", .name = "Togo" },
+    { .cc = "TH", .name = "Thailand" },
+    { .cc = "TJ", .name = "Tajikistan" },
+    { .cc = "TL", .name = "Timor-Leste" },
+    { .cc = "TM", .name = "Turkmenistan" },
+    { .cc = "TN", .name = "Tunisia" },
+    { .cc = "TO", .name = "Tonga" },
+    { .cc = "TR", .name = "Turkey" },
+    { .cc = "TT", .name = "Trinidad and Tobago" },
+    { .cc = "TV", .name = "Tuvalu" },
+    { .cc = "TW", .name = "Taiwan" },
+    { .cc = "TZ", .name = "Tanzania" },
+    { .cc = "UA", .name = "Ukraine" },
+    { .cc = "UG", .name = "Uganda" },
+    { .cc = "US", .name = "United States" },
+    { .cc = "UY", .name = "Uruguay" },
+    { .cc = "UZ", .name = "Uzbekistan" },
+    { .cc = "VC", .name = "Saint Vincent and the Grenadines" },
+    { .cc = "VE", .name = "Venezuela" },
+    { .cc = "VG", .name = "British Virgin Islands" },
+    { .cc = "VI", .name = "United States Virgin Islands" },
+    { .cc = "VN", .name = "Vietnam" },
+    { .cc = "VU", .name = "Vanuatu" },
+    { .cc = "WF", .name = "Wallis and Futuna" },
+    { .cc = "WS", .name = "Samoa" },
+    { .cc = "XK", .name = "Kosovo" },
+    { .cc = "YE", .name = "Yemen" },
+    { .cc = "YT", .name = "Mayotte" },
+    { .cc = "ZA", .name = "South Africa" },
+    { .cc = "ZM", .name = "Zambia" },
+    { .cc = "ZW", .name = "Zimbabwe" },
+};
+
+#define	NUM_ICAO2CC	(ARRAY_NUM_ELEM(icao2cc_table) - 1)
+
+static int
+cc2name_compar(const void *a, const void *b)
+{
+	const cc2name_t *ca = a, *cb = b;
+	return (strcmp(ca->cc, cb->cc));
+}
+
+static const icao2cc_t *
+icao2cc_lookup(const char *icao)
 {
 	/*
 	 * Doing a linear search is not particularly elegant, but the size
@@ -436,12 +684,31 @@ icao2cc(const char *icao)
 	for (int i = 0; icao2cc_table[i].icao != NULL; i++) {
 		if (memcmp(icao, icao2cc_table[i].icao,
 		    strlen(icao2cc_table[i].icao)) == 0)
-			return (icao2cc_table[i].cc);
+			return (&icao2cc_table[i]);
 	}
-
 	return (NULL);
 }
 
+static void
+icao2cc_fill_info(const icao2cc_t *ent, icao2cc_info_t *info)
+{
+	info->icao = ent->icao;
+	info->cc = ent->cc;
+	info->lang = ent->lang;
+	info->name = cc2name(ent->cc);
+}
+
+/*
+ * Converts an ICAO code to a country code. This performs a simple prefix
+ * match using the icao2cc_table.
+ */
+const char *
+icao2cc(const char *icao)
+{
+	const icao2cc_t *ent = icao2cc_lookup(icao);
+	return (ent != NULL ? ent->cc : NULL);
+}
+
 /*
  * Grabs an ICAO airport code and tries to map it to language code of
  * the principal language spoken at that airport. This shouldn't be relied
@@ -453,10 +720,117 @@ icao2cc(const char *icao)
 const char *
 icao2lang(const char *icao)
 {
+	const icao2cc_t *ent = icao2cc_lookup(icao);
+	return (ent != NULL ? ent->lang : "XX");
+}
+
+/*
+ * Same as icao2cc, but returns all the information we have on the
+ * ICAO code's country. Returns B_TRUE and fills `info' if a mapping was
+ * found, otherwise returns B_FALSE. In the returned info, `icao' is the
+ * table entry which matched (usually just a one- or two-letter prefix of
+ * the input code).
+ */
+bool_t
+icao2cc_info(const char *icao, icao2cc_info_t *info)
+{
+	const icao2cc_t *ent;
+
+	ASSERT(icao != NULL);
+	ASSERT(info != NULL);
+	ent = icao2cc_lookup(icao);
+	if (ent == NULL)
+		return (B_FALSE);
+	icao2cc_fill_info(ent, info);
+
+	return (B_TRUE);
+}
+
+/*
+ * Returns the English name of a country given its ISO 3166-1 alpha-2
+ * country code (as returned by icao2cc), or NULL if the country code
+ * is unknown.
+ */
+const char *
+cc2name(const char *cc)
+{
+	cc2name_t key = { .cc = cc };
+	const cc2name_t *ent;
+
+	ASSERT(cc != NULL);
+	ent = bsearch(&key, cc2name_table, ARRAY_NUM_ELEM(cc2name_table),
+	    sizeof (*cc2name_table), cc2name_compar);
+	return (ent != NULL ? ent->name : NULL);
+}
+
+/*
+ * Performs the reverse of icao2cc: collects all ICAO code prefixes which
+ * map to a country code. Some countries also have entries for individual
+ * airports, which are returned as well. Countries sharing a prefix (e.g.
+ * Brunei and Malaysia on "WB") each get the shared prefix, even though
+ * icao2cc only ever maps it to one of them.
+ *
+ * @param cc The country code to look up.
+ * @param prefixes Optional array to be filled with the ICAO prefixes. The
+ *	returned strings are static and must not be freed.
+ * @param cap Capacity of `prefixes'.
+ *
+ * @return The total number of prefixes mapping to `cc', which may be
+ *	greater than `cap'. Call with `prefixes' set to NULL to find out
+ *	how large an array you need.
+ */
+size_t
+cc2icao_regions(const char *cc, const char **prefixes, size_t cap)
+{
+	size_t n = 0;
+
+	ASSERT(cc != NULL);
+	ASSERT(prefixes != NULL || cap == 0);
+
 	for (int i = 0; icao2cc_table[i].icao != NULL; i++) {
-		if (memcmp(icao, icao2cc_table[i].icao,
-		    strlen(icao2cc_table[i].icao)) == 0)
-			return (icao2cc_table[i].lang);
+		bool_t dup = B_FALSE;
+
+		if (strcmp(icao2cc_table[i].cc, cc) != 0)
+			continue;
+		/* skip duplicate table entries */
+		for (int j = 0; j < i; j++) {
+			if (strcmp(icao2cc_table[j].icao,
+			    icao2cc_table[i].icao) == 0 &&
+			    strcmp(icao2cc_table[j].cc, cc) == 0) {
+				dup = B_TRUE;
+				break;
+			}
+		}
+		if (dup)
+			continue;
+		if (n < cap)
+			prefixes[n] = icao2cc_table[i].icao;
+		n++;
 	}
-	return ("XX");
+
+	return (n);
+}
+
+/*
+ * Returns the number of ICAO prefix-to-country mappings known. Use this
+ * together with icao2cc_get to iterate over all of them, e.g. to build a
+ * region selection UI.
+ */
+size_t
+icao2cc_count(void)
+{
+	return (NUM_ICAO2CC);
+}
+
+/*
+ * Returns the ICAO prefix-to-country mapping at index `i', which must be
+ * less than icao2cc_count(). The mappings are ordered from the most
+ * specific to the most general, same as they are matched by icao2cc.
+ */
+void
+icao2cc_get(size_t i, icao2cc_info_t *info)
+{
+	ASSERT3U(i, <, NUM_ICAO2CC);
+	ASSERT(info != NULL);
+	icao2cc_fill_info(&icao2cc_table[i], info);
 }
@@ -0,0 +1,338 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <errno.h>
+#include <stdio.h>
+#include <string.h>
+
+#if	IBM
+#include <io.h>
+#else	/* !IBM */
+#include <unistd.h>
+#endif	/* !IBM */
+
+#include <acfutils/assert.h>
+#include <acfutils/crc64.h>
+#include <acfutils/helpers.h>
+#include <acfutils/journal.h>
+#include <acfutils/log.h>
+#include <acfutils/safe_alloc.h>
+#include <acfutils/thread.h>
+
+#define	JOURNAL_MAGIC	0x4c4e524au	/* "JRNL" in little endian */
+
+/*
+ * On-disk record header. All fields are in host byte order, since the
+ * journal is only ever meant to be read back on the machine which wrote
+ * it. The checksum covers the header (with the `crc' field zeroed) and
+ * the record payload which immediately follows the header.
+ */
+typedef struct {
+	uint32_t	magic;
+	uint32_t	type;
+	uint64_t	seq;
+	uint32_t	len;
+	uint32_t	rsvd;
+	uint64_t	crc;
+} rec_hdr_t;
+CTASSERT(sizeof (rec_hdr_t) == 32);
+
+struct journal_s {
+	char		*path;
+	FILE		*fp;
+	mutex_t		lock;
+	uint64_t	seq;
+	bool_t		sync;
+};
+
+static uint64_t
+rec_crc(const rec_hdr_t *hdr, const void *data)
+{
+	rec_hdr_t tmp = *hdr;
+	uint64_t crc;
+
+	tmp.crc = 0;
+	crc64_state_init(&crc);
+	crc = crc64_append(crc, &tmp, sizeof (tmp));
+	return (crc64_append(crc, data, hdr->len));
+}
+
+/*
+ * Makes sure everything we've written to the journal is on stable
+ * storage before we return, so a crash right after can't lose it.
+ */
+static bool_t
+journal_flush(journal_t *journal)
+{
+	if (fflush(journal->fp) != 0)
+		return (B_FALSE);
+	if (!journal->sync)
+		return (B_TRUE);
+#if	IBM
+	return (_commit(_fileno(journal->fp)) == 0);
+#else	/* !IBM */
+	return (fsync(fileno(journal->fp)) == 0);
+#endif	/* !IBM */
+}
+
+static bool_t
+journal_truncate(journal_t *journal, long len)
+{
+	if (fflush(journal->fp) != 0)
+		return (B_FALSE);
+#if	IBM
+	if (_chsize_s(_fileno(journal->fp), len) != 0)
+		return (B_FALSE);
+#else	/* !IBM */
+	if (ftruncate(fileno(journal->fp), len) != 0)
+		return (B_FALSE);
+#endif	/* !IBM */
+	return (journal_flush(journal));
+}
+
+/*
+ * Replays the valid records in `buf' and returns the length of the
+ * valid prefix of the journal. Replay stops at the first truncated or
+ * corrupted record, since everything after it can't be trusted.
+ */
+static size_t
+journal_replay(journal_t *journal, const uint8_t *buf, size_t bufsz,
+    journal_replay_cb_t replay_cb, void *userinfo)
+{
+	size_t off = 0;
+
+	while (bufsz - off >= sizeof (rec_hdr_t)) {
+		rec_hdr_t hdr;
+		const uint8_t *data = buf + off + sizeof (hdr);
+
+		memcpy(&hdr, buf + off, sizeof (hdr));
+		if (hdr.magic != JOURNAL_MAGIC ||
+		    hdr.len > JOURNAL_MAX_REC_LEN ||
+		    hdr.len > bufsz - off - sizeof (hdr) ||
+		    hdr.seq < journal->seq ||
+		    rec_crc(&hdr, data) != hdr.crc)
+			break;
+		if (replay_cb != NULL)
+			replay_cb(hdr.type, hdr.seq, data, hdr.len, userinfo);
+		journal->seq = hdr.seq + 1;
+		off += sizeof (hdr) + hdr.len;
+	}
+
+	return (off);
+}
+
+/*
+ * Opens a journal of critical state changes, creating it if it doesn't
+ * exist yet. Plugins can use a journal to make state changes durable
+ * between full state saves (e.g. failures which have been applied, or
+ * accumulated wear), since a full save is often too expensive to do on
+ * every change. The typical lifecycle is:
+ *
+ * 1) At startup, load the last full save of your state, then call
+ *	journal_open. Any records appended since the last full save are
+ *	passed to `replay_cb' in the order they were appended, so you can
+ *	re-apply them on top of the loaded state.
+ * 2) Whenever a critical state change happens, describe it in a record
+ *	and call journal_append. Once journal_append returns, the record
+ *	is on stable storage and will be replayed after a crash.
+ * 3) After completing a full state save, call journal_reset to discard
+ *	all records, since they are now part of the full save.
+ *
+ * Every record is protected by a CRC64 checksum. If the application
+ * crashed in the middle of appending a record, the incomplete record
+ * (and anything following it) is discarded on open and the journal is
+ * truncated back to the last valid record. You must have called
+ * crc64_init before using journals.
+ *
+ * @param path Path to the journal file.
+ * @param replay_cb Optional callback to be called for every valid record
+ *	in the journal. The record payload passed to the callback is only
+ *	valid for the duration of the call.
+ * @param userinfo Optional argument passed to `replay_cb'.
+ *
+ * @return The journal, or NULL if the journal file couldn't be opened
+ *	for writing (an error is logged).
+ */
+journal_t *
+journal_open(const char *path, journal_replay_cb_t replay_cb, void *userinfo)
+{
+	journal_t *journal;
+	size_t bufsz, valid;
+	void *buf;
+
+	ASSERT(path != NULL);
+
+	journal = safe_calloc(1, sizeof (*journal));
+	journal->path = safe_strdup(path);
+	journal->sync = B_TRUE;
+	mutex_init(&journal->lock);
+
+	buf = file2buf(path, &bufsz);
+	valid = journal_replay(journal, buf, bufsz, replay_cb, userinfo);
+	free(buf);
+
+	journal->fp = fopen(path, "ab");
+	if (journal->fp == NULL) {
+		logMsg("Error opening journal %s: %s", path, strerror(errno));
+		journal_close(journal);
+		return (NULL);
+	}
+	if (valid < bufsz) {
+		logMsg("Journal %s: discarding %ld bytes of incomplete or "
+		    "corrupted records", path, (long)(bufsz - valid));
+		if (!journal_truncate(journal, valid)) {
+			logMsg("Error truncating journal %s: %s", path,
+			    strerror(errno));
+			journal_close(journal);
+			return (NULL);
+		}
+	}
+
+	return (journal);
+}
+
+void
+journal_close(journal_t *journal)
+{
+	if (journal == NULL)
+		return;
+	if (journal->fp != NULL)
+		fclose(journal->fp);
+	mutex_destroy(&journal->lock);
+	free(journal->path);
+	free(journal);
+}
+
+/*
+ * Appends a record to the journal. This function may be called from any
+ * thread.
+ *
+ * @param type An application-defined record type, passed back to the
+ *	replay callback in journal_open.
+ * @param data Record payload. May be NULL if `len' is 0.
+ * @param len Length of `data' in bytes. Must not exceed
+ *	JOURNAL_MAX_REC_LEN.
+ *
+ * @return B_TRUE if the record has been written to stable storage (or
+ *	just written, if syncing has been disabled with journal_set_sync).
+ *	B_FALSE if an I/O error occurred, in which case an error is logged
+ *	and the record is not part of the journal.
+ */
+bool_t
+journal_append(journal_t *journal, uint32_t type, const void *data,
+    size_t len)
+{
+	rec_hdr_t hdr = { .magic = JOURNAL_MAGIC, .type = type };
+	long start;
+	bool_t res;
+
+	ASSERT(journal != NULL);
+	ASSERT(data != NULL || len == 0);
+	ASSERT3U(len, <=, JOURNAL_MAX_REC_LEN);
+
+	mutex_enter(&journal->lock);
+
+	hdr.seq = journal->seq;
+	hdr.len = len;
+	hdr.crc = rec_crc(&hdr, data);
+
+	/*
+	 * On append streams, the MS CRT only reports the real position
+	 * after the first I/O, so explicitly seek to the end of the file
+	 * to find out where this record starts.
+	 */
+	if (fseek(journal->fp, 0, SEEK_END) == 0)
+		start = ftell(journal->fp);
+	else
+		start = -1;
+	res = (fwrite(&hdr, sizeof (hdr), 1, journal->fp) == 1 &&
+	    (len == 0 || fwrite(data, len, 1, journal->fp) == 1) &&
+	    journal_flush(journal));
+	if (res) {
+		journal->seq++;
+	} else {
+		logMsg("Error writing journal %s: %s", journal->path,
+		    strerror(errno));
+		/*
+		 * Get rid of any partially written record, otherwise it
+		 * would prevent replay of subsequently appended records.
+		 */
+		if (start >= 0)
+			(void) journal_truncate(journal, start);
+	}
+
+	mutex_exit(&journal->lock);
+
+	return (res);
+}
+
+/*
+ * Discards all records in the journal. Call this after you've completed
+ * a full save of your state, so the records aren't replayed on top of
+ * it again. Sequence numbers keep increasing across resets for as long
+ * as the journal stays open.
+ */
+bool_t
+journal_reset(journal_t *journal)
+{
+	bool_t res;
+
+	ASSERT(journal != NULL);
+
+	mutex_enter(&journal->lock);
+	res = journal_truncate(journal, 0);
+	if (!res) {
+		logMsg("Error truncating journal %s: %s", journal->path,
+		    strerror(errno));
+	}
+	mutex_exit(&journal->lock);
+
+	return (res);
+}
+
+/*
+ * Returns the sequence number which will be assigned to the next record
+ * appended to the journal.
+ */
+uint64_t
+journal_get_seq(journal_t *journal)
+{
+	uint64_t seq;
+
+	ASSERT(journal != NULL);
+	mutex_enter(&journal->lock);
+	seq = journal->seq;
+	mutex_exit(&journal->lock);
+
+	return (seq);
+}
+
+/*
+ * Controls whether journal_append waits for each record to reach stable
+ * storage before returning (the default). Disabling this makes appends
+ * much faster, at the cost of possibly losing the most recent records if
+ * the operating system crashes (an application crash loses nothing).
+ */
+void
+journal_set_sync(journal_t *journal, bool_t sync)
+{
+	ASSERT(journal != NULL);
+	mutex_enter(&journal->lock);
+	journal->sync = sync;
+	mutex_exit(&journal->lock);
+}
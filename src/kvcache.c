@@ -0,0 +1,302 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <stddef.h>
+#include <string.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/avl.h>
+#include <acfutils/kvcache.h>
+#include <acfutils/list.h>
+#include <acfutils/safe_alloc.h>
+#include <acfutils/thread.h>
+#include <acfutils/time.h>
+
+typedef struct {
+	char		*key;
+	void		*value;
+	size_t		len;
+	uint64_t	expiry;		/* nanoclock time, 0 = never */
+	avl_node_t	tree_node;
+	list_node_t	lru_node;
+} entry_t;
+
+struct kvcache_s {
+	mutex_t		lock;
+	size_t		max_entries;
+	size_t		max_size;
+	uint64_t	ttl;		/* nanoseconds, 0 = never expire */
+	avl_tree_t	tree;
+	list_t		lru;		/* most recently used at the head */
+	kvcache_stats_t	stats;
+};
+
+static int
+entry_compar(const void *a, const void *b)
+{
+	const entry_t *ea = a, *eb = b;
+	int res = strcmp(ea->key, eb->key);
+
+	if (res < 0)
+		return (-1);
+	if (res > 0)
+		return (1);
+	return (0);
+}
+
+static void
+entry_remove(kvcache_t *cache, entry_t *e)
+{
+	avl_remove(&cache->tree, e);
+	list_remove(&cache->lru, e);
+	ASSERT3U(cache->stats.total_size, >=, e->len);
+	cache->stats.total_size -= e->len;
+	free(e->key);
+	free(e->value);
+	free(e);
+}
+
+static bool_t
+entry_expired(const entry_t *e, uint64_t now)
+{
+	return (e->expiry != 0 && now >= e->expiry);
+}
+
+/*
+ * Creates a new cache. The cache maps string keys to opaque values. All
+ * values are copied in and out of the cache, so the cache can be safely
+ * shared between threads. This is meant as a common facility for modules
+ * which need to cache the results of expensive operations (such as
+ * downloaded weather reports or decoded chart images), so they don't
+ * each need to implement their own eviction logic.
+ *
+ * @param max_entries Maximum number of entries in the cache. Once the
+ *	cache is full, the least recently used entries are evicted to make
+ *	room for new ones. Pass 0 for no limit.
+ * @param max_size Maximum total size of all values in the cache in bytes.
+ *	Least recently used entries are evicted to stay below this limit.
+ *	Pass 0 for no limit.
+ * @param ttl Time-to-live of entries in seconds. Entries older than this
+ *	are treated as absent and dropped from the cache. Pass 0 for entries
+ *	which never expire.
+ *
+ * @return The cache. Use kvcache_free to dispose of it.
+ */
+kvcache_t *
+kvcache_alloc(size_t max_entries, size_t max_size, double ttl)
+{
+	kvcache_t *cache = safe_calloc(1, sizeof (*cache));
+
+	ASSERT3F(ttl, >=, 0);
+
+	mutex_init(&cache->lock);
+	cache->max_entries = max_entries;
+	cache->max_size = max_size;
+	cache->ttl = SEC2NSEC(ttl);
+	avl_create(&cache->tree, entry_compar, sizeof (entry_t),
+	    offsetof(entry_t, tree_node));
+	list_create(&cache->lru, sizeof (entry_t), offsetof(entry_t, lru_node));
+
+	return (cache);
+}
+
+void
+kvcache_free(kvcache_t *cache)
+{
+	if (cache == NULL)
+		return;
+	kvcache_purge(cache);
+	avl_destroy(&cache->tree);
+	list_destroy(&cache->lru);
+	mutex_destroy(&cache->lock);
+	free(cache);
+}
+
+/*
+ * Inserts a value into the cache, replacing any existing value under the
+ * same key. The value is copied, so the caller retains ownership of
+ * `value'. If the value on its own exceeds the cache's size limit, it is
+ * not cached at all.
+ */
+void
+kvcache_put(kvcache_t *cache, const char *key, const void *value, size_t len)
+{
+	entry_t srch = { .key = (char *)key };
+	entry_t *e;
+	avl_index_t where;
+
+	ASSERT(cache != NULL);
+	ASSERT(key != NULL);
+	ASSERT(value != NULL || len == 0);
+
+	mutex_enter(&cache->lock);
+
+	e = avl_find(&cache->tree, &srch, NULL);
+	if (e != NULL)
+		entry_remove(cache, e);
+	if (cache->max_size != 0 && len > cache->max_size) {
+		mutex_exit(&cache->lock);
+		return;
+	}
+	while ((cache->max_entries != 0 &&
+	    avl_numnodes(&cache->tree) >= cache->max_entries) ||
+	    (cache->max_size != 0 &&
+	    cache->stats.total_size + len > cache->max_size)) {
+		entry_t *lru = list_tail(&cache->lru);
+
+		ASSERT(lru != NULL);
+		entry_remove(cache, lru);
+		cache->stats.evictions++;
+	}
+
+	e = safe_calloc(1, sizeof (*e));
+	e->key = safe_strdup(key);
+	e->value = safe_malloc(MAX(len, 1));
+	if (len != 0)
+		memcpy(e->value, value, len);
+	e->len = len;
+	if (cache->ttl != 0)
+		e->expiry = nanoclock() + cache->ttl;
+	VERIFY3P(avl_find(&cache->tree, e, &where), ==, NULL);
+	avl_insert(&cache->tree, e, where);
+	list_insert_head(&cache->lru, e);
+	cache->stats.total_size += len;
+
+	mutex_exit(&cache->lock);
+}
+
+/*
+ * Looks up a value in the cache.
+ *
+ * @param len Optional return argument which will be filled with the
+ *	length of the returned value.
+ *
+ * @return A copy of the cached value, which you must free using
+ *	lacf_free. Returns NULL if the key isn't in the cache, or its
+ *	entry has expired.
+ */
+void *
+kvcache_get(kvcache_t *cache, const char *key, size_t *len)
+{
+	entry_t srch = { .key = (char *)key };
+	entry_t *e;
+	void *value = NULL;
+
+	ASSERT(cache != NULL);
+	ASSERT(key != NULL);
+
+	mutex_enter(&cache->lock);
+	e = avl_find(&cache->tree, &srch, NULL);
+	if (e != NULL && entry_expired(e, nanoclock())) {
+		entry_remove(cache, e);
+		cache->stats.expirations++;
+		e = NULL;
+	}
+	if (e != NULL) {
+		value = safe_malloc(MAX(e->len, 1));
+		memcpy(value, e->value, e->len);
+		if (len != NULL)
+			*len = e->len;
+		/* move to the MRU position */
+		list_remove(&cache->lru, e);
+		list_insert_head(&cache->lru, e);
+		cache->stats.hits++;
+	} else {
+		cache->stats.misses++;
+	}
+	mutex_exit(&cache->lock);
+
+	return (value);
+}
+
+/*
+ * Removes a single entry from the cache. Returns B_TRUE if the entry
+ * was present.
+ */
+bool_t
+kvcache_remove(kvcache_t *cache, const char *key)
+{
+	entry_t srch = { .key = (char *)key };
+	entry_t *e;
+
+	ASSERT(cache != NULL);
+	ASSERT(key != NULL);
+
+	mutex_enter(&cache->lock);
+	e = avl_find(&cache->tree, &srch, NULL);
+	if (e != NULL)
+		entry_remove(cache, e);
+	mutex_exit(&cache->lock);
+
+	return (e != NULL);
+}
+
+/*
+ * Drops all expired entries from the cache. Expired entries are never
+ * returned by kvcache_get, so this only serves to release their memory
+ * early. Call this periodically if you use a TTL and lookups are rare.
+ */
+void
+kvcache_expire(kvcache_t *cache)
+{
+	uint64_t now = nanoclock();
+	entry_t *e, *next;
+
+	ASSERT(cache != NULL);
+
+	mutex_enter(&cache->lock);
+	for (e = list_head(&cache->lru); e != NULL; e = next) {
+		next = list_next(&cache->lru, e);
+		if (entry_expired(e, now)) {
+			entry_remove(cache, e);
+			cache->stats.expirations++;
+		}
+	}
+	mutex_exit(&cache->lock);
+}
+
+/*
+ * Drops all entries from the cache. Use this when all cached data
+ * becomes stale at once, e.g. when the simulator's date changes and
+ * cached weather reports no longer apply. The statistics counters are
+ * left intact.
+ */
+void
+kvcache_purge(kvcache_t *cache)
+{
+	entry_t *e;
+
+	ASSERT(cache != NULL);
+
+	mutex_enter(&cache->lock);
+	while ((e = list_head(&cache->lru)) != NULL)
+		entry_remove(cache, e);
+	mutex_exit(&cache->lock);
+}
+
+void
+kvcache_get_stats(kvcache_t *cache, kvcache_stats_t *stats)
+{
+	ASSERT(cache != NULL);
+	ASSERT(stats != NULL);
+
+	mutex_enter(&cache->lock);
+	*stats = cache->stats;
+	stats->num_entries = avl_numnodes(&cache->tree);
+	mutex_exit(&cache->lock);
+}
@@ -20,18 +20,22 @@
 #include <string.h>
 #include <stdio.h>
 #include <time.h>
+#include <math.h>
 
 #if	IBM
 #include <windows.h>
 #include <dbghelp.h>
+#include <io.h>
 #include <psapi.h>
 #else
 #include <execinfo.h>   /* used for stack tracing */
+#include <unistd.h>
 #endif	/* !IBM */
 
 #include <acfutils/assert.h>
 #include <acfutils/helpers.h>
 #include <acfutils/log.h>
+#include <acfutils/safe_alloc.h>
 #include <acfutils/thread.h>
 
 #define	DATE_FMT	"%Y-%m-%d %H:%M:%S"
@@ -71,6 +75,53 @@ static DWORD num_modules;
 static logfunc_t log_func = NULL;
 static const char *log_prefix = NULL;
 
+#define	MAX_MODULE_LVLS		64
+#define	MODULE_NAME_LEN		64
+typedef struct {
+	char		module[MODULE_NAME_LEN];
+	log_lvl_t	lvl;
+} module_lvl_t;
+
+static log_fmt_t log_fmt = LOG_FMT_TEXT;
+static log_lvl_t log_lvl = LOG_LVL_INFO;
+static mutex_t module_lvls_lock;
+static module_lvl_t module_lvls[MAX_MODULE_LVLS];
+static unsigned num_module_lvls = 0;
+
+/*
+ * The most recently logged messages are retained in memory, so they can
+ * be attached to crash reports (see except_set_report_dir) or shown in
+ * an in-sim console (see log_get_recent).
+ */
+#define	DFL_RECENT_CAP		128
+static mutex_t recent_lock;
+static log_msg_t *recent_msgs = NULL;
+static unsigned recent_cap = 0;
+static unsigned recent_head = 0;	/* index of the oldest message */
+static unsigned num_recent_msgs = 0;
+/*
+ * Set while a thread is modifying the recent messages buffer. Since
+ * recent_lock is recursive, a crash handler running on the thread which
+ * holds the lock could otherwise enter it and see a half-updated buffer.
+ */
+static volatile bool_t recent_busy = B_FALSE;
+/*
+ * Backtraces logged from crash handlers can't be copied into the buffer
+ * using malloc, so the most recent one is copied into this static slot
+ * instead. At most one message in the buffer references the slot, an
+ * older reference is replaced by recent_bt_gone.
+ */
+#define	RECENT_BT_LEN		(64 * 1024)
+static char recent_bt[RECENT_BT_LEN];
+static char recent_bt_gone[] = "(backtrace superseded by a later one)\n";
+
+static void
+recent_msg_free(char *msg)
+{
+	if (msg != recent_bt && msg != recent_bt_gone)
+		free(msg);
+}
+
 void
 log_init(logfunc_t func, const char *prefix)
 {
@@ -79,6 +130,11 @@ log_init(logfunc_t func, const char *prefix)
 		abort();
 	log_func = func;
 	log_prefix = prefix;
+	mutex_init(&module_lvls_lock);
+	num_module_lvls = 0;
+	mutex_init(&recent_lock);
+	recent_cap = DFL_RECENT_CAP;
+	recent_msgs = safe_calloc(recent_cap, sizeof (*recent_msgs));
 #if	IBM
 	mutex_init(&backtrace_lock);
 #endif
@@ -87,11 +143,221 @@ log_init(logfunc_t func, const char *prefix)
 void
 log_fini(void)
 {
+	mutex_destroy(&module_lvls_lock);
+	for (unsigned i = 0; i < num_recent_msgs; i++) {
+		recent_msg_free(
+		    recent_msgs[(recent_head + i) % recent_cap].msg);
+	}
+	free(recent_msgs);
+	recent_msgs = NULL;
+	recent_cap = 0;
+	recent_head = 0;
+	num_recent_msgs = 0;
+	mutex_destroy(&recent_lock);
 #if	IBM
 	mutex_destroy(&backtrace_lock);
 #endif
 }
 
+static void
+recent_enter(void)
+{
+	mutex_enter(&recent_lock);
+	recent_busy = B_TRUE;
+}
+
+static void
+recent_exit(void)
+{
+	recent_busy = B_FALSE;
+	mutex_exit(&recent_lock);
+}
+
+/*
+ * Non-blocking version of recent_enter for use in crash handlers. Fails
+ * if the buffer is in use, either by another thread, or by the crashed
+ * thread itself.
+ */
+static bool_t
+recent_tryenter(void)
+{
+	if (!mutex_tryenter(&recent_lock))
+		return (B_FALSE);
+	if (recent_busy) {
+		mutex_exit(&recent_lock);
+		return (B_FALSE);
+	}
+	recent_busy = B_TRUE;
+	return (B_TRUE);
+}
+
+/*
+ * Stores a message in the recent messages buffer, dropping the oldest
+ * message if the buffer is full. Takes ownership of `msg'. The caller
+ * must have entered the buffer.
+ */
+static void
+recent_insert(time_t t, char *msg)
+{
+	log_msg_t *slot;
+
+	if (recent_cap == 0) {
+		recent_msg_free(msg);
+		return;
+	}
+	if (num_recent_msgs == recent_cap) {
+		slot = &recent_msgs[recent_head];
+		recent_msg_free(slot->msg);
+		recent_head = (recent_head + 1) % recent_cap;
+	} else {
+		slot = &recent_msgs[(recent_head + num_recent_msgs) %
+		    recent_cap];
+		num_recent_msgs++;
+	}
+	slot->time = t;
+	slot->msg = msg;
+}
+
+static void
+recent_add(time_t t, char *msg)
+{
+	recent_enter();
+	recent_insert(t, msg);
+	recent_exit();
+}
+
+/*
+ * Like recent_add, but for backtraces logged by log_backtrace, which is
+ * called from crash handlers. It never blocks, since the buffer might be
+ * held by the crashed thread or by a thread which will never run again
+ * (if the buffer is busy, the backtrace is dropped). It also doesn't
+ * allocate memory, the backtrace is copied into the recent_bt slot.
+ */
+static void
+recent_tryadd_bt(time_t t, const char *msg)
+{
+	if (!recent_tryenter())
+		return;
+	for (unsigned i = 0; i < num_recent_msgs; i++) {
+		log_msg_t *m = &recent_msgs[(recent_head + i) % recent_cap];
+
+		if (m->msg == recent_bt)
+			m->msg = recent_bt_gone;
+	}
+	lacf_strlcpy(recent_bt, msg, sizeof (recent_bt));
+	recent_insert(t, recent_bt);
+	recent_exit();
+}
+
+/*
+ * Sets how many of the most recently logged messages are retained in
+ * memory (128 by default). Shrinking the buffer drops the oldest
+ * messages. Setting the capacity to 0 disables message retention. The
+ * logging subsystem must be initialized (see log_init) before calling this.
+ */
+void
+log_set_recent_cap(unsigned cap)
+{
+	log_msg_t *msgs = (cap != 0 ? safe_calloc(cap, sizeof (*msgs)) : NULL);
+	unsigned skip;
+
+	recent_enter();
+	skip = (num_recent_msgs > cap ? num_recent_msgs - cap : 0);
+	for (unsigned i = 0; i < num_recent_msgs; i++) {
+		log_msg_t *msg = &recent_msgs[(recent_head + i) % recent_cap];
+
+		if (i < skip)
+			recent_msg_free(msg->msg);
+		else
+			msgs[i - skip] = *msg;
+	}
+	free(recent_msgs);
+	recent_msgs = msgs;
+	recent_cap = cap;
+	recent_head = 0;
+	num_recent_msgs -= skip;
+	recent_exit();
+}
+
+/*
+ * Returns copies of up to `n' of the most recently logged messages,
+ * oldest first. The number of returned messages is placed in `num_msgs'.
+ * The messages are formatted exactly as they were passed to the logging
+ * function, including the trailing newline. Backtraces logged using
+ * log_backtrace are returned as a single multi-line message. The
+ * returned array must be freed using log_free_recent.
+ */
+log_msg_t *
+log_get_recent(unsigned n, unsigned *num_msgs)
+{
+	log_msg_t *msgs;
+	unsigned skip;
+
+	ASSERT(num_msgs != NULL);
+
+	recent_enter();
+	n = MIN(n, num_recent_msgs);
+	skip = num_recent_msgs - n;
+	msgs = safe_calloc(MAX(n, 1), sizeof (*msgs));
+	for (unsigned i = 0; i < n; i++) {
+		const log_msg_t *msg =
+		    &recent_msgs[(recent_head + skip + i) % recent_cap];
+
+		msgs[i].time = msg->time;
+		msgs[i].msg = safe_strdup(msg->msg);
+	}
+	recent_exit();
+	*num_msgs = n;
+
+	return (msgs);
+}
+
+/*
+ * Frees an array of messages returned from log_get_recent.
+ */
+void
+log_free_recent(log_msg_t *msgs, unsigned num_msgs)
+{
+	for (unsigned i = 0; i < num_msgs; i++)
+		free(msgs[i].msg);
+	free(msgs);
+}
+
+/*
+ * Writes the most recently logged messages (oldest first) to the file
+ * descriptor `fd'. This includes any backtraces logged using
+ * log_backtrace. This function is meant to be used from crash handlers:
+ * it doesn't allocate any memory and only uses write(2). It also never
+ * blocks, so if the messages are currently being modified (e.g. the
+ * crash occurred while logging a message), nothing is written.
+ */
+void
+log_write_recent(int fd)
+{
+	ASSERT3S(fd, >=, 0);
+
+	if (!recent_tryenter())
+		return;
+	for (unsigned i = 0; i < num_recent_msgs; i++) {
+		const char *msg =
+		    recent_msgs[(recent_head + i) % recent_cap].msg;
+		size_t len = strlen(msg);
+
+		while (len > 0) {
+#if	IBM
+			int n = _write(fd, msg, len);
+#else
+			ssize_t n = write(fd, msg, len);
+#endif
+			if (n <= 0)
+				break;
+			msg += n;
+			len -= n;
+		}
+	}
+	recent_exit();
+}
+
 void
 log_impl(const char *filename, int line, const char *fmt, ...)
 {
@@ -101,13 +367,55 @@ log_impl(const char *filename, int line, const char *fmt, ...)
 	va_end(ap);
 }
 
-void
-log_impl_v(const char *filename, int line, const char *fmt, va_list ap)
+/*
+ * Appends `str' to `buf' as a quoted JSON string.
+ */
+static void
+append_json_str(char **buf, size_t *len, const char *str)
+{
+	append_format(buf, len, "\"");
+	for (; *str != '\0'; str++) {
+		switch (*str) {
+		case '"':
+			append_format(buf, len, "\\\"");
+			break;
+		case '\\':
+			append_format(buf, len, "\\\\");
+			break;
+		case '\n':
+			append_format(buf, len, "\\n");
+			break;
+		case '\r':
+			append_format(buf, len, "\\r");
+			break;
+		case '\t':
+			append_format(buf, len, "\\t");
+			break;
+		default:
+			if ((unsigned char)*str < 0x20) {
+				append_format(buf, len, "\\u%04x",
+				    (unsigned char)*str);
+			} else {
+				append_format(buf, len, "%c", *str);
+			}
+			break;
+		}
+	}
+	append_format(buf, len, "\"");
+}
+
+/*
+ * Formats a complete log line (according to the log format set using
+ * log_set_format) and passes it to the logging function. `fields' are
+ * the already formatted key-value fields of a structured log message,
+ * or NULL. Takes ownership of `msg' and `fields'.
+ */
+static void
+log_emit(const char *filename, int line, char *msg, char *fields)
 {
-	va_list ap_copy;
 	char timedate[32];
-	char *buf;
-	size_t prefix_len, len;
+	char *buf = NULL;
+	size_t len = 0;
 	struct tm *tm;
 	time_t t;
 
@@ -119,18 +427,247 @@ log_impl_v(const char *filename, int line, const char *fmt, va_list ap)
 	if (log_func == NULL || log_prefix == NULL)
 		abort();
 
-	prefix_len = snprintf(NULL, 0, PREFIX_FMT);
-	va_copy(ap_copy, ap);
-	len = vsnprintf(NULL, 0, fmt, ap_copy);
+	if (log_fmt == LOG_FMT_JSON) {
+		append_format(&buf, &len, "{\"time\":\"%s\",\"prefix\":",
+		    timedate);
+		append_json_str(&buf, &len, log_prefix);
+		append_format(&buf, &len, ",\"file\":");
+		append_json_str(&buf, &len, filename);
+		append_format(&buf, &len, ",\"line\":%d,\"msg\":", line);
+		append_json_str(&buf, &len, msg);
+		append_format(&buf, &len, "%s}\n",
+		    fields != NULL ? fields : "");
+	} else {
+		append_format(&buf, &len, PREFIX_FMT);
+		append_format(&buf, &len, "%s%s\n", msg,
+		    fields != NULL ? fields : "");
+	}
+	log_func(buf);
 
-	buf = (char *)malloc(prefix_len + len + 2);
+	recent_add(t, buf);
+	free(msg);
+	free(fields);
+}
 
-	(void) snprintf(buf, prefix_len + 1, PREFIX_FMT);
-	(void) vsnprintf(&buf[prefix_len], len + 1, fmt, ap);
-	(void) sprintf(&buf[strlen(buf)], "\n");
-	log_func(buf);
+void
+log_impl_v(const char *filename, int line, const char *fmt, va_list ap)
+{
+	log_emit(filename, line, vsprintf_alloc(fmt, ap), NULL);
+}
+
+/*
+ * Selects the format of log lines. LOG_FMT_TEXT (the default) is the
+ * classic human-readable format. LOG_FMT_JSON emits every log message as
+ * a single-line JSON object with "time", "prefix", "file", "line" and
+ * "msg" members (plus the fields of structured messages logged using
+ * logMsgKV), which is easy to parse with post-flight analysis tooling.
+ * Backtraces (see log_backtrace) are always emitted as plain text.
+ */
+void
+log_set_format(log_fmt_t fmt)
+{
+	ASSERT(fmt == LOG_FMT_TEXT || fmt == LOG_FMT_JSON);
+	log_fmt = fmt;
+}
+
+log_fmt_t
+log_get_format(void)
+{
+	return (log_fmt);
+}
 
-	free(buf);
+void
+log_kv_impl(const char *filename, int line, const char *msg, ...)
+{
+	va_list ap;
+	va_start(ap, msg);
+	log_kv_impl_v(filename, line, msg, ap);
+	va_end(ap);
+}
+
+/*
+ * Formats the key-value fields of a structured log message. In text
+ * format, fields are appended as ` key=value', with string values
+ * quoted and escaped the same way as in JSON, so that a log line never
+ * spans multiple lines. In JSON format, each field becomes a member of
+ * the message's JSON object. Non-finite numbers are emitted as JSON null.
+ */
+void
+log_kv_impl_v(const char *filename, int line, const char *msg, va_list ap)
+{
+	char *fields = NULL;
+	size_t len = 0;
+	bool_t json = (log_fmt == LOG_FMT_JSON);
+	log_kv_type_t type;
+
+	ASSERT(msg != NULL);
+
+	while ((type = va_arg(ap, int)) != LOG_KV_TYPE_END) {
+		const char *key = va_arg(ap, const char *);
+
+		if (json) {
+			append_format(&fields, &len, ",");
+			append_json_str(&fields, &len, key);
+			append_format(&fields, &len, ":");
+		} else {
+			append_format(&fields, &len, " %s=", key);
+		}
+		switch (type) {
+		case LOG_KV_TYPE_INT:
+			append_format(&fields, &len, "%lld",
+			    va_arg(ap, long long));
+			break;
+		case LOG_KV_TYPE_DBL: {
+			double val = va_arg(ap, double);
+			if (json && !isfinite(val))
+				append_format(&fields, &len, "null");
+			else
+				append_format(&fields, &len, "%.12g", val);
+			break;
+		}
+		case LOG_KV_TYPE_BOOL:
+			append_format(&fields, &len, "%s",
+			    va_arg(ap, int) ? "true" : "false");
+			break;
+		case LOG_KV_TYPE_STR: {
+			const char *val = va_arg(ap, const char *);
+			if (val == NULL)
+				append_format(&fields, &len, "null");
+			else
+				append_json_str(&fields, &len, val);
+			break;
+		}
+		default:
+			VERIFY_FAIL();
+		}
+	}
+
+	log_emit(filename, line, safe_strdup(msg), fields);
+}
+
+/*
+ * Sets the global log level. Messages logged using logDbg, logInfo,
+ * logWarn and logErr below this level are discarded, unless overridden
+ * for their module using log_set_module_level. The default level is
+ * LOG_LVL_INFO, so debug messages can be left compiled in and enabled
+ * only when needed. Messages logged using plain logMsg are never
+ * discarded.
+ */
+void
+log_set_level(log_lvl_t lvl)
+{
+	log_lvl = lvl;
+}
+
+log_lvl_t
+log_get_level(void)
+{
+	return (log_lvl);
+}
+
+static module_lvl_t *
+find_module_lvl(const char *module)
+{
+	for (unsigned i = 0; i < num_module_lvls; i++) {
+		if (strcmp(module_lvls[i].module, module) == 0)
+			return (&module_lvls[i]);
+	}
+	return (NULL);
+}
+
+/*
+ * Sets the log level for a single module, overriding the global log
+ * level. A module is a source file, identified by its base name (e.g.
+ * "navdb.c"). The logging subsystem must be initialized (see log_init)
+ * before calling this.
+ */
+void
+log_set_module_level(const char *module, log_lvl_t lvl)
+{
+	module_lvl_t *ml;
+
+	ASSERT(module != NULL);
+	ASSERT3U(strlen(module), <, MODULE_NAME_LEN);
+
+	mutex_enter(&module_lvls_lock);
+	ml = find_module_lvl(module);
+	if (ml == NULL) {
+		VERIFY3U(num_module_lvls, <, MAX_MODULE_LVLS);
+		ml = &module_lvls[num_module_lvls++];
+		lacf_strlcpy(ml->module, module, sizeof (ml->module));
+	}
+	ml->lvl = lvl;
+	mutex_exit(&module_lvls_lock);
+}
+
+/*
+ * Removes the module-specific log level set by log_set_module_level,
+ * so the module follows the global log level again.
+ */
+void
+log_clear_module_level(const char *module)
+{
+	module_lvl_t *ml;
+
+	ASSERT(module != NULL);
+
+	mutex_enter(&module_lvls_lock);
+	ml = find_module_lvl(module);
+	if (ml != NULL) {
+		*ml = module_lvls[num_module_lvls - 1];
+		num_module_lvls--;
+	}
+	mutex_exit(&module_lvls_lock);
+}
+
+/*
+ * Returns B_TRUE if a message of level `lvl' logged from `filename'
+ * (a source file base name) would be logged. Use this to avoid
+ * expensive preparation of debug messages which would be discarded.
+ */
+bool_t
+log_lvl_enabled(log_lvl_t lvl, const char *filename)
+{
+	log_lvl_t min_lvl = log_lvl;
+	const module_lvl_t *ml;
+
+	mutex_enter(&module_lvls_lock);
+	ml = find_module_lvl(filename);
+	if (ml != NULL)
+		min_lvl = ml->lvl;
+	mutex_exit(&module_lvls_lock);
+
+	return (lvl >= min_lvl);
+}
+
+void
+log_lvl_impl(log_lvl_t lvl, const char *filename, int line,
+    const char *fmt, ...)
+{
+	va_list ap;
+	va_start(ap, fmt);
+	log_lvl_impl_v(lvl, filename, line, fmt, ap);
+	va_end(ap);
+}
+
+void
+log_lvl_impl_v(log_lvl_t lvl, const char *filename, int line,
+    const char *fmt, va_list ap)
+{
+	static const char *lvl_tags[] = {
+	    [LOG_LVL_DEBUG] = "DEBUG: ",
+	    [LOG_LVL_INFO] = "",
+	    [LOG_LVL_WARN] = "WARNING: ",
+	    [LOG_LVL_ERR] = "ERROR: "
+	};
+	char *tagged_fmt;
+
+	ASSERT3U(lvl, <, ARRAY_NUM_ELEM(lvl_tags));
+	if (!log_lvl_enabled(lvl, filename))
+		return;
+	tagged_fmt = sprintf_alloc("%s%s", lvl_tags[lvl], fmt);
+	log_impl_v(filename, line, tagged_fmt, ap);
+	free(tagged_fmt);
 }
 
 #if	IBM
@@ -321,6 +858,7 @@ log_backtrace(int skip_frames)
 	if (log_func == NULL)
 		abort();
 	log_func(backtrace_buf);
+	recent_tryadd_bt(time(NULL), backtrace_buf);
 	fputs(backtrace_buf, stderr);
 	fflush(stderr);
 	SymCleanup(process);
@@ -408,6 +946,7 @@ log_backtrace_sw64(PCONTEXT ctx)
 	if (log_func == NULL)
 		abort();
 	log_func(backtrace_buf);
+	recent_tryadd_bt(time(NULL), backtrace_buf);
 	fputs(backtrace_buf, stderr);
 	fflush(stderr);
 	SymCleanup(process);
@@ -442,6 +981,7 @@ log_backtrace(int skip_frames)
 	log_func(msg);
 	fputs(msg, stderr);
 
+	recent_tryadd_bt(time(NULL), msg);
 	free(msg);
 	free(fnames);
 }
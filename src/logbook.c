@@ -0,0 +1,454 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <errno.h>
+#include <math.h>
+#include <stdio.h>
+#include <string.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/conf.h>
+#include <acfutils/helpers.h>
+#include <acfutils/log.h>
+#include <acfutils/logbook.h>
+#include <acfutils/perf.h>
+#include <acfutils/safe_alloc.h>
+#include <acfutils/time.h>
+
+/*
+ * Ground speed above which the aircraft is considered to be moving
+ * under its own power (block off) and below which it is considered
+ * to be stopped (block on).
+ */
+#define	BLOCK_OFF_GS	KT2MPS(3)
+#define	BLOCK_ON_GS	KT2MPS(1)
+
+typedef enum {
+	LB_PARKED,	/* at the gate, waiting for block off */
+	LB_TAXI_OUT,	/* block off, waiting for takeoff */
+	LB_AIRBORNE,
+	LB_TAXI_IN	/* landed, waiting for block on */
+} lb_state_t;
+
+struct logbook_s {
+	char			*filename;
+	conf_t			*conf;
+	size_t			num_entries;
+
+	char			dep[8];
+	char			arr[8];
+	lb_state_t		state;
+	logbook_entry_t		cur;
+	geo_pos2_t		last_pos;
+	double			last_vs;
+	/*
+	 * Start time of the current airborne segment and the air time
+	 * accumulated in the flight's previous segments (touch-and-go's).
+	 */
+	double			seg_takeoff_t;
+	double			seg_air_time;
+
+	logbook_entry_cb_t	cb;
+	void			*cb_userinfo;
+};
+
+/*
+ * Opens a logbook. The logbook automatically detects when a flight is
+ * started and finished, based on the aircraft state passed to it every
+ * frame in logbook_update. Each completed flight is appended as a new
+ * entry to the logbook store and the store is immediately written back
+ * to disk, so no entries are lost if the simulator crashes.
+ *
+ * @param filename Path to the logbook store file. This is a regular conf
+ *	file (see conf.h), with one set of "entry/<N>/<field>" keys per
+ *	logbook entry. If the file doesn't exist, a new empty logbook is
+ *	created when the first entry is written.
+ *
+ * @return The logbook, or NULL if the file exists but couldn't be parsed
+ *	(the error is logged). Use logbook_close to dispose of it.
+ */
+logbook_t *
+logbook_open(const char *filename)
+{
+	logbook_t *lb;
+	conf_t *conf;
+	int errline, num_entries = 0;
+
+	ASSERT(filename != NULL);
+
+	if (file_exists(filename, NULL)) {
+		conf = conf_read_file(filename, &errline);
+		if (conf == NULL) {
+			if (errline < 0) {
+				logMsg("Error reading logbook %s: %s",
+				    filename, strerror(errno));
+			} else {
+				logMsg("Error parsing logbook %s: syntax "
+				    "error on line %d", filename, errline);
+			}
+			return (NULL);
+		}
+		(void) conf_get_i(conf, "num_entries", &num_entries);
+	} else {
+		conf = conf_create_empty();
+	}
+
+	lb = safe_calloc(1, sizeof (*lb));
+	lb->filename = safe_strdup(filename);
+	lb->conf = conf;
+	lb->num_entries = MAX(num_entries, 0);
+	lb->state = LB_PARKED;
+	lb->last_pos = NULL_GEO_POS2;
+
+	return (lb);
+}
+
+/*
+ * Closes a logbook. A flight which is currently in progress is discarded
+ * and NOT written to the logbook.
+ */
+void
+logbook_close(logbook_t *lb)
+{
+	if (lb == NULL)
+		return;
+	conf_free(lb->conf);
+	free(lb->filename);
+	free(lb);
+}
+
+/*
+ * Sets the departure and arrival airports which will be recorded in the
+ * logbook entry of the current flight. This can be called at any time
+ * before the flight is completed (e.g. once the flight plan is entered).
+ * Either argument may be NULL to leave it empty.
+ */
+void
+logbook_set_route(logbook_t *lb, const char *dep, const char *arr)
+{
+	ASSERT(lb != NULL);
+	lacf_strlcpy(lb->dep, dep != NULL ? dep : "", sizeof (lb->dep));
+	lacf_strlcpy(lb->arr, arr != NULL ? arr : "", sizeof (lb->arr));
+}
+
+/*
+ * Installs a callback which is called every time a flight is completed
+ * and a new logbook entry is written. Pass NULL for `cb' to remove a
+ * previously installed callback.
+ */
+void
+logbook_set_cb(logbook_t *lb, logbook_entry_cb_t cb, void *userinfo)
+{
+	ASSERT(lb != NULL);
+	lb->cb = cb;
+	lb->cb_userinfo = userinfo;
+}
+
+static void
+store_entry(logbook_t *lb, const logbook_entry_t *e)
+{
+	size_t i = lb->num_entries;
+	char *filename_new, *buf;
+	size_t bufsz;
+
+	conf_set_str_v(lb->conf, "entry/%d/dep", e->dep, (int)i);
+	conf_set_str_v(lb->conf, "entry/%d/arr", e->arr, (int)i);
+	conf_set_da_v(lb->conf, "entry/%d/block_off_t", e->block_off_t,
+	    (int)i);
+	conf_set_da_v(lb->conf, "entry/%d/takeoff_t", e->takeoff_t, (int)i);
+	conf_set_da_v(lb->conf, "entry/%d/landing_t", e->landing_t, (int)i);
+	conf_set_da_v(lb->conf, "entry/%d/block_on_t", e->block_on_t, (int)i);
+	conf_set_da_v(lb->conf, "entry/%d/block_time", e->block_time, (int)i);
+	conf_set_da_v(lb->conf, "entry/%d/air_time", e->air_time, (int)i);
+	conf_set_i_v(lb->conf, "entry/%d/num_landings", e->num_landings,
+	    (int)i);
+	conf_set_da_v(lb->conf, "entry/%d/touchdown_vs", e->touchdown_vs,
+	    (int)i);
+	conf_set_da_v(lb->conf, "entry/%d/dist", e->dist, (int)i);
+	lb->num_entries++;
+	conf_set_i(lb->conf, "num_entries", lb->num_entries);
+
+	/*
+	 * conf_t can only be serialized into a file, so we serialize into
+	 * a scratch file and read it back. file_write_atomic then replaces
+	 * the logbook, so a crash in the middle of the write can't cost us
+	 * the existing entries.
+	 */
+	filename_new = sprintf_alloc("%s.new", lb->filename);
+	if (!conf_write_file(lb->conf, filename_new) ||
+	    (buf = file2buf(filename_new, &bufsz)) == NULL) {
+		logMsg("Error writing logbook %s: %s", filename_new,
+		    strerror(errno));
+	} else {
+		/* file_write_atomic logs its own errors */
+		(void) file_write_atomic(lb->filename, buf, bufsz);
+		free(buf);
+	}
+	(void) remove_file(filename_new, B_TRUE);
+	free(filename_new);
+}
+
+static void
+complete_flight(logbook_t *lb, double utime)
+{
+	lb->cur.block_on_t = utime;
+	lb->cur.block_time = utime - lb->cur.block_off_t;
+	lacf_strlcpy(lb->cur.dep, lb->dep, sizeof (lb->cur.dep));
+	lacf_strlcpy(lb->cur.arr, lb->arr, sizeof (lb->cur.arr));
+	store_entry(lb, &lb->cur);
+	if (lb->cb != NULL)
+		lb->cb(&lb->cur, lb->cb_userinfo);
+	memset(&lb->cur, 0, sizeof (lb->cur));
+	lb->seg_air_time = 0;
+	lb->state = LB_PARKED;
+}
+
+/*
+ * Feeds the current aircraft state to the logbook. This should be called
+ * once per simulator frame. The logbook tracks the flight through the
+ * following sequence:
+ *
+ * 1) Block off: the aircraft starts moving on the ground with at least
+ *	one engine running.
+ * 2) Takeoff: the aircraft leaves the ground. Block off is implied if
+ *	it hasn't been detected yet (e.g. when starting a flight in the
+ *	air or on the runway with engines already running).
+ * 3) Landing: the aircraft touches down. The vertical speed in the last
+ *	frame before touchdown is recorded as the touchdown rate. Touch-
+ *	and-go's simply go back to step 2 and each counts as a landing.
+ * 4) Block on: the aircraft comes to a stop on the ground with all
+ *	engines shut down. This completes the flight and a new logbook
+ *	entry is written.
+ *
+ * If the aircraft returns to a stop with engines shut down without ever
+ * having taken off (e.g. a taxi test), no logbook entry is written.
+ *
+ * @param st Current aircraft state.
+ * @param utime Current UNIX time in seconds.
+ */
+void
+logbook_update(logbook_t *lb, const logbook_state_t *st, double utime)
+{
+	bool_t stopped;
+
+	ASSERT(lb != NULL);
+	ASSERT(st != NULL);
+	ASSERT(!isnan(utime));
+
+	stopped = (st->gs < BLOCK_ON_GS && !st->eng_running);
+
+	switch (lb->state) {
+	case LB_PARKED:
+		if (!st->on_ground) {
+			/* starting in the air, imply block off & takeoff */
+			lb->cur.block_off_t = utime;
+			lb->cur.takeoff_t = utime;
+			lb->seg_takeoff_t = utime;
+			lb->state = LB_AIRBORNE;
+		} else if (st->eng_running && st->gs > BLOCK_OFF_GS) {
+			lb->cur.block_off_t = utime;
+			lb->state = LB_TAXI_OUT;
+		}
+		break;
+	case LB_TAXI_OUT:
+		if (!st->on_ground) {
+			lb->cur.takeoff_t = utime;
+			lb->seg_takeoff_t = utime;
+			lb->state = LB_AIRBORNE;
+		} else if (stopped) {
+			/* never took off, discard */
+			memset(&lb->cur, 0, sizeof (lb->cur));
+			lb->state = LB_PARKED;
+		}
+		break;
+	case LB_AIRBORNE:
+		lb->cur.air_time = lb->seg_air_time +
+		    (utime - lb->seg_takeoff_t);
+		if (!IS_NULL_GEO_POS2(lb->last_pos) &&
+		    !IS_NULL_GEO_POS2(st->pos))
+			lb->cur.dist += gc_distance(lb->last_pos, st->pos);
+		if (st->on_ground) {
+			lb->cur.landing_t = utime;
+			lb->cur.num_landings++;
+			lb->cur.touchdown_vs = -lb->last_vs;
+			lb->state = LB_TAXI_IN;
+		}
+		break;
+	case LB_TAXI_IN:
+		if (!st->on_ground) {
+			/* touch-and-go, start a new airborne segment */
+			lb->seg_takeoff_t = utime;
+			lb->seg_air_time = lb->cur.air_time;
+			lb->state = LB_AIRBORNE;
+		} else if (stopped) {
+			complete_flight(lb, utime);
+		}
+		break;
+	}
+	if (lb->state == LB_AIRBORNE) {
+		lb->last_pos = st->pos;
+	} else {
+		lb->last_pos = NULL_GEO_POS2;
+	}
+	lb->last_vs = st->vs;
+}
+
+/*
+ * Returns B_TRUE if a flight is currently in progress (i.e. block off
+ * has been detected, but block on hasn't happened yet).
+ */
+bool_t
+logbook_is_in_flight(const logbook_t *lb)
+{
+	ASSERT(lb != NULL);
+	return (lb->state != LB_PARKED);
+}
+
+/*
+ * Retrieves the partial logbook entry of the flight currently in
+ * progress. Returns B_FALSE if no flight is in progress.
+ */
+bool_t
+logbook_get_cur_entry(const logbook_t *lb, logbook_entry_t *entry)
+{
+	ASSERT(lb != NULL);
+	ASSERT(entry != NULL);
+	if (lb->state == LB_PARKED)
+		return (B_FALSE);
+	*entry = lb->cur;
+	lacf_strlcpy(entry->dep, lb->dep, sizeof (entry->dep));
+	lacf_strlcpy(entry->arr, lb->arr, sizeof (entry->arr));
+	return (B_TRUE);
+}
+
+/*
+ * Returns the number of completed entries in the logbook.
+ */
+size_t
+logbook_get_num_entries(const logbook_t *lb)
+{
+	ASSERT(lb != NULL);
+	return (lb->num_entries);
+}
+
+/*
+ * Retrieves a completed logbook entry. Entries are numbered in order of
+ * completion, starting at 0.
+ *
+ * @return B_TRUE if the entry was retrieved, B_FALSE if `idx' is out of
+ *	range or the entry in the store is damaged.
+ */
+bool_t
+logbook_get_entry(const logbook_t *lb, size_t idx, logbook_entry_t *e)
+{
+	const char *dep, *arr;
+	int num_landings;
+	int i = idx;
+
+	ASSERT(lb != NULL);
+	ASSERT(e != NULL);
+
+	if (idx >= lb->num_entries)
+		return (B_FALSE);
+	memset(e, 0, sizeof (*e));
+	if (!conf_get_da_v(lb->conf, "entry/%d/block_off_t", &e->block_off_t,
+	    i) ||
+	    !conf_get_da_v(lb->conf, "entry/%d/block_on_t", &e->block_on_t,
+	    i))
+		return (B_FALSE);
+	if (conf_get_str_v(lb->conf, "entry/%d/dep", &dep, i))
+		lacf_strlcpy(e->dep, dep, sizeof (e->dep));
+	if (conf_get_str_v(lb->conf, "entry/%d/arr", &arr, i))
+		lacf_strlcpy(e->arr, arr, sizeof (e->arr));
+	(void) conf_get_da_v(lb->conf, "entry/%d/takeoff_t", &e->takeoff_t, i);
+	(void) conf_get_da_v(lb->conf, "entry/%d/landing_t", &e->landing_t, i);
+	(void) conf_get_da_v(lb->conf, "entry/%d/block_time", &e->block_time,
+	    i);
+	(void) conf_get_da_v(lb->conf, "entry/%d/air_time", &e->air_time, i);
+	if (conf_get_i_v(lb->conf, "entry/%d/num_landings", &num_landings, i))
+		e->num_landings = MAX(num_landings, 0);
+	(void) conf_get_da_v(lb->conf, "entry/%d/touchdown_vs",
+	    &e->touchdown_vs, i);
+	(void) conf_get_da_v(lb->conf, "entry/%d/dist", &e->dist, i);
+
+	return (B_TRUE);
+}
+
+/*
+ * Unset times (e.g. the landing time of an entry that never landed) are
+ * exported as empty fields.
+ */
+static void
+fmt_entry_time(double utime, char buf[LACF_ISO8601_BUFSZ])
+{
+	if (utime != 0)
+		lacf_fmt_iso8601(utime, buf);
+	else
+		buf[0] = '\0';
+}
+
+/*
+ * Exports all completed logbook entries into a CSV file. All times are
+ * ISO 8601 UTC timestamps, durations are in decimal hours, the distance
+ * is in nautical miles and the touchdown rate is in feet per minute.
+ *
+ * @return B_TRUE on success, B_FALSE on failure (error is logged).
+ */
+bool_t
+logbook_export_csv(const logbook_t *lb, const char *filename)
+{
+	FILE *fp;
+	bool_t ok;
+
+	ASSERT(lb != NULL);
+	ASSERT(filename != NULL);
+
+	fp = fopen(filename, "wb");
+	if (fp == NULL) {
+		logMsg("Error writing logbook export %s: %s", filename,
+		    strerror(errno));
+		return (B_FALSE);
+	}
+	fprintf(fp, "dep,arr,block_off,takeoff,landing,block_on,"
+	    "block_time,air_time,landings,touchdown_fpm,dist_nm\n");
+	for (size_t i = 0; i < lb->num_entries; i++) {
+		logbook_entry_t e;
+		char off[LACF_ISO8601_BUFSZ], to[LACF_ISO8601_BUFSZ];
+		char ldg[LACF_ISO8601_BUFSZ], on[LACF_ISO8601_BUFSZ];
+
+		if (!logbook_get_entry(lb, i, &e))
+			continue;
+		fmt_entry_time(e.block_off_t, off);
+		fmt_entry_time(e.takeoff_t, to);
+		fmt_entry_time(e.landing_t, ldg);
+		fmt_entry_time(e.block_on_t, on);
+		fprintf(fp, "%s,%s,%s,%s,%s,%s,%.2f,%.2f,%u,%.0f,%.1f\n",
+		    e.dep, e.arr, off, to, ldg, on, e.block_time / 3600.0,
+		    e.air_time / 3600.0, e.num_landings,
+		    MPS2FPM(e.touchdown_vs), MET2NM(e.dist));
+	}
+	ok = !ferror(fp);
+	if (fclose(fp) != 0)
+		ok = B_FALSE;
+	if (!ok) {
+		logMsg("Error writing logbook export %s: %s", filename,
+		    strerror(errno));
+	}
+
+	return (ok);
+}
@@ -17,6 +17,8 @@
  */
 
 #include <math.h>
+#include <stdlib.h>
+#include <string.h>
 
 #include <acfutils/geom.h>
 #include <acfutils/helpers.h>
@@ -54,6 +56,123 @@ quadratic_solve(double a, double b, double c, double x[2])
 	}
 }
 
+static int
+dbl_compar(const void *a, const void *b)
+{
+	double da = *(const double *)a, db = *(const double *)b;
+
+	return (da < db ? -1 : (da > db ? 1 : 0));
+}
+
+/*
+ * Solves cubic equation ax^3 + bx^2 + cx + d = 0. The real solutions are
+ * placed in 'x' in ascending order. Returns the number of distinct real
+ * solutions (0 - 3). If 'a' is zero, this degrades to quadratic_solve.
+ */
+unsigned
+cubic_solve(double a, double b, double c, double d, double x[3])
+{
+	double p, q, disc, shift;
+	const double coeff[4] = { d, c, b, a };
+	unsigned n;
+
+	if (a == 0) {
+		n = quadratic_solve(b, c, d, x);
+		if (n == 2 && x[0] > x[1]) {
+			double tmp = x[0];
+			x[0] = x[1];
+			x[1] = tmp;
+		}
+		return (n);
+	}
+	/* Convert to the depressed cubic t^3 + pt + q = 0, x = t - b/3a */
+	b /= a;
+	c /= a;
+	d /= a;
+	shift = -b / 3;
+	p = c - POW2(b) / 3;
+	q = 2 * POW3(b) / 27 - b * c / 3 + d;
+	disc = POW2(q) / 4 + POW3(p) / 27;
+
+	if (fabs(p) < ROUND_ERROR && fabs(q) < ROUND_ERROR) {
+		/* triple root */
+		x[0] = shift;
+		n = 1;
+	} else if (disc > ROUND_ERROR) {
+		/* one real root (Cardano) */
+		double sq = sqrt(disc);
+		x[0] = cbrt(-q / 2 + sq) + cbrt(-q / 2 - sq) + shift;
+		n = 1;
+	} else if (disc > -ROUND_ERROR) {
+		/* a double root and a simple root */
+		double u = cbrt(-q / 2);
+		x[0] = 2 * u + shift;
+		x[1] = -u + shift;
+		n = 2;
+	} else {
+		/* three distinct real roots (trigonometric method) */
+		double r = 2 * sqrt(-p / 3);
+		double phi = acos(clamp(3 * q / (p * r), -1, 1)) / 3;
+
+		for (int i = 0; i < 3; i++)
+			x[i] = r * cos(phi - 2 * M_PI * i / 3) + shift;
+		n = 3;
+	}
+	/* polish the roots to full precision with a Newton step */
+	for (unsigned i = 0; i < n; i++) {
+		double deriv = (3 * a * x[i] + 2 * a * b) * x[i] + a * c;
+		double val = poly_eval(x[i], coeff, 4);
+
+		if (fabs(deriv) > ROUND_ERROR)
+			x[i] -= val / deriv;
+	}
+	qsort(x, n, sizeof (*x), dbl_compar);
+
+	return (n);
+}
+
+/*
+ * Evaluates a polynomial using Horner's method. The polynomial is
+ * defined by its coefficients in ascending order of power, i.e.
+ * coeff[0] + coeff[1].x + coeff[2].x^2 + ... (same as in pn_interp_t).
+ *
+ * @param x The point at which to evaluate the polynomial.
+ * @param coeff The polynomial's coefficients.
+ * @param n Number of coefficients (the polynomial's degree + 1).
+ */
+double
+poly_eval(double x, const double *coeff, unsigned n)
+{
+	double y = 0;
+
+	ASSERT(coeff != NULL || n == 0);
+	for (unsigned i = n; i > 0; i--)
+		y = y * x + coeff[i - 1];
+
+	return (y);
+}
+
+/*
+ * Computes the derivative of a polynomial (see poly_eval for the
+ * coefficient layout). The derivative's coefficients are written to
+ * `deriv', which must have space for at least `n' - 1 coefficients.
+ * `deriv' may point to the same array as `coeff'.
+ *
+ * @return The number of coefficients of the derivative.
+ */
+unsigned
+poly_deriv(const double *coeff, unsigned n, double *deriv)
+{
+	ASSERT(coeff != NULL || n == 0);
+	ASSERT(deriv != NULL || n <= 1);
+	if (n <= 1)
+		return (0);
+	for (unsigned i = 1; i < n; i++)
+		deriv[i - 1] = coeff[i] * i;
+
+	return (n - 1);
+}
+
 /*
  * Interpolates a linear function defined by two points.
  *
@@ -176,6 +295,345 @@ fx_lin_multi_inv(double y, const struct vect2_s *points, size_t *num_out)
 	return (fx_lin_multi_inv2(y, points, B_FALSE, num_out));
 }
 
+struct lin_tbl_s {
+	size_t	n;
+	double	*x;
+	double	*y;
+};
+
+struct lin_tbl2_s {
+	size_t	nx;
+	size_t	ny;
+	double	*x;
+	double	*y;
+	double	*z;
+};
+
+static bool_t
+tbl_axis_valid(const double *x, size_t n)
+{
+	if (n < 2)
+		return (B_FALSE);
+	for (size_t i = 0; i < n; i++) {
+		if (!isfinite(x[i]) || (i > 0 && x[i] <= x[i - 1]))
+			return (B_FALSE);
+	}
+	return (B_TRUE);
+}
+
+/*
+ * Locates the segment of a table axis for `x' using a binary search and
+ * returns the index of the segment's first point. The fractional
+ * position of `x' in the segment is returned in `t'. Outside of the
+ * axis, the first or last segment is used and `t' is either clamped to
+ * that segment or extrapolated from it.
+ */
+static size_t
+tbl_axis_lookup(const double *axis, size_t n, double x, bool_t extrapolate,
+    double *t)
+{
+	size_t lo = 0, hi = n - 1;
+
+	ASSERT3U(n, >=, 2);
+	while (hi - lo > 1) {
+		size_t mid = (lo + hi) / 2;
+
+		if (x < axis[mid])
+			hi = mid;
+		else
+			lo = mid;
+	}
+	*t = (x - axis[lo]) / (axis[lo + 1] - axis[lo]);
+	if (!extrapolate)
+		*t = clamp(*t, 0, 1);
+
+	return (lo);
+}
+
+/*
+ * Constructs a 1D linear interpolation table. This is the equivalent of
+ * fx_lin_multi for lookup-heavy code (such as engine performance maps),
+ * using a binary search instead of a linear scan of the points.
+ *
+ * @param points The table points. There must be at least 2 points and
+ *	their X coordinates must be finite and strictly increasing. The
+ *	points are copied, so the caller may free them after this call.
+ *	Unlike with fx_lin_multi, the array needn't be terminated by a
+ *	NULL_VECT2.
+ * @param n Number of points in `points'.
+ *
+ * @return The new table, or NULL if the points failed validation. Use
+ *	lin_tbl_free to free the table.
+ */
+lin_tbl_t *
+lin_tbl_alloc(const vect2_t *points, size_t n)
+{
+	lin_tbl_t *tbl;
+
+	ASSERT(points != NULL || n == 0);
+	for (size_t i = 0; i < n; i++) {
+		if (!isfinite(points[i].x) || isnan(points[i].y) ||
+		    (i > 0 && points[i].x <= points[i - 1].x))
+			return (NULL);
+	}
+	if (n < 2)
+		return (NULL);
+
+	tbl = safe_calloc(1, sizeof (*tbl));
+	tbl->n = n;
+	tbl->x = safe_calloc(n, sizeof (*tbl->x));
+	tbl->y = safe_calloc(n, sizeof (*tbl->y));
+	for (size_t i = 0; i < n; i++) {
+		tbl->x[i] = points[i].x;
+		tbl->y[i] = points[i].y;
+	}
+
+	return (tbl);
+}
+
+void
+lin_tbl_free(lin_tbl_t *tbl)
+{
+	if (tbl == NULL)
+		return;
+	free(tbl->x);
+	free(tbl->y);
+	free(tbl);
+}
+
+/*
+ * Evaluates a 1D table at `x'. If `x' falls outside of the table and
+ * `extrapolate' is B_TRUE, the nearest segment is extrapolated (same as
+ * fx_lin_multi). Otherwise, the result is clamped to the value at the
+ * nearest end of the table.
+ */
+double
+lin_tbl_eval(const lin_tbl_t *tbl, double x, bool_t extrapolate)
+{
+	size_t i;
+	double t;
+
+	ASSERT(tbl != NULL);
+	ASSERT(!isnan(x));
+	i = tbl_axis_lookup(tbl->x, tbl->n, x, extrapolate, &t);
+
+	return (tbl->y[i] + t * (tbl->y[i + 1] - tbl->y[i]));
+}
+
+/*
+ * Constructs a 2D bilinear interpolation table.
+ *
+ * @param x Breakpoints along the X axis. There must be at least 2 and
+ *	they must be finite and strictly increasing.
+ * @param nx Number of elements in `x'.
+ * @param y Breakpoints along the Y axis, with the same requirements as
+ *	for `x'.
+ * @param ny Number of elements in `y'.
+ * @param z Table values, `nx' * `ny' elements long. The value at
+ *	(x[i], y[j]) is stored in z[i * ny + j].
+ *
+ * All arrays are copied, so the caller may free them after this call.
+ *
+ * @return The new table, or NULL if the breakpoints failed validation.
+ *	Use lin_tbl2_free to free the table.
+ */
+lin_tbl2_t *
+lin_tbl2_alloc(const double *x, size_t nx, const double *y, size_t ny,
+    const double *z)
+{
+	lin_tbl2_t *tbl;
+
+	ASSERT(x != NULL || nx == 0);
+	ASSERT(y != NULL || ny == 0);
+	if (!tbl_axis_valid(x, nx) || !tbl_axis_valid(y, ny))
+		return (NULL);
+	ASSERT(z != NULL);
+
+	tbl = safe_calloc(1, sizeof (*tbl));
+	tbl->nx = nx;
+	tbl->ny = ny;
+	tbl->x = safe_calloc(nx, sizeof (*tbl->x));
+	tbl->y = safe_calloc(ny, sizeof (*tbl->y));
+	tbl->z = safe_calloc(nx * ny, sizeof (*tbl->z));
+	memcpy(tbl->x, x, nx * sizeof (*x));
+	memcpy(tbl->y, y, ny * sizeof (*y));
+	memcpy(tbl->z, z, nx * ny * sizeof (*z));
+
+	return (tbl);
+}
+
+void
+lin_tbl2_free(lin_tbl2_t *tbl)
+{
+	if (tbl == NULL)
+		return;
+	free(tbl->x);
+	free(tbl->y);
+	free(tbl->z);
+	free(tbl);
+}
+
+/*
+ * Evaluates a 2D table at (x, y) using bilinear interpolation. The
+ * `extrapolate' argument has the same meaning as for lin_tbl_eval and
+ * applies to both axes.
+ */
+double
+lin_tbl2_eval(const lin_tbl2_t *tbl, double x, double y, bool_t extrapolate)
+{
+	size_t i, j;
+	double tx, ty, z0, z1;
+	const double *row0, *row1;
+
+	ASSERT(tbl != NULL);
+	ASSERT(!isnan(x));
+	ASSERT(!isnan(y));
+
+	i = tbl_axis_lookup(tbl->x, tbl->nx, x, extrapolate, &tx);
+	j = tbl_axis_lookup(tbl->y, tbl->ny, y, extrapolate, &ty);
+	row0 = &tbl->z[i * tbl->ny];
+	row1 = &tbl->z[(i + 1) * tbl->ny];
+	z0 = row0[j] + ty * (row0[j + 1] - row0[j]);
+	z1 = row1[j] + ty * (row1[j + 1] - row1[j]);
+
+	return (z0 + tx * (z1 - z0));
+}
+
+struct cspline_s {
+	size_t	n;
+	double	*x;
+	double	*y;
+	double	*m;		/* second derivatives at the points */
+};
+
+/*
+ * Constructs a natural cubic spline passing through a set of points.
+ * Unlike pn_interp_t, the spline stays well-behaved with many points,
+ * making it suitable for generating smooth paths and animation curves.
+ *
+ * @param points The points to pass through. There must be at least 2
+ *	points and their X coordinates must be finite and strictly
+ *	increasing. The points are copied.
+ * @param n Number of points in `points'.
+ *
+ * @return The new spline, or NULL if the points failed validation. Use
+ *	cspline_free to free the spline.
+ */
+cspline_t *
+cspline_alloc(const vect2_t *points, size_t n)
+{
+	cspline_t *sp;
+	double *c;
+
+	ASSERT(points != NULL || n == 0);
+	for (size_t i = 0; i < n; i++) {
+		if (!isfinite(points[i].x) || !isfinite(points[i].y) ||
+		    (i > 0 && points[i].x <= points[i - 1].x))
+			return (NULL);
+	}
+	if (n < 2)
+		return (NULL);
+
+	sp = safe_calloc(1, sizeof (*sp));
+	sp->n = n;
+	sp->x = safe_calloc(n, sizeof (*sp->x));
+	sp->y = safe_calloc(n, sizeof (*sp->y));
+	sp->m = safe_calloc(n, sizeof (*sp->m));
+	for (size_t i = 0; i < n; i++) {
+		sp->x[i] = points[i].x;
+		sp->y[i] = points[i].y;
+	}
+	/*
+	 * Solve the tridiagonal system for the second derivatives using
+	 * the Thomas algorithm. The natural boundary conditions set the
+	 * second derivatives at both ends to zero.
+	 */
+	c = safe_calloc(n, sizeof (*c));
+	for (size_t i = 1; i + 1 < n; i++) {
+		double h0 = sp->x[i] - sp->x[i - 1];
+		double h1 = sp->x[i + 1] - sp->x[i];
+		double d = 6 * ((sp->y[i + 1] - sp->y[i]) / h1 -
+		    (sp->y[i] - sp->y[i - 1]) / h0);
+		double denom = 2 * (h0 + h1) - h0 * c[i - 1];
+
+		c[i] = h1 / denom;
+		sp->m[i] = (d - h0 * sp->m[i - 1]) / denom;
+	}
+	for (size_t i = n - 2; i > 0; i--)
+		sp->m[i] -= c[i] * sp->m[i + 1];
+	free(c);
+
+	return (sp);
+}
+
+void
+cspline_free(cspline_t *sp)
+{
+	if (sp == NULL)
+		return;
+	free(sp->x);
+	free(sp->y);
+	free(sp->m);
+	free(sp);
+}
+
+static double
+cspline_seg_deriv(const cspline_t *sp, size_t i, double x)
+{
+	double h = sp->x[i + 1] - sp->x[i];
+	double a = (sp->x[i + 1] - x) / h, b = (x - sp->x[i]) / h;
+
+	return ((sp->y[i + 1] - sp->y[i]) / h -
+	    (3 * POW2(a) - 1) / 6 * h * sp->m[i] +
+	    (3 * POW2(b) - 1) / 6 * h * sp->m[i + 1]);
+}
+
+/*
+ * Evaluates a cubic spline at `x'. Outside of the spline's points, the
+ * spline is extrapolated linearly along its end tangent.
+ */
+double
+cspline_eval(const cspline_t *sp, double x)
+{
+	size_t i;
+	double t, h, a, b;
+
+	ASSERT(sp != NULL);
+	ASSERT(!isnan(x));
+	if (x < sp->x[0]) {
+		return (sp->y[0] + (x - sp->x[0]) *
+		    cspline_seg_deriv(sp, 0, sp->x[0]));
+	}
+	if (x > sp->x[sp->n - 1]) {
+		return (sp->y[sp->n - 1] + (x - sp->x[sp->n - 1]) *
+		    cspline_seg_deriv(sp, sp->n - 2, sp->x[sp->n - 1]));
+	}
+	i = tbl_axis_lookup(sp->x, sp->n, x, B_FALSE, &t);
+	h = sp->x[i + 1] - sp->x[i];
+	a = 1 - t;
+	b = t;
+
+	return (a * sp->y[i] + b * sp->y[i + 1] + ((POW3(a) - a) * sp->m[i] +
+	    (POW3(b) - b) * sp->m[i + 1]) * POW2(h) / 6);
+}
+
+/*
+ * Returns the first derivative (slope) of a cubic spline at `x'.
+ */
+double
+cspline_deriv(const cspline_t *sp, double x)
+{
+	size_t i;
+	double t;
+
+	ASSERT(sp != NULL);
+	ASSERT(!isnan(x));
+	i = tbl_axis_lookup(sp->x, sp->n, x, B_FALSE, &t);
+
+	return (cspline_seg_deriv(sp, i, clamp(x, sp->x[0],
+	    sp->x[sp->n - 1])));
+}
+
 /*
  * Algorithm credit: https://en.wikibooks.org/wiki/\
  *	Algorithm_Implementation/Mathematics/Polynomial_interpolation
@@ -0,0 +1,609 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <ctype.h>
+#include <math.h>
+#include <string.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/helpers.h>
+#include <acfutils/metar.h>
+#include <acfutils/perf.h>
+
+#define	SM2MET(x)	((x) * 1609.344)	/* statute miles to meters */
+
+static const char *wx_codes[] = {
+	/* descriptors */
+	"MI", "BC", "PR", "DR", "BL", "SH", "TS", "FZ",
+	/* precipitation */
+	"DZ", "RA", "SN", "SG", "IC", "PL", "GR", "GS", "UP",
+	/* obscuration */
+	"BR", "FG", "FU", "VA", "DU", "SA", "HZ", "PY",
+	/* other */
+	"PO", "SQ", "FC", "SS", "DS",
+	NULL
+};
+
+static bool_t
+all_digits(const char *str, size_t len)
+{
+	if (strlen(str) < len)
+		return (B_FALSE);
+	for (size_t i = 0; i < len; i++) {
+		if (!isdigit(str[i]))
+			return (B_FALSE);
+	}
+	return (B_TRUE);
+}
+
+static bool_t
+parse_time(const char *tok, metar_t *metar)
+{
+	if (strlen(tok) != 7 || tok[6] != 'Z' || !all_digits(tok, 6))
+		return (B_FALSE);
+	metar->day = (tok[0] - '0') * 10 + (tok[1] - '0');
+	metar->hour = (tok[2] - '0') * 10 + (tok[3] - '0');
+	metar->min = (tok[4] - '0') * 10 + (tok[5] - '0');
+	return (B_TRUE);
+}
+
+static bool_t
+parse_wind(const char *tok, metar_t *metar)
+{
+	const char *p;
+	char *end;
+	double factor;
+	int dir, spd, gust = 0;
+
+	/* don't touch `metar' until we're sure this is a wind group */
+	if (strncmp(tok, "VRB", 3) == 0) {
+		dir = METAR_WIND_VRB;
+	} else if (all_digits(tok, 3)) {
+		dir = (tok[0] - '0') * 100 + (tok[1] - '0') * 10 +
+		    (tok[2] - '0');
+	} else {
+		return (B_FALSE);
+	}
+	p = &tok[3];
+	if (!isdigit(*p))
+		return (B_FALSE);
+	spd = strtol(p, &end, 10);
+	p = end;
+	if (*p == 'G') {
+		gust = strtol(p + 1, &end, 10);
+		if (end == p + 1)
+			return (B_FALSE);
+		p = end;
+	}
+	if (strcmp(p, "KT") == 0)
+		factor = 1;
+	else if (strcmp(p, "MPS") == 0)
+		factor = MPS2KT(1);
+	else if (strcmp(p, "KMH") == 0)
+		factor = MPS2KT(1) / 3.6;
+	else
+		return (B_FALSE);
+	metar->wind_dir = dir;
+	metar->wind_spd = round(spd * factor);
+	metar->wind_gust = round(gust * factor);
+	metar->wind_valid = B_TRUE;
+
+	return (B_TRUE);
+}
+
+static bool_t
+parse_wind_var(const char *tok, metar_t *metar)
+{
+	if (strlen(tok) != 7 || tok[3] != 'V' || !all_digits(tok, 3) ||
+	    !all_digits(&tok[4], 3))
+		return (B_FALSE);
+	metar->wind_var_from = atoi(tok);
+	metar->wind_var_to = atoi(&tok[4]);
+	return (B_TRUE);
+}
+
+/*
+ * Parses a statute mile fraction, e.g. "1/2" or "3".
+ */
+static bool_t
+parse_sm(const char *str, size_t len, double *sm)
+{
+	const char *slash = memchr(str, '/', len);
+
+	for (size_t i = 0; i < len; i++) {
+		if (!isdigit(str[i]) && str[i] != '/')
+			return (B_FALSE);
+	}
+	if (slash != NULL) {
+		int den = atoi(slash + 1);
+
+		if (den == 0)
+			return (B_FALSE);
+		*sm = atoi(str) / (double)den;
+	} else {
+		*sm = atoi(str);
+	}
+	return (B_TRUE);
+}
+
+/*
+ * Parses the visibility group. `next' is the following token (or NULL),
+ * which is needed for whole & fractional mile groups like "1 1/2SM".
+ * Returns the number of tokens consumed.
+ */
+static int
+parse_vis(const char *tok, const char *next, metar_t *metar)
+{
+	size_t len = strlen(tok);
+	double sm, frac;
+
+	if (len >= 4 && all_digits(tok, 4) &&
+	    (len == 4 || strcmp(&tok[4], "NDV") == 0)) {
+		metar->vis = atoi(tok);
+		if (metar->vis >= 9999)
+			metar->vis = METAR_VIS_MAX;
+		metar->vis_valid = B_TRUE;
+		return (1);
+	}
+	if (next != NULL && all_digits(tok, len) && len <= 2 &&
+	    strlen(next) > 2 && strcmp(&next[strlen(next) - 2], "SM") == 0 &&
+	    parse_sm(next, strlen(next) - 2, &frac) && frac < 1) {
+		sm = atoi(tok) + frac;
+		metar->vis = MIN(SM2MET(sm), METAR_VIS_MAX);
+		metar->vis_sm = B_TRUE;
+		metar->vis_valid = B_TRUE;
+		return (2);
+	}
+	if (len > 2 && strcmp(&tok[len - 2], "SM") == 0) {
+		/* "M1/4SM" means less than 1/4 mile, "P6SM" more than 6 */
+		if (tok[0] == 'M' || tok[0] == 'P') {
+			tok++;
+			len--;
+		}
+		if (!parse_sm(tok, len - 2, &sm))
+			return (0);
+		metar->vis = MIN(SM2MET(sm), METAR_VIS_MAX);
+		metar->vis_sm = B_TRUE;
+		metar->vis_valid = B_TRUE;
+		return (1);
+	}
+	return (0);
+}
+
+static bool_t
+parse_wx(const char *tok, metar_t *metar)
+{
+	const char *p = tok;
+	size_t len;
+
+	if (*p == '-' || *p == '+')
+		p++;
+	else if (strncmp(p, "VC", 2) == 0)
+		p += 2;
+	len = strlen(p);
+	if (len == 0 || len % 2 != 0)
+		return (B_FALSE);
+	for (size_t i = 0; i < len; i += 2) {
+		bool_t found = B_FALSE;
+
+		for (int j = 0; wx_codes[j] != NULL; j++) {
+			if (strncmp(&p[i], wx_codes[j], 2) == 0) {
+				found = B_TRUE;
+				break;
+			}
+		}
+		if (!found)
+			return (B_FALSE);
+	}
+	if (metar->num_wx < METAR_MAX_WX) {
+		lacf_strlcpy(metar->wx[metar->num_wx], tok,
+		    sizeof (metar->wx[0]));
+		metar->num_wx++;
+	}
+	return (B_TRUE);
+}
+
+static bool_t
+parse_cld(const char *tok, metar_t *metar)
+{
+	static const char *covers[] = { "FEW", "SCT", "BKN", "OVC" };
+	metar_cld_t cld = {};
+	const char *p;
+
+	if (strcmp(tok, "SKC") == 0 || strcmp(tok, "CLR") == 0 ||
+	    strcmp(tok, "NSC") == 0 || strcmp(tok, "NCD") == 0) {
+		metar->sky_clear = B_TRUE;
+		return (B_TRUE);
+	}
+	if (strncmp(tok, "VV", 2) == 0) {
+		cld.cover = METAR_CLD_VV;
+		p = &tok[2];
+	} else {
+		int i;
+
+		for (i = 0; i < (int)ARRAY_NUM_ELEM(covers); i++) {
+			if (strncmp(tok, covers[i], 3) == 0)
+				break;
+		}
+		if (i == (int)ARRAY_NUM_ELEM(covers))
+			return (B_FALSE);
+		cld.cover = i;
+		p = &tok[3];
+	}
+	/* the base may be "///" if the ceilometer couldn't measure it */
+	if (all_digits(p, 3))
+		cld.base = atoi(p) * 100;
+	else if (strncmp(p, "///", 3) != 0)
+		return (B_FALSE);
+	p += 3;
+	if (strcmp(p, "CB") == 0)
+		cld.cb = B_TRUE;
+	else if (strcmp(p, "TCU") == 0)
+		cld.tcu = B_TRUE;
+	else if (*p != '\0' && strcmp(p, "///") != 0)
+		return (B_FALSE);
+	if (metar->num_clds < METAR_MAX_CLDS) {
+		metar->clds[metar->num_clds] = cld;
+		metar->num_clds++;
+	}
+	return (B_TRUE);
+}
+
+static bool_t
+parse_temp_val(const char *str, size_t len, int *val)
+{
+	bool_t neg = B_FALSE;
+
+	if (len > 0 && str[0] == 'M') {
+		neg = B_TRUE;
+		str++;
+		len--;
+	}
+	if (len != 2 || !all_digits(str, 2))
+		return (B_FALSE);
+	*val = (str[0] - '0') * 10 + (str[1] - '0');
+	if (neg)
+		*val = -(*val);
+	return (B_TRUE);
+}
+
+static bool_t
+parse_temp(const char *tok, metar_t *metar)
+{
+	const char *slash = strchr(tok, '/');
+
+	if (slash == NULL || !parse_temp_val(tok, slash - tok, &metar->temp))
+		return (B_FALSE);
+	/* dew point may be missing (e.g. "15/" or "15///") */
+	if (!parse_temp_val(slash + 1, strlen(slash + 1), &metar->dewpt))
+		metar->dewpt = metar->temp;
+	metar->temp_valid = B_TRUE;
+	return (B_TRUE);
+}
+
+static bool_t
+parse_qnh(const char *tok, metar_t *metar)
+{
+	if (strlen(tok) != 5 || !all_digits(&tok[1], 4))
+		return (B_FALSE);
+	if (tok[0] == 'Q') {
+		metar->qnh = atoi(&tok[1]);
+		metar->qnh_inhg = B_FALSE;
+	} else if (tok[0] == 'A') {
+		metar->qnh = atoi(&tok[1]) / 100.0 * 33.8639;
+		metar->qnh_inhg = B_TRUE;
+	} else {
+		return (B_FALSE);
+	}
+	metar->qnh_valid = B_TRUE;
+	return (B_TRUE);
+}
+
+static void
+metar_init(metar_t *metar)
+{
+	memset(metar, 0, sizeof (*metar));
+	metar->wind_var_from = -1;
+	metar->wind_var_to = -1;
+}
+
+static char **
+tokenize(const char *str, size_t *n)
+{
+	char *buf = safe_strdup(str);
+	char **toks;
+
+	for (char *p = buf; *p != '\0'; p++) {
+		if (isspace(*p) || *p == '=')
+			*p = ' ';
+	}
+	toks = strsplit(buf, " ", B_TRUE, n);
+	free(buf);
+
+	return (toks);
+}
+
+/*
+ * Parses a weather conditions group, which can appear both in METARs and
+ * in TAF forecast periods. Returns the number of tokens consumed, or 0
+ * if the token at `i' isn't a conditions group.
+ */
+static int
+parse_cond(char **toks, size_t n, size_t i, metar_t *metar)
+{
+	const char *tok = toks[i];
+	int consumed;
+
+	if (strcmp(tok, "CAVOK") == 0) {
+		metar->cavok = B_TRUE;
+		metar->vis = METAR_VIS_MAX;
+		metar->vis_valid = B_TRUE;
+		return (1);
+	}
+	if (!metar->wind_valid && parse_wind(tok, metar))
+		return (1);
+	if (metar->wind_valid && parse_wind_var(tok, metar))
+		return (1);
+	if (!metar->vis_valid && (consumed = parse_vis(tok,
+	    i + 1 < n ? toks[i + 1] : NULL, metar)) != 0)
+		return (consumed);
+	if (tok[0] == 'R' && isdigit(tok[1])) {
+		/* runway visual range, not decoded */
+		return (1);
+	}
+	if (parse_cld(tok, metar) || parse_temp(tok, metar) ||
+	    parse_qnh(tok, metar) || parse_wx(tok, metar))
+		return (1);
+	return (0);
+}
+
+/*
+ * Joins tokens into the remarks buffer, truncating if necessary.
+ */
+static void
+copy_rmk(char **toks, size_t n, size_t i, metar_t *metar)
+{
+	for (; i < n; i++) {
+		if (metar->rmk[0] != '\0')
+			strncat(metar->rmk, " ", sizeof (metar->rmk) -
+			    strlen(metar->rmk) - 1);
+		strncat(metar->rmk, toks[i], sizeof (metar->rmk) -
+		    strlen(metar->rmk) - 1);
+	}
+}
+
+/*
+ * Parses a METAR or SPECI weather report in the standard ICAO format
+ * (including the US variants, such as visibility in statute miles and
+ * altimeter settings in inHg). Trend forecasts (everything starting with
+ * "NOSIG", "BECMG" or "TEMPO") are ignored, as are any groups which
+ * can't be decoded, so the parser copes with most of the non-standard
+ * reports found in practice. Remarks (everything following "RMK") aren't
+ * decoded, as their contents vary greatly between countries, but their
+ * raw text is returned in the `rmk' field.
+ *
+ * @param str The METAR text. A leading "METAR" or "SPECI" keyword is
+ *	optional.
+ * @param metar Output parsed report.
+ *
+ * @return B_TRUE if the report was parsed, B_FALSE if it didn't contain
+ *	at least a station identifier.
+ */
+bool_t
+metar_parse(const char *str, metar_t *metar)
+{
+	char **toks;
+	size_t n;
+	size_t i = 0;
+	bool_t trend = B_FALSE;
+
+	ASSERT(str != NULL);
+	ASSERT(metar != NULL);
+
+	metar_init(metar);
+	toks = tokenize(str, &n);
+
+	while (i < n && (strcmp(toks[i], "METAR") == 0 ||
+	    strcmp(toks[i], "SPECI") == 0 || strcmp(toks[i], "COR") == 0))
+		i++;
+	if (i >= n || strlen(toks[i]) != 4) {
+		free_strlist(toks, n);
+		return (B_FALSE);
+	}
+	lacf_strlcpy(metar->station, toks[i], sizeof (metar->station));
+	for (i++; i < n; i++) {
+		const char *tok = toks[i];
+		int consumed;
+
+		if (strcmp(tok, "RMK") == 0) {
+			copy_rmk(toks, n, i + 1, metar);
+			break;
+		}
+		if (strcmp(tok, "NOSIG") == 0 || strcmp(tok, "BECMG") == 0 ||
+		    strcmp(tok, "TEMPO") == 0)
+			trend = B_TRUE;
+		if (trend)
+			continue;
+		if (strcmp(tok, "AUTO") == 0) {
+			metar->is_auto = B_TRUE;
+		} else if (metar->day == 0 && parse_time(tok, metar)) {
+			continue;
+		} else if ((consumed = parse_cond(toks, n, i, metar)) != 0) {
+			i += consumed - 1;
+		}
+	}
+	free_strlist(toks, n);
+
+	return (B_TRUE);
+}
+
+/*
+ * Parses a "ddhh/ddhh" validity period group.
+ */
+static bool_t
+parse_validity(const char *tok, int *from_day, int *from_hour, int *to_day,
+    int *to_hour)
+{
+	if (strlen(tok) != 9 || tok[4] != '/' || !all_digits(tok, 4) ||
+	    !all_digits(&tok[5], 4))
+		return (B_FALSE);
+	*from_day = (tok[0] - '0') * 10 + (tok[1] - '0');
+	*from_hour = (tok[2] - '0') * 10 + (tok[3] - '0');
+	*to_day = (tok[5] - '0') * 10 + (tok[6] - '0');
+	*to_hour = (tok[7] - '0') * 10 + (tok[8] - '0');
+	return (B_TRUE);
+}
+
+static taf_period_t *
+taf_add_period(taf_t *taf, taf_chg_t type)
+{
+	taf_period_t *period;
+
+	if (taf->num_periods >= TAF_MAX_PERIODS)
+		return (NULL);
+	period = &taf->periods[taf->num_periods++];
+	memset(period, 0, sizeof (*period));
+	period->type = type;
+	period->to_day = -1;
+	period->to_hour = -1;
+	metar_init(&period->wx);
+
+	return (period);
+}
+
+/*
+ * Parses a TAF (terminal aerodrome forecast) in the standard ICAO format.
+ * The forecast is split into periods. The first period (of type
+ * TAF_CHG_BASE) holds the initial forecast conditions and spans the
+ * entire validity of the TAF. Each subsequent "FM", "BECMG", "TEMPO" or
+ * "PROBnn" group starts a new period. Please note that, as in the TAF
+ * itself, a "BECMG", "TEMPO" or "PROB" period only lists the conditions
+ * which change, so to determine the full forecast conditions at a given
+ * time, you must overlay them on top of the preceding "FM" or base
+ * period.
+ *
+ * The conditions in each period are decoded into a metar_t structure,
+ * where only the wind, visibility, weather and cloud fields are used.
+ * An "NSW" (no significant weather) group is decoded as the `nsw' flag
+ * of the period. Temperature forecast groups ("TX" and "TN") and any
+ * groups which can't be decoded are skipped. Forecasts with more than
+ * TAF_MAX_PERIODS periods are truncated.
+ *
+ * @param str The TAF text. A leading "TAF" keyword is optional.
+ * @param taf Output parsed forecast.
+ *
+ * @return B_TRUE if the forecast was parsed, B_FALSE if it didn't contain
+ *	at least a station identifier.
+ */
+bool_t
+taf_parse(const char *str, taf_t *taf)
+{
+	char **toks;
+	size_t n;
+	size_t i = 0;
+	taf_period_t *period;
+
+	ASSERT(str != NULL);
+	ASSERT(taf != NULL);
+
+	memset(taf, 0, sizeof (*taf));
+	toks = tokenize(str, &n);
+
+	for (; i < n; i++) {
+		if (strcmp(toks[i], "AMD") == 0)
+			taf->amd = B_TRUE;
+		else if (strcmp(toks[i], "COR") == 0)
+			taf->cor = B_TRUE;
+		else if (strcmp(toks[i], "TAF") != 0)
+			break;
+	}
+	if (i >= n || strlen(toks[i]) != 4) {
+		free_strlist(toks, n);
+		return (B_FALSE);
+	}
+	lacf_strlcpy(taf->station, toks[i], sizeof (taf->station));
+	i++;
+	if (i < n && strlen(toks[i]) == 7 && toks[i][6] == 'Z' &&
+	    all_digits(toks[i], 6)) {
+		taf->day = (toks[i][0] - '0') * 10 + (toks[i][1] - '0');
+		taf->hour = (toks[i][2] - '0') * 10 + (toks[i][3] - '0');
+		taf->min = (toks[i][4] - '0') * 10 + (toks[i][5] - '0');
+		i++;
+	}
+	period = taf_add_period(taf, TAF_CHG_BASE);
+	if (i < n && parse_validity(toks[i], &taf->from_day, &taf->from_hour,
+	    &taf->to_day, &taf->to_hour)) {
+		period->from_day = taf->from_day;
+		period->from_hour = taf->from_hour;
+		period->to_day = taf->to_day;
+		period->to_hour = taf->to_hour;
+		i++;
+	}
+
+	for (; i < n; i++) {
+		const char *tok = toks[i];
+		int consumed;
+
+		if (strcmp(tok, "RMK") == 0)
+			break;
+		if (strcmp(tok, "CNL") == 0) {
+			taf->cnl = B_TRUE;
+		} else if (strcmp(tok, "BECMG") == 0 ||
+		    strcmp(tok, "TEMPO") == 0) {
+			taf_chg_t type = (tok[0] == 'B' ? TAF_CHG_BECMG :
+			    TAF_CHG_TEMPO);
+			/* "PROB30 TEMPO" forms a single period */
+			if (period != NULL && period->type == TAF_CHG_PROB &&
+			    type == TAF_CHG_TEMPO && i > 0 &&
+			    strncmp(toks[i - 1], "PROB", 4) == 0)
+				period->type = TAF_CHG_TEMPO;
+			else
+				period = taf_add_period(taf, type);
+		} else if (strncmp(tok, "PROB", 4) == 0 && strlen(tok) == 6 &&
+		    all_digits(&tok[4], 2)) {
+			period = taf_add_period(taf, TAF_CHG_PROB);
+			if (period != NULL)
+				period->prob = atoi(&tok[4]);
+		} else if (strncmp(tok, "FM", 2) == 0 && strlen(tok) == 8 &&
+		    all_digits(&tok[2], 6)) {
+			period = taf_add_period(taf, TAF_CHG_FM);
+			if (period != NULL) {
+				period->from_day = (tok[2] - '0') * 10 +
+				    (tok[3] - '0');
+				period->from_hour = (tok[4] - '0') * 10 +
+				    (tok[5] - '0');
+				period->from_min = (tok[6] - '0') * 10 +
+				    (tok[7] - '0');
+			}
+		} else if (period == NULL) {
+			/* out of period slots, skip the rest */
+			continue;
+		} else if (parse_validity(tok, &period->from_day,
+		    &period->from_hour, &period->to_day, &period->to_hour)) {
+			continue;
+		} else if (strcmp(tok, "NSW") == 0) {
+			period->nsw = B_TRUE;
+		} else if ((consumed = parse_cond(toks, n, i,
+		    &period->wx)) != 0) {
+			i += consumed - 1;
+		}
+	}
+	free_strlist(toks, n);
+
+	return (B_TRUE);
+}
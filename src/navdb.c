@@ -0,0 +1,691 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <errno.h>
+#include <stdio.h>
+#include <string.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/helpers.h>
+#include <acfutils/htbl.h>
+#include <acfutils/log.h>
+#include <acfutils/navdb.h>
+#include <acfutils/safe_alloc.h>
+
+#define	NAVDB_HTBL_SZ	16384
+
+struct navdb_s {
+	navdb_fix_t	*fixes;		/* sorted by latitude */
+	size_t		num_fixes;
+	navdb_navaid_t	*navaids;	/* sorted by latitude */
+	size_t		num_navaids;
+	navdb_awy_seg_t	*segs;
+	size_t		num_segs;
+
+	htbl_t		fix_idx;	/* by ident, multi-value */
+	htbl_t		navaid_idx;	/* by ident, multi-value */
+	htbl_t		awy_idx;	/* by airway name, multi-value */
+};
+
+static FILE *
+open_nav_file(const char *xpdir, const char *fname, char **path)
+{
+	FILE *fp;
+
+	/* First try 'Custom Data', then 'default data' */
+	*path = mkpathname(xpdir, "Custom Data", fname, NULL);
+	fp = fopen(*path, "r");
+	if (fp == NULL) {
+		free(*path);
+		*path = mkpathname(xpdir, "Resources", "default data", fname,
+		    NULL);
+		fp = fopen(*path, "r");
+	}
+	return (fp);
+}
+
+/*
+ * Returns true if the line is part of the file header (the "I"/"A"
+ * byte-order marker & version line) or is the "99" end-of-file marker.
+ */
+static bool_t
+is_hdr_line(const char *line, unsigned linenum)
+{
+	return (linenum <= 2 || strcmp(line, "99") == 0);
+}
+
+static void
+make_key(char key[NAVDB_NAME_LEN], const char *str)
+{
+	memset(key, 0, NAVDB_NAME_LEN);
+	lacf_strlcpy(key, str, NAVDB_NAME_LEN);
+}
+
+static bool_t
+read_fixes(navdb_t *db, const char *filename)
+{
+	FILE *fp = fopen(filename, "r");
+	char *line = NULL;
+	size_t linecap = 0, cap = 0;
+	unsigned linenum = 0;
+
+	if (fp == NULL) {
+		logMsg("Error reading %s: %s", filename, strerror(errno));
+		return (B_FALSE);
+	}
+	while (parser_get_next_line(fp, &line, &linecap, &linenum) > 0) {
+		char **comps;
+		size_t n;
+		navdb_fix_t *fix;
+
+		if (is_hdr_line(line, linenum))
+			continue;
+		comps = strsplit(line, " ", B_TRUE, &n);
+		if (n < 3) {
+			free_strlist(comps, n);
+			continue;
+		}
+		if (db->num_fixes == cap) {
+			cap = MAX(cap * 2, 1024);
+			db->fixes = safe_realloc(db->fixes,
+			    cap * sizeof (*db->fixes));
+		}
+		fix = &db->fixes[db->num_fixes];
+		memset(fix, 0, sizeof (*fix));
+		fix->pos = GEO_POS2(atof(comps[0]), atof(comps[1]));
+		lacf_strlcpy(fix->ident, comps[2], sizeof (fix->ident));
+		/* apt & region fields only exist in version 1101+ files */
+		if (n >= 5) {
+			lacf_strlcpy(fix->apt, comps[3], sizeof (fix->apt));
+			lacf_strlcpy(fix->region, comps[4],
+			    sizeof (fix->region));
+		}
+		free_strlist(comps, n);
+		if (!is_valid_lat(fix->pos.lat) ||
+		    !is_valid_lon(fix->pos.lon)) {
+			logMsg("%s:%d: invalid fix position", filename,
+			    linenum);
+			continue;
+		}
+		db->num_fixes++;
+	}
+	free(line);
+	fclose(fp);
+
+	return (B_TRUE);
+}
+
+static navaid_type_t
+xp2navaid_type(int xp_type)
+{
+	switch (xp_type) {
+	case 2:
+		return (NAVAID_NDB);
+	case 3:
+		return (NAVAID_VOR);
+	case 4:
+	case 5:
+		return (NAVAID_LOC);
+	case 6:
+		return (NAVAID_GS);
+	case 7:
+	case 8:
+	case 9:
+		return (NAVAID_MKR);
+	case 12:
+	case 13:
+		return (NAVAID_DME);
+	default:
+		return (NAVAID_OTHER);
+	}
+}
+
+static double
+xp2navaid_freq(navaid_type_t type, double freq)
+{
+	switch (type) {
+	case NAVAID_NDB:
+		/* NDB frequencies are in kHz */
+		return (freq * 1000);
+	case NAVAID_VOR:
+	case NAVAID_LOC:
+	case NAVAID_GS:
+	case NAVAID_DME:
+		/* VHF frequencies are in units of 10 kHz */
+		return (freq * 10000);
+	default:
+		return (0);
+	}
+}
+
+static bool_t
+read_navaids(navdb_t *db, const char *filename)
+{
+	FILE *fp = fopen(filename, "r");
+	char *line = NULL;
+	size_t linecap = 0, cap = 0;
+	unsigned linenum = 0;
+
+	if (fp == NULL) {
+		logMsg("Error reading %s: %s", filename, strerror(errno));
+		return (B_FALSE);
+	}
+	while (parser_get_next_line(fp, &line, &linecap, &linenum) > 0) {
+		char **comps;
+		size_t n;
+		navdb_navaid_t *nav;
+
+		if (is_hdr_line(line, linenum))
+			continue;
+		comps = strsplit(line, " ", B_TRUE, &n);
+		if (n < 11) {
+			free_strlist(comps, n);
+			continue;
+		}
+		if (db->num_navaids == cap) {
+			cap = MAX(cap * 2, 1024);
+			db->navaids = safe_realloc(db->navaids,
+			    cap * sizeof (*db->navaids));
+		}
+		nav = &db->navaids[db->num_navaids];
+		memset(nav, 0, sizeof (*nav));
+		nav->xp_type = atoi(comps[0]);
+		nav->type = xp2navaid_type(nav->xp_type);
+		nav->pos = GEO_POS3(atof(comps[1]), atof(comps[2]),
+		    atof(comps[3]));
+		nav->freq = xp2navaid_freq(nav->type, atof(comps[4]));
+		nav->range = atof(comps[5]);
+		nav->param = atof(comps[6]);
+		lacf_strlcpy(nav->ident, comps[7], sizeof (nav->ident));
+		lacf_strlcpy(nav->apt, comps[8], sizeof (nav->apt));
+		lacf_strlcpy(nav->region, comps[9], sizeof (nav->region));
+		for (size_t i = 10; i < n; i++) {
+			if (i > 10) {
+				strncat(nav->name, " ", sizeof (nav->name) -
+				    strlen(nav->name) - 1);
+			}
+			strncat(nav->name, comps[i], sizeof (nav->name) -
+			    strlen(nav->name) - 1);
+		}
+		free_strlist(comps, n);
+		if (!is_valid_lat(nav->pos.lat) ||
+		    !is_valid_lon(nav->pos.lon)) {
+			logMsg("%s:%d: invalid navaid position", filename,
+			    linenum);
+			continue;
+		}
+		db->num_navaids++;
+	}
+	free(line);
+	fclose(fp);
+
+	return (B_TRUE);
+}
+
+static bool_t
+parse_awy_pt(const char *ident, const char *region, const char *type,
+    navdb_awy_pt_t *pt)
+{
+	lacf_strlcpy(pt->ident, ident, sizeof (pt->ident));
+	lacf_strlcpy(pt->region, region, sizeof (pt->region));
+	pt->pos = NULL_GEO_POS2;
+	switch (atoi(type)) {
+	case 11:
+		pt->type = NAVDB_PT_FIX;
+		return (B_TRUE);
+	case 2:
+		pt->type = NAVDB_PT_NDB;
+		return (B_TRUE);
+	case 3:
+		pt->type = NAVDB_PT_VOR;
+		return (B_TRUE);
+	default:
+		return (B_FALSE);
+	}
+}
+
+static bool_t
+read_awys(navdb_t *db, const char *filename)
+{
+	FILE *fp = fopen(filename, "r");
+	char *line = NULL;
+	size_t linecap = 0, cap = 0;
+	unsigned linenum = 0;
+
+	if (fp == NULL) {
+		logMsg("Error reading %s: %s", filename, strerror(errno));
+		return (B_FALSE);
+	}
+	while (parser_get_next_line(fp, &line, &linecap, &linenum) > 0) {
+		char **comps;
+		size_t n;
+		navdb_awy_seg_t *seg;
+
+		if (is_hdr_line(line, linenum))
+			continue;
+		comps = strsplit(line, " ", B_TRUE, &n);
+		if (n < 11) {
+			free_strlist(comps, n);
+			continue;
+		}
+		if (db->num_segs == cap) {
+			cap = MAX(cap * 2, 1024);
+			db->segs = safe_realloc(db->segs,
+			    cap * sizeof (*db->segs));
+		}
+		seg = &db->segs[db->num_segs];
+		memset(seg, 0, sizeof (*seg));
+		if (!parse_awy_pt(comps[0], comps[1], comps[2], &seg->from) ||
+		    !parse_awy_pt(comps[3], comps[4], comps[5], &seg->to)) {
+			logMsg("%s:%d: invalid airway endpoint type",
+			    filename, linenum);
+			free_strlist(comps, n);
+			continue;
+		}
+		/* N = no restriction, F = forward only, B = backward only */
+		seg->fwd_ok = (strcmp(comps[6], "B") != 0);
+		seg->back_ok = (strcmp(comps[6], "F") != 0);
+		seg->high = (atoi(comps[7]) == 2);
+		seg->base_fl = atoi(comps[8]);
+		seg->top_fl = atoi(comps[9]);
+		lacf_strlcpy(seg->name, comps[10], sizeof (seg->name));
+		free_strlist(comps, n);
+		db->num_segs++;
+	}
+	free(line);
+	fclose(fp);
+
+	return (B_TRUE);
+}
+
+static int
+fix_compar(const void *a, const void *b)
+{
+	const navdb_fix_t *fa = a, *fb = b;
+
+	if (fa->pos.lat < fb->pos.lat)
+		return (-1);
+	if (fa->pos.lat > fb->pos.lat)
+		return (1);
+	return (0);
+}
+
+static int
+navaid_compar(const void *a, const void *b)
+{
+	const navdb_navaid_t *na = a, *nb = b;
+
+	if (na->pos.lat < nb->pos.lat)
+		return (-1);
+	if (na->pos.lat > nb->pos.lat)
+		return (1);
+	return (0);
+}
+
+static void
+resolve_awy_pt(navdb_t *db, navdb_awy_pt_t *pt)
+{
+	char key[NAVDB_NAME_LEN];
+	const list_t *list;
+
+	make_key(key, pt->ident);
+	if (pt->type == NAVDB_PT_FIX) {
+		list = htbl_lookup_multi(&db->fix_idx, key);
+		if (list == NULL)
+			return;
+		for (const void *mv = list_head(list); mv != NULL;
+		    mv = list_next(list, mv)) {
+			const navdb_fix_t *fix = HTBL_VALUE_MULTI(mv);
+
+			if (strcmp(fix->region, pt->region) == 0) {
+				pt->pos = fix->pos;
+				return;
+			}
+		}
+	} else {
+		navaid_type_t type = (pt->type == NAVDB_PT_NDB ?
+		    NAVAID_NDB : NAVAID_VOR);
+
+		list = htbl_lookup_multi(&db->navaid_idx, key);
+		if (list == NULL)
+			return;
+		for (const void *mv = list_head(list); mv != NULL;
+		    mv = list_next(list, mv)) {
+			const navdb_navaid_t *nav = HTBL_VALUE_MULTI(mv);
+
+			if (nav->type == type &&
+			    strcmp(nav->region, pt->region) == 0) {
+				pt->pos = GEO3_TO_GEO2(nav->pos);
+				return;
+			}
+		}
+	}
+}
+
+static void
+index_db(navdb_t *db)
+{
+	char key[NAVDB_NAME_LEN];
+
+	qsort(db->fixes, db->num_fixes, sizeof (*db->fixes), fix_compar);
+	qsort(db->navaids, db->num_navaids, sizeof (*db->navaids),
+	    navaid_compar);
+
+	for (size_t i = 0; i < db->num_fixes; i++) {
+		make_key(key, db->fixes[i].ident);
+		htbl_set(&db->fix_idx, key, &db->fixes[i]);
+	}
+	for (size_t i = 0; i < db->num_navaids; i++) {
+		make_key(key, db->navaids[i].ident);
+		htbl_set(&db->navaid_idx, key, &db->navaids[i]);
+	}
+	for (size_t i = 0; i < db->num_segs; i++) {
+		navdb_awy_seg_t *seg = &db->segs[i];
+		char **names;
+		size_t n;
+
+		resolve_awy_pt(db, &seg->from);
+		resolve_awy_pt(db, &seg->to);
+		/*
+		 * Segments shared by multiple airways list all of the
+		 * airway names separated by hyphens (e.g. "J1-J2"), so
+		 * index the segment under each individual name.
+		 */
+		names = strsplit(seg->name, "-", B_TRUE, &n);
+		for (size_t j = 0; j < n; j++) {
+			make_key(key, names[j]);
+			htbl_set(&db->awy_idx, key, seg);
+		}
+		free_strlist(names, n);
+	}
+}
+
+/*
+ * Opens the X-Plane navigation database. The navdata files are first
+ * looked for in the "Custom Data" folder and, if not found there, in
+ * "Resources/default data".
+ *
+ * @param xpdir Path to the X-Plane installation.
+ *
+ * @return The navigation database, or NULL if any of the navdata files
+ *	couldn't be read (the error is logged). Use navdb_close to
+ *	dispose of it.
+ */
+navdb_t *
+navdb_open(const char *xpdir)
+{
+	char *fix_dat = NULL, *nav_dat = NULL, *awy_dat = NULL;
+	FILE *fix_fp, *nav_fp, *awy_fp;
+	navdb_t *db = NULL;
+
+	ASSERT(xpdir != NULL);
+
+	fix_fp = open_nav_file(xpdir, "earth_fix.dat", &fix_dat);
+	nav_fp = open_nav_file(xpdir, "earth_nav.dat", &nav_dat);
+	awy_fp = open_nav_file(xpdir, "earth_awy.dat", &awy_dat);
+	if (fix_fp == NULL || nav_fp == NULL || awy_fp == NULL) {
+		logMsg("Error opening navdata: cannot find earth_fix.dat, "
+		    "earth_nav.dat or earth_awy.dat in %s", xpdir);
+	} else {
+		db = navdb_open_files(fix_dat, nav_dat, awy_dat);
+	}
+	if (fix_fp != NULL)
+		fclose(fix_fp);
+	if (nav_fp != NULL)
+		fclose(nav_fp);
+	if (awy_fp != NULL)
+		fclose(awy_fp);
+	free(fix_dat);
+	free(nav_dat);
+	free(awy_dat);
+
+	return (db);
+}
+
+/*
+ * Same as navdb_open, but lets you specify the exact paths to the
+ * navdata files. Any of the arguments may be NULL, in which case that
+ * part of the database is left empty (e.g. if you only need navaids,
+ * you can avoid the overhead of loading all fixes & airways). Note that
+ * airway endpoints can only be resolved to positions if the fixes and
+ * navaids are loaded as well.
+ */
+navdb_t *
+navdb_open_files(const char *fix_dat, const char *nav_dat, const char *awy_dat)
+{
+	navdb_t *db = safe_calloc(1, sizeof (*db));
+
+	htbl_create(&db->fix_idx, NAVDB_HTBL_SZ, NAVDB_NAME_LEN, B_TRUE);
+	htbl_create(&db->navaid_idx, NAVDB_HTBL_SZ, NAVDB_NAME_LEN, B_TRUE);
+	htbl_create(&db->awy_idx, NAVDB_HTBL_SZ, NAVDB_NAME_LEN, B_TRUE);
+
+	if ((fix_dat != NULL && !read_fixes(db, fix_dat)) ||
+	    (nav_dat != NULL && !read_navaids(db, nav_dat)) ||
+	    (awy_dat != NULL && !read_awys(db, awy_dat))) {
+		navdb_close(db);
+		return (NULL);
+	}
+	index_db(db);
+
+	return (db);
+}
+
+/*
+ * Destroys a navigation database. Any pointers to fixes, navaids or
+ * airway segments obtained from the database become invalid.
+ */
+void
+navdb_close(navdb_t *db)
+{
+	if (db == NULL)
+		return;
+
+	htbl_empty(&db->fix_idx, NULL, NULL);
+	htbl_destroy(&db->fix_idx);
+	htbl_empty(&db->navaid_idx, NULL, NULL);
+	htbl_destroy(&db->navaid_idx);
+	htbl_empty(&db->awy_idx, NULL, NULL);
+	htbl_destroy(&db->awy_idx);
+	free(db->fixes);
+	free(db->navaids);
+	free(db->segs);
+	free(db);
+}
+
+size_t
+navdb_get_num_fixes(const navdb_t *db)
+{
+	ASSERT(db != NULL);
+	return (db->num_fixes);
+}
+
+size_t
+navdb_get_num_navaids(const navdb_t *db)
+{
+	ASSERT(db != NULL);
+	return (db->num_navaids);
+}
+
+size_t
+navdb_get_num_awy_segs(const navdb_t *db)
+{
+	ASSERT(db != NULL);
+	return (db->num_segs);
+}
+
+static size_t
+lookup_multi(const htbl_t *htbl, const char *ident,
+    void (*found_cb)(const void *obj, void *userinfo), void *userinfo)
+{
+	char key[NAVDB_NAME_LEN];
+	const list_t *list;
+
+	make_key(key, ident);
+	list = htbl_lookup_multi(htbl, key);
+	if (list == NULL)
+		return (0);
+	if (found_cb != NULL) {
+		for (const void *mv = list_head(list); mv != NULL;
+		    mv = list_next(list, mv))
+			found_cb(HTBL_VALUE_MULTI(mv), userinfo);
+	}
+	return (list_count(list));
+}
+
+/*
+ * Looks up all fixes with a given identifier. Fix identifiers aren't
+ * globally unique, so there may be multiple matches. Use the `region'
+ * and `pos' fields of the fixes to pick the one you want.
+ *
+ * @param ident The fix identifier to look for.
+ * @param found_cb Optional callback which is called for every fix found.
+ * @param userinfo Optional userinfo argument for `found_cb'.
+ *
+ * @return The number of fixes found.
+ */
+size_t
+navdb_lookup_fix(const navdb_t *db, const char *ident,
+    void (*found_cb)(const navdb_fix_t *fix, void *userinfo), void *userinfo)
+{
+	ASSERT(db != NULL);
+	ASSERT(ident != NULL);
+	return (lookup_multi(&db->fix_idx, ident,
+	    (void (*)(const void *, void *))found_cb, userinfo));
+}
+
+/*
+ * Same as navdb_lookup_fix, but for navaids. Note that co-located
+ * navaids (e.g. a VOR and its associated DME) are separate entries in
+ * the database, so a VOR-DME will be returned twice: once as a
+ * NAVAID_VOR and once as a NAVAID_DME.
+ *
+ * The `param' field of the returned navaids depends on the navaid type:
+ * - NAVAID_VOR: slaved magnetic variation in degrees.
+ * - NAVAID_LOC & NAVAID_MKR: true bearing of the localizer course.
+ * - NAVAID_GS: glideslope angle * 100000 + true bearing of the course.
+ * - NAVAID_DME: DME bias in NM.
+ * - other types: the raw value from earth_nav.dat.
+ */
+size_t
+navdb_lookup_navaid(const navdb_t *db, const char *ident,
+    void (*found_cb)(const navdb_navaid_t *navaid, void *userinfo),
+    void *userinfo)
+{
+	ASSERT(db != NULL);
+	ASSERT(ident != NULL);
+	return (lookup_multi(&db->navaid_idx, ident,
+	    (void (*)(const void *, void *))found_cb, userinfo));
+}
+
+/*
+ * Looks up all segments of an airway. The segments are returned in no
+ * particular order. Segments which are shared by multiple airways are
+ * returned for each of the airways they belong to.
+ *
+ * @return The number of airway segments found.
+ */
+size_t
+navdb_lookup_awy(const navdb_t *db, const char *name,
+    void (*found_cb)(const navdb_awy_seg_t *seg, void *userinfo),
+    void *userinfo)
+{
+	ASSERT(db != NULL);
+	ASSERT(name != NULL);
+	return (lookup_multi(&db->awy_idx, name,
+	    (void (*)(const void *, void *))found_cb, userinfo));
+}
+
+/*
+ * Returns the index of the first element in a latitude-sorted array
+ * with a latitude of at least `min_lat'.
+ */
+#define	LAT_LOWER_BOUND(arr, num, min_lat, res) \
+	do { \
+		size_t lo = 0, hi = (num); \
+		while (lo < hi) { \
+			size_t mid = lo + (hi - lo) / 2; \
+			if ((arr)[mid].pos.lat < (min_lat)) \
+				lo = mid + 1; \
+			else \
+				hi = mid; \
+		} \
+		(res) = lo; \
+	} while (0)
+
+/*
+ * Finds all fixes within a given radius around a point.
+ *
+ * @param pos The center point of the search.
+ * @param radius Search radius in meters.
+ * @param found_cb Optional callback which is called for every fix found.
+ * @param userinfo Optional userinfo argument for `found_cb'.
+ *
+ * @return The number of fixes found.
+ */
+size_t
+navdb_find_fixes(const navdb_t *db, geo_pos2_t pos, double radius,
+    void (*found_cb)(const navdb_fix_t *fix, void *userinfo), void *userinfo)
+{
+	double dlat = RAD2DEG(radius / EARTH_MSL);
+	size_t i, num = 0;
+
+	ASSERT(db != NULL);
+	ASSERT(!IS_NULL_GEO_POS2(pos));
+	ASSERT3F(radius, >=, 0);
+
+	LAT_LOWER_BOUND(db->fixes, db->num_fixes, pos.lat - dlat, i);
+	for (; i < db->num_fixes && db->fixes[i].pos.lat <= pos.lat + dlat;
+	    i++) {
+		if (gc_distance(pos, db->fixes[i].pos) <= radius) {
+			if (found_cb != NULL)
+				found_cb(&db->fixes[i], userinfo);
+			num++;
+		}
+	}
+
+	return (num);
+}
+
+/*
+ * Same as navdb_find_fixes, but for navaids.
+ */
+size_t
+navdb_find_navaids(const navdb_t *db, geo_pos2_t pos, double radius,
+    void (*found_cb)(const navdb_navaid_t *navaid, void *userinfo),
+    void *userinfo)
+{
+	double dlat = RAD2DEG(radius / EARTH_MSL);
+	size_t i, num = 0;
+
+	ASSERT(db != NULL);
+	ASSERT(!IS_NULL_GEO_POS2(pos));
+	ASSERT3F(radius, >=, 0);
+
+	LAT_LOWER_BOUND(db->navaids, db->num_navaids, pos.lat - dlat, i);
+	for (; i < db->num_navaids &&
+	    db->navaids[i].pos.lat <= pos.lat + dlat; i++) {
+		if (gc_distance(pos, GEO3_TO_GEO2(db->navaids[i].pos)) <=
+		    radius) {
+			if (found_cb != NULL)
+				found_cb(&db->navaids[i], userinfo);
+			num++;
+		}
+	}
+
+	return (num);
+}
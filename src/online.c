@@ -0,0 +1,777 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <math.h>
+#include <stddef.h>
+#include <string.h>
+
+#include <curl/curl.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/helpers.h>
+#include <acfutils/log.h>
+#include <acfutils/online.h>
+#include <acfutils/perf.h>
+#include <acfutils/safe_alloc.h>
+#include <acfutils/thread.h>
+#include <acfutils/time.h>
+#include <acfutils/worker.h>
+
+#include "chart_prov_common.h"
+#include "jsmn/jsmn.h"
+#include "jsmn/jsmn_path.h"
+
+#define	VATSIM_DATA_URL		"https://data.vatsim.net/v3/vatsim-data.json"
+#define	VATSIM_XCVR_URL		\
+	"https://data.vatsim.net/v3/transceivers-data.json"
+#define	IVAO_DATA_URL		"https://api.ivao.aero/v2/tracker/whazzup"
+
+/*
+ * Both networks only update their feeds every 15 seconds, so fetching
+ * more often than that is pointless and just puts load on their servers.
+ */
+#define	MIN_REFRESH_INTVAL	15		/* seconds */
+#define	DFL_REFRESH_INTVAL	60		/* seconds */
+#define	DL_TIMEOUT		30L		/* seconds */
+#define	REALLOC_STEP		(1 << 20)	/* bytes */
+#define	MAX_DL_SIZE		(64 << 20)	/* bytes */
+
+typedef struct {
+	uint8_t		*buf;
+	size_t		bufcap;
+	size_t		bufsz;
+} dl_info_t;
+
+typedef struct {
+	char		callsign[16];
+	geo_pos2_t	pos;
+} xcvr_t;
+
+struct online_s {
+	online_net_t		net;
+	char			*cainfo;
+	worker_t		wk;
+
+	mutex_t			lock;
+	/* protected by lock */
+	online_station_t	*stations;
+	size_t			num_stations;
+	time_t			update_time;
+	time_t			fetch_time;
+};
+
+static size_t
+dl_write(char *ptr, size_t size, size_t nmemb, void *userdata)
+{
+	dl_info_t *dl_info;
+	size_t bytes = size * nmemb;
+
+	ASSERT(userdata != NULL);
+	dl_info = userdata;
+
+	if (dl_info->bufcap < dl_info->bufsz + bytes + 1) {
+		do {
+			dl_info->bufcap += REALLOC_STEP;
+		} while (dl_info->bufcap < dl_info->bufsz + bytes + 1);
+		if (dl_info->bufcap > MAX_DL_SIZE) {
+			logMsg("Error downloading network status: too much "
+			    "data received (%ld bytes)", (long)dl_info->bufcap);
+			return (0);
+		}
+		dl_info->buf = safe_realloc(dl_info->buf, dl_info->bufcap);
+	}
+	memcpy(&dl_info->buf[dl_info->bufsz], ptr, bytes);
+	dl_info->bufsz += bytes;
+	/* keep the buffer NUL-terminated for the JSON parser */
+	dl_info->buf[dl_info->bufsz] = 0;
+
+	return (bytes);
+}
+
+/*
+ * Downloads `url' and returns its contents as a NUL-terminated string,
+ * or NULL on error (the error is logged).
+ */
+static char *
+download(const char *url, const char *cainfo, size_t *len)
+{
+	CURL *curl;
+	CURLcode res;
+	dl_info_t dl_info = {};
+	long code = 0;
+
+	curl = curl_easy_init();
+	VERIFY(curl != NULL);
+	chart_setup_curl(curl, cainfo);
+	curl_easy_setopt(curl, CURLOPT_URL, url);
+	curl_easy_setopt(curl, CURLOPT_WRITEFUNCTION, dl_write);
+	curl_easy_setopt(curl, CURLOPT_WRITEDATA, &dl_info);
+	curl_easy_setopt(curl, CURLOPT_TIMEOUT, DL_TIMEOUT);
+	curl_easy_setopt(curl, CURLOPT_ACCEPT_ENCODING, "");
+
+	res = curl_easy_perform(curl);
+	if (res == CURLE_OK)
+		curl_easy_getinfo(curl, CURLINFO_RESPONSE_CODE, &code);
+	curl_easy_cleanup(curl);
+
+	if (res != CURLE_OK) {
+		logMsg("Error downloading %s: %s", url,
+		    curl_easy_strerror(res));
+	} else if (code != 200) {
+		logMsg("Error downloading %s: server responded with error "
+		    "code %ld", url, code);
+	} else if (dl_info.bufsz == 0) {
+		logMsg("Error downloading %s: empty response", url);
+	} else {
+		*len = dl_info.bufsz;
+		return ((char *)dl_info.buf);
+	}
+	free(dl_info.buf);
+
+	return (NULL);
+}
+
+/*
+ * Tokenizes a JSON document. Returns the token array and fills in
+ * `n_toks', or returns NULL on parse error (the error is logged).
+ */
+static jsmntok_t *
+json_parse(const char *json, size_t len, int *n_toks, const char *url)
+{
+	jsmn_parser parser;
+	jsmntok_t *toks;
+	int n;
+
+	jsmn_init(&parser);
+	n = jsmn_parse(&parser, json, len, NULL, 0);
+	if (n <= 0) {
+		logMsg("Error parsing %s: invalid JSON data", url);
+		return (NULL);
+	}
+	toks = safe_calloc(n, sizeof (*toks));
+	jsmn_init(&parser);
+	*n_toks = jsmn_parse(&parser, json, len, toks, n);
+	if (*n_toks <= 0) {
+		logMsg("Error parsing %s: invalid JSON data", url);
+		free(toks);
+		return (NULL);
+	}
+
+	return (toks);
+}
+
+/*
+ * Looks up the value of member `key' in the JSON object at token index
+ * `obj'. Returns the token index of the value, or -1 if not found.
+ */
+static int
+json_obj_get(const char *json, const jsmntok_t *toks, int n_toks, int obj,
+    const char *key)
+{
+	int len = strlen(key);
+
+	if (obj < 0 || obj >= n_toks || toks[obj].type != JSMN_OBJECT)
+		return (-1);
+	for (int i = 0, j = obj + 1; i < toks[obj].size && j + 1 < n_toks;
+	    i++) {
+		if (toks[j].type == JSMN_STRING &&
+		    toks[j].end - toks[j].start == len &&
+		    strncmp(&json[toks[j].start], key, len) == 0)
+			return (j + 1);
+		j += 1 + jsmn_count_toks_r(toks, n_toks, j + 1);
+	}
+
+	return (-1);
+}
+
+static bool_t
+json_is_null(const char *json, const jsmntok_t *tok)
+{
+	return (tok->type == JSMN_PRIMITIVE && json[tok->start] == 'n');
+}
+
+static bool_t
+json_get_str(const char *json, const jsmntok_t *toks, int n_toks, int obj,
+    const char *key, char *buf, size_t cap)
+{
+	int i = json_obj_get(json, toks, n_toks, obj, key);
+
+	if (i < 0 || json_is_null(json, &toks[i]) ||
+	    toks[i].type == JSMN_OBJECT || toks[i].type == JSMN_ARRAY) {
+		buf[0] = '\0';
+		return (B_FALSE);
+	}
+	jsmn_get_tok_data(json, &toks[i], buf, cap);
+	/* jsmn_unescape doesn't handle escaped quotes */
+	if (toks[i].type == JSMN_STRING)
+		jsmn_unescape_string(buf, "\\\"", '"');
+
+	return (B_TRUE);
+}
+
+static double
+json_get_num(const char *json, const jsmntok_t *toks, int n_toks, int obj,
+    const char *key, double dfl)
+{
+	char buf[32];
+
+	if (!json_get_str(json, toks, n_toks, obj, key, buf, sizeof (buf)) ||
+	    buf[0] == '\0')
+		return (dfl);
+	return (atof(buf));
+}
+
+/*
+ * Joins a JSON array of strings into a single '\n'-separated string.
+ * Returns NULL if the array is missing or empty.
+ */
+static char *
+json_get_lines(const char *json, const jsmntok_t *toks, int n_toks, int obj,
+    const char *key)
+{
+	int arr = json_obj_get(json, toks, n_toks, obj, key);
+	char *str = NULL;
+	size_t sz = 0;
+
+	if (arr < 0 || toks[arr].type != JSMN_ARRAY)
+		return (NULL);
+	for (int i = 0, j = arr + 1; i < toks[arr].size && j < n_toks; i++) {
+		if (toks[j].type == JSMN_STRING) {
+			char *line = jsmn_strdup_tok_data(json, &toks[j]);
+
+			jsmn_unescape_string(line, "\\\"", '"');
+
+			append_format(&str, &sz, "%s%s", sz != 0 ? "\n" : "",
+			    line);
+			free(line);
+		}
+		j += jsmn_count_toks_r(toks, n_toks, j);
+	}
+
+	return (str);
+}
+
+/*
+ * Determines the facility type from the callsign suffix (e.g. "EDDF_TWR").
+ */
+static online_fac_t
+callsign2fac(const char *callsign)
+{
+	const char *suffix = strrchr(callsign, '_');
+
+	if (suffix == NULL)
+		return (ONLINE_FAC_OBS);
+	suffix++;
+	if (strcmp(suffix, "ATIS") == 0)
+		return (ONLINE_FAC_ATIS);
+	if (strcmp(suffix, "DEL") == 0)
+		return (ONLINE_FAC_DEL);
+	if (strcmp(suffix, "GND") == 0 || strcmp(suffix, "RMP") == 0)
+		return (ONLINE_FAC_GND);
+	if (strcmp(suffix, "TWR") == 0)
+		return (ONLINE_FAC_TWR);
+	if (strcmp(suffix, "APP") == 0 || strcmp(suffix, "DEP") == 0)
+		return (ONLINE_FAC_APP);
+	if (strcmp(suffix, "CTR") == 0)
+		return (ONLINE_FAC_CTR);
+	if (strcmp(suffix, "FSS") == 0)
+		return (ONLINE_FAC_FSS);
+	return (ONLINE_FAC_OBS);
+}
+
+/*
+ * Default visibility ranges for when the network doesn't provide one.
+ */
+static double
+fac2range(online_fac_t fac)
+{
+	switch (fac) {
+	case ONLINE_FAC_DEL:
+	case ONLINE_FAC_GND:
+		return (NM2MET(20));
+	case ONLINE_FAC_TWR:
+	case ONLINE_FAC_ATIS:
+		return (NM2MET(50));
+	case ONLINE_FAC_APP:
+		return (NM2MET(150));
+	case ONLINE_FAC_CTR:
+		return (NM2MET(400));
+	case ONLINE_FAC_FSS:
+		return (NM2MET(1500));
+	default:
+		return (0);
+	}
+}
+
+static void
+add_station(online_station_t **stations, size_t *num_stations,
+    const online_station_t *st)
+{
+	*stations = safe_realloc(*stations,
+	    (*num_stations + 1) * sizeof (**stations));
+	(*stations)[*num_stations] = *st;
+	(*num_stations)++;
+}
+
+static int
+xcvr_compar(const void *a, const void *b)
+{
+	const xcvr_t *xa = a, *xb = b;
+	return (strcmp(xa->callsign, xb->callsign));
+}
+
+/*
+ * Parses the VATSIM transceivers feed, which is the only place where
+ * VATSIM publishes the positions of its ATC stations. The returned
+ * array is sorted by callsign.
+ */
+static xcvr_t *
+vatsim_parse_xcvrs(const char *json, size_t len, size_t *num_xcvrs)
+{
+	jsmntok_t *toks;
+	int n_toks;
+	xcvr_t *xcvrs;
+	size_t n = 0;
+
+	toks = json_parse(json, len, &n_toks, VATSIM_XCVR_URL);
+	if (toks == NULL)
+		return (NULL);
+	if (toks[0].type != JSMN_ARRAY) {
+		logMsg("Error parsing %s: unexpected document structure",
+		    VATSIM_XCVR_URL);
+		free(toks);
+		return (NULL);
+	}
+	xcvrs = safe_calloc(MAX(toks[0].size, 1), sizeof (*xcvrs));
+	for (int i = 0, j = 1; i < toks[0].size && j < n_toks; i++) {
+		int arr = json_obj_get(json, toks, n_toks, j, "transceivers");
+
+		/* A station's first transceiver is good enough for us */
+		if (arr >= 0 && toks[arr].type == JSMN_ARRAY &&
+		    toks[arr].size > 0) {
+			xcvr_t *x = &xcvrs[n];
+
+			json_get_str(json, toks, n_toks, j, "callsign",
+			    x->callsign, sizeof (x->callsign));
+			x->pos = GEO_POS2(json_get_num(json, toks, n_toks,
+			    arr + 1, "latDeg", NAN), json_get_num(json, toks,
+			    n_toks, arr + 1, "lonDeg", NAN));
+			if (is_valid_lat(x->pos.lat) &&
+			    is_valid_lon(x->pos.lon))
+				n++;
+		}
+		j += jsmn_count_toks_r(toks, n_toks, j);
+	}
+	free(toks);
+	qsort(xcvrs, n, sizeof (*xcvrs), xcvr_compar);
+	*num_xcvrs = n;
+
+	return (xcvrs);
+}
+
+static void
+vatsim_parse_list(const char *json, const jsmntok_t *toks, int n_toks,
+    const char *list, const xcvr_t *xcvrs, size_t num_xcvrs,
+    online_station_t **stations, size_t *num_stations)
+{
+	int arr = json_obj_get(json, toks, n_toks, 0, list);
+
+	if (arr < 0 || toks[arr].type != JSMN_ARRAY)
+		return;
+	for (int i = 0, j = arr + 1; i < toks[arr].size && j < n_toks; i++) {
+		online_station_t st = { .pos = NULL_GEO_POS2 };
+		char code[4];
+		const xcvr_t *x;
+
+		json_get_str(json, toks, n_toks, j, "callsign", st.callsign,
+		    sizeof (st.callsign));
+		json_get_str(json, toks, n_toks, j, "name", st.name,
+		    sizeof (st.name));
+		st.freq = round(json_get_num(json, toks, n_toks, j,
+		    "frequency", 0) * 1000) * 1000;
+		st.facility = callsign2fac(st.callsign);
+		st.range = NM2MET(json_get_num(json, toks, n_toks, j,
+		    "visual_range", 0));
+		if (st.range == 0)
+			st.range = fac2range(st.facility);
+		if (json_get_str(json, toks, n_toks, j, "atis_code", code,
+		    sizeof (code)))
+			st.atis_code = code[0];
+		st.atis_text = json_get_lines(json, toks, n_toks, j,
+		    "text_atis");
+		x = bsearch(st.callsign, xcvrs, num_xcvrs, sizeof (*xcvrs),
+		    xcvr_compar);
+		if (x != NULL)
+			st.pos = x->pos;
+		/* 199.998 MHz is the "not on frequency" placeholder */
+		if (st.callsign[0] != '\0' && st.facility != ONLINE_FAC_OBS &&
+		    st.freq != 199998000)
+			add_station(stations, num_stations, &st);
+		else
+			free(st.atis_text);
+		j += jsmn_count_toks_r(toks, n_toks, j);
+	}
+}
+
+static bool_t
+vatsim_fetch(const char *cainfo, online_station_t **stations,
+    size_t *num_stations)
+{
+	char *json;
+	size_t len;
+	jsmntok_t *toks;
+	int n_toks;
+	xcvr_t *xcvrs;
+	size_t num_xcvrs = 0;
+
+	json = download(VATSIM_XCVR_URL, cainfo, &len);
+	if (json == NULL)
+		return (B_FALSE);
+	xcvrs = vatsim_parse_xcvrs(json, len, &num_xcvrs);
+	free(json);
+	if (xcvrs == NULL)
+		return (B_FALSE);
+
+	json = download(VATSIM_DATA_URL, cainfo, &len);
+	if (json == NULL) {
+		free(xcvrs);
+		return (B_FALSE);
+	}
+	toks = json_parse(json, len, &n_toks, VATSIM_DATA_URL);
+	if (toks == NULL) {
+		free(json);
+		free(xcvrs);
+		return (B_FALSE);
+	}
+	vatsim_parse_list(json, toks, n_toks, "controllers", xcvrs,
+	    num_xcvrs, stations, num_stations);
+	vatsim_parse_list(json, toks, n_toks, "atis", xcvrs, num_xcvrs,
+	    stations, num_stations);
+
+	free(toks);
+	free(json);
+	free(xcvrs);
+
+	return (B_TRUE);
+}
+
+static bool_t
+ivao_fetch(const char *cainfo, online_station_t **stations,
+    size_t *num_stations)
+{
+	char *json;
+	size_t len;
+	jsmntok_t *toks;
+	int n_toks, clients, arr;
+
+	json = download(IVAO_DATA_URL, cainfo, &len);
+	if (json == NULL)
+		return (B_FALSE);
+	toks = json_parse(json, len, &n_toks, IVAO_DATA_URL);
+	if (toks == NULL) {
+		free(json);
+		return (B_FALSE);
+	}
+	clients = json_obj_get(json, toks, n_toks, 0, "clients");
+	arr = json_obj_get(json, toks, n_toks, clients, "atcs");
+	if (arr < 0 || toks[arr].type != JSMN_ARRAY) {
+		logMsg("Error parsing %s: unexpected document structure",
+		    IVAO_DATA_URL);
+		free(toks);
+		free(json);
+		return (B_FALSE);
+	}
+	for (int i = 0, j = arr + 1; i < toks[arr].size && j < n_toks; i++) {
+		online_station_t st = { .pos = NULL_GEO_POS2 };
+		int sess = json_obj_get(json, toks, n_toks, j, "atcSession");
+		int track = json_obj_get(json, toks, n_toks, j, "lastTrack");
+		int atis = json_obj_get(json, toks, n_toks, j, "atis");
+		char code[4];
+
+		json_get_str(json, toks, n_toks, j, "callsign", st.callsign,
+		    sizeof (st.callsign));
+		st.facility = callsign2fac(st.callsign);
+		st.range = fac2range(st.facility);
+		st.freq = round(json_get_num(json, toks, n_toks, sess,
+		    "frequency", 0) * 1000) * 1000;
+		st.pos = GEO_POS2(json_get_num(json, toks, n_toks, track,
+		    "latitude", NAN), json_get_num(json, toks, n_toks, track,
+		    "longitude", NAN));
+		if (!is_valid_lat(st.pos.lat) || !is_valid_lon(st.pos.lon))
+			st.pos = NULL_GEO_POS2;
+		st.atis_text = json_get_lines(json, toks, n_toks, atis,
+		    "lines");
+		if (json_get_str(json, toks, n_toks, atis, "revision", code,
+		    sizeof (code)))
+			st.atis_code = code[0];
+		if (st.callsign[0] != '\0' && st.facility != ONLINE_FAC_OBS)
+			add_station(stations, num_stations, &st);
+		else
+			free(st.atis_text);
+		j += jsmn_count_toks_r(toks, n_toks, j);
+	}
+	free(toks);
+	free(json);
+
+	return (B_TRUE);
+}
+
+static bool_t
+online_worker(void *userinfo)
+{
+	online_t *ol = userinfo;
+	online_station_t *stations = NULL, *old_stations;
+	size_t num_stations = 0, old_num_stations;
+	time_t now = time(NULL);
+	bool_t ok;
+
+	ASSERT(ol != NULL);
+
+	/* Rate-limit early refreshes requested via online_refresh */
+	mutex_enter(&ol->lock);
+	if (ol->fetch_time != 0 && now - ol->fetch_time < MIN_REFRESH_INTVAL) {
+		mutex_exit(&ol->lock);
+		return (B_TRUE);
+	}
+	ol->fetch_time = now;
+	mutex_exit(&ol->lock);
+
+	if (ol->net == ONLINE_NET_VATSIM)
+		ok = vatsim_fetch(ol->cainfo, &stations, &num_stations);
+	else
+		ok = ivao_fetch(ol->cainfo, &stations, &num_stations);
+	/*
+	 * On failure we simply keep serving the previously fetched data
+	 * until the next refresh attempt.
+	 */
+	if (!ok) {
+		online_free_stations(stations, num_stations);
+		return (B_TRUE);
+	}
+
+	mutex_enter(&ol->lock);
+	old_stations = ol->stations;
+	old_num_stations = ol->num_stations;
+	ol->stations = stations;
+	ol->num_stations = num_stations;
+	ol->update_time = now;
+	mutex_exit(&ol->lock);
+
+	online_free_stations(old_stations, old_num_stations);
+
+	return (B_TRUE);
+}
+
+/*
+ * Creates a new online network status fetcher. The fetcher immediately
+ * starts downloading the network's status feed in a background thread
+ * and periodically refreshes it (every 60 seconds by default, see
+ * online_set_refresh_intval). All query functions operate on the most
+ * recently downloaded data, so they never block on network access and
+ * can be called from the main sim thread. Multiple consumers should
+ * share a single fetcher to avoid hitting the network feeds more than
+ * necessary.
+ *
+ * @param net The network to fetch (VATSIM or IVAO).
+ * @param cainfo Optional path to a CA certificate bundle file to use for
+ *	verifying the server's TLS certificate. Pass NULL to use the
+ *	default bundle of the platform.
+ *
+ * @return The fetcher. Use online_free to dispose of it.
+ */
+online_t *
+online_alloc(online_net_t net, const char *cainfo)
+{
+	online_t *ol = safe_calloc(1, sizeof (*ol));
+
+	ASSERT(net == ONLINE_NET_VATSIM || net == ONLINE_NET_IVAO);
+	ol->net = net;
+	if (cainfo != NULL)
+		ol->cainfo = safe_strdup(cainfo);
+	mutex_init(&ol->lock);
+	worker_init(&ol->wk, online_worker, SEC2USEC(DFL_REFRESH_INTVAL), ol,
+	    net == ONLINE_NET_VATSIM ? "online_vatsim" : "online_ivao");
+
+	return (ol);
+}
+
+void
+online_free(online_t *ol)
+{
+	if (ol == NULL)
+		return;
+	worker_fini(&ol->wk);
+	online_free_stations(ol->stations, ol->num_stations);
+	mutex_destroy(&ol->lock);
+	free(ol->cainfo);
+	free(ol);
+}
+
+/*
+ * Sets the interval at which the network status feed is refreshed.
+ * Intervals shorter than 15 seconds are clamped to 15 seconds, since
+ * neither network updates its feed any faster than that.
+ */
+void
+online_set_refresh_intval(online_t *ol, unsigned secs)
+{
+	ASSERT(ol != NULL);
+	secs = MAX(secs, MIN_REFRESH_INTVAL);
+	worker_set_interval_nowake(&ol->wk, SEC2USEC(secs));
+}
+
+/*
+ * Requests an immediate refresh of the network status feed. The refresh
+ * happens asynchronously. If the previous download happened less than
+ * 15 seconds ago, the request is ignored.
+ */
+void
+online_refresh(online_t *ol)
+{
+	ASSERT(ol != NULL);
+	worker_wake_up(&ol->wk);
+}
+
+/*
+ * Returns the time when the network status data was last successfully
+ * updated, or 0 if no data has been downloaded yet.
+ */
+time_t
+online_get_update_time(online_t *ol)
+{
+	time_t t;
+
+	ASSERT(ol != NULL);
+	mutex_enter(&ol->lock);
+	t = ol->update_time;
+	mutex_exit(&ol->lock);
+
+	return (t);
+}
+
+static void
+station_copy(online_station_t *dst, const online_station_t *src)
+{
+	*dst = *src;
+	if (src->atis_text != NULL)
+		dst->atis_text = safe_strdup(src->atis_text);
+}
+
+static int
+station_dist_compar(const void *a, const void *b)
+{
+	const online_station_t *sa = a, *sb = b;
+
+	if (sa->dist < sb->dist)
+		return (-1);
+	if (sa->dist > sb->dist)
+		return (1);
+	return (0);
+}
+
+/*
+ * Returns all ATC stations (including ATIS stations) within `radius'
+ * meters of `pos', sorted by increasing distance. The `dist' field of
+ * each returned station is set to its distance from `pos'. Stations
+ * whose position isn't known are never returned.
+ *
+ * @param stations Output array of stations. Must be freed by the caller
+ *	using online_free_stations.
+ *
+ * @return The number of stations returned in `stations'.
+ */
+size_t
+online_get_stations(online_t *ol, geo_pos2_t pos, double radius,
+    online_station_t **stations)
+{
+	size_t n = 0;
+
+	ASSERT(ol != NULL);
+	ASSERT(!IS_NULL_GEO_POS2(pos));
+	ASSERT(stations != NULL);
+
+	*stations = NULL;
+	mutex_enter(&ol->lock);
+	for (size_t i = 0; i < ol->num_stations; i++) {
+		const online_station_t *st = &ol->stations[i];
+		double dist;
+
+		if (IS_NULL_GEO_POS2(st->pos))
+			continue;
+		dist = gc_distance(pos, st->pos);
+		if (dist > radius)
+			continue;
+		*stations = safe_realloc(*stations, (n + 1) *
+		    sizeof (**stations));
+		station_copy(&(*stations)[n], st);
+		(*stations)[n].dist = dist;
+		n++;
+	}
+	mutex_exit(&ol->lock);
+	if (n != 0)
+		qsort(*stations, n, sizeof (**stations), station_dist_compar);
+
+	return (n);
+}
+
+/*
+ * Looks up the ATIS station of an airport (e.g. "EDDF_ATIS"). Airports
+ * with separate arrival & departure ATIS stations (e.g. "EDDF_A_ATIS")
+ * are matched too, in which case the first one found is returned.
+ *
+ * @param station Output station information. The `atis_text' field is
+ *	allocated and must be freed by the caller using lacf_free.
+ *
+ * @return B_TRUE if the station is online, B_FALSE otherwise.
+ */
+bool_t
+online_get_atis(online_t *ol, const char *icao, online_station_t *station)
+{
+	size_t len;
+	bool_t found = B_FALSE;
+
+	ASSERT(ol != NULL);
+	ASSERT(icao != NULL);
+	ASSERT(station != NULL);
+
+	len = strlen(icao);
+	mutex_enter(&ol->lock);
+	for (size_t i = 0; i < ol->num_stations; i++) {
+		const online_station_t *st = &ol->stations[i];
+
+		if (st->facility == ONLINE_FAC_ATIS &&
+		    strncmp(st->callsign, icao, len) == 0 &&
+		    st->callsign[len] == '_') {
+			station_copy(station, st);
+			station->dist = 0;
+			found = B_TRUE;
+			break;
+		}
+	}
+	mutex_exit(&ol->lock);
+
+	return (found);
+}
+
+void
+online_free_stations(online_station_t *stations, size_t n)
+{
+	for (size_t i = 0; i < n; i++)
+		free(stations[i].atis_text);
+	free(stations);
+}
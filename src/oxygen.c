@@ -0,0 +1,417 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <math.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/math.h>
+#include <acfutils/oxygen.h>
+#include <acfutils/perf.h>
+#include <acfutils/safe_alloc.h>
+
+#define	MINUTE_VOL		15	/* crew breathing, l/min at cabin alt */
+#define	EMER_FLOW_MULT		1.5	/* positive pressure mask leakage */
+#define	CREW_LOW_FRAC		0.25	/* crew bottle low quantity fraction */
+#define	HYPOXIA_ALT		10000	/* feet */
+#define	RAPID_DECOMP_RATE	5000	/* ft/min */
+#define	CABIN_RATE_LAG		2	/* seconds */
+#define	RECOVERY_TIME		30	/* seconds, full recovery on oxygen */
+#define	RECOVERED_RESERVE	0.2
+#define	DFL_PAX_DEPLOY_ALT	14000	/* feet */
+
+typedef struct {
+	oxy_mask_t	mask;
+	double		flow;		/* l/min NTP */
+	double		reserve;	/* 1 = fully alert, 0 = unconscious */
+	bool_t		conscious;
+} crew_t;
+
+struct oxy_s {
+	unsigned	num_crew;
+	crew_t		crew[OXY_MAX_CREW];
+	double		crew_cap;
+	double		crew_qty;
+	bool_t		crew_low;
+
+	double		pax_dur;
+	double		pax_rem;
+	double		pax_deploy_alt;
+	bool_t		pax_deployed;
+
+	double		cabin_alt;
+	double		cabin_rate;
+	bool_t		rapid;
+
+	oxy_cb_t	cb;
+	void		*userinfo;
+};
+
+/*
+ * Approximate times of useful consciousness at moderate activity, per
+ * FAA AC 61-107. Interpolation is done on the logarithm of the time.
+ */
+static const vect2_t tuc_curve[] = {
+    { 15000, 3600 },
+    { 18000, 1500 },
+    { 22000, 600 },
+    { 25000, 240 },
+    { 28000, 165 },
+    { 30000, 90 },
+    { 35000, 45 },
+    { 40000, 18 },
+    { 43000, 10 },
+    { 50000, 10 },
+    NULL_VECT2
+};
+
+/*
+ * Estimates the time of useful consciousness, i.e. how long an
+ * unprotected person remains capable of taking corrective action after
+ * being exposed to a given cabin altitude.
+ *
+ * @param cabin_alt Cabin pressure altitude in feet.
+ * @param rapid Set to B_TRUE if the exposure is the result of a rapid
+ *	decompression, which roughly halves the time.
+ *
+ * @return The time of useful consciousness in seconds, or INFINITY
+ *	below 15,000 feet, where hypoxia isn't considered incapacitating.
+ */
+double
+oxy_tuc(double cabin_alt, bool_t rapid)
+{
+	double tuc;
+	vect2_t log_curve[ARRAY_NUM_ELEM(tuc_curve)];
+
+	ASSERT(!isnan(cabin_alt));
+	if (cabin_alt < tuc_curve[0].x)
+		return (INFINITY);
+	for (size_t i = 0; i < ARRAY_NUM_ELEM(tuc_curve); i++) {
+		log_curve[i] = (IS_NULL_VECT(tuc_curve[i]) ? NULL_VECT2 :
+		    VECT2(tuc_curve[i].x, log(tuc_curve[i].y)));
+	}
+	cabin_alt = MIN(cabin_alt, tuc_curve[ARRAY_NUM_ELEM(tuc_curve) - 2].x);
+	tuc = exp(fx_lin_multi(cabin_alt, log_curve, B_FALSE));
+
+	return (rapid ? tuc / 2 : tuc);
+}
+
+/*
+ * Creates a new oxygen system model, consisting of a gaseous crew oxygen
+ * bottle feeding `num_crew' crew masks and chemical passenger oxygen
+ * generators.
+ *
+ * Typical usage:
+ *
+ *	oxy = oxy_alloc(3, 3256, 15 * 60, oxy_event, NULL);
+ *	...on every frame:
+ *	oxy_update(oxy, cabin_alt, d_t);
+ *	...when a crew member dons their mask:
+ *	oxy_set_crew_mask(oxy, 0, OXY_MASK_100);
+ *
+ * The crew oxygen consumption is computed from the cabin altitude and
+ * each crew member's mask setting. OXY_MASK_NORMAL mixes just enough
+ * oxygen into cabin air to maintain a sea level oxygen partial pressure,
+ * which becomes 100% oxygen at approximately 34,000 feet.
+ *
+ * Each crew member not breathing supplemental oxygen (mask off or crew
+ * bottle empty) above 15,000 feet accumulates hypoxic exposure, using
+ * up their time of useful consciousness (see oxy_tuc). Once that runs
+ * out, the crew member loses consciousness (OXY_EVT_CREW_INCAP). Once
+ * back on oxygen or below 10,000 feet, they recover within 30 seconds
+ * (OXY_EVT_CREW_RECOVERED). A rapid cabin altitude climb of more than
+ * 5,000 ft/min above 10,000 feet is treated as a rapid decompression,
+ * halving the time of useful consciousness until the cabin descends
+ * below 10,000 feet again.
+ *
+ * The passenger masks drop automatically when the cabin altitude climbs
+ * above 14,000 feet (see oxy_set_pax_deploy_alt), or when commanded by
+ * oxy_pax_deploy. The generators then supply oxygen for `pax_dur' and
+ * can't be shut off.
+ *
+ * @param num_crew Number of crew masks, up to OXY_MAX_CREW.
+ * @param crew_cap Crew oxygen bottle capacity in liters of oxygen at
+ *	normal temperature and pressure. The bottle starts out full.
+ * @param pax_dur Passenger oxygen generator duration in seconds.
+ * @param cb Event callback, called from inside of oxy_update and
+ *	oxy_pax_deploy.
+ */
+oxy_t *
+oxy_alloc(unsigned num_crew, double crew_cap, double pax_dur, oxy_cb_t cb,
+    void *userinfo)
+{
+	oxy_t *oxy = safe_calloc(1, sizeof (*oxy));
+
+	ASSERT3U(num_crew, <=, OXY_MAX_CREW);
+	ASSERT3F(crew_cap, >=, 0);
+	ASSERT3F(pax_dur, >=, 0);
+	ASSERT(cb != NULL);
+
+	oxy->num_crew = num_crew;
+	for (unsigned i = 0; i < num_crew; i++) {
+		oxy->crew[i].reserve = 1;
+		oxy->crew[i].conscious = B_TRUE;
+	}
+	oxy->crew_cap = crew_cap;
+	oxy->crew_qty = crew_cap;
+	oxy->pax_dur = pax_dur;
+	oxy->pax_rem = pax_dur;
+	oxy->pax_deploy_alt = DFL_PAX_DEPLOY_ALT;
+	oxy->cabin_alt = NAN;
+	oxy->cb = cb;
+	oxy->userinfo = userinfo;
+
+	return (oxy);
+}
+
+void
+oxy_free(oxy_t *oxy)
+{
+	free(oxy);
+}
+
+static double
+crew_flow(oxy_mask_t mask, double cabin_press)
+{
+	double ntp_vol = MINUTE_VOL * cabin_press / ISA_SL_PRESS;
+	double o2_frac;
+
+	switch (mask) {
+	case OXY_MASK_OFF:
+		return (0);
+	case OXY_MASK_NORMAL:
+		/* mix to sea level O2 partial pressure, bottle O2 is 100% */
+		o2_frac = MIN(0.21 * ISA_SL_PRESS / cabin_press, 1);
+		return (ntp_vol * (o2_frac - 0.21) / 0.79);
+	case OXY_MASK_100:
+		return (ntp_vol);
+	case OXY_MASK_EMER:
+		return (ntp_vol * EMER_FLOW_MULT);
+	}
+	VERIFY_FAIL();
+}
+
+static void
+update_crew(oxy_t *oxy, double d_t)
+{
+	double cabin_press = alt2press(oxy->cabin_alt, ISA_SL_PRESS);
+	double tuc = oxy_tuc(oxy->cabin_alt, oxy->rapid);
+	double total_flow = 0;
+
+	for (unsigned i = 0; i < oxy->num_crew; i++) {
+		crew_t *crew = &oxy->crew[i];
+		bool_t on_oxy = (crew->mask != OXY_MASK_OFF &&
+		    oxy->crew_qty > 0);
+
+		crew->flow = (on_oxy ? crew_flow(crew->mask, cabin_press) : 0);
+		total_flow += crew->flow;
+
+		if (on_oxy || isinf(tuc) || oxy->cabin_alt < HYPOXIA_ALT) {
+			crew->reserve = MIN(crew->reserve + d_t /
+			    RECOVERY_TIME, 1);
+		} else {
+			crew->reserve = MAX(crew->reserve - d_t / tuc, 0);
+		}
+		if (crew->conscious && crew->reserve == 0) {
+			crew->conscious = B_FALSE;
+			oxy->cb(OXY_EVT_CREW_INCAP, i, oxy->userinfo);
+		} else if (!crew->conscious &&
+		    crew->reserve >= RECOVERED_RESERVE) {
+			crew->conscious = B_TRUE;
+			oxy->cb(OXY_EVT_CREW_RECOVERED, i, oxy->userinfo);
+		}
+	}
+	if (oxy->crew_qty > 0 && total_flow > 0) {
+		oxy->crew_qty = MAX(oxy->crew_qty - total_flow * d_t / 60, 0);
+		if (!oxy->crew_low &&
+		    oxy->crew_qty < CREW_LOW_FRAC * oxy->crew_cap) {
+			oxy->crew_low = B_TRUE;
+			oxy->cb(OXY_EVT_CREW_LOW, 0, oxy->userinfo);
+		}
+		if (oxy->crew_qty == 0)
+			oxy->cb(OXY_EVT_CREW_DEPLETED, 0, oxy->userinfo);
+	}
+}
+
+/*
+ * Updates the oxygen system.
+ *
+ * @param cabin_alt Cabin pressure altitude in feet.
+ * @param d_t Time elapsed since the previous update in seconds.
+ */
+void
+oxy_update(oxy_t *oxy, double cabin_alt, double d_t)
+{
+	ASSERT(oxy != NULL);
+	ASSERT(!isnan(cabin_alt));
+	ASSERT3F(d_t, >=, 0);
+
+	if (!isnan(oxy->cabin_alt) && d_t > 0) {
+		FILTER_IN(oxy->cabin_rate, (cabin_alt - oxy->cabin_alt) /
+		    d_t * 60, d_t, CABIN_RATE_LAG);
+	}
+	oxy->cabin_alt = cabin_alt;
+	if (cabin_alt < HYPOXIA_ALT)
+		oxy->rapid = B_FALSE;
+	else if (oxy->cabin_rate > RAPID_DECOMP_RATE)
+		oxy->rapid = B_TRUE;
+
+	if (cabin_alt > oxy->pax_deploy_alt)
+		oxy_pax_deploy(oxy);
+	if (oxy->pax_deployed && oxy->pax_rem > 0) {
+		oxy->pax_rem = MAX(oxy->pax_rem - d_t, 0);
+		if (oxy->pax_rem == 0)
+			oxy->cb(OXY_EVT_PAX_DEPLETED, 0, oxy->userinfo);
+	}
+
+	update_crew(oxy, d_t);
+}
+
+void
+oxy_set_crew_mask(oxy_t *oxy, unsigned crew_idx, oxy_mask_t mask)
+{
+	ASSERT(oxy != NULL);
+	ASSERT3U(crew_idx, <, oxy->num_crew);
+	ASSERT3U(mask, <=, OXY_MASK_EMER);
+	oxy->crew[crew_idx].mask = mask;
+}
+
+oxy_mask_t
+oxy_get_crew_mask(const oxy_t *oxy, unsigned crew_idx)
+{
+	ASSERT(oxy != NULL);
+	ASSERT3U(crew_idx, <, oxy->num_crew);
+	return (oxy->crew[crew_idx].mask);
+}
+
+/*
+ * Returns the quantity of oxygen remaining in the crew bottle in liters
+ * NTP. The bottle pressure is proportional to this quantity.
+ */
+double
+oxy_get_crew_qty(const oxy_t *oxy)
+{
+	ASSERT(oxy != NULL);
+	return (oxy->crew_qty);
+}
+
+/*
+ * Sets the quantity of oxygen in the crew bottle (e.g. to start a flight
+ * with a partially used bottle, or to simulate servicing).
+ */
+void
+oxy_set_crew_qty(oxy_t *oxy, double qty)
+{
+	ASSERT(oxy != NULL);
+	ASSERT3F(qty, >=, 0);
+	oxy->crew_qty = MIN(qty, oxy->crew_cap);
+	oxy->crew_low = (oxy->crew_qty < CREW_LOW_FRAC * oxy->crew_cap);
+}
+
+/*
+ * Returns a crew mask's current oxygen flow in liters NTP per minute.
+ */
+double
+oxy_get_crew_flow(const oxy_t *oxy, unsigned crew_idx)
+{
+	ASSERT(oxy != NULL);
+	ASSERT3U(crew_idx, <, oxy->num_crew);
+	return (oxy->crew[crew_idx].flow);
+}
+
+/*
+ * Returns how long (in seconds) a crew member remains conscious at the
+ * current cabin altitude, taking into account the hypoxic exposure they
+ * have already accumulated. Returns INFINITY if the crew member is
+ * breathing supplemental oxygen or the cabin altitude is safe.
+ */
+double
+oxy_get_crew_tuc(const oxy_t *oxy, unsigned crew_idx)
+{
+	const crew_t *crew;
+
+	ASSERT(oxy != NULL);
+	ASSERT3U(crew_idx, <, oxy->num_crew);
+	crew = &oxy->crew[crew_idx];
+	if (isnan(oxy->cabin_alt) || oxy->cabin_alt < HYPOXIA_ALT ||
+	    (crew->mask != OXY_MASK_OFF && oxy->crew_qty > 0))
+		return (INFINITY);
+	return (crew->reserve * oxy_tuc(oxy->cabin_alt, oxy->rapid));
+}
+
+bool_t
+oxy_crew_is_conscious(const oxy_t *oxy, unsigned crew_idx)
+{
+	ASSERT(oxy != NULL);
+	ASSERT3U(crew_idx, <, oxy->num_crew);
+	return (oxy->crew[crew_idx].conscious);
+}
+
+/*
+ * Drops the passenger masks and starts the passenger oxygen generators.
+ * Does nothing if the masks have already been deployed.
+ */
+void
+oxy_pax_deploy(oxy_t *oxy)
+{
+	ASSERT(oxy != NULL);
+	if (oxy->pax_deployed)
+		return;
+	oxy->pax_deployed = B_TRUE;
+	oxy->cb(OXY_EVT_PAX_MASKS_DROP, 0, oxy->userinfo);
+}
+
+/*
+ * Sets the cabin altitude in feet above which the passenger masks are
+ * deployed automatically. Pass INFINITY to disable automatic deployment.
+ */
+void
+oxy_set_pax_deploy_alt(oxy_t *oxy, double cabin_alt)
+{
+	ASSERT(oxy != NULL);
+	ASSERT(!isnan(cabin_alt));
+	oxy->pax_deploy_alt = cabin_alt;
+}
+
+bool_t
+oxy_pax_masks_deployed(const oxy_t *oxy)
+{
+	ASSERT(oxy != NULL);
+	return (oxy->pax_deployed);
+}
+
+/*
+ * Returns the remaining passenger oxygen generator time in seconds.
+ */
+double
+oxy_get_pax_time_rem(const oxy_t *oxy)
+{
+	ASSERT(oxy != NULL);
+	return (oxy->pax_rem);
+}
+
+/*
+ * Restows the passenger masks and replaces the generators, as done by
+ * maintenance after a deployment.
+ */
+void
+oxy_pax_reset(oxy_t *oxy)
+{
+	ASSERT(oxy != NULL);
+	oxy->pax_deployed = B_FALSE;
+	oxy->pax_rem = oxy->pax_dur;
+}
@@ -2372,6 +2372,9 @@ alt2press(double alt_ft, double qnh_Pa)
 
 /*
  * Calculates pressure altitude from static air pressure under ISA conditions.
+ * This is also what a barometric altimeter indicates when fed `press' as
+ * its static pressure and set to `qnh' (pass ISA_SL_PRESS for "STD").
+ * Given the QFE and QNH at an airport, it returns the field elevation.
  *
  * @param press Static air pressure in Pa.
  * @param qnh Local QNH in Pa.
@@ -2385,6 +2388,63 @@ press2alt(double press_Pa, double qnh_Pa)
 	    EARTH_GRAVITY)));
 }
 
+/*
+ * Converts QNH into QFE (the pressure at field elevation).
+ *
+ * @param qnh Local QNH in Pa.
+ * @param elev_ft Field elevation in feet.
+ *
+ * @return QFE in Pa.
+ */
+double
+qnh2qfe(double qnh_Pa, double elev_ft)
+{
+	return (alt2press(elev_ft, qnh_Pa));
+}
+
+/*
+ * Converts QFE (the pressure at field elevation) into QNH.
+ *
+ * @param qfe QFE in Pa.
+ * @param elev_ft Field elevation in feet.
+ *
+ * @return QNH in Pa.
+ */
+double
+qfe2qnh(double qfe_Pa, double elev_ft)
+{
+	/* the barometric formula is linear in the reference pressure */
+	return (qfe_Pa * (ISA_SL_PRESS / alt2press(elev_ft, ISA_SL_PRESS)));
+}
+
+/*
+ * Rounds a pressure to the resolution of an inHg altimeter setting
+ * (0.01 inHg).
+ *
+ * @param press Pressure in Pa.
+ *
+ * @return Altimeter setting in inHg.
+ */
+double
+altim_setting_inhg(double press_Pa)
+{
+	return (round(PA2INHG(press_Pa) * 100) / 100);
+}
+
+/*
+ * Rounds a pressure to the resolution of an hPa altimeter setting
+ * (1 hPa). QNH & QFE are reported rounded down to a whole hPa.
+ *
+ * @param press Pressure in Pa.
+ *
+ * @return Altimeter setting in hPa.
+ */
+double
+altim_setting_hpa(double press_Pa)
+{
+	return (floor(PA2HPA(press_Pa)));
+}
+
 double
 alt2press_baro(double alt_m, double p0_Pa, double T0_K, double g_mss)
 {
@@ -2439,6 +2499,262 @@ press2alt_baro(double p_Pa, double p0_Pa, double T0_K, double g_mss)
 	    (g_mss * DRY_AIR_MOL)))) / ISA_TLR_PER_1M);
 }
 
+/*
+ * ISA layers up to the stratopause. Altitudes are geopotential, lapse
+ * rates are in K/m (negative means temperature decreasing with altitude).
+ * Base pressures are computed on first use, so that they are consistent
+ * with the gas constants we use everywhere else.
+ */
+typedef struct {
+	double	base_alt;	/* meters */
+	double	base_temp;	/* Kelvin */
+	double	lapse;		/* K/m */
+} isa_layer_t;
+
+static const isa_layer_t isa_layers[] = {
+    { 0,	ISA_SL_TEMP_K,	-ISA_TLR_PER_1M },	/* troposphere */
+    { 11000,	216.65,		0 },			/* tropopause */
+    { 20000,	216.65,		0.001 },		/* stratosphere */
+    { 32000,	228.65,		0.0028 }		/* stratosphere */
+};
+#define	NUM_ISA_LAYERS	ARRAY_NUM_ELEM(isa_layers)
+#define	ISA_MAX_ALT	47000		/* meters, stratopause */
+
+/*
+ * Computes the standard pressure at altitude `alt_m' inside of `layer',
+ * given the pressure at the base of the layer.
+ */
+static double
+isa_layer_press(const isa_layer_t *layer, double base_press, double alt_m)
+{
+	double g_M_R = (EARTH_GRAVITY * DRY_AIR_MOL) / R_univ;
+	double d_h = alt_m - layer->base_alt;
+
+	if (layer->lapse == 0) {
+		return (base_press * exp(-g_M_R * d_h / layer->base_temp));
+	} else {
+		double T = layer->base_temp + layer->lapse * d_h;
+		return (base_press * pow(T / layer->base_temp,
+		    -g_M_R / layer->lapse));
+	}
+}
+
+/*
+ * Inverse of isa_layer_press.
+ */
+static double
+isa_layer_alt(const isa_layer_t *layer, double base_press, double press)
+{
+	double g_M_R = (EARTH_GRAVITY * DRY_AIR_MOL) / R_univ;
+
+	if (layer->lapse == 0) {
+		return (layer->base_alt - log(press / base_press) *
+		    layer->base_temp / g_M_R);
+	} else {
+		double T = layer->base_temp * pow(press / base_press,
+		    -layer->lapse / g_M_R);
+		return (layer->base_alt + (T - layer->base_temp) /
+		    layer->lapse);
+	}
+}
+
+static void
+isa_base_press(double base_press[NUM_ISA_LAYERS])
+{
+	base_press[0] = ISA_SL_PRESS;
+	for (unsigned i = 1; i < NUM_ISA_LAYERS; i++) {
+		base_press[i] = isa_layer_press(&isa_layers[i - 1],
+		    base_press[i - 1], isa_layers[i].base_alt);
+	}
+}
+
+static unsigned
+isa_alt2layer(double alt_m)
+{
+	unsigned i;
+	for (i = NUM_ISA_LAYERS - 1; i > 0; i--) {
+		if (alt_m >= isa_layers[i].base_alt)
+			break;
+	}
+	return (i);
+}
+
+/*
+ * Computes the state of the atmosphere at a given pressure altitude using
+ * the full ISA model, including the isothermal tropopause layer and the
+ * stratosphere up to 47 km (approx. 154,000 ft). Unlike alt2press and
+ * isadev2sat, which only model the troposphere, this is valid above
+ * the tropopause.
+ *
+ * @param alt_m Pressure altitude in meters (geopotential). Values below
+ *	sea level extrapolate the troposphere. Values above 47 km are
+ *	clamped.
+ * @param isadev ISA temperature deviation in degrees C. This only affects
+ *	the temperature, density and speed of sound, not the pressure,
+ *	since `alt_m' is a pressure altitude.
+ * @param isa Output state of the atmosphere.
+ */
+void
+isa_atmos(double alt_m, double isadev, isa_atmos_t *isa)
+{
+	double base_press[NUM_ISA_LAYERS];
+	const isa_layer_t *layer;
+	double oat;
+
+	ASSERT(isa != NULL);
+	ASSERT(!isnan(alt_m));
+
+	alt_m = MIN(alt_m, ISA_MAX_ALT);
+	isa_base_press(base_press);
+	layer = &isa_layers[isa_alt2layer(alt_m)];
+
+	isa->press = isa_layer_press(layer, base_press[layer - isa_layers],
+	    alt_m);
+	isa->temp = layer->base_temp + layer->lapse *
+	    (alt_m - layer->base_alt) + isadev;
+	oat = KELVIN2C(isa->temp);
+	isa->dens = air_density(isa->press, oat);
+	isa->sos = speed_sound(oat);
+}
+
+/*
+ * Same as press2alt, but uses the full ISA model (see isa_atmos), so it
+ * is valid above the tropopause.
+ *
+ * @param press_Pa Static air pressure in Pa.
+ *
+ * @return Pressure altitude in meters, relative to 1013.25 hPa.
+ */
+double
+isa_press2alt(double press_Pa)
+{
+	double base_press[NUM_ISA_LAYERS];
+	unsigned i;
+
+	ASSERT3F(press_Pa, >, 0);
+	isa_base_press(base_press);
+	for (i = NUM_ISA_LAYERS - 1; i > 0; i--) {
+		if (press_Pa <= base_press[i])
+			break;
+	}
+	return (MIN(isa_layer_alt(&isa_layers[i], base_press[i], press_Pa),
+	    ISA_MAX_ALT));
+}
+
+/*
+ * Computes density altitude, i.e. the altitude in the ISA atmosphere at
+ * which the air density equals the density of the air specified by
+ * `press_Pa' and `oat'.
+ *
+ * @param press_Pa Static air pressure in Pa.
+ * @param oat Static outside air temperature in degrees C.
+ *
+ * @return Density altitude in meters.
+ */
+double
+isa_dens_alt(double press_Pa, double oat)
+{
+	double dens = air_density(press_Pa, oat);
+	double lo = -5000, hi = ISA_MAX_ALT;
+
+	ASSERT3F(dens, >, 0);
+	/*
+	 * ISA density strictly decreases with altitude, so we can simply
+	 * bisect for the matching altitude.
+	 */
+	while (hi - lo > 0.01) {
+		double mid = (lo + hi) / 2;
+		isa_atmos_t isa;
+
+		isa_atmos(mid, 0, &isa);
+		if (isa.dens > dens)
+			lo = mid;
+		else
+			hi = mid;
+	}
+	return ((lo + hi) / 2);
+}
+
+/*
+ * ICAO standard atmosphere (ICAO Doc 7488) at 1000 m intervals of
+ * geopotential altitude, covering altitudes up to approx. 65,600 ft.
+ * These are the published values, which isa_atmos reproduces to within
+ * 0.06%, so they can also serve as reference values when checking it.
+ */
+static const isa_table_ent_t isa_table[] = {
+    { -1000, 294.65, 113929, 1.347 },
+    { 0, 288.15, 101325, 1.225 },
+    { 1000, 281.65, 89874.6, 1.1116 },
+    { 2000, 275.15, 79495.2, 1.0065 },
+    { 3000, 268.65, 70108.5, 0.90912 },
+    { 4000, 262.15, 61640.2, 0.81913 },
+    { 5000, 255.65, 54019.9, 0.73612 },
+    { 6000, 249.15, 47181.0, 0.6597 },
+    { 7000, 242.65, 41060.7, 0.5895 },
+    { 8000, 236.15, 35599.8, 0.52517 },
+    { 9000, 229.65, 30742.5, 0.46635 },
+    { 10000, 223.15, 26436.3, 0.41271 },
+    { 11000, 216.65, 22632.1, 0.36392 },
+    { 12000, 216.65, 19330.4, 0.31083 },
+    { 13000, 216.65, 16510.4, 0.26548 },
+    { 14000, 216.65, 14101.8, 0.22675 },
+    { 15000, 216.65, 12044.6, 0.19367 },
+    { 16000, 216.65, 10287.5, 0.16542 },
+    { 17000, 216.65, 8786.68, 0.14129 },
+    { 18000, 216.65, 7504.84, 0.12068 },
+    { 19000, 216.65, 6410.01, 0.10307 },
+    { 20000, 216.65, 5474.89, 0.088035 }
+};
+
+/*
+ * Returns the ICAO standard atmosphere table. The table is sorted by
+ * altitude, with entries spaced ISA_TABLE_STEP meters apart.
+ *
+ * @param num Return argument which will be filled with the number of
+ *	entries in the table.
+ */
+const isa_table_ent_t *
+isa_table_get(size_t *num)
+{
+	ASSERT(num != NULL);
+	*num = ARRAY_NUM_ELEM(isa_table);
+	return (isa_table);
+}
+
+/*
+ * Fast approximation of isa_atmos (with zero ISA deviation) for use in
+ * hot loops which need dense altitude queries. Interpolates in the ICAO
+ * standard atmosphere table: temperature linearly, pressure and density
+ * logarithmically. Since the layer boundaries coincide with table
+ * entries, the interpolation error is below 0.06%. Altitudes
+ * outside of the table are clamped to its first or last entry.
+ *
+ * @param alt_m Pressure altitude in meters (geopotential).
+ * @param isa Output state of the atmosphere.
+ */
+void
+isa_table_lookup(double alt_m, isa_atmos_t *isa)
+{
+	const isa_table_ent_t *e1, *e2;
+	double pos, t;
+	size_t i;
+
+	ASSERT(isa != NULL);
+	ASSERT(!isnan(alt_m));
+
+	pos = (alt_m - isa_table[0].alt) / ISA_TABLE_STEP;
+	pos = clamp(pos, 0, ARRAY_NUM_ELEM(isa_table) - 1);
+	i = MIN(floor(pos), ARRAY_NUM_ELEM(isa_table) - 2);
+	t = pos - i;
+	e1 = &isa_table[i];
+	e2 = &isa_table[i + 1];
+
+	isa->temp = wavg(e1->temp, e2->temp, t);
+	isa->press = exp(wavg(log(e1->press), log(e2->press), t));
+	isa->dens = exp(wavg(log(e1->dens), log(e2->dens), t));
+	isa->sos = speed_sound(KELVIN2C(isa->temp));
+}
+
 /*
  * Converts pressure altitude to flight level.
  *
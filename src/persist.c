@@ -0,0 +1,366 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <errno.h>
+#include <inttypes.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/crc64.h>
+#include <acfutils/helpers.h>
+#include <acfutils/log.h>
+#include <acfutils/persist.h>
+#include <acfutils/safe_alloc.h>
+
+#define	FORMAT_KEY	"persist.format"
+#define	VERSION_KEY	"persist.version"
+#define	CRC_FOOTER	"# persist.crc64 = "
+
+typedef struct {
+	persist_migrate_t	func;
+	void			*userinfo;
+} migration_t;
+
+struct persist_s {
+	char		*format;
+	unsigned	version;
+	/* migrations[i] migrates from version i to i + 1 */
+	migration_t	*migrations;
+};
+
+/*
+ * Allocates a persistence handler for a particular kind of saved state.
+ * This wraps the conf_t file format with a few features which are easy
+ * to get wrong when persisting state between simulator sessions:
+ *
+ * - Format identification: the file records the name of the state
+ *	format, so that e.g. a failures save file can't be accidentally
+ *	loaded as a wear save file.
+ * - Schema versions: the file records the version of the format it was
+ *	written with. When you change the set of keys you save (or their
+ *	meaning), bump the version number and register a migration
+ *	function from the old version, which rewrites the old keys into
+ *	their new form. Old save files are then migrated step by step to
+ *	the current version on load. Files written by a newer version of
+ *	your code are refused, instead of being misinterpreted.
+ * - Integrity checking: the file ends with a CRC64 footer covering the
+ *	entire file contents, so truncated or corrupted files are detected
+ *	and refused, instead of silently restoring partial state.
+ * - Atomic writes: the file is first written on the side and then
+ *	swapped into place, so a crash mid-write doesn't lose the old file.
+ *
+ * Typical usage:
+ *
+ *	static bool_t
+ *	migrate_v1(conf_t *conf, unsigned from_version, void *userinfo)
+ *	{
+ *		double temp;
+ *		// v2 renamed "brake_temp" to "brake.temp"
+ *		if (conf_get_d(conf, "brake_temp", &temp)) {
+ *			conf_set_d(conf, "brake.temp", temp);
+ *			conf_set_str(conf, "brake_temp", NULL);
+ *		}
+ *		return (B_TRUE);
+ *	}
+ *
+ *	persist_t *persist = persist_alloc("my_acf_state", 2);
+ *	persist_add_migration(persist, 1, migrate_v1, NULL);
+ *	...saving:
+ *	conf_t *conf = conf_create_empty();
+ *	conf_set_d(conf, "brake.temp", brake_temp);
+ *	conf_set_data(conf, "pid.state", &pid, sizeof (pid));
+ *	persist_write(persist, conf, path);
+ *	conf_free(conf);
+ *	...loading:
+ *	conf_t *conf = persist_read(persist, path, NULL);
+ *	if (conf != NULL) {
+ *		conf_get_d(conf, "brake.temp", &brake_temp);
+ *		...
+ *		conf_free(conf);
+ *	}
+ *
+ * Raw binary state, such as a delay_line_t, can be stored using
+ * conf_set_data. Note that raw structures change layout between library
+ * versions, so that is exactly the kind of state which needs a version
+ * bump & migration when it changes.
+ *
+ * Since the integrity footer uses CRC64, crc64_init must have been
+ * called before reading or writing any files.
+ *
+ * @param format Name of the state format. Must not be empty.
+ * @param version Current version of the format. Must be at least 1.
+ *
+ * @return The new persistence handler. Use persist_free to free it.
+ */
+persist_t *
+persist_alloc(const char *format, unsigned version)
+{
+	persist_t *persist = safe_calloc(1, sizeof (*persist));
+
+	ASSERT(format != NULL);
+	ASSERT(format[0] != '\0');
+	ASSERT3U(version, >=, 1);
+	persist->format = safe_strdup(format);
+	persist->version = version;
+	persist->migrations = safe_calloc(version,
+	    sizeof (*persist->migrations));
+
+	return (persist);
+}
+
+void
+persist_free(persist_t *persist)
+{
+	if (persist == NULL)
+		return;
+	free(persist->format);
+	free(persist->migrations);
+	free(persist);
+}
+
+/*
+ * Registers a migration function, which converts saved state from
+ * `from_version' to `from_version + 1'. Migrations must be registered
+ * for all versions from the oldest version you want to be able to load,
+ * up to the current version minus one.
+ *
+ * @param from_version Version which the function migrates from. Must be
+ *	less than the current version.
+ * @param func Migration function.
+ * @param userinfo Optional userinfo argument to pass to `func'.
+ */
+void
+persist_add_migration(persist_t *persist, unsigned from_version,
+    persist_migrate_t func, void *userinfo)
+{
+	ASSERT(persist != NULL);
+	ASSERT3U(from_version, <, persist->version);
+	ASSERT(func != NULL);
+	persist->migrations[from_version].func = func;
+	persist->migrations[from_version].userinfo = userinfo;
+}
+
+/*
+ * Writes the state in `conf' to a file, tagged with the format name and
+ * current version, and with an integrity footer. The passed `conf' isn't
+ * modified. Keys starting with "persist." are reserved.
+ *
+ * @return B_TRUE on success, B_FALSE on error (which is logged).
+ */
+bool_t
+persist_write(const persist_t *persist, const conf_t *conf,
+    const char *filename)
+{
+	conf_t *copy;
+	char *filename_new;
+	char *buf = NULL;
+	size_t bufsz, len;
+	bool_t res = B_FALSE;
+
+	ASSERT(persist != NULL);
+	ASSERT(conf != NULL);
+	ASSERT(filename != NULL);
+
+	copy = conf_create_copy(conf);
+	conf_set_str(copy, FORMAT_KEY, persist->format);
+	conf_set_i(copy, VERSION_KEY, persist->version);
+	/*
+	 * conf_t can only be serialized into a file, so we serialize into
+	 * a scratch file and read it back to get at the contents. The
+	 * actual crash-safe write is then done by file_write_atomic.
+	 */
+	filename_new = sprintf_alloc("%s.new", filename);
+
+	if (!conf_write_file(copy, filename_new)) {
+		logMsg("Error writing %s: %s", filename_new, strerror(errno));
+		goto out;
+	}
+	buf = file2buf(filename_new, &bufsz);
+	if (buf == NULL) {
+		logMsg("Error reading back %s: %s", filename_new,
+		    strerror(errno));
+		goto out;
+	}
+	/* file2buf doesn't NUL-terminate, so append right at bufsz */
+	len = bufsz;
+	append_format(&buf, &len, CRC_FOOTER "%016" PRIx64 "\n",
+	    crc64(buf, bufsz));
+	res = file_write_atomic(filename, buf, len);
+out:
+	(void) remove_file(filename_new, B_TRUE);
+	free(buf);
+	free(filename_new);
+	conf_free(copy);
+
+	return (res);
+}
+
+/*
+ * Checks the integrity footer at the end of the file contents. On
+ * success, `datasz' is set to the length of the verified contents
+ * preceding the footer.
+ */
+static bool_t
+check_footer(const char *filename, const char *buf, size_t bufsz,
+    size_t *datasz)
+{
+	const char *footer = NULL;
+	uint64_t crc;
+
+	/* locate the start of the last line */
+	for (size_t i = bufsz; i > 0; i--) {
+		if (buf[i - 1] == '\n' && i < bufsz) {
+			footer = &buf[i];
+			break;
+		}
+	}
+	if (footer == NULL || strncmp(footer, CRC_FOOTER,
+	    strlen(CRC_FOOTER)) != 0 ||
+	    sscanf(footer + strlen(CRC_FOOTER), "%" SCNx64, &crc) != 1) {
+		logMsg("Error reading %s: integrity footer missing, file "
+		    "is likely truncated", filename);
+		return (B_FALSE);
+	}
+	if (crc64(buf, footer - buf) != crc) {
+		logMsg("Error reading %s: integrity check failed, file is "
+		    "corrupted", filename);
+		return (B_FALSE);
+	}
+	*datasz = footer - buf;
+	return (B_TRUE);
+}
+
+/*
+ * Opens the contents of `buf' as a read-only stream, so that conf_read
+ * can parse exactly the contents which passed the integrity check,
+ * rather than reading the file again, which could have changed since.
+ */
+static FILE *
+buf_open(void *buf, size_t bufsz)
+{
+#if	IBM
+	/* Windows has no fmemopen, so go through an anonymous temp file */
+	FILE *fp = tmpfile();
+
+	if (fp == NULL)
+		return (NULL);
+	if (fwrite(buf, 1, bufsz, fp) != bufsz || fflush(fp) != 0 ||
+	    fseek(fp, 0, SEEK_SET) != 0) {
+		fclose(fp);
+		return (NULL);
+	}
+	return (fp);
+#else	/* !IBM */
+	return (fmemopen(buf, bufsz, "r"));
+#endif	/* !IBM */
+}
+
+/*
+ * Reads saved state from a file previously written with persist_write.
+ * The file's integrity and format are checked, and if the file was
+ * written with an older version, the registered migrations are applied
+ * to bring it up to the current version.
+ *
+ * @param file_version Optional return argument, which will be filled
+ *	with the version the file was originally written with.
+ *
+ * @return The loaded state, or NULL on error (which is logged). The
+ *	"persist." keys are stripped from the returned configuration.
+ *	Use conf_free to free it.
+ */
+conf_t *
+persist_read(const persist_t *persist, const char *filename,
+    unsigned *file_version)
+{
+	char *buf;
+	size_t bufsz, datasz;
+	FILE *fp;
+	conf_t *conf;
+	int errline;
+	const char *format;
+	int version;
+
+	ASSERT(persist != NULL);
+	ASSERT(filename != NULL);
+
+	buf = file2buf(filename, &bufsz);
+	if (buf == NULL) {
+		logMsg("Error reading %s: %s", filename, strerror(errno));
+		return (NULL);
+	}
+	if (!check_footer(filename, buf, bufsz, &datasz)) {
+		free(buf);
+		return (NULL);
+	}
+	fp = buf_open(buf, datasz);
+	if (fp == NULL) {
+		logMsg("Error reading %s: %s", filename, strerror(errno));
+		free(buf);
+		return (NULL);
+	}
+	conf = conf_read(fp, &errline);
+	fclose(fp);
+	free(buf);
+	if (conf == NULL) {
+		logMsg("Error reading %s: syntax error on line %d",
+		    filename, errline);
+		return (NULL);
+	}
+	if (!conf_get_str(conf, FORMAT_KEY, &format) ||
+	    strcmp(format, persist->format) != 0) {
+		logMsg("Error reading %s: file doesn't contain %s state",
+		    filename, persist->format);
+		goto errout;
+	}
+	if (!conf_get_i(conf, VERSION_KEY, &version) || version < 1) {
+		logMsg("Error reading %s: missing or invalid version",
+		    filename);
+		goto errout;
+	}
+	if ((unsigned)version > persist->version) {
+		logMsg("Error reading %s: file version %d is newer than the "
+		    "supported version %u", filename, version,
+		    persist->version);
+		goto errout;
+	}
+	if (file_version != NULL)
+		*file_version = version;
+	for (unsigned v = version; v < persist->version; v++) {
+		const migration_t *mig = &persist->migrations[v];
+
+		if (mig->func == NULL) {
+			logMsg("Error reading %s: don't know how to migrate "
+			    "version %u to version %u", filename, v, v + 1);
+			goto errout;
+		}
+		if (!mig->func(conf, v, mig->userinfo)) {
+			logMsg("Error reading %s: migration from version %u "
+			    "to version %u failed", filename, v, v + 1);
+			goto errout;
+		}
+	}
+	conf_set_str(conf, FORMAT_KEY, NULL);
+	conf_set_str(conf, VERSION_KEY, NULL);
+
+	return (conf);
+errout:
+	conf_free(conf);
+	return (NULL);
+}
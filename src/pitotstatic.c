@@ -0,0 +1,150 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <math.h>
+#include <string.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/math.h>
+#include <acfutils/perf.h>
+#include <acfutils/pitotstatic.h>
+
+#define	DFL_PITOT_LAG	0.2	/* seconds */
+#define	DFL_STATIC_LAG	0.5	/* seconds */
+#define	DFL_VSI_LAG	3.0	/* seconds */
+#define	DFL_DRAIN_LAG	10.0	/* seconds */
+
+/*
+ * Initializes a pitot-static system. Each independent system (e.g. the
+ * captain's, first officer's and standby systems) gets its own
+ * pitotstatic_t and all instruments connected to that system read its
+ * outputs.
+ *
+ * Typical usage:
+ *
+ *	pitotstatic_t capt;
+ *	pitotstatic_init(&capt);
+ *	...on every frame:
+ *	capt.pitot_fail = (xpfail_is_active(&pitot_fail_dr) ?
+ *	    PS_PORT_BLOCKED : PS_PORT_OK);
+ *	pitotstatic_update(&capt, amb_press, oat, ktas, baro_setting, d_t);
+ *	...then drive the captain's ASI, altimeter and VSI from capt.ias,
+ *	capt.alt and capt.vs.
+ *
+ * The response of the system can be tuned by modifying the lag and
+ * position error parameters after calling this function. The lags
+ * default to 0.2 seconds for the pitot line, 0.5 seconds for the static
+ * line and 3 seconds for the VSI. The static position error defaults to
+ * zero. A positive `pos_err' raises the sensed static pressure by that
+ * fraction of the impact pressure, causing the altimeter to under-read
+ * and the ASI to over-read as speed increases.
+ *
+ * Port blockages are simulated as follows:
+ *
+ *	- a blocked pitot port with an open drain hole slowly bleeds the
+ *	  trapped pressure down to static pressure, so the IAS decays to
+ *	  zero over `drain_lag' seconds.
+ *	- a blocked pitot port with a blocked drain traps the total
+ *	  pressure, so the ASI behaves like an altimeter, over-reading in
+ *	  a climb and under-reading in a descent.
+ *	- a blocked static port traps the static pressure, so the
+ *	  altimeter freezes, the VSI reads zero and the ASI under-reads in
+ *	  a climb and over-reads in a descent. PS_PORT_BLOCKED and
+ *	  PS_PORT_BLOCKED_DRAIN behave identically for the static port.
+ */
+void
+pitotstatic_init(pitotstatic_t *ps)
+{
+	ASSERT(ps != NULL);
+	memset(ps, 0, sizeof (*ps));
+	ps->pitot_lag = DFL_PITOT_LAG;
+	ps->static_lag = DFL_STATIC_LAG;
+	ps->vsi_lag = DFL_VSI_LAG;
+	ps->drain_lag = DFL_DRAIN_LAG;
+	ps->ias = NAN;
+	ps->mach = NAN;
+	ps->alt = NAN;
+	ps->pt = NAN;
+	ps->ps = NAN;
+}
+
+/*
+ * Updates the pitot-static system from the true air data. The first
+ * update sets the sensed pressures directly, subsequent updates apply
+ * the line lags and failures.
+ *
+ * @param press True ambient static air pressure in Pa.
+ * @param oat True static outside air temperature in degrees C.
+ * @param ktas True airspeed in knots.
+ * @param baro Altimeter barometric setting in Pa.
+ * @param d_t Time elapsed since the previous update in seconds.
+ */
+void
+pitotstatic_update(pitotstatic_t *ps, double press, double oat, double ktas,
+    double baro, double d_t)
+{
+	double qc, pt_true, ps_true, qc_s, alt_prev;
+
+	ASSERT(ps != NULL);
+	ASSERT3F(press, >, 0);
+	ASSERT3F(baro, >, 0);
+	ASSERT(!isnan(oat));
+	ASSERT(!isnan(ktas));
+
+	qc = impact_press(ktas2mach(MAX(ktas, 0), oat), press);
+	pt_true = press + qc;
+	ps_true = press + ps->pos_err * qc;
+
+	if (!ps->inited || d_t <= 0) {
+		ps->pt = pt_true;
+		ps->ps = ps_true;
+		ps->vs = 0;
+	} else {
+		if (ps->static_fail == PS_PORT_OK)
+			FILTER_IN(ps->ps, ps_true, d_t, ps->static_lag);
+		switch (ps->pitot_fail) {
+		case PS_PORT_OK:
+			FILTER_IN(ps->pt, pt_true, d_t, ps->pitot_lag);
+			break;
+		case PS_PORT_BLOCKED:
+			FILTER_IN(ps->pt, ps->ps, d_t, ps->drain_lag);
+			break;
+		case PS_PORT_BLOCKED_DRAIN:
+			break;
+		}
+	}
+
+	qc_s = MAX(ps->pt - ps->ps, 0);
+	ps->ias = impact_press2kcas(qc_s);
+	/* subsonic inversion of the impact pressure equation */
+	ps->mach = sqrt(5 * (pow(qc_s / ps->ps + 1, 2.0 / 7) - 1));
+	ps->alt = press2alt(ps->ps, baro);
+
+	/*
+	 * The VSI is driven from the rate of change of static pressure,
+	 * which is independent of the barometric setting, so we
+	 * differentiate a standard pressure altitude.
+	 */
+	alt_prev = ps->vs_alt;
+	ps->vs_alt = press2alt(ps->ps, ISA_SL_PRESS);
+	if (ps->inited && d_t > 0) {
+		FILTER_IN(ps->vs, (ps->vs_alt - alt_prev) / d_t * 60, d_t,
+		    ps->vsi_lag);
+	}
+	ps->inited = B_TRUE;
+}
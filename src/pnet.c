@@ -0,0 +1,494 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <math.h>
+#include <stdlib.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/math.h>
+#include <acfutils/pnet.h>
+#include <acfutils/safe_alloc.h>
+
+#define	MAX_ITER	200
+#define	PRESS_TOL	1.0		/* Pa */
+/* compliance of an empty accumulator relative to its volume, 1/Pa */
+#define	ACCUM_EMPTY_COMPL	1e-9
+
+typedef enum {
+	NODE_RES,
+	NODE_PLAIN,
+	NODE_ACCUM
+} node_type_t;
+
+typedef struct {
+	node_type_t	type;
+	double		press;		/* Pa */
+	double		compliance;	/* NODE_PLAIN: m^3/Pa */
+	double		volume;		/* NODE_ACCUM: total volume, m^3 */
+	double		precharge;	/* NODE_ACCUM: gas precharge, Pa */
+	double		qty;		/* NODE_RES: fluid quantity, m^3 */
+	double		prev_press;	/* scratch space for pnet_update */
+} node_t;
+
+typedef struct {
+	unsigned	res;
+	unsigned	node;
+	double		max_flow;	/* m^3/s */
+	double		max_press;	/* Pa */
+	double		speed;		/* 0 - 1 */
+	double		flow;		/* m^3/s */
+} pump_t;
+
+typedef struct {
+	unsigned	a, b;
+	double		conductance;	/* m^3/s per Pa */
+	bool_t		check;		/* only allows flow from a to b */
+	double		open;		/* 0 - 1 */
+	double		flow;		/* m^3/s, positive from a to b */
+} valve_t;
+
+struct pnet_s {
+	node_t		*nodes;
+	unsigned	num_nodes;
+	pump_t		*pumps;
+	unsigned	num_pumps;
+	valve_t		*valves;
+	unsigned	num_valves;
+};
+
+/*
+ * Allocates an empty pressure network. The network is built out of the
+ * following elements:
+ *
+ * - reservoirs: nodes held at a fixed pressure, holding a quantity of
+ *	fluid. Pumps draw fluid from reservoirs and consumers return it
+ *	to them. A reservoir can also represent the ambient atmosphere
+ *	(e.g. as the destination of leaks or pneumatic consumers).
+ * - plain nodes: pipes, manifolds and the like. Their pressure rises
+ *	linearly with the amount of fluid pushed into them, according to
+ *	their compliance (for a hydraulic line, that's its volume divided
+ *	by the fluid's bulk modulus).
+ * - accumulators: nodes with a gas precharge. Below the precharge
+ *	pressure they hold no fluid. Above it, fluid compresses the gas
+ *	(isothermally), so they store a large amount of fluid at nearly
+ *	constant pressure.
+ * - pumps: move fluid from a reservoir into a node. Pumps are pressure
+ *	compensated, so their flow drops off linearly from `max_flow' at
+ *	zero pressure to no flow at `max_press'. Their output is scaled by
+ *	their speed (e.g. engine N2 or electric motor state). A pump can't
+ *	deliver anything once its reservoir runs dry.
+ * - valves: connect two nodes, with flow proportional to the pressure
+ *	difference and to how far the valve is open. Valves also model
+ *	consumers (a valve from a node back to a reservoir, opened while
+ *	the consumer is operating), leaks and restrictors. A check valve
+ *	only lets fluid flow from `node_a' to `node_b'.
+ *
+ * All pressures are gauge pressures in Pa, volumes are in m^3 and flows
+ * in m^3/s. The network is solved implicitly, so that even very stiff
+ * hydraulic networks remain stable at normal frame rates.
+ *
+ * Typical usage:
+ *
+ *	pnet_t *pnet = pnet_alloc();
+ *	unsigned res = pnet_add_res(pnet, 0, 0.02);
+ *	unsigned sys = pnet_add_node(pnet, 1e-11);
+ *	unsigned acc = pnet_add_accum(pnet, 0.005, 1.4e7);
+ *	unsigned edp = pnet_add_pump(pnet, res, sys, 0.0025, 2.1e7);
+ *	unsigned acc_line = pnet_add_valve(pnet, sys, acc, 1e-8, B_FALSE);
+ *	unsigned gear = pnet_add_valve(pnet, sys, res, 5e-11, B_FALSE);
+ *	...
+ *	every frame:
+ *	pnet_pump_set_speed(pnet, edp, n2 / 100);
+ *	pnet_valve_set_open(pnet, gear, gear_in_transit ? 1 : 0);
+ *	pnet_update(pnet, d_t);
+ *	sys_press = pnet_node_get_press(pnet, sys);
+ *	...
+ *	pnet_free(pnet);
+ */
+pnet_t *
+pnet_alloc(void)
+{
+	return (safe_calloc(1, sizeof (pnet_t)));
+}
+
+void
+pnet_free(pnet_t *pnet)
+{
+	if (pnet == NULL)
+		return;
+	free(pnet->nodes);
+	free(pnet->pumps);
+	free(pnet->valves);
+	free(pnet);
+}
+
+static unsigned
+add_node(pnet_t *pnet, const node_t *node)
+{
+	ASSERT(pnet != NULL);
+	pnet->nodes = safe_realloc(pnet->nodes, (pnet->num_nodes + 1) *
+	    sizeof (*pnet->nodes));
+	pnet->nodes[pnet->num_nodes] = *node;
+	return (pnet->num_nodes++);
+}
+
+/*
+ * Adds a reservoir held at pressure `press', initially containing `qty'
+ * m^3 of fluid. Returns the node number of the reservoir.
+ */
+unsigned
+pnet_add_res(pnet_t *pnet, double press, double qty)
+{
+	ASSERT3F(qty, >=, 0);
+	return (add_node(pnet, &(node_t){
+	    .type = NODE_RES, .press = press, .qty = qty
+	}));
+}
+
+/*
+ * Adds a plain node with a compliance of `compliance' m^3/Pa. The node
+ * starts out unpressurized. Returns the node number.
+ */
+unsigned
+pnet_add_node(pnet_t *pnet, double compliance)
+{
+	ASSERT3F(compliance, >, 0);
+	return (add_node(pnet, &(node_t){
+	    .type = NODE_PLAIN, .compliance = compliance
+	}));
+}
+
+/*
+ * Adds an accumulator with a total volume of `volume' m^3 and a gas
+ * precharge pressure of `precharge' Pa. The accumulator starts out
+ * empty. Returns its node number.
+ */
+unsigned
+pnet_add_accum(pnet_t *pnet, double volume, double precharge)
+{
+	ASSERT3F(volume, >, 0);
+	ASSERT3F(precharge, >, 0);
+	return (add_node(pnet, &(node_t){
+	    .type = NODE_ACCUM, .volume = volume, .precharge = precharge
+	}));
+}
+
+static node_t *
+get_node(const pnet_t *pnet, unsigned node)
+{
+	ASSERT(pnet != NULL);
+	ASSERT3U(node, <, pnet->num_nodes);
+	return (&pnet->nodes[node]);
+}
+
+/*
+ * Returns the pressure at a node (of any type).
+ */
+double
+pnet_node_get_press(const pnet_t *pnet, unsigned node)
+{
+	return (get_node(pnet, node)->press);
+}
+
+void
+pnet_res_set_qty(pnet_t *pnet, unsigned res, double qty)
+{
+	node_t *node = get_node(pnet, res);
+
+	ASSERT3U(node->type, ==, NODE_RES);
+	ASSERT3F(qty, >=, 0);
+	node->qty = qty;
+}
+
+double
+pnet_res_get_qty(const pnet_t *pnet, unsigned res)
+{
+	const node_t *node = get_node(pnet, res);
+
+	ASSERT3U(node->type, ==, NODE_RES);
+	return (node->qty);
+}
+
+static double
+accum_fluid(const node_t *node, double press)
+{
+	if (press <= node->precharge)
+		return (0);
+	return (node->volume * (1 - node->precharge / press));
+}
+
+/*
+ * Returns the volume of fluid (in m^3) stored in an accumulator.
+ */
+double
+pnet_accum_get_fluid(const pnet_t *pnet, unsigned accum)
+{
+	const node_t *node = get_node(pnet, accum);
+
+	ASSERT3U(node->type, ==, NODE_ACCUM);
+	return (accum_fluid(node, node->press));
+}
+
+/*
+ * Adds a pump moving fluid from reservoir `res' into node `node'. The
+ * pump delivers up to `max_flow' m^3/s at zero pressure, dropping off
+ * linearly to no flow at `max_press' Pa. The pump starts out stopped.
+ * Returns the pump number.
+ */
+unsigned
+pnet_add_pump(pnet_t *pnet, unsigned res, unsigned node, double max_flow,
+    double max_press)
+{
+	ASSERT3U(get_node(pnet, res)->type, ==, NODE_RES);
+	ASSERT3U(get_node(pnet, node)->type, !=, NODE_RES);
+	ASSERT3F(max_flow, >, 0);
+	ASSERT3F(max_press, >, 0);
+
+	pnet->pumps = safe_realloc(pnet->pumps, (pnet->num_pumps + 1) *
+	    sizeof (*pnet->pumps));
+	pnet->pumps[pnet->num_pumps] = (pump_t){
+	    .res = res, .node = node, .max_flow = max_flow,
+	    .max_press = max_press
+	};
+	return (pnet->num_pumps++);
+}
+
+/*
+ * Sets the speed of a pump, as a fraction from 0 (stopped) to 1 (full
+ * rated output).
+ */
+void
+pnet_pump_set_speed(pnet_t *pnet, unsigned pump, double speed)
+{
+	ASSERT(pnet != NULL);
+	ASSERT3U(pump, <, pnet->num_pumps);
+	pnet->pumps[pump].speed = clamp(speed, 0, 1);
+}
+
+/*
+ * Returns the flow (in m^3/s) delivered by a pump during the last
+ * pnet_update.
+ */
+double
+pnet_pump_get_flow(const pnet_t *pnet, unsigned pump)
+{
+	ASSERT(pnet != NULL);
+	ASSERT3U(pump, <, pnet->num_pumps);
+	return (pnet->pumps[pump].flow);
+}
+
+/*
+ * Adds a valve between nodes `node_a' and `node_b'. When fully open,
+ * the valve passes `conductance' m^3/s for every Pa of pressure
+ * difference across it. If `check' is B_TRUE, the valve is a check
+ * valve, only letting fluid flow from `node_a' to `node_b'. The valve
+ * starts out closed. Returns the valve number.
+ */
+unsigned
+pnet_add_valve(pnet_t *pnet, unsigned node_a, unsigned node_b,
+    double conductance, bool_t check)
+{
+	ASSERT3U(node_a, <, pnet->num_nodes);
+	ASSERT3U(node_b, <, pnet->num_nodes);
+	ASSERT3U(node_a, !=, node_b);
+	ASSERT3F(conductance, >, 0);
+
+	pnet->valves = safe_realloc(pnet->valves, (pnet->num_valves + 1) *
+	    sizeof (*pnet->valves));
+	pnet->valves[pnet->num_valves] = (valve_t){
+	    .a = node_a, .b = node_b, .conductance = conductance,
+	    .check = check
+	};
+	return (pnet->num_valves++);
+}
+
+/*
+ * Sets how far a valve is open, from 0 (closed) to 1 (fully open).
+ */
+void
+pnet_valve_set_open(pnet_t *pnet, unsigned valve, double open)
+{
+	ASSERT(pnet != NULL);
+	ASSERT3U(valve, <, pnet->num_valves);
+	pnet->valves[valve].open = clamp(open, 0, 1);
+}
+
+/*
+ * Returns the flow (in m^3/s) through a valve during the last
+ * pnet_update. Positive values mean flow from `node_a' to `node_b'.
+ */
+double
+pnet_valve_get_flow(const pnet_t *pnet, unsigned valve)
+{
+	ASSERT(pnet != NULL);
+	ASSERT3U(valve, <, pnet->num_valves);
+	return (pnet->valves[valve].flow);
+}
+
+static double
+valve_cond(const pnet_t *pnet, const valve_t *valve)
+{
+	if (valve->check && pnet->nodes[valve->a].press <
+	    pnet->nodes[valve->b].press)
+		return (0);
+	return (valve->conductance * valve->open);
+}
+
+/*
+ * Returns the pump's flow as `a - b * press' at the node's pressure.
+ */
+static void
+pump_coeffs(const pnet_t *pnet, const pump_t *pump, double *a, double *b)
+{
+	const node_t *res = &pnet->nodes[pump->res];
+	double press = pnet->nodes[pump->node].press;
+
+	if (res->qty <= 0 || pump->speed <= 0 || press >= pump->max_press) {
+		*a = 0;
+		*b = 0;
+	} else {
+		*a = pump->max_flow * pump->speed;
+		*b = *a / pump->max_press;
+	}
+}
+
+/*
+ * Adds the fluid storage term of a node's equation to `num' and `den'.
+ * For accumulators, the stored fluid volume is linearized around the
+ * current pressure estimate, so that fluid is conserved once the
+ * iteration converges.
+ */
+static void
+storage_coeffs(const node_t *node, double d_t, double *num, double *den)
+{
+	double compl, dv;
+
+	if (node->type == NODE_PLAIN) {
+		*num += node->compliance / d_t * node->prev_press;
+		*den += node->compliance / d_t;
+		return;
+	}
+	ASSERT3U(node->type, ==, NODE_ACCUM);
+	/* an empty accumulator still has a tiny bit of give to it */
+	compl = node->volume * ACCUM_EMPTY_COMPL;
+	*num += compl / d_t * node->prev_press;
+	*den += compl / d_t;
+
+	if (node->press > node->precharge)
+		dv = node->volume * node->precharge / POW2(node->press);
+	else
+		dv = 0;
+	*num += (dv * node->press - accum_fluid(node, node->press) +
+	    accum_fluid(node, node->prev_press)) / d_t;
+	*den += dv / d_t;
+}
+
+/*
+ * Performs one Gauss-Seidel sweep over the backward Euler equations of
+ * all non-reservoir nodes:
+ *
+ *	(V_i(P_i) - V_i(P_i_prev)) / d_t = sum(inflows at P)
+ *
+ * Returns the largest pressure change made.
+ */
+static double
+gs_sweep(pnet_t *pnet, double d_t)
+{
+	double max_delta = 0;
+
+	for (unsigned i = 0; i < pnet->num_nodes; i++) {
+		node_t *node = &pnet->nodes[i];
+		double num, den, press;
+
+		if (node->type == NODE_RES)
+			continue;
+		num = 0;
+		den = 0;
+		storage_coeffs(node, d_t, &num, &den);
+		for (unsigned j = 0; j < pnet->num_valves; j++) {
+			const valve_t *valve = &pnet->valves[j];
+			double g = valve_cond(pnet, valve);
+			unsigned other;
+
+			if (valve->a == i)
+				other = valve->b;
+			else if (valve->b == i)
+				other = valve->a;
+			else
+				continue;
+			num += g * pnet->nodes[other].press;
+			den += g;
+		}
+		for (unsigned j = 0; j < pnet->num_pumps; j++) {
+			const pump_t *pump = &pnet->pumps[j];
+			double a, b;
+
+			if (pump->node != i)
+				continue;
+			pump_coeffs(pnet, pump, &a, &b);
+			num += a;
+			den += b;
+		}
+		/* Fluid can't sustain negative pressure, it cavitates. */
+		press = MAX(num / den, 0);
+		max_delta = MAX(max_delta, fabs(press - node->press));
+		node->press = press;
+	}
+
+	return (max_delta);
+}
+
+/*
+ * Advances the network by `d_t' seconds. This solves for the new node
+ * pressures, then updates pump and valve flows and reservoir quantities.
+ */
+void
+pnet_update(pnet_t *pnet, double d_t)
+{
+	ASSERT(pnet != NULL);
+	ASSERT3F(d_t, >, 0);
+
+	for (unsigned i = 0; i < pnet->num_nodes; i++)
+		pnet->nodes[i].prev_press = pnet->nodes[i].press;
+	for (int iter = 0; iter < MAX_ITER; iter++) {
+		if (gs_sweep(pnet, d_t) < PRESS_TOL)
+			break;
+	}
+
+	for (unsigned i = 0; i < pnet->num_pumps; i++) {
+		pump_t *pump = &pnet->pumps[i];
+		node_t *res = &pnet->nodes[pump->res];
+		double a, b;
+
+		pump_coeffs(pnet, pump, &a, &b);
+		pump->flow = MIN(MAX(a - b * pnet->nodes[pump->node].press, 0),
+		    res->qty / d_t);
+		res->qty -= pump->flow * d_t;
+	}
+	for (unsigned i = 0; i < pnet->num_valves; i++) {
+		valve_t *valve = &pnet->valves[i];
+		node_t *a = &pnet->nodes[valve->a];
+		node_t *b = &pnet->nodes[valve->b];
+
+		valve->flow = valve_cond(pnet, valve) * (a->press - b->press);
+		if (a->type == NODE_RES)
+			a->qty = MAX(a->qty - valve->flow * d_t, 0);
+		if (b->type == NODE_RES)
+			b->qty = MAX(b->qty + valve->flow * d_t, 0);
+	}
+}
@@ -0,0 +1,344 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <stdlib.h>
+#include <string.h>
+
+#include <XPLMUtilities.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/glew.h>
+#include <acfutils/helpers.h>
+#include <acfutils/log.h>
+#include <acfutils/preflight.h>
+#include <acfutils/safe_alloc.h>
+
+typedef enum {
+	REQ_FILE,
+	REQ_DIR,
+	REQ_XP_VERSION,
+	REQ_GL_VERSION,
+	REQ_GL_EXT
+} req_type_t;
+
+typedef struct {
+	req_type_t	type;
+	char		*str;		/* REQ_FILE, REQ_DIR, REQ_GL_EXT */
+	int		ver[2];		/* REQ_XP_VERSION, REQ_GL_VERSION */
+} req_t;
+
+struct preflight_s {
+	req_t			*reqs;
+	preflight_result_t	*results;
+	size_t			num_reqs;
+	bool_t			ran;
+};
+
+/*
+ * Allocates a new startup dependency checker. A plugin declares all of
+ * the things it depends on (files & directories, X-Plane and XPLM
+ * versions, OpenGL versions and extensions), then calls preflight_run
+ * once at startup to check them all in one go. Rather than failing at
+ * the first missing dependency, the checker produces a consolidated
+ * report of everything that's wrong, which can be shown to the user or
+ * attached to a support request.
+ *
+ * Each requirement is tied to a named feature of the plugin, or to NULL,
+ * meaning the plugin can't work at all without it. A failed feature
+ * requirement only means that the feature should be disabled, which the
+ * plugin checks using preflight_feature_ok.
+ *
+ * Because the OpenGL checks use GLEW, preflight_run must be called from
+ * the main X-Plane thread after GLEW has been initialized (e.g. from
+ * XPluginStart after glewInit).
+ *
+ * Typical usage:
+ *
+ *	preflight_t *pf = preflight_alloc();
+ *	preflight_add_xp_version(pf, NULL, 11500, 0);
+ *	preflight_add_file(pf, NULL, mkpathname(plugindir, "data",
+ *	    "config.cfg", NULL), B_FALSE);
+ *	preflight_add_gl_version(pf, "efb", 3, 3);
+ *	preflight_add_gl_ext(pf, "efb", "GL_ARB_texture_storage");
+ *	if (!preflight_run(pf)) {
+ *		char *report = preflight_get_report(pf);
+ *		... show the report to the user ...
+ *		lacf_free(report);
+ *		preflight_free(pf);
+ *		return (0);
+ *	}
+ *	efb_enabled = preflight_feature_ok(pf, "efb");
+ *	preflight_free(pf);
+ */
+preflight_t *
+preflight_alloc(void)
+{
+	return (safe_calloc(1, sizeof (preflight_t)));
+}
+
+void
+preflight_free(preflight_t *pf)
+{
+	if (pf == NULL)
+		return;
+	for (size_t i = 0; i < pf->num_reqs; i++) {
+		free(pf->reqs[i].str);
+		free((char *)pf->results[i].feature);
+		free((char *)pf->results[i].desc);
+	}
+	free(pf->reqs);
+	free(pf->results);
+	free(pf);
+}
+
+static req_t *
+add_req(preflight_t *pf, const char *feature, req_type_t type)
+{
+	ASSERT(pf != NULL);
+	ASSERT_MSG(!pf->ran, "%s", "Can't add requirements after "
+	    "preflight_run");
+
+	pf->reqs = safe_realloc(pf->reqs, (pf->num_reqs + 1) *
+	    sizeof (*pf->reqs));
+	pf->results = safe_realloc(pf->results, (pf->num_reqs + 1) *
+	    sizeof (*pf->results));
+	memset(&pf->reqs[pf->num_reqs], 0, sizeof (*pf->reqs));
+	memset(&pf->results[pf->num_reqs], 0, sizeof (*pf->results));
+	pf->reqs[pf->num_reqs].type = type;
+	if (feature != NULL)
+		pf->results[pf->num_reqs].feature = safe_strdup(feature);
+
+	return (&pf->reqs[pf->num_reqs++]);
+}
+
+/*
+ * Requires that the file or directory (if `is_dir' is B_TRUE) at `path'
+ * exists.
+ */
+void
+preflight_add_file(preflight_t *pf, const char *feature, const char *path,
+    bool_t is_dir)
+{
+	ASSERT(path != NULL);
+	add_req(pf, feature, is_dir ? REQ_DIR : REQ_FILE)->str =
+	    safe_strdup(path);
+}
+
+/*
+ * Requires a minimum X-Plane version (e.g. 12060 for 12.06) and XPLM
+ * SDK version (e.g. 400). Pass 0 to skip either check.
+ */
+void
+preflight_add_xp_version(preflight_t *pf, const char *feature,
+    int min_xp_ver, int min_xplm_ver)
+{
+	req_t *req;
+
+	ASSERT(min_xp_ver > 0 || min_xplm_ver > 0);
+	req = add_req(pf, feature, REQ_XP_VERSION);
+	req->ver[0] = min_xp_ver;
+	req->ver[1] = min_xplm_ver;
+}
+
+/*
+ * Requires that the OpenGL context supports at least version
+ * `major'.`minor'.
+ */
+void
+preflight_add_gl_version(preflight_t *pf, const char *feature, int major,
+    int minor)
+{
+	req_t *req = add_req(pf, feature, REQ_GL_VERSION);
+
+	ASSERT3S(major, >, 0);
+	ASSERT3S(minor, >=, 0);
+	req->ver[0] = major;
+	req->ver[1] = minor;
+}
+
+/*
+ * Requires that the OpenGL context supports extension `ext' (e.g.
+ * "GL_ARB_texture_storage").
+ */
+void
+preflight_add_gl_ext(preflight_t *pf, const char *feature, const char *ext)
+{
+	ASSERT(ext != NULL);
+	add_req(pf, feature, REQ_GL_EXT)->str = safe_strdup(ext);
+}
+
+static bool_t
+check_req(const req_t *req, char **desc)
+{
+	bool_t isdir, exists;
+
+	switch (req->type) {
+	case REQ_FILE:
+	case REQ_DIR:
+		exists = file_exists(req->str, &isdir);
+		*desc = sprintf_alloc("%s %s", req->type == REQ_DIR ?
+		    "directory" : "file", req->str);
+		if (!exists)
+			return (B_FALSE);
+		if (isdir != (req->type == REQ_DIR)) {
+			size_t len = strlen(*desc);
+
+			append_format(desc, &len, " (%s)", isdir ?
+			    "is a directory" : "not a directory");
+			return (B_FALSE);
+		}
+		return (B_TRUE);
+	case REQ_XP_VERSION: {
+		int xp_ver, xplm_ver;
+		XPLMHostApplicationID host_id;
+		size_t len = 0;
+
+		XPLMGetVersions(&xp_ver, &xplm_ver, &host_id);
+		if (req->ver[0] > 0) {
+			append_format(desc, &len, "X-Plane %d.%02d%s",
+			    req->ver[0] / 1000, (req->ver[0] % 1000) / 10,
+			    req->ver[1] > 0 ? " / " : "");
+		}
+		if (req->ver[1] > 0)
+			append_format(desc, &len, "XPLM %d", req->ver[1]);
+		append_format(desc, &len, " or later (have %d.%02d / %d)",
+		    xp_ver / 1000, (xp_ver % 1000) / 10, xplm_ver);
+		return (xp_ver >= req->ver[0] && xplm_ver >= req->ver[1]);
+	}
+	case REQ_GL_VERSION: {
+		char name[32];
+
+		snprintf(name, sizeof (name), "GL_VERSION_%d_%d",
+		    req->ver[0], req->ver[1]);
+		*desc = sprintf_alloc("OpenGL %d.%d or later (have %s)",
+		    req->ver[0], req->ver[1], glGetString(GL_VERSION) != NULL ?
+		    (const char *)glGetString(GL_VERSION) : "unknown");
+		return (glewIsSupported(name));
+	}
+	case REQ_GL_EXT:
+		*desc = sprintf_alloc("OpenGL extension %s", req->str);
+		return (glewIsSupported(req->str));
+	}
+	VERIFY_FAIL();
+}
+
+/*
+ * Checks all requirements and logs a report of the ones that failed.
+ * Returns B_TRUE if all requirements of the plugin as a whole (i.e.
+ * those added with a NULL feature) were met, B_FALSE otherwise. Call
+ * preflight_feature_ok to find out which optional features can be used.
+ */
+bool_t
+preflight_run(preflight_t *pf)
+{
+	bool_t core_ok = B_TRUE;
+
+	ASSERT(pf != NULL);
+	ASSERT_MSG(!pf->ran, "%s", "preflight_run called twice");
+
+	for (size_t i = 0; i < pf->num_reqs; i++) {
+		preflight_result_t *res = &pf->results[i];
+		char *desc = NULL;
+
+		res->passed = check_req(&pf->reqs[i], &desc);
+		res->desc = desc;
+		if (res->passed)
+			continue;
+		if (res->feature == NULL) {
+			logMsg("Preflight: requirement not met: %s",
+			    res->desc);
+			core_ok = B_FALSE;
+		} else {
+			logMsg("Preflight: requirement not met: %s, "
+			    "disabling feature %s", res->desc, res->feature);
+		}
+	}
+	pf->ran = B_TRUE;
+
+	return (core_ok);
+}
+
+/*
+ * Returns B_TRUE if all requirements of `feature' were met. Features
+ * without any requirements are always OK. Passing NULL checks the
+ * requirements of the plugin as a whole.
+ */
+bool_t
+preflight_feature_ok(const preflight_t *pf, const char *feature)
+{
+	ASSERT(pf != NULL);
+	ASSERT_MSG(pf->ran, "%s", "preflight_run must be called first");
+
+	for (size_t i = 0; i < pf->num_reqs; i++) {
+		const preflight_result_t *res = &pf->results[i];
+
+		if (res->passed)
+			continue;
+		if (feature == NULL ? res->feature == NULL :
+		    (res->feature != NULL &&
+		    strcmp(res->feature, feature) == 0))
+			return (B_FALSE);
+	}
+	return (B_TRUE);
+}
+
+/*
+ * Returns the result of every requirement check, in the order in which
+ * the requirements were added. The returned array remains valid until
+ * the checker is freed.
+ */
+const preflight_result_t *
+preflight_get_results(const preflight_t *pf, size_t *num)
+{
+	ASSERT(pf != NULL);
+	ASSERT_MSG(pf->ran, "%s", "preflight_run must be called first");
+	ASSERT(num != NULL);
+	*num = pf->num_reqs;
+	return (pf->results);
+}
+
+/*
+ * Returns a human-readable multi-line report of all requirement checks.
+ * The returned string must be freed using lacf_free.
+ */
+char *
+preflight_get_report(const preflight_t *pf)
+{
+	char *report = NULL;
+	size_t len = 0, num_ok = 0;
+
+	ASSERT(pf != NULL);
+	ASSERT_MSG(pf->ran, "%s", "preflight_run must be called first");
+
+	for (size_t i = 0; i < pf->num_reqs; i++) {
+		if (pf->results[i].passed)
+			num_ok++;
+	}
+	append_format(&report, &len, "%d of %d requirements met:\n",
+	    (int)num_ok, (int)pf->num_reqs);
+	for (size_t i = 0; i < pf->num_reqs; i++) {
+		const preflight_result_t *res = &pf->results[i];
+
+		append_format(&report, &len, "[%s] %s (%s%s)\n",
+		    res->passed ? " OK " : "FAIL", res->desc,
+		    res->feature != NULL ? "feature " : "required",
+		    res->feature != NULL ? res->feature : "");
+	}
+
+	return (report);
+}
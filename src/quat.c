@@ -0,0 +1,215 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <math.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/helpers.h>
+#include <acfutils/math.h>
+#include <acfutils/quat.h>
+
+/*
+ * Attitude quaternions in this module rotate vectors from the aircraft
+ * body frame into the local North-East-Down (NED) frame. The body frame
+ * has its X axis pointing forward out of the nose, its Y axis out along
+ * the right wing and its Z axis pointing down. Euler angles follow the
+ * usual aerospace convention: the body is first rotated by heading about
+ * the down axis, then by pitch about the new right axis and finally by
+ * roll about the new forward axis.
+ *
+ * Quaternions are a more robust representation for attitude math than
+ * Euler angles: they don't suffer from gimbal lock at +-90 degrees of
+ * pitch and can be smoothly interpolated using quat_slerp.
+ */
+
+/*
+ * Constructs an attitude quaternion from Euler angles.
+ */
+quat_t
+quat_from_euler(euler_t euler)
+{
+	double cp = cos(DEG2RAD(euler.pitch) / 2);
+	double sp = sin(DEG2RAD(euler.pitch) / 2);
+	double cr = cos(DEG2RAD(euler.roll) / 2);
+	double sr = sin(DEG2RAD(euler.roll) / 2);
+	double cy = cos(DEG2RAD(euler.hdg) / 2);
+	double sy = sin(DEG2RAD(euler.hdg) / 2);
+
+	return (QUAT(cr * cp * cy + sr * sp * sy,
+	    sr * cp * cy - cr * sp * sy,
+	    cr * sp * cy + sr * cp * sy,
+	    cr * cp * sy - sr * sp * cy));
+}
+
+/*
+ * Converts an attitude quaternion into Euler angles. At +-90 degrees of
+ * pitch, heading and roll become indistinguishable (gimbal lock), so
+ * roll is returned as zero and the entire rotation about the vertical
+ * axis is returned in the heading. The returned heading is normalized
+ * into the [0,360) range.
+ */
+euler_t
+quat_to_euler(quat_t q)
+{
+	const double gimbal_lock_eps = 1e-9;
+	double sin_pitch;
+	euler_t e;
+
+	q = quat_unit(q);
+	sin_pitch = 2 * (q.w * q.y - q.z * q.x);
+	if (fabs(sin_pitch) >= 1 - gimbal_lock_eps) {
+		/*
+		 * Only the difference (nose up) or sum (nose down) of
+		 * heading and roll is defined here.
+		 */
+		e.pitch = (sin_pitch > 0 ? 90 : -90);
+		e.roll = 0;
+		e.hdg = normalize_hdg(RAD2DEG((sin_pitch > 0 ? -2 : 2) *
+		    atan2(q.x, q.w)));
+		return (e);
+	}
+	e.roll = RAD2DEG(atan2(2 * (q.w * q.x + q.y * q.z),
+	    1 - 2 * (POW2(q.x) + POW2(q.y))));
+	e.pitch = RAD2DEG(asin(sin_pitch));
+	e.hdg = normalize_hdg(RAD2DEG(atan2(2 * (q.w * q.z + q.x * q.y),
+	    1 - 2 * (POW2(q.y) + POW2(q.z)))));
+
+	return (e);
+}
+
+/*
+ * Constructs a quaternion representing a rotation by `angle' degrees
+ * about `axis'. The axis doesn't need to be a unit vector, but it must
+ * not be a null vector.
+ */
+quat_t
+quat_from_axis_angle(vect3_t axis, double angle)
+{
+	double s = sin(DEG2RAD(angle) / 2);
+
+	ASSERT3F(vect3_abs(axis), >, 0);
+	axis = vect3_unit(axis, NULL);
+
+	return (QUAT(cos(DEG2RAD(angle) / 2), axis.x * s, axis.y * s,
+	    axis.z * s));
+}
+
+/*
+ * Multiplies two quaternions. The resulting rotation is the rotation
+ * `b' followed by the rotation `a'.
+ */
+quat_t
+quat_mul(quat_t a, quat_t b)
+{
+	return (QUAT(a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z,
+	    a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+	    a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+	    a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w));
+}
+
+/*
+ * Returns the conjugate of a quaternion. For unit quaternions, this is
+ * the inverse rotation.
+ */
+quat_t
+quat_conj(quat_t q)
+{
+	return (QUAT(q.w, -q.x, -q.y, -q.z));
+}
+
+double
+quat_abs(quat_t q)
+{
+	return (sqrt(POW2(q.w) + POW2(q.x) + POW2(q.y) + POW2(q.z)));
+}
+
+/*
+ * Normalizes a quaternion to unit length. Use this periodically when
+ * repeatedly multiplying quaternions, to avoid accumulating rounding
+ * errors.
+ */
+quat_t
+quat_unit(quat_t q)
+{
+	double l = quat_abs(q);
+
+	ASSERT3F(l, >, 0);
+	return (QUAT(q.w / l, q.x / l, q.y / l, q.z / l));
+}
+
+/*
+ * Spherical linear interpolation between two attitudes. Always takes
+ * the shortest path between the attitudes.
+ *
+ * @param t Interpolation parameter. 0 returns `a', 1 returns `b'.
+ */
+quat_t
+quat_slerp(quat_t a, quat_t b, double t)
+{
+	double dot, theta, sa, sb;
+
+	a = quat_unit(a);
+	b = quat_unit(b);
+	dot = a.w * b.w + a.x * b.x + a.y * b.y + a.z * b.z;
+	/* q and -q are the same rotation, so go the shorter way around */
+	if (dot < 0) {
+		b = QUAT(-b.w, -b.x, -b.y, -b.z);
+		dot = -dot;
+	}
+	if (dot > 0.9995) {
+		/*
+		 * The attitudes are nearly identical, so plain linear
+		 * interpolation is accurate and avoids dividing by a
+		 * near-zero sine.
+		 */
+		return (quat_unit(QUAT(wavg(a.w, b.w, t), wavg(a.x, b.x, t),
+		    wavg(a.y, b.y, t), wavg(a.z, b.z, t))));
+	}
+	theta = acos(dot);
+	sa = sin((1 - t) * theta) / sin(theta);
+	sb = sin(t * theta) / sin(theta);
+
+	return (QUAT(sa * a.w + sb * b.w, sa * a.x + sb * b.x,
+	    sa * a.y + sb * b.y, sa * a.z + sb * b.z));
+}
+
+/*
+ * Rotates a vector by a unit quaternion. For attitude quaternions, this
+ * converts a vector from the body frame into the NED frame.
+ */
+vect3_t
+quat_rot(quat_t q, vect3_t v)
+{
+	/* v' = v + 2w(u x v) + 2u x (u x v), where u = (x, y, z) */
+	vect3_t u = VECT3(q.x, q.y, q.z);
+	vect3_t t = vect3_scmul(vect3_xprod(u, v), 2);
+
+	return (vect3_add(vect3_add(v, vect3_scmul(t, q.w)),
+	    vect3_xprod(u, t)));
+}
+
+/*
+ * Rotates a vector by the inverse of a unit quaternion. For attitude
+ * quaternions, this converts a vector from the NED frame into the body
+ * frame.
+ */
+vect3_t
+quat_rot_inv(quat_t q, vect3_t v)
+{
+	return (quat_rot(quat_conj(q), v));
+}
@@ -0,0 +1,206 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <string.h>
+
+#include <acfutils/range.h>
+#include <acfutils/safe_alloc.h>
+
+struct envelope_s {
+	vect2_t		*pts;		/* terminated by NULL_VECT2 */
+	size_t		num_pts;
+	range_t		x;
+	range_t		y;
+};
+
+/*
+ * Reads a range from a configuration. The range's limits are stored
+ * under the keys "<key>.min" and "<key>.max".
+ *
+ * @return B_TRUE if both limits were found, B_FALSE otherwise, in which
+ *	case `r' is left unmodified.
+ */
+bool_t
+range_conf_get(const conf_t *conf, const char *key, range_t *r)
+{
+	double min, max;
+
+	ASSERT(conf != NULL);
+	ASSERT(key != NULL);
+	ASSERT(r != NULL);
+
+	if (!conf_get_d_v(conf, "%s.min", &min, key) ||
+	    !conf_get_d_v(conf, "%s.max", &max, key))
+		return (B_FALSE);
+	*r = RANGE(min, max);
+
+	return (B_TRUE);
+}
+
+/*
+ * Stores a range in a configuration in a form suitable for
+ * range_conf_get.
+ */
+void
+range_conf_set(conf_t *conf, const char *key, range_t r)
+{
+	ASSERT(conf != NULL);
+	ASSERT(key != NULL);
+	conf_set_d_v(conf, "%s.min", r.min, key);
+	conf_set_d_v(conf, "%s.max", r.max, key);
+}
+
+/*
+ * Creates a 2D envelope, such as an aircraft's weight-vs-CG envelope.
+ * The envelope is a closed polygon, whose last point connects back to
+ * its first point.
+ *
+ * @param pts The polygon's points, in either clockwise or counter-
+ *	clockwise order. The points are copied, so the caller retains
+ *	ownership of the array.
+ * @param num_pts Number of points in `pts'. Must be at least 3.
+ *
+ * @return The new envelope. Use envelope_free to free it.
+ */
+envelope_t *
+envelope_alloc(const vect2_t *pts, size_t num_pts)
+{
+	envelope_t *env = safe_calloc(1, sizeof (*env));
+
+	ASSERT(pts != NULL);
+	ASSERT3U(num_pts, >=, 3);
+
+	env->pts = safe_calloc(num_pts + 1, sizeof (*env->pts));
+	memcpy(env->pts, pts, num_pts * sizeof (*pts));
+	env->pts[num_pts] = NULL_VECT2;
+	env->num_pts = num_pts;
+	env->x = RANGE_EMPTY;
+	env->y = RANGE_EMPTY;
+	for (size_t i = 0; i < num_pts; i++) {
+		ASSERT(!IS_NULL_VECT2(pts[i]));
+		env->x = range_union(env->x, RANGE(pts[i].x, pts[i].x));
+		env->y = range_union(env->y, RANGE(pts[i].y, pts[i].y));
+	}
+
+	return (env);
+}
+
+void
+envelope_free(envelope_t *env)
+{
+	if (env == NULL)
+		return;
+	free(env->pts);
+	free(env);
+}
+
+/*
+ * Returns B_TRUE if `pt' lies inside of the envelope.
+ */
+bool_t
+envelope_contains(const envelope_t *env, vect2_t pt)
+{
+	ASSERT(env != NULL);
+	if (!range_contains(env->x, pt.x) || !range_contains(env->y, pt.y))
+		return (B_FALSE);
+	return (point_in_poly(pt, env->pts));
+}
+
+/*
+ * Returns `pt' if it lies inside of the envelope, otherwise returns the
+ * point on the envelope's boundary closest to `pt'.
+ */
+vect2_t
+envelope_clamp(const envelope_t *env, vect2_t pt)
+{
+	vect2_t best = NULL_VECT2;
+	double best_dist = INFINITY;
+
+	ASSERT(env != NULL);
+	if (envelope_contains(env, pt))
+		return (pt);
+
+	for (size_t i = 0; i < env->num_pts; i++) {
+		vect2_t a = env->pts[i];
+		vect2_t ab = vect2_sub(env->pts[(i + 1) % env->num_pts], a);
+		double len2 = vect2_dotprod(ab, ab);
+		double t = (len2 > 0 ? clamp(vect2_dotprod(vect2_sub(pt, a),
+		    ab) / len2, 0, 1) : 0);
+		vect2_t p = vect2_add(a, vect2_scmul(ab, t));
+		double dist = vect2_dist(p, pt);
+
+		if (dist < best_dist) {
+			best = p;
+			best_dist = dist;
+		}
+	}
+
+	return (best);
+}
+
+/*
+ * Returns the range of X values spanned by the envelope.
+ */
+range_t
+envelope_range_x(const envelope_t *env)
+{
+	ASSERT(env != NULL);
+	return (env->x);
+}
+
+/*
+ * Returns the range of Y values spanned by the envelope.
+ */
+range_t
+envelope_range_y(const envelope_t *env)
+{
+	ASSERT(env != NULL);
+	return (env->y);
+}
+
+/*
+ * Returns the range of Y values spanned by the envelope at a particular
+ * X coordinate. For example, in a CG-vs-weight envelope (with CG on the
+ * X axis), this returns the weight limits for a given CG position. If
+ * the envelope isn't convex, this returns the range from the lowest to
+ * the highest boundary crossing. If `x' lies outside of the envelope,
+ * the returned range is empty.
+ */
+range_t
+envelope_range_y_at(const envelope_t *env, double x)
+{
+	range_t r = RANGE_EMPTY;
+
+	ASSERT(env != NULL);
+	for (size_t i = 0; i < env->num_pts; i++) {
+		vect2_t a = env->pts[i];
+		vect2_t b = env->pts[(i + 1) % env->num_pts];
+
+		if (!range_contains(RANGE(MIN(a.x, b.x), MAX(a.x, b.x)), x))
+			continue;
+		if (a.x == b.x) {
+			r = range_union(r, RANGE(MIN(a.y, b.y),
+			    MAX(a.y, b.y)));
+		} else {
+			double y = fx_lin(x, a.x, a.y, b.x, b.y);
+			r = range_union(r, RANGE(y, y));
+		}
+	}
+
+	return (r);
+}
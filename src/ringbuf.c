@@ -0,0 +1,275 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <string.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/ringbuf.h>
+#include <acfutils/safe_alloc.h>
+#include <acfutils/thread.h>
+
+struct ringbuf_s {
+	size_t		elem_sz;
+	uint32_t	cap;
+	unsigned	flags;
+	uint8_t		*buf;
+	/*
+	 * Free-running element counters. They are only ever incremented
+	 * and are allowed to wrap around, which works out, because the
+	 * capacity is a power of 2.
+	 */
+	atomic32_t	head;		/* next slot to write */
+	atomic32_t	tail;		/* next slot to read */
+	/*
+	 * Per-slot sequence numbers. The slot which is to hold element
+	 * `idx' has sequence number `idx' while it's free for writing,
+	 * `idx + 1' once the element has been written and `idx + cap'
+	 * once the element has been popped or dropped, at which point the
+	 * slot is free for writing the element one lap later. Producers
+	 * reserve a slot by advancing `head' and publish it by advancing
+	 * its sequence number, so they never need to wait for each other.
+	 */
+	atomic32_t	*seq;
+	atomic64_t	num_dropped;
+};
+
+/*
+ * Allocates a ring buffer. Ring buffers are meant for passing small
+ * pieces of data (e.g. sensor samples or log entries) from one or more
+ * producer threads to a single consumer thread (e.g. the flight loop),
+ * without allocating memory or blocking the consumer.
+ *
+ * Elements are copied into and out of the ring buffer by value, so they
+ * must be plain data structures. The consumer is lock-free: it never
+ * waits for a producer, even if a producer is preempted in the middle
+ * of a push (elements pushed after such a push simply become visible
+ * once it completes). With a single producer (the default), the producer
+ * is also lock-free. If you pass RINGBUF_MULTI_PROD, any number of threads
+ * may push into the ring buffer. The producers then reserve their slots
+ * using an atomic compare-and-swap, so they don't block each other
+ * either, except when the ring buffer is full in overwrite mode and the
+ * oldest element is still being pushed by a preempted producer.
+ *
+ * When the ring buffer is full, by default ringbuf_push fails. If you
+ * pass RINGBUF_OVERWRITE, the oldest element is dropped instead, which
+ * is usually what you want for streams of samples, where only the most
+ * recent ones matter.
+ *
+ * Typical usage:
+ *
+ *	ringbuf_t *rb = ringbuf_alloc(sizeof (sample_t), 64,
+ *	    RINGBUF_OVERWRITE);
+ *	...in the worker thread:
+ *	ringbuf_push(rb, &sample);
+ *	...in the flight loop:
+ *	sample_t sample;
+ *	while (ringbuf_pop(rb, &sample))
+ *		...process sample...
+ *
+ * @param elem_sz Size of each element in bytes.
+ * @param cap Capacity of the ring buffer in elements. Must be a power of 2.
+ * @param flags Bitwise OR of ringbuf_flags_t values, or 0.
+ *
+ * @return The new ring buffer. Use ringbuf_free to free it.
+ */
+ringbuf_t *
+ringbuf_alloc(size_t elem_sz, size_t cap, unsigned flags)
+{
+	ringbuf_t *rb = safe_calloc(1, sizeof (*rb));
+
+	ASSERT(elem_sz != 0);
+	ASSERT(cap != 0);
+	ASSERT_MSG((cap & (cap - 1)) == 0, "Ring buffer capacity %d must be "
+	    "a power of 2", (int)cap);
+	ASSERT3U(cap, <=, 1u << 30);
+	ASSERT0(flags & ~(RINGBUF_MULTI_PROD | RINGBUF_OVERWRITE));
+
+	rb->elem_sz = elem_sz;
+	rb->cap = cap;
+	rb->flags = flags;
+	rb->buf = safe_calloc(cap, elem_sz);
+	rb->seq = safe_calloc(cap, sizeof (*rb->seq));
+	for (uint32_t i = 0; i < cap; i++)
+		rb->seq[i] = i;
+	memory_barrier();
+
+	return (rb);
+}
+
+/*
+ * Frees a ring buffer. No other threads may be using it anymore.
+ */
+void
+ringbuf_free(ringbuf_t *rb)
+{
+	if (rb == NULL)
+		return;
+	free(rb->buf);
+	free(rb->seq);
+	free(rb);
+}
+
+static inline void *
+slot(ringbuf_t *rb, uint32_t idx)
+{
+	return (&rb->buf[(idx & (rb->cap - 1)) * rb->elem_sz]);
+}
+
+/*
+ * Pushes an element into the ring buffer. Unless the ring buffer was
+ * created with RINGBUF_MULTI_PROD, only one thread may push elements.
+ *
+ * @return B_TRUE if the element was pushed. B_FALSE if the ring buffer
+ *	is full (only without RINGBUF_OVERWRITE).
+ */
+bool_t
+ringbuf_push(ringbuf_t *rb, const void *elem)
+{
+	uint32_t head;
+	unsigned backoff = 0;
+
+	ASSERT(rb != NULL);
+	ASSERT(elem != NULL);
+
+	for (;;) {
+		uint32_t idx;
+		int32_t dif;
+
+		head = rb->head;
+		idx = head & (rb->cap - 1);
+		memory_barrier();
+		dif = (int32_t)((uint32_t)rb->seq[idx] - head);
+		if (dif == 0) {
+			/* slot is free, try to reserve it */
+			if (!(rb->flags & RINGBUF_MULTI_PROD)) {
+				rb->head = head + 1;
+				break;
+			}
+			if (atomic_cas_32(&rb->head, head, head + 1))
+				break;
+		} else if (dif > 0) {
+			/* another producer reserved the slot, reload head */
+		} else if (!(rb->flags & RINGBUF_OVERWRITE)) {
+			/* full */
+			atomic_inc_64(&rb->num_dropped);
+			return (B_FALSE);
+		} else if (dif == 1 - (int32_t)rb->cap) {
+			uint32_t tail = head - rb->cap;
+			/*
+			 * Drop the oldest element. If the consumer is reading
+			 * it at the moment, its own attempt to advance the
+			 * tail will fail and it will retry with the next
+			 * element.
+			 */
+			if (atomic_cas_32(&rb->tail, tail, tail + 1)) {
+				atomic_inc_64(&rb->num_dropped);
+				rb->seq[idx] = head;
+			} else {
+				spin_backoff(&backoff);
+			}
+		} else {
+			/*
+			 * The oldest element is still being pushed, so we can't
+			 * drop it yet.
+			 */
+			spin_backoff(&backoff);
+		}
+	}
+	memcpy(slot(rb, head), elem, rb->elem_sz);
+	memory_barrier();
+	rb->seq[head & (rb->cap - 1)] = head + 1;
+
+	return (B_TRUE);
+}
+
+/*
+ * Pops the oldest element from the ring buffer. Only one thread may pop
+ * elements.
+ *
+ * @param elem Buffer which will be filled with the element.
+ *
+ * @return B_TRUE if an element was popped, B_FALSE if the ring buffer
+ *	was empty (or the oldest element is still being pushed).
+ */
+bool_t
+ringbuf_pop(ringbuf_t *rb, void *elem)
+{
+	ASSERT(rb != NULL);
+	ASSERT(elem != NULL);
+
+	for (;;) {
+		uint32_t tail = rb->tail;
+		uint32_t idx = tail & (rb->cap - 1);
+
+		memory_barrier();
+		if ((uint32_t)rb->seq[idx] != tail + 1) {
+			memory_barrier();
+			/* a producer may have dropped the element meanwhile */
+			if ((uint32_t)rb->tail != tail)
+				continue;
+			return (B_FALSE);
+		}
+		memory_barrier();
+		memcpy(elem, slot(rb, tail), rb->elem_sz);
+		memory_barrier();
+		/*
+		 * In overwrite mode, a producer might have dropped this
+		 * element while we were copying it, in which case the copy
+		 * may be torn and we must retry with the next element.
+		 */
+		if (atomic_cas_32(&rb->tail, tail, tail + 1)) {
+			rb->seq[idx] = tail + rb->cap;
+			return (B_TRUE);
+		}
+	}
+}
+
+/*
+ * Returns the number of elements in the ring buffer. Since other threads
+ * may be pushing & popping at the same time, this is only a snapshot.
+ */
+size_t
+ringbuf_get_count(const ringbuf_t *rb)
+{
+	uint32_t tail, head;
+
+	ASSERT(rb != NULL);
+	tail = rb->tail;
+	memory_barrier();
+	head = rb->head;
+	return (MIN(head - tail, rb->cap));
+}
+
+size_t
+ringbuf_get_cap(const ringbuf_t *rb)
+{
+	ASSERT(rb != NULL);
+	return (rb->cap);
+}
+
+/*
+ * Returns the number of elements which were dropped because the ring
+ * buffer was full. In overwrite mode these are the overwritten oldest
+ * elements, otherwise these are the elements which failed to push.
+ */
+uint64_t
+ringbuf_get_num_dropped(const ringbuf_t *rb)
+{
+	ASSERT(rb != NULL);
+	return (rb->num_dropped);
+}
@@ -0,0 +1,188 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <math.h>
+#include <string.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/avl.h>
+#include <acfutils/helpers.h>
+#include <acfutils/rwysel.h>
+#include <acfutils/safe_alloc.h>
+
+/*
+ * Score weights. The headwind component dominates, so that in any
+ * significant wind the runway most into the wind wins. Preferential
+ * runways and the runway currently in use get a bonus, which lets them
+ * stay in use in light or crosswind conditions. Runway length breaks
+ * ties, such as in calm winds.
+ */
+#define	HEADWIND_WEIGHT		10	/* per knot */
+#define	LENGTH_WEIGHT		5	/* per kilometer */
+#define	PREF_BONUS		30
+#define	CUR_BONUS		15
+#define	LIMITS_PENALTY		1000
+
+/*
+ * Initializes runway selection parameters to their defaults.
+ */
+void
+rwysel_params_init(rwysel_params_t *params)
+{
+	ASSERT(params != NULL);
+	memset(params, 0, sizeof (*params));
+	params->calm_wind = 5;
+	params->max_tailwind = 5;
+	params->max_xwind = 25;
+}
+
+static bool_t
+is_pref(const rwysel_params_t *params, const char *id, rwysel_op_t op)
+{
+	for (size_t i = 0; i < params->num_prefs; i++) {
+		const rwysel_pref_t *pref = &params->prefs[i];
+
+		if (strcmp(pref->id, id) == 0)
+			return (op == RWYSEL_DEP ? pref->dep : pref->arr);
+	}
+	return (B_FALSE);
+}
+
+static void
+score_cand(rwysel_cand_t *cand, double wind_dir, double wind_spd,
+    rwysel_op_t op, const rwysel_params_t *params)
+{
+	bool_t calm = (isnan(wind_dir) || wind_spd < params->calm_wind);
+
+	if (isnan(wind_dir)) {
+		/*
+		 * Variable winds can come from any direction, so the
+		 * full wind speed could be a tailwind or crosswind.
+		 */
+		cand->headwind = -wind_spd;
+		cand->xwind = wind_spd;
+	} else {
+		double rhdg = DEG2RAD(rel_hdg(cand->end->hdg, wind_dir));
+
+		cand->headwind = wind_spd * cos(rhdg);
+		cand->xwind = fabs(wind_spd * sin(rhdg));
+	}
+	cand->within_limits = (-cand->headwind <= params->max_tailwind &&
+	    cand->xwind <= params->max_xwind);
+
+	cand->score = LENGTH_WEIGHT * (cand->rwy->length / 1000);
+	if (!calm)
+		cand->score += HEADWIND_WEIGHT * cand->headwind;
+	if (!cand->within_limits)
+		cand->score -= LIMITS_PENALTY;
+	if (is_pref(params, cand->end->id, op))
+		cand->score += PREF_BONUS;
+	if (params->cur_id != NULL &&
+	    strcmp(params->cur_id, cand->end->id) == 0)
+		cand->score += CUR_BONUS;
+}
+
+static int
+cand_compar(const void *a, const void *b)
+{
+	const rwysel_cand_t *ca = a, *cb = b;
+
+	if (ca->score > cb->score)
+		return (-1);
+	if (ca->score < cb->score)
+		return (1);
+	return (strcmp(ca->end->id, cb->end->id));
+}
+
+/*
+ * Ranks the runway ends of an airport by their suitability for use in
+ * the current wind. Runways which exceed the tailwind or crosswind limits
+ * are still returned (with `within_limits' set to B_FALSE), but they are
+ * always ranked below runways within limits. Water runways and runways
+ * shorter than `min_len' are never returned.
+ *
+ * @param arpt The airport. Must have been loaded by airportdb, so that
+ *	its runway information is available.
+ * @param wind_dir Wind direction in degrees true. Pass NAN for variable
+ *	winds.
+ * @param wind_spd Wind speed in knots. To select runways conservatively
+ *	in gusty conditions, pass the gust speed.
+ * @param op Whether the runway is for departures or arrivals. This only
+ *	matters for preferential runways.
+ * @param params Selection parameters. Pass NULL to use the defaults
+ *	(see rwysel_params_init).
+ * @param cands Output array of candidates, sorted from best to worst.
+ * @param max_cands Capacity of `cands'. Twice the number of runways of
+ *	the airport is always sufficient.
+ *
+ * @return The number of candidates filled into `cands'.
+ */
+size_t
+rwysel_rank(const airport_t *arpt, double wind_dir, double wind_spd,
+    rwysel_op_t op, const rwysel_params_t *params, rwysel_cand_t *cands,
+    size_t max_cands)
+{
+	rwysel_params_t dfl_params;
+	rwysel_cand_t *all;
+	size_t n = 0;
+
+	ASSERT(arpt != NULL);
+	ASSERT(cands != NULL || max_cands == 0);
+	if (params == NULL) {
+		rwysel_params_init(&dfl_params);
+		params = &dfl_params;
+	}
+
+	all = safe_calloc(2 * avl_numnodes(&arpt->rwys) + 1, sizeof (*all));
+	for (const runway_t *rwy = avl_first(&arpt->rwys); rwy != NULL;
+	    rwy = AVL_NEXT(&arpt->rwys, rwy)) {
+		if (rwy->surf == RWY_SURF_WATER ||
+		    rwy->length < params->min_len)
+			continue;
+		for (int i = 0; i < 2; i++) {
+			all[n].rwy = rwy;
+			all[n].end = &rwy->ends[i];
+			score_cand(&all[n], wind_dir, wind_spd, op, params);
+			n++;
+		}
+	}
+	qsort(all, n, sizeof (*all), cand_compar);
+	n = MIN(n, max_cands);
+	/* `cands' may be NULL with max_cands == 0 */
+	if (n != 0)
+		memcpy(cands, all, n * sizeof (*all));
+	free(all);
+
+	return (n);
+}
+
+/*
+ * Convenience wrapper around rwysel_rank, which only returns the best
+ * runway end.
+ *
+ * @return B_TRUE if a runway was selected and filled into `cand',
+ *	B_FALSE if the airport has no suitable runways.
+ */
+bool_t
+rwysel_select(const airport_t *arpt, double wind_dir, double wind_spd,
+    rwysel_op_t op, const rwysel_params_t *params, rwysel_cand_t *cand)
+{
+	ASSERT(cand != NULL);
+	return (rwysel_rank(arpt, wind_dir, wind_spd, op, params,
+	    cand, 1) != 0);
+}
@@ -0,0 +1,172 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <math.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/math.h>
+#include <acfutils/sensorpos.h>
+
+/*
+ * Sensors and antennas are rarely located at the aircraft's center of
+ * gravity, which is where flight models usually compute the aircraft's
+ * state. The offset between the two is the sensor's "lever arm", and
+ * the functions in this module translate quantities between the CG and
+ * the sensor location. This affects e.g.:
+ *
+ *	- radio altimeters, whose antennas are usually well aft of the CG,
+ *	  so they read less as the aircraft rotates for takeoff or flares.
+ *	- GPS receivers, whose antennas sit on top of the fuselage.
+ *	- angle of attack vanes and pitot tubes near the nose, which sense
+ *	  additional airflow as the aircraft pitches and yaws.
+ *	- IRS accelerometers, which sense the centripetal and tangential
+ *	  accelerations of rotation when not placed at the CG.
+ *
+ * All vectors are in the aircraft body frame, with the X axis pointing
+ * forward, Y out along the right wing and Z down (same as in quat.h).
+ * Distances are in meters, velocities in m/s, accelerations in m/s^2,
+ * angular rates in rad/s and angular accelerations in rad/s^2. The body
+ * angular rate vector `omega' is (roll rate, pitch rate, yaw rate),
+ * positive right wing down, nose up and nose right respectively.
+ */
+
+/*
+ * Computes a sensor's lever arm from its position and the CG position,
+ * both in the same body-fixed coordinates (e.g. relative to the nose
+ * or the aircraft's reference datum, converted to the axis convention
+ * above). Since the CG moves with loading and fuel burn, recompute the
+ * lever arm whenever the CG changes.
+ */
+vect3_t
+sensorpos_arm(vect3_t sensor_pos, vect3_t cg_pos)
+{
+	return (vect3_sub(sensor_pos, cg_pos));
+}
+
+/*
+ * Computes the velocity of a sensor from the velocity of the CG `vel'
+ * and the body angular rate `omega'.
+ */
+vect3_t
+sensorpos_vel(vect3_t vel, vect3_t omega, vect3_t arm)
+{
+	return (vect3_add(vel, vect3_xprod(omega, arm)));
+}
+
+/*
+ * Computes the acceleration sensed at a sensor location from the
+ * acceleration at the CG `accel', the body angular rate `omega' and the
+ * body angular acceleration `omega_dot'. This adds the tangential and
+ * centripetal acceleration terms of the rotating aircraft.
+ */
+vect3_t
+sensorpos_accel(vect3_t accel, vect3_t omega, vect3_t omega_dot, vect3_t arm)
+{
+	return (vect3_add(vect3_add(accel, vect3_xprod(omega_dot, arm)),
+	    vect3_xprod(omega, vect3_xprod(omega, arm))));
+}
+
+/*
+ * Computes the local airflow sensed by an air data sensor (such as an
+ * angle of attack vane or pitot probe).
+ *
+ * @param vel Velocity of the CG relative to the air mass, in the body
+ *	frame.
+ * @param omega Body angular rate.
+ * @param arm Lever arm of the sensor.
+ * @param tas Optional return of the local true airspeed in m/s.
+ * @param aoa Optional return of the local angle of attack in degrees.
+ * @param aos Optional return of the local angle of sideslip in degrees
+ *	(positive with the airflow coming from the right).
+ */
+void
+sensorpos_airflow(vect3_t vel, vect3_t omega, vect3_t arm, double *tas,
+    double *aoa, double *aos)
+{
+	vect3_t v = sensorpos_vel(vel, omega, arm);
+	double spd = vect3_abs(v);
+
+	if (tas != NULL)
+		*tas = spd;
+	if (aoa != NULL)
+		*aoa = RAD2DEG(atan2(v.z, v.x));
+	if (aos != NULL)
+		*aos = (spd > 0 ? RAD2DEG(asin(clamp(v.y / spd, -1, 1))) : 0);
+}
+
+/*
+ * Computes the height above ground of a radio altimeter antenna from
+ * the height of the CG above ground `hgt' (in meters) and the aircraft
+ * attitude. This assumes level terrain below the aircraft.
+ */
+double
+sensorpos_cg2ra(double hgt, euler_t att, vect3_t arm)
+{
+	/* Z in the NED frame points down, so it reduces the height */
+	return (hgt - quat_rot(quat_from_euler(att), arm).z);
+}
+
+/*
+ * Inverse of sensorpos_cg2ra: computes the height above ground of the
+ * CG from a radio altimeter reading.
+ */
+double
+sensorpos_ra2cg(double ra, euler_t att, vect3_t arm)
+{
+	return (ra + quat_rot(quat_from_euler(att), arm).z);
+}
+
+/*
+ * Displaces a geographic position by a local NED offset in meters. The
+ * lever arms involved are tiny compared to the Earth's radius, so a
+ * spherical approximation is more than adequate.
+ */
+static geo_pos3_t
+geo_ned_offset(geo_pos3_t pos, vect3_t ned)
+{
+	double lat_r = DEG2RAD(pos.lat);
+
+	pos.lat += RAD2DEG(ned.x / EARTH_MSL);
+	if (fabs(cos(lat_r)) > 1e-9)
+		pos.lon += RAD2DEG(ned.y / (EARTH_MSL * cos(lat_r)));
+	pos.elev -= ned.z;
+
+	return (pos);
+}
+
+/*
+ * Computes the geographic position of a sensor (e.g. a GPS antenna) from
+ * the position of the CG and the aircraft attitude. Elevations are in
+ * meters.
+ */
+geo_pos3_t
+sensorpos_cg2geo(geo_pos3_t pos, euler_t att, vect3_t arm)
+{
+	return (geo_ned_offset(pos, quat_rot(quat_from_euler(att), arm)));
+}
+
+/*
+ * Inverse of sensorpos_cg2geo: computes the position of the CG from the
+ * position of a sensor (e.g. the position reported by a GPS receiver).
+ */
+geo_pos3_t
+sensorpos_geo2cg(geo_pos3_t pos, euler_t att, vect3_t arm)
+{
+	return (geo_ned_offset(pos, vect3_neg(quat_rot(quat_from_euler(att),
+	    arm))));
+}
@@ -0,0 +1,352 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <ctype.h>
+#include <errno.h>
+#include <stddef.h>
+#include <string.h>
+
+#if	IBM
+#include <windows.h>
+#else	/* !IBM */
+#include <fcntl.h>
+#include <sys/mman.h>
+#include <sys/stat.h>
+#include <unistd.h>
+#endif	/* !IBM */
+
+#include <acfutils/assert.h>
+#include <acfutils/helpers.h>
+#include <acfutils/log.h>
+#include <acfutils/safe_alloc.h>
+#include <acfutils/shmem.h>
+#include <acfutils/thread.h>
+
+#define	SHMEM_MAGIC	0x4c414346u	/* "LACF" */
+#define	SHMEM_VERSION	1
+#define	MAX_READ_TRIES	1000
+
+/*
+ * Header at the start of the shared region. The payload follows on the
+ * next cache line.
+ */
+typedef struct {
+	uint32_t		magic;
+	uint32_t		version;
+	uint64_t		size;
+	volatile uint64_t	seq;
+} shmem_hdr_t;
+
+#define	PAYLOAD_OFF	64
+
+struct shmem_s {
+	char		*name;
+	size_t		size;
+	bool_t		created;
+	shmem_hdr_t	*hdr;
+	uint8_t		*data;
+#if	IBM
+	HANDLE		mapping;
+#endif
+};
+
+/*
+ * Opens a named shared memory region. Shared memory regions are the
+ * fastest way to continuously exchange data (e.g. telemetry) between
+ * two plugins, or between a plugin and an external program, without
+ * having to go through sockets.
+ *
+ * Access to the region uses a "seqlock" protocol: the region contains
+ * a sequence number, which the writer makes odd before it starts
+ * modifying the data and even again once done. Readers copy the data
+ * out and retry if the sequence number was odd or changed during the
+ * copy. This means the writer never blocks, readers never block the
+ * writer and no locks are shared between processes, so a crashing
+ * process can never leave the region locked. The protocol requires
+ * that there is only a single writer to a region. To exchange data in
+ * both directions, open two regions.
+ *
+ * Typical usage:
+ *
+ *	...in the writer:
+ *	shmem_t *shm = shmem_open("my_telemetry", sizeof (telem_t), B_TRUE);
+ *	...every frame:
+ *	shmem_write(shm, &telem, sizeof (telem));
+ *
+ *	...in the reader:
+ *	shmem_t *shm = shmem_open("my_telemetry", sizeof (telem_t), B_FALSE);
+ *	...periodically:
+ *	if (shm != NULL && shmem_read(shm, &telem, sizeof (telem), NULL))
+ *		...use telem...
+ *
+ * Since the region may be shared between programs built with different
+ * compilers, the exchanged data should only consist of fixed-size types
+ * (e.g. int32_t, double) and avoid pointers.
+ *
+ * @param name Name of the region. This must only consist of letters,
+ *	digits, '_', '-' and '.' and shouldn't be longer than 30 characters
+ *	(macOS limits the length of shared memory names).
+ * @param size Size of the data to exchange in bytes. Both sides must
+ *	open the region with the same size.
+ * @param create If B_TRUE, the region is created if it doesn't exist
+ *	yet and is removed from the system when this side closes it. If
+ *	B_FALSE, the region must already exist.
+ *
+ * @return The opened region, or NULL on error (which is logged, unless
+ *	`create' was B_FALSE and the region doesn't exist or its creator
+ *	hasn't finished setting it up yet, in which case simply try again
+ *	later). Use shmem_close to close it.
+ */
+shmem_t *
+shmem_open(const char *name, size_t size, bool_t create)
+{
+	shmem_t *shm;
+	size_t map_size = PAYLOAD_OFF + size;
+	void *map;
+
+	ASSERT(name != NULL);
+	ASSERT(size != 0);
+	for (const char *c = name; *c != '\0'; c++) {
+		ASSERT_MSG(isalnum((unsigned char)*c) || *c == '_' ||
+		    *c == '-' || *c == '.',
+		    "Invalid shared memory region name \"%s\"", name);
+	}
+#if	IBM
+	{
+		char *win_name = sprintf_alloc("Local\\lacf_%s", name);
+		HANDLE mapping;
+
+		if (create) {
+			mapping = CreateFileMappingA(INVALID_HANDLE_VALUE,
+			    NULL, PAGE_READWRITE, (DWORD)((uint64_t)map_size >>
+			    32), (DWORD)map_size, win_name);
+		} else {
+			mapping = OpenFileMappingA(FILE_MAP_ALL_ACCESS,
+			    FALSE, win_name);
+		}
+		if (mapping == NULL) {
+			if (create || GetLastError() != ERROR_FILE_NOT_FOUND) {
+				win_perror(GetLastError(), "Error opening "
+				    "shared memory region %s", name);
+			}
+			free(win_name);
+			return (NULL);
+		}
+		free(win_name);
+		map = MapViewOfFile(mapping, FILE_MAP_ALL_ACCESS, 0, 0,
+		    map_size);
+		if (map == NULL) {
+			win_perror(GetLastError(), "Error mapping shared "
+			    "memory region %s", name);
+			CloseHandle(mapping);
+			return (NULL);
+		}
+		shm = safe_calloc(1, sizeof (*shm));
+		shm->mapping = mapping;
+	}
+#else	/* !IBM */
+	{
+		char *posix_name = sprintf_alloc("/lacf_%s", name);
+		int fd = shm_open(posix_name, O_RDWR | (create ? O_CREAT : 0),
+		    0600);
+		struct stat st;
+
+		if (fd == -1) {
+			if (create || errno != ENOENT) {
+				logMsg("Error opening shared memory region "
+				    "%s: %s", name, strerror(errno));
+			}
+			free(posix_name);
+			return (NULL);
+		}
+		if (fstat(fd, &st) != 0 || (st.st_size == 0 && create &&
+		    ftruncate(fd, map_size) != 0)) {
+			logMsg("Error sizing shared memory region %s: %s",
+			    name, strerror(errno));
+			close(fd);
+			if (create)
+				shm_unlink(posix_name);
+			free(posix_name);
+			return (NULL);
+		}
+		if (st.st_size == 0 && !create) {
+			/* the creator hasn't sized the region yet */
+			close(fd);
+			free(posix_name);
+			return (NULL);
+		}
+		free(posix_name);
+		map = mmap(NULL, map_size, PROT_READ | PROT_WRITE,
+		    MAP_SHARED, fd, 0);
+		close(fd);
+		if (map == MAP_FAILED) {
+			logMsg("Error mapping shared memory region %s: %s",
+			    name, strerror(errno));
+			return (NULL);
+		}
+		shm = safe_calloc(1, sizeof (*shm));
+	}
+#endif	/* !IBM */
+	shm->name = safe_strdup(name);
+	shm->size = size;
+	shm->created = create;
+	shm->hdr = map;
+	shm->data = (uint8_t *)map + PAYLOAD_OFF;
+
+	if (shm->hdr->magic == 0 && create) {
+		/* freshly created region, the OS zero-fills it */
+		shm->hdr->version = SHMEM_VERSION;
+		shm->hdr->size = size;
+		memory_barrier();
+		shm->hdr->magic = SHMEM_MAGIC;
+	} else if (shm->hdr->magic == 0) {
+		/* the creator hasn't initialized the region yet */
+		shm->created = B_FALSE;
+		shmem_close(shm);
+		return (NULL);
+	} else if (shm->hdr->magic != SHMEM_MAGIC ||
+	    shm->hdr->version != SHMEM_VERSION || shm->hdr->size != size) {
+		logMsg("Error opening shared memory region %s: region "
+		    "layout mismatch (magic %x, version %u, size %llu; "
+		    "expected size %llu)", name, (unsigned)shm->hdr->magic,
+		    (unsigned)shm->hdr->version,
+		    (unsigned long long)shm->hdr->size,
+		    (unsigned long long)size);
+		/* don't remove a region which belongs to someone else */
+		shm->created = B_FALSE;
+		shmem_close(shm);
+		return (NULL);
+	}
+	/*
+	 * A region left over by a writer which crashed in the middle of a
+	 * write has an odd sequence number. As the new writer, make it even
+	 * again, otherwise our writes would appear to be consistent while
+	 * in progress.
+	 */
+	if (create && (shm->hdr->seq & 1))
+		shm->hdr->seq++;
+
+	return (shm);
+}
+
+/*
+ * Closes a shared memory region. If the region was opened with
+ * `create' set, the region's name is removed from the system, although
+ * other processes which have it open can continue using it.
+ */
+void
+shmem_close(shmem_t *shm)
+{
+	if (shm == NULL)
+		return;
+#if	IBM
+	UnmapViewOfFile(shm->hdr);
+	CloseHandle(shm->mapping);
+#else	/* !IBM */
+	munmap(shm->hdr, PAYLOAD_OFF + shm->size);
+	if (shm->created) {
+		char *posix_name = sprintf_alloc("/lacf_%s", shm->name);
+		shm_unlink(posix_name);
+		free(posix_name);
+	}
+#endif	/* !IBM */
+	free(shm->name);
+	free(shm);
+}
+
+size_t
+shmem_get_size(const shmem_t *shm)
+{
+	ASSERT(shm != NULL);
+	return (shm->size);
+}
+
+/*
+ * Writes data into the region. Only a single thread in a single process
+ * may write to a region. This never blocks.
+ *
+ * @param size Number of bytes to write from the start of the region.
+ *	Must not be greater than the region's size.
+ */
+void
+shmem_write(shmem_t *shm, const void *data, size_t size)
+{
+	ASSERT(shm != NULL);
+	ASSERT(data != NULL);
+	ASSERT3U(size, <=, shm->size);
+
+	shm->hdr->seq++;	/* odd: write in progress */
+	memory_barrier();
+	memcpy(shm->data, data, size);
+	memory_barrier();
+	shm->hdr->seq++;	/* even: data consistent */
+}
+
+/*
+ * Reads a consistent copy of the data in the region. This never blocks,
+ * but if the writer is writing at the time, the read is retried.
+ *
+ * @param size Number of bytes to read from the start of the region.
+ *	Must not be greater than the region's size.
+ * @param seq Optional return argument, which will be filled with the
+ *	sequence number of the data read. The sequence number increases
+ *	with every write, so you can use it to detect new data.
+ *
+ * @return B_TRUE if a consistent copy was read. B_FALSE if the writer
+ *	hasn't written anything yet, or if a consistent copy couldn't be
+ *	obtained after many retries (e.g. because the writer process
+ *	crashed in the middle of a write).
+ */
+bool_t
+shmem_read(const shmem_t *shm, void *data, size_t size, uint64_t *seq)
+{
+	ASSERT(shm != NULL);
+	ASSERT(data != NULL);
+	ASSERT3U(size, <=, shm->size);
+
+	for (int i = 0; i < MAX_READ_TRIES; i++) {
+		uint64_t seq1 = shm->hdr->seq, seq2;
+
+		if (seq1 == 0)
+			return (B_FALSE);
+		if (seq1 & 1)
+			continue;
+		memory_barrier();
+		memcpy(data, shm->data, size);
+		memory_barrier();
+		seq2 = shm->hdr->seq;
+		if (seq1 == seq2) {
+			if (seq != NULL)
+				*seq = seq1;
+			return (B_TRUE);
+		}
+	}
+	return (B_FALSE);
+}
+
+/*
+ * Returns the current sequence number of the region. This is 0 if
+ * nothing has been written to the region yet. Otherwise, it increases
+ * with every write.
+ */
+uint64_t
+shmem_get_seq(const shmem_t *shm)
+{
+	ASSERT(shm != NULL);
+	return (shm->hdr->seq);
+}
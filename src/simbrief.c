@@ -0,0 +1,408 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <errno.h>
+#include <stddef.h>
+#include <string.h>
+
+#include <curl/curl.h>
+#include <libxml/parser.h>
+#include <libxml/xpath.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/helpers.h>
+#include <acfutils/log.h>
+#include <acfutils/perf.h>
+#include <acfutils/safe_alloc.h>
+#include <acfutils/simbrief.h>
+
+#include "chart_prov_common.h"
+
+#define	SIMBRIEF_URL	"https://www.simbrief.com/api/xml.fetcher.php"
+#define	DL_TIMEOUT	30L		/* seconds */
+#define	REALLOC_STEP	(256 << 10)	/* bytes */
+#define	MAX_DL_SIZE	(16 << 20)	/* bytes */
+
+typedef struct {
+	uint8_t		*buf;
+	size_t		bufcap;
+	size_t		bufsz;
+} dl_info_t;
+
+static size_t
+dl_write(char *ptr, size_t size, size_t nmemb, void *userdata)
+{
+	dl_info_t *dl_info;
+	size_t bytes = size * nmemb;
+
+	ASSERT(userdata != NULL);
+	dl_info = userdata;
+
+	if (dl_info->bufcap < dl_info->bufsz + bytes) {
+		do {
+			dl_info->bufcap += REALLOC_STEP;
+		} while (dl_info->bufcap < dl_info->bufsz + bytes);
+		if (dl_info->bufcap > MAX_DL_SIZE) {
+			logMsg("Error downloading SimBrief OFP: too much data "
+			    "received (%ld bytes)", (long)dl_info->bufcap);
+			return (0);
+		}
+		dl_info->buf = safe_realloc(dl_info->buf, dl_info->bufcap);
+	}
+	memcpy(&dl_info->buf[dl_info->bufsz], ptr, bytes);
+	dl_info->bufsz += bytes;
+
+	return (bytes);
+}
+
+/*
+ * Returns an allocated copy of the text content of the first node matching
+ * the XPath expression `path', or NULL if no such node exists.
+ */
+static char *
+get_str_alloc(xmlXPathContext *ctx, const char *path)
+{
+	xmlXPathObject *obj = xmlXPathEvalExpression((xmlChar *)path, ctx);
+	char *str = NULL;
+
+	if (obj != NULL && obj->nodesetval != NULL &&
+	    obj->nodesetval->nodeNr > 0) {
+		xmlChar *content = xmlNodeGetContent(
+		    obj->nodesetval->nodeTab[0]);
+
+		if (content != NULL) {
+			str = safe_strdup((char *)content);
+			xmlFree(content);
+		}
+	}
+	if (obj != NULL)
+		xmlXPathFreeObject(obj);
+
+	return (str);
+}
+
+/*
+ * Same as get_str_alloc, but copies the text content into `buf'. Returns
+ * B_TRUE if a matching node was found.
+ */
+static bool_t
+get_str(xmlXPathContext *ctx, const char *path, char *buf, size_t cap)
+{
+	char *str = get_str_alloc(ctx, path);
+
+	if (str == NULL)
+		return (B_FALSE);
+	lacf_strlcpy(buf, str, cap);
+	free(str);
+
+	return (B_TRUE);
+}
+
+static double
+get_num(xmlXPathContext *ctx, const char *path)
+{
+	char buf[32];
+
+	if (!get_str(ctx, path, buf, sizeof (buf)))
+		return (0);
+	return (atof(buf));
+}
+
+static void
+load_fix(simbrief_fix_t *fix, const xmlNode *fix_node)
+{
+	memset(fix, 0, sizeof (*fix));
+	fix->pos = NULL_GEO_POS2;
+
+	for (const xmlNode *node = fix_node->children; node != NULL;
+	    node = node->next) {
+		const char *content;
+
+		if (node->children == NULL || node->name == NULL ||
+		    node->children[0].content == NULL)
+			continue;
+		content = (char *)node->children[0].content;
+		if (strcmp((char *)node->name, "ident") == 0) {
+			lacf_strlcpy(fix->ident, content, sizeof (fix->ident));
+		} else if (strcmp((char *)node->name, "type") == 0) {
+			lacf_strlcpy(fix->type, content, sizeof (fix->type));
+		} else if (strcmp((char *)node->name, "via_airway") == 0) {
+			lacf_strlcpy(fix->via, content, sizeof (fix->via));
+		} else if (strcmp((char *)node->name, "pos_lat") == 0) {
+			fix->pos.lat = atof(content);
+		} else if (strcmp((char *)node->name, "pos_long") == 0) {
+			fix->pos.lon = atof(content);
+		} else if (strcmp((char *)node->name, "altitude_feet") == 0) {
+			fix->alt = atoi(content);
+		}
+	}
+}
+
+static void
+load_nodes(xmlXPathContext *ctx, const char *path, simbrief_ofp_t *ofp,
+    void (*load_func)(simbrief_ofp_t *ofp, const xmlNode *node, size_t i,
+    size_t n))
+{
+	xmlXPathObject *obj = xmlXPathEvalExpression((xmlChar *)path, ctx);
+
+	if (obj == NULL)
+		return;
+	if (obj->nodesetval != NULL) {
+		for (int i = 0; i < obj->nodesetval->nodeNr; i++) {
+			load_func(ofp, obj->nodesetval->nodeTab[i], i,
+			    obj->nodesetval->nodeNr);
+		}
+	}
+	xmlXPathFreeObject(obj);
+}
+
+static void
+load_altn(simbrief_ofp_t *ofp, const xmlNode *node, size_t i, size_t n)
+{
+	xmlChar *content;
+
+	if (ofp->altns == NULL) {
+		ofp->altns = safe_calloc(n, sizeof (*ofp->altns));
+		ofp->num_altns = 0;
+	}
+	ASSERT3U(i, <, n);
+	content = xmlNodeGetContent(node);
+	if (content == NULL)
+		return;
+	lacf_strlcpy(ofp->altns[ofp->num_altns], (char *)content,
+	    sizeof (*ofp->altns));
+	xmlFree(content);
+	if (ofp->altns[ofp->num_altns][0] != '\0')
+		ofp->num_altns++;
+}
+
+static void
+load_navlog_fix(simbrief_ofp_t *ofp, const xmlNode *node, size_t i, size_t n)
+{
+	if (ofp->fixes == NULL) {
+		ofp->fixes = safe_calloc(n, sizeof (*ofp->fixes));
+		ofp->num_fixes = 0;
+	}
+	ASSERT3U(i, <, n);
+	load_fix(&ofp->fixes[ofp->num_fixes], node);
+	if (ofp->fixes[ofp->num_fixes].ident[0] != '\0' &&
+	    is_valid_lat(ofp->fixes[ofp->num_fixes].pos.lat) &&
+	    is_valid_lon(ofp->fixes[ofp->num_fixes].pos.lon))
+		ofp->num_fixes++;
+}
+
+static simbrief_ofp_t *
+parse_doc(xmlDoc *doc)
+{
+	simbrief_ofp_t *ofp;
+	xmlXPathContext *ctx;
+	char buf[64];
+	char *route = NULL;
+	double mass_fact = 1;
+
+	ctx = xmlXPathNewContext(doc);
+	if (ctx == NULL) {
+		logMsg("Error parsing SimBrief OFP: cannot create XPath "
+		    "context");
+		return (NULL);
+	}
+	if (get_str(ctx, "/OFP/fetch/status", buf, sizeof (buf)) &&
+	    strcmp(buf, "Success") != 0) {
+		logMsg("Error parsing SimBrief OFP: server returned error "
+		    "\"%s\"", buf);
+		xmlXPathFreeContext(ctx);
+		return (NULL);
+	}
+	if (!get_str(ctx, "/OFP/origin/icao_code", buf, sizeof (buf)) ||
+	    buf[0] == '\0') {
+		logMsg("Error parsing SimBrief OFP: document doesn't look "
+		    "like an OFP (missing origin airport)");
+		xmlXPathFreeContext(ctx);
+		return (NULL);
+	}
+
+	ofp = safe_calloc(1, sizeof (*ofp));
+	lacf_strlcpy(ofp->orig, buf, sizeof (ofp->orig));
+	(void) get_str(ctx, "/OFP/origin/plan_rwy", ofp->orig_rwy,
+	    sizeof (ofp->orig_rwy));
+	(void) get_str(ctx, "/OFP/destination/icao_code", ofp->dest,
+	    sizeof (ofp->dest));
+	(void) get_str(ctx, "/OFP/destination/plan_rwy", ofp->dest_rwy,
+	    sizeof (ofp->dest_rwy));
+	(void) get_str(ctx, "/OFP/atc/callsign", ofp->callsign,
+	    sizeof (ofp->callsign));
+	(void) get_str(ctx, "/OFP/aircraft/icaocode", ofp->acf_icao,
+	    sizeof (ofp->acf_icao));
+	ofp->cruise_alt = get_num(ctx, "/OFP/general/initial_altitude");
+	ofp->cost_index = get_num(ctx, "/OFP/general/costindex");
+
+	route = get_str_alloc(ctx, "/OFP/general/route");
+	ofp->route = (route != NULL ? route : safe_strdup(""));
+
+	/* Weights & fuel are given in the units the user selected */
+	if (get_str(ctx, "/OFP/params/units", buf, sizeof (buf)) &&
+	    strcmp(buf, "lbs") == 0)
+		mass_fact = LBS2KG(1);
+	ofp->zfw = get_num(ctx, "/OFP/weights/est_zfw") * mass_fact;
+	ofp->tow = get_num(ctx, "/OFP/weights/est_tow") * mass_fact;
+	ofp->ldw = get_num(ctx, "/OFP/weights/est_ldw") * mass_fact;
+	ofp->payload = get_num(ctx, "/OFP/weights/payload") * mass_fact;
+	ofp->fuel_ramp = get_num(ctx, "/OFP/fuel/plan_ramp") * mass_fact;
+	ofp->fuel_taxi = get_num(ctx, "/OFP/fuel/taxi") * mass_fact;
+	ofp->fuel_trip = get_num(ctx, "/OFP/fuel/enroute_burn") * mass_fact;
+	ofp->fuel_contingency = get_num(ctx, "/OFP/fuel/contingency") *
+	    mass_fact;
+	ofp->fuel_altn = get_num(ctx, "/OFP/fuel/alternate_burn") * mass_fact;
+	ofp->fuel_reserve = get_num(ctx, "/OFP/fuel/reserve") * mass_fact;
+	ofp->fuel_extra = get_num(ctx, "/OFP/fuel/extra") * mass_fact;
+
+	load_nodes(ctx, "/OFP/alternate/icao_code", ofp, load_altn);
+	load_nodes(ctx, "/OFP/navlog/fix", ofp, load_navlog_fix);
+
+	xmlXPathFreeContext(ctx);
+
+	return (ofp);
+}
+
+/*
+ * Parses a SimBrief OFP in XML format from a memory buffer.
+ *
+ * @return The parsed OFP, or NULL if the buffer doesn't contain a valid
+ *	OFP (the error is logged). Use simbrief_free to dispose of it.
+ */
+simbrief_ofp_t *
+simbrief_parse(const void *buf, size_t len)
+{
+	xmlDoc *doc;
+	simbrief_ofp_t *ofp;
+
+	ASSERT(buf != NULL || len == 0);
+
+	doc = xmlReadMemory(buf, len, "ofp.xml", NULL,
+	    XML_PARSE_NOERROR | XML_PARSE_NOWARNING | XML_PARSE_NONET);
+	if (doc == NULL) {
+		logMsg("Error parsing SimBrief OFP: XML parsing error");
+		return (NULL);
+	}
+	ofp = parse_doc(doc);
+	xmlFreeDoc(doc);
+
+	return (ofp);
+}
+
+/*
+ * Same as simbrief_parse, but parses a previously downloaded OFP XML file.
+ */
+simbrief_ofp_t *
+simbrief_parse_file(const char *filename)
+{
+	size_t len;
+	void *buf;
+	simbrief_ofp_t *ofp;
+
+	ASSERT(filename != NULL);
+
+	buf = file2buf(filename, &len);
+	if (buf == NULL) {
+		logMsg("Error reading SimBrief OFP %s: %s", filename,
+		    strerror(errno));
+		return (NULL);
+	}
+	ofp = simbrief_parse(buf, len);
+	free(buf);
+
+	return (ofp);
+}
+
+/*
+ * Downloads and parses the latest OFP generated by a SimBrief user.
+ * This function performs a blocking network request, so it should NOT
+ * be called from the simulator's main thread. Instead, call it from a
+ * background thread (e.g. a worker_t) and hand the result over to the
+ * main thread once it's done.
+ *
+ * @param pilot_id The SimBrief pilot ID (a number, shown in the user's
+ *	SimBrief account settings).
+ * @param cainfo Optional path to a CA certificate bundle file to use for
+ *	verifying the server's TLS certificate. Pass NULL to use the
+ *	default bundle of the platform.
+ *
+ * @return The parsed OFP, or NULL if the download or parsing failed (the
+ *	error is logged). Use simbrief_free to dispose of it.
+ */
+simbrief_ofp_t *
+simbrief_fetch(const char *pilot_id, const char *cainfo)
+{
+	CURL *curl;
+	CURLcode res;
+	dl_info_t dl_info = {};
+	char *escaped, *url;
+	long code = 0;
+	simbrief_ofp_t *ofp = NULL;
+
+	ASSERT(pilot_id != NULL);
+
+	curl = curl_easy_init();
+	VERIFY(curl != NULL);
+
+	escaped = curl_easy_escape(curl, pilot_id, 0);
+	VERIFY(escaped != NULL);
+	url = sprintf_alloc("%s?userid=%s", SIMBRIEF_URL, escaped);
+	curl_free(escaped);
+
+	chart_setup_curl(curl, cainfo);
+	curl_easy_setopt(curl, CURLOPT_URL, url);
+	curl_easy_setopt(curl, CURLOPT_WRITEFUNCTION, dl_write);
+	curl_easy_setopt(curl, CURLOPT_WRITEDATA, &dl_info);
+	curl_easy_setopt(curl, CURLOPT_TIMEOUT, DL_TIMEOUT);
+
+	res = curl_easy_perform(curl);
+	if (res == CURLE_OK)
+		curl_easy_getinfo(curl, CURLINFO_RESPONSE_CODE, &code);
+	if (res != CURLE_OK) {
+		logMsg("Error downloading SimBrief OFP: %s",
+		    curl_easy_strerror(res));
+	} else if (code != 200 && code != 400) {
+		/*
+		 * SimBrief returns a 400 along with an XML document
+		 * containing the error description, so pass those on
+		 * to the parser to log the detailed error.
+		 */
+		logMsg("Error downloading SimBrief OFP: server responded "
+		    "with error code %ld", code);
+	} else {
+		ofp = simbrief_parse(dl_info.buf, dl_info.bufsz);
+	}
+
+	curl_easy_cleanup(curl);
+	free(dl_info.buf);
+	free(url);
+
+	return (ofp);
+}
+
+void
+simbrief_free(simbrief_ofp_t *ofp)
+{
+	if (ofp == NULL)
+		return;
+	free(ofp->route);
+	free(ofp->altns);
+	free(ofp->fixes);
+	free(ofp);
+}
@@ -32,8 +32,18 @@
 #include "acfutils/thread.h"
 #include "acfutils/time.h"
 
+struct taskq_future_s {
+	mutex_t		lock;
+	condvar_t	cv;
+	bool		done;
+	bool		canceled;
+	void		*result;
+};
+
 typedef struct {
 	void		*task;
+	taskq_func_t	func;	/* for taskq_submit_func */
+	taskq_future_t	*fut;	/* for taskq_submit_func */
 	list_node_t	node;
 } taskq_task_t;
 
@@ -62,8 +72,23 @@ struct taskq_s {
 	list_t			tasks;
 	list_t			threads;
 	unsigned		num_thr_ready;
+	unsigned		num_busy;	/* threads processing a task */
+	size_t			max_queued;	/* 0 = unlimited */
+	/* signaled when a task is dequeued or completed */
+	condvar_t		state_cv;
 };
 
+static void
+future_complete(taskq_future_t *fut, void *result, bool canceled)
+{
+	mutex_enter(&fut->lock);
+	fut->result = result;
+	fut->canceled = canceled;
+	fut->done = true;
+	cv_broadcast(&fut->cv);
+	mutex_exit(&fut->lock);
+}
+
 static bool
 task_wait_for_work(taskq_t *tq)
 {
@@ -112,14 +137,24 @@ taskq_worker(void *info)
 			}
 		}
 		tq->num_thr_ready--;
+		tq->num_busy++;
+		cv_broadcast(&tq->state_cv);
 		mutex_exit(&tq->lock);
 
 		/* Process the task */
-		tq->proc_func(tq->userinfo, thr->thr_info, task->task);
+		if (task->func != NULL) {
+			future_complete(task->fut, task->func(task->task),
+			    false);
+		} else {
+			tq->proc_func(tq->userinfo, thr->thr_info, task->task);
+		}
 		free(task);
 
 		mutex_enter(&tq->lock);
 		tq->num_thr_ready++;
+		ASSERT(tq->num_busy != 0);
+		tq->num_busy--;
+		cv_broadcast(&tq->state_cv);
 	}
 	ASSERT(tq->num_thr_ready != 0);
 	tq->num_thr_ready--;
@@ -161,6 +196,7 @@ taskq_alloc(unsigned num_threads_min, unsigned num_threads_max,
 
 	mutex_init(&tq->lock);
 	cv_init(&tq->cv);
+	cv_init(&tq->state_cv);
 	list_create(&tq->tasks, sizeof (taskq_task_t),
 	    offsetof(taskq_task_t, node));
 	list_create(&tq->threads, sizeof (taskq_thr_t),
@@ -202,28 +238,30 @@ taskq_free(taskq_t *tq)
 	 * Discard incomplete work.
 	 */
 	while ((task = list_remove_head(&tq->tasks)) != NULL) {
-		tq->discard_func(tq->userinfo, task->task);
+		if (task->func != NULL)
+			future_complete(task->fut, NULL, true);
+		else
+			tq->discard_func(tq->userinfo, task->task);
 		free(task);
 	}
 	list_destroy(&tq->tasks);
 	/*
 	 * Destroy threading primitives.
 	 */
+	cv_destroy(&tq->state_cv);
 	cv_destroy(&tq->cv);
 	mutex_destroy(&tq->lock);
 
 	free(tq);
 }
 
-void
-taskq_submit(taskq_t *tq, void *task)
+/*
+ * Enqueues a task. Caller must hold tq->lock and have checked that the
+ * queue has room for it.
+ */
+static void
+taskq_enqueue(taskq_t *tq, taskq_task_t *t)
 {
-	taskq_task_t *t = safe_calloc(1, sizeof (*t));
-
-	ASSERT(tq != NULL);
-	t->task = task;
-
-	mutex_enter(&tq->lock);
 	list_insert_tail(&tq->tasks, t);
 	if (tq->num_thr_ready != 0) {
 		/* Only wake up a single worker */
@@ -235,7 +273,223 @@ taskq_submit(taskq_t *tq, void *task)
 		list_insert_tail(&tq->threads, thr);
 		VERIFY(thread_create(&thr->thr, taskq_worker, thr));
 	}
+}
+
+static bool
+taskq_is_full(const taskq_t *tq)
+{
+	return (tq->max_queued != 0 &&
+	    list_count(&tq->tasks) >= tq->max_queued);
+}
+
+/*
+ * Submits a task for processing by the taskq's proc_func. If the taskq
+ * has a queue length limit (see taskq_set_max_queued) and the queue is
+ * full, this blocks until a worker dequeues a task.
+ */
+void
+taskq_submit(taskq_t *tq, void *task)
+{
+	taskq_task_t *t = safe_calloc(1, sizeof (*t));
+
+	ASSERT(tq != NULL);
+	t->task = task;
+
+	mutex_enter(&tq->lock);
+	while (taskq_is_full(tq))
+		cv_wait(&tq->state_cv, &tq->lock);
+	taskq_enqueue(tq, t);
+	mutex_exit(&tq->lock);
+}
+
+/*
+ * Same as taskq_submit, but never blocks. If the queue is full, returns
+ * false and the task is not submitted.
+ */
+bool
+taskq_try_submit(taskq_t *tq, void *task)
+{
+	taskq_task_t *t;
+
+	ASSERT(tq != NULL);
+
+	mutex_enter(&tq->lock);
+	if (taskq_is_full(tq)) {
+		mutex_exit(&tq->lock);
+		return (false);
+	}
+	t = safe_calloc(1, sizeof (*t));
+	t->task = task;
+	taskq_enqueue(tq, t);
+	mutex_exit(&tq->lock);
+
+	return (true);
+}
+
+/*
+ * Submits a one-off function call to be run on the taskq's threads,
+ * instead of the taskq's proc_func. This is useful for running ad-hoc
+ * background jobs without spawning a dedicated thread for each:
+ *
+ *	taskq_future_t *fut = taskq_submit_func(tq, load_tile, tile_info);
+ *	...later, either poll for completion without blocking:
+ *	if (taskq_future_is_done(fut)) {
+ *		tile_t *tile = taskq_future_wait(fut, NULL);
+ *		taskq_future_free(fut);
+ *	}
+ *	...or block until the result is available:
+ *	tile_t *tile = taskq_future_wait(fut, NULL);
+ *	taskq_future_free(fut);
+ *
+ * Respects the queue length limit like taskq_submit does. If the taskq
+ * is freed before the function gets to run, the function is never
+ * called and the future is completed as canceled.
+ *
+ * @param func Function to call on a worker thread. Its return value is
+ *	stored in the future.
+ * @param arg Argument to pass to `func'.
+ *
+ * @return A future, which you must free using taskq_future_free.
+ */
+taskq_future_t *
+taskq_submit_func(taskq_t *tq, taskq_func_t func, void *arg)
+{
+	taskq_task_t *t = safe_calloc(1, sizeof (*t));
+	taskq_future_t *fut = safe_calloc(1, sizeof (*fut));
+
+	ASSERT(tq != NULL);
+	ASSERT(func != NULL);
+	mutex_init(&fut->lock);
+	cv_init(&fut->cv);
+	t->task = arg;
+	t->func = func;
+	t->fut = fut;
+
+	mutex_enter(&tq->lock);
+	while (taskq_is_full(tq))
+		cv_wait(&tq->state_cv, &tq->lock);
+	taskq_enqueue(tq, t);
 	mutex_exit(&tq->lock);
+
+	return (fut);
+}
+
+/*
+ * Returns true if the function associated with the future has finished
+ * running or has been canceled.
+ */
+bool
+taskq_future_is_done(taskq_future_t *fut)
+{
+	bool done;
+
+	ASSERT(fut != NULL);
+	mutex_enter(&fut->lock);
+	done = fut->done;
+	mutex_exit(&fut->lock);
+
+	return (done);
+}
+
+/*
+ * Waits for the function associated with the future to finish and
+ * returns its return value. If the function was canceled, returns NULL.
+ *
+ * @param canceled Optional return argument, which will be set to true
+ *	if the function was canceled.
+ */
+void *
+taskq_future_wait(taskq_future_t *fut, bool *canceled)
+{
+	void *result;
+
+	ASSERT(fut != NULL);
+	mutex_enter(&fut->lock);
+	while (!fut->done)
+		cv_wait(&fut->cv, &fut->lock);
+	result = fut->result;
+	if (canceled != NULL)
+		*canceled = fut->canceled;
+	mutex_exit(&fut->lock);
+
+	return (result);
+}
+
+/*
+ * Frees a future. If the function associated with the future hasn't
+ * finished yet, this waits for it to finish first. Freeing the result
+ * of the function (if any) is the caller's responsibility.
+ */
+void
+taskq_future_free(taskq_future_t *fut)
+{
+	if (fut == NULL)
+		return;
+	(void) taskq_future_wait(fut, NULL);
+	cv_destroy(&fut->cv);
+	mutex_destroy(&fut->lock);
+	free(fut);
+}
+
+/*
+ * Blocks until all submitted tasks have been processed, allowing a
+ * graceful shutdown where no queued work is discarded by taskq_free.
+ * Tasks submitted by other threads while waiting extend the wait.
+ */
+void
+taskq_wait_idle(taskq_t *tq)
+{
+	ASSERT(tq != NULL);
+	mutex_enter(&tq->lock);
+	while (list_count(&tq->tasks) != 0 || tq->num_busy != 0)
+		cv_wait(&tq->state_cv, &tq->lock);
+	mutex_exit(&tq->lock);
+}
+
+/*
+ * Returns the number of tasks waiting in the queue, not including tasks
+ * currently being processed.
+ */
+size_t
+taskq_get_num_queued(taskq_t *tq)
+{
+	size_t n;
+
+	ASSERT(tq != NULL);
+	mutex_enter(&tq->lock);
+	n = list_count(&tq->tasks);
+	mutex_exit(&tq->lock);
+
+	return (n);
+}
+
+/*
+ * Sets the maximum number of tasks which can be waiting in the queue.
+ * When the queue is full, taskq_submit and taskq_submit_func block and
+ * taskq_try_submit fails. Pass 0 for an unlimited queue (the default).
+ */
+void
+taskq_set_max_queued(taskq_t *tq, size_t max_queued)
+{
+	ASSERT(tq != NULL);
+	mutex_enter(&tq->lock);
+	tq->max_queued = max_queued;
+	/* wake up any blocked submitters to re-check */
+	cv_broadcast(&tq->state_cv);
+	mutex_exit(&tq->lock);
+}
+
+size_t
+taskq_get_max_queued(taskq_t *tq)
+{
+	size_t n;
+
+	ASSERT(tq != NULL);
+	mutex_enter(&tq->lock);
+	n = tq->max_queued;
+	mutex_exit(&tq->lock);
+
+	return (n);
 }
 
 bool
@@ -0,0 +1,477 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <math.h>
+#include <string.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/avl.h>
+#include <acfutils/dsf.h>
+#include <acfutils/helpers.h>
+#include <acfutils/log.h>
+#include <acfutils/math.h>
+#include <acfutils/safe_alloc.h>
+#include <acfutils/terrain.h>
+#include <acfutils/thread.h>
+
+#define	ELEV_RASTER_NAME	"elevation"
+
+typedef struct {
+	/* south-west corner of the tile, used as the lookup key */
+	int		lat;
+	int		lon;
+	double		south, north;
+	double		west, east;
+	unsigned	width, height;
+	bool_t		post_ctr;
+	/* width x height elevations in meters, southernmost row first */
+	float		*elev;
+	avl_node_t	node;
+} tile_t;
+
+struct terr_s {
+	mutex_t		lock;
+	avl_tree_t	tiles;
+};
+
+static int
+tile_compar(const void *a, const void *b)
+{
+	const tile_t *ta = a, *tb = b;
+
+	if (ta->lat < tb->lat)
+		return (-1);
+	if (ta->lat > tb->lat)
+		return (1);
+	if (ta->lon < tb->lon)
+		return (-1);
+	if (ta->lon > tb->lon)
+		return (1);
+	return (0);
+}
+
+static void
+tile_free(tile_t *tile)
+{
+	free(tile->elev);
+	free(tile);
+}
+
+/*
+ * Allocates an empty terrain elevation database. Elevation data is added
+ * to it one DSF tile at a time and can then be queried at arbitrary
+ * points (with bilinear interpolation between the DEM posts), or sampled
+ * along a path for terrain awareness purposes.
+ *
+ * Typical usage:
+ *
+ *	terr_t *terr = terr_alloc();
+ *	terr_add_dsf(terr, "<xpdir>/Global Scenery/X-Plane 11 Global "
+ *	    "Scenery/Earth nav data/+40-080/+47-075.dsf");
+ *	...
+ *	double elev = terr_get_elev(terr, GEO_POS2(47.5, -74.5));
+ *	if (!isnan(elev))
+ *		... elev is the terrain elevation in meters ...
+ *	...
+ *	terr_free(terr);
+ *
+ * Loading a DSF is slow (the file typically needs to be decompressed
+ * first), so you will want to do this from a background thread. The
+ * database is internally locked, so it is safe to add and remove tiles
+ * while other threads are querying it.
+ */
+terr_t *
+terr_alloc(void)
+{
+	terr_t *terr = safe_calloc(1, sizeof (*terr));
+
+	mutex_init(&terr->lock);
+	avl_create(&terr->tiles, tile_compar, sizeof (tile_t),
+	    offsetof(tile_t, node));
+
+	return (terr);
+}
+
+/*
+ * Frees a terrain database previously allocated using terr_alloc,
+ * including all of its loaded tiles.
+ */
+void
+terr_free(terr_t *terr)
+{
+	void *cookie = NULL;
+	tile_t *tile;
+
+	if (terr == NULL)
+		return;
+	while ((tile = avl_destroy_nodes(&terr->tiles, &cookie)) != NULL)
+		tile_free(tile);
+	avl_destroy(&terr->tiles);
+	mutex_destroy(&terr->lock);
+	free(terr);
+}
+
+static bool_t
+read_bounds(const dsf_t *dsf, tile_t *tile)
+{
+	const dsf_atom_t *prop = dsf_lookup(dsf, DSF_ATOM_HEAD, 0,
+	    DSF_ATOM_PROP, 0, 0);
+	unsigned found = 0;
+
+	if (prop == NULL)
+		return (B_FALSE);
+	for (const dsf_prop_t *p = list_head(&prop->prop_atom.props);
+	    p != NULL; p = list_next(&prop->prop_atom.props, p)) {
+		if (strcmp(p->name, "sim/south") == 0) {
+			tile->south = atof(p->value);
+			found |= 1 << 0;
+		} else if (strcmp(p->name, "sim/north") == 0) {
+			tile->north = atof(p->value);
+			found |= 1 << 1;
+		} else if (strcmp(p->name, "sim/west") == 0) {
+			tile->west = atof(p->value);
+			found |= 1 << 2;
+		} else if (strcmp(p->name, "sim/east") == 0) {
+			tile->east = atof(p->value);
+			found |= 1 << 3;
+		}
+	}
+
+	return (found == 0xf && tile->north > tile->south &&
+	    tile->east > tile->west);
+}
+
+/*
+ * Locates the index of the elevation raster in the DEMN string table.
+ * Returns -1 if the DSF doesn't contain an elevation raster.
+ */
+static int
+find_elev_raster(const dsf_t *dsf)
+{
+	const dsf_atom_t *demn = dsf_lookup(dsf, DSF_ATOM_DEFN, 0,
+	    DSF_ATOM_DEMN, 0, 0);
+	const char *str, *end;
+
+	if (demn == NULL)
+		return (-1);
+	str = (const char *)demn->payload;
+	end = str + demn->payload_sz;
+	for (int idx = 0; str < end; idx++) {
+		size_t len = strnlen(str, end - str);
+
+		if (strncmp(str, ELEV_RASTER_NAME, len) == 0 &&
+		    len == strlen(ELEV_RASTER_NAME))
+			return (idx);
+		str += len + 1;
+	}
+
+	return (-1);
+}
+
+static double
+read_post(const dsf_demi_atom_t *demi, const uint8_t *p)
+{
+	double raw;
+
+	switch (demi->flags & DEMI_DATA_MASK) {
+	case DEMI_DATA_FP32: {
+		float f;
+		memcpy(&f, p, sizeof (f));
+		raw = f;
+		break;
+	}
+	case DEMI_DATA_SINT:
+		if (demi->bpp == 1) {
+			raw = *(const int8_t *)p;
+		} else if (demi->bpp == 2) {
+			int16_t v;
+			memcpy(&v, p, sizeof (v));
+			raw = v;
+		} else {
+			int32_t v;
+			memcpy(&v, p, sizeof (v));
+			raw = v;
+		}
+		break;
+	default:
+		if (demi->bpp == 1) {
+			raw = *p;
+		} else if (demi->bpp == 2) {
+			uint16_t v;
+			memcpy(&v, p, sizeof (v));
+			raw = v;
+		} else {
+			uint32_t v;
+			memcpy(&v, p, sizeof (v));
+			raw = v;
+		}
+		break;
+	}
+
+	return (raw * demi->scale + demi->offset);
+}
+
+static tile_t *
+tile_from_dsf(const dsf_t *dsf, const char *filename)
+{
+	tile_t *tile = safe_calloc(1, sizeof (*tile));
+	const dsf_atom_t *dems, *demi_atom = NULL, *demd_atom = NULL;
+	const dsf_demi_atom_t *demi;
+	int idx;
+
+	if (!read_bounds(dsf, tile)) {
+		logMsg("Error loading terrain from %s: missing or invalid "
+		    "tile bounds", filename);
+		goto errout;
+	}
+	tile->lat = floor(tile->south);
+	tile->lon = floor(tile->west);
+
+	idx = find_elev_raster(dsf);
+	dems = dsf_lookup(dsf, DSF_ATOM_DEMS, 0, 0);
+	if (idx < 0 || dems == NULL) {
+		logMsg("Error loading terrain from %s: DSF contains no "
+		    "elevation raster", filename);
+		goto errout;
+	}
+	for (int i = 0; i <= idx; i++) {
+		demi_atom = dsf_iter(dems, DSF_ATOM_DEMI, demi_atom);
+		demd_atom = dsf_iter(dems, DSF_ATOM_DEMD, demd_atom);
+		if (demi_atom == NULL || demd_atom == NULL) {
+			logMsg("Error loading terrain from %s: DEMS atom "
+			    "is missing raster %d", filename, idx);
+			goto errout;
+		}
+	}
+	demi = &demi_atom->demi_atom;
+	if (demi->width < 2 || demi->height < 2 ||
+	    (demi->bpp != 1 && demi->bpp != 2 && demi->bpp != 4) ||
+	    ((demi->flags & DEMI_DATA_MASK) == DEMI_DATA_FP32 &&
+	    demi->bpp != 4) ||
+	    demd_atom->payload_sz != (uint64_t)demi->width * demi->height *
+	    demi->bpp) {
+		logMsg("Error loading terrain from %s: invalid elevation "
+		    "raster format (%dx%d, %d bpp, flags %x, %d bytes)",
+		    filename, demi->width, demi->height, demi->bpp,
+		    demi->flags, demd_atom->payload_sz);
+		goto errout;
+	}
+	tile->width = demi->width;
+	tile->height = demi->height;
+	tile->post_ctr = !!(demi->flags & DEMI_POST_CTR);
+	tile->elev = safe_malloc(tile->width * tile->height *
+	    sizeof (*tile->elev));
+	for (size_t i = 0, n = tile->width * tile->height; i < n; i++) {
+		tile->elev[i] = read_post(demi,
+		    &demd_atom->payload[i * demi->bpp]);
+	}
+
+	return (tile);
+errout:
+	tile_free(tile);
+	return (NULL);
+}
+
+/*
+ * Loads the elevation raster from the DSF file `filename' (either raw or
+ * 7-zip compressed, as shipped with X-Plane) and adds it to the terrain
+ * database. If a tile covering the same area was already loaded, it is
+ * replaced. Returns B_TRUE on success, or B_FALSE if the DSF couldn't be
+ * read or contains no usable elevation raster (the error is logged).
+ */
+bool_t
+terr_add_dsf(terr_t *terr, const char *filename)
+{
+	dsf_t *dsf;
+	tile_t *tile, *old;
+	avl_index_t where;
+
+	ASSERT(terr != NULL);
+	ASSERT(filename != NULL);
+
+	dsf = dsf_init(filename);
+	if (dsf == NULL)
+		return (B_FALSE);
+	tile = tile_from_dsf(dsf, filename);
+	dsf_fini(dsf);
+	if (tile == NULL)
+		return (B_FALSE);
+
+	mutex_enter(&terr->lock);
+	old = avl_find(&terr->tiles, tile, &where);
+	if (old != NULL) {
+		avl_remove(&terr->tiles, old);
+		tile_free(old);
+		VERIFY3P(avl_find(&terr->tiles, tile, &where), ==, NULL);
+	}
+	avl_insert(&terr->tiles, tile, where);
+	mutex_exit(&terr->lock);
+
+	return (B_TRUE);
+}
+
+/*
+ * Removes the tile whose south-west corner lies at `lat' x `lon' from
+ * the terrain database to free up memory. Returns B_TRUE if the tile
+ * was loaded, B_FALSE otherwise.
+ */
+bool_t
+terr_remove_tile(terr_t *terr, int lat, int lon)
+{
+	tile_t srch = { .lat = lat, .lon = lon };
+	tile_t *tile;
+
+	ASSERT(terr != NULL);
+
+	mutex_enter(&terr->lock);
+	tile = avl_find(&terr->tiles, &srch, NULL);
+	if (tile != NULL)
+		avl_remove(&terr->tiles, tile);
+	mutex_exit(&terr->lock);
+
+	if (tile == NULL)
+		return (B_FALSE);
+	tile_free(tile);
+
+	return (B_TRUE);
+}
+
+/*
+ * Returns the number of tiles currently loaded in the terrain database.
+ */
+size_t
+terr_get_num_tiles(terr_t *terr)
+{
+	size_t n;
+
+	ASSERT(terr != NULL);
+	mutex_enter(&terr->lock);
+	n = avl_numnodes(&terr->tiles);
+	mutex_exit(&terr->lock);
+
+	return (n);
+}
+
+static double
+tile_get_elev(const tile_t *tile, geo_pos2_t pos)
+{
+	double x = (pos.lon - tile->west) / (tile->east - tile->west);
+	double y = (pos.lat - tile->south) / (tile->north - tile->south);
+	unsigned x1, y1, x2, y2;
+	double fx, fy, e1, e2;
+
+	if (tile->post_ctr) {
+		x = x * tile->width - 0.5;
+		y = y * tile->height - 0.5;
+	} else {
+		x = x * (tile->width - 1);
+		y = y * (tile->height - 1);
+	}
+	x = clamp(x, 0, tile->width - 1);
+	y = clamp(y, 0, tile->height - 1);
+	x1 = MIN(floor(x), tile->width - 2);
+	y1 = MIN(floor(y), tile->height - 2);
+	x2 = x1 + 1;
+	y2 = y1 + 1;
+	fx = x - x1;
+	fy = y - y1;
+
+	e1 = wavg(tile->elev[y1 * tile->width + x1],
+	    tile->elev[y1 * tile->width + x2], fx);
+	e2 = wavg(tile->elev[y2 * tile->width + x1],
+	    tile->elev[y2 * tile->width + x2], fx);
+
+	return (wavg(e1, e2, fy));
+}
+
+static double
+terr_get_elev_impl(terr_t *terr, geo_pos2_t pos)
+{
+	int lat = floor(pos.lat), lon = floor(pos.lon);
+
+	/*
+	 * Points lying exactly on a tile's northern or eastern edge are
+	 * also covered by that tile, so try the neighbors too.
+	 */
+	for (int i = 0; i < 4; i++) {
+		tile_t srch = { .lat = lat - (i & 1), .lon = lon - (i >> 1) };
+		const tile_t *tile = avl_find(&terr->tiles, &srch, NULL);
+
+		if (tile != NULL && pos.lat >= tile->south &&
+		    pos.lat <= tile->north && pos.lon >= tile->west &&
+		    pos.lon <= tile->east)
+			return (tile_get_elev(tile, pos));
+	}
+
+	return (NAN);
+}
+
+/*
+ * Returns the terrain elevation (in meters above mean sea level) at
+ * `pos', bilinearly interpolated between the surrounding DEM posts.
+ * If no loaded tile covers `pos', returns NAN.
+ */
+double
+terr_get_elev(terr_t *terr, geo_pos2_t pos)
+{
+	double elev;
+
+	ASSERT(terr != NULL);
+	ASSERT(!IS_NULL_GEO_POS(pos));
+
+	mutex_enter(&terr->lock);
+	elev = terr_get_elev_impl(terr, pos);
+	mutex_exit(&terr->lock);
+
+	return (elev);
+}
+
+/*
+ * Samples the terrain elevation along the WGS-84 geodesic from `start'
+ * to `end' at `num_pts' equally spaced points (including both endpoints)
+ * and stores the elevations (in meters) into `elevs', which must be able
+ * to hold at least `num_pts' values. Points not covered by any loaded
+ * tile are set to NAN. Returns the highest elevation found along the
+ * path, or NAN if none of the points were covered.
+ */
+double
+terr_get_profile(terr_t *terr, geo_pos2_t start, geo_pos2_t end,
+    size_t num_pts, double *elevs)
+{
+	double hdg, dist, max_elev = NAN;
+
+	ASSERT(terr != NULL);
+	ASSERT3U(num_pts, >=, 2);
+	ASSERT(elevs != NULL);
+
+	dist = geodesic_inverse(&wgs84, start, end, &hdg, NULL);
+
+	mutex_enter(&terr->lock);
+	for (size_t i = 0; i < num_pts; i++) {
+		geo_pos2_t pos = (i + 1 < num_pts ? geodesic_direct(&wgs84,
+		    start, hdg, (dist * i) / (num_pts - 1), NULL) : end);
+
+		elevs[i] = terr_get_elev_impl(terr, pos);
+		if (!isnan(elevs[i]) && (isnan(max_elev) ||
+		    elevs[i] > max_elev))
+			max_elev = elevs[i];
+	}
+	mutex_exit(&terr->lock);
+
+	return (max_elev);
+}
@@ -21,6 +21,8 @@
 #else	/* !IBM */
 #include <sys/time.h>
 #endif	/* !IBM */
+#include <math.h>
+#include <stdio.h>
 #include <stdlib.h>
 
 #include <stdint.h>
@@ -94,3 +96,33 @@ lacf_timegm(const struct tm *t)
 
 	return (result);
 }
+
+void
+lacf_fmt_iso8601(double utime, char buf[LACF_ISO8601_BUFSZ])
+{
+	int64_t secs = floor(utime);
+	int ms = (utime - secs) * 1000;
+	int64_t days = secs / 86400;
+	int64_t sod = secs % 86400;
+	int64_t era, z, doe, yoe, doy, mp, d, m, y;
+
+	ms = (ms < 0 ? 0 : (ms > 999 ? 999 : ms));
+	if (sod < 0) {
+		sod += 86400;
+		days--;
+	}
+	/* civil-from-days, proleptic Gregorian calendar */
+	z = days + 719468;
+	era = (z >= 0 ? z : z - 146096) / 146097;
+	doe = z - era * 146097;
+	yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+	doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+	mp = (5 * doy + 2) / 153;
+	d = doy - (153 * mp + 2) / 5 + 1;
+	m = (mp < 10 ? mp + 3 : mp - 9);
+	y = yoe + era * 400 + (m <= 2);
+
+	snprintf(buf, LACF_ISO8601_BUFSZ, "%04d-%02d-%02dT%02d:%02d:%02d.%03dZ",
+	    (int)y, (int)m, (int)d, (int)(sod / 3600),
+	    (int)((sod / 60) % 60), (int)(sod % 60), ms);
+}
@@ -0,0 +1,381 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <math.h>
+#include <stddef.h>
+#include <string.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/avl.h>
+#include <acfutils/helpers.h>
+#include <acfutils/safe_alloc.h>
+#include <acfutils/thread.h>
+#include <acfutils/traffic.h>
+
+#define	DFL_SMOOTH_TIME	2		/* seconds */
+#define	DFL_MAX_EXTRAP	10		/* seconds */
+#define	DFL_MAX_AGE	30		/* seconds */
+/*
+ * Position jumps larger than this are applied immediately instead of
+ * being smoothed over, since they're most likely a teleport or a
+ * reused identifier.
+ */
+#define	SNAP_DIST	1852		/* meters */
+#define	SNAP_ELEV	300		/* meters */
+
+typedef struct {
+	char			id[TRAFFIC_ID_LEN];
+	traffic_report_t	rep;
+	/*
+	 * Local time (traffic->now) at which the last report arrived. The
+	 * report's own timestamp can lag well behind it (e.g. due to
+	 * network latency), so blending is timed from the arrival.
+	 */
+	double			arrival;
+	/*
+	 * Difference between the displayed and reported state at the time
+	 * the last report arrived. This is blended out over the smoothing
+	 * time.
+	 */
+	double			off_lat;
+	double			off_lon;
+	double			off_elev;
+	double			off_trk;
+	traffic_tgt_t		state;
+	avl_node_t		node;
+} tgt_t;
+
+struct traffic_s {
+	mutex_t		lock;
+	avl_tree_t	tgts;
+	double		smooth_time;
+	double		max_extrap;
+	double		max_age;
+	double		now;
+};
+
+static int
+tgt_compar(const void *a, const void *b)
+{
+	const tgt_t *ta = a, *tb = b;
+	int res = strcmp(ta->id, tb->id);
+
+	if (res < 0)
+		return (-1);
+	if (res > 0)
+		return (1);
+	return (0);
+}
+
+/*
+ * Computes the displayed state of a target at time `now' by dead
+ * reckoning from its last report and blending out the smoothing offset.
+ */
+static void
+tgt_compute(const traffic_t *traffic, const tgt_t *tgt, double now,
+    traffic_tgt_t *state)
+{
+	const traffic_report_t *rep = &tgt->rep;
+	double d_t = MAX(now - rep->time, 0);
+	double d_t_extrap = MIN(d_t, traffic->max_extrap);
+	double d_t_blend = MAX(now - tgt->arrival, 0);
+	double w = 0;
+	geo_pos2_t pos = GEO3_TO_GEO2(rep->pos);
+
+	if (rep->gs > 0 && d_t_extrap > 0) {
+		pos = rhumb_displace(pos, rep->trk,
+		    rep->gs * d_t_extrap);
+	}
+	if (traffic->smooth_time > 0)
+		w = MAX(1 - d_t_blend / traffic->smooth_time, 0);
+
+	lacf_strlcpy(state->id, tgt->id, sizeof (state->id));
+	state->pos = GEO_POS3(clamp(pos.lat + tgt->off_lat * w, -90, 90),
+	    normalize_lon(pos.lon + tgt->off_lon * w),
+	    rep->pos.elev + rep->vs * d_t_extrap + tgt->off_elev * w);
+	state->trk = normalize_hdg(rep->trk + tgt->off_trk * w);
+	state->gs = rep->gs;
+	state->vs = rep->vs;
+	state->age = d_t;
+	state->frozen = (d_t > traffic->max_extrap);
+}
+
+/*
+ * Creates a new traffic target table. The table takes sparse position
+ * reports of traffic targets (e.g. from an online network feed or a
+ * simulated TCAS sensor) and provides smooth per-frame states for all
+ * targets:
+ *
+ * - Between reports, targets are dead-reckoned along a rhumb line on
+ *	their last reported track, ground speed and vertical speed.
+ *	Extrapolation stops after 10 seconds without a report (see
+ *	traffic_set_max_extrap), after which the target is frozen in place.
+ * - When a new report arrives, the difference between where the target
+ *	was displayed and where the report places it now is blended out
+ *	over 2 seconds from the report's arrival (see
+ *	traffic_set_smooth_time), so targets don't visibly jump, even if
+ *	the report's timestamp lags behind the current time. Jumps of
+ *	more than 1 NM or 300 meters vertically are applied immediately.
+ * - Targets without a report for 30 seconds (see traffic_set_max_age)
+ *	are removed from the table.
+ *
+ * All times are in seconds and can use any clock, as long as the clock
+ * is monotonic and used consistently for the reports and traffic_update
+ * (e.g. the sim's elapsed time, or USEC2SEC(microclock()) if reports
+ * arrive in real time). Reports may be submitted from any thread.
+ *
+ * Typical usage: submit reports as they arrive using traffic_report,
+ * call traffic_update once per frame with the current time, then read
+ * out the targets using traffic_get_tgts or traffic_get_tgt.
+ *
+ * @return The table. Use traffic_free to dispose of it.
+ */
+traffic_t *
+traffic_alloc(void)
+{
+	traffic_t *traffic = safe_calloc(1, sizeof (*traffic));
+
+	mutex_init(&traffic->lock);
+	avl_create(&traffic->tgts, tgt_compar, sizeof (tgt_t),
+	    offsetof(tgt_t, node));
+	traffic->smooth_time = DFL_SMOOTH_TIME;
+	traffic->max_extrap = DFL_MAX_EXTRAP;
+	traffic->max_age = DFL_MAX_AGE;
+
+	return (traffic);
+}
+
+void
+traffic_free(traffic_t *traffic)
+{
+	void *cookie = NULL;
+	tgt_t *tgt;
+
+	if (traffic == NULL)
+		return;
+	while ((tgt = avl_destroy_nodes(&traffic->tgts, &cookie)) != NULL)
+		free(tgt);
+	avl_destroy(&traffic->tgts);
+	mutex_destroy(&traffic->lock);
+	free(traffic);
+}
+
+/*
+ * Sets the time over which position jumps on new reports are smoothed
+ * out. Set to 0 to disable smoothing.
+ */
+void
+traffic_set_smooth_time(traffic_t *traffic, double secs)
+{
+	ASSERT(traffic != NULL);
+	ASSERT3F(secs, >=, 0);
+	mutex_enter(&traffic->lock);
+	traffic->smooth_time = secs;
+	mutex_exit(&traffic->lock);
+}
+
+/*
+ * Sets the maximum time for which targets are dead-reckoned after their
+ * last report.
+ */
+void
+traffic_set_max_extrap(traffic_t *traffic, double secs)
+{
+	ASSERT(traffic != NULL);
+	ASSERT3F(secs, >=, 0);
+	mutex_enter(&traffic->lock);
+	traffic->max_extrap = secs;
+	mutex_exit(&traffic->lock);
+}
+
+/*
+ * Sets the time after their last report at which targets are removed.
+ */
+void
+traffic_set_max_age(traffic_t *traffic, double secs)
+{
+	ASSERT(traffic != NULL);
+	ASSERT3F(secs, >, 0);
+	mutex_enter(&traffic->lock);
+	traffic->max_age = secs;
+	mutex_exit(&traffic->lock);
+}
+
+/*
+ * Submits a new position report for a target. If the target isn't in
+ * the table yet, it is added. Reports older than the last report of the
+ * target are ignored.
+ */
+void
+traffic_report(traffic_t *traffic, const traffic_report_t *report)
+{
+	tgt_t srch, *tgt;
+	avl_index_t where;
+	double now;
+
+	ASSERT(traffic != NULL);
+	ASSERT(report != NULL);
+	ASSERT(report->id[0] != '\0');
+	ASSERT(!IS_NULL_GEO_POS3(report->pos));
+
+	lacf_strlcpy(srch.id, report->id, sizeof (srch.id));
+
+	mutex_enter(&traffic->lock);
+	now = MAX(traffic->now, report->time);
+	tgt = avl_find(&traffic->tgts, &srch, &where);
+	if (tgt == NULL) {
+		tgt = safe_calloc(1, sizeof (*tgt));
+		lacf_strlcpy(tgt->id, report->id, sizeof (tgt->id));
+		avl_insert(&traffic->tgts, tgt, where);
+		tgt->rep = *report;
+		tgt->rep.trk = normalize_hdg(report->trk);
+	} else if (report->time <= tgt->rep.time) {
+		mutex_exit(&traffic->lock);
+		return;
+	} else {
+		traffic_tgt_t disp, rep;
+
+		tgt_compute(traffic, tgt, now, &disp);
+		tgt->rep = *report;
+		tgt->rep.trk = normalize_hdg(report->trk);
+		tgt->off_lat = 0;
+		tgt->off_lon = 0;
+		tgt->off_elev = 0;
+		tgt->off_trk = 0;
+		tgt_compute(traffic, tgt, now, &rep);
+		if (gc_distance(GEO3_TO_GEO2(disp.pos),
+		    GEO3_TO_GEO2(rep.pos)) < SNAP_DIST &&
+		    fabs(disp.pos.elev - rep.pos.elev) < SNAP_ELEV) {
+			tgt->off_lat = disp.pos.lat - rep.pos.lat;
+			tgt->off_lon = normalize_lon(disp.pos.lon -
+			    rep.pos.lon);
+			tgt->off_elev = disp.pos.elev - rep.pos.elev;
+			tgt->off_trk = rel_hdg(rep.trk, disp.trk);
+		}
+	}
+	tgt->arrival = now;
+	tgt_compute(traffic, tgt, now, &tgt->state);
+	mutex_exit(&traffic->lock);
+}
+
+/*
+ * Removes a target from the table (e.g. when the network reports that
+ * it has disconnected). Does nothing if the target doesn't exist.
+ */
+void
+traffic_remove(traffic_t *traffic, const char *id)
+{
+	tgt_t srch, *tgt;
+
+	ASSERT(traffic != NULL);
+	ASSERT(id != NULL);
+
+	lacf_strlcpy(srch.id, id, sizeof (srch.id));
+	mutex_enter(&traffic->lock);
+	tgt = avl_find(&traffic->tgts, &srch, NULL);
+	if (tgt != NULL) {
+		avl_remove(&traffic->tgts, tgt);
+		free(tgt);
+	}
+	mutex_exit(&traffic->lock);
+}
+
+/*
+ * Advances all targets to time `now' and removes stale targets. Call
+ * this once per frame before reading out the targets.
+ */
+void
+traffic_update(traffic_t *traffic, double now)
+{
+	tgt_t *tgt, *next;
+
+	ASSERT(traffic != NULL);
+
+	mutex_enter(&traffic->lock);
+	traffic->now = now;
+	for (tgt = avl_first(&traffic->tgts); tgt != NULL; tgt = next) {
+		next = AVL_NEXT(&traffic->tgts, tgt);
+		if (now - tgt->rep.time > traffic->max_age) {
+			avl_remove(&traffic->tgts, tgt);
+			free(tgt);
+		} else {
+			tgt_compute(traffic, tgt, now, &tgt->state);
+		}
+	}
+	mutex_exit(&traffic->lock);
+}
+
+size_t
+traffic_get_num_tgts(traffic_t *traffic)
+{
+	size_t n;
+
+	ASSERT(traffic != NULL);
+	mutex_enter(&traffic->lock);
+	n = avl_numnodes(&traffic->tgts);
+	mutex_exit(&traffic->lock);
+
+	return (n);
+}
+
+/*
+ * Copies the states of all targets as of the last traffic_update call
+ * into `tgts'. At most `cap' targets are returned. Returns the number
+ * of targets filled in.
+ */
+size_t
+traffic_get_tgts(traffic_t *traffic, traffic_tgt_t *tgts, size_t cap)
+{
+	size_t n = 0;
+
+	ASSERT(traffic != NULL);
+	ASSERT(tgts != NULL || cap == 0);
+
+	mutex_enter(&traffic->lock);
+	for (const tgt_t *tgt = avl_first(&traffic->tgts);
+	    tgt != NULL && n < cap; tgt = AVL_NEXT(&traffic->tgts, tgt)) {
+		tgts[n++] = tgt->state;
+	}
+	mutex_exit(&traffic->lock);
+
+	return (n);
+}
+
+/*
+ * Looks up a single target by identifier. Returns B_TRUE and fills in
+ * `tgt' if the target exists, otherwise returns B_FALSE.
+ */
+bool_t
+traffic_get_tgt(traffic_t *traffic, const char *id, traffic_tgt_t *tgt)
+{
+	tgt_t srch;
+	const tgt_t *t;
+
+	ASSERT(traffic != NULL);
+	ASSERT(id != NULL);
+	ASSERT(tgt != NULL);
+
+	lacf_strlcpy(srch.id, id, sizeof (srch.id));
+	mutex_enter(&traffic->lock);
+	t = avl_find(&traffic->tgts, &srch, NULL);
+	if (t != NULL)
+		*tgt = t->state;
+	mutex_exit(&traffic->lock);
+
+	return (t != NULL);
+}
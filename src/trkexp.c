@@ -0,0 +1,421 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <errno.h>
+#include <math.h>
+#include <stdio.h>
+#include <string.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/helpers.h>
+#include <acfutils/list.h>
+#include <acfutils/log.h>
+#include <acfutils/safe_alloc.h>
+#include <acfutils/thread.h>
+#include <acfutils/time.h>
+#include <acfutils/trkexp.h>
+#include <acfutils/worker.h>
+
+#define	TRKEXP_INIT_CAP	1024
+
+typedef struct {
+	geo_pos3_t	pos;
+	double		utime;
+} trkpt_t;
+
+typedef struct {
+	char		name[128];
+	trkpt_t		*pts;
+	size_t		num_pts;
+	list_node_t	node;
+} trk_flight_t;
+
+struct trkexp_s {
+	char		*outdir;
+	unsigned	fmt_mask;
+
+	/* only accessed from the caller's thread */
+	trkpt_t		*pts;
+	size_t		num_pts;
+	size_t		cap;
+
+	/* protected by lock */
+	mutex_t		lock;
+	list_t		queue;
+
+	worker_t	wk;
+};
+
+static void
+write_xml_str(FILE *fp, const char *str)
+{
+	for (; *str != 0; str++) {
+		switch (*str) {
+		case '&':
+			fputs("&amp;", fp);
+			break;
+		case '<':
+			fputs("&lt;", fp);
+			break;
+		case '>':
+			fputs("&gt;", fp);
+			break;
+		case '"':
+			fputs("&quot;", fp);
+			break;
+		case '\'':
+			fputs("&apos;", fp);
+			break;
+		default:
+			fputc(*str, fp);
+			break;
+		}
+	}
+}
+
+static bool_t
+close_file(FILE *fp, const char *filename)
+{
+	bool_t ok = !ferror(fp);
+
+	if (fclose(fp) != 0)
+		ok = B_FALSE;
+	if (!ok) {
+		logMsg("Error writing track file %s: %s", filename,
+		    strerror(errno));
+	}
+	return (ok);
+}
+
+static bool_t
+write_gpx(const trkpt_t *pts, size_t num_pts, const char *filename,
+    const char *name)
+{
+	FILE *fp = fopen(filename, "wb");
+
+	if (fp == NULL) {
+		logMsg("Error writing track file %s: %s", filename,
+		    strerror(errno));
+		return (B_FALSE);
+	}
+	fprintf(fp, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"
+	    "<gpx version=\"1.1\" creator=\"libacfutils\" "
+	    "xmlns=\"http://www.topografix.com/GPX/1/1\">\n"
+	    "  <trk>\n");
+	if (name != NULL) {
+		fprintf(fp, "    <name>");
+		write_xml_str(fp, name);
+		fprintf(fp, "</name>\n");
+	}
+	fprintf(fp, "    <trkseg>\n");
+	for (size_t i = 0; i < num_pts; i++) {
+		char timebuf[LACF_ISO8601_BUFSZ];
+
+		lacf_fmt_iso8601(pts[i].utime, timebuf);
+		fprintf(fp, "      <trkpt lat=\"%.7f\" lon=\"%.7f\">"
+		    "<ele>%.1f</ele><time>%s</time></trkpt>\n",
+		    pts[i].pos.lat, pts[i].pos.lon, pts[i].pos.elev, timebuf);
+	}
+	fprintf(fp, "    </trkseg>\n"
+	    "  </trk>\n"
+	    "</gpx>\n");
+
+	return (close_file(fp, filename));
+}
+
+static bool_t
+write_kml(const trkpt_t *pts, size_t num_pts, const char *filename,
+    const char *name)
+{
+	FILE *fp = fopen(filename, "wb");
+
+	if (fp == NULL) {
+		logMsg("Error writing track file %s: %s", filename,
+		    strerror(errno));
+		return (B_FALSE);
+	}
+	fprintf(fp, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"
+	    "<kml xmlns=\"http://www.opengis.net/kml/2.2\" "
+	    "xmlns:gx=\"http://www.google.com/kml/ext/2.2\">\n"
+	    "<Document>\n");
+	if (name != NULL) {
+		fprintf(fp, "  <name>");
+		write_xml_str(fp, name);
+		fprintf(fp, "</name>\n");
+	}
+	fprintf(fp, "  <Style id=\"track\">\n"
+	    "    <LineStyle><color>ff00ffff</color><width>2</width>"
+	    "</LineStyle>\n"
+	    "    <PolyStyle><color>7f00ff00</color></PolyStyle>\n"
+	    "  </Style>\n"
+	    "  <Placemark>\n");
+	if (name != NULL) {
+		fprintf(fp, "    <name>");
+		write_xml_str(fp, name);
+		fprintf(fp, "</name>\n");
+	}
+	fprintf(fp, "    <styleUrl>#track</styleUrl>\n"
+	    "    <gx:Track>\n"
+	    "      <extrude>1</extrude>\n"
+	    "      <altitudeMode>absolute</altitudeMode>\n");
+	for (size_t i = 0; i < num_pts; i++) {
+		char timebuf[LACF_ISO8601_BUFSZ];
+
+		lacf_fmt_iso8601(pts[i].utime, timebuf);
+		fprintf(fp, "      <when>%s</when>\n", timebuf);
+	}
+	for (size_t i = 0; i < num_pts; i++) {
+		fprintf(fp, "      <gx:coord>%.7f %.7f %.1f</gx:coord>\n",
+		    pts[i].pos.lon, pts[i].pos.lat, pts[i].pos.elev);
+	}
+	fprintf(fp, "    </gx:Track>\n"
+	    "  </Placemark>\n"
+	    "</Document>\n"
+	    "</kml>\n");
+
+	return (close_file(fp, filename));
+}
+
+static void
+export_flight(const trkexp_t *trk, const trk_flight_t *flt)
+{
+	char timebuf[LACF_ISO8601_BUFSZ];
+	char basename[32], filename[40];
+	char *path;
+
+	ASSERT(flt->num_pts != 0);
+
+	if (!create_directory_recursive(trk->outdir))
+		return;
+	/*
+	 * Name output files after the start time of the flight, so that
+	 * each flight is written to its own set of files.
+	 */
+	lacf_fmt_iso8601(flt->pts[0].utime, timebuf);
+	snprintf(basename, sizeof (basename), "%.4s%.2s%.2s_%.2s%.2s%.2s",
+	    &timebuf[0], &timebuf[5], &timebuf[8], &timebuf[11],
+	    &timebuf[14], &timebuf[17]);
+
+	if (trk->fmt_mask & TRKEXP_FMT_GPX) {
+		snprintf(filename, sizeof (filename), "%s.gpx", basename);
+		path = mkpathname(trk->outdir, filename, NULL);
+		(void) write_gpx(flt->pts, flt->num_pts, path,
+		    flt->name[0] != 0 ? flt->name : basename);
+		free(path);
+	}
+	if (trk->fmt_mask & TRKEXP_FMT_KML) {
+		snprintf(filename, sizeof (filename), "%s.kml", basename);
+		path = mkpathname(trk->outdir, filename, NULL);
+		(void) write_kml(flt->pts, flt->num_pts, path,
+		    flt->name[0] != 0 ? flt->name : basename);
+		free(path);
+	}
+}
+
+static void
+export_queue(trkexp_t *trk)
+{
+	for (;;) {
+		trk_flight_t *flt;
+
+		mutex_enter(&trk->lock);
+		flt = list_remove_head(&trk->queue);
+		mutex_exit(&trk->lock);
+		if (flt == NULL)
+			break;
+		export_flight(trk, flt);
+		free(flt->pts);
+		free(flt);
+	}
+}
+
+static bool_t
+export_worker(void *userinfo)
+{
+	export_queue(userinfo);
+	return (B_TRUE);
+}
+
+static void
+export_worker_fini(void *userinfo)
+{
+	/* Make sure nothing that was queued before shutdown gets lost */
+	export_queue(userinfo);
+}
+
+/*
+ * Creates a new flight path exporter. The exporter accumulates a history
+ * of aircraft positions (see trkexp_add_pt). When a flight is finished
+ * (see trkexp_end_flight), the recorded track is handed off to a
+ * background thread, which writes it out into a new set of files, so
+ * the simulator frame is never stalled by the file I/O.
+ *
+ * @param outdir Directory into which the output files will be written.
+ *	Each flight produces a separate set of files in this directory,
+ *	named after the UTC start time of the flight (e.g.
+ *	"20210314_153000.gpx"). The directory is created if it doesn't
+ *	exist yet.
+ * @param fmt_mask A bitmask of TRKEXP_FMT_* constants, selecting which
+ *	output formats to write:
+ *	- TRKEXP_FMT_GPX: GPX 1.1 track with elevation and timestamps,
+ *	  suitable for logbook and mapping applications.
+ *	- TRKEXP_FMT_KML: a KML gx:Track with timestamps, absolute altitude
+ *	  and extrusion down to the ground, for display in Google Earth.
+ *
+ * @return The new exporter. Use trkexp_free to dispose of it.
+ */
+trkexp_t *
+trkexp_alloc(const char *outdir, unsigned fmt_mask)
+{
+	trkexp_t *trk = safe_calloc(1, sizeof (*trk));
+
+	ASSERT(outdir != NULL);
+	ASSERT(fmt_mask != 0);
+	ASSERT0(fmt_mask & ~(TRKEXP_FMT_GPX | TRKEXP_FMT_KML));
+
+	trk->outdir = safe_strdup(outdir);
+	trk->fmt_mask = fmt_mask;
+	mutex_init(&trk->lock);
+	list_create(&trk->queue, sizeof (trk_flight_t),
+	    offsetof(trk_flight_t, node));
+	worker_init2(&trk->wk, NULL, export_worker, export_worker_fini, 0,
+	    trk, "trkexp");
+
+	return (trk);
+}
+
+/*
+ * Destroys a flight path exporter. If a flight is currently being
+ * recorded, it is ended and written out first (as if by calling
+ * trkexp_end_flight with a NULL name). This function waits for all
+ * pending file writes to complete before returning.
+ */
+void
+trkexp_free(trkexp_t *trk)
+{
+	if (trk == NULL)
+		return;
+
+	trkexp_end_flight(trk, NULL);
+	worker_fini(&trk->wk);
+	ASSERT(list_is_empty(&trk->queue));
+	list_destroy(&trk->queue);
+	mutex_destroy(&trk->lock);
+	free(trk->pts);
+	free(trk->outdir);
+	free(trk);
+}
+
+/*
+ * Appends a new position to the track of the current flight.
+ *
+ * @param pos Aircraft position. The elevation must be in meters above
+ *	mean sea level.
+ * @param utime UNIX time of the position sample, in seconds (may contain
+ *	a fractional part). Timestamps should be monotonically increasing.
+ */
+void
+trkexp_add_pt(trkexp_t *trk, geo_pos3_t pos, double utime)
+{
+	ASSERT(trk != NULL);
+	ASSERT(!IS_NULL_GEO_POS3(pos));
+	ASSERT(!isnan(utime));
+
+	if (trk->num_pts == trk->cap) {
+		trk->cap = MAX(trk->cap * 2, TRKEXP_INIT_CAP);
+		trk->pts = safe_realloc(trk->pts,
+		    trk->cap * sizeof (*trk->pts));
+	}
+	trk->pts[trk->num_pts].pos = pos;
+	trk->pts[trk->num_pts].utime = utime;
+	trk->num_pts++;
+}
+
+/*
+ * Returns the number of positions recorded in the current flight.
+ */
+size_t
+trkexp_get_num_pts(const trkexp_t *trk)
+{
+	ASSERT(trk != NULL);
+	return (trk->num_pts);
+}
+
+/*
+ * Ends the current flight and starts a new one. The track recorded so
+ * far is handed off to the background thread to be written out in the
+ * configured formats. If no positions have been recorded, this does
+ * nothing.
+ *
+ * @param name Optional human-readable name of the flight (e.g.
+ *	"LKPR-EGLL"), which is embedded in the output files. Pass NULL to
+ *	use the file name instead.
+ */
+void
+trkexp_end_flight(trkexp_t *trk, const char *name)
+{
+	trk_flight_t *flt;
+
+	ASSERT(trk != NULL);
+
+	if (trk->num_pts == 0)
+		return;
+
+	flt = safe_calloc(1, sizeof (*flt));
+	if (name != NULL)
+		lacf_strlcpy(flt->name, name, sizeof (flt->name));
+	flt->pts = trk->pts;
+	flt->num_pts = trk->num_pts;
+	trk->pts = NULL;
+	trk->num_pts = 0;
+	trk->cap = 0;
+
+	mutex_enter(&trk->lock);
+	list_insert_tail(&trk->queue, flt);
+	mutex_exit(&trk->lock);
+
+	worker_wake_up(&trk->wk);
+}
+
+/*
+ * Synchronously writes the track recorded so far in the current flight
+ * to a GPX file. The flight isn't ended, so recording continues.
+ *
+ * @param filename Full path to the output file.
+ * @param name Optional track name to embed in the file. May be NULL.
+ *
+ * @return B_TRUE on success, B_FALSE on failure (error is logged).
+ */
+bool_t
+trkexp_write_gpx(const trkexp_t *trk, const char *filename, const char *name)
+{
+	ASSERT(trk != NULL);
+	ASSERT(filename != NULL);
+	return (write_gpx(trk->pts, trk->num_pts, filename, name));
+}
+
+/*
+ * Same as trkexp_write_gpx, but writes a KML file instead.
+ */
+bool_t
+trkexp_write_kml(const trkexp_t *trk, const char *filename, const char *name)
+{
+	ASSERT(trk != NULL);
+	ASSERT(filename != NULL);
+	return (write_kml(trk->pts, trk->num_pts, filename, name));
+}
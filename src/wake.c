@@ -0,0 +1,265 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <acfutils/acfttype.h>
+#include <acfutils/assert.h>
+#include <acfutils/wake.h>
+
+/*
+ * Looks up the ICAO wake turbulence category of an aircraft type.
+ *
+ * @param icao_type ICAO Doc 8643 aircraft type designator (e.g. "B738").
+ *
+ * @return The wake turbulence category, or WAKE_CAT_UNKNOWN if the type
+ *	isn't in the built-in aircraft type database (see acfttype.h).
+ *	For unknown types, if the MTOW of the aircraft is known, use
+ *	wake_cat_from_mtow instead.
+ */
+wake_cat_t
+wake_cat_from_type(const char *icao_type)
+{
+	acfttype_t info;
+
+	if (!acfttype_lookup(icao_type, &info))
+		return (WAKE_CAT_UNKNOWN);
+	return (info.wtc);
+}
+
+/*
+ * Determines the ICAO wake turbulence category from the maximum
+ * certificated takeoff mass (in kg). Since the super category is
+ * assigned per-type rather than by mass, this never returns WAKE_CAT_J.
+ */
+wake_cat_t
+wake_cat_from_mtow(double mtow)
+{
+	ASSERT3F(mtow, >, 0);
+	if (mtow <= 7000)
+		return (WAKE_CAT_L);
+	if (mtow < 136000)
+		return (WAKE_CAT_M);
+	return (WAKE_CAT_H);
+}
+
+/*
+ * Returns the one-letter abbreviation of a wake turbulence category, as
+ * used in ICAO flight plans ("L", "M", "H" or "J"), or "?" for
+ * WAKE_CAT_UNKNOWN.
+ */
+const char *
+wake_cat2str(wake_cat_t cat)
+{
+	switch (cat) {
+	case WAKE_CAT_L:
+		return ("L");
+	case WAKE_CAT_M:
+		return ("M");
+	case WAKE_CAT_H:
+		return ("H");
+	case WAKE_CAT_J:
+		return ("J");
+	default:
+		return ("?");
+	}
+}
+
+/*
+ * Same as wake_cat_from_type, but returns the RECAT-EU category.
+ */
+recat_t
+recat_from_type(const char *icao_type)
+{
+	acfttype_t info;
+
+	if (!acfttype_lookup(icao_type, &info))
+		return (RECAT_UNKNOWN);
+	return (info.recat);
+}
+
+/*
+ * Determines the RECAT-EU category from the maximum certificated takeoff
+ * mass (in kg) and the wingspan (in meters). As with wake_cat_from_mtow,
+ * this never returns the super heavy category (RECAT_A).
+ */
+recat_t
+recat_from_mtow_span(double mtow, double span)
+{
+	ASSERT3F(mtow, >, 0);
+	ASSERT3F(span, >, 0);
+	if (mtow < 15000)
+		return (RECAT_F);
+	if (mtow < 100000)
+		return (span < 32 ? RECAT_E : RECAT_D);
+	if (span >= 52)
+		return (RECAT_B);
+	if (span >= 38)
+		return (RECAT_C);
+	return (RECAT_D);
+}
+
+/*
+ * Returns the one-letter abbreviation of a RECAT-EU category ("A" to
+ * "F"), or "?" for RECAT_UNKNOWN.
+ */
+const char *
+recat2str(recat_t cat)
+{
+	static const char *names[NUM_RECATS] = {
+	    "?", "A", "B", "C", "D", "E", "F"
+	};
+
+	if (cat < 0 || cat >= NUM_RECATS)
+		return ("?");
+	return (names[cat]);
+}
+
+/*
+ * Returns the ICAO Doc 4444 distance-based wake turbulence separation
+ * minimum (in NM) between a leading and a following aircraft on approach
+ * or departure. Returns 0 if no wake turbulence minimum applies, in which
+ * case the applicable radar separation minimum must be used instead. If
+ * either category is unknown, the most conservative assumption is made
+ * (a super leader or a light follower).
+ */
+double
+wake_sep_dist(wake_cat_t lead, wake_cat_t follow)
+{
+	/* indexed as [lead][follow] */
+	static const double sep[NUM_WAKE_CATS][NUM_WAKE_CATS] = {
+	    [WAKE_CAT_M] = {
+		[WAKE_CAT_L] = 5
+	    },
+	    [WAKE_CAT_H] = {
+		[WAKE_CAT_L] = 6, [WAKE_CAT_M] = 5, [WAKE_CAT_H] = 4
+	    },
+	    [WAKE_CAT_J] = {
+		[WAKE_CAT_L] = 8, [WAKE_CAT_M] = 7, [WAKE_CAT_H] = 6
+	    }
+	};
+
+	if (lead <= WAKE_CAT_UNKNOWN || lead >= NUM_WAKE_CATS)
+		lead = WAKE_CAT_J;
+	if (follow <= WAKE_CAT_UNKNOWN || follow >= NUM_WAKE_CATS)
+		follow = WAKE_CAT_L;
+
+	return (sep[lead][follow]);
+}
+
+/*
+ * Returns the ICAO Doc 4444 time-based wake turbulence separation minimum
+ * (in seconds) between departing aircraft using the same runway. Returns
+ * 0 if no wake turbulence minimum applies. Unknown categories are handled
+ * the same as in wake_sep_dist.
+ *
+ * @param intersection Set to B_TRUE if the following aircraft departs
+ *	from an intermediate part of the runway (an intersection departure),
+ *	which adds one minute to any separation that applies (e.g. 3
+ *	minutes for a light aircraft departing behind a medium one).
+ */
+double
+wake_dep_sep_time(wake_cat_t lead, wake_cat_t follow, bool_t intersection)
+{
+	/* indexed as [lead][follow] */
+	static const double sep[NUM_WAKE_CATS][NUM_WAKE_CATS] = {
+	    [WAKE_CAT_M] = {
+		[WAKE_CAT_L] = 120
+	    },
+	    [WAKE_CAT_H] = {
+		[WAKE_CAT_L] = 120, [WAKE_CAT_M] = 120
+	    },
+	    [WAKE_CAT_J] = {
+		[WAKE_CAT_L] = 180, [WAKE_CAT_M] = 180, [WAKE_CAT_H] = 120
+	    }
+	};
+
+	if (lead <= WAKE_CAT_UNKNOWN || lead >= NUM_WAKE_CATS)
+		lead = WAKE_CAT_J;
+	if (follow <= WAKE_CAT_UNKNOWN || follow >= NUM_WAKE_CATS)
+		follow = WAKE_CAT_L;
+
+	if (intersection && sep[lead][follow] != 0)
+		return (sep[lead][follow] + 60);
+	return (sep[lead][follow]);
+}
+
+/*
+ * Same as wake_sep_dist, but uses the RECAT-EU distance-based separation
+ * minima. Unknown categories are treated as RECAT_A for the leader and
+ * RECAT_F for the follower.
+ */
+double
+recat_sep_dist(recat_t lead, recat_t follow)
+{
+	/* indexed as [lead][follow] */
+	static const double sep[NUM_RECATS][NUM_RECATS] = {
+	    [RECAT_A] = {
+		[RECAT_B] = 4, [RECAT_C] = 5, [RECAT_D] = 5,
+		[RECAT_E] = 6, [RECAT_F] = 8
+	    },
+	    [RECAT_B] = {
+		[RECAT_B] = 3, [RECAT_C] = 4, [RECAT_D] = 4,
+		[RECAT_E] = 5, [RECAT_F] = 7
+	    },
+	    [RECAT_C] = {
+		[RECAT_C] = 3, [RECAT_D] = 3, [RECAT_E] = 4, [RECAT_F] = 6
+	    },
+	    [RECAT_D] = { [RECAT_F] = 5 },
+	    [RECAT_E] = { [RECAT_F] = 4 },
+	    [RECAT_F] = { [RECAT_F] = 3 }
+	};
+
+	if (lead <= RECAT_UNKNOWN || lead >= NUM_RECATS)
+		lead = RECAT_A;
+	if (follow <= RECAT_UNKNOWN || follow >= NUM_RECATS)
+		follow = RECAT_F;
+
+	return (sep[lead][follow]);
+}
+
+/*
+ * Same as wake_dep_sep_time, but uses the RECAT-EU time-based departure
+ * separation minima. Unknown categories are handled the same as in
+ * recat_sep_dist.
+ */
+double
+recat_dep_sep_time(recat_t lead, recat_t follow)
+{
+	/* indexed as [lead][follow] */
+	static const double sep[NUM_RECATS][NUM_RECATS] = {
+	    [RECAT_A] = {
+		[RECAT_B] = 100, [RECAT_C] = 120, [RECAT_D] = 140,
+		[RECAT_E] = 160, [RECAT_F] = 180
+	    },
+	    [RECAT_B] = {
+		[RECAT_D] = 100, [RECAT_E] = 120, [RECAT_F] = 140
+	    },
+	    [RECAT_C] = {
+		[RECAT_D] = 80, [RECAT_E] = 100, [RECAT_F] = 120
+	    },
+	    [RECAT_D] = { [RECAT_F] = 120 },
+	    [RECAT_E] = { [RECAT_F] = 100 },
+	    [RECAT_F] = { [RECAT_F] = 80 }
+	};
+
+	if (lead <= RECAT_UNKNOWN || lead >= NUM_RECATS)
+		lead = RECAT_A;
+	if (follow <= RECAT_UNKNOWN || follow >= NUM_RECATS)
+		follow = RECAT_F;
+
+	return (sep[lead][follow]);
+}
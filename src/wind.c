@@ -0,0 +1,339 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <math.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/crc64.h>
+#include <acfutils/helpers.h>
+#include <acfutils/math.h>
+#include <acfutils/perf.h>
+#include <acfutils/safe_alloc.h>
+#include <acfutils/wind.h>
+
+#define	GUST_MEAN_INTVAL	8.0	/* mean time between gusts, seconds */
+#define	GUST_MIN_DUR		2.0	/* seconds */
+#define	GUST_MAX_DUR		6.0	/* seconds */
+#define	GUST_MIN_FRACT		0.5	/* weakest gust relative to peak */
+#define	TURB_LOW_ALT		FEET2MET(1000)	/* meters AGL */
+#define	TURB_HIGH_ALT		FEET2MET(2000)	/* meters AGL */
+#define	TURB_MIN_ALT		FEET2MET(10)	/* meters AGL */
+#define	TURB_HIGH_LEN		FEET2MET(1750)	/* meters */
+#define	TURB_MIN_SPD		1.0	/* m/s */
+
+struct wind_s {
+	wind_layer_t	*layers;
+	size_t		num_layers;
+	crc64_rng_t	rng;
+
+	/* gust envelope state */
+	double		gust_wait;	/* time until the next gust, seconds */
+	double		gust_t;		/* time into the current gust */
+	double		gust_dur;	/* duration of the current gust */
+	double		gust_fract;	/* strength of the current gust */
+
+	/* turbulence filter states: longitudinal, lateral & vertical */
+	double		turb_u, turb_v, turb_w;
+};
+
+/*
+ * Allocates a new wind model. The model starts out with no wind layers,
+ * so it produces calm air until wind_set_layers is called. `seed' seeds
+ * the model's private random number generator, so that two models with
+ * the same seed and inputs produce the same gusts and turbulence. Since
+ * the PRNG uses crc64, crc64_init must have been called beforehand.
+ *
+ * The wind is defined as a series of layers at different altitudes.
+ * Between layers, the wind vector, gust and turbulence intensity are
+ * interpolated linearly. Below the lowest layer and above the highest
+ * layer, the respective layer's values apply.
+ *
+ * On top of the mean wind, the model generates:
+ *
+ * - gusts: discrete events at random intervals, each following a
+ *	(1 - cos) envelope lasting a few seconds. Gusts blow along the mean
+ *	wind direction and peak at up to the layer's `gust' speed.
+ * - turbulence: three components (along the mean wind, across it and
+ *	vertical), each produced by passing white noise through a first
+ *	order filter, as in the Dryden turbulence model. The filter scale
+ *	lengths follow the MIL-F-8785C low and medium/high altitude models,
+ *	so turbulence gets smaller-scale and less vertical close to the
+ *	ground. The turbulence's frequency content depends on the aircraft's
+ *	true airspeed, so that it can be sampled by an aircraft flying
+ *	through the turbulence field.
+ *
+ * The returned wind vectors are in the local tangent plane, with X
+ * pointing east, Y pointing north and Z pointing up, in m/s. They point
+ * in the direction in which the air is moving.
+ *
+ * Typical usage:
+ *
+ *	wind_t *wind = wind_alloc(seed);
+ *	wind_layer_t layers[] = {
+ *	    { .alt = 0, .dir = 270, .spd = 8, .gust = 13, .turb = 1 },
+ *	    { .alt = 3000, .dir = 290, .spd = 20, .turb = 0.5 }
+ *	};
+ *	wind_set_layers(wind, layers, ARRAY_NUM_ELEM(layers));
+ *	...
+ *	every frame:
+ *	vect3_t w = wind_sample(wind, alt_msl, alt_agl, tas, d_t);
+ *	...
+ *	wind_free(wind);
+ */
+wind_t *
+wind_alloc(uint64_t seed)
+{
+	wind_t *wind = safe_calloc(1, sizeof (*wind));
+
+	crc64_rng_seed(&wind->rng, seed);
+	wind->gust_wait = GUST_MEAN_INTVAL *
+	    crc64_rng_fract(&wind->rng);
+
+	return (wind);
+}
+
+void
+wind_free(wind_t *wind)
+{
+	if (wind == NULL)
+		return;
+	free(wind->layers);
+	free(wind);
+}
+
+static int
+layer_compar(const void *a, const void *b)
+{
+	const wind_layer_t *la = a, *lb = b;
+
+	if (la->alt < lb->alt)
+		return (-1);
+	if (la->alt > lb->alt)
+		return (1);
+	return (0);
+}
+
+/*
+ * Replaces the wind layers of the model. The layers needn't be sorted by
+ * altitude. Passing zero layers removes all wind (any remaining
+ * turbulence dies down quickly). Gust & turbulence state is preserved,
+ * so this can be called at any time (e.g. as new weather data arrives)
+ * without discontinuities in the turbulence.
+ */
+void
+wind_set_layers(wind_t *wind, const wind_layer_t *layers, size_t num_layers)
+{
+	ASSERT(wind != NULL);
+	ASSERT(layers != NULL || num_layers == 0);
+
+	free(wind->layers);
+	wind->layers = NULL;
+	wind->num_layers = num_layers;
+	if (num_layers == 0)
+		return;
+	wind->layers = safe_calloc(num_layers, sizeof (*wind->layers));
+	memcpy(wind->layers, layers, num_layers * sizeof (*layers));
+	qsort(wind->layers, num_layers, sizeof (*wind->layers), layer_compar);
+}
+
+static vect3_t
+layer_vect(const wind_layer_t *layer)
+{
+	vect2_t dir = hdg2dir(normalize_hdg(layer->dir + 180));
+
+	return (VECT3(dir.x * layer->spd, dir.y * layer->spd, 0));
+}
+
+/*
+ * Interpolates the layers at altitude `alt'. Returns the mean wind
+ * vector, and optionally the gust increment & turbulence intensity.
+ */
+static vect3_t
+interp_layers(const wind_t *wind, double alt, double *gust_incr,
+    double *turb)
+{
+	const wind_layer_t *lo, *hi;
+	vect3_t v_lo, v_hi;
+	double t;
+
+	if (wind->num_layers == 0) {
+		if (gust_incr != NULL)
+			*gust_incr = 0;
+		if (turb != NULL)
+			*turb = 0;
+		return (ZERO_VECT3);
+	}
+
+	lo = &wind->layers[0];
+	hi = &wind->layers[wind->num_layers - 1];
+	for (size_t i = 1; i < wind->num_layers; i++) {
+		if (wind->layers[i].alt >= alt) {
+			lo = &wind->layers[i - 1];
+			hi = &wind->layers[i];
+			break;
+		}
+	}
+	if (alt <= lo->alt || hi->alt <= lo->alt)
+		t = (alt <= lo->alt ? 0 : 1);
+	else
+		t = MIN((alt - lo->alt) / (hi->alt - lo->alt), 1);
+
+	if (gust_incr != NULL) {
+		*gust_incr = wavg(MAX(lo->gust - lo->spd, 0),
+		    MAX(hi->gust - hi->spd, 0), t);
+	}
+	if (turb != NULL)
+		*turb = wavg(MAX(lo->turb, 0), MAX(hi->turb, 0), t);
+
+	v_lo = layer_vect(lo);
+	v_hi = layer_vect(hi);
+
+	return (VECT3(wavg(v_lo.x, v_hi.x, t), wavg(v_lo.y, v_hi.y, t), 0));
+}
+
+/*
+ * Returns the mean wind vector at altitude `alt' (meters AMSL), without
+ * any gusts or turbulence. This doesn't advance the model's state.
+ */
+vect3_t
+wind_get_mean(const wind_t *wind, double alt)
+{
+	ASSERT(wind != NULL);
+	return (interp_layers(wind, alt, NULL, NULL));
+}
+
+/*
+ * Advances the gust envelope and returns its current value (0 - 1).
+ */
+static double
+gust_update(wind_t *wind, double d_t)
+{
+	if (wind->gust_dur == 0) {
+		wind->gust_wait -= d_t;
+		if (wind->gust_wait > 0)
+			return (0);
+		wind->gust_t = 0;
+		wind->gust_dur = GUST_MIN_DUR + (GUST_MAX_DUR - GUST_MIN_DUR) *
+		    crc64_rng_fract(&wind->rng);
+		wind->gust_fract = GUST_MIN_FRACT + (1 - GUST_MIN_FRACT) *
+		    crc64_rng_fract(&wind->rng);
+	}
+	wind->gust_t += d_t;
+	if (wind->gust_t >= wind->gust_dur) {
+		/* exponentially distributed intervals between gusts */
+		wind->gust_dur = 0;
+		wind->gust_wait = -GUST_MEAN_INTVAL *
+		    log(1 - crc64_rng_fract(&wind->rng));
+		return (0);
+	}
+	return (wind->gust_fract *
+	    (1 - cos(2 * M_PI * wind->gust_t / wind->gust_dur)) / 2);
+}
+
+/*
+ * Advances one first order Dryden-style turbulence filter with scale
+ * length `len' and RMS intensity `sigma'.
+ */
+static double
+turb_filter(crc64_rng_t *rng, double state, double sigma, double len,
+    double spd, double d_t)
+{
+	double a = exp(-spd * d_t / len);
+
+	return (a * state + sigma * sqrt(1 - POW2(a)) *
+	    crc64_rng_normal(rng, 1));
+}
+
+static void
+turb_update(wind_t *wind, double sigma, double agl, double tas, double d_t)
+{
+	double h = MAX(agl, TURB_MIN_ALT);
+	double spd = MAX(tas, TURB_MIN_SPD);
+	double len_uv, len_w, sigma_uv, sigma_w;
+
+	if (h < TURB_HIGH_ALT) {
+		/* MIL-F-8785C low altitude model works in feet */
+		double h_ft = MET2FEET(MIN(h, TURB_LOW_ALT));
+		double k = 0.177 + 0.000823 * h_ft;
+		double low_len_uv = FEET2MET(h_ft / pow(k, 1.2));
+		double low_len_w = FEET2MET(h_ft);
+		double low_sigma_uv = sigma / pow(k, 0.4);
+		double t = iter_fract(h, TURB_LOW_ALT, TURB_HIGH_ALT, B_TRUE);
+
+		len_uv = wavg(low_len_uv, TURB_HIGH_LEN, t);
+		len_w = wavg(low_len_w, TURB_HIGH_LEN, t);
+		sigma_uv = wavg(low_sigma_uv, sigma, t);
+		sigma_w = sigma;
+	} else {
+		len_uv = len_w = TURB_HIGH_LEN;
+		sigma_uv = sigma_w = sigma;
+	}
+
+	wind->turb_u = turb_filter(&wind->rng, wind->turb_u, sigma_uv,
+	    len_uv, spd, d_t);
+	wind->turb_v = turb_filter(&wind->rng, wind->turb_v, sigma_uv,
+	    len_uv, spd, d_t);
+	wind->turb_w = turb_filter(&wind->rng, wind->turb_w, sigma_w,
+	    len_w, spd, d_t);
+}
+
+/*
+ * Advances the model by `d_t' seconds and returns the wind at the
+ * aircraft's position, including gusts and turbulence.
+ *
+ * @param alt Aircraft altitude in meters AMSL.
+ * @param agl Aircraft height above ground in meters. This is used to
+ *	scale the turbulence close to the ground.
+ * @param tas Aircraft true airspeed in m/s.
+ * @param d_t Time elapsed since the last call in seconds.
+ *
+ * @return The wind vector (east, north, up) in m/s.
+ */
+vect3_t
+wind_sample(wind_t *wind, double alt, double agl, double tas, double d_t)
+{
+	double gust_incr, sigma, spd;
+	vect3_t mean, along, across;
+
+	ASSERT(wind != NULL);
+	ASSERT3F(d_t, >=, 0);
+
+	mean = interp_layers(wind, alt, &gust_incr, &sigma);
+	gust_incr *= gust_update(wind, d_t);
+	if (d_t > 0)
+		turb_update(wind, sigma, agl, tas, d_t);
+
+	/*
+	 * Gusts and longitudinal turbulence act along the mean wind. In
+	 * calm air, we simply pick north as the reference direction.
+	 */
+	spd = vect3_abs(mean);
+	if (spd > 0)
+		along = vect3_scmul(mean, 1 / spd);
+	else
+		along = VECT3(0, 1, 0);
+	across = VECT3(along.y, -along.x, 0);
+
+	return (VECT3(mean.x + along.x * (gust_incr + wind->turb_u) +
+	    across.x * wind->turb_v,
+	    mean.y + along.y * (gust_incr + wind->turb_u) +
+	    across.y * wind->turb_v,
+	    wind->turb_w));
+}
@@ -0,0 +1,365 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <math.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/math.h>
+#include <acfutils/perf.h>
+#include <acfutils/safe_alloc.h>
+#include <acfutils/windshear.h>
+
+#define	REACT_MIN_RA		50	/* feet */
+#define	REACT_MAX_RA		1500	/* feet */
+#define	PRED_MIN_RA		50	/* feet */
+#define	PRED_MAX_RA		1200	/* feet */
+#define	MIN_TAS			KT2MPS(60)
+#define	WIND_RATE_LAG		1.0	/* seconds */
+#define	FFACTOR_LAG		2.0	/* seconds */
+#define	WARN_FFACTOR		0.13
+#define	CAUTION_FFACTOR		-0.13	/* increasing performance shear */
+#define	PRED_FFACTOR		0.13
+#define	PRED_WARN_DIST		NM2MET(1.5)
+#define	PRED_MAX_DIST		NM2MET(3)
+#define	PRED_STEP		NM2MET(0.25)
+#define	PRED_SCAN_INTVAL	1.0	/* seconds per radar sweep */
+#define	ALERT_HOLD_TIME		5	/* seconds */
+#define	ESCAPE_MAX_PITCH	15	/* degrees */
+#define	ESCAPE_AOA_MARGIN	1	/* degrees below the stick shaker */
+#define	ESCAPE_CLEAR_TIME	10	/* seconds */
+
+struct ws_s {
+	ws_gust_field_cb_t	gust_cb;
+	void			*userinfo;
+
+	double			prev_headwind;
+	double			hw_rate;	/* m/s^2, filtered */
+	double			ffactor;	/* reactive, filtered */
+
+	double			pred_ffactor;	/* worst one ahead */
+	double			pred_dist;	/* distance to the worst one */
+	double			scan_timer;
+
+	ws_alert_t		alert;
+	bool_t			alert_pred;
+	double			alert_timer;	/* time since last trigger */
+
+	bool_t			escape;
+	double			escape_pitch;
+	double			escape_clear_timer;
+};
+
+/*
+ * Allocates a windshear detection & guidance computer, in the style of
+ * the systems installed in most modern transport category aircraft.
+ *
+ * Reactive detection is based on the "F-factor", which expresses the
+ * rate at which the wind is robbing the aircraft of specific energy,
+ * normalized by the aircraft's weight and airspeed:
+ *
+ *	F = (dWx/dt) / g - Wh / V
+ *
+ * Where `Wx' is the horizontal tailwind component (so a decaying headwind
+ * increases F), `Wh' is the vertical wind (positive up, so a downdraft
+ * increases F), `g' is gravitational acceleration and `V' is the true
+ * airspeed. An F-factor of 0.1 corresponds to losing the equivalent of
+ * 0.1 g of excess thrust. A sustained F-factor above 0.13 triggers a
+ * windshear warning, while a strongly negative F-factor (an increasing
+ * performance shear, such as encountered when entering a microburst's
+ * outflow) triggers a caution. Reactive alerts are armed between 50 and
+ * 1500 feet radio altitude.
+ *
+ * Predictive detection is optional. If you pass a `gust_cb' and set
+ * `pws_on' in the inputs, the gust field callback is polled once per
+ * simulated radar sweep at points up to 3 NM ahead of the aircraft.
+ * Shear ahead computes an F-factor from the headwind gradient along the
+ * flight path and any downdraft. A predictive warning is issued if a
+ * hazard is within 1.5 NM, otherwise a predictive caution. Predictive
+ * alerts are armed between 50 and 1200 feet radio altitude.
+ *
+ * Escape guidance becomes active with any windshear warning. It commands
+ * a pitch attitude of 15 degrees, but no more than permitted to keep the
+ * angle of attack 1 degree below the stick shaker. The guidance remains
+ * active until the aircraft has been clear of the shear for 10 seconds.
+ *
+ * Typical usage:
+ *
+ *	ws_t *ws = ws_alloc(my_radar_cb, my_data);
+ *	...every frame:
+ *	ws_inputs_t in = {
+ *	    .tas = tas, .headwind = hw, .vert_wind = vw, .ra = ra,
+ *	    .pitch = pitch, .aoa = aoa, .aoa_limit = shaker_aoa,
+ *	    .on_ground = on_ground, .pws_on = radar_on
+ *	};
+ *	ws_update(ws, &in, d_t);
+ *	...combine with other alerting systems:
+ *	unsigned active = ws_get_alert_mask(ws) | my_taws_tcas_alerts;
+ *	switch (alert_arbitrate(active)) { ... }
+ *	...drive the flight director pitch bar:
+ *	if (ws_escape_is_active(ws))
+ *	    fd_pitch = ws_get_escape_pitch(ws);
+ *
+ * @param gust_cb Optional callback to query the gust field ahead of the
+ *	aircraft for predictive windshear alerting. Pass NULL to only
+ *	provide reactive alerting.
+ * @param userinfo Optional userinfo argument passed to `gust_cb'.
+ *
+ * @return The new windshear computer. Use ws_free to free it.
+ */
+ws_t *
+ws_alloc(ws_gust_field_cb_t gust_cb, void *userinfo)
+{
+	ws_t *ws = safe_calloc(1, sizeof (*ws));
+
+	ws->gust_cb = gust_cb;
+	ws->userinfo = userinfo;
+	ws->prev_headwind = NAN;
+	ws->escape_pitch = NAN;
+
+	return (ws);
+}
+
+void
+ws_free(ws_t *ws)
+{
+	free(ws);
+}
+
+static void
+react_update(ws_t *ws, const ws_inputs_t *in, double d_t)
+{
+	double f;
+
+	if (isnan(ws->prev_headwind))
+		ws->prev_headwind = in->headwind;
+	FILTER_IN(ws->hw_rate, (in->headwind - ws->prev_headwind) / d_t,
+	    d_t, WIND_RATE_LAG);
+	ws->prev_headwind = in->headwind;
+
+	if (in->on_ground || in->tas < MIN_TAS) {
+		ws->ffactor = 0;
+		return;
+	}
+	/* a decaying headwind is an increasing tailwind */
+	f = -ws->hw_rate / EARTH_GRAVITY - in->vert_wind / in->tas;
+	FILTER_IN(ws->ffactor, f, d_t, FFACTOR_LAG);
+}
+
+/*
+ * Simulates one radar sweep along the flight path, locating the worst
+ * F-factor ahead of the aircraft.
+ */
+static void
+pred_scan(ws_t *ws, const ws_inputs_t *in)
+{
+	vect2_t prev_wind;
+
+	ws->pred_ffactor = 0;
+	ws->pred_dist = NAN;
+	if (ws->gust_cb == NULL || !in->pws_on || in->tas < MIN_TAS ||
+	    !ws->gust_cb(0, &prev_wind, ws->userinfo))
+		return;
+	for (double d = PRED_STEP; d <= PRED_MAX_DIST; d += PRED_STEP) {
+		vect2_t wind;
+		double f;
+
+		if (!ws->gust_cb(d, &wind, ws->userinfo)) {
+			prev_wind = NULL_VECT2;
+			continue;
+		}
+		if (IS_NULL_VECT(prev_wind)) {
+			prev_wind = wind;
+			continue;
+		}
+		/*
+		 * Headwind loss over the step is converted into a rate
+		 * using the time it would take us to fly through it.
+		 */
+		f = ((prev_wind.x - wind.x) / PRED_STEP) *
+		    (in->tas / EARTH_GRAVITY) - wind.y / in->tas;
+		if (f > ws->pred_ffactor) {
+			ws->pred_ffactor = f;
+			ws->pred_dist = d;
+		}
+		prev_wind = wind;
+	}
+}
+
+static void
+alerts_update(ws_t *ws, const ws_inputs_t *in, double d_t)
+{
+	bool_t react_armed = (!in->on_ground && in->ra >= REACT_MIN_RA &&
+	    in->ra <= REACT_MAX_RA);
+	bool_t pred_armed = (!in->on_ground && in->ra >= PRED_MIN_RA &&
+	    in->ra <= PRED_MAX_RA);
+	ws_alert_t alert = WS_ALERT_NONE;
+	bool_t pred = B_FALSE;
+
+	/* reactive alerts take precedence over predictive ones */
+	if (react_armed && ws->ffactor >= WARN_FFACTOR) {
+		alert = WS_ALERT_WARNING;
+	} else if (pred_armed && ws->pred_ffactor >= PRED_FFACTOR) {
+		alert = (ws->pred_dist <= PRED_WARN_DIST ?
+		    WS_ALERT_WARNING : WS_ALERT_CAUTION);
+		pred = B_TRUE;
+	} else if (react_armed && ws->ffactor <= CAUTION_FFACTOR) {
+		alert = WS_ALERT_CAUTION;
+	}
+
+	if (alert != WS_ALERT_NONE && alert >= ws->alert) {
+		ws->alert = alert;
+		ws->alert_pred = pred;
+		ws->alert_timer = 0;
+	} else if (ws->alert != WS_ALERT_NONE) {
+		/* hold the alert for a while to avoid it flickering */
+		ws->alert_timer += d_t;
+		if (ws->alert_timer >= ALERT_HOLD_TIME || in->on_ground) {
+			ws->alert = alert;
+			ws->alert_pred = pred;
+			ws->alert_timer = 0;
+		}
+	}
+}
+
+static void
+escape_update(ws_t *ws, const ws_inputs_t *in, double d_t)
+{
+	double aoa_room;
+
+	if (ws->alert == WS_ALERT_WARNING && !in->on_ground) {
+		ws->escape = B_TRUE;
+		ws->escape_clear_timer = 0;
+	} else if (ws->escape) {
+		if (ws->ffactor < WARN_FFACTOR / 2)
+			ws->escape_clear_timer += d_t;
+		else
+			ws->escape_clear_timer = 0;
+		if (ws->escape_clear_timer >= ESCAPE_CLEAR_TIME ||
+		    in->on_ground)
+			ws->escape = B_FALSE;
+	}
+	if (!ws->escape) {
+		ws->escape_pitch = NAN;
+		return;
+	}
+	/*
+	 * Pitch and AoA move together in the short term, so the remaining
+	 * AoA margin is the amount we can still add to the pitch attitude.
+	 */
+	aoa_room = in->aoa_limit - ESCAPE_AOA_MARGIN - in->aoa;
+	ws->escape_pitch = MIN(ESCAPE_MAX_PITCH, in->pitch + aoa_room);
+}
+
+/*
+ * Updates the windshear computer. Call this every simulator frame.
+ *
+ * @param in Current aircraft & air data state. All fields must be set.
+ * @param d_t Time elapsed since the last call in seconds.
+ */
+void
+ws_update(ws_t *ws, const ws_inputs_t *in, double d_t)
+{
+	ASSERT(ws != NULL);
+	ASSERT(in != NULL);
+	ASSERT3F(d_t, >, 0);
+	ASSERT(!isnan(in->tas));
+	ASSERT(!isnan(in->headwind));
+	ASSERT(!isnan(in->vert_wind));
+
+	react_update(ws, in, d_t);
+	ws->scan_timer -= d_t;
+	if (ws->scan_timer <= 0) {
+		pred_scan(ws, in);
+		ws->scan_timer = PRED_SCAN_INTVAL;
+	}
+	alerts_update(ws, in, d_t);
+	escape_update(ws, in, d_t);
+}
+
+/*
+ * Returns the current reactive F-factor. Positive values are performance
+ * decreasing shears.
+ */
+double
+ws_get_ffactor(const ws_t *ws)
+{
+	ASSERT(ws != NULL);
+	return (ws->ffactor);
+}
+
+/*
+ * Returns the worst F-factor found ahead of the aircraft during the last
+ * predictive scan, or 0 if no shear was detected.
+ */
+double
+ws_get_pred_ffactor(const ws_t *ws)
+{
+	ASSERT(ws != NULL);
+	return (ws->pred_ffactor);
+}
+
+/*
+ * Returns the current windshear alert level.
+ *
+ * @param predictive Optional return argument, which will be set to
+ *	B_TRUE if the alert was raised by the predictive system.
+ */
+ws_alert_t
+ws_get_alert(const ws_t *ws, bool_t *predictive)
+{
+	ASSERT(ws != NULL);
+	if (predictive != NULL)
+		*predictive = ws->alert_pred;
+	return (ws->alert);
+}
+
+/*
+ * Returns the current alert as a bitmask suitable for combining with
+ * other alert sources and passing to alert_arbitrate.
+ */
+unsigned
+ws_get_alert_mask(const ws_t *ws)
+{
+	ASSERT(ws != NULL);
+	switch (ws->alert) {
+	case WS_ALERT_WARNING:
+		return (ALERT_MASK(ALERT_WS_WARNING));
+	case WS_ALERT_CAUTION:
+		return (ALERT_MASK(ALERT_WS_CAUTION));
+	default:
+		return (0);
+	}
+}
+
+bool_t
+ws_escape_is_active(const ws_t *ws)
+{
+	ASSERT(ws != NULL);
+	return (ws->escape);
+}
+
+/*
+ * Returns the escape guidance pitch target in degrees, or NAN if escape
+ * guidance isn't active.
+ */
+double
+ws_get_escape_pitch(const ws_t *ws)
+{
+	ASSERT(ws != NULL);
+	return (ws->escape_pitch);
+}
@@ -0,0 +1,276 @@
+/*
+ * CDDL HEADER START
+ *
+ * This file and its contents are supplied under the terms of the
+ * Common Development and Distribution License ("CDDL"), version 1.0.
+ * You may only use this file in accordance with the terms of version
+ * 1.0 of the CDDL.
+ *
+ * A full copy of the text of the CDDL should have accompanied this
+ * source.  A copy of the CDDL is also available via the Internet at
+ * http://www.illumos.org/license/CDDL.
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2021 Saso Kiselkov. All rights reserved.
+ */
+
+#include <math.h>
+#include <string.h>
+
+#include <acfutils/assert.h>
+#include <acfutils/safe_alloc.h>
+#include <acfutils/winfilt.h>
+
+typedef struct {
+	double	value;
+	double	t;
+} sample_t;
+
+struct winfilt_s {
+	size_t		cap;
+	double		max_age;
+	/* ring buffer of samples in arrival order */
+	sample_t	*ring;
+	size_t		head;		/* index of the oldest sample */
+	size_t		num;
+	/* the same samples, kept sorted by value */
+	double		*sorted;
+	double		sum;
+	double		sum_sq;
+	size_t		num_removed;	/* since the sums were last rebuilt */
+};
+
+/*
+ * Allocates a windowed filter. The filter keeps the most recent samples
+ * pushed into it and computes their average, root mean square, median,
+ * minimum and maximum. Samples are dropped once the window is full, or
+ * once they are older than `max_age'.
+ *
+ * Typical usage:
+ *
+ *	winfilt_t *filt = winfilt_alloc(50, 2.0);
+ *	...on every sensor reading:
+ *	winfilt_push(filt, reading, now);
+ *	smoothed = winfilt_get_median(filt);
+ *
+ * Pushing a sample and computing the average & RMS are O(1). Pushing a
+ * sample also keeps a sorted copy of the window up to date, using a
+ * binary search to locate the insertion & removal points, so that the
+ * median, minimum and maximum can be read in O(1).
+ *
+ * @param cap Maximum number of samples in the window.
+ * @param max_age Maximum age of samples in the window in seconds, or 0
+ *	if samples should only be dropped once the window is full.
+ *
+ * @return The new filter. Use winfilt_free to free it.
+ */
+winfilt_t *
+winfilt_alloc(size_t cap, double max_age)
+{
+	winfilt_t *filt = safe_calloc(1, sizeof (*filt));
+
+	ASSERT(cap != 0);
+	ASSERT3F(max_age, >=, 0);
+	filt->cap = cap;
+	filt->max_age = max_age;
+	filt->ring = safe_calloc(cap, sizeof (*filt->ring));
+	filt->sorted = safe_calloc(cap, sizeof (*filt->sorted));
+
+	return (filt);
+}
+
+void
+winfilt_free(winfilt_t *filt)
+{
+	if (filt == NULL)
+		return;
+	free(filt->ring);
+	free(filt->sorted);
+	free(filt);
+}
+
+/*
+ * Drops all samples from the filter.
+ */
+void
+winfilt_reset(winfilt_t *filt)
+{
+	ASSERT(filt != NULL);
+	filt->head = 0;
+	filt->num = 0;
+	filt->sum = 0;
+	filt->sum_sq = 0;
+	filt->num_removed = 0;
+}
+
+/*
+ * Returns the index of the first element in the sorted array which is
+ * not less than `value'.
+ */
+static size_t
+sorted_lookup(const winfilt_t *filt, double value)
+{
+	size_t lo = 0, hi = filt->num;
+
+	while (lo < hi) {
+		size_t mid = (lo + hi) / 2;
+
+		if (filt->sorted[mid] < value)
+			lo = mid + 1;
+		else
+			hi = mid;
+	}
+	return (lo);
+}
+
+static void
+rebuild_sums(winfilt_t *filt)
+{
+	filt->sum = 0;
+	filt->sum_sq = 0;
+	for (size_t i = 0; i < filt->num; i++) {
+		filt->sum += filt->sorted[i];
+		filt->sum_sq += filt->sorted[i] * filt->sorted[i];
+	}
+	filt->num_removed = 0;
+}
+
+static void
+remove_oldest(winfilt_t *filt)
+{
+	double value = filt->ring[filt->head].value;
+	size_t idx = sorted_lookup(filt, value);
+
+	ASSERT(filt->num != 0);
+	ASSERT3U(idx, <, filt->num);
+	memmove(&filt->sorted[idx], &filt->sorted[idx + 1],
+	    (filt->num - idx - 1) * sizeof (*filt->sorted));
+	filt->head = (filt->head + 1) % filt->cap;
+	filt->num--;
+	filt->sum -= value;
+	filt->sum_sq -= value * value;
+	/*
+	 * Repeatedly adding & subtracting accumulates rounding errors in
+	 * the sums, so periodically recompute them from scratch.
+	 */
+	if (++filt->num_removed >= filt->cap)
+		rebuild_sums(filt);
+}
+
+/*
+ * Drops samples older than the filter's maximum age. This is done
+ * automatically by winfilt_push, but you can call it to expire samples
+ * when no new samples are arriving (e.g. while a sensor has failed).
+ *
+ * @param now Current time in seconds, in the same time base as passed
+ *	to winfilt_push.
+ */
+void
+winfilt_expire(winfilt_t *filt, double now)
+{
+	ASSERT(filt != NULL);
+	ASSERT(!isnan(now));
+	if (filt->max_age == 0)
+		return;
+	while (filt->num != 0 &&
+	    now - filt->ring[filt->head].t > filt->max_age)
+		remove_oldest(filt);
+}
+
+/*
+ * Pushes a new sample into the filter, dropping the oldest sample if
+ * the window is full.
+ *
+ * @param value The sample value. Must not be NAN.
+ * @param now Current time in seconds in an arbitrary, monotonically
+ *	increasing time base (e.g. the simulator's elapsed time).
+ */
+void
+winfilt_push(winfilt_t *filt, double value, double now)
+{
+	size_t idx;
+
+	ASSERT(filt != NULL);
+	ASSERT(!isnan(value));
+
+	winfilt_expire(filt, now);
+	if (filt->num == filt->cap)
+		remove_oldest(filt);
+
+	filt->ring[(filt->head + filt->num) % filt->cap] =
+	    (sample_t){ .value = value, .t = now };
+	idx = sorted_lookup(filt, value);
+	memmove(&filt->sorted[idx + 1], &filt->sorted[idx],
+	    (filt->num - idx) * sizeof (*filt->sorted));
+	filt->sorted[idx] = value;
+	filt->num++;
+	filt->sum += value;
+	filt->sum_sq += value * value;
+}
+
+size_t
+winfilt_get_num(const winfilt_t *filt)
+{
+	ASSERT(filt != NULL);
+	return (filt->num);
+}
+
+/*
+ * Returns the average of the samples in the window, or NAN if the
+ * window is empty.
+ */
+double
+winfilt_get_avg(const winfilt_t *filt)
+{
+	ASSERT(filt != NULL);
+	if (filt->num == 0)
+		return (NAN);
+	return (filt->sum / filt->num);
+}
+
+/*
+ * Returns the root mean square of the samples in the window, or NAN if
+ * the window is empty.
+ */
+double
+winfilt_get_rms(const winfilt_t *filt)
+{
+	ASSERT(filt != NULL);
+	if (filt->num == 0)
+		return (NAN);
+	/* rounding errors can make a tiny sum of squares negative */
+	return (sqrt(MAX(filt->sum_sq, 0) / filt->num));
+}
+
+/*
+ * Returns the median of the samples in the window, or NAN if the window
+ * is empty. With an even number of samples, the average of the two
+ * middle samples is returned.
+ */
+double
+winfilt_get_median(const winfilt_t *filt)
+{
+	ASSERT(filt != NULL);
+	if (filt->num == 0)
+		return (NAN);
+	if (filt->num % 2 == 1)
+		return (filt->sorted[filt->num / 2]);
+	return ((filt->sorted[filt->num / 2 - 1] +
+	    filt->sorted[filt->num / 2]) / 2);
+}
+
+double
+winfilt_get_min(const winfilt_t *filt)
+{
+	ASSERT(filt != NULL);
+	return (filt->num != 0 ? filt->sorted[0] : NAN);
+}
+
+double
+winfilt_get_max(const winfilt_t *filt)
+{
+	ASSERT(filt != NULL);
+	return (filt->num != 0 ? filt->sorted[filt->num - 1] : NAN);
+}
@@ -150,6 +150,31 @@ wmm_get_decl(const wmm_t *wmm, geo_pos3_t p)
 	return (gme.Decl);
 }
 
+/*
+ * Returns the magnetic inclination (dip) in degrees at a given point.
+ * Positive inclination means the field vector points downward (as in
+ * the northern hemisphere), negative means it points upward.
+ *
+ * @param wmm Magnetic model to use. See wmm_open.
+ * @param p Geodetic position on the WGS84 spheroid for which to determine
+ *	the magnetic inclination.
+ */
+double
+wmm_get_incl(const wmm_t *wmm, geo_pos3_t p)
+{
+	MAGtype_CoordSpherical		coord_sph;
+	MAGtype_CoordGeodetic		coord_geo = {
+		.lambda = p.lon, .phi = p.lat,
+		.HeightAboveEllipsoid = FEET2MET(p.elev)
+	};
+	MAGtype_GeoMagneticElements	gme;
+
+	MAG_GeodeticToSpherical(wmm->ellip, coord_geo, &coord_sph);
+	MAG_Geomag(wmm->ellip, coord_sph, coord_geo, wmm->timed_model, &gme);
+
+	return (gme.Incl);
+}
+
 /*
  * Converts a magnetic heading to true according to the world magnetic model.
  *
@@ -0,0 +1,114 @@
+/*
+ * CDDL HEADER START
+ *
+ * The contents of this file are subject to the terms of the
+ * Common Development and Distribution License, Version 1.0 only
+ * (the "License").  You may not use this file except in compliance
+ * with the License.
+ *
+ * You can obtain a copy of the license in the file COPYING
+ * or http://www.opensource.org/licenses/CDDL-1.0.
+ * See the License for the specific language governing permissions
+ * and limitations under the License.
+ *
+ * When distributing Covered Code, include this CDDL HEADER in each
+ * file and include the License file COPYING.
+ * If applicable, add the following below this CDDL HEADER, with the
+ * fields enclosed by brackets "[]" replaced with your own identifying
+ * information: Portions Copyright [yyyy] [name of copyright owner]
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2026 Saso Kiselkov. All rights reserved.
+ */
+
+#include <math.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#include <acfutils/perf.h>
+
+#define	TEMP_EPSILON	0.01	/* Kelvin */
+#define	REL_EPSILON	0.0006	/* 0.06% */
+
+static int
+check_rel(const char *what, double alt, double val, double ref)
+{
+	if (fabs(val - ref) > fabs(ref) * REL_EPSILON) {
+		printf("FAIL: %s at %.0f m: %g, expected %g\n", what, alt,
+		    val, ref);
+		return (1);
+	}
+	return (0);
+}
+
+static int
+check_temp(const char *what, double alt, double val, double ref)
+{
+	if (fabs(val - ref) > TEMP_EPSILON) {
+		printf("FAIL: %s at %.0f m: %.3f K, expected %.3f K\n", what,
+		    alt, val, ref);
+		return (1);
+	}
+	return (0);
+}
+
+int
+main(void)
+{
+	int fails = 0;
+	size_t num;
+	const isa_table_ent_t *tab = isa_table_get(&num);
+
+	for (size_t i = 0; i < num; i++) {
+		const isa_table_ent_t *e = &tab[i];
+		isa_atmos_t isa;
+
+		if (i > 0 && e->alt - tab[i - 1].alt != ISA_TABLE_STEP) {
+			printf("FAIL: table entry %d not %d m after previous\n",
+			    (int)i, ISA_TABLE_STEP);
+			fails++;
+		}
+
+		/* the model against the published values */
+		isa_atmos(e->alt, 0, &isa);
+		fails += check_temp("isa_atmos temp", e->alt, isa.temp,
+		    e->temp);
+		fails += check_rel("isa_atmos press", e->alt, isa.press,
+		    e->press);
+		fails += check_rel("isa_atmos dens", e->alt, isa.dens,
+		    e->dens);
+
+		/* the table lookup at the entries */
+		isa_table_lookup(e->alt, &isa);
+		fails += check_temp("isa_table_lookup temp", e->alt,
+		    isa.temp, e->temp);
+		fails += check_rel("isa_table_lookup press", e->alt,
+		    isa.press, e->press);
+		fails += check_rel("isa_table_lookup dens", e->alt,
+		    isa.dens, e->dens);
+
+		/* the table lookup between the entries, against the model */
+		if (i + 1 < num) {
+			double alt = e->alt + ISA_TABLE_STEP / 2;
+			isa_atmos_t ref;
+
+			isa_atmos(alt, 0, &ref);
+			isa_table_lookup(alt, &isa);
+			fails += check_temp("isa_table_lookup temp", alt,
+			    isa.temp, ref.temp);
+			fails += check_rel("isa_table_lookup press", alt,
+			    isa.press, ref.press);
+			fails += check_rel("isa_table_lookup dens", alt,
+			    isa.dens, ref.dens);
+		}
+	}
+
+	if (fails != 0) {
+		printf("%d test(s) failed\n", fails);
+		return (1);
+	}
+	printf("all tests passed\n");
+	return (0);
+}
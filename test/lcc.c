@@ -0,0 +1,96 @@
+/*
+ * CDDL HEADER START
+ *
+ * The contents of this file are subject to the terms of the
+ * Common Development and Distribution License, Version 1.0 only
+ * (the "License").  You may not use this file except in compliance
+ * with the License.
+ *
+ * You can obtain a copy of the license in the file COPYING
+ * or http://www.opensource.org/licenses/CDDL-1.0.
+ * See the License for the specific language governing permissions
+ * and limitations under the License.
+ *
+ * When distributing Covered Code, include this CDDL HEADER in each
+ * file and include the License file COPYING.
+ * If applicable, add the following below this CDDL HEADER, with the
+ * fields enclosed by brackets "[]" replaced with your own identifying
+ * information: Portions Copyright [yyyy] [name of copyright owner]
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2026 Saso Kiselkov. All rights reserved.
+ */
+
+#include <math.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#include <acfutils/geom.h>
+#include <acfutils/helpers.h>
+
+#define	XY_EPSILON	1e-6	/* Earth radii */
+#define	GEO_EPSILON	1e-9	/* degrees */
+
+/*
+ * Checks that lcc2geo inverts geo2lcc at `pos'.
+ */
+static int
+check_round_trip(const lcc_t *lcc, geo_pos2_t pos)
+{
+	geo_pos2_t res = lcc2geo(geo2lcc(pos, lcc), lcc);
+
+	if (fabs(res.lat - pos.lat) > GEO_EPSILON ||
+	    fabs(normalize_lon(res.lon - pos.lon)) > GEO_EPSILON) {
+		printf("FAIL: round trip of (%f, %f) yielded (%f, %f)\n",
+		    pos.lat, pos.lon, res.lat, res.lon);
+		return (1);
+	}
+	return (0);
+}
+
+int
+main(void)
+{
+	int fails = 0;
+	lcc_t lcc;
+	vect2_t v;
+
+	/*
+	 * Snyder, "Map Projections - A Working Manual" (USGS Professional
+	 * Paper 1395), p. 295: spherical Lambert conformal conic with
+	 * standard parallels 33N & 45N, origin at 23N 96W.
+	 */
+	lcc = lcc_init(23, -96, 33, 45);
+	v = geo2lcc(GEO_POS2(35, -75), &lcc);
+	if (fabs(v.x - 0.2966785) > XY_EPSILON ||
+	    fabs(v.y - 0.2462112) > XY_EPSILON) {
+		printf("FAIL: Snyder example yielded (%.7f, %.7f), expected "
+		    "(0.2966785, 0.2462112)\n", v.x, v.y);
+		fails++;
+	}
+	for (double lat = -10; lat <= 80; lat += 15) {
+		for (double lon = -170; lon <= 170; lon += 34)
+			fails += check_round_trip(&lcc, GEO_POS2(lat, lon));
+	}
+
+	/* southern hemisphere cone */
+	lcc = lcc_init(-30, 135, -20, -40);
+	for (double lat = -80; lat <= 10; lat += 15) {
+		for (double lon = -170; lon <= 170; lon += 34)
+			fails += check_round_trip(&lcc, GEO_POS2(lat, lon));
+	}
+
+	/* single standard parallel */
+	lcc = lcc_init(50, 10, 50, 50);
+	for (double lat = 0; lat <= 80; lat += 20)
+		fails += check_round_trip(&lcc, GEO_POS2(lat, 25));
+
+	if (fails != 0) {
+		printf("%d test(s) failed\n", fails);
+		return (1);
+	}
+	printf("all tests passed\n");
+	return (0);
+}
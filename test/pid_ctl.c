@@ -0,0 +1,173 @@
+/*
+ * CDDL HEADER START
+ *
+ * The contents of this file are subject to the terms of the
+ * Common Development and Distribution License, Version 1.0 only
+ * (the "License").  You may not use this file except in compliance
+ * with the License.
+ *
+ * You can obtain a copy of the license in the file COPYING
+ * or http://www.opensource.org/licenses/CDDL-1.0.
+ * See the License for the specific language governing permissions
+ * and limitations under the License.
+ *
+ * When distributing Covered Code, include this CDDL HEADER in each
+ * file and include the License file COPYING.
+ * If applicable, add the following below this CDDL HEADER, with the
+ * fields enclosed by brackets "[]" replaced with your own identifying
+ * information: Portions Copyright [yyyy] [name of copyright owner]
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2026 Saso Kiselkov. All rights reserved.
+ */
+
+#include <math.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#include <acfutils/conf.h>
+#include <acfutils/log.h>
+#include <acfutils/pid_ctl.h>
+#include <acfutils/pid_ctl_parsing.h>
+
+#define	D_T	0.05	/* seconds */
+
+static void
+log_func(const char *str)
+{
+	fputs(str, stderr);
+}
+
+/*
+ * Holds a PID controller in saturation with a large error, then flips
+ * the error sign and returns how long the output takes to come out of
+ * saturation. The controller's raw output at the end of the saturated
+ * phase is returned in `raw_sat'.
+ */
+static double
+recovery_time(double k_aw, double *raw_sat)
+{
+	pid_ctl_t pid;
+	double t;
+
+	pid_ctl_init(&pid, 1, 1, 1000, 0, 1);
+	pid_ctl_set_integ_clamp(&pid, B_FALSE);
+	pid_ctl_set_out_lim(&pid, -1, 1);
+	pid_ctl_set_k_aw(&pid, k_aw);
+
+	for (t = 0; t < 20; t += D_T)
+		pid_ctl_update(&pid, 5, D_T);
+	*raw_sat = pid_ctl_get_raw(&pid);
+	for (t = 0; t < 300; t += D_T) {
+		pid_ctl_update(&pid, -0.5, D_T);
+		if (pid_ctl_get(&pid) < 1)
+			break;
+	}
+	return (t);
+}
+
+/*
+ * With error e, back-calculation holds the raw output at
+ * out_max + e * k_i / k_aw while saturated (less the e * d_t which the
+ * back-calculation removes at the end of each update), so the controller
+ * responds as soon as the error reverses. Without it, the integrator
+ * winds up to e * t and takes ages to unwind.
+ */
+static int
+check_anti_windup(void)
+{
+	double raw_aw, raw_no_aw;
+	double t_aw = recovery_time(2, &raw_aw);
+	double t_no_aw = recovery_time(0, &raw_no_aw);
+	int fails = 0;
+
+	if (fabs(raw_aw - (3.5 - 5 * D_T)) > 0.01) {
+		printf("FAIL: saturated raw output with anti-windup %g, "
+		    "expected %g\n", raw_aw, 3.5 - 5 * D_T);
+		fails++;
+	}
+	if (raw_no_aw < 90) {
+		printf("FAIL: saturated raw output without anti-windup %g, "
+		    "expected the integrator to wind up\n", raw_no_aw);
+		fails++;
+	}
+	if (t_aw > 2 * D_T || t_no_aw < 100) {
+		printf("FAIL: recovery from saturation took %g s with "
+		    "anti-windup and %g s without\n", t_aw, t_no_aw);
+		fails++;
+	}
+	return (fails);
+}
+
+/*
+ * Checks that pid_ctl_parse rejects invalid output limits and anti-windup
+ * gains, and that it reads back what pid_ctl_write stored.
+ */
+static int
+check_parse(void)
+{
+	conf_t *conf = conf_create_empty();
+	pid_ctl_t pid, pid2;
+	int fails = 0;
+
+	conf_set_d(conf, "pid/k_p", 1.5);
+	conf_set_d(conf, "pid/k_i", 0.5);
+	conf_set_d(conf, "pid/lim_i", 10);
+	conf_set_d(conf, "pid/k_d", 0.1);
+	conf_set_d(conf, "pid/r_d", 2);
+	conf_set_b(conf, "pid/integ_clamp", B_FALSE);
+	conf_set_d(conf, "pid/out_min", 1);
+	conf_set_d(conf, "pid/out_max", -1);
+	if (pid_ctl_parse(&pid, conf, "pid")) {
+		printf("FAIL: out_min > out_max accepted\n");
+		fails++;
+	}
+	conf_set_d(conf, "pid/out_min", -1);
+	conf_set_d(conf, "pid/out_max", 1);
+	conf_set_d(conf, "pid/k_aw", -1);
+	if (pid_ctl_parse(&pid, conf, "pid")) {
+		printf("FAIL: negative k_aw accepted\n");
+		fails++;
+	}
+	conf_set_d(conf, "pid/k_aw", 0.5);
+	if (!pid_ctl_parse(&pid, conf, "pid")) {
+		printf("FAIL: valid config rejected\n");
+		fails++;
+	}
+	conf_free(conf);
+
+	conf = conf_create_empty();
+	pid_ctl_write(&pid, conf, "pid");
+	if (!pid_ctl_parse(&pid2, conf, "pid") || pid2.k_p != pid.k_p ||
+	    pid2.k_i != pid.k_i || pid2.lim_i != pid.lim_i ||
+	    pid2.k_d != pid.k_d || pid2.r_d != pid.r_d ||
+	    pid2.integ_clamp != pid.integ_clamp ||
+	    pid2.out_min != pid.out_min || pid2.out_max != pid.out_max ||
+	    pid2.k_aw != pid.k_aw) {
+		printf("FAIL: pid_ctl_write/pid_ctl_parse round trip\n");
+		fails++;
+	}
+	conf_free(conf);
+
+	return (fails);
+}
+
+int
+main(void)
+{
+	int fails = 0;
+
+	log_init(log_func, "pid_ctl");
+
+	fails += check_anti_windup();
+	fails += check_parse();
+
+	if (fails != 0) {
+		printf("%d test(s) failed\n", fails);
+		return (1);
+	}
+	printf("all tests passed\n");
+	return (0);
+}
@@ -0,0 +1,88 @@
+/*
+ * CDDL HEADER START
+ *
+ * The contents of this file are subject to the terms of the
+ * Common Development and Distribution License, Version 1.0 only
+ * (the "License").  You may not use this file except in compliance
+ * with the License.
+ *
+ * You can obtain a copy of the license in the file COPYING
+ * or http://www.opensource.org/licenses/CDDL-1.0.
+ * See the License for the specific language governing permissions
+ * and limitations under the License.
+ *
+ * When distributing Covered Code, include this CDDL HEADER in each
+ * file and include the License file COPYING.
+ * If applicable, add the following below this CDDL HEADER, with the
+ * fields enclosed by brackets "[]" replaced with your own identifying
+ * information: Portions Copyright [yyyy] [name of copyright owner]
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2026 Saso Kiselkov. All rights reserved.
+ */
+
+#include <math.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#include <acfutils/quat.h>
+
+/*
+ * Checks that converting `e' into a quaternion, back into Euler angles
+ * and into a quaternion again yields the same attitude (to within `tol'
+ * meters per meter). At +-90 degrees of pitch, the Euler angles
+ * themselves aren't unique, so we compare where the attitudes put the
+ * body axes instead.
+ */
+static int
+check_euler(euler_t e, double tol)
+{
+	static const vect3_t axes[] = {
+	    {1, 0, 0}, {0, 1, 0}, {0, 0, 1}
+	};
+	quat_t q1 = quat_from_euler(e);
+	euler_t e2 = quat_to_euler(q1);
+	quat_t q2 = quat_from_euler(e2);
+
+	for (int i = 0; i < 3; i++) {
+		vect3_t v1 = quat_rot(q1, axes[i]);
+		vect3_t v2 = quat_rot(q2, axes[i]);
+
+		if (vect3_abs(vect3_sub(v1, v2)) > tol) {
+			printf("FAIL: pitch %g roll %g hdg %g -> "
+			    "pitch %g roll %g hdg %g: axis %d "
+			    "(%.3f, %.3f, %.3f) != (%.3f, %.3f, %.3f)\n",
+			    e.pitch, e.roll, e.hdg, e2.pitch, e2.roll, e2.hdg,
+			    i, v1.x, v1.y, v1.z, v2.x, v2.y, v2.z);
+			return (1);
+		}
+	}
+	return (0);
+}
+
+int
+main(void)
+{
+	int fails = 0;
+
+	fails += check_euler(EULER(0, 0, 0), 1e-9);
+	fails += check_euler(EULER(10, -20, 135), 1e-9);
+	fails += check_euler(EULER(-45, 170, 300), 1e-9);
+	/* gimbal lock */
+	fails += check_euler(EULER(90, 10, 45), 1e-9);
+	fails += check_euler(EULER(90, -120, 350), 1e-9);
+	fails += check_euler(EULER(-90, 10, 45), 1e-9);
+	fails += check_euler(EULER(-90, 75, 200), 1e-9);
+	/* close to gimbal lock, where roll gets folded into heading */
+	fails += check_euler(EULER(89.9999, 10, 45), 1e-5);
+
+	if (fails != 0) {
+		printf("%d test(s) failed\n", fails);
+		return (1);
+	}
+	printf("all tests passed\n");
+
+	return (0);
+}
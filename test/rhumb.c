@@ -0,0 +1,103 @@
+/*
+ * CDDL HEADER START
+ *
+ * The contents of this file are subject to the terms of the
+ * Common Development and Distribution License, Version 1.0 only
+ * (the "License").  You may not use this file except in compliance
+ * with the License.
+ *
+ * You can obtain a copy of the license in the file COPYING
+ * or http://www.opensource.org/licenses/CDDL-1.0.
+ * See the License for the specific language governing permissions
+ * and limitations under the License.
+ *
+ * When distributing Covered Code, include this CDDL HEADER in each
+ * file and include the License file COPYING.
+ * If applicable, add the following below this CDDL HEADER, with the
+ * fields enclosed by brackets "[]" replaced with your own identifying
+ * information: Portions Copyright [yyyy] [name of copyright owner]
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2026 Saso Kiselkov. All rights reserved.
+ */
+
+#include <math.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#include <acfutils/geom.h>
+#include <acfutils/helpers.h>
+
+#define	EPSILON	1e-6	/* degrees */
+
+/*
+ * Checks that displacing `start' along the rhumb line towards `end'
+ * by the rhumb line distance between the two lands on `end'.
+ */
+static int
+check_round_trip(geo_pos2_t start, geo_pos2_t end)
+{
+	double hdg = rhumb_hdg(start, end);
+	double dist = rhumb_distance(start, end);
+	geo_pos2_t pos = rhumb_displace(start, hdg, dist);
+
+	if (fabs(pos.lat - end.lat) > EPSILON ||
+	    fabs(normalize_lon(pos.lon - end.lon)) > EPSILON) {
+		printf("FAIL: (%f, %f) -> (%f, %f): got (%f, %f)\n",
+		    start.lat, start.lon, end.lat, end.lon, pos.lat, pos.lon);
+		return (1);
+	}
+	return (0);
+}
+
+/*
+ * A rhumb line that isn't a meridian winds around the pole ever more
+ * tightly as it approaches it, so when displacing over a pole, the
+ * longitude change must exceed that of stopping just short of the pole.
+ * The latitude must be reflected back to the same side of the pole.
+ */
+static int
+check_pole_crossing(geo_pos2_t start, double hdg, double dlat)
+{
+	double short_dist = DEG2RAD(90 - start.lat - 0.1) / cos(DEG2RAD(hdg)) *
+	    EARTH_MSL;
+	double dist = DEG2RAD(dlat) / cos(DEG2RAD(hdg)) * EARTH_MSL;
+	geo_pos2_t short_pos = rhumb_displace(start, hdg, short_dist);
+	geo_pos2_t pos = rhumb_displace(start, hdg, dist);
+	double exp_lat = 180 - (start.lat + dlat);
+
+	if (!is_valid_lat(pos.lat) || !is_valid_lon(pos.lon) ||
+	    fabs(pos.lat - exp_lat) > EPSILON ||
+	    fabs(normalize_lon(pos.lon - start.lon)) <=
+	    fabs(normalize_lon(short_pos.lon - start.lon))) {
+		printf("FAIL: (%f, %f) hdg %f over the pole: got (%f, %f), "
+		    "short of the pole (%f, %f)\n", start.lat, start.lon, hdg,
+		    pos.lat, pos.lon, short_pos.lat, short_pos.lon);
+		return (1);
+	}
+	return (0);
+}
+
+int
+main(void)
+{
+	int fails = 0;
+
+	fails += check_round_trip(GEO_POS2(0, 0), GEO_POS2(10, 10));
+	fails += check_round_trip(GEO_POS2(50, -5), GEO_POS2(40, -74));
+	fails += check_round_trip(GEO_POS2(-33.9, 151.2), GEO_POS2(-37.8, 145));
+	fails += check_round_trip(GEO_POS2(60, 170), GEO_POS2(60, -170));
+	fails += check_round_trip(GEO_POS2(-20, 30), GEO_POS2(-20, 40));
+
+	fails += check_pole_crossing(GEO_POS2(80, 0), 1, 20);
+	fails += check_pole_crossing(GEO_POS2(85, 20), 3, 7);
+
+	if (fails != 0) {
+		printf("%d test(s) failed\n", fails);
+		return (1);
+	}
+	printf("all tests passed\n");
+	return (0);
+}
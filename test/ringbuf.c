@@ -0,0 +1,140 @@
+/*
+ * CDDL HEADER START
+ *
+ * The contents of this file are subject to the terms of the
+ * Common Development and Distribution License, Version 1.0 only
+ * (the "License").  You may not use this file except in compliance
+ * with the License.
+ *
+ * You can obtain a copy of the license in the file COPYING
+ * or http://www.opensource.org/licenses/CDDL-1.0.
+ * See the License for the specific language governing permissions
+ * and limitations under the License.
+ *
+ * When distributing Covered Code, include this CDDL HEADER in each
+ * file and include the License file COPYING.
+ * If applicable, add the following below this CDDL HEADER, with the
+ * fields enclosed by brackets "[]" replaced with your own identifying
+ * information: Portions Copyright [yyyy] [name of copyright owner]
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2026 Saso Kiselkov. All rights reserved.
+ */
+
+/*
+ * Stress-tests ring buffers with multiple producers, checking that every
+ * element arrives intact, exactly once and in per-producer order, and
+ * that all elements which didn't arrive were counted as dropped.
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+
+#include <acfutils/ringbuf.h>
+#include <acfutils/thread.h>
+
+#define	NUM_PROD	4
+#define	NUM_PUSHES	500000
+
+typedef struct {
+	uint32_t	prod;
+	uint32_t	n;
+	uint64_t	check;		/* to spot torn copies */
+	uint8_t		pad[48];
+} elem_t;
+
+static ringbuf_t		*rb;
+static atomic32_t		prods_running;
+
+static uint64_t
+elem_check(uint32_t prod, uint32_t n)
+{
+	return (((uint64_t)prod << 32 | n) * 0x9e3779b97f4a7c15llu);
+}
+
+static void
+producer(void *arg)
+{
+	uint32_t prod = (uintptr_t)arg;
+
+	for (uint32_t n = 0; n < NUM_PUSHES; n++) {
+		elem_t e = { .prod = prod, .n = n,
+		    .check = elem_check(prod, n) };
+
+		ringbuf_push(rb, &e);
+	}
+	atomic_dec_32(&prods_running);
+}
+
+static int
+run(const char *name, unsigned flags)
+{
+	thread_t threads[NUM_PROD];
+	int64_t last[NUM_PROD];
+	uint64_t popped = 0, dropped;
+	int fails = 0;
+	elem_t e;
+
+	rb = ringbuf_alloc(sizeof (elem_t), 256,
+	    flags | RINGBUF_MULTI_PROD);
+	prods_running = NUM_PROD;
+	for (int i = 0; i < NUM_PROD; i++) {
+		last[i] = -1;
+		VERIFY(thread_create(&threads[i], producer,
+		    (void *)(uintptr_t)i));
+	}
+	for (;;) {
+		bool_t done = (prods_running == 0);
+
+		memory_barrier();
+		while (ringbuf_pop(rb, &e)) {
+			if (e.prod >= NUM_PROD ||
+			    e.check != elem_check(e.prod, e.n)) {
+				printf("FAIL: %s: torn element\n", name);
+				fails++;
+				continue;
+			}
+			if ((int64_t)e.n <= last[e.prod]) {
+				printf("FAIL: %s: producer %u element %u "
+				    "out of order (after %lld)\n", name,
+				    e.prod, e.n, (long long)last[e.prod]);
+				fails++;
+			}
+			last[e.prod] = e.n;
+			popped++;
+		}
+		if (done)
+			break;
+	}
+	for (int i = 0; i < NUM_PROD; i++)
+		thread_join(&threads[i]);
+	dropped = ringbuf_get_num_dropped(rb);
+	if (popped + dropped != (uint64_t)NUM_PROD * NUM_PUSHES) {
+		printf("FAIL: %s: popped %llu + dropped %llu != pushed %llu\n",
+		    name, (unsigned long long)popped,
+		    (unsigned long long)dropped,
+		    (unsigned long long)NUM_PROD * NUM_PUSHES);
+		fails++;
+	}
+	ringbuf_free(rb);
+
+	return (fails);
+}
+
+int
+main(void)
+{
+	int fails = 0;
+
+	fails += run("fail when full", 0);
+	fails += run("overwrite", RINGBUF_OVERWRITE);
+
+	if (fails != 0) {
+		printf("%d test(s) failed\n", fails);
+		return (1);
+	}
+	printf("all tests passed\n");
+	return (0);
+}
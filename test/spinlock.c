@@ -0,0 +1,174 @@
+/*
+ * CDDL HEADER START
+ *
+ * The contents of this file are subject to the terms of the
+ * Common Development and Distribution License, Version 1.0 only
+ * (the "License").  You may not use this file except in compliance
+ * with the License.
+ *
+ * You can obtain a copy of the license in the file COPYING
+ * or http://www.opensource.org/licenses/CDDL-1.0.
+ * See the License for the specific language governing permissions
+ * and limitations under the License.
+ *
+ * When distributing Covered Code, include this CDDL HEADER in each
+ * file and include the License file COPYING.
+ * If applicable, add the following below this CDDL HEADER, with the
+ * fields enclosed by brackets "[]" replaced with your own identifying
+ * information: Portions Copyright [yyyy] [name of copyright owner]
+ *
+ * CDDL HEADER END
+ */
+/*
+ * Copyright 2026 Saso Kiselkov. All rights reserved.
+ */
+
+/*
+ * Benchmarks spinlock_t against mutex_t and seqlock_t readers against a
+ * busy writer, while checking that neither lets anything slip through.
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+
+#include <acfutils/thread.h>
+#include <acfutils/time.h>
+
+#define	NUM_THREADS	4
+#define	NUM_ITERS	1000000
+#define	NUM_WRITES	1000000
+
+static spinlock_t	spin;
+static mutex_t		mtx;
+static uint64_t		counter;
+
+typedef struct {
+	uint64_t	a;
+	uint64_t	b;
+} pair_t;
+
+static pair_t		shared;
+static seqlock_t	seq;
+static volatile bool_t	writer_done;
+
+static void
+spin_worker(void *unused)
+{
+	UNUSED(unused);
+	for (int i = 0; i < NUM_ITERS; i++) {
+		spinlock_enter(&spin);
+		counter++;
+		spinlock_exit(&spin);
+	}
+}
+
+static void
+mutex_worker(void *unused)
+{
+	UNUSED(unused);
+	for (int i = 0; i < NUM_ITERS; i++) {
+		mutex_enter(&mtx);
+		counter++;
+		mutex_exit(&mtx);
+	}
+}
+
+/*
+ * Runs `worker' on NUM_THREADS threads, prints how long each lock
+ * round trip took and checks that no increment of `counter' was lost.
+ */
+static int
+bench_lock(const char *name, void (*worker)(void *))
+{
+	thread_t threads[NUM_THREADS];
+	uint64_t start, end;
+
+	counter = 0;
+	start = microclock();
+	for (int i = 0; i < NUM_THREADS; i++)
+		VERIFY(thread_create(&threads[i], worker, NULL));
+	for (int i = 0; i < NUM_THREADS; i++)
+		thread_join(&threads[i]);
+	end = microclock();
+
+	printf("%-8s %d threads: %6.1f ns/op\n", name, NUM_THREADS,
+	    (end - start) * 1000.0 / ((double)NUM_THREADS * NUM_ITERS));
+	if (counter != (uint64_t)NUM_THREADS * NUM_ITERS) {
+		printf("FAIL: %s lost updates: counter %llu, expected %llu\n",
+		    name, (unsigned long long)counter,
+		    (unsigned long long)NUM_THREADS * NUM_ITERS);
+		return (1);
+	}
+	return (0);
+}
+
+static void
+seq_writer(void *unused)
+{
+	UNUSED(unused);
+	for (uint64_t i = 1; i <= NUM_WRITES; i++) {
+		seqlock_write_begin(&seq);
+		shared.a = i;
+		shared.b = i;
+		seqlock_write_end(&seq);
+	}
+	writer_done = B_TRUE;
+}
+
+/*
+ * Reads `shared' as fast as possible while a writer keeps updating it
+ * and checks that no torn copy is ever returned.
+ */
+static int
+bench_seqlock(void)
+{
+	thread_t writer;
+	uint64_t start, end, reads = 0, torn = 0;
+
+	writer_done = B_FALSE;
+	start = microclock();
+	VERIFY(thread_create(&writer, seq_writer, NULL));
+	while (!writer_done) {
+		pair_t copy;
+
+		SEQLOCK_READ(&seq, &copy, &shared);
+		if (copy.a != copy.b)
+			torn++;
+		reads++;
+	}
+	thread_join(&writer);
+	end = microclock();
+
+	printf("seqlock  1 writer:  %6.1f ns/write, %6.1f ns/read\n",
+	    (end - start) * 1000.0 / NUM_WRITES,
+	    (end - start) * 1000.0 / reads);
+	if (torn != 0) {
+		printf("FAIL: seqlock returned %llu torn reads\n",
+		    (unsigned long long)torn);
+		return (1);
+	}
+	return (0);
+}
+
+int
+main(void)
+{
+	int fails = 0;
+
+	spinlock_init(&spin);
+	mutex_init(&mtx);
+	seqlock_init(&seq);
+
+	fails += bench_lock("spinlock", spin_worker);
+	fails += bench_lock("mutex", mutex_worker);
+	fails += bench_seqlock();
+
+	mutex_destroy(&mtx);
+
+	if (fails != 0) {
+		printf("%d test(s) failed\n", fails);
+		return (1);
+	}
+	printf("all tests passed\n");
+	return (0);
+}